@@ -7,4 +7,7 @@ pub trait DnsModifier {
     fn subvert(&self) -> Result<(), String>;
     fn revert(&self) -> Result<(), String>;
     fn inspect(&self, stdout: &mut (dyn io::Write + Send)) -> Result<(), String>;
+    // The DNS servers the system was using before it was subverted; if it isn't subverted, the
+    // ones it's using now.
+    fn saved_nameservers(&self) -> Result<Vec<String>, String>;
 }
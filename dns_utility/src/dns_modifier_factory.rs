@@ -7,6 +7,10 @@ use std::path::Path;
 
 #[cfg(target_os = "linux")]
 use crate::resolv_conf_dns_modifier::ResolvConfDnsModifier;
+#[cfg(target_os = "linux")]
+use crate::resolved_dns_modifier::ResolvedDnsModifier;
+#[cfg(target_os = "linux")]
+use std::fs;
 
 #[cfg(target_os = "macos")]
 use crate::dynamic_store_dns_modifier::DynamicStoreDnsModifier;
@@ -35,9 +39,12 @@ impl DnsModifierFactoryReal {
     }
 }
 
-const QUALIFIER_FACTORIES: [&dyn QualifierFactory; 3] = [
+// Order matters: systemd-resolved also provides an /etc/resolv.conf, but it must be checked first
+// so that we don't edit a file it will overwrite.
+const QUALIFIER_FACTORIES: [&dyn QualifierFactory; 4] = [
     &DynamicStoreQualifierFactory {},
     &WinQualifierFactory {},
+    &ResolvedQualifierFactory {},
     &ResolvConfQualifierFactory {},
 ];
 
@@ -46,6 +53,36 @@ trait QualifierFactory {
     fn make(&self) -> Box<dyn DnsModifier>;
 }
 
+struct ResolvedQualifierFactory;
+#[cfg(target_os = "linux")]
+impl QualifierFactory for ResolvedQualifierFactory {
+    fn system_qualifies(&self) -> bool {
+        is_resolved_stub(Path::new("/etc/resolv.conf"))
+    }
+    fn make(&self) -> Box<dyn DnsModifier> {
+        Box::new(ResolvedDnsModifier::new())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl QualifierFactory for ResolvedQualifierFactory {
+    fn system_qualifies(&self) -> bool {
+        false
+    }
+    fn make(&self) -> Box<dyn DnsModifier> {
+        panic!("Should never be called")
+    }
+}
+
+// When systemd-resolved is managing DNS, /etc/resolv.conf is a link into its runtime directory.
+#[cfg(target_os = "linux")]
+fn is_resolved_stub(resolv_conf: &Path) -> bool {
+    match fs::read_link(resolv_conf) {
+        Ok(target) => target.to_string_lossy().contains("/run/systemd/resolve/"),
+        Err(_) => false,
+    }
+}
+
 struct ResolvConfQualifierFactory;
 #[cfg(target_os = "linux")]
 impl QualifierFactory for ResolvConfQualifierFactory {
@@ -134,6 +171,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn resolved_qualifier_factory_works_on_this_os() {
+        let subject = ResolvedQualifierFactory {};
+
+        let result = subject.system_qualifies();
+
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(result, is_resolved_stub(Path::new("/etc/resolv.conf")))
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            assert_eq!(result, false)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn is_resolved_stub_recognizes_links_into_resolveds_runtime_directory() {
+        use std::os::unix::fs::symlink;
+        let dir = std::env::current_dir()
+            .unwrap()
+            .join("generated")
+            .join("DnsModifierFactory")
+            .join("is_resolved_stub_recognizes_links_into_resolveds_runtime_directory");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let stub = dir.join("stub-resolv.conf");
+        let plain = dir.join("plain-resolv.conf");
+        let other_link = dir.join("other-resolv.conf");
+        symlink("../run/systemd/resolve/stub-resolv.conf", &stub).unwrap();
+        fs::write(&plain, "nameserver 8.8.8.8\n").unwrap();
+        symlink("../run/NetworkManager/resolv.conf", &other_link).unwrap();
+
+        assert_eq!(is_resolved_stub(&stub), true);
+        assert_eq!(is_resolved_stub(&plain), false);
+        assert_eq!(is_resolved_stub(&other_link), false);
+        assert_eq!(is_resolved_stub(&dir.join("nonexistent")), false);
+    }
+
     #[test]
     fn win_qualifier_factory_works_on_this_os() {
         let subject = WinQualifierFactory {};
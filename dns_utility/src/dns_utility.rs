@@ -141,6 +141,10 @@ mod tests {
             write!(stdout, "{}", self.inspect_to_stdout.borrow_mut().remove(0)).unwrap();
             self.inspect_results.borrow_mut().remove(0)
         }
+
+        fn saved_nameservers(&self) -> Result<Vec<String>, String> {
+            unimplemented!()
+        }
     }
 
     impl DnsModifierMock {
@@ -58,6 +58,17 @@ impl DnsModifier for DynamicStoreDnsModifier {
         writeln!(stdout, "{}", output).expect("write is broken");
         Ok(())
     }
+
+    fn saved_nameservers(&self) -> Result<Vec<String>, String> {
+        let (_, mut dns_info) = self.get_dns_info(false)?;
+        match dns_info.remove(SERVER_ADDRESSES_BAK) {
+            Some(saved_addresses) => Ok(saved_addresses),
+            None => match dns_info.remove(SERVER_ADDRESSES) {
+                Some(active_addresses) => Ok(active_addresses),
+                None => Err(String::from("This system has no DNS settings")),
+            },
+        }
+    }
 }
 
 impl Default for DynamicStoreDnsModifier {
@@ -1310,6 +1321,49 @@ mod tests {
         assert_eq!(result.is_ok(), true);
     }
 
+    #[test]
+    fn saved_nameservers_prefers_backed_up_addresses() {
+        let mut ipv4_map: HashMap<String, CFPropertyList> = HashMap::new();
+        let primary_service_cfpl = CFString::from_static_string("booga").to_CFPropertyList();
+        ipv4_map.insert(String::from(PRIMARY_SERVICE), primary_service_cfpl);
+        let mut server_addresses_map: HashMap<String, CFPropertyList> = HashMap::new();
+        let active_addresses_cfpl =
+            CFArray::from_CFTypes(&[CFString::from_static_string("127.0.0.1")])
+                .to_untyped()
+                .to_CFPropertyList();
+        let backup_addresses_cfpl = CFArray::from_CFTypes(&[
+            CFString::from_static_string("1.2.3.4"),
+            CFString::from_static_string("5.6.7.8"),
+        ])
+        .to_untyped()
+        .to_CFPropertyList();
+        server_addresses_map.insert(String::from(SERVER_ADDRESSES), active_addresses_cfpl);
+        server_addresses_map.insert(String::from(SERVER_ADDRESSES_BAK), backup_addresses_cfpl);
+        let store = StoreWrapperMock::new()
+            .get_dictionary_string_cfpl_result(Some(ipv4_map))
+            .cfpl_to_string_result(Ok(String::from("booga")))
+            .get_dictionary_string_cfpl_result(Some(server_addresses_map))
+            .cfpl_to_vec_result(Ok(vec![
+                CFString::from_static_string("127.0.0.1").to_CFPropertyList()
+            ]))
+            .cfpl_to_string_result(Ok(String::from("127.0.0.1")))
+            .cfpl_to_vec_result(Ok(vec![
+                CFString::from_static_string("1.2.3.4").to_CFPropertyList(),
+                CFString::from_static_string("5.6.7.8").to_CFPropertyList(),
+            ]))
+            .cfpl_to_string_result(Ok(String::from("1.2.3.4")))
+            .cfpl_to_string_result(Ok(String::from("5.6.7.8")));
+        let mut subject = DynamicStoreDnsModifier::new();
+        subject.store = Box::new(store);
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Ok(vec!["1.2.3.4".to_string(), "5.6.7.8".to_string()])
+        );
+    }
+
     fn compare_cfpls(a: &CFPropertyList, b: &CFPropertyList) {
         if !a.eq(b) {
             println!("The following two CFPropertyLists were not equal:");
@@ -19,6 +19,8 @@ pub mod ipconfig_wrapper;
 #[cfg(target_os = "windows")]
 pub mod netsh;
 pub mod resolv_conf_dns_modifier;
+pub mod resolvectl;
+pub mod resolved_dns_modifier;
 pub mod utils;
 #[cfg(target_os = "windows")]
 pub mod win_dns_modifier;
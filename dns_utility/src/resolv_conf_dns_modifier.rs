@@ -42,6 +42,11 @@ impl DnsModifier for ResolvConfDnsModifier {
         let (_, contents) = self.open_resolv_conf(false)?;
         self.inspect_contents(contents, stdout)
     }
+
+    fn saved_nameservers(&self) -> Result<Vec<String>, String> {
+        let (_, contents) = self.open_resolv_conf(false)?;
+        self.saved_nameservers_from_contents(&contents)
+    }
 }
 
 impl Default for ResolvConfDnsModifier {
@@ -160,6 +165,28 @@ impl ResolvConfDnsModifier {
         Ok(())
     }
 
+    fn saved_nameservers_from_contents(&self, contents: &str) -> Result<Vec<String>, String> {
+        let active_nameservers = self.active_nameservers(contents);
+        self.check_disconnected(&active_nameservers)?;
+        let nameserver_lines: Vec<String> = if self.check_already_subverted(&active_nameservers) {
+            self.existing_nameservers(contents)
+                .into_iter()
+                .filter(|(line, _)| line.starts_with('#'))
+                .map(|(line, _)| line[1..].to_string())
+                .filter(|line| !ResolvConfDnsModifier::is_masq_ip(line))
+                .collect()
+        } else {
+            active_nameservers
+                .into_iter()
+                .map(|(line, _)| line)
+                .collect()
+        };
+        Ok(nameserver_lines
+            .into_iter()
+            .map(|line| self.nameserver_line_to_ip(line))
+            .collect())
+    }
+
     pub fn nameserver_line_to_ip(&self, nameserver_line: String) -> String {
         let regex = Regex::new(r"^\s*nameserver\s+([^\s#]*)").expect("Regex syntax error");
         let captures = regex
@@ -838,6 +865,54 @@ mod tests {
         assert_eq!(result.is_ok(), true);
     }
 
+    #[test]
+    fn saved_nameservers_finds_commented_nameservers_when_subverted() {
+        let root = make_root("saved_nameservers_finds_commented_nameservers_when_subverted");
+        make_resolv_conf (&root, "#comment\n## nameserver 1.1.1.1\n#nameserver 8.8.8.8\n# nameserver 9.9.9.9\nnameserver 127.0.0.1\n");
+        let mut subject = ResolvConfDnsModifier::new();
+        subject.root = root.clone();
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Ok(vec!["8.8.8.8".to_string(), "9.9.9.9".to_string()])
+        );
+    }
+
+    #[test]
+    fn saved_nameservers_finds_active_nameservers_when_not_subverted() {
+        let root = make_root("saved_nameservers_finds_active_nameservers_when_not_subverted");
+        make_resolv_conf(
+            &root,
+            "#nameserver 1.1.1.1\nnameserver 8.8.8.8\nnameserver 9.9.9.9\n",
+        );
+        let mut subject = ResolvConfDnsModifier::new();
+        subject.root = root.clone();
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Ok(vec!["8.8.8.8".to_string(), "9.9.9.9".to_string()])
+        );
+    }
+
+    #[test]
+    fn saved_nameservers_complains_if_there_are_no_nameservers() {
+        let root = make_root("saved_nameservers_complains_if_there_are_no_nameservers");
+        make_resolv_conf(&root, "#comment\n");
+        let mut subject = ResolvConfDnsModifier::new();
+        subject.root = root.clone();
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Err("This system does not appear to be connected to a network".to_string())
+        );
+    }
+
     fn make_root(test_name: &str) -> PathBuf {
         let cur_dir = env::current_dir().unwrap();
         let generated_dir = cur_dir.join(Path::new("generated"));
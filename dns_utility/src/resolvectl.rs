@@ -0,0 +1,134 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+#![cfg(target_os = "linux")]
+
+use crate::resolvectl::ResolvectlError::IOError;
+use std::{io, process};
+
+// resolvectl talks to systemd-resolved over D-Bus; per-link settings made through it last until
+// the link is reconfigured or the system reboots.
+pub trait Resolvectl {
+    fn dns(&self) -> Result<String, ResolvectlError>;
+    fn set_dns(&self, link: &str, servers: &[String]) -> Result<(), ResolvectlError>;
+}
+
+#[derive(Default)]
+pub struct ResolvectlCommand {}
+
+#[derive(Debug)]
+pub enum ResolvectlError {
+    NonZeroExit(i32),
+    NoCodeExit,
+    IOError(io::Error),
+}
+
+impl ResolvectlCommand {
+    fn command() -> process::Command {
+        process::Command::new("resolvectl")
+    }
+}
+
+fn make_set_args(link: &str, servers: &[String]) -> Vec<String> {
+    let mut args = vec!["dns".to_string(), link.to_string()];
+    args.extend(servers.iter().cloned());
+    args
+}
+
+impl Resolvectl for ResolvectlCommand {
+    fn dns(&self) -> Result<String, ResolvectlError> {
+        let output = match Self::command().arg("dns").output() {
+            Ok(output) => output,
+            Err(e) => return Err(IOError(e)),
+        };
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            match output.status.code() {
+                Some(code) => Err(ResolvectlError::NonZeroExit(code)),
+                None => Err(ResolvectlError::NoCodeExit),
+            }
+        }
+    }
+
+    fn set_dns(&self, link: &str, servers: &[String]) -> Result<(), ResolvectlError> {
+        let mut resolvectl = Self::command();
+        let command = resolvectl.args(&make_set_args(link, servers));
+        match command.status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => match status.code() {
+                Some(code) => Err(ResolvectlError::NonZeroExit(code)),
+                None => Err(ResolvectlError::NoCodeExit),
+            },
+            Err(e) => Err(IOError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests_utils {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    pub(crate) struct ResolvectlMock {
+        dns_results: RefCell<Vec<Result<String, ResolvectlError>>>,
+        pub set_dns_parameters: Arc<Mutex<Vec<(String, Vec<String>)>>>,
+        set_dns_results: RefCell<Vec<Result<(), ResolvectlError>>>,
+    }
+
+    impl Resolvectl for ResolvectlMock {
+        fn dns(&self) -> Result<String, ResolvectlError> {
+            self.dns_results
+                .borrow_mut()
+                .pop()
+                .unwrap_or_else(|| panic!("dns called without a stub"))
+        }
+
+        fn set_dns(&self, link: &str, servers: &[String]) -> Result<(), ResolvectlError> {
+            self.set_dns_parameters
+                .lock()
+                .expect("set_dns couldn't take params")
+                .push((link.to_string(), servers.to_vec()));
+            self.set_dns_results
+                .borrow_mut()
+                .pop()
+                .unwrap_or_else(|| panic!("set_dns called without a stub"))
+        }
+    }
+
+    impl ResolvectlMock {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn dns_result(self, result: Result<String, ResolvectlError>) -> Self {
+            self.dns_results.borrow_mut().insert(0, result);
+            self
+        }
+
+        pub fn set_dns_result(self, result: Result<(), ResolvectlError>) -> Self {
+            self.set_dns_results.borrow_mut().insert(0, result);
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn make_set_args_lists_the_link_and_its_servers() {
+        let result = make_set_args("wlan0", &["1.1.1.1".to_string(), "8.8.8.8".to_string()]);
+
+        assert_eq!(
+            result,
+            vec![
+                "dns".to_string(),
+                "wlan0".to_string(),
+                "1.1.1.1".to_string(),
+                "8.8.8.8".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,744 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+#![cfg(target_os = "linux")]
+use crate::dns_modifier::DnsModifier;
+use crate::resolvectl::{Resolvectl, ResolvectlCommand, ResolvectlError};
+use regex::Regex;
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const MASQ_NAMESERVER: &str = "127.0.0.1";
+
+// Per-link DNS settings in systemd-resolved are runtime state, so the backup lives under /run
+// where it disappears along with them on reboot.
+const BACKUP_DIRECTORY: &str = "run/masq";
+const BACKUP_FILE: &str = "resolved-dns-backup";
+
+#[derive(Clone, Debug, PartialEq)]
+struct LinkDns {
+    link: String,
+    servers: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ResolvedDns {
+    global: Vec<String>,
+    links: Vec<LinkDns>,
+}
+
+pub struct ResolvedDnsModifier {
+    root: PathBuf,
+    resolvectl: Box<dyn Resolvectl>,
+}
+
+impl DnsModifier for ResolvedDnsModifier {
+    fn type_name(&self) -> &'static str {
+        "ResolvedDnsModifier"
+    }
+
+    fn subvert(&self) -> Result<(), String> {
+        let dns = self.current_dns()?;
+        Self::check_global(&dns)?;
+        let configured_links = Self::configured_links(&dns);
+        Self::check_disconnected(&configured_links)?;
+        if Self::is_subverted(&configured_links) {
+            return Ok(());
+        }
+        Self::check_for_nonsense(&configured_links)?;
+        self.write_backup(&configured_links)?;
+        let masq_servers = vec![MASQ_NAMESERVER.to_string()];
+        for (index, link_dns) in configured_links.iter().enumerate() {
+            if let Err(e) = self.set_dns(&link_dns.link, &masq_servers) {
+                self.restore_links(&configured_links[..index]);
+                let _ = fs::remove_file(self.backup_path());
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    fn revert(&self) -> Result<(), String> {
+        let backup = match self.read_backup()? {
+            Some(backup) => backup,
+            None => {
+                let dns = self.current_dns()?;
+                return if Self::is_subverted(&Self::configured_links(&dns)) {
+                    Err(String::from(
+                        "There do not appear to be any DNS settings to revert to",
+                    ))
+                } else {
+                    Ok(())
+                };
+            }
+        };
+        for link_dns in &backup {
+            self.set_dns(&link_dns.link, &link_dns.servers)?;
+        }
+        match fs::remove_file(self.backup_path()) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!(
+                "{:?} could not be removed: {}",
+                self.backup_path(),
+                e
+            )),
+        }
+    }
+
+    fn inspect(&self, stdout: &mut (dyn io::Write + Send)) -> Result<(), String> {
+        let dns = self.current_dns()?;
+        let active_nameservers = Self::active_nameservers(&dns);
+        if active_nameservers.is_empty() {
+            return Err(String::from(
+                "This system does not appear to be connected to a network",
+            ));
+        }
+        let output_list = active_nameservers
+            .into_iter()
+            .fold(String::new(), |so_far, ip_address| {
+                format!("{}{}\n", so_far, ip_address)
+            });
+        write!(stdout, "{}", output_list).expect("stdout doesn't work");
+        Ok(())
+    }
+
+    fn saved_nameservers(&self) -> Result<Vec<String>, String> {
+        match self.read_backup()? {
+            Some(backup) => Ok(Self::union_of_servers(&backup)),
+            None => {
+                let dns = self.current_dns()?;
+                let active_nameservers = Self::active_nameservers(&dns);
+                if active_nameservers.is_empty() {
+                    return Err(String::from(
+                        "This system does not appear to be connected to a network",
+                    ));
+                }
+                Ok(active_nameservers
+                    .into_iter()
+                    .filter(|server| server != MASQ_NAMESERVER)
+                    .collect())
+            }
+        }
+    }
+}
+
+impl Default for ResolvedDnsModifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResolvedDnsModifier {
+    pub fn new() -> ResolvedDnsModifier {
+        ResolvedDnsModifier {
+            root: PathBuf::from("/"),
+            resolvectl: Box::new(ResolvectlCommand::default()),
+        }
+    }
+
+    fn current_dns(&self) -> Result<ResolvedDns, String> {
+        match self.resolvectl.dns() {
+            Ok(output) => Ok(Self::parse_dns(&output)),
+            Err(e) => Err(Self::describe_error("read", e)),
+        }
+    }
+
+    fn set_dns(&self, link: &str, servers: &[String]) -> Result<(), String> {
+        self.resolvectl
+            .set_dns(link, servers)
+            .map_err(|e| Self::describe_error("modify", e))
+    }
+
+    fn describe_error(action: &str, error: ResolvectlError) -> String {
+        match error {
+            ResolvectlError::IOError(ref e) if e.kind() == ErrorKind::NotFound => {
+                String::from("resolvectl was not found; is systemd-resolved installed?")
+            }
+            ResolvectlError::IOError(e) => {
+                format!("Could not {} systemd-resolved DNS settings: {}", action, e)
+            }
+            ResolvectlError::NonZeroExit(code) => format!(
+                "Could not {} systemd-resolved DNS settings: resolvectl exited with code {}",
+                action, code
+            ),
+            ResolvectlError::NoCodeExit => format!(
+                "Could not {} systemd-resolved DNS settings: resolvectl was terminated",
+                action
+            ),
+        }
+    }
+
+    // Understands both "Global:" and "Link 2 (eth0): 10.0.2.3 8.8.8.8" lines, ignoring any others.
+    fn parse_dns(output: &str) -> ResolvedDns {
+        let global_regex = Regex::new(r"^\s*Global:(.*)$").expect("Regex syntax error");
+        let link_regex =
+            Regex::new(r"^\s*Link\s+\d+\s+\(([^)]+)\):(.*)$").expect("Regex syntax error");
+        let servers_from = |list: &str| -> Vec<String> {
+            list.split_whitespace().map(|s| s.to_string()).collect()
+        };
+        let mut result = ResolvedDns {
+            global: vec![],
+            links: vec![],
+        };
+        output.lines().for_each(|line| {
+            if let Some(captures) = link_regex.captures(line) {
+                result.links.push(LinkDns {
+                    link: captures[1].to_string(),
+                    servers: servers_from(&captures[2]),
+                })
+            } else if let Some(captures) = global_regex.captures(line) {
+                result.global = servers_from(&captures[1])
+            }
+        });
+        result
+    }
+
+    fn configured_links(dns: &ResolvedDns) -> Vec<LinkDns> {
+        dns.links
+            .iter()
+            .filter(|link_dns| !link_dns.servers.is_empty())
+            .cloned()
+            .collect()
+    }
+
+    // systemd-resolved may send any query to the Global servers as well as to the link's own.
+    fn active_nameservers(dns: &ResolvedDns) -> Vec<String> {
+        let global = LinkDns {
+            link: String::new(),
+            servers: dns.global.clone(),
+        };
+        let mut links = vec![global];
+        links.extend(Self::configured_links(dns));
+        Self::union_of_servers(&links)
+    }
+
+    fn union_of_servers(links: &[LinkDns]) -> Vec<String> {
+        links
+            .iter()
+            .flat_map(|link_dns| link_dns.servers.iter())
+            .fold(vec![], |mut so_far, server| {
+                if !so_far.contains(server) {
+                    so_far.push(server.clone())
+                }
+                so_far
+            })
+    }
+
+    // Global servers come from resolved.conf and can't be changed at runtime through resolvectl;
+    // subverting only the links would leave every lookup free to go around the MASQ Node.
+    fn check_global(dns: &ResolvedDns) -> Result<(), String> {
+        if dns.global.iter().all(|server| server == MASQ_NAMESERVER) {
+            Ok(())
+        } else {
+            Err(format!(
+                "systemd-resolved has Global DNS servers ({}) that can't be subverted, so lookups \
+                 could go around MASQ. Remove the DNS= setting from /etc/systemd/resolved.conf and \
+                 /etc/systemd/resolved.conf.d/*.conf, restart systemd-resolved, and try again",
+                dns.global.join(" ")
+            ))
+        }
+    }
+
+    fn check_disconnected(configured_links: &[LinkDns]) -> Result<(), String> {
+        if configured_links.is_empty() {
+            Err(String::from(
+                "This system does not appear to be connected to a network",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn is_subverted(configured_links: &[LinkDns]) -> bool {
+        configured_links
+            .iter()
+            .all(|link_dns| link_dns.servers == vec![MASQ_NAMESERVER.to_string()])
+    }
+
+    fn check_for_nonsense(configured_links: &[LinkDns]) -> Result<(), String> {
+        if configured_links
+            .iter()
+            .any(|link_dns| link_dns.servers.iter().any(|s| s == MASQ_NAMESERVER))
+        {
+            Err(String::from(
+                "This system's DNS settings don't make sense; aborting",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Best effort only: we're already on our way out with an error.
+    fn restore_links(&self, links: &[LinkDns]) {
+        links.iter().for_each(|link_dns| {
+            let _ = self.resolvectl.set_dns(&link_dns.link, &link_dns.servers);
+        })
+    }
+
+    fn backup_path(&self) -> PathBuf {
+        Path::new(&self.root)
+            .join(BACKUP_DIRECTORY)
+            .join(BACKUP_FILE)
+    }
+
+    fn write_backup(&self, links: &[LinkDns]) -> Result<(), String> {
+        let path = self.backup_path();
+        let contents = links.iter().fold(String::new(), |so_far, link_dns| {
+            format!(
+                "{}{} {}\n",
+                so_far,
+                link_dns.link,
+                link_dns.servers.join(" ")
+            )
+        });
+        let result = match path.parent() {
+            Some(directory) => fs::create_dir_all(directory),
+            None => Ok(()),
+        }
+        .and_then(|_| fs::write(&path, contents));
+        result.map_err(|e| format!("{:?} could not be written: {}", path, e))
+    }
+
+    fn read_backup(&self) -> Result<Option<Vec<LinkDns>>, String> {
+        let path = self.backup_path();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("{:?} could not be read: {}", path, e)),
+        };
+        let links = contents
+            .lines()
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                let link = words.next()?.to_string();
+                Some(LinkDns {
+                    link,
+                    servers: words.map(|s| s.to_string()).collect(),
+                })
+            })
+            .filter(|link_dns| !link_dns.servers.is_empty())
+            .collect::<Vec<LinkDns>>();
+        if links.is_empty() {
+            Err(format!("{:?} doesn't contain any DNS settings", path))
+        } else {
+            Ok(Some(links))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolvectl::tests_utils::ResolvectlMock;
+    use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
+    use std::env;
+
+    const CONNECTED: &str = "Global:\nLink 2 (eth0): 10.0.2.3 8.8.8.8\nLink 3 (docker0):\nLink 4 (wlan0): 8.8.8.8 1.1.1.1\n";
+    const SUBVERTED: &str =
+        "Global:\nLink 2 (eth0): 127.0.0.1\nLink 3 (docker0):\nLink 4 (wlan0): 127.0.0.1\n";
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(MASQ_NAMESERVER, "127.0.0.1");
+        assert_eq!(BACKUP_DIRECTORY, "run/masq");
+        assert_eq!(BACKUP_FILE, "resolved-dns-backup");
+    }
+
+    #[test]
+    fn parse_dns_handles_global_and_link_lines() {
+        let result = ResolvedDnsModifier::parse_dns(
+            "Global: 9.9.9.9\nLink 2 (eth0): 10.0.2.3 8.8.8.8\nLink 3 (docker0):\nbooga\n",
+        );
+
+        assert_eq!(
+            result,
+            ResolvedDns {
+                global: vec!["9.9.9.9".to_string()],
+                links: vec![
+                    LinkDns {
+                        link: "eth0".to_string(),
+                        servers: vec!["10.0.2.3".to_string(), "8.8.8.8".to_string()],
+                    },
+                    LinkDns {
+                        link: "docker0".to_string(),
+                        servers: vec![],
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn inspect_reports_the_upstreams_of_every_link_once() {
+        let mut stream_holder = FakeStreamHolder::new();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Ok(CONNECTED.to_string())));
+
+        let result = subject.inspect(stream_holder.streams().stdout);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stream_holder.stdout.get_string(),
+            "10.0.2.3\n8.8.8.8\n1.1.1.1\n".to_string()
+        );
+    }
+
+    #[test]
+    fn inspect_falls_back_to_global_servers_when_no_link_has_any() {
+        let mut stream_holder = FakeStreamHolder::new();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.resolvectl = Box::new(
+            ResolvectlMock::new().dns_result(Ok("Global: 9.9.9.9\nLink 2 (eth0):\n".to_string())),
+        );
+
+        let result = subject.inspect(stream_holder.streams().stdout);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stream_holder.stdout.get_string(), "9.9.9.9\n".to_string());
+    }
+
+    #[test]
+    fn inspect_reports_global_servers_along_with_link_servers() {
+        let mut stream_holder = FakeStreamHolder::new();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Ok(
+            "Global: 9.9.9.9 8.8.8.8\nLink 2 (eth0): 10.0.2.3 8.8.8.8\n".to_string(),
+        )));
+
+        let result = subject.inspect(stream_holder.streams().stdout);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stream_holder.stdout.get_string(),
+            "9.9.9.9\n8.8.8.8\n10.0.2.3\n".to_string()
+        );
+    }
+
+    #[test]
+    fn inspect_reports_subverted_links_as_masq() {
+        let mut stream_holder = FakeStreamHolder::new();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Ok(SUBVERTED.to_string())));
+
+        let result = subject.inspect(stream_holder.streams().stdout);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stream_holder.stdout.get_string(), "127.0.0.1\n".to_string());
+    }
+
+    #[test]
+    fn inspect_complains_if_there_are_no_nameservers() {
+        let mut stream_holder = FakeStreamHolder::new();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.resolvectl =
+            Box::new(ResolvectlMock::new().dns_result(Ok("Global:\nLink 2 (eth0):\n".to_string())));
+
+        let result = subject.inspect(stream_holder.streams().stdout);
+
+        assert_eq!(
+            result,
+            Err("This system does not appear to be connected to a network".to_string())
+        );
+        assert_eq!(stream_holder.stdout.get_string(), String::new());
+    }
+
+    #[test]
+    fn inspect_complains_if_resolvectl_is_missing() {
+        let mut stream_holder = FakeStreamHolder::new();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Err(
+            ResolvectlError::IOError(io::Error::from(ErrorKind::NotFound)),
+        )));
+
+        let result = subject.inspect(stream_holder.streams().stdout);
+
+        assert_eq!(
+            result,
+            Err("resolvectl was not found; is systemd-resolved installed?".to_string())
+        );
+    }
+
+    #[test]
+    fn subvert_backs_up_and_replaces_the_dns_of_every_configured_link() {
+        let root = make_root("subvert_backs_up_and_replaces_the_dns_of_every_configured_link");
+        let resolvectl = ResolvectlMock::new()
+            .dns_result(Ok(CONNECTED.to_string()))
+            .set_dns_result(Ok(()))
+            .set_dns_result(Ok(()));
+        let set_dns_params_arc = resolvectl.set_dns_parameters.clone();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root.clone();
+        subject.resolvectl = Box::new(resolvectl);
+
+        let result = subject.subvert();
+
+        assert_eq!(result, Ok(()));
+        let set_dns_params = set_dns_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_dns_params,
+            vec![
+                ("eth0".to_string(), vec!["127.0.0.1".to_string()]),
+                ("wlan0".to_string(), vec!["127.0.0.1".to_string()]),
+            ]
+        );
+        assert_eq!(
+            get_backup(&root),
+            "eth0 10.0.2.3 8.8.8.8\nwlan0 8.8.8.8 1.1.1.1\n".to_string()
+        );
+    }
+
+    #[test]
+    fn subvert_does_nothing_if_already_subverted() {
+        let root = make_root("subvert_does_nothing_if_already_subverted");
+        let resolvectl = ResolvectlMock::new().dns_result(Ok(SUBVERTED.to_string()));
+        let set_dns_params_arc = resolvectl.set_dns_parameters.clone();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root.clone();
+        subject.resolvectl = Box::new(resolvectl);
+
+        let result = subject.subvert();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(set_dns_params_arc.lock().unwrap().is_empty(), true);
+        assert_eq!(subject.backup_path().exists(), false);
+    }
+
+    #[test]
+    fn subvert_complains_if_system_is_disconnected() {
+        let root = make_root("subvert_complains_if_system_is_disconnected");
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl =
+            Box::new(ResolvectlMock::new().dns_result(Ok("Global:\nLink 2 (eth0):\n".to_string())));
+
+        let result = subject.subvert();
+
+        assert_eq!(
+            result,
+            Err("This system does not appear to be connected to a network".to_string())
+        );
+    }
+
+    #[test]
+    fn subvert_refuses_to_leave_global_servers_in_place() {
+        vec![
+            "Global: 9.9.9.9 1.1.1.1\nLink 2 (eth0):\n",
+            "Global: 9.9.9.9 1.1.1.1\nLink 2 (eth0): 10.0.2.3\n",
+        ]
+        .into_iter()
+        .for_each(|dns| {
+            let root = make_root("subvert_refuses_to_leave_global_servers_in_place");
+            let resolvectl = ResolvectlMock::new().dns_result(Ok(dns.to_string()));
+            let set_dns_params_arc = resolvectl.set_dns_parameters.clone();
+            let mut subject = ResolvedDnsModifier::new();
+            subject.root = root;
+            subject.resolvectl = Box::new(resolvectl);
+
+            let result = subject.subvert();
+
+            assert_eq!(
+                result,
+                Err(
+                    "systemd-resolved has Global DNS servers (9.9.9.9 1.1.1.1) that can't be \
+                     subverted, so lookups could go around MASQ. Remove the DNS= setting from \
+                     /etc/systemd/resolved.conf and /etc/systemd/resolved.conf.d/*.conf, restart \
+                     systemd-resolved, and try again"
+                        .to_string()
+                ),
+                "{}",
+                dns
+            );
+            assert_eq!(set_dns_params_arc.lock().unwrap().is_empty(), true);
+            assert_eq!(subject.backup_path().exists(), false);
+        });
+    }
+
+    #[test]
+    fn subvert_complains_about_partially_subverted_settings() {
+        let root = make_root("subvert_complains_about_partially_subverted_settings");
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Ok(
+            "Link 2 (eth0): 127.0.0.1\nLink 4 (wlan0): 8.8.8.8\n".to_string(),
+        )));
+
+        let result = subject.subvert();
+
+        assert_eq!(
+            result,
+            Err("This system's DNS settings don't make sense; aborting".to_string())
+        );
+    }
+
+    #[test]
+    fn subvert_rolls_back_if_a_link_cannot_be_modified() {
+        let root = make_root("subvert_rolls_back_if_a_link_cannot_be_modified");
+        let resolvectl = ResolvectlMock::new()
+            .dns_result(Ok(CONNECTED.to_string()))
+            .set_dns_result(Ok(()))
+            .set_dns_result(Err(ResolvectlError::NonZeroExit(1)))
+            .set_dns_result(Ok(()));
+        let set_dns_params_arc = resolvectl.set_dns_parameters.clone();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(resolvectl);
+
+        let result = subject.subvert();
+
+        assert_eq!(
+            result,
+            Err(
+                "Could not modify systemd-resolved DNS settings: resolvectl exited with code 1"
+                    .to_string()
+            )
+        );
+        let set_dns_params = set_dns_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_dns_params,
+            vec![
+                ("eth0".to_string(), vec!["127.0.0.1".to_string()]),
+                ("wlan0".to_string(), vec!["127.0.0.1".to_string()]),
+                (
+                    "eth0".to_string(),
+                    vec!["10.0.2.3".to_string(), "8.8.8.8".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(subject.backup_path().exists(), false);
+    }
+
+    #[test]
+    fn revert_restores_backed_up_links_and_removes_the_backup() {
+        let root = make_root("revert_restores_backed_up_links_and_removes_the_backup");
+        make_backup(&root, "eth0 10.0.2.3 8.8.8.8\nwlan0 8.8.8.8 1.1.1.1\n");
+        let resolvectl = ResolvectlMock::new()
+            .set_dns_result(Ok(()))
+            .set_dns_result(Ok(()));
+        let set_dns_params_arc = resolvectl.set_dns_parameters.clone();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(resolvectl);
+
+        let result = subject.revert();
+
+        assert_eq!(result, Ok(()));
+        let set_dns_params = set_dns_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_dns_params,
+            vec![
+                (
+                    "eth0".to_string(),
+                    vec!["10.0.2.3".to_string(), "8.8.8.8".to_string()]
+                ),
+                (
+                    "wlan0".to_string(),
+                    vec!["8.8.8.8".to_string(), "1.1.1.1".to_string()]
+                ),
+            ]
+        );
+        assert_eq!(subject.backup_path().exists(), false);
+    }
+
+    #[test]
+    fn revert_does_nothing_if_not_subverted() {
+        let root = make_root("revert_does_nothing_if_not_subverted");
+        let resolvectl = ResolvectlMock::new().dns_result(Ok(CONNECTED.to_string()));
+        let set_dns_params_arc = resolvectl.set_dns_parameters.clone();
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(resolvectl);
+
+        let result = subject.revert();
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(set_dns_params_arc.lock().unwrap().is_empty(), true);
+    }
+
+    #[test]
+    fn revert_complains_if_subverted_without_a_backup() {
+        let root = make_root("revert_complains_if_subverted_without_a_backup");
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Ok(SUBVERTED.to_string())));
+
+        let result = subject.revert();
+
+        assert_eq!(
+            result,
+            Err("There do not appear to be any DNS settings to revert to".to_string())
+        );
+    }
+
+    #[test]
+    fn saved_nameservers_come_from_the_backup_when_subverted() {
+        let root = make_root("saved_nameservers_come_from_the_backup_when_subverted");
+        make_backup(&root, "eth0 10.0.2.3 8.8.8.8\nwlan0 8.8.8.8 1.1.1.1\n");
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(ResolvectlMock::new());
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Ok(vec![
+                "10.0.2.3".to_string(),
+                "8.8.8.8".to_string(),
+                "1.1.1.1".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn saved_nameservers_come_from_resolved_when_not_subverted() {
+        let root = make_root("saved_nameservers_come_from_resolved_when_not_subverted");
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(ResolvectlMock::new().dns_result(Ok(CONNECTED.to_string())));
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Ok(vec![
+                "10.0.2.3".to_string(),
+                "8.8.8.8".to_string(),
+                "1.1.1.1".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn saved_nameservers_include_global_servers_when_no_link_has_any() {
+        let root = make_root("saved_nameservers_include_global_servers_when_no_link_has_any");
+        let mut subject = ResolvedDnsModifier::new();
+        subject.root = root;
+        subject.resolvectl = Box::new(
+            ResolvectlMock::new().dns_result(Ok("Global: 9.9.9.9\nLink 2 (eth0):\n".to_string())),
+        );
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(result, Ok(vec!["9.9.9.9".to_string()]));
+    }
+
+    fn make_root(test_name: &str) -> PathBuf {
+        let cur_dir = env::current_dir().unwrap();
+        let generated_dir = cur_dir.join(Path::new("generated"));
+        let suite_dir = generated_dir.join(Path::new("ResolvedDnsModifier"));
+        let base_dir = suite_dir.join(Path::new(test_name));
+        let _ = fs::remove_dir_all(base_dir.clone()); // don't care if it doesn't exist
+        fs::create_dir_all(base_dir.clone()).unwrap();
+        base_dir
+    }
+
+    fn make_backup(root: &PathBuf, contents: &str) {
+        let directory = Path::new(root).join(BACKUP_DIRECTORY);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join(BACKUP_FILE), contents).unwrap();
+    }
+
+    fn get_backup(root: &PathBuf) -> String {
+        fs::read_to_string(Path::new(root).join(BACKUP_DIRECTORY).join(BACKUP_FILE)).unwrap()
+    }
+}
@@ -90,6 +90,22 @@ impl DnsModifier for WinDnsModifier {
         write!(stdout, "{}", output).expect("write is broken");
         Ok(())
     }
+
+    fn saved_nameservers(&self) -> Result<Vec<String>, String> {
+        let interfaces = self.find_interfaces_to_inspect()?;
+        let saved_lists: HashSet<String> = interfaces
+            .iter()
+            .flat_map(|interface| interface.get_value("NameServerBak").ok())
+            .collect();
+        let dns_server_list_csv = match saved_lists.into_iter().next() {
+            Some(saved_list) => saved_list,
+            None => self.find_dns_server_list(interfaces)?,
+        };
+        Ok(dns_server_list_csv
+            .split(',')
+            .map(|dns_server| dns_server.to_string())
+            .collect())
+    }
 }
 
 impl Default for WinDnsModifier {
@@ -1693,6 +1709,43 @@ mod tests {
         assert_eq!(stream_holder.stdout.get_string(), String::new());
     }
 
+    #[test]
+    fn saved_nameservers_finds_backed_up_nameservers() {
+        let one_active_interface = RegKeyMock::default()
+            .get_value_result("DefaultGateway", Ok("Common Gateway IP".to_string()))
+            .get_value_result(
+                "DhcpDefaultGateway",
+                Err(Error::from_raw_os_error(NOT_FOUND)),
+            )
+            .get_value_result("NameServerBak", Ok("8.8.8.8,8.8.8.9".to_string()));
+        let another_active_interface = RegKeyMock::default()
+            .get_value_result("DefaultGateway", Err(Error::from_raw_os_error(NOT_FOUND)))
+            .get_value_result("DhcpDefaultGateway", Ok("Common Gateway IP".to_string()))
+            .get_value_result("NameServerBak", Ok("8.8.8.8,8.8.8.9".to_string()));
+        let interfaces = RegKeyMock::default()
+            .enum_keys_result(vec![
+                Ok("one_active_interface"),
+                Ok("another_active_interface"),
+            ])
+            .open_subkey_with_flags_result(Ok(Box::new(one_active_interface)))
+            .open_subkey_with_flags_result(Ok(Box::new(another_active_interface)));
+        let hive = RegKeyMock::default().open_subkey_with_flags_result(Ok(Box::new(interfaces)));
+        let mut subject = WinDnsModifier::default();
+        subject.hive = Box::new(hive);
+        let ipconfig = IpconfigWrapperMock::new();
+        subject.ipconfig = Box::new(ipconfig.get_adapters_result(build_adapter_stubs(&[
+            ("one_active_interface", "Ethernet"),
+            ("another_active_interface", "Wifi"),
+        ])));
+
+        let result = subject.saved_nameservers();
+
+        assert_eq!(
+            result,
+            Ok(vec!["8.8.8.8".to_string(), "8.8.8.9".to_string()])
+        );
+    }
+
     #[test]
     fn inspect_works_if_everything_is_fine() {
         let mut stream_holder = FakeStreamHolder::new();
@@ -1,12 +1,13 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
 use crate::command_context::ContextError::ConnectionRefused;
+use crate::commands::commands_common::{json_output, CommandError};
 use crate::communications::broadcast_handler::{
     BroadcastHandler, BroadcastHandlerReal, StreamFactory,
 };
-use crate::communications::connection_manager::{ConnectionManager, REDIRECT_TIMEOUT_MILLIS};
-use crate::communications::node_conversation::ClientError;
 use masq_lib::messages::{TIMEOUT_ERROR, UNMARSHAL_ERROR};
+use masq_lib::ui_client::node_conversation::ClientError;
+use masq_lib::ui_client::UiClient;
 use masq_lib::ui_gateway::MessageBody;
 use std::fmt::{Debug, Formatter};
 use std::io;
@@ -61,10 +62,11 @@ pub trait CommandContext {
 }
 
 pub struct CommandContextReal {
-    connection: ConnectionManager,
+    client: UiClient,
     pub stdin: Box<dyn Read>,
     pub stdout: Box<dyn Write>,
     pub stderr: Box<dyn Write>,
+    pub timeout_millis_opt: Option<u64>,
 }
 
 impl Debug for CommandContextReal {
@@ -75,12 +77,11 @@ impl Debug for CommandContextReal {
 
 impl CommandContext for CommandContextReal {
     fn active_port(&self) -> Option<u16> {
-        self.connection.active_ui_port()
+        self.client.active_port()
     }
 
     fn send(&mut self, outgoing_message: MessageBody) -> Result<(), ContextError> {
-        let conversation = self.connection.start_conversation();
-        match conversation.send(outgoing_message) {
+        match self.client.send_raw(outgoing_message) {
             Ok(_) => Ok(()),
             Err(e) => Err(e.into()),
         }
@@ -91,8 +92,8 @@ impl CommandContext for CommandContextReal {
         outgoing_message: MessageBody,
         timeout_millis: u64,
     ) -> Result<MessageBody, ContextError> {
-        let conversation = self.connection.start_conversation();
-        let incoming_message_result = conversation.transact(outgoing_message, timeout_millis);
+        let timeout_millis = self.timeout_millis_opt.unwrap_or(timeout_millis);
+        let incoming_message_result = self.client.transact_raw(outgoing_message, timeout_millis);
         let incoming_message = match incoming_message_result {
             Err(e) => return Err(e.into()),
             Ok(message) => match message.payload {
@@ -116,30 +117,106 @@ impl CommandContext for CommandContextReal {
     }
 
     fn close(&mut self) {
-        self.connection.close();
+        self.client.close();
     }
 }
 
 impl CommandContextReal {
     pub fn new(
         daemon_ui_port: u16,
+        ui_token_opt: Option<String>,
+        json: bool,
         broadcast_stream_factory: Box<dyn StreamFactory>,
     ) -> Result<Self, ContextError> {
-        let mut connection = ConnectionManager::new();
-        let broadcast_handler = BroadcastHandlerReal::new();
+        let broadcast_handler = BroadcastHandlerReal::new(json);
         let broadcast_handle = broadcast_handler.start(broadcast_stream_factory);
-        match connection.connect(daemon_ui_port, broadcast_handle, REDIRECT_TIMEOUT_MILLIS) {
-            Ok(_) => Ok(Self {
-                connection,
+        match UiClient::connect(daemon_ui_port, ui_token_opt, Some(broadcast_handle)) {
+            Ok(client) => Ok(Self {
+                client,
                 stdin: Box::new(io::stdin()),
                 stdout: Box::new(io::stdout()),
                 stderr: Box::new(io::stderr()),
+                timeout_millis_opt: None,
             }),
             Err(e) => Err(ConnectionRefused(format!("{:?}", e))),
         }
     }
 }
 
+// In --json mode, commands run against this instead of the real context. Each successful response
+// payload is written to the real stdout as a line of JSON, and what the command writes for a human
+// is held back. A command that never hears from the Daemon or Node, like help, has only that to
+// say, so it's written to stdout as a line of JSON instead. An error fails the command only if the
+// command fails with it; one the command handles is part of its job.
+pub struct JsonCommandContext<'a> {
+    delegate: &'a mut dyn CommandContext,
+    human_output: Vec<u8>,
+    sink: io::Sink,
+    payload_written: bool,
+}
+
+impl<'a> CommandContext for JsonCommandContext<'a> {
+    fn active_port(&self) -> Option<u16> {
+        self.delegate.active_port()
+    }
+
+    fn send(&mut self, message: MessageBody) -> Result<(), ContextError> {
+        self.delegate.send(message)
+    }
+
+    fn transact(
+        &mut self,
+        message: MessageBody,
+        timeout_millis: u64,
+    ) -> Result<MessageBody, ContextError> {
+        let result = self.delegate.transact(message, timeout_millis);
+        if let Ok(MessageBody {
+            payload: Ok(payload),
+            ..
+        }) = &result
+        {
+            writeln!(self.delegate.stdout(), "{}", payload).expect("writeln! failed");
+            self.payload_written = true;
+        }
+        result
+    }
+
+    fn stdin(&mut self) -> &mut dyn Read {
+        self.delegate.stdin()
+    }
+
+    fn stdout(&mut self) -> &mut dyn Write {
+        &mut self.human_output
+    }
+
+    fn stderr(&mut self) -> &mut dyn Write {
+        &mut self.sink
+    }
+
+    fn close(&mut self) {
+        self.delegate.close()
+    }
+}
+
+impl<'a> JsonCommandContext<'a> {
+    pub fn new(delegate: &'a mut dyn CommandContext) -> Self {
+        Self {
+            delegate,
+            human_output: vec![],
+            sink: io::sink(),
+            payload_written: false,
+        }
+    }
+
+    pub fn finish(self, result: Result<(), CommandError>) -> Result<(), CommandError> {
+        if result.is_ok() && !self.payload_written && !self.human_output.is_empty() {
+            let output = String::from_utf8_lossy(&self.human_output);
+            writeln!(self.delegate.stdout(), "{}", json_output(&output)).expect("writeln! failed");
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,8 +224,11 @@ mod tests {
         ConnectionDropped, ConnectionRefused, PayloadError,
     };
     use crate::communications::broadcast_handler::StreamFactoryReal;
+    use crate::test_utils::mocks::{CommandContextMock, TestStreamFactory};
     use masq_lib::messages::{
-        FromMessageBody, UiCrashRequest, UiSetupRequest, TIMEOUT_ERROR, UNMARSHAL_ERROR,
+        FromMessageBody, UiCheckPasswordRequest, UiCheckPasswordResponse, UiCrashRequest,
+        UiNewPasswordBroadcast, UiSetupRequest, NODE_NOT_RUNNING_ERROR, TIMEOUT_ERROR,
+        UNMARSHAL_ERROR,
     };
     use masq_lib::messages::{ToMessageBody, UiShutdownRequest, UiShutdownResponse};
     use masq_lib::test_utils::fake_stream_holder::{ByteArrayReader, ByteArrayWriter};
@@ -206,7 +286,8 @@ mod tests {
         let server = MockWebSocketsServer::new(port);
         let handle = server.start();
 
-        let subject = CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+        let subject =
+            CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new())).unwrap();
 
         assert_eq!(subject.active_port(), Some(port));
         handle.stop();
@@ -224,7 +305,7 @@ mod tests {
         let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
         let stop_handle = server.start();
         let mut subject =
-            CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+            CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new())).unwrap();
         subject.stdin = Box::new(stdin);
         subject.stdout = Box::new(stdout);
         subject.stderr = Box::new(stderr);
@@ -256,7 +337,7 @@ mod tests {
         running_test();
         let port = find_free_port();
 
-        let result = CommandContextReal::new(port, Box::new(StreamFactoryReal::new()));
+        let result = CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new()));
 
         match result {
             Err(ConnectionRefused(_)) => (),
@@ -276,7 +357,7 @@ mod tests {
         });
         let stop_handle = server.start();
         let mut subject =
-            CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+            CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new())).unwrap();
 
         let response = subject.transact(UiSetupRequest { values: vec![] }.tmb(1), 1000);
 
@@ -291,7 +372,7 @@ mod tests {
         let server = MockWebSocketsServer::new(port).queue_string("disconnect");
         let stop_handle = server.start();
         let mut subject =
-            CommandContextReal::new(port, Box::new(StreamFactoryReal::new())).unwrap();
+            CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new())).unwrap();
 
         let response = subject.transact(UiSetupRequest { values: vec![] }.tmb(1), 1000);
 
@@ -314,7 +395,7 @@ mod tests {
         let server = MockWebSocketsServer::new(port);
         let stop_handle = server.start();
         let stream_factory = Box::new(StreamFactoryReal::new());
-        let subject_result = CommandContextReal::new(port, stream_factory);
+        let subject_result = CommandContextReal::new(port, None, false, stream_factory);
         let mut subject = subject_result.unwrap();
         subject.stdin = Box::new(stdin);
         subject.stdout = Box::new(stdout);
@@ -344,4 +425,115 @@ mod tests {
         );
         stop_handle.stop();
     }
+
+    #[test]
+    fn transact_uses_the_timeout_override_if_there_is_one() {
+        running_test();
+        let port = find_free_port();
+        let server =
+            MockWebSocketsServer::new(port).queue_response(UiNewPasswordBroadcast {}.tmb(0));
+        let stop_handle = server.start();
+        let (stream_factory, _) = TestStreamFactory::new();
+        let mut subject =
+            CommandContextReal::new(port, None, false, Box::new(stream_factory)).unwrap();
+        subject.timeout_millis_opt = Some(100);
+
+        let response = subject.transact(UiShutdownRequest {}.tmb(1), 60000);
+
+        assert_eq!(
+            response,
+            Err(PayloadError(
+                TIMEOUT_ERROR,
+                "No response from Node or Daemon after 100ms".to_string()
+            ))
+        );
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn json_context_writes_payloads_to_the_real_stdout_and_swallows_human_output() {
+        let mut delegate = CommandContextMock::new()
+            .transact_result(Ok(UiCheckPasswordResponse { matches: true }.tmb(1)));
+        let stdout_arc = delegate.stdout_arc();
+        let stderr_arc = delegate.stderr_arc();
+        let mut subject = JsonCommandContext::new(&mut delegate);
+
+        let response = subject.transact(
+            UiCheckPasswordRequest {
+                db_password_opt: None,
+            }
+            .tmb(1),
+            1000,
+        );
+        writeln!(subject.stdout(), "Password is correct").unwrap();
+        writeln!(subject.stderr(), "Something went wrong").unwrap();
+        let result = subject.finish(Ok(()));
+
+        assert_eq!(
+            UiCheckPasswordResponse::fmb(response.unwrap()).unwrap(),
+            (UiCheckPasswordResponse { matches: true }, 1)
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "{\"matches\":true}\n".to_string()
+        );
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+    }
+
+    #[test]
+    fn json_context_writes_output_to_the_real_stdout_as_json_if_there_are_no_payloads() {
+        let mut delegate = CommandContextMock::new();
+        let stdout_arc = delegate.stdout_arc();
+        let stderr_arc = delegate.stderr_arc();
+        let mut subject = JsonCommandContext::new(&mut delegate);
+
+        write!(subject.stdout(), "Usage:\n    \"masq\" [FLAGS]\n").unwrap();
+        writeln!(subject.stderr(), "Something went wrong").unwrap();
+        let result = subject.finish(Ok(()));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "{\"output\":\"Usage:\\n    \\\"masq\\\" [FLAGS]\\n\"}\n".to_string()
+        );
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+    }
+
+    #[test]
+    fn json_context_does_not_fail_a_command_that_handled_an_error() {
+        let mut delegate = CommandContextMock::new()
+            .transact_result(Err(PayloadError(
+                NODE_NOT_RUNNING_ERROR,
+                "Node is not running".to_string(),
+            )))
+            .transact_result(Err(ConnectionDropped("booga".to_string())));
+        let stdout_arc = delegate.stdout_arc();
+        let mut subject = JsonCommandContext::new(&mut delegate);
+
+        let _ = subject.transact(UiShutdownRequest {}.tmb(1), 1000);
+        let _ = subject.transact(UiShutdownRequest {}.tmb(2), 1000);
+        let result = subject.finish(Ok(()));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stdout_arc.lock().unwrap().get_string(), String::new());
+    }
+
+    #[test]
+    fn json_context_passes_along_the_error_the_command_failed_with_and_no_output() {
+        let mut delegate =
+            CommandContextMock::new().send_result(Err(ConnectionDropped("booga".to_string())));
+        let stdout_arc = delegate.stdout_arc();
+        let mut subject = JsonCommandContext::new(&mut delegate);
+
+        let _ = subject.send(UiShutdownRequest {}.tmb(0));
+        writeln!(subject.stdout(), "Shutdown failed").unwrap();
+        let result = subject.finish(Err(CommandError::Other("Command failed".to_string())));
+
+        assert_eq!(
+            result,
+            Err(CommandError::Other("Command failed".to_string()))
+        );
+        assert_eq!(stdout_arc.lock().unwrap().get_string(), String::new());
+    }
 }
@@ -1,12 +1,16 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
 use crate::command_factory::CommandFactoryError::{CommandSyntax, UnrecognizedSubcommand};
+use crate::commands::bandwidth_command::BandwidthCommand;
 use crate::commands::change_password_command::ChangePasswordCommand;
 use crate::commands::check_password_command::CheckPasswordCommand;
 use crate::commands::commands_common::Command;
 use crate::commands::crash_command::CrashCommand;
+use crate::commands::crash_report_command::CrashReportCommand;
 use crate::commands::descriptor_command::DescriptorCommand;
 use crate::commands::generate_wallets_command::GenerateWalletsCommand;
+use crate::commands::help_command::HelpCommand;
+use crate::commands::logs_command::LogsCommand;
 use crate::commands::setup_command::SetupCommand;
 use crate::commands::shutdown_command::ShutdownCommand;
 use crate::commands::start_command::StartCommand;
@@ -27,6 +31,10 @@ pub struct CommandFactoryReal {}
 impl CommandFactory for CommandFactoryReal {
     fn make(&self, pieces: Vec<String>) -> Result<Box<dyn Command>, CommandFactoryError> {
         let boxed_command: Box<dyn Command> = match pieces[0].as_str() {
+            "bandwidth" => match BandwidthCommand::new(pieces) {
+                Ok(command) => Box::new(command),
+                Err(msg) => return Err(CommandSyntax(msg)),
+            },
             "change-password" => match ChangePasswordCommand::new_change(pieces) {
                 Ok(command) => Box::new(command),
                 Err(msg) => return Err(CommandSyntax(msg)),
@@ -39,11 +47,23 @@ impl CommandFactory for CommandFactoryReal {
                 Ok(command) => Box::new(command),
                 Err(msg) => return Err(CommandSyntax(msg)),
             },
+            "crash-report" => match CrashReportCommand::new(pieces) {
+                Ok(command) => Box::new(command),
+                Err(msg) => return Err(CommandSyntax(msg)),
+            },
             "descriptor" => Box::new(DescriptorCommand::new()),
             "generate-wallets" => match GenerateWalletsCommand::new(pieces) {
                 Ok(command) => Box::new(command),
                 Err(msg) => return Err(CommandSyntax(msg)),
             },
+            "help" => match HelpCommand::new(pieces) {
+                Ok(command) => Box::new(command),
+                Err(msg) => return Err(CommandSyntax(msg)),
+            },
+            "logs" => match LogsCommand::new(pieces) {
+                Ok(command) => Box::new(command),
+                Err(msg) => return Err(CommandSyntax(msg)),
+            },
             "set-password" => match ChangePasswordCommand::new_set(pieces) {
                 Ok(command) => Box::new(command),
                 Err(msg) => return Err(CommandSyntax(msg)),
@@ -199,6 +219,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn factory_produces_help() {
+        let subject = CommandFactoryReal::new();
+
+        let command = subject
+            .make(vec!["help".to_string(), "setup".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            command.as_any().downcast_ref::<HelpCommand>().unwrap(),
+            &HelpCommand {
+                command_opt: Some("setup".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn complains_about_help_for_unknown_command() {
+        let subject = CommandFactoryReal::new();
+
+        let result = subject
+            .make(vec!["help".to_string(), "booga".to_string()])
+            .err()
+            .unwrap();
+
+        match result {
+            CommandSyntax(msg) => assert_eq!(
+                msg.starts_with("There is no 'booga' command."),
+                true,
+                "{}",
+                msg
+            ),
+            x => panic!("Expected syntax error, got {:?}", x),
+        }
+    }
+
     #[test]
     fn factory_produces_set_password() {
         let subject = CommandFactoryReal::new();
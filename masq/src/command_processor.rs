@@ -1,11 +1,14 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
-use crate::command_context::CommandContextReal;
 use crate::command_context::{CommandContext, ContextError};
+use crate::command_context::{CommandContextReal, JsonCommandContext};
 use crate::commands::commands_common::{Command, CommandError};
 use crate::communications::broadcast_handler::StreamFactory;
 use crate::schema::app;
 use clap::value_t;
+use masq_lib::ui_auth::{read_ui_token, ui_token_path};
+use std::any::Any;
+use std::path::{Path, PathBuf};
 
 pub trait CommandProcessorFactory {
     fn make(
@@ -26,8 +29,15 @@ impl CommandProcessorFactory for CommandProcessorFactoryReal {
     ) -> Result<Box<dyn CommandProcessor>, CommandError> {
         let matches = app().get_matches_from(args);
         let ui_port = value_t!(matches, "ui-port", u16).expect("ui-port is not properly defaulted");
-        match CommandContextReal::new(ui_port, broadcast_stream_factory) {
-            Ok(context) => Ok(Box::new(CommandProcessorReal { context })),
+        let json = matches.is_present("json");
+        let timeout_millis_opt = value_t!(matches, "timeout", u64).ok();
+        let ui_token_opt = ui_token_opt(matches.value_of("ui-token-file"), default_ui_token_file())
+            .map_err(CommandError::ConnectionProblem)?;
+        match CommandContextReal::new(ui_port, ui_token_opt, json, broadcast_stream_factory) {
+            Ok(mut context) => {
+                context.timeout_millis_opt = timeout_millis_opt;
+                Ok(Box::new(CommandProcessorReal { context, json }))
+            }
             Err(ContextError::ConnectionRefused(s)) => Err(CommandError::ConnectionProblem(s)),
             Err(e) => panic!("Unexpected error: {:?}", e),
         }
@@ -40,24 +50,57 @@ impl CommandProcessorFactoryReal {
     }
 }
 
+fn default_ui_token_file() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| ui_token_path(&dir.join("MASQ")))
+}
+
+// A token file the user names must be usable; the default one is only there if the Daemon was
+// started with --ui-auth, and without it masq simply connects with read-only access.
+fn ui_token_opt(
+    ui_token_file_opt: Option<&str>,
+    default_ui_token_file_opt: Option<PathBuf>,
+) -> Result<Option<String>, String> {
+    match ui_token_file_opt {
+        Some(ui_token_file) => read_ui_token(Path::new(ui_token_file))
+            .map(Some)
+            .map_err(|e| format!("Couldn't read UI token from {}: {}", ui_token_file, e)),
+        None => Ok(default_ui_token_file_opt.and_then(|path| read_ui_token(&path).ok())),
+    }
+}
+
 pub trait CommandProcessor {
     fn process(&mut self, command: Box<dyn Command>) -> Result<(), CommandError>;
     fn close(&mut self);
+
+    fn as_any(&self) -> &dyn Any {
+        unimplemented!("If your tests don't use this, don't implement it");
+    }
 }
 
 pub struct CommandProcessorReal {
     #[allow(dead_code)]
     context: CommandContextReal,
+    json: bool,
 }
 
 impl CommandProcessor for CommandProcessorReal {
     fn process(&mut self, command: Box<dyn Command>) -> Result<(), CommandError> {
-        command.execute(&mut self.context)
+        if self.json {
+            let mut json_context = JsonCommandContext::new(&mut self.context);
+            let result = command.execute(&mut json_context);
+            json_context.finish(result)
+        } else {
+            command.execute(&mut self.context)
+        }
     }
 
     fn close(&mut self) {
         self.context.close();
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -65,10 +108,22 @@ mod tests {
     use super::*;
     use crate::command_context::CommandContext;
     use crate::communications::broadcast_handler::StreamFactoryReal;
+    use crate::test_utils::mocks::TestStreamFactory;
+    use crossbeam_channel::unbounded;
     use masq_lib::messages::ToMessageBody;
+    use masq_lib::messages::{UiNewPasswordBroadcast, NODE_NOT_RUNNING_ERROR, TIMEOUT_ERROR};
     use masq_lib::messages::{UiShutdownRequest, UiShutdownResponse};
+    use masq_lib::test_utils::fake_stream_holder::ByteArrayWriter;
     use masq_lib::test_utils::mock_websockets_server::MockWebSocketsServer;
-    use masq_lib::utils::find_free_port;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+    use masq_lib::ui_auth::UI_TOKEN_HEADER;
+    use masq_lib::ui_gateway::MessageBody;
+    use masq_lib::ui_gateway::MessagePath::Conversation;
+    use masq_lib::utils::{find_free_port, localhost};
+    use std::io::Read;
+    use std::net::{SocketAddr, TcpListener};
+    use std::thread;
+    use std::time::Duration;
 
     #[derive(Debug)]
     struct TestCommand {}
@@ -124,4 +179,236 @@ mod tests {
         let received = stop_handle.stop();
         assert_eq!(received, vec![Ok(UiShutdownRequest {}.tmb(1))]);
     }
+
+    #[derive(Debug)]
+    struct ChattyCommand {}
+
+    impl Command for ChattyCommand {
+        fn execute<'a>(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+            writeln!(context.stdout(), "Shutting down").unwrap();
+            match context.transact(UiShutdownRequest {}.tmb(1), 1000) {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    writeln!(context.stderr(), "Shutdown failed: {:?}", e).unwrap();
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn json_mode_prints_response_payloads_instead_of_human_output() {
+        let port = find_free_port();
+        let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
+        let stop_handle = server.start();
+        let mut context =
+            CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new())).unwrap();
+        let stdout = ByteArrayWriter::new();
+        let stdout_arc = stdout.inner_arc();
+        let stderr = ByteArrayWriter::new();
+        let stderr_arc = stderr.inner_arc();
+        context.stdout = Box::new(stdout);
+        context.stderr = Box::new(stderr);
+        let mut subject = CommandProcessorReal {
+            context,
+            json: true,
+        };
+
+        let result = subject.process(Box::new(ChattyCommand {}));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(stdout_arc.lock().unwrap().get_string(), "{}\n".to_string());
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn json_mode_prints_the_output_of_a_command_that_handled_an_error_as_json() {
+        let port = find_free_port();
+        let server = MockWebSocketsServer::new(port).queue_response(MessageBody {
+            opcode: "shutdown".to_string(),
+            path: Conversation(1),
+            payload: Err((NODE_NOT_RUNNING_ERROR, "Node is not running".to_string())),
+        });
+        let stop_handle = server.start();
+        let mut context =
+            CommandContextReal::new(port, None, false, Box::new(StreamFactoryReal::new())).unwrap();
+        let stdout = ByteArrayWriter::new();
+        let stdout_arc = stdout.inner_arc();
+        let stderr = ByteArrayWriter::new();
+        let stderr_arc = stderr.inner_arc();
+        context.stdout = Box::new(stdout);
+        context.stderr = Box::new(stderr);
+        let mut subject = CommandProcessorReal {
+            context,
+            json: true,
+        };
+
+        let result = subject.process(Box::new(ChattyCommand {}));
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "{\"output\":\"Shutting down\\n\"}\n".to_string()
+        );
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn factory_turns_on_json_mode_when_asked() {
+        let port = find_free_port();
+        let args = [
+            "masq".to_string(),
+            "--ui-port".to_string(),
+            format!("{}", port),
+            "--json".to_string(),
+        ];
+        let subject = CommandProcessorFactoryReal::new();
+        let server = MockWebSocketsServer::new(port);
+        let stop_handle = server.start();
+
+        let processor = subject
+            .make(Box::new(StreamFactoryReal::new()), &args)
+            .unwrap();
+
+        let processor_real = processor
+            .as_any()
+            .downcast_ref::<CommandProcessorReal>()
+            .unwrap();
+        assert_eq!(processor_real.json, true);
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn factory_applies_the_timeout_override() {
+        let port = find_free_port();
+        let args = [
+            "masq".to_string(),
+            "--ui-port".to_string(),
+            format!("{}", port),
+            "--timeout".to_string(),
+            "100".to_string(),
+        ];
+        let subject = CommandProcessorFactoryReal::new();
+        let server =
+            MockWebSocketsServer::new(port).queue_response(UiNewPasswordBroadcast {}.tmb(0));
+        let stop_handle = server.start();
+        let (stream_factory, _) = TestStreamFactory::new();
+        let mut processor = subject.make(Box::new(stream_factory), &args).unwrap();
+
+        let result = processor.process(Box::new(TestCommand {}));
+
+        assert_eq!(
+            result,
+            Err(CommandError::Other(format!(
+                "{:?}",
+                ContextError::PayloadError(
+                    TIMEOUT_ERROR,
+                    "No response from Node or Daemon after 100ms".to_string()
+                )
+            )))
+        );
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn ui_token_opt_reads_the_specified_token_file() {
+        let home_dir = ensure_node_home_directory_exists(
+            "command_processor",
+            "ui_token_opt_reads_the_specified_token_file",
+        );
+        let token_file = home_dir.join("my-token");
+        std::fs::write(&token_file, "booga\n").unwrap();
+        let default_token_file = home_dir.join("ui-token");
+        std::fs::write(&default_token_file, "agoob\n").unwrap();
+
+        let result = ui_token_opt(token_file.to_str(), Some(default_token_file));
+
+        assert_eq!(result, Ok(Some("booga".to_string())));
+    }
+
+    #[test]
+    fn ui_token_opt_complains_about_an_unreadable_specified_token_file() {
+        let home_dir = ensure_node_home_directory_exists(
+            "command_processor",
+            "ui_token_opt_complains_about_an_unreadable_specified_token_file",
+        );
+        let token_file = home_dir.join("nonexistent");
+
+        let result = ui_token_opt(token_file.to_str(), None);
+
+        let message = result.err().unwrap();
+        assert_eq!(
+            message.starts_with(&format!(
+                "Couldn't read UI token from {}: ",
+                token_file.display()
+            )),
+            true,
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn ui_token_opt_uses_the_default_token_file_only_if_it_is_there() {
+        let home_dir = ensure_node_home_directory_exists(
+            "command_processor",
+            "ui_token_opt_uses_the_default_token_file_only_if_it_is_there",
+        );
+        let default_token_file = home_dir.join("ui-token");
+        std::fs::write(&default_token_file, "booga\n").unwrap();
+
+        let present_result = ui_token_opt(None, Some(default_token_file));
+        let missing_result = ui_token_opt(None, Some(home_dir.join("nonexistent")));
+        let undefined_result = ui_token_opt(None, None);
+
+        assert_eq!(present_result, Ok(Some("booga".to_string())));
+        assert_eq!(missing_result, Ok(None));
+        assert_eq!(undefined_result, Ok(None));
+    }
+
+    #[test]
+    fn factory_presents_the_ui_token_when_connecting() {
+        let home_dir = ensure_node_home_directory_exists(
+            "command_processor",
+            "factory_presents_the_ui_token_when_connecting",
+        );
+        let token_file = home_dir.join("ui-token");
+        std::fs::write(&token_file, "booga\n").unwrap();
+        let port = find_free_port();
+        let listener = TcpListener::bind(SocketAddr::new(localhost(), port)).unwrap();
+        let (tx, rx) = unbounded();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = vec![];
+            let mut buf = [0u8; 1024];
+            while !String::from_utf8_lossy(&head).contains("\r\n\r\n") {
+                let len = stream.read(&mut buf).unwrap();
+                if len == 0 {
+                    break;
+                }
+                head.extend_from_slice(&buf[..len]);
+            }
+            tx.send(String::from_utf8_lossy(&head).to_string()).unwrap();
+        });
+        let args = [
+            "masq".to_string(),
+            "--ui-port".to_string(),
+            format!("{}", port),
+            "--ui-token-file".to_string(),
+            token_file.to_string_lossy().to_string(),
+        ];
+        let subject = CommandProcessorFactoryReal::new();
+
+        let _ = subject.make(Box::new(StreamFactoryReal::new()), &args);
+
+        let head = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            head.contains(&format!("{}: booga\r\n", UI_TOKEN_HEADER)),
+            true,
+            "{}",
+            head
+        );
+    }
 }
@@ -0,0 +1,253 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use masq_lib::messages::{
+    UiBandwidthLimitsRequest, UiBandwidthLimitsResponse, UiServiceBandwidthLimits,
+};
+use masq_lib::shared_schema::common_validators::validate_bandwidth_limits;
+use std::any::Any;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub struct BandwidthCommand {
+    pub routing_opt: Option<UiServiceBandwidthLimits>,
+    pub exit_opt: Option<UiServiceBandwidthLimits>,
+}
+
+pub fn bandwidth_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("bandwidth")
+        .about("Displays or changes the bandwidth limits on the routing and exit services your Node provides, \
+                and shows whether those limits are currently holding traffic back")
+        .arg(
+            Arg::with_name("routing")
+                .help("New routing limits in kilobytes per second: download:upload:neighbor-download:neighbor-upload. \
+                       Zero means unlimited.")
+                .long("routing")
+                .value_name("KBPS")
+                .takes_value(true)
+                .validator(validate_bandwidth_limits),
+        )
+        .arg(
+            Arg::with_name("exit")
+                .help("New exit limits in kilobytes per second: download:upload:neighbor-download:neighbor-upload. \
+                       Zero means unlimited.")
+                .long("exit")
+                .value_name("KBPS")
+                .takes_value(true)
+                .validator(validate_bandwidth_limits),
+        )
+}
+
+impl Command for BandwidthCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let response: UiBandwidthLimitsResponse = transaction(
+            UiBandwidthLimitsRequest {
+                routing_opt: self.routing_opt,
+                exit_opt: self.exit_opt,
+            },
+            context,
+            STANDARD_COMMAND_TIMEOUT_MILLIS,
+        )?;
+        Self::write_limits(&response, context.stdout());
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl BandwidthCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match bandwidth_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        Ok(Self {
+            routing_opt: Self::limits_from(&matches, "routing"),
+            exit_opt: Self::limits_from(&matches, "exit"),
+        })
+    }
+
+    fn limits_from(matches: &ArgMatches, name: &str) -> Option<UiServiceBandwidthLimits> {
+        matches
+            .value_of(name)
+            .map(|value| UiServiceBandwidthLimits::from_str(value).expect("Validator failed"))
+    }
+
+    fn write_limits(response: &UiBandwidthLimitsResponse, stdout: &mut dyn Write) {
+        writeln!(
+            stdout,
+            "{:10}{:>12}{:>12}{:>20}{:>20}",
+            "KB/s", "Download", "Upload", "Neighbor download", "Neighbor upload"
+        )
+        .expect("writeln! failed");
+        Self::write_service_limits("Routing", &response.routing, stdout);
+        Self::write_service_limits("Exit", &response.exit, stdout);
+        writeln!(
+            stdout,
+            "{}",
+            if response.throttled {
+                "The limits are holding traffic back."
+            } else {
+                "The limits are not holding traffic back."
+            }
+        )
+        .expect("writeln! failed");
+    }
+
+    fn write_service_limits(name: &str, limits: &UiServiceBandwidthLimits, stdout: &mut dyn Write) {
+        let show = |kbps: u64| {
+            if kbps == 0 {
+                "unlimited".to_string()
+            } else {
+                kbps.to_string()
+            }
+        };
+        writeln!(
+            stdout,
+            "{:10}{:>12}{:>12}{:>20}{:>20}",
+            name,
+            show(limits.download),
+            show(limits.upload),
+            show(limits.neighbor_download),
+            show(limits.neighbor_upload)
+        )
+        .expect("writeln! failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryReal};
+    use crate::commands::commands_common::CommandError::Payload;
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::ToMessageBody;
+    use std::sync::{Arc, Mutex};
+
+    fn make_limits(
+        download: u64,
+        upload: u64,
+        neighbor_download: u64,
+        neighbor_upload: u64,
+    ) -> UiServiceBandwidthLimits {
+        UiServiceBandwidthLimits {
+            download,
+            upload,
+            neighbor_download,
+            neighbor_upload,
+        }
+    }
+
+    #[test]
+    fn testing_command_factory_here() {
+        let factory = CommandFactoryReal::new();
+
+        let result = factory
+            .make(vec![
+                "bandwidth".to_string(),
+                "--exit".to_string(),
+                "2048:512:0:128".to_string(),
+            ])
+            .unwrap();
+
+        let command = result.as_any().downcast_ref::<BandwidthCommand>().unwrap();
+        assert_eq!(
+            command,
+            &BandwidthCommand {
+                routing_opt: None,
+                exit_opt: Some(make_limits(2048, 512, 0, 128)),
+            }
+        );
+    }
+
+    #[test]
+    fn command_line_is_interpreted_correctly() {
+        let make = |args: &[&str]| {
+            let mut pieces = vec!["bandwidth".to_string()];
+            pieces.extend(args.iter().map(|arg| arg.to_string()));
+            BandwidthCommand::new(pieces)
+        };
+
+        assert_eq!(
+            make(&[]),
+            Ok(BandwidthCommand {
+                routing_opt: None,
+                exit_opt: None,
+            })
+        );
+        assert_eq!(
+            make(&["--routing", "1:2:3:4", "--exit", "0:0:0:0"]),
+            Ok(BandwidthCommand {
+                routing_opt: Some(make_limits(1, 2, 3, 4)),
+                exit_opt: Some(make_limits(0, 0, 0, 0)),
+            })
+        );
+        assert_eq!(make(&["--routing", "1:2:3"]).is_err(), true);
+        assert_eq!(make(&["--exit", "fast"]).is_err(), true);
+        assert_eq!(make(&["--exit", "10000001:0:0:0"]).is_err(), true);
+    }
+
+    #[test]
+    fn bandwidth_command_sends_the_new_limits_and_displays_the_result() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiBandwidthLimitsResponse {
+                routing: make_limits(1024, 256, 0, 64),
+                exit: make_limits(0, 0, 0, 0),
+                throttled: true,
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = BandwidthCommand {
+            routing_opt: Some(make_limits(1024, 256, 0, 64)),
+            exit_opt: None,
+        };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiBandwidthLimitsRequest {
+                    routing_opt: Some(make_limits(1024, 256, 0, 64)),
+                    exit_opt: None,
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "KB/s          Download      Upload   Neighbor download     Neighbor upload\n\
+             Routing           1024         256           unlimited                  64\n\
+             Exit         unlimited   unlimited           unlimited           unlimited\n\
+             The limits are holding traffic back.\n"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn bandwidth_command_reports_failure() {
+        let mut context = CommandContextMock::new()
+            .transact_result(Err(ContextError::PayloadError(0x1234, "Booga".to_string())));
+        let subject = BandwidthCommand {
+            routing_opt: None,
+            exit_opt: None,
+        };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Err(Payload(0x1234, "Booga".to_string())));
+    }
+}
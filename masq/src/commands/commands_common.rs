@@ -6,12 +6,23 @@ use crate::commands::commands_common::CommandError::{
 };
 use masq_lib::messages::{FromMessageBody, ToMessageBody, UiMessageError};
 use masq_lib::ui_gateway::MessageBody;
+use serde_json::{json, Value};
 use std::any::Any;
 use std::fmt::Debug;
 use std::fmt::Display;
 
 pub const STANDARD_COMMAND_TIMEOUT_MILLIS: u64 = 5000;
 
+// Errors that originate in masq rather than in the Daemon or Node. Like the codes in
+// masq_lib::messages, these are part of the --json interface and must not change.
+pub const CONNECTION_PROBLEM_ERROR: u64 = 0x4000_0000_0000_0001;
+pub const TRANSMISSION_ERROR: u64 = 0x4000_0000_0000_0002;
+pub const RECEPTION_ERROR: u64 = 0x4000_0000_0000_0003;
+pub const UNEXPECTED_RESPONSE_ERROR: u64 = 0x4000_0000_0000_0004;
+pub const OTHER_COMMAND_ERROR: u64 = 0x4000_0000_0000_0005;
+pub const UNRECOGNIZED_COMMAND_ERROR: u64 = 0x4000_0000_0000_0006;
+pub const COMMAND_SYNTAX_ERROR: u64 = 0x4000_0000_0000_0007;
+
 #[derive(Debug, PartialEq)]
 pub enum CommandError {
     ConnectionProblem(String),
@@ -36,6 +47,50 @@ impl Display for CommandError {
     }
 }
 
+impl CommandError {
+    pub fn code(&self) -> u64 {
+        match self {
+            ConnectionProblem(_) => CONNECTION_PROBLEM_ERROR,
+            Transmission(_) => TRANSMISSION_ERROR,
+            Reception(_) => RECEPTION_ERROR,
+            UnexpectedResponse(_) => UNEXPECTED_RESPONSE_ERROR,
+            Payload(code, _) => *code,
+            Other(_) => OTHER_COMMAND_ERROR,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Payload(_, s) => s.clone(),
+            e => e.to_string(),
+        }
+    }
+}
+
+pub fn json_error(code: u64, message: &str) -> String {
+    json!({"error": {"code": code, "message": message}}).to_string()
+}
+
+pub fn json_output(output: &str) -> String {
+    json!({ "output": output }).to_string()
+}
+
+// A broadcast's payload is JSON already, so it goes in as is
+pub fn json_broadcast(message_body: &MessageBody) -> String {
+    match &message_body.payload {
+        Ok(payload) => json!({
+            "opcode": message_body.opcode,
+            "payload": serde_json::from_str::<Value>(payload)
+                .unwrap_or_else(|_| Value::String(payload.clone())),
+        }),
+        Err((code, message)) => json!({
+            "opcode": message_body.opcode,
+            "error": {"code": code, "message": message},
+        }),
+    }
+    .to_string()
+}
+
 pub trait Command: Debug {
     fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError>;
 
@@ -103,8 +158,8 @@ mod tests {
     };
     use crate::test_utils::mocks::CommandContextMock;
     use masq_lib::messages::{UiStartOrder, UiStartResponse};
-    use masq_lib::ui_gateway::MessageBody;
     use masq_lib::ui_gateway::MessagePath::Conversation;
+    use masq_lib::ui_gateway::{MessageBody, MessagePath};
 
     #[test]
     fn two_way_transaction_passes_dropped_connection_error() {
@@ -223,6 +278,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn command_errors_have_stable_codes() {
+        assert_eq!(
+            ConnectionProblem("string".to_string()).code(),
+            0x4000_0000_0000_0001
+        );
+        assert_eq!(
+            Transmission("string".to_string()).code(),
+            0x4000_0000_0000_0002
+        );
+        assert_eq!(
+            Reception("string".to_string()).code(),
+            0x4000_0000_0000_0003
+        );
+        assert_eq!(
+            UnexpectedResponse(UiMessageError::DeserializationError("string".to_string())).code(),
+            0x4000_0000_0000_0004
+        );
+        assert_eq!(Payload(1234, "string".to_string()).code(), 1234);
+        assert_eq!(Other("string".to_string()).code(), 0x4000_0000_0000_0005);
+    }
+
+    #[test]
+    fn payload_error_message_leaves_out_the_code() {
+        assert_eq!(
+            Payload(1234, "string".to_string()).message(),
+            "string".to_string()
+        );
+        assert_eq!(
+            Transmission("string".to_string()).message(),
+            "Transmission problem: string".to_string()
+        );
+    }
+
+    #[test]
+    fn json_error_is_one_line_of_json() {
+        let result = json_error(0x8000_0000_0000_0002, "Node is \"not\" running\nat all");
+
+        assert_eq!(
+            result,
+            r#"{"error":{"code":9223372036854775810,"message":"Node is \"not\" running\nat all"}}"#
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn json_output_is_one_line_of_json() {
+        let result = json_output("Usage:\n    \"masq\"\n");
+
+        assert_eq!(result, r#"{"output":"Usage:\n    \"masq\"\n"}"#.to_string());
+    }
+
+    #[test]
+    fn json_broadcast_is_one_line_of_json_with_the_payload_inline() {
+        let ok = json_broadcast(&MessageBody {
+            opcode: "crash".to_string(),
+            path: MessagePath::FireAndForget,
+            payload: Ok(r#"{"processId": 1234}"#.to_string()),
+        });
+        let err = json_broadcast(&MessageBody {
+            opcode: "crash".to_string(),
+            path: MessagePath::FireAndForget,
+            payload: Err((12, "bad\nthing".to_string())),
+        });
+
+        assert_eq!(
+            ok,
+            r#"{"opcode":"crash","payload":{"processId":1234}}"#.to_string()
+        );
+        assert_eq!(
+            err,
+            r#"{"error":{"code":12,"message":"bad\nthing"},"opcode":"crash"}"#.to_string()
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Couldn't redirect to Node: \"message\"")]
     fn context_error_converter_sad() {
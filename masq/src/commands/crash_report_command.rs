@@ -0,0 +1,532 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, AppSettings, Arg, SubCommand};
+use masq_lib::messages::{
+    CrashReason, UiCrashReport, UiCrashReportRequest, UiCrashReportResponse, UiCrashReportsRequest,
+    UiCrashReportsResponse, UiDeleteCrashReportsRequest, UiDeleteCrashReportsResponse,
+};
+use std::any::Any;
+use std::io::Write;
+
+#[derive(Debug, PartialEq)]
+pub enum CrashReportAction {
+    List,
+    Show(String),
+    Delete(Vec<String>),
+    DeleteAll,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CrashReportCommand {
+    pub action: CrashReportAction,
+}
+
+pub fn crash_report_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("crash-report")
+        .about("Lists, displays, or deletes the reports the Daemon has recorded about Node crashes")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("list").about("Lists the recorded crash reports, oldest first"),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Displays a crash report, including the panic message, backtrace, and setup")
+                .arg(
+                    Arg::with_name("id")
+                        .help("ID of the crash report to display, as shown by 'crash-report list'")
+                        .index(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("delete")
+                .about("Deletes crash reports")
+                .arg(
+                    Arg::with_name("id")
+                        .help("IDs of the crash reports to delete")
+                        .index(1)
+                        .multiple(true)
+                        .required_unless("all")
+                        .conflicts_with("all"),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .help("Deletes every recorded crash report")
+                        .long("all")
+                        .takes_value(false),
+                ),
+        )
+}
+
+impl Command for CrashReportCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        match &self.action {
+            CrashReportAction::List => {
+                let response = Self::list(context)?;
+                Self::write_list(&response, context.stdout());
+            }
+            CrashReportAction::Show(id) => {
+                let response: UiCrashReportResponse = transaction(
+                    UiCrashReportRequest { id: id.clone() },
+                    context,
+                    STANDARD_COMMAND_TIMEOUT_MILLIS,
+                )?;
+                Self::write_report(&response.report, context.stdout());
+            }
+            CrashReportAction::Delete(ids) => Self::delete(ids.clone(), context)?,
+            CrashReportAction::DeleteAll => {
+                let response = Self::list(context)?;
+                if response.reports.is_empty() {
+                    writeln!(context.stdout(), "There are no crash reports to delete.")
+                        .expect("writeln! failed");
+                } else {
+                    let ids = response
+                        .reports
+                        .into_iter()
+                        .map(|summary| summary.id)
+                        .collect();
+                    Self::delete(ids, context)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl CrashReportCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match crash_report_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        let action = match matches.subcommand() {
+            ("list", _) => CrashReportAction::List,
+            ("show", Some(sub_matches)) => CrashReportAction::Show(
+                sub_matches
+                    .value_of("id")
+                    .expect("id parameter is not properly required")
+                    .to_string(),
+            ),
+            ("delete", Some(sub_matches)) if sub_matches.is_present("all") => {
+                CrashReportAction::DeleteAll
+            }
+            ("delete", Some(sub_matches)) => CrashReportAction::Delete(
+                sub_matches
+                    .values_of("id")
+                    .expect("id parameter is not properly required")
+                    .map(|id| id.to_string())
+                    .collect(),
+            ),
+            (other, _) => panic!("Unexpected crash-report subcommand: '{}'", other),
+        };
+        Ok(Self { action })
+    }
+
+    fn list(context: &mut dyn CommandContext) -> Result<UiCrashReportsResponse, CommandError> {
+        transaction(
+            UiCrashReportsRequest {},
+            context,
+            STANDARD_COMMAND_TIMEOUT_MILLIS,
+        )
+    }
+
+    fn delete(ids: Vec<String>, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let response: UiDeleteCrashReportsResponse = transaction(
+            UiDeleteCrashReportsRequest { ids },
+            context,
+            STANDARD_COMMAND_TIMEOUT_MILLIS,
+        )?;
+        response.deleted.iter().for_each(|id| {
+            writeln!(context.stdout(), "Deleted crash report {}", id).expect("writeln! failed")
+        });
+        Ok(())
+    }
+
+    fn write_list(response: &UiCrashReportsResponse, stdout: &mut dyn Write) {
+        if response.reports.is_empty() {
+            writeln!(stdout, "No crash reports have been recorded.").expect("writeln! failed");
+            return;
+        }
+        writeln!(
+            stdout,
+            "{:22}{:27}{:10}{}",
+            "ID", "Time", "Process", "Panic message"
+        )
+        .expect("writeln! failed");
+        response.reports.iter().for_each(|summary| {
+            writeln!(
+                stdout,
+                "{:22}{:27}{:<10}{}",
+                summary.id,
+                summary.time,
+                summary.process_id,
+                summary
+                    .panic_message
+                    .as_ref()
+                    .map(|msg| msg.as_str())
+                    .unwrap_or("(none)")
+            )
+            .expect("writeln! failed")
+        });
+    }
+
+    fn write_report(report: &UiCrashReport, stdout: &mut dyn Write) {
+        let none = "(none)".to_string();
+        writeln!(stdout, "Crash report {}\n", report.id).expect("writeln! failed");
+        writeln!(stdout, "{:16}{}", "Time:", report.time).expect("writeln! failed");
+        writeln!(stdout, "{:16}{}", "Process ID:", report.process_id).expect("writeln! failed");
+        writeln!(
+            stdout,
+            "{:16}{}",
+            "Exit code:",
+            report
+                .exit_code
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| none.clone())
+        )
+        .expect("writeln! failed");
+        writeln!(
+            stdout,
+            "{:16}{}",
+            "Reason:",
+            Self::interpret_reason(&report.crash_reason).trim_end()
+        )
+        .expect("writeln! failed");
+        writeln!(
+            stdout,
+            "{:16}{}",
+            "Panic message:",
+            report.panic_message.as_ref().unwrap_or(&none)
+        )
+        .expect("writeln! failed");
+        writeln!(
+            stdout,
+            "{:16}{}",
+            "Panic location:",
+            report.panic_location.as_ref().unwrap_or(&none)
+        )
+        .expect("writeln! failed");
+        writeln!(
+            stdout,
+            "\nBacktrace:\n{}",
+            report.backtrace.as_ref().unwrap_or(&none).trim_end()
+        )
+        .expect("writeln! failed");
+        writeln!(stdout, "\nSetup:").expect("writeln! failed");
+        report.setup.iter().for_each(|value| {
+            writeln!(
+                stdout,
+                "{:29}{:64} {:?}",
+                value.name, value.value, value.status
+            )
+            .expect("writeln! failed")
+        });
+    }
+
+    fn interpret_reason(reason: &CrashReason) -> String {
+        match reason {
+            CrashReason::ChildWaitFailure(msg) => {
+                format!("The Daemon couldn't wait on the child process: {}", msg)
+            }
+            CrashReason::NoInformation => "No information".to_string(),
+            CrashReason::Unrecognized(msg) => msg.clone(),
+            CrashReason::DaemonCrashed => "The Daemon crashed".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryReal};
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::UiSetupResponseValueStatus::{Default, Set};
+    use masq_lib::messages::{
+        ToMessageBody, UiCrashReportSummary, UiSetupResponseValue, CRASH_REPORT_ERROR,
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn make_report() -> UiCrashReport {
+        UiCrashReport {
+            id: "20210203040506-2345".to_string(),
+            time: "2021-02-03T04:05:06+00:00".to_string(),
+            process_id: 2345,
+            exit_code: Some(101),
+            crash_reason: CrashReason::Unrecognized("Standard error\n".to_string()),
+            panic_message: Some("Intentional crash".to_string()),
+            panic_location: Some("src/dispatcher.rs:42:13".to_string()),
+            backtrace: Some("   0: backtrace::backtrace\n".to_string()),
+            setup: vec![
+                UiSetupResponseValue::new("data-directory", "/home/booga/data", Default),
+                UiSetupResponseValue::new("neighborhood-mode", "zero-hop", Set),
+            ],
+        }
+    }
+
+    fn make_subject(pieces: Vec<&str>) -> Result<CrashReportCommand, String> {
+        CrashReportCommand::new(pieces.into_iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn testing_command_factory_here() {
+        let factory = CommandFactoryReal::new();
+
+        let result = factory
+            .make(vec!["crash-report".to_string(), "list".to_string()])
+            .unwrap();
+
+        let crash_report_command: &CrashReportCommand = result.as_any().downcast_ref().unwrap();
+        assert_eq!(
+            crash_report_command,
+            &CrashReportCommand {
+                action: CrashReportAction::List
+            }
+        );
+    }
+
+    #[test]
+    fn parses_every_action() {
+        assert_eq!(
+            make_subject(vec!["crash-report", "show", "20210203040506-2345"]),
+            Ok(CrashReportCommand {
+                action: CrashReportAction::Show("20210203040506-2345".to_string())
+            })
+        );
+        assert_eq!(
+            make_subject(vec!["crash-report", "delete", "one", "two"]),
+            Ok(CrashReportCommand {
+                action: CrashReportAction::Delete(vec!["one".to_string(), "two".to_string()])
+            })
+        );
+        assert_eq!(
+            make_subject(vec!["crash-report", "delete", "--all"]),
+            Ok(CrashReportCommand {
+                action: CrashReportAction::DeleteAll
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_incomplete_commands() {
+        assert_eq!(make_subject(vec!["crash-report"]).is_err(), true);
+        assert_eq!(make_subject(vec!["crash-report", "show"]).is_err(), true);
+        assert_eq!(make_subject(vec!["crash-report", "delete"]).is_err(), true);
+        assert_eq!(
+            make_subject(vec!["crash-report", "delete", "one", "--all"]).is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn list_displays_summaries() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let report = make_report();
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiCrashReportsResponse {
+                reports: vec![UiCrashReportSummary::from(&report)],
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_subject(vec!["crash-report", "list"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "ID                    Time                       Process   Panic message\n\
+             20210203040506-2345   2021-02-03T04:05:06+00:00  2345      Intentional crash\n"
+        );
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiCrashReportsRequest {}.tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+    }
+
+    #[test]
+    fn list_says_so_when_there_are_no_reports() {
+        let mut context = CommandContextMock::new()
+            .transact_result(Ok(UiCrashReportsResponse { reports: vec![] }.tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_subject(vec!["crash-report", "list"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "No crash reports have been recorded.\n"
+        );
+    }
+
+    #[test]
+    fn show_displays_whole_report() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiCrashReportResponse {
+                report: make_report(),
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_subject(vec!["crash-report", "show", "20210203040506-2345"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            format!(
+                "Crash report 20210203040506-2345\n\n\
+                 Time:           2021-02-03T04:05:06+00:00\n\
+                 Process ID:     2345\n\
+                 Exit code:      101\n\
+                 Reason:         Standard error\n\
+                 Panic message:  Intentional crash\n\
+                 Panic location: src/dispatcher.rs:42:13\n\
+                 \nBacktrace:\n   0: backtrace::backtrace\n\
+                 \nSetup:\n\
+                 {:29}{:64} Default\n\
+                 {:29}{:64} Set\n",
+                "data-directory", "/home/booga/data", "neighborhood-mode", "zero-hop"
+            )
+        );
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiCrashReportRequest {
+                    id: "20210203040506-2345".to_string()
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+    }
+
+    #[test]
+    fn show_passes_along_unknown_id_error() {
+        let mut context =
+            CommandContextMock::new().transact_result(Err(ContextError::PayloadError(
+                CRASH_REPORT_ERROR,
+                "There is no crash report with ID 'booga'".to_string(),
+            )));
+        let subject = make_subject(vec!["crash-report", "show", "booga"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(CommandError::Payload(
+                CRASH_REPORT_ERROR,
+                "There is no crash report with ID 'booga'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn delete_deletes_named_reports() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let ids = vec!["one".to_string(), "two".to_string()];
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiDeleteCrashReportsResponse {
+                deleted: ids.clone(),
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_subject(vec!["crash-report", "delete", "one", "two"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Deleted crash report one\nDeleted crash report two\n"
+        );
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiDeleteCrashReportsRequest { ids }.tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+    }
+
+    #[test]
+    fn delete_all_lists_then_deletes_everything() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let report = make_report();
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiCrashReportsResponse {
+                reports: vec![UiCrashReportSummary::from(&report)],
+            }
+            .tmb(0)))
+            .transact_result(Ok(UiDeleteCrashReportsResponse {
+                deleted: vec![report.id.clone()],
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_subject(vec!["crash-report", "delete", "--all"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Deleted crash report 20210203040506-2345\n"
+        );
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![
+                (
+                    UiCrashReportsRequest {}.tmb(0),
+                    STANDARD_COMMAND_TIMEOUT_MILLIS
+                ),
+                (
+                    UiDeleteCrashReportsRequest {
+                        ids: vec![report.id]
+                    }
+                    .tmb(0),
+                    STANDARD_COMMAND_TIMEOUT_MILLIS
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_all_with_nothing_to_delete_sends_no_delete_request() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiCrashReportsResponse { reports: vec![] }.tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = make_subject(vec!["crash-report", "delete", "--all"]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "There are no crash reports to delete.\n"
+        );
+        assert_eq!(transact_params_arc.lock().unwrap().len(), 1);
+    }
+}
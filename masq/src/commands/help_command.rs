@@ -0,0 +1,122 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{Command, CommandError};
+use crate::schema::{app, subcommand_names, subcommands};
+use clap::{App, Arg, SubCommand};
+use std::any::Any;
+
+#[derive(Debug, PartialEq)]
+pub struct HelpCommand {
+    pub command_opt: Option<String>,
+}
+
+pub fn help_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("help")
+        .about("Lists the available commands, or describes the usage of one of them")
+        .arg(
+            Arg::with_name("command")
+                .help("Name of the command whose usage should be described")
+                .index(1)
+                .required(false),
+        )
+}
+
+impl Command for HelpCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        let mut stdout = context.stdout();
+        let result = match &self.command_opt {
+            None => app().write_help(&mut stdout),
+            Some(name) => subcommands()
+                .into_iter()
+                .find(|subcommand| subcommand.get_name() == name)
+                .expect("HelpCommand was made for an unrecognized command")
+                .write_help(&mut stdout),
+        };
+        result.expect("write_help failed");
+        writeln!(stdout).expect("writeln! failed");
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl HelpCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match help_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        match matches.value_of("command") {
+            None => Ok(Self { command_opt: None }),
+            Some(name) if subcommand_names().contains(&name.to_string()) => Ok(Self {
+                command_opt: Some(name.to_string()),
+            }),
+            Some(name) => Err(format!(
+                "There is no '{}' command. Try one of these: {}",
+                name,
+                subcommand_names().join(", ")
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mocks::CommandContextMock;
+
+    #[test]
+    fn help_with_no_command_lists_the_commands() {
+        let mut context = CommandContextMock::new();
+        let stdout_arc = context.stdout_arc();
+        let subject = HelpCommand::new(vec!["help".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let stdout = stdout_arc.lock().unwrap().get_string();
+        assert_eq!(stdout.contains("SUBCOMMANDS:"), true, "{}", stdout);
+        assert_eq!(stdout.contains("generate-wallets"), true, "{}", stdout);
+        assert_eq!(stdout.contains("--ui-port"), true, "{}", stdout);
+    }
+
+    #[test]
+    fn help_with_a_command_prints_its_usage() {
+        let mut context = CommandContextMock::new();
+        let stdout_arc = context.stdout_arc();
+        let stderr_arc = context.stderr_arc();
+        let subject = HelpCommand::new(vec!["help".to_string(), "crash".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let stdout = stdout_arc.lock().unwrap().get_string();
+        assert_eq!(stdout.starts_with("crash"), true, "{}", stdout);
+        assert_eq!(
+            stdout.contains("Causes an element of the Node to crash"),
+            true,
+            "{}",
+            stdout
+        );
+        assert_eq!(stdout.contains("<actor>"), true, "{}", stdout);
+        assert_eq!(stderr_arc.lock().unwrap().get_string(), String::new());
+    }
+
+    #[test]
+    fn help_complains_about_unknown_commands() {
+        let result = HelpCommand::new(vec!["help".to_string(), "booga".to_string()]);
+
+        assert_eq!(
+            result,
+            Err(
+                "There is no 'booga' command. Try one of these: bandwidth, change-password, \
+                 check-password, crash, crash-report, descriptor, generate-wallets, help, logs, \
+                 set-password, setup, shutdown, start"
+                    .to_string()
+            )
+        );
+    }
+}
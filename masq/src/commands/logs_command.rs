@@ -0,0 +1,460 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::command_context::CommandContext;
+use crate::commands::commands_common::{
+    transaction, Command, CommandError, STANDARD_COMMAND_TIMEOUT_MILLIS,
+};
+use clap::{App, Arg, SubCommand};
+use masq_lib::messages::{
+    UiLogLevelRequest, UiLogLevelResponse, UiLogSubscribeRequest, UiLogSubscribeResponse,
+    UiLogUnsubscribeRequest, UiLogUnsubscribeResponse,
+};
+use std::any::Any;
+use std::io::{Read, Write};
+
+const SETTABLE_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+const FOLLOWABLE_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+const DEFAULT_FOLLOW_LEVEL: &str = "info";
+
+#[derive(Debug, PartialEq)]
+pub enum LogsAction {
+    ShowLevels,
+    SetLevel {
+        components: Vec<String>,
+        level: String,
+    },
+    Follow {
+        components: Vec<String>,
+        level: String,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogsCommand {
+    pub action: LogsAction,
+}
+
+pub fn logs_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("logs")
+        .about("Displays or changes the Node's log levels, or follows the Node's log as it's written")
+        .arg(
+            Arg::with_name("component")
+                .help("Name of a Node component as it appears in the log, such as Neighborhood or ProxyServer. \
+                       May be repeated. Without it, --set-level changes the default level and --follow \
+                       displays every component.")
+                .long("component")
+                .value_name("COMPONENT")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("set-level")
+                .help("Changes the log level of the specified components, or the default log level")
+                .long("set-level")
+                .value_name("LEVEL")
+                .takes_value(true)
+                .possible_values(SETTABLE_LEVELS)
+                .conflicts_with("follow"),
+        )
+        .arg(
+            Arg::with_name("follow")
+                .help("Displays log lines as the Node writes them, until you press Enter")
+                .long("follow")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("level")
+                .help("With --follow, the most detailed level of log line to display [default: info]")
+                .long("level")
+                .value_name("LEVEL")
+                .takes_value(true)
+                .possible_values(FOLLOWABLE_LEVELS)
+                .requires("follow"),
+        )
+}
+
+impl Command for LogsCommand {
+    fn execute(&self, context: &mut dyn CommandContext) -> Result<(), CommandError> {
+        match &self.action {
+            LogsAction::ShowLevels => {
+                let response: UiLogLevelResponse = transaction(
+                    UiLogLevelRequest {
+                        component_opt: None,
+                        level_opt: None,
+                    },
+                    context,
+                    STANDARD_COMMAND_TIMEOUT_MILLIS,
+                )?;
+                Self::write_levels(&response, context.stdout());
+            }
+            LogsAction::SetLevel { components, level } => {
+                let component_opts = if components.is_empty() {
+                    vec![None]
+                } else {
+                    components.iter().cloned().map(Some).collect()
+                };
+                let mut last_response_opt: Option<UiLogLevelResponse> = None;
+                for component_opt in component_opts {
+                    last_response_opt = Some(transaction(
+                        UiLogLevelRequest {
+                            component_opt,
+                            level_opt: Some(level.clone()),
+                        },
+                        context,
+                        STANDARD_COMMAND_TIMEOUT_MILLIS,
+                    )?);
+                }
+                Self::write_levels(
+                    &last_response_opt.expect("No log level was set"),
+                    context.stdout(),
+                );
+            }
+            LogsAction::Follow { components, level } => Self::follow(components, level, context)?,
+        }
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl LogsCommand {
+    pub fn new(pieces: Vec<String>) -> Result<Self, String> {
+        let matches = match logs_subcommand().get_matches_from_safe(pieces) {
+            Ok(matches) => matches,
+            Err(e) => return Err(format!("{}", e)),
+        };
+        let components = matches
+            .values_of("component")
+            .map(|values| values.map(|value| value.to_string()).collect())
+            .unwrap_or_else(Vec::new);
+        let action = if matches.is_present("follow") {
+            LogsAction::Follow {
+                components,
+                level: matches
+                    .value_of("level")
+                    .unwrap_or(DEFAULT_FOLLOW_LEVEL)
+                    .to_string(),
+            }
+        } else if let Some(level) = matches.value_of("set-level") {
+            LogsAction::SetLevel {
+                components,
+                level: level.to_string(),
+            }
+        } else {
+            LogsAction::ShowLevels
+        };
+        Ok(Self { action })
+    }
+
+    fn follow(
+        components: &[String],
+        level: &str,
+        context: &mut dyn CommandContext,
+    ) -> Result<(), CommandError> {
+        let _: UiLogSubscribeResponse = transaction(
+            UiLogSubscribeRequest {
+                components: components.to_vec(),
+                level: level.to_string(),
+            },
+            context,
+            STANDARD_COMMAND_TIMEOUT_MILLIS,
+        )?;
+        let source = if components.is_empty() {
+            "every component".to_string()
+        } else {
+            components.join(", ")
+        };
+        writeln!(
+            context.stdout(),
+            "Following {} log lines from {}. Press Enter to stop.",
+            level,
+            source
+        )
+        .expect("writeln! failed");
+        context.stdout().flush().expect("flush failed");
+        Self::wait_for_enter(context.stdin());
+        let _: UiLogUnsubscribeResponse = transaction(
+            UiLogUnsubscribeRequest {},
+            context,
+            STANDARD_COMMAND_TIMEOUT_MILLIS,
+        )?;
+        writeln!(context.stdout(), "Stopped following the log.").expect("writeln! failed");
+        Ok(())
+    }
+
+    // Returns at the end of a line, or when there's nothing more to read
+    fn wait_for_enter(stdin: &mut dyn Read) {
+        let mut byte = [0u8];
+        while let Ok(1) = stdin.read(&mut byte) {
+            if byte[0] == b'\n' {
+                break;
+            }
+        }
+    }
+
+    fn write_levels(response: &UiLogLevelResponse, stdout: &mut dyn Write) {
+        writeln!(stdout, "{:24}{}", "Component", "Level").expect("writeln! failed");
+        writeln!(stdout, "{:24}{}", "(default)", response.default_level).expect("writeln! failed");
+        response
+            .component_levels
+            .iter()
+            .for_each(|component_level| {
+                writeln!(
+                    stdout,
+                    "{:24}{}",
+                    component_level.component, component_level.level
+                )
+                .expect("writeln! failed")
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_context::ContextError;
+    use crate::command_factory::{CommandFactory, CommandFactoryReal};
+    use crate::commands::commands_common::CommandError::Payload;
+    use crate::test_utils::mocks::CommandContextMock;
+    use masq_lib::messages::{ToMessageBody, UiComponentLogLevel};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn testing_command_factory_here() {
+        let factory = CommandFactoryReal::new();
+
+        let result = factory
+            .make(vec![
+                "logs".to_string(),
+                "--follow".to_string(),
+                "--component".to_string(),
+                "Neighborhood".to_string(),
+                "--component".to_string(),
+                "ProxyServer".to_string(),
+                "--level".to_string(),
+                "debug".to_string(),
+            ])
+            .unwrap();
+
+        let command = result.as_any().downcast_ref::<LogsCommand>().unwrap();
+        assert_eq!(
+            command,
+            &LogsCommand {
+                action: LogsAction::Follow {
+                    components: vec!["Neighborhood".to_string(), "ProxyServer".to_string()],
+                    level: "debug".to_string(),
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn command_line_is_interpreted_correctly() {
+        let make = |args: &[&str]| {
+            let mut pieces = vec!["logs".to_string()];
+            pieces.extend(args.iter().map(|arg| arg.to_string()));
+            LogsCommand::new(pieces).map(|command| command.action)
+        };
+
+        assert_eq!(make(&[]), Ok(LogsAction::ShowLevels));
+        assert_eq!(
+            make(&["--set-level", "warn"]),
+            Ok(LogsAction::SetLevel {
+                components: vec![],
+                level: "warn".to_string()
+            })
+        );
+        assert_eq!(
+            make(&["--follow"]),
+            Ok(LogsAction::Follow {
+                components: vec![],
+                level: "info".to_string()
+            })
+        );
+        assert_eq!(make(&["--level", "debug"]).is_err(), true);
+        assert_eq!(make(&["--follow", "--set-level", "debug"]).is_err(), true);
+        assert_eq!(make(&["--set-level", "loud"]).is_err(), true);
+        assert_eq!(make(&["--follow", "--level", "off"]).is_err(), true);
+    }
+
+    #[test]
+    fn show_levels_displays_default_and_component_levels() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiLogLevelResponse {
+                default_level: "warn".to_string(),
+                component_levels: vec![UiComponentLogLevel {
+                    component: "Neighborhood".to_string(),
+                    level: "debug".to_string(),
+                }],
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = LogsCommand::new(vec!["logs".to_string()]).unwrap();
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![(
+                UiLogLevelRequest {
+                    component_opt: None,
+                    level_opt: None,
+                }
+                .tmb(0),
+                STANDARD_COMMAND_TIMEOUT_MILLIS
+            )]
+        );
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Component               Level\n\
+             (default)               warn\n\
+             Neighborhood            debug\n"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn set_level_changes_each_component_and_displays_the_result() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiLogLevelResponse {
+                default_level: "warn".to_string(),
+                component_levels: vec![UiComponentLogLevel {
+                    component: "Neighborhood".to_string(),
+                    level: "trace".to_string(),
+                }],
+            }
+            .tmb(0)))
+            .transact_result(Ok(UiLogLevelResponse {
+                default_level: "warn".to_string(),
+                component_levels: vec![
+                    UiComponentLogLevel {
+                        component: "Neighborhood".to_string(),
+                        level: "trace".to_string(),
+                    },
+                    UiComponentLogLevel {
+                        component: "ProxyServer".to_string(),
+                        level: "trace".to_string(),
+                    },
+                ],
+            }
+            .tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = LogsCommand {
+            action: LogsAction::SetLevel {
+                components: vec!["Neighborhood".to_string(), "ProxyServer".to_string()],
+                level: "trace".to_string(),
+            },
+        };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![
+                (
+                    UiLogLevelRequest {
+                        component_opt: Some("Neighborhood".to_string()),
+                        level_opt: Some("trace".to_string()),
+                    }
+                    .tmb(0),
+                    STANDARD_COMMAND_TIMEOUT_MILLIS
+                ),
+                (
+                    UiLogLevelRequest {
+                        component_opt: Some("ProxyServer".to_string()),
+                        level_opt: Some("trace".to_string()),
+                    }
+                    .tmb(0),
+                    STANDARD_COMMAND_TIMEOUT_MILLIS
+                ),
+            ]
+        );
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Component               Level\n\
+             (default)               warn\n\
+             Neighborhood            trace\n\
+             ProxyServer             trace\n"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn set_level_reports_failure() {
+        let mut context = CommandContextMock::new().transact_result(Err(
+            ContextError::PayloadError(0x1234, "Logging has not been initialized".to_string()),
+        ));
+        let subject = LogsCommand {
+            action: LogsAction::SetLevel {
+                components: vec![],
+                level: "debug".to_string(),
+            },
+        };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(
+            result,
+            Err(Payload(
+                0x1234,
+                "Logging has not been initialized".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn follow_subscribes_until_enter_is_pressed_then_unsubscribes() {
+        let transact_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut context = CommandContextMock::new()
+            .stdin_input("\nleftover")
+            .transact_params(&transact_params_arc)
+            .transact_result(Ok(UiLogSubscribeResponse {}.tmb(0)))
+            .transact_result(Ok(UiLogUnsubscribeResponse {}.tmb(0)));
+        let stdout_arc = context.stdout_arc();
+        let subject = LogsCommand {
+            action: LogsAction::Follow {
+                components: vec!["Neighborhood".to_string()],
+                level: "debug".to_string(),
+            },
+        };
+
+        let result = subject.execute(&mut context);
+
+        assert_eq!(result, Ok(()));
+        let transact_params = transact_params_arc.lock().unwrap();
+        assert_eq!(
+            *transact_params,
+            vec![
+                (
+                    UiLogSubscribeRequest {
+                        components: vec!["Neighborhood".to_string()],
+                        level: "debug".to_string(),
+                    }
+                    .tmb(0),
+                    STANDARD_COMMAND_TIMEOUT_MILLIS
+                ),
+                (
+                    UiLogUnsubscribeRequest {}.tmb(0),
+                    STANDARD_COMMAND_TIMEOUT_MILLIS
+                ),
+            ]
+        );
+        assert_eq!(
+            stdout_arc.lock().unwrap().get_string(),
+            "Following debug log lines from Neighborhood. Press Enter to stop.\n\
+             Stopped following the log.\n"
+                .to_string()
+        );
+    }
+}
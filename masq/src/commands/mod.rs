@@ -1,11 +1,15 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
+pub mod bandwidth_command;
 pub mod change_password_command;
 pub mod check_password_command;
 pub mod commands_common;
 pub mod crash_command;
+pub mod crash_report_command;
 pub mod descriptor_command;
 pub mod generate_wallets_command;
+pub mod help_command;
+pub mod logs_command;
 pub mod setup_command;
 pub mod shutdown_command;
 pub mod start_command;
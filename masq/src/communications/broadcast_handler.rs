@@ -1,21 +1,22 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
 use crate::commands::change_password_command::ChangePasswordCommand;
+use crate::commands::commands_common::json_broadcast;
 use crate::commands::setup_command::SetupCommand;
 use crate::notifications::crashed_notification::CrashNotifier;
+use crate::notifications::log_notification::LogNotifier;
+use crate::notifications::restart_notification::RestartNotifier;
 use crossbeam_channel::{unbounded, Receiver, RecvError, Sender};
 use masq_lib::messages::{
-    FromMessageBody, UiNewPasswordBroadcast, UiNodeCrashedBroadcast, UiSetupBroadcast,
+    FromMessageBody, UiLogBroadcast, UiNewPasswordBroadcast, UiNodeCrashedBroadcast,
+    UiNodeRestartBroadcast, UiSetupBroadcast,
 };
+use masq_lib::ui_client::BroadcastHandle;
 use masq_lib::ui_gateway::MessageBody;
 use std::fmt::Debug;
 use std::io::Write;
 use std::thread;
 
-pub trait BroadcastHandle: Send {
-    fn send(&self, message_body: MessageBody);
-}
-
 pub struct BroadcastHandleGeneric {
     message_tx: Sender<MessageBody>,
 }
@@ -32,44 +33,56 @@ pub trait BroadcastHandler {
     fn start(self, stream_factory: Box<dyn StreamFactory>) -> Box<dyn BroadcastHandle>;
 }
 
-pub struct BroadcastHandlerReal {}
+// In --json mode, stdout carries nothing but lines of JSON, so every broadcast is written there as
+// one line of its own, with no prompt.
+pub struct BroadcastHandlerReal {
+    json: bool,
+}
 
 impl BroadcastHandler for BroadcastHandlerReal {
     fn start(self, stream_factory: Box<dyn StreamFactory>) -> Box<dyn BroadcastHandle> {
         let (message_tx, message_rx) = unbounded();
+        let json = self.json;
         thread::spawn(move || {
             let (mut stdout, mut stderr) = stream_factory.make();
             loop {
-                Self::thread_loop_guts(&message_rx, stdout.as_mut(), stderr.as_mut())
+                Self::thread_loop_guts(&message_rx, json, stdout.as_mut(), stderr.as_mut())
             }
         });
         Box::new(BroadcastHandleGeneric { message_tx })
     }
 }
 
-impl Default for BroadcastHandlerReal {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl BroadcastHandlerReal {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(json: bool) -> Self {
+        Self { json }
     }
 
     fn handle_message_body(
         message_body_result: Result<MessageBody, RecvError>,
+        json: bool,
         stdout: &mut dyn Write,
         stderr: &mut dyn Write,
     ) {
         match message_body_result {
             Err(_) => (), // Receiver died; masq is going down
+            Ok(message_body) if json => {
+                if let Ok((body, _)) = UiLogBroadcast::fmb(message_body.clone()) {
+                    LogNotifier::handle_broadcast(body, true, stdout);
+                } else {
+                    writeln!(stdout, "{}", json_broadcast(&message_body)).expect("writeln! failed");
+                    stdout.flush().expect("flush failed");
+                }
+            }
             Ok(message_body) => {
                 if let Ok((body, _)) = UiSetupBroadcast::fmb(message_body.clone()) {
                     SetupCommand::handle_broadcast(body, stdout);
                 } else if let Ok((body, _)) = UiNodeCrashedBroadcast::fmb(message_body.clone()) {
                     CrashNotifier::handle_broadcast(body, stdout);
+                } else if let Ok((body, _)) = UiNodeRestartBroadcast::fmb(message_body.clone()) {
+                    RestartNotifier::handle_broadcast(body, stdout);
+                } else if let Ok((body, _)) = UiLogBroadcast::fmb(message_body.clone()) {
+                    LogNotifier::handle_broadcast(body, false, stdout);
                 } else if let Ok((_, _)) = UiNewPasswordBroadcast::fmb(message_body.clone()) {
                     ChangePasswordCommand::handle_broadcast(stdout);
                 } else {
@@ -86,11 +99,12 @@ impl BroadcastHandlerReal {
 
     fn thread_loop_guts(
         message_rx: &Receiver<MessageBody>,
+        json: bool,
         stdout: &mut dyn Write,
         stderr: &mut dyn Write,
     ) {
         select! {
-            recv(message_rx) -> message_body_result => Self::handle_message_body (message_body_result, stdout, stderr),
+            recv(message_rx) -> message_body_result => Self::handle_message_body (message_body_result, json, stdout, stderr),
         }
     }
 }
@@ -125,14 +139,15 @@ mod tests {
     use super::*;
     use crate::test_utils::mocks::TestStreamFactory;
     use masq_lib::messages::UiSetupBroadcast;
-    use masq_lib::messages::{CrashReason, ToMessageBody, UiNodeCrashedBroadcast};
+    use masq_lib::messages::{CrashReason, RestartOutcome, ToMessageBody, UiNodeCrashedBroadcast};
     use masq_lib::ui_gateway::MessagePath;
+    use serde_json::{json, Value};
 
     #[test]
     fn broadcast_of_setup_triggers_correct_handler() {
         let (factory, handle) = TestStreamFactory::new();
         // This thread will leak, and will only stop when the tests stop running.
-        let subject = BroadcastHandlerReal::new().start(Box::new(factory));
+        let subject = BroadcastHandlerReal::new(false).start(Box::new(factory));
         let message = UiSetupBroadcast {
             running: true,
             values: vec![],
@@ -167,7 +182,7 @@ mod tests {
     fn broadcast_of_crashed_triggers_correct_handler() {
         let (factory, handle) = TestStreamFactory::new();
         // This thread will leak, and will only stop when the tests stop running.
-        let subject = BroadcastHandlerReal::new().start(Box::new(factory));
+        let subject = BroadcastHandlerReal::new(false).start(Box::new(factory));
         let message = UiNodeCrashedBroadcast {
             process_id: 1234,
             crash_reason: CrashReason::Unrecognized("Unknown crash reason".to_string()),
@@ -189,11 +204,155 @@ mod tests {
         );
     }
 
+    #[test]
+    fn broadcast_of_crashed_is_one_line_of_json_in_json_mode() {
+        let (factory, handle) = TestStreamFactory::new();
+        // This thread will leak, and will only stop when the tests stop running.
+        let subject = BroadcastHandlerReal::new(true).start(Box::new(factory));
+        let message = UiNodeCrashedBroadcast {
+            process_id: 1234,
+            crash_reason: CrashReason::Unrecognized("Unknown crash reason".to_string()),
+        }
+        .tmb(0);
+
+        subject.send(message);
+
+        let stdout = handle.stdout_so_far();
+        assert_eq!(stdout.ends_with('\n'), true, "stdout: '{}'", stdout);
+        assert_eq!(
+            stdout.trim_end().contains('\n'),
+            false,
+            "stdout: '{}'",
+            stdout
+        );
+        assert_eq!(stdout.contains("masq> "), false, "stdout: '{}'", stdout);
+        let line: Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(
+            line,
+            json!({
+                "opcode": "crashed",
+                "payload": {
+                    "processId": 1234,
+                    "crashReason": {"Unrecognized": "Unknown crash reason"}
+                }
+            })
+        );
+        assert_eq!(
+            handle.stderr_so_far(),
+            "".to_string(),
+            "stderr: '{}'",
+            stdout
+        );
+    }
+
+    #[test]
+    fn unrecognized_broadcast_is_passed_along_in_json_mode() {
+        let (factory, handle) = TestStreamFactory::new();
+        // This thread will leak, and will only stop when the tests stop running.
+        let subject = BroadcastHandlerReal::new(true).start(Box::new(factory));
+        let message = MessageBody {
+            opcode: "unrecognized".to_string(),
+            path: MessagePath::FireAndForget,
+            payload: Ok("{}".to_string()),
+        };
+
+        subject.send(message);
+
+        assert_eq!(
+            handle.stdout_so_far(),
+            "{\"opcode\":\"unrecognized\",\"payload\":{}}\n".to_string()
+        );
+        assert_eq!(handle.stderr_so_far(), String::new());
+    }
+
+    #[test]
+    fn broadcast_of_restart_triggers_correct_handler() {
+        let (factory, handle) = TestStreamFactory::new();
+        // This thread will leak, and will only stop when the tests stop running.
+        let subject = BroadcastHandlerReal::new(false).start(Box::new(factory));
+        let message = UiNodeRestartBroadcast {
+            attempt: 1,
+            outcome: RestartOutcome::Restarted(2345),
+        }
+        .tmb(0);
+
+        subject.send(message);
+
+        let stdout = handle.stdout_so_far();
+        assert_eq!(
+            stdout,
+            "\nThe Daemon restarted the Node as process 2345 (attempt 1).\n\nmasq> ".to_string()
+        );
+        assert_eq!(
+            handle.stderr_so_far(),
+            "".to_string(),
+            "stderr: '{}'",
+            stdout
+        );
+    }
+
+    #[test]
+    fn broadcast_of_log_line_triggers_correct_handler() {
+        let (factory, handle) = TestStreamFactory::new();
+        // This thread will leak, and will only stop when the tests stop running.
+        let subject = BroadcastHandlerReal::new(false).start(Box::new(factory));
+        let message = UiLogBroadcast {
+            time: "2021-02-03T04:05:06.789".to_string(),
+            level: "warn".to_string(),
+            component: "ProxyServer".to_string(),
+            message: "Stream went away".to_string(),
+        }
+        .tmb(0);
+
+        subject.send(message);
+
+        let stdout = handle.stdout_so_far();
+        assert_eq!(
+            stdout,
+            "2021-02-03T04:05:06.789 WARN: ProxyServer: Stream went away\n".to_string()
+        );
+        assert_eq!(
+            handle.stderr_so_far(),
+            "".to_string(),
+            "stderr: '{}'",
+            stdout
+        );
+    }
+
+    #[test]
+    fn broadcast_of_log_line_is_a_flat_json_object_in_json_mode() {
+        let (factory, handle) = TestStreamFactory::new();
+        // This thread will leak, and will only stop when the tests stop running.
+        let subject = BroadcastHandlerReal::new(true).start(Box::new(factory));
+        let message = UiLogBroadcast {
+            time: "2021-02-03T04:05:06.789".to_string(),
+            level: "warn".to_string(),
+            component: "ProxyServer".to_string(),
+            message: "Stream went away".to_string(),
+        }
+        .tmb(0);
+
+        subject.send(message);
+
+        let stdout = handle.stdout_so_far();
+        assert_eq!(
+            serde_json::from_str::<Value>(&stdout).unwrap(),
+            json!({
+                "time": "2021-02-03T04:05:06.789",
+                "level": "warn",
+                "component": "ProxyServer",
+                "message": "Stream went away",
+            })
+        );
+        assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+        assert_eq!(handle.stderr_so_far(), "".to_string());
+    }
+
     #[test]
     fn broadcast_of_new_password_triggers_correct_handler() {
         let (factory, handle) = TestStreamFactory::new();
         // This thread will leak, and will only stop when the tests stop running.
-        let subject = BroadcastHandlerReal::new().start(Box::new(factory));
+        let subject = BroadcastHandlerReal::new(false).start(Box::new(factory));
         let message = UiNewPasswordBroadcast {}.tmb(0);
 
         subject.send(message);
@@ -215,7 +374,7 @@ mod tests {
     fn unexpected_broadcasts_are_ineffectual_but_dont_kill_the_handler() {
         let (factory, handle) = TestStreamFactory::new();
         // This thread will leak, and will only stop when the tests stop running.
-        let subject = BroadcastHandlerReal::new().start(Box::new(factory));
+        let subject = BroadcastHandlerReal::new(false).start(Box::new(factory));
         let bad_message = MessageBody {
             opcode: "unrecognized".to_string(),
             path: MessagePath::FireAndForget,
@@ -1,5 +1,2 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 pub mod broadcast_handler;
-mod client_listener_thread;
-pub mod connection_manager;
-pub mod node_conversation;
@@ -1,14 +1,35 @@
 // Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
+use crate::schema::{setup_parameter_names, subcommand_names};
 use crate::utils::MASQ_PROMPT;
+use rustyline::completion::Completer;
 use rustyline::error::ReadlineError;
-use rustyline::Editor;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::fs;
+use std::fs::OpenOptions;
 use std::io;
 use std::io::ErrorKind;
 use std::io::{BufRead, Read};
+use std::path::{Path, PathBuf};
+
+pub const HISTORY_FILE_NAME: &str = "masq_history";
+
+// Lines that carry passwords or keys are kept out of the history, so that the secrets don't end
+// up lying around on disk.
+const SECRET_COMMANDS: &[&str] = &[
+    "change-password",
+    "check-password",
+    "generate-wallets",
+    "set-password",
+];
+const SECRET_PARAMETERS: &[&str] = &["--consuming-private-key", "--db-password"];
 
 pub struct LineReader {
     delegate: Box<dyn EditorTrait>,
+    history_path_opt: Option<PathBuf>,
 }
 
 impl Read for LineReader {
@@ -39,7 +60,12 @@ impl BufRead for LineReader {
                 other => return Err(io::Error::new(ErrorKind::Other, format!("{}", other))),
             },
         };
-        self.delegate.add_history_entry(&line);
+        if !contains_secrets(&line) && self.delegate.add_history_entry(&line) {
+            if let Some(history_path) = &self.history_path_opt {
+                // Losing the history is an inconvenience, not a reason to stop taking commands
+                let _ = self.delegate.save_history(history_path);
+            }
+        }
         let len = line.len();
         buf.clear();
         buf.push_str(&line);
@@ -55,19 +81,48 @@ impl Default for LineReader {
 
 impl LineReader {
     pub fn new() -> LineReader {
+        LineReader::with_history_path(default_history_path())
+    }
+
+    pub fn with_history_path(history_path_opt: Option<PathBuf>) -> LineReader {
+        let mut delegate = EditorReal::default();
+        if let Some(history_path) = &history_path_opt {
+            // There won't be a history file the first time
+            let _ = delegate.load_history(history_path);
+        }
         LineReader {
-            delegate: Box::new(EditorReal::default()),
+            delegate: Box::new(delegate),
+            history_path_opt,
         }
     }
 }
 
+fn default_history_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("MASQ").join(HISTORY_FILE_NAME))
+}
+
+fn contains_secrets(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    match words.next() {
+        None => false,
+        Some(command) if SECRET_COMMANDS.contains(&command) => true,
+        Some(_) => words.any(|word| {
+            SECRET_PARAMETERS
+                .iter()
+                .any(|parameter| word.starts_with(parameter))
+        }),
+    }
+}
+
 trait EditorTrait {
     fn readline(&mut self, prompt: &str) -> Result<String, ReadlineError>;
     fn add_history_entry(&mut self, line: &str) -> bool;
+    fn load_history(&mut self, path: &Path) -> Result<(), ReadlineError>;
+    fn save_history(&mut self, path: &Path) -> Result<(), ReadlineError>;
 }
 
 struct EditorReal {
-    delegate: Editor<()>,
+    delegate: Editor<MasqHelper>,
 }
 
 impl EditorTrait for EditorReal {
@@ -78,19 +133,121 @@ impl EditorTrait for EditorReal {
     fn add_history_entry(&mut self, line: &str) -> bool {
         self.delegate.add_history_entry(line)
     }
+
+    fn load_history(&mut self, path: &Path) -> Result<(), ReadlineError> {
+        self.delegate.load_history(path)
+    }
+
+    fn save_history(&mut self, path: &Path) -> Result<(), ReadlineError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Create the file readable only by its owner before anything is written to it; rustyline
+        // truncates an existing file rather than replacing it, so the permissions stick.
+        open_private(path)?;
+        self.delegate.save_history(path)
+    }
+}
+
+#[cfg(unix)]
+fn open_private(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(path)?;
+    // A history file left by an earlier version may still be readable by others.
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn open_private(path: &Path) -> io::Result<()> {
+    OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(())
 }
 
 impl Default for EditorReal {
     fn default() -> Self {
-        EditorReal {
-            delegate: Editor::new(),
+        let mut delegate = Editor::new();
+        delegate.set_helper(Some(MasqHelper::new()));
+        EditorReal { delegate }
+    }
+}
+
+// Completes command names at the start of the line and after "help", and parameter names after
+// "setup".
+struct MasqHelper {
+    command_names: Vec<String>,
+    setup_parameter_names: Vec<String>,
+}
+
+impl Completer for MasqHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.complete_line(line, pos))
+    }
+}
+
+impl Hinter for MasqHelper {
+    type Hint = String;
+}
+
+impl Highlighter for MasqHelper {}
+
+impl Validator for MasqHelper {}
+
+impl Helper for MasqHelper {}
+
+impl MasqHelper {
+    fn new() -> Self {
+        let mut command_names = subcommand_names();
+        command_names.push("exit".to_string());
+        command_names.sort();
+        Self {
+            command_names,
+            setup_parameter_names: setup_parameter_names()
+                .into_iter()
+                .map(|name| format!("--{}", name))
+                .collect(),
         }
     }
+
+    fn complete_line(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let before_cursor = &line[..pos];
+        let word_start = before_cursor
+            .rfind(char::is_whitespace)
+            .map_or(0, |idx| idx + 1);
+        let word = &before_cursor[word_start..];
+        let previous_words = before_cursor[..word_start]
+            .split_whitespace()
+            .collect::<Vec<&str>>();
+        let choices = match previous_words.as_slice() {
+            [] | ["help"] => &self.command_names,
+            ["setup", ..] if word.is_empty() || word.starts_with('-') => {
+                &self.setup_parameter_names
+            }
+            _ => return (word_start, vec![]),
+        };
+        let candidates = choices
+            .iter()
+            .filter(|choice| choice.starts_with(word))
+            .cloned()
+            .collect();
+        (word_start, candidates)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
     use std::cell::RefCell;
     use std::sync::{Arc, Mutex};
 
@@ -99,6 +256,7 @@ mod tests {
         readline_results: RefCell<Vec<Result<String, ReadlineError>>>,
         add_history_entry_params: Arc<Mutex<Vec<String>>>,
         add_history_entry_results: RefCell<Vec<bool>>,
+        save_history_params: Arc<Mutex<Vec<PathBuf>>>,
     }
 
     impl EditorTrait for EditorMock {
@@ -117,6 +275,18 @@ mod tests {
                 .push(line.to_string());
             self.add_history_entry_results.borrow_mut().remove(0)
         }
+
+        fn load_history(&mut self, _path: &Path) -> Result<(), ReadlineError> {
+            unimplemented!()
+        }
+
+        fn save_history(&mut self, path: &Path) -> Result<(), ReadlineError> {
+            self.save_history_params
+                .lock()
+                .unwrap()
+                .push(path.to_path_buf());
+            Ok(())
+        }
     }
 
     impl EditorMock {
@@ -126,6 +296,7 @@ mod tests {
                 readline_results: RefCell::new(vec![]),
                 add_history_entry_params: Arc::new(Mutex::new(vec![])),
                 add_history_entry_results: RefCell::new(vec![]),
+                save_history_params: Arc::new(Mutex::new(vec![])),
             }
         }
 
@@ -148,12 +319,17 @@ mod tests {
             self.add_history_entry_results.borrow_mut().push(result);
             self
         }
+
+        fn save_history_params(mut self, params: &Arc<Mutex<Vec<PathBuf>>>) -> Self {
+            self.save_history_params = params.clone();
+            self
+        }
     }
 
     #[test]
     #[should_panic(expected = "Should never be called")]
     fn read_doesnt_work() {
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
 
         let _ = subject.read(&mut [0; 0]);
     }
@@ -161,7 +337,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "Should never be called")]
     fn fill_buf_doesnt_work() {
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
 
         let _ = subject.fill_buf();
     }
@@ -169,7 +345,7 @@ mod tests {
     #[test]
     #[should_panic(expected = "Should never be called")]
     fn consume_doesnt_work() {
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
 
         let _ = subject.consume(0);
     }
@@ -184,7 +360,7 @@ mod tests {
             .readline_result(Ok(line.to_string()))
             .add_history_entry_params(&add_history_entry_params_arc)
             .add_history_entry_result(true);
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
         subject.delegate = Box::new(editor);
         let mut buf = "this should be overwritten".to_string();
 
@@ -201,7 +377,7 @@ mod tests {
     #[test]
     fn read_line_works_when_rustyline_says_eof() {
         let editor = EditorMock::new().readline_result(Err(ReadlineError::Eof));
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
         subject.delegate = Box::new(editor);
         let mut buf = String::new();
 
@@ -214,7 +390,7 @@ mod tests {
     #[test]
     fn read_line_works_when_rustyline_says_interrupted() {
         let editor = EditorMock::new().readline_result(Err(ReadlineError::Interrupted));
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
         subject.delegate = Box::new(editor);
         let mut buf = String::new();
 
@@ -230,7 +406,7 @@ mod tests {
             ErrorKind::Other,
             "Booga!",
         ))));
-        let mut subject = LineReader::new();
+        let mut subject = LineReader::with_history_path(None);
         subject.delegate = Box::new(editor);
         let mut buf = String::new();
 
@@ -239,4 +415,184 @@ mod tests {
         assert_eq!(result.err().unwrap().to_string(), "Booga!".to_string());
         assert_eq!(buf, String::new());
     }
+
+    #[test]
+    fn read_line_saves_history_after_each_new_entry() {
+        let save_history_params_arc = Arc::new(Mutex::new(vec![]));
+        let editor = EditorMock::new()
+            .readline_result(Ok("setup".to_string()))
+            .readline_result(Ok("setup".to_string()))
+            .add_history_entry_result(true)
+            .add_history_entry_result(false)
+            .save_history_params(&save_history_params_arc);
+        let mut subject = LineReader::with_history_path(None);
+        subject.delegate = Box::new(editor);
+        subject.history_path_opt = Some(PathBuf::from("/home/booga/masq_history"));
+        let mut buf = String::new();
+
+        subject.read_line(&mut buf).unwrap();
+        subject.read_line(&mut buf).unwrap();
+
+        let save_history_params = save_history_params_arc.lock().unwrap();
+        assert_eq!(
+            *save_history_params,
+            vec![PathBuf::from("/home/booga/masq_history")]
+        );
+    }
+
+    #[test]
+    fn read_line_keeps_secrets_out_of_the_history() {
+        let lines = vec![
+            "set-password --new-db-password booga",
+            "change-password booga agoob",
+            "check-password agoob",
+            "generate-wallets --db-password agoob",
+            "setup --chain ropsten --db-password agoob",
+            "setup --consuming-private-key=0123456789ABCDEF",
+            "setup --chain ropsten",
+        ];
+        let add_history_entry_params_arc = Arc::new(Mutex::new(vec![]));
+        let save_history_params_arc = Arc::new(Mutex::new(vec![]));
+        let editor = lines.iter().fold(
+            EditorMock::new()
+                .add_history_entry_params(&add_history_entry_params_arc)
+                .add_history_entry_result(true)
+                .save_history_params(&save_history_params_arc),
+            |editor, line| editor.readline_result(Ok(line.to_string())),
+        );
+        let mut subject =
+            LineReader::with_history_path(Some(PathBuf::from("/home/booga/masq_history")));
+        subject.delegate = Box::new(editor);
+        let mut buf = String::new();
+
+        lines.iter().for_each(|line| {
+            subject.read_line(&mut buf).unwrap();
+            assert_eq!(&buf, line);
+        });
+
+        let add_history_entry_params = add_history_entry_params_arc.lock().unwrap();
+        assert_eq!(
+            *add_history_entry_params,
+            vec!["setup --chain ropsten".to_string()]
+        );
+        let save_history_params = save_history_params_arc.lock().unwrap();
+        assert_eq!(save_history_params.len(), 1);
+    }
+
+    #[test]
+    fn contains_secrets_looks_only_at_the_command_and_its_parameter_names() {
+        assert_eq!(contains_secrets(""), false);
+        assert_eq!(contains_secrets("setup --db-password-booga"), true);
+        assert_eq!(contains_secrets("help set-password"), false);
+        assert_eq!(contains_secrets("descriptor"), false);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn history_file_is_readable_only_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+        let home_dir = ensure_node_home_directory_exists(
+            "line_reader",
+            "history_file_is_readable_only_by_its_owner",
+        );
+        let history_path = home_dir.join("history").join(HISTORY_FILE_NAME);
+        let mut subject = EditorReal::default();
+        subject.add_history_entry("setup");
+
+        subject.save_history(&history_path).unwrap();
+
+        let mode = fs::metadata(&history_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn history_file_left_readable_by_others_is_tightened_up() {
+        use std::os::unix::fs::PermissionsExt;
+        let home_dir = ensure_node_home_directory_exists(
+            "line_reader",
+            "history_file_left_readable_by_others_is_tightened_up",
+        );
+        let history_path = home_dir.join(HISTORY_FILE_NAME);
+        fs::write(&history_path, "setup\n").unwrap();
+        fs::set_permissions(&history_path, fs::Permissions::from_mode(0o644)).unwrap();
+        let mut subject = EditorReal::default();
+        subject.add_history_entry("descriptor");
+
+        subject.save_history(&history_path).unwrap();
+
+        let mode = fs::metadata(&history_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn history_goes_in_the_masq_data_directory() {
+        let result = default_history_path();
+
+        assert_eq!(
+            result,
+            dirs::data_local_dir().map(|dir| dir.join("MASQ").join("masq_history"))
+        );
+    }
+
+    #[test]
+    fn completes_command_names_at_the_start_of_the_line() {
+        let subject = MasqHelper::new();
+
+        assert_eq!(
+            subject.complete_line("se", 2),
+            (0, vec!["set-password".to_string(), "setup".to_string()])
+        );
+        assert_eq!(
+            subject.complete_line("  ex", 4),
+            (2, vec!["exit".to_string()])
+        );
+        assert_eq!(subject.complete_line("", 0).1, subject.command_names);
+    }
+
+    #[test]
+    fn completes_command_names_after_help() {
+        let subject = MasqHelper::new();
+
+        assert_eq!(
+            subject.complete_line("help ch", 7),
+            (
+                5,
+                vec!["change-password".to_string(), "check-password".to_string()]
+            )
+        );
+    }
+
+    #[test]
+    fn completes_setup_parameter_names() {
+        let subject = MasqHelper::new();
+
+        assert_eq!(
+            subject.complete_line("setup --chain ropsten --neighborhood-m", 38),
+            (22, vec!["--neighborhood-mode".to_string()])
+        );
+        assert_eq!(
+            subject.complete_line("setup --dns-s", 13),
+            (6, vec!["--dns-servers".to_string()])
+        );
+    }
+
+    #[test]
+    fn completes_only_the_part_of_the_line_before_the_cursor() {
+        let subject = MasqHelper::new();
+
+        assert_eq!(
+            subject.complete_line("setup --dns-s --chain ropsten", 13),
+            (6, vec!["--dns-servers".to_string()])
+        );
+    }
+
+    #[test]
+    fn offers_nothing_where_it_has_nothing_to_offer() {
+        let subject = MasqHelper::new();
+
+        assert_eq!(subject.complete_line("setup --chain ro", 16), (14, vec![]));
+        assert_eq!(subject.complete_line("descriptor --b", 14), (11, vec![]));
+        assert_eq!(subject.complete_line("booga", 5), (0, vec![]));
+    }
 }
@@ -5,6 +5,9 @@ use masq_cli_lib::command_factory::{CommandFactory, CommandFactoryReal};
 use masq_cli_lib::command_processor::{
     CommandProcessor, CommandProcessorFactory, CommandProcessorFactoryReal,
 };
+use masq_cli_lib::commands::commands_common::{
+    json_error, COMMAND_SYNTAX_ERROR, UNRECOGNIZED_COMMAND_ERROR,
+};
 use masq_cli_lib::communications::broadcast_handler::StreamFactoryReal;
 use masq_cli_lib::utils::{BufReadFactory, BufReadFactoryReal};
 use masq_lib::command;
@@ -12,6 +15,9 @@ use masq_lib::command::{Command, StdStreams};
 use std::io;
 use std::io::BufRead;
 
+// Global flags that take no value, and so can't be mistaken for the value of the flag before them
+const VALUELESS_GLOBAL_FLAGS: &[&str] = &["--json"];
+
 fn main() {
     let mut streams: StdStreams<'_> = StdStreams {
         stdin: &mut io::stdin(),
@@ -33,6 +39,7 @@ struct Main {
 
 impl command::Command for Main {
     fn go(&mut self, streams: &mut StdStreams<'_>, args: &[String]) -> u8 {
+        let json = Self::json_requested(args);
         let broadcast_stream_factory = StreamFactoryReal::new();
         let mut command_processor = match self
             .processor_factory
@@ -40,18 +47,24 @@ impl command::Command for Main {
         {
             Ok(processor) => processor,
             Err(e) => {
-                writeln!(streams.stderr, "Can't connect to Daemon or Node ({:?}). Probably this means the Daemon isn't running.", e).expect ("writeln! failed");
+                let message = format!("Can't connect to Daemon or Node ({:?}). Probably this means the Daemon isn't running.", e);
+                Self::write_error(streams.stderr, json, e.code(), &message);
                 return 1;
             }
         };
         let result = match Self::extract_subcommand(args) {
             Some(command_parts) => {
-                match self.handle_command(&mut *command_processor, command_parts, streams.stderr) {
+                match self.handle_command(
+                    &mut *command_processor,
+                    command_parts,
+                    streams.stderr,
+                    json,
+                ) {
                     Ok(_) => 0,
                     Err(_) => 1,
                 }
             }
-            None => self.go_interactive(&mut *command_processor, streams),
+            None => self.go_interactive(&mut *command_processor, streams, json),
         };
         command_processor.close();
         result
@@ -72,13 +85,30 @@ impl Main {
         for idx in 1..args_vec.len() {
             let one = &args_vec[idx - 1];
             let two = &args_vec[idx];
-            if !one.starts_with("--") && !two.starts_with("--") {
+            if (!one.starts_with("--") || VALUELESS_GLOBAL_FLAGS.contains(&one.as_str()))
+                && !two.starts_with("--")
+            {
                 return Some(args_vec.into_iter().skip(idx).collect());
             }
         }
         None
     }
 
+    fn json_requested(args: &[String]) -> bool {
+        let subcommand_len = Self::extract_subcommand(args).map_or(0, |parts| parts.len());
+        args[..(args.len() - subcommand_len)]
+            .iter()
+            .any(|arg| arg == "--json")
+    }
+
+    fn write_error(stderr: &mut dyn io::Write, json: bool, code: u64, message: &str) {
+        if json {
+            writeln!(stderr, "{}", json_error(code, message)).expect("writeln! failed");
+        } else {
+            writeln!(stderr, "{}", message).expect("writeln! failed");
+        }
+    }
+
     fn accept_subcommand(stdin: &mut dyn BufRead) -> Result<Option<Vec<String>>, std::io::Error> {
         let mut line = String::new();
         match stdin.read_line(&mut line) {
@@ -117,6 +147,7 @@ impl Main {
         &self,
         processor: &mut dyn CommandProcessor,
         streams: &mut StdStreams<'_>,
+        json: bool,
     ) -> u8 {
         let mut line_reader = self.buf_read_factory.make();
         loop {
@@ -134,7 +165,7 @@ impl Main {
             if args[0] == "exit" {
                 break;
             }
-            match self.handle_command(processor, args, streams.stderr) {
+            match self.handle_command(processor, args, streams.stderr, json) {
                 Ok(_) => (),
                 Err(_) => continue,
             }
@@ -147,20 +178,23 @@ impl Main {
         processor: &mut dyn CommandProcessor,
         command_parts: Vec<String>,
         stderr: &mut dyn io::Write,
+        json: bool,
     ) -> Result<(), ()> {
         let command = match self.command_factory.make(command_parts) {
             Ok(c) => c,
             Err(UnrecognizedSubcommand(msg)) => {
-                writeln!(stderr, "Unrecognized command: '{}'", msg).expect("writeln! failed");
+                let message = format!("Unrecognized command: '{}'", msg);
+                Self::write_error(stderr, json, UNRECOGNIZED_COMMAND_ERROR, &message);
                 return Err(());
             }
             Err(CommandSyntax(msg)) => {
-                writeln!(stderr, "{}", msg).expect("writeln! failed");
+                Self::write_error(stderr, json, COMMAND_SYNTAX_ERROR, &msg);
                 return Err(());
             }
         };
         if let Err(e) = processor.process(command) {
-            writeln!(stderr, "{}", e).expect("writeln! failed");
+            let message = if json { e.message() } else { e.to_string() };
+            Self::write_error(stderr, json, e.code(), &message);
             Err(())
         } else {
             Ok(())
@@ -177,6 +211,7 @@ mod tests {
     use masq_cli_lib::commands::commands_common;
     use masq_cli_lib::commands::commands_common::CommandError;
     use masq_cli_lib::commands::commands_common::CommandError::Transmission;
+    use masq_cli_lib::commands::commands_common::CONNECTION_PROBLEM_ERROR;
     use masq_cli_lib::test_utils::mocks::{
         CommandContextMock, CommandFactoryMock, CommandProcessorFactoryMock, CommandProcessorMock,
         MockCommand,
@@ -698,4 +733,149 @@ mod tests {
             "Can't connect to Daemon or Node (ConnectionProblem(\"booga\")). Probably this means the Daemon isn't running.\n".to_string()
         );
     }
+
+    #[test]
+    fn extract_subcommand_knows_that_json_takes_no_value() {
+        let result = Main::extract_subcommand(&[
+            "masq".to_string(),
+            "--ui-port".to_string(),
+            "5333".to_string(),
+            "--json".to_string(),
+            "setup".to_string(),
+            "--log-level".to_string(),
+            "debug".to_string(),
+        ]);
+
+        assert_eq!(
+            result,
+            Some(vec![
+                "setup".to_string(),
+                "--log-level".to_string(),
+                "debug".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn json_requested_looks_only_in_front_of_the_subcommand() {
+        assert_eq!(
+            Main::json_requested(&["masq".to_string(), "--json".to_string()]),
+            true
+        );
+        assert_eq!(
+            Main::json_requested(&[
+                "masq".to_string(),
+                "--json".to_string(),
+                "descriptor".to_string()
+            ]),
+            true
+        );
+        assert_eq!(
+            Main::json_requested(&[
+                "masq".to_string(),
+                "setup".to_string(),
+                "--json".to_string()
+            ]),
+            false
+        );
+        assert_eq!(Main::json_requested(&["masq".to_string()]), false);
+    }
+
+    #[test]
+    fn go_reports_command_failure_as_json_when_asked() {
+        let command = MockCommand::new(UiShutdownRequest {}.tmb(1)).execute_result(Ok(())); // irrelevant
+        let command_factory = CommandFactoryMock::new().make_result(Ok(Box::new(command)));
+        let processor = CommandProcessorMock::new().process_result(Err(CommandError::Payload(
+            0x8000_0000_0000_0002,
+            "Node is not running".to_string(),
+        )));
+        let processor_factory =
+            CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor)));
+        let mut subject = Main {
+            command_factory: Box::new(command_factory),
+            processor_factory: Box::new(processor_factory),
+            buf_read_factory: Box::new(BufReadFactoryMock::new()),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "command".to_string(),
+                "--json".to_string(),
+                "subcommand".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(stream_holder.stdout.get_string(), "".to_string());
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            "{\"error\":{\"code\":9223372036854775810,\"message\":\"Node is not running\"}}\n"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn go_reports_unrecognized_command_as_json_when_asked() {
+        let command_factory =
+            CommandFactoryMock::new().make_result(Err(UnrecognizedSubcommand("booga".to_string())));
+        let processor = CommandProcessorMock::new();
+        let processor_factory =
+            CommandProcessorFactoryMock::new().make_result(Ok(Box::new(processor)));
+        let mut subject = Main {
+            command_factory: Box::new(command_factory),
+            processor_factory: Box::new(processor_factory),
+            buf_read_factory: Box::new(BufReadFactoryMock::new()),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "command".to_string(),
+                "--json".to_string(),
+                "booga".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            format!(
+                "{{\"error\":{{\"code\":{},\"message\":\"Unrecognized command: 'booga'\"}}}}\n",
+                UNRECOGNIZED_COMMAND_ERROR
+            )
+        );
+    }
+
+    #[test]
+    fn go_reports_missing_daemon_as_json_when_asked() {
+        let processor_factory = CommandProcessorFactoryMock::new()
+            .make_result(Err(CommandError::ConnectionProblem("booga".to_string())));
+        let mut subject = Main {
+            command_factory: Box::new(CommandFactoryMock::new()),
+            processor_factory: Box::new(processor_factory),
+            buf_read_factory: Box::new(BufReadFactoryMock::new()),
+        };
+        let mut stream_holder = FakeStreamHolder::new();
+
+        let result = subject.go(
+            &mut stream_holder.streams(),
+            &[
+                "command".to_string(),
+                "--json".to_string(),
+                "subcommand".to_string(),
+            ],
+        );
+
+        assert_eq!(result, 1);
+        assert_eq!(
+            stream_holder.stderr.get_string(),
+            format!(
+                "{{\"error\":{{\"code\":{},\"message\":\"Can't connect to Daemon or Node (ConnectionProblem(\\\"booga\\\")). Probably this means the Daemon isn't running.\"}}}}\n",
+                CONNECTION_PROBLEM_ERROR
+            )
+        );
+    }
 }
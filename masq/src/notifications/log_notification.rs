@@ -0,0 +1,78 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use masq_lib::messages::UiLogBroadcast;
+use std::io::Write;
+
+pub struct LogNotifier {}
+
+impl LogNotifier {
+    // Log lines come in bunches; a prompt after each one would bury them. In --json mode
+    // each one is a flat JSON object, so `masq --json logs --follow` can be piped into a parser.
+    pub fn handle_broadcast(broadcast: UiLogBroadcast, json: bool, stdout: &mut dyn Write) {
+        if json {
+            let line = serde_json::to_string(&broadcast).expect("UiLogBroadcast won't serialize");
+            writeln!(stdout, "{}", line).expect("writeln! failed");
+        } else {
+            writeln!(
+                stdout,
+                "{} {}: {}: {}",
+                broadcast.time,
+                broadcast.level.to_uppercase(),
+                broadcast.component,
+                broadcast.message
+            )
+            .expect("writeln! failed");
+        }
+        stdout.flush().expect("flush failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::test_utils::fake_stream_holder::ByteArrayWriter;
+    use serde_json::{json, Value};
+
+    #[test]
+    pub fn writes_log_line_like_the_log_file() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiLogBroadcast {
+            time: "2021-02-03T04:05:06.789".to_string(),
+            level: "debug".to_string(),
+            component: "Neighborhood".to_string(),
+            message: "Gossip arrived".to_string(),
+        };
+
+        LogNotifier::handle_broadcast(msg, false, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "2021-02-03T04:05:06.789 DEBUG: Neighborhood: Gossip arrived\n".to_string()
+        );
+    }
+
+    #[test]
+    pub fn writes_log_line_as_one_json_object_in_json_mode() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiLogBroadcast {
+            time: "2021-02-03T04:05:06.789".to_string(),
+            level: "debug".to_string(),
+            component: "Neighborhood".to_string(),
+            message: "Gossip \"arrived\"\nlate".to_string(),
+        };
+
+        LogNotifier::handle_broadcast(msg, true, &mut stdout);
+
+        let output = stdout.get_string();
+        assert_eq!(output.lines().count(), 1, "{}", output);
+        assert_eq!(
+            serde_json::from_str::<Value>(&output).unwrap(),
+            json!({
+                "time": "2021-02-03T04:05:06.789",
+                "level": "debug",
+                "component": "Neighborhood",
+                "message": "Gossip \"arrived\"\nlate",
+            })
+        );
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use masq_lib::messages::{RestartOutcome, UiNodeRestartBroadcast};
+use std::io::Write;
+
+pub struct RestartNotifier {}
+
+impl RestartNotifier {
+    pub fn handle_broadcast(response: UiNodeRestartBroadcast, stdout: &mut dyn Write) {
+        let message = match response.outcome {
+            RestartOutcome::Restarted(process_id) => format!(
+                "The Daemon restarted the Node as process {} (attempt {}).",
+                process_id, response.attempt
+            ),
+            RestartOutcome::Failed(msg) => format!(
+                "The Daemon's attempt {} to restart the Node failed: {}\nIt will try again.",
+                response.attempt, msg
+            ),
+            RestartOutcome::Abandoned => {
+                "The Daemon has restarted the Node as many times as it's allowed to \
+                and has given up.\nUse the start command once you've found out what's wrong."
+                    .to_string()
+            }
+        };
+        writeln!(stdout, "\n{}\n", message).expect("writeln! failed");
+        write!(stdout, "masq> ").expect("write! failed");
+        stdout.flush().expect("flush failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::test_utils::fake_stream_holder::ByteArrayWriter;
+
+    #[test]
+    pub fn handles_successful_restart() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiNodeRestartBroadcast {
+            attempt: 2,
+            outcome: RestartOutcome::Restarted(12345),
+        };
+
+        RestartNotifier::handle_broadcast(msg, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "\nThe Daemon restarted the Node as process 12345 (attempt 2).\n\nmasq> ".to_string()
+        );
+    }
+
+    #[test]
+    pub fn handles_failed_restart() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiNodeRestartBroadcast {
+            attempt: 1,
+            outcome: RestartOutcome::Failed("Couldn't find executable".to_string()),
+        };
+
+        RestartNotifier::handle_broadcast(msg, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "\nThe Daemon's attempt 1 to restart the Node failed: Couldn't find executable\nIt will try again.\n\nmasq> ".to_string()
+        );
+    }
+
+    #[test]
+    pub fn handles_abandoned_restart() {
+        let mut stdout = ByteArrayWriter::new();
+        let msg = UiNodeRestartBroadcast {
+            attempt: 6,
+            outcome: RestartOutcome::Abandoned,
+        };
+
+        RestartNotifier::handle_broadcast(msg, &mut stdout);
+
+        assert_eq!(
+            stdout.get_string(),
+            "\nThe Daemon has restarted the Node as many times as it's allowed to and has given up.\nUse the start command once you've found out what's wrong.\n\nmasq> ".to_string()
+        );
+    }
+}
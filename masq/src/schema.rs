@@ -1,11 +1,15 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::commands::bandwidth_command::bandwidth_subcommand;
 use crate::commands::change_password_command::{
     change_password_subcommand, set_password_subcommand,
 };
 use crate::commands::check_password_command::check_password_subcommand;
 use crate::commands::crash_command::crash_subcommand;
+use crate::commands::crash_report_command::crash_report_subcommand;
 use crate::commands::descriptor_command::descriptor_subcommand;
 use crate::commands::generate_wallets_command::generate_wallets_subcommand;
+use crate::commands::help_command::help_subcommand;
+use crate::commands::logs_command::logs_subcommand;
 use crate::commands::setup_command::setup_subcommand;
 use crate::commands::shutdown_command::shutdown_subcommand;
 use crate::commands::start_command::start_subcommand;
@@ -22,6 +26,21 @@ lazy_static! {
     static ref DEFAULT_UI_PORT_STRING: String = format!("{}", DEFAULT_UI_PORT);
 }
 
+const JSON_HELP: &str =
+    "Print each response from the Daemon or Node as a line of JSON on stdout, and each \
+     error a command fails with as a line of JSON with a numeric code on stderr, instead of \
+     human-readable text. A command that doesn't talk to the Daemon or Node, like help, prints \
+     its text as the \"output\" field of a line of JSON on stdout. Messages the Daemon or Node \
+     sends unasked, such as crash notices, arrive on stdout as lines of JSON too, with \"opcode\" \
+     and \"payload\" fields.";
+const TIMEOUT_HELP: &str =
+    "Number of milliseconds to wait for the Daemon or Node to respond to each \
+     request before giving up. If not specified, each command uses its own timeout.";
+const UI_TOKEN_FILE_HELP: &str =
+    "The file holding the token that lets masq use every command when the Daemon was started with --ui-auth. \
+     If not specified, masq looks for the ui-token file the Daemon writes in your MASQ data directory; without a \
+     token, only read-only commands such as descriptor and financials are allowed.";
+
 pub fn app_head() -> App<'static, 'static> {
     App::new("masq")
         .global_settings(if cfg!(test) {
@@ -34,6 +53,7 @@ pub fn app_head() -> App<'static, 'static> {
         .version("1.0.0")
         .author("MASQ")
         .about("masq is a command-line user interface to the MASQ Daemon and the MASQ Node")
+        .setting(AppSettings::DisableHelpSubcommand)
 }
 
 pub fn app() -> App<'static, 'static> {
@@ -47,15 +67,71 @@ pub fn app() -> App<'static, 'static> {
                 .validator(validate_ui_port)
                 .help(UI_PORT_HELP.as_str()),
         )
-        .subcommand(set_password_subcommand())
-        .subcommand(change_password_subcommand())
-        .subcommand(check_password_subcommand())
-        .subcommand(crash_subcommand())
-        .subcommand(descriptor_subcommand())
-        .subcommand(generate_wallets_subcommand())
-        .subcommand(setup_subcommand())
-        .subcommand(start_subcommand())
-        .subcommand(shutdown_subcommand())
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .takes_value(false)
+                .help(JSON_HELP),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("TIMEOUT")
+                .takes_value(true)
+                .validator(validate_timeout)
+                .help(TIMEOUT_HELP),
+        )
+        .arg(
+            Arg::with_name("ui-token-file")
+                .long("ui-token-file")
+                .value_name("FILE")
+                .takes_value(true)
+                .help(UI_TOKEN_FILE_HELP),
+        )
+        .subcommands(subcommands())
+}
+
+pub fn subcommands() -> Vec<App<'static, 'static>> {
+    vec![
+        bandwidth_subcommand(),
+        set_password_subcommand(),
+        change_password_subcommand(),
+        check_password_subcommand(),
+        crash_subcommand(),
+        crash_report_subcommand(),
+        descriptor_subcommand(),
+        generate_wallets_subcommand(),
+        help_subcommand(),
+        logs_subcommand(),
+        setup_subcommand(),
+        start_subcommand(),
+        shutdown_subcommand(),
+    ]
+}
+
+pub fn subcommand_names() -> Vec<String> {
+    let mut names = subcommands()
+        .iter()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect::<Vec<String>>();
+    names.sort();
+    names
+}
+
+// clap 2 has no public way to list an App's arguments, so this reaches into its parser.
+pub fn setup_parameter_names() -> Vec<String> {
+    let setup = setup_subcommand();
+    let mut names = setup
+        .p
+        .opts
+        .iter()
+        .filter_map(|opt| opt.s.long)
+        .chain(setup.p.flags.iter().filter_map(|flag| flag.s.long))
+        .filter(|name| !["help", "version"].contains(name))
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>();
+    names.sort();
+    names
 }
 
 fn validate_ui_port(port: String) -> Result<(), String> {
@@ -65,3 +141,69 @@ fn validate_ui_port(port: String) -> Result<(), String> {
         Err(_) => Err(port),
     }
 }
+
+fn validate_timeout(millis: String) -> Result<(), String> {
+    match str::parse::<u64>(&millis) {
+        Ok(m) if m > 0 => Ok(()),
+        _ => Err(millis),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subcommand_names_are_sorted() {
+        let result = subcommand_names();
+
+        assert_eq!(
+            result,
+            vec![
+                "bandwidth".to_string(),
+                "change-password".to_string(),
+                "check-password".to_string(),
+                "crash".to_string(),
+                "crash-report".to_string(),
+                "descriptor".to_string(),
+                "generate-wallets".to_string(),
+                "help".to_string(),
+                "logs".to_string(),
+                "set-password".to_string(),
+                "setup".to_string(),
+                "shutdown".to_string(),
+                "start".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn setup_parameter_names_come_from_the_shared_schema() {
+        let result = setup_parameter_names();
+
+        assert_eq!(result.contains(&"chain".to_string()), true);
+        assert_eq!(result.contains(&"dns-servers".to_string()), true);
+        assert_eq!(result.contains(&"neighborhood-mode".to_string()), true);
+        assert_eq!(result.contains(&"help".to_string()), false);
+        let mut sorted = result.clone();
+        sorted.sort();
+        assert_eq!(result, sorted);
+    }
+
+    #[test]
+    fn validate_timeout_accepts_positive_integers() {
+        assert_eq!(validate_timeout("1".to_string()), Ok(()));
+        assert_eq!(validate_timeout("30000".to_string()), Ok(()));
+    }
+
+    #[test]
+    fn validate_timeout_rejects_zero_and_non_integers() {
+        assert_eq!(validate_timeout("0".to_string()), Err("0".to_string()));
+        assert_eq!(validate_timeout("-5".to_string()), Err("-5".to_string()));
+        assert_eq!(validate_timeout("1.5".to_string()), Err("1.5".to_string()));
+        assert_eq!(
+            validate_timeout("booga".to_string()),
+            Err("booga".to_string())
+        );
+    }
+}
@@ -7,7 +7,9 @@ use crate::commands::commands_common::CommandError::Transmission;
 use crate::commands::commands_common::{Command, CommandError};
 use crate::communications::broadcast_handler::StreamFactory;
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
-use masq_lib::test_utils::fake_stream_holder::{ByteArrayWriter, ByteArrayWriterInner};
+use masq_lib::test_utils::fake_stream_holder::{
+    ByteArrayReader, ByteArrayWriter, ByteArrayWriterInner,
+};
 use masq_lib::ui_gateway::MessageBody;
 use std::cell::RefCell;
 use std::io::{Read, Write};
@@ -53,6 +55,7 @@ pub struct CommandContextMock {
     send_results: RefCell<Vec<Result<(), ContextError>>>,
     transact_params: Arc<Mutex<Vec<(MessageBody, u64)>>>,
     transact_results: RefCell<Vec<Result<MessageBody, ContextError>>>,
+    stdin: Box<dyn Read>,
     stdout: Box<dyn Write>,
     stdout_arc: Arc<Mutex<ByteArrayWriterInner>>,
     stderr: Box<dyn Write>,
@@ -82,7 +85,7 @@ impl CommandContext for CommandContextMock {
     }
 
     fn stdin(&mut self) -> &mut dyn Read {
-        unimplemented!()
+        &mut self.stdin
     }
 
     fn stdout(&mut self) -> &mut dyn Write {
@@ -110,6 +113,7 @@ impl Default for CommandContextMock {
             send_results: RefCell::new(vec![]),
             transact_params: Arc::new(Mutex::new(vec![])),
             transact_results: RefCell::new(vec![]),
+            stdin: Box::new(ByteArrayReader::new(b"")),
             stdout: Box::new(stdout),
             stdout_arc,
             stderr: Box::new(stderr),
@@ -148,6 +152,11 @@ impl CommandContextMock {
         self
     }
 
+    pub fn stdin_input(mut self, input: &str) -> Self {
+        self.stdin = Box::new(ByteArrayReader::new(input.as_bytes()));
+        self
+    }
+
     pub fn stdout_arc(&self) -> Arc<Mutex<ByteArrayWriterInner>> {
         self.stdout_arc.clone()
     }
@@ -1,3 +1,2 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
-pub mod client_utils;
 pub mod mocks;
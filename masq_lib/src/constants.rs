@@ -9,3 +9,6 @@ pub const LOWEST_USABLE_INSECURE_PORT: u16 = 1025;
 pub const HIGHEST_USABLE_PORT: u16 = 65535;
 pub const DEFAULT_UI_PORT: u16 = 5333;
 pub const CURRENT_LOGFILE_NAME: &str = "MASQNode_rCURRENT.log";
+// About 80 gigabits per second: more than any Node will see, but small enough that converting
+// it to bytes can't overflow.
+pub const MAX_BANDWIDTH_KBPS: u64 = 10_000_000;
@@ -0,0 +1,105 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// How the Node's ProxyClient talks to the --dns-servers when it looks up hosts for exit streams.
+// The encrypted protocols carry the name the servers' certificates must present. This is the one
+// parser for --dns-protocol, shared by the command-line validator and the Node.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DnsProtocol {
+    Udp,
+    Tls(String),
+    Https(String),
+}
+
+impl Default for DnsProtocol {
+    fn default() -> Self {
+        DnsProtocol::Udp
+    }
+}
+
+impl Display for DnsProtocol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DnsProtocol::Udp => write!(f, "udp"),
+            DnsProtocol::Tls(tls_dns_name) => write!(f, "tls:{}", tls_dns_name),
+            DnsProtocol::Https(tls_dns_name) => write!(f, "https:{}", tls_dns_name),
+        }
+    }
+}
+
+impl FromStr for DnsProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (protocol, tls_dns_name) = match s.find(':') {
+            Some(idx) => (s[..idx].to_lowercase(), Some(s[idx + 1..].to_string())),
+            None => (s.to_lowercase(), None),
+        };
+        match (protocol.as_str(), tls_dns_name) {
+            ("udp", None) => Ok(DnsProtocol::Udp),
+            ("tls", Some(name)) if !name.is_empty() => Ok(DnsProtocol::Tls(name)),
+            ("https", Some(name)) if !name.is_empty() => Ok(DnsProtocol::Https(name)),
+            _ => Err(format!(
+                "Unknown DNS protocol '{}': must be udp, tls:<server name>, or https:<server name>",
+                s
+            )),
+        }
+    }
+}
+
+impl DnsProtocol {
+    pub fn default_port(&self) -> u16 {
+        match self {
+            DnsProtocol::Udp => 53,
+            DnsProtocol::Tls(_) => 853,
+            DnsProtocol::Https(_) => 443,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dns_protocol_round_trips_through_strings() {
+        vec![
+            DnsProtocol::Udp,
+            DnsProtocol::Tls("cloudflare-dns.com".to_string()),
+            DnsProtocol::Https("dns.google".to_string()),
+        ]
+        .into_iter()
+        .for_each(|protocol| {
+            assert_eq!(DnsProtocol::from_str(&protocol.to_string()), Ok(protocol))
+        });
+        assert_eq!(
+            DnsProtocol::from_str("TLS:cloudflare-dns.com"),
+            Ok(DnsProtocol::Tls("cloudflare-dns.com".to_string()))
+        );
+    }
+
+    #[test]
+    fn dns_protocol_rejects_unknown_protocols_and_missing_server_names() {
+        vec!["tcp", "tls", "https:", "udp:dns.google", ""]
+            .into_iter()
+            .for_each(|text| {
+                assert_eq!(
+                    DnsProtocol::from_str(text),
+                    Err(format!(
+                        "Unknown DNS protocol '{}': must be udp, tls:<server name>, or https:<server name>",
+                        text
+                    ))
+                )
+            });
+    }
+
+    #[test]
+    fn dns_protocol_default_ports_are_correct() {
+        assert_eq!(DnsProtocol::default().default_port(), 53);
+        assert_eq!(DnsProtocol::Tls("a".to_string()).default_port(), 853);
+        assert_eq!(DnsProtocol::Https("a".to_string()).default_port(), 443);
+    }
+}
@@ -0,0 +1,124 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// The server ports to which a Node will carry opaque streams (port mappings and CONNECT tunnels to
+// anything but 443) as an exit. Written as a comma-separated list of ports and ranges, such as
+// 22,993,8000-8100; this is the one parser for --exit-ports.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExitPorts {
+    ranges: Vec<(u16, u16)>,
+}
+
+impl Display for ExitPorts {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|(low, high)| {
+                if low == high {
+                    low.to_string()
+                } else {
+                    format!("{}-{}", low, high)
+                }
+            })
+            .collect::<Vec<String>>();
+        write!(f, "{}", ranges.join(","))
+    }
+}
+
+impl FromStr for ExitPorts {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_port = |port: &str| match port.parse::<u16>() {
+            Ok(port) if port > 0 => Ok(port),
+            _ => Err(format!("Bad exit port '{}' in '{}'", port, s)),
+        };
+        let ranges = s
+            .split(',')
+            .map(|range| match range.find('-') {
+                Some(idx) => {
+                    let (low, high) = (parse_port(&range[..idx])?, parse_port(&range[idx + 1..])?);
+                    if low > high {
+                        Err(format!("Backward exit port range '{}' in '{}'", range, s))
+                    } else {
+                        Ok((low, high))
+                    }
+                }
+                None => parse_port(range).map(|port| (port, port)),
+            })
+            .collect::<Result<Vec<(u16, u16)>, String>>()?;
+        Ok(ExitPorts { ranges })
+    }
+}
+
+// The mail ports: SMTP, SMTPS and submission. An exit with no --exit-ports carries no opaque
+// streams to them, so that it can't be made into a spam relay without its owner's say-so.
+pub const DEFAULT_REFUSED_EXIT_PORTS: &[u16] = &[25, 465, 587];
+
+impl ExitPorts {
+    pub fn allows(&self, port: u16) -> bool {
+        self.ranges
+            .iter()
+            .any(|(low, high)| (*low..=*high).contains(&port))
+    }
+
+    pub fn allowed_by_default(port: u16) -> bool {
+        !DEFAULT_REFUSED_EXIT_PORTS.contains(&port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_ports_round_trip_through_strings_and_allow_what_they_list() {
+        let subject = ExitPorts::from_str("22,993,8000-8100").unwrap();
+
+        assert_eq!(subject.to_string(), "22,993,8000-8100".to_string());
+        vec![22, 993, 8000, 8050, 8100]
+            .into_iter()
+            .for_each(|port| assert_eq!(subject.allows(port), true, "{}", port));
+        vec![1, 21, 23, 443, 7999, 8101, 65535]
+            .into_iter()
+            .for_each(|port| assert_eq!(subject.allows(port), false, "{}", port));
+    }
+
+    #[test]
+    fn without_exit_ports_every_port_but_the_mail_ports_is_allowed() {
+        vec![25, 465, 587]
+            .into_iter()
+            .for_each(|port| assert_eq!(ExitPorts::allowed_by_default(port), false, "{}", port));
+        vec![1, 22, 24, 26, 443, 993, 8080, 65535]
+            .into_iter()
+            .for_each(|port| assert_eq!(ExitPorts::allowed_by_default(port), true, "{}", port));
+    }
+
+    #[test]
+    fn exit_ports_reject_bad_ports_and_backward_ranges() {
+        vec![
+            ("", "Bad exit port '' in ''"),
+            ("22,booga", "Bad exit port 'booga' in '22,booga'"),
+            ("0", "Bad exit port '0' in '0'"),
+            ("65536", "Bad exit port '65536' in '65536'"),
+            ("22-", "Bad exit port '' in '22-'"),
+            (
+                "8100-8000",
+                "Backward exit port range '8100-8000' in '8100-8000'",
+            ),
+        ]
+        .into_iter()
+        .for_each(|(text, message)| {
+            assert_eq!(
+                ExitPorts::from_str(text),
+                Err(message.to_string()),
+                "{}",
+                text
+            )
+        });
+    }
+}
@@ -9,8 +9,13 @@ pub mod messages;
 pub mod command;
 pub mod constants;
 pub mod crash_point;
+pub mod dns_protocol;
+pub mod exit_ports;
+pub mod port_mapping;
 pub mod shared_schema;
 pub mod test_utils;
+pub mod ui_auth;
+pub mod ui_client;
 pub mod ui_gateway;
 pub mod ui_traffic_converter;
 pub mod utils;
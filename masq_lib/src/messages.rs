@@ -1,5 +1,6 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
+use crate::constants::MAX_BANDWIDTH_KBPS;
 use crate::messages::UiMessageError::{DeserializationError, PayloadError, UnexpectedMessage};
 use crate::shared_schema::ConfiguratorError;
 use crate::ui_gateway::MessagePath::{Conversation, FireAndForget};
@@ -12,6 +13,7 @@ use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Debug;
+use std::str::FromStr;
 
 pub const NODE_UI_PROTOCOL: &str = "MASQNode-UIv2";
 
@@ -21,6 +23,8 @@ pub const NODE_ALREADY_RUNNING_ERROR: u64 = 0x8000_0000_0000_0003;
 pub const UNMARSHAL_ERROR: u64 = 0x8000_0000_0000_0004;
 pub const SETUP_ERROR: u64 = 0x8000_0000_0000_0005;
 pub const TIMEOUT_ERROR: u64 = 0x8000_0000_0000_0006;
+pub const CRASH_REPORT_ERROR: u64 = 0x8000_0000_0000_0007;
+pub const UNAUTHORIZED_ERROR: u64 = 0x8000_0000_0000_0008;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum UiMessageError {
@@ -374,7 +378,7 @@ pub struct UiStartResponse {
 }
 conversation_message!(UiStartResponse, "start");
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum CrashReason {
     ChildWaitFailure(String),
     NoInformation,
@@ -391,6 +395,93 @@ pub struct UiNodeCrashedBroadcast {
 }
 fire_and_forget_message!(UiNodeCrashedBroadcast, "crashed");
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum RestartOutcome {
+    Restarted(u32),
+    Failed(String),
+    Abandoned,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiNodeRestartBroadcast {
+    pub attempt: u32,
+    pub outcome: RestartOutcome,
+}
+fire_and_forget_message!(UiNodeRestartBroadcast, "restart");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiCrashReport {
+    pub id: String,
+    pub time: String,
+    #[serde(rename = "processId")]
+    pub process_id: u32,
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<i32>,
+    #[serde(rename = "crashReason")]
+    pub crash_reason: CrashReason,
+    #[serde(rename = "panicMessage")]
+    pub panic_message: Option<String>,
+    #[serde(rename = "panicLocation")]
+    pub panic_location: Option<String>,
+    pub backtrace: Option<String>,
+    pub setup: Vec<UiSetupResponseValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiCrashReportSummary {
+    pub id: String,
+    pub time: String,
+    #[serde(rename = "processId")]
+    pub process_id: u32,
+    #[serde(rename = "panicMessage")]
+    pub panic_message: Option<String>,
+}
+
+impl From<&UiCrashReport> for UiCrashReportSummary {
+    fn from(report: &UiCrashReport) -> Self {
+        UiCrashReportSummary {
+            id: report.id.clone(),
+            time: report.time.clone(),
+            process_id: report.process_id,
+            panic_message: report.panic_message.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiCrashReportsRequest {}
+conversation_message!(UiCrashReportsRequest, "crashReports");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiCrashReportsResponse {
+    pub reports: Vec<UiCrashReportSummary>,
+}
+conversation_message!(UiCrashReportsResponse, "crashReports");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiCrashReportRequest {
+    pub id: String,
+}
+conversation_message!(UiCrashReportRequest, "crashReport");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiCrashReportResponse {
+    pub report: UiCrashReport,
+}
+conversation_message!(UiCrashReportResponse, "crashReport");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiDeleteCrashReportsRequest {
+    pub ids: Vec<String>,
+}
+conversation_message!(UiDeleteCrashReportsRequest, "deleteCrashReports");
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct UiDeleteCrashReportsResponse {
+    pub deleted: Vec<String>,
+}
+conversation_message!(UiDeleteCrashReportsResponse, "deleteCrashReports");
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct UiRedirect {
     pub port: u16,
@@ -413,10 +504,95 @@ pub struct UiUnmarshalError {
 }
 fire_and_forget_message!(UiUnmarshalError, "unmarshalError");
 
+// Never sent by a UI: the WebSocketSupervisor delivers this to the actors on behalf of a UI that
+// has disconnected, so that they can forget about it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiClientDisconnectedNotice {}
+fire_and_forget_message!(UiClientDisconnectedNotice, "clientDisconnected");
+
 ///////////////////////////////////////////////////////////////////
 // These messages are sent to or by the Node only
 ///////////////////////////////////////////////////////////////////
 
+// Kilobytes per second; zero means unlimited.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct UiServiceBandwidthLimits {
+    pub download: u64,
+    pub upload: u64,
+    #[serde(rename = "neighborDownload")]
+    pub neighbor_download: u64,
+    #[serde(rename = "neighborUpload")]
+    pub neighbor_upload: u64,
+}
+
+// The one parser for the download:upload:neighbor-download:neighbor-upload form the command line
+// and masq take.
+impl FromStr for UiServiceBandwidthLimits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers = s
+            .split(':')
+            .map(|piece| piece.parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>();
+        let limits = match numbers {
+            Ok(numbers) if numbers.len() == 4 => UiServiceBandwidthLimits {
+                download: numbers[0],
+                upload: numbers[1],
+                neighbor_download: numbers[2],
+                neighbor_upload: numbers[3],
+            },
+            _ => return Err(format!(
+                "Bandwidth limits must be download:upload:neighbor-download:neighbor-upload in kilobytes per second, not '{}'",
+                s
+            )),
+        };
+        limits.check()?;
+        Ok(limits)
+    }
+}
+
+impl UiServiceBandwidthLimits {
+    pub fn check(&self) -> Result<(), String> {
+        let limits = [
+            self.download,
+            self.upload,
+            self.neighbor_download,
+            self.neighbor_upload,
+        ];
+        if limits.iter().all(|kbps| *kbps <= MAX_BANDWIDTH_KBPS) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Bandwidth limits can be no more than {} kilobytes per second, not {}:{}:{}:{}",
+                MAX_BANDWIDTH_KBPS,
+                self.download,
+                self.upload,
+                self.neighbor_download,
+                self.neighbor_upload
+            ))
+        }
+    }
+}
+
+// Leave both limits out to ask for the current ones without changing anything.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiBandwidthLimitsRequest {
+    #[serde(rename = "routingOpt")]
+    pub routing_opt: Option<UiServiceBandwidthLimits>,
+    #[serde(rename = "exitOpt")]
+    pub exit_opt: Option<UiServiceBandwidthLimits>,
+}
+conversation_message!(UiBandwidthLimitsRequest, "bandwidthLimits");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiBandwidthLimitsResponse {
+    pub routing: UiServiceBandwidthLimits,
+    pub exit: UiServiceBandwidthLimits,
+    pub throttled: bool,
+}
+conversation_message!(UiBandwidthLimitsResponse, "bandwidthLimits");
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UiChangePasswordRequest {
     #[serde(rename = "oldPasswordOpt")]
@@ -500,6 +676,21 @@ pub struct UiDescriptorResponse {
 }
 conversation_message!(UiDescriptorResponse, "descriptor");
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiExitStreamStatsRequest {}
+conversation_message!(UiExitStreamStatsRequest, "exitStreamStats");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiExitStreamStatsResponse {
+    #[serde(rename = "activeStreams")]
+    pub active_streams: u64,
+    #[serde(rename = "reapedIdle")]
+    pub reaped_idle: u64,
+    #[serde(rename = "reapedExpired")]
+    pub reaped_expired: u64,
+}
+conversation_message!(UiExitStreamStatsResponse, "exitStreamStats");
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct UiPayableAccount {
     pub wallet: String,
@@ -568,6 +759,80 @@ pub struct UiGenerateWalletsResponse {
 }
 conversation_message!(UiGenerateWalletsResponse, "generateWallets");
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiGossipStatsRequest {}
+conversation_message!(UiGossipStatsRequest, "gossipStats");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiGossipStatsResponse {
+    pub accepted: u64,
+    #[serde(rename = "droppedBanned")]
+    pub dropped_banned: u64,
+    #[serde(rename = "droppedTooManyRecords")]
+    pub dropped_too_many_records: u64,
+    #[serde(rename = "droppedRecordTooLarge")]
+    pub dropped_record_too_large: u64,
+    #[serde(rename = "droppedIpRateExceeded")]
+    pub dropped_ip_rate_exceeded: u64,
+    #[serde(rename = "droppedKeyRateExceeded")]
+    pub dropped_key_rate_exceeded: u64,
+    #[serde(rename = "currentlyBanned")]
+    pub currently_banned: u64,
+}
+conversation_message!(UiGossipStatsResponse, "gossipStats");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogBroadcast {
+    pub time: String,
+    pub level: String,
+    pub component: String,
+    pub message: String,
+}
+fire_and_forget_message!(UiLogBroadcast, "log");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiComponentLogLevel {
+    pub component: String,
+    pub level: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogLevelRequest {
+    #[serde(rename = "componentOpt")]
+    pub component_opt: Option<String>,
+    #[serde(rename = "levelOpt")]
+    pub level_opt: Option<String>,
+}
+conversation_message!(UiLogLevelRequest, "logLevel");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogLevelResponse {
+    #[serde(rename = "defaultLevel")]
+    pub default_level: String,
+    #[serde(rename = "componentLevels")]
+    pub component_levels: Vec<UiComponentLogLevel>,
+}
+conversation_message!(UiLogLevelResponse, "logLevel");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogSubscribeRequest {
+    pub components: Vec<String>,
+    pub level: String,
+}
+conversation_message!(UiLogSubscribeRequest, "logSubscribe");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogSubscribeResponse {}
+conversation_message!(UiLogSubscribeResponse, "logSubscribe");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogUnsubscribeRequest {}
+conversation_message!(UiLogUnsubscribeRequest, "logUnsubscribe");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UiLogUnsubscribeResponse {}
+conversation_message!(UiLogUnsubscribeResponse, "logUnsubscribe");
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct UiNewPasswordBroadcast {}
 fire_and_forget_message!(UiNewPasswordBroadcast, "newPassword");
@@ -933,4 +1198,38 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn ui_service_bandwidth_limits_can_be_parsed() {
+        let result = UiServiceBandwidthLimits::from_str("2048:512:0:128");
+
+        assert_eq!(
+            result,
+            Ok(UiServiceBandwidthLimits {
+                download: 2048,
+                upload: 512,
+                neighbor_download: 0,
+                neighbor_upload: 128,
+            })
+        );
+    }
+
+    #[test]
+    fn ui_service_bandwidth_limits_refuses_limits_that_are_too_big() {
+        let too_big = format!("0:{}:0:0", MAX_BANDWIDTH_KBPS + 1);
+
+        let at_max = UiServiceBandwidthLimits::from_str(&format!("0:{}:0:0", MAX_BANDWIDTH_KBPS));
+        let over_max = UiServiceBandwidthLimits::from_str(&too_big);
+        let overflowing = UiServiceBandwidthLimits::from_str("0:0:0:18446744073709551615");
+
+        assert_eq!(at_max.is_ok(), true);
+        assert_eq!(
+            over_max,
+            Err(format!(
+                "Bandwidth limits can be no more than 10000000 kilobytes per second, not {}",
+                too_big
+            ))
+        );
+        assert_eq!(overflowing.is_err(), true);
+    }
 }
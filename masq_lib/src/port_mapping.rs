@@ -0,0 +1,114 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+// A local port whose connections are carried, unexamined, to a fixed host and port at the exit.
+// Written as <local port>:<target host>:<target port>; an IPv6 target host goes in brackets. This is
+// the one parser for --port-mappings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortMapping {
+    pub local_port: u16,
+    pub target_hostname: String,
+    pub target_port: u16,
+}
+
+impl FromStr for PortMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let complain = || {
+            format!(
+                "Port mapping '{}' should look like <local port>:<target host>:<target port>",
+                s
+            )
+        };
+        let first_colon = s.find(':').ok_or_else(complain)?;
+        let last_colon = s.rfind(':').ok_or_else(complain)?;
+        if last_colon <= first_colon + 1 {
+            return Err(complain());
+        }
+        let local_port = match s[..first_colon].parse::<u16>() {
+            Ok(0) | Err(_) => return Err(complain()),
+            Ok(port) => port,
+        };
+        let target_port = match s[last_colon + 1..].parse::<u16>() {
+            Ok(0) | Err(_) => return Err(complain()),
+            Ok(port) => port,
+        };
+        let target_hostname = s[first_colon + 1..last_colon]
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        Ok(PortMapping {
+            local_port,
+            target_hostname,
+            target_port,
+        })
+    }
+}
+
+impl Display for PortMapping {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.target_hostname.contains(':') {
+            write!(
+                f,
+                "{}:[{}]:{}",
+                self.local_port, self.target_hostname, self.target_port
+            )
+        } else {
+            write!(
+                f,
+                "{}:{}:{}",
+                self.local_port, self.target_hostname, self.target_port
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn port_mappings_round_trip_through_strings() {
+        vec![
+            ("2222:ssh.example.com:22", "ssh.example.com"),
+            ("9993:[2001:db8::1]:993", "2001:db8::1"),
+        ]
+        .into_iter()
+        .for_each(|(string, target_hostname)| {
+            let port_mapping = PortMapping::from_str(string).unwrap();
+
+            assert_eq!(port_mapping.target_hostname, target_hostname);
+            assert_eq!(port_mapping.to_string(), string);
+        });
+    }
+
+    #[test]
+    fn malformed_port_mappings_are_rejected() {
+        vec![
+            "2222",
+            "2222:22",
+            "0:host:22",
+            "2222:host:0",
+            "booga:host:22",
+            "2222::22",
+        ]
+        .into_iter()
+        .for_each(|string| {
+            let result = PortMapping::from_str(string);
+
+            assert_eq!(
+                result,
+                Err(format!(
+                    "Port mapping '{}' should look like <local port>:<target host>:<target port>",
+                    string
+                )),
+                "{}",
+                string
+            );
+        });
+    }
+}
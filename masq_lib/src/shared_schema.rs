@@ -34,6 +34,17 @@ pub const DB_PASSWORD_HELP: &str =
     "A password or phrase to decrypt the encrypted material in the database, to include your \
      mnemonic seed (if applicable) and your list of previous neighbors. If you don't provide this \
      password, none of the encrypted data in your database will be used.";
+pub const DNS_BYPASS_HELP: &str =
+    "Comma-separated list of domains that should not be routed over the MASQ Network, such as names on your \
+     LAN (e.g. lan,home.arpa,printer.example.com). While DNS is subverted, lookups for these domains and their \
+     subdomains go to the DNS servers your computer was using before subversion, as do lookups for records \
+     other than IP addresses (MX, TXT, SRV, and so on). By default, every domain goes over the MASQ Network.";
+pub const DNS_PROTOCOL_HELP: &str =
+    "How your Node should talk to the --dns-servers while providing exit services for other MASQ Nodes. \
+     'udp' is ordinary, unencrypted DNS, which lets anyone between your Node and the DNS servers see every host \
+     your Node looks up. 'tls:<server name>' (DNS over TLS, port 853) and 'https:<server name>' (DNS over HTTPS, \
+     port 443) encrypt the lookups; the server name is the name on the DNS servers' certificates, such as \
+     cloudflare-dns.com for 1.1.1.1 or dns.google for 8.8.8.8. Default is udp.";
 pub const DNS_SERVERS_HELP: &str =
     "IP addresses of DNS Servers for host name look-up while providing exit \
      services for other MASQ Nodes (e.g. 1.0.0.1,1.1.1.1,8.8.8.8,9.9.9.9, etc.)";
@@ -42,17 +53,56 @@ pub const EARNING_WALLET_HELP: &str =
      (case-insensitive). If you already have a derivation-path earning wallet, don't supply this. \
      If you have supplied an earning wallet address before, either don't supply it again or be \
      careful to supply exactly the same one you supplied before.";
+pub const EXIT_ABSOLUTE_TIMEOUT_HELP: &str =
+    "The number of seconds your Node will keep a stream open to a server on behalf of another Node's client, \
+     no matter how busy the stream is. When a stream grows older than this, your Node closes it and tells the \
+     originating Node that it's finished. Default is 86400 (one day).";
+pub const EXIT_BANDWIDTH_HELP: &str =
+    "Caps the bandwidth your Node will give to exit services: that is, to the traffic between other Nodes' \
+     clients and the servers your Node talks to on their behalf. The value is four numbers of kilobytes per \
+     second separated by colons: download:upload:neighbor-download:neighbor-upload. The first two limit all \
+     exit traffic together; the last two limit the traffic to and from each neighboring Node separately. \
+     Zero means unlimited (e.g. 2048:512:0:128). Default is unlimited in every direction.";
+pub const EXIT_IDLE_TIMEOUT_HELP: &str =
+    "The number of seconds your Node will keep a stream open to a server on behalf of another Node's client \
+     when no data is passing through it in either direction. This keeps your Node from holding sockets and \
+     memory for clients that have gone away without saying goodbye. Default is 300 (five minutes).";
+pub const EXIT_PORTS_HELP: &str =
+    "The server ports to which your Node will carry opaque streams for other Nodes' clients: that is, \
+     port-mapped connections and CONNECT tunnels to anything but port 443, whose contents your Node can't \
+     see. Give ports and ranges of ports separated by commas (but no spaces), such as 22,993,8000-8100. \
+     Ordinary HTTP and TLS traffic is not affected. By default, opaque streams may go to any port but the \
+     mail ports 25, 465 and 587; list them here if you want your Node to carry mail. Whatever this says, \
+     your Node never carries streams of any kind to loopback, private, link-local or unspecified addresses, \
+     so nobody can use it to reach the services on your machine or your LAN.";
 pub const IP_ADDRESS_HELP: &str = "The public IP address of your MASQ Node: that is, the IPv4 \
-     address at which other Nodes can contact yours. If you're running your Node behind \
+     or IPv6 address at which other Nodes can contact yours. If you're running your Node behind \
      a router, this will be the IP address of the router. If this IP address starts with 192.168 or 10.0, \
-     it's a local address rather than a public address, and other Nodes won't be able to see yours. \
-     --ip is meaningless except in --neighborhood-mode standard.";
+     or with fe80 or fd for IPv6, it's a local address rather than a public address, and other Nodes \
+     won't be able to see yours. IPv6 addresses are written without brackets, such as 2001:db8::5. \
+     If your Node can be reached over both IPv4 and IPv6, give one address of each, separated by a comma, \
+     such as 1.2.3.4,2001:db8::5: the first is your Node's main address, and Nodes that can use \
+     both will race connections to the two. --ip is meaningless except in --neighborhood-mode standard.";
+pub const LOG_FORMAT_HELP: &str =
+    "The shape of the lines in the Node's logfile. 'text' is meant for people to read. 'json' writes each \
+     log record as a JSON object on its own line, carrying the timestamp (RFC 3339, with the local UTC \
+     offset), thread, level, component, and message, plus structured fields such as the stream key, peer \
+     address, or public key when the Node knows them. Use 'json' if you feed your logs to an aggregator. \
+     Default is 'text'.";
 pub const LOG_LEVEL_HELP: &str =
     "The minimum severity of the logs that should appear in the Node's logfile. You should probably not specify \
      a level lower than the default unless you're doing testing or forensics: a Node at the 'trace' log level \
      generates a lot of log traffic. This will both consume your disk space and degrade your Node's performance. \
      You should probably not specify a level higher than the default unless you have security concerns about \
      persistent logs being kept on your computer: if your Node crashes, it's good to know why.";
+pub const MASQUERADE_HELP: &str =
+    "The disguise your Node's traffic wears on the connections it opens to other Nodes: 'json' makes it look \
+     like JSON documents, 'http' like HTTP/1.1 POST requests and responses, and 'tls' like TLS application \
+     data. Your Node understands all three no matter which one you choose, and it answers each neighbor in \
+     the disguise that neighbor used, so Nodes with different choices can still talk to each other. Pick \
+     whichever one best blends in with the other traffic on your network. Nodes from releases before \
+     'http' and 'tls' existed understand only 'json'; their Gossip doesn't say they accept the others, \
+     so your Node uses 'json' on the connections it opens to them, whatever you choose. Default is json.";
 pub const NEIGHBORS_HELP: &str = "One or more Node descriptors for running Nodes in the MASQ \
      Network to which you'd like your Node to connect on startup. A Node descriptor looks like \
      this:\n\ngBviQbjOS3e5ReFQCvIhUM3i02d1zPleo1iXg/EN6zQ:86.75.30.9:5542 (initial ':' for testnet) and\n\
@@ -61,6 +111,11 @@ pub const NEIGHBORS_HELP: &str = "One or more Node descriptors for running Nodes
      if you don't specify a neighbor, your Node will start without being connected to any MASQ \
      Network, although other Nodes will be able to connect to yours if they know your Node's descriptor. \
      --neighbors is meaningless in --neighborhood-mode zero-hop.";
+pub const METRICS_PORT_HELP: &str =
+    "A local port on which your Node will serve counters about its traffic, streams, routes, Gossip, \
+     DNS queries, and balances at /metrics, in the text format Prometheus scrapes. The port is bound \
+     to localhost only, so nothing outside your computer can read it. There is no default value; if \
+     you don't specify one, your Node won't serve metrics.";
 pub const NEIGHBORHOOD_MODE_HELP: &str = "This configures the way the Node relates to other Nodes.\n\n\
      zero-hop means that your Node will operate as its own MASQ Network and will not communicate with any \
      other Nodes. --ip, --neighbors, and --clandestine-port are incompatible with --neighborhood_mode \
@@ -80,6 +135,24 @@ pub const NEIGHBORHOOD_MODE_HELP: &str = "This configures the way the Node relat
      standard means that your Node will operate fully unconstrained, both originating and accepting \
      connections, both consuming and providing services, and when you operate behind a router, it \
      requires that you forward your clandestine port through that router to your Node's machine.";
+pub const PORT_MAPPINGS_HELP: &str =
+    "One or more local ports whose connections your Node will carry through the MASQ Network, without looking \
+     inside them, to a fixed host and port at the exit Node: for example, 2222:ssh.example.com:22 lets you \
+     point an SSH client at port 2222 on your own machine to reach ssh.example.com. Each mapping looks like \
+     <local port>:<target host>:<target port>; if the target host is an IPv6 address, put it in square brackets. \
+     If you have more than one, separate them with commas (but no spaces). No two mappings may share a local \
+     port, and none may use port 80, port 443, or your --proxy-port. There is no default value.";
+pub const PROXY_PORT_HELP: &str =
+    "A local port on which your Node will act as an explicit HTTP proxy, for browsers and other programs \
+     that can be pointed at one. It understands plain HTTP requests with absolute URIs and CONNECT requests \
+     for any destination port, and since your programs find it through their proxy settings rather than \
+     through DNS, you don't need to subvert your system's DNS to use it. There is no default value; if you \
+     don't specify one, your Node won't listen for explicit-proxy connections.";
+pub const ROUTING_BANDWIDTH_HELP: &str =
+    "Caps the bandwidth your Node will give to routing services: that is, to the CORES packages it relays \
+     from one Node to another without being their exit. The value is four numbers of kilobytes per second \
+     separated by colons, in the same form as --exit-bandwidth: download:upload:neighbor-download:neighbor-upload. \
+     Zero means unlimited. Default is unlimited in every direction.";
 pub const REAL_USER_HELP: &str =
     "The user whose identity Node will assume when dropping privileges after bootstrapping. Since Node refuses to \
      run with root privilege after bootstrapping, you might want to use this if you start the Node as root, or if \
@@ -202,6 +275,15 @@ pub fn ui_port_arg(help: &str) -> Arg {
 
 pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
     head.arg(
+        Arg::with_name("allow-local-destinations")
+            .long("allow-local-destinations")
+            .value_name("ALLOW-LOCAL-DESTINATIONS")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["false", "true"])
+            .hidden(true),
+    )
+    .arg(
         Arg::with_name("blockchain-service-url")
             .long("blockchain-service-url")
             .empty_values(false)
@@ -241,6 +323,24 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
     )
     .arg(data_directory_arg())
     .arg(db_password_arg(DB_PASSWORD_HELP))
+    .arg(
+        Arg::with_name("dns-bypass")
+            .long("dns-bypass")
+            .value_name("DNS-BYPASS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_dns_bypass)
+            .help(DNS_BYPASS_HELP),
+    )
+    .arg(
+        Arg::with_name("dns-protocol")
+            .long("dns-protocol")
+            .value_name("DNS-PROTOCOL")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_dns_protocol)
+            .help(DNS_PROTOCOL_HELP),
+    )
     .arg(
         Arg::with_name("dns-servers")
             .long("dns-servers")
@@ -255,6 +355,42 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
         common_validators::validate_ethereum_address,
     ))
     .arg(chain_arg())
+    .arg(
+        Arg::with_name("exit-absolute-timeout")
+            .long("exit-absolute-timeout")
+            .value_name("SECONDS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_timeout_seconds)
+            .help(EXIT_ABSOLUTE_TIMEOUT_HELP),
+    )
+    .arg(
+        Arg::with_name("exit-bandwidth")
+            .long("exit-bandwidth")
+            .value_name("KBPS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_bandwidth_limits)
+            .help(EXIT_BANDWIDTH_HELP),
+    )
+    .arg(
+        Arg::with_name("exit-idle-timeout")
+            .long("exit-idle-timeout")
+            .value_name("SECONDS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_timeout_seconds)
+            .help(EXIT_IDLE_TIMEOUT_HELP),
+    )
+    .arg(
+        Arg::with_name("exit-ports")
+            .long("exit-ports")
+            .value_name("PORTS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_exit_ports)
+            .help(EXIT_PORTS_HELP),
+    )
     .arg(
         Arg::with_name("fake-public-key")
             .long("fake-public-key")
@@ -278,9 +414,19 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .value_name("IP")
             .min_values(0)
             .max_values(1)
-            .validator(common_validators::validate_ip_address)
+            .validator(common_validators::validate_node_ip_addresses)
             .help(IP_ADDRESS_HELP),
     )
+    .arg(
+        Arg::with_name("log-format")
+            .long("log-format")
+            .value_name("LOG-FORMAT")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["text", "json"])
+            .case_insensitive(true)
+            .help(LOG_FORMAT_HELP),
+    )
     .arg(
         Arg::with_name("log-level")
             .long("log-level")
@@ -291,6 +437,25 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .case_insensitive(true)
             .help(LOG_LEVEL_HELP),
     )
+    .arg(
+        Arg::with_name("masquerade")
+            .long("masquerade")
+            .value_name("MASQUERADE")
+            .min_values(0)
+            .max_values(1)
+            .possible_values(&["json", "http", "tls"])
+            .case_insensitive(true)
+            .help(MASQUERADE_HELP),
+    )
+    .arg(
+        Arg::with_name("metrics-port")
+            .long("metrics-port")
+            .value_name("METRICS-PORT")
+            .empty_values(false)
+            .min_values(0)
+            .validator(common_validators::validate_ui_port)
+            .help(METRICS_PORT_HELP),
+    )
     .arg(
         Arg::with_name("neighborhood-mode")
             .long("neighborhood-mode")
@@ -308,11 +473,42 @@ pub fn shared_app(head: App<'static, 'static>) -> App<'static, 'static> {
             .min_values(0)
             .help(NEIGHBORS_HELP),
     )
+    .arg(
+        Arg::with_name("port-mappings")
+            .long("port-mappings")
+            .value_name("PORT-MAPPINGS")
+            .empty_values(false)
+            .min_values(0)
+            .validator(common_validators::validate_port_mappings)
+            .help(PORT_MAPPINGS_HELP),
+    )
+    .arg(
+        Arg::with_name("proxy-port")
+            .long("proxy-port")
+            .value_name("PROXY-PORT")
+            .empty_values(false)
+            .min_values(0)
+            .validator(common_validators::validate_proxy_port)
+            .help(PROXY_PORT_HELP),
+    )
     .arg(real_user_arg())
+    .arg(
+        Arg::with_name("routing-bandwidth")
+            .long("routing-bandwidth")
+            .value_name("KBPS")
+            .min_values(0)
+            .max_values(1)
+            .validator(common_validators::validate_bandwidth_limits)
+            .help(ROUTING_BANDWIDTH_HELP),
+    )
 }
 
 pub mod common_validators {
     use crate::constants::LOWEST_USABLE_INSECURE_PORT;
+    use crate::dns_protocol::DnsProtocol;
+    use crate::exit_ports::ExitPorts;
+    use crate::messages::UiServiceBandwidthLimits;
+    use crate::port_mapping::PortMapping;
     use regex::Regex;
     use std::net::IpAddr;
     use std::str::FromStr;
@@ -342,6 +538,44 @@ pub mod common_validators {
         }
     }
 
+    pub fn validate_node_ip_addresses(addresses: String) -> Result<(), String> {
+        let ip_addrs = match addresses
+            .split(',')
+            .map(IpAddr::from_str)
+            .collect::<Result<Vec<IpAddr>, _>>()
+        {
+            Ok(ip_addrs) => ip_addrs,
+            Err(_) => return Err(addresses),
+        };
+        match ip_addrs.as_slice() {
+            [_] => Ok(()),
+            [first, second] if first.is_ipv4() != second.is_ipv4() => Ok(()),
+            _ => Err(addresses),
+        }
+    }
+
+    pub fn validate_dns_bypass(domains: String) -> Result<(), String> {
+        let bad_domains = domains
+            .split(',')
+            .filter(|domain| {
+                domain.trim_end_matches('.').is_empty()
+                    || domain.contains(|c: char| c.is_whitespace() || c == ':' || c == '/')
+            })
+            .collect::<Vec<&str>>();
+        if bad_domains.is_empty() {
+            Ok(())
+        } else {
+            Err(domains)
+        }
+    }
+
+    pub fn validate_dns_protocol(protocol: String) -> Result<(), String> {
+        match DnsProtocol::from_str(&protocol) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(protocol),
+        }
+    }
+
     pub fn validate_clandestine_port(clandestine_port: String) -> Result<(), String> {
         match clandestine_port.parse::<u16>() {
             Ok(clandestine_port) if clandestine_port >= LOWEST_USABLE_INSECURE_PORT => Ok(()),
@@ -420,6 +654,46 @@ pub mod common_validators {
         }
     }
 
+    pub fn validate_exit_ports(exit_ports: String) -> Result<(), String> {
+        match ExitPorts::from_str(&exit_ports) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(exit_ports),
+        }
+    }
+
+    pub fn validate_port_mappings(port_mappings: String) -> Result<(), String> {
+        if port_mappings
+            .split(',')
+            .all(|mapping| PortMapping::from_str(mapping).is_ok())
+        {
+            Ok(())
+        } else {
+            Err(port_mappings)
+        }
+    }
+
+    pub fn validate_proxy_port(port: String) -> Result<(), String> {
+        match str::parse::<u16>(&port) {
+            Ok(0) => Err(port),
+            Ok(_) => Ok(()),
+            Err(_) => Err(port),
+        }
+    }
+
+    pub fn validate_timeout_seconds(seconds: String) -> Result<(), String> {
+        match seconds.parse::<u64>() {
+            Ok(secs) if secs > 0 => Ok(()),
+            _ => Err(seconds),
+        }
+    }
+
+    pub fn validate_bandwidth_limits(limits: String) -> Result<(), String> {
+        match UiServiceBandwidthLimits::from_str(&limits) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(limits),
+        }
+    }
+
     pub fn validate_ui_port(port: String) -> Result<(), String> {
         match str::parse::<u16>(&port) {
             Ok(port_number) if port_number < LOWEST_USABLE_INSECURE_PORT => Err(port),
@@ -518,6 +792,249 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validate_ip_address_given_valid_ipv6_input() {
+        assert_eq!(
+            Ok(()),
+            common_validators::validate_ip_address(String::from("2001:db8::5"))
+        );
+    }
+
+    #[test]
+    fn validate_ip_address_given_bracketed_ipv6_input() {
+        assert_eq!(
+            Err(String::from("[2001:db8::5]")),
+            common_validators::validate_ip_address(String::from("[2001:db8::5]")),
+        );
+    }
+
+    #[test]
+    fn validate_node_ip_addresses_accepts_one_address_or_one_of_each_family() {
+        assert_eq!(
+            common_validators::validate_node_ip_addresses(String::from("1.2.3.4")),
+            Ok(())
+        );
+        assert_eq!(
+            common_validators::validate_node_ip_addresses(String::from("1.2.3.4,2001:db8::5")),
+            Ok(())
+        );
+        assert_eq!(
+            common_validators::validate_node_ip_addresses(String::from("2001:db8::5,1.2.3.4")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn validate_node_ip_addresses_complains_about_bad_or_redundant_addresses() {
+        vec![
+            "booga",
+            "1.2.3.4,booga",
+            "1.2.3.4,5.6.7.8",
+            "::1,::2",
+            "1.2.3.4,::1,::2",
+        ]
+        .into_iter()
+        .for_each(|addresses| {
+            assert_eq!(
+                common_validators::validate_node_ip_addresses(addresses.to_string()),
+                Err(addresses.to_string()),
+                "{}",
+                addresses
+            )
+        });
+    }
+
+    #[test]
+    fn validate_exit_ports_works() {
+        let result = common_validators::validate_exit_ports(String::from("22,993,8000-8100"));
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_exit_ports_rejects_bad_ports_and_backward_ranges() {
+        vec!["", "booga", "0", "65536", "22,", "22-", "-22", "8100-8000"]
+            .into_iter()
+            .for_each(|exit_ports| {
+                let result = common_validators::validate_exit_ports(exit_ports.to_string());
+
+                assert_eq!(result, Err(exit_ports.to_string()), "{}", exit_ports);
+            });
+    }
+
+    #[test]
+    fn validate_port_mappings_works() {
+        let result = common_validators::validate_port_mappings(String::from(
+            "2222:ssh.example.com:22,9993:[2001:db8::1]:993",
+        ));
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_port_mappings_rejects_malformed_mappings() {
+        vec![
+            "2222",
+            "2222:22",
+            "2222::22",
+            "0:ssh.example.com:22",
+            "2222:ssh.example.com:65536",
+            "2222:ssh.example.com:22,booga",
+        ]
+        .into_iter()
+        .for_each(|port_mappings| {
+            let result = common_validators::validate_port_mappings(port_mappings.to_string());
+
+            assert_eq!(result, Err(port_mappings.to_string()), "{}", port_mappings);
+        });
+    }
+
+    #[test]
+    fn validate_proxy_port_rejects_zero_and_non_numeric_ports() {
+        assert_eq!(
+            common_validators::validate_proxy_port(String::from("booga")),
+            Err(String::from("booga"))
+        );
+        assert_eq!(
+            common_validators::validate_proxy_port(String::from("0")),
+            Err(String::from("0"))
+        );
+    }
+
+    #[test]
+    fn validate_proxy_port_works() {
+        let result = common_validators::validate_proxy_port(String::from("8080"));
+
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn validate_timeout_seconds_rejects_zero_and_non_numeric_values() {
+        assert_eq!(
+            common_validators::validate_timeout_seconds(String::from("booga")),
+            Err(String::from("booga"))
+        );
+        assert_eq!(
+            common_validators::validate_timeout_seconds(String::from("0")),
+            Err(String::from("0"))
+        );
+        assert_eq!(
+            common_validators::validate_timeout_seconds(String::from("-5")),
+            Err(String::from("-5"))
+        );
+    }
+
+    #[test]
+    fn validate_dns_bypass_accepts_lists_of_domains() {
+        vec![
+            "lan",
+            "lan,home.arpa",
+            "Printer.Example.COM.",
+            "xn--bcher-kva.example",
+        ]
+        .into_iter()
+        .for_each(|domains| {
+            assert_eq!(
+                common_validators::validate_dns_bypass(domains.to_string()),
+                Ok(()),
+                "{}",
+                domains
+            )
+        });
+    }
+
+    #[test]
+    fn validate_dns_bypass_rejects_empty_entries_and_things_that_are_not_domains() {
+        vec![
+            "",
+            "lan,,home.arpa",
+            ".",
+            "home arpa",
+            "lan:53",
+            "http://lan",
+        ]
+        .into_iter()
+        .for_each(|domains| {
+            assert_eq!(
+                common_validators::validate_dns_bypass(domains.to_string()),
+                Err(domains.to_string())
+            )
+        });
+    }
+
+    #[test]
+    fn validate_dns_protocol_accepts_udp_and_named_encrypted_protocols() {
+        vec!["udp", "UDP", "tls:cloudflare-dns.com", "https:dns.google"]
+            .into_iter()
+            .for_each(|protocol| {
+                assert_eq!(
+                    common_validators::validate_dns_protocol(protocol.to_string()),
+                    Ok(()),
+                    "{}",
+                    protocol
+                )
+            });
+    }
+
+    #[test]
+    fn validate_dns_protocol_rejects_unknown_protocols_and_missing_server_names() {
+        vec!["tcp", "tls", "https:", "udp:dns.google", ""]
+            .into_iter()
+            .for_each(|protocol| {
+                assert_eq!(
+                    common_validators::validate_dns_protocol(protocol.to_string()),
+                    Err(protocol.to_string())
+                )
+            });
+    }
+
+    #[test]
+    fn validate_bandwidth_limits_accepts_four_numbers_of_kilobytes_per_second() {
+        vec![
+            "0:0:0:0",
+            "2048:512:0:128",
+            "1:2:3:4",
+            "10000000:0:0:10000000",
+        ]
+        .into_iter()
+        .for_each(|limits| {
+            assert_eq!(
+                common_validators::validate_bandwidth_limits(limits.to_string()),
+                Ok(()),
+                "{}",
+                limits
+            )
+        });
+    }
+
+    #[test]
+    fn validate_bandwidth_limits_rejects_the_wrong_number_of_pieces_and_non_numbers() {
+        vec![
+            "",
+            "100",
+            "1:2:3",
+            "1:2:3:4:5",
+            "1:2:three:4",
+            "1:-2:3:4",
+            "1::3:4",
+            "1:2:3:10000001",
+        ]
+        .into_iter()
+        .for_each(|limits| {
+            assert_eq!(
+                common_validators::validate_bandwidth_limits(limits.to_string()),
+                Err(limits.to_string())
+            )
+        });
+    }
+
+    #[test]
+    fn validate_timeout_seconds_works() {
+        let result = common_validators::validate_timeout_seconds(String::from("300"));
+
+        assert_eq!(result, Ok(()));
+    }
+
     #[test]
     fn validate_ui_port_complains_about_non_numeric_ui_port() {
         let result = common_validators::validate_ui_port(String::from("booga"));
@@ -1,7 +1,7 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
-use masq_lib::messages::NODE_UI_PROTOCOL;
-use masq_lib::utils::localhost;
+use crate::messages::NODE_UI_PROTOCOL;
+use crate::utils::localhost;
 use std::net::TcpStream;
 use websocket::sync::Client;
 use websocket::ClientBuilder;
@@ -1,5 +1,6 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
+pub mod client_utils;
 pub mod environment_guard;
 pub mod fake_stream_holder;
 pub mod mock_websockets_server;
@@ -1,6 +1,7 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
 use crate::messages::{FromMessageBody, ToMessageBody, UiMessageError};
+use crate::ui_auth::ui_token_headers;
 use crate::ui_gateway::MessageTarget::ClientId;
 use crate::ui_traffic_converter::UiTrafficConverter;
 use crate::utils::localhost;
@@ -16,12 +17,28 @@ pub struct UiConnection {
 
 impl UiConnection {
     pub fn make(port: u16, protocol: &str) -> Result<UiConnection, String> {
+        Self::make_with_token_opt(port, protocol, None)
+    }
+
+    pub fn make_with_token(port: u16, protocol: &str, token: &str) -> Result<UiConnection, String> {
+        Self::make_with_token_opt(port, protocol, Some(token))
+    }
+
+    fn make_with_token_opt(
+        port: u16,
+        protocol: &str,
+        token_opt: Option<&str>,
+    ) -> Result<UiConnection, String> {
         let client_builder =
             match ClientBuilder::new(format!("ws://{}:{}", localhost(), port).as_str()) {
-                Ok(cb) => cb,
+                Ok(cb) => cb.add_protocol(protocol),
                 Err(e) => return Err(format!("{:?}", e)),
             };
-        let client = match client_builder.add_protocol(protocol).connect_insecure() {
+        let mut client_builder = match token_opt {
+            Some(token) => client_builder.custom_headers(&ui_token_headers(token)),
+            None => client_builder,
+        };
+        let client = match client_builder.connect_insecure() {
             Ok(c) => c,
             Err(e) => return Err(format!("{:?}", e)),
         };
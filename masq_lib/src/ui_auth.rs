@@ -0,0 +1,130 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use websocket::header::Headers;
+
+pub const UI_TOKEN_HEADER: &str = "X-MASQ-UI-Token";
+pub const UI_TOKEN_FILE_NAME: &str = "ui-token";
+// The Daemon tells the Node it launches where the token is by setting this in its environment.
+pub const UI_TOKEN_FILE_ENV_VAR: &str = "MASQ_UI_TOKEN_FILE";
+
+// Clients that connect without presenting the token may still use these; everything else
+// requires an authenticated connection. Nothing here may reveal more than a summary: the log tap
+// ignores the log level, and full crash reports carry backtraces and setup data, so both need the
+// token.
+pub const READ_ONLY_OPCODES: &[&str] = &[
+    "crashReports",
+    "descriptor",
+    "exitStreamStats",
+    "financials",
+    "gossipStats",
+];
+
+pub fn is_read_only_opcode(opcode: &str) -> bool {
+    READ_ONLY_OPCODES.contains(&opcode)
+}
+
+// The MASQ directory is the one directly under the local data directory, where the Daemon logs.
+pub fn ui_token_path(masq_directory: &Path) -> PathBuf {
+    masq_directory.join(UI_TOKEN_FILE_NAME)
+}
+
+pub fn read_ui_token(path: &Path) -> io::Result<String> {
+    let token = fs::read_to_string(path)?.trim().to_string();
+    if token.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is empty", path.display()),
+        ))
+    } else {
+        Ok(token)
+    }
+}
+
+pub fn ui_token_headers(token: &str) -> Headers {
+    let mut headers = Headers::new();
+    headers.set_raw(UI_TOKEN_HEADER, vec![token.as_bytes().to_vec()]);
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::utils::ensure_node_home_directory_exists;
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(UI_TOKEN_HEADER, "X-MASQ-UI-Token");
+        assert_eq!(UI_TOKEN_FILE_NAME, "ui-token");
+        assert_eq!(UI_TOKEN_FILE_ENV_VAR, "MASQ_UI_TOKEN_FILE");
+    }
+
+    #[test]
+    fn read_only_opcodes_are_recognized() {
+        assert_eq!(is_read_only_opcode("financials"), true);
+        assert_eq!(is_read_only_opcode("crashReports"), true);
+        assert_eq!(is_read_only_opcode("shutdown"), false);
+        assert_eq!(is_read_only_opcode("generateWallets"), false);
+        assert_eq!(is_read_only_opcode("setup"), false);
+        assert_eq!(is_read_only_opcode("crashReport"), false);
+        assert_eq!(is_read_only_opcode("logSubscribe"), false);
+        assert_eq!(is_read_only_opcode("logUnsubscribe"), false);
+    }
+
+    #[test]
+    fn ui_token_path_is_in_the_masq_directory() {
+        let result = ui_token_path(Path::new("/home/booga/.local/share/MASQ"));
+
+        assert_eq!(
+            result,
+            PathBuf::from("/home/booga/.local/share/MASQ/ui-token")
+        );
+    }
+
+    #[test]
+    fn read_ui_token_trims_whitespace() {
+        let home_dir =
+            ensure_node_home_directory_exists("ui_auth", "read_ui_token_trims_whitespace");
+        let path = home_dir.join(UI_TOKEN_FILE_NAME);
+        fs::write(&path, "  0123456789abcdef\n").unwrap();
+
+        let result = read_ui_token(&path);
+
+        assert_eq!(result.unwrap(), "0123456789abcdef".to_string());
+    }
+
+    #[test]
+    fn read_ui_token_complains_about_empty_or_missing_files() {
+        let home_dir = ensure_node_home_directory_exists(
+            "ui_auth",
+            "read_ui_token_complains_about_empty_or_missing_files",
+        );
+        let empty_path = home_dir.join("empty");
+        fs::write(&empty_path, "\n").unwrap();
+        let missing_path = home_dir.join("missing");
+
+        let empty_result = read_ui_token(&empty_path);
+        let missing_result = read_ui_token(&missing_path);
+
+        assert_eq!(
+            empty_result.err().unwrap().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            missing_result.err().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn ui_token_headers_carry_the_token() {
+        let result = ui_token_headers("booga");
+
+        assert_eq!(
+            result.get_raw(UI_TOKEN_HEADER),
+            Some(&[b"booga".to_vec()][..])
+        );
+    }
+}
@@ -1,8 +1,8 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
+use crate::ui_gateway::MessageBody;
+use crate::ui_traffic_converter::UiTrafficConverter;
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use masq_lib::ui_gateway::MessageBody;
-use masq_lib::ui_traffic_converter::UiTrafficConverter;
 use std::net::TcpStream;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -137,12 +137,12 @@ impl ClientListenerThread {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::messages::ToMessageBody;
+    use crate::messages::{UiShutdownRequest, UiShutdownResponse};
     use crate::test_utils::client_utils::make_client;
+    use crate::test_utils::mock_websockets_server::MockWebSocketsServer;
+    use crate::utils::find_free_port;
     use crossbeam_channel::unbounded;
-    use masq_lib::messages::ToMessageBody;
-    use masq_lib::messages::{UiShutdownRequest, UiShutdownResponse};
-    use masq_lib::test_utils::mock_websockets_server::MockWebSocketsServer;
-    use masq_lib::utils::find_free_port;
     use std::time::Duration;
     use websocket::ws::sender::Sender;
 
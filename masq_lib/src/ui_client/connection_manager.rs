@@ -1,17 +1,16 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
-use crate::communications::broadcast_handler::{
-    BroadcastHandle, BroadcastHandler, StreamFactory, StreamFactoryReal,
-};
-use crate::communications::client_listener_thread::{ClientListener, ClientListenerError};
-use crate::communications::node_conversation::{NodeConversation, NodeConversationTermination};
-use crossbeam_channel::{unbounded, RecvTimeoutError};
+use crate::messages::{CrashReason, FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast};
+use crate::messages::{UiRedirect, NODE_UI_PROTOCOL};
+use crate::ui_auth::ui_token_headers;
+use crate::ui_client::client_listener_thread::{ClientListener, ClientListenerError};
+use crate::ui_client::node_conversation::{NodeConversation, NodeConversationTermination};
+use crate::ui_client::BroadcastHandle;
+use crate::ui_gateway::{MessageBody, MessagePath};
+use crate::ui_traffic_converter::UiTrafficConverter;
+use crate::utils::localhost;
+use crossbeam_channel::{select, unbounded, RecvTimeoutError};
 use crossbeam_channel::{Receiver, RecvError, Sender};
-use masq_lib::messages::{CrashReason, FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast};
-use masq_lib::messages::{UiRedirect, NODE_UI_PROTOCOL};
-use masq_lib::ui_gateway::{MessageBody, MessagePath};
-use masq_lib::ui_traffic_converter::UiTrafficConverter;
-use masq_lib::utils::localhost;
 use std::collections::{HashMap, HashSet};
 use std::net::TcpStream;
 use std::thread;
@@ -84,18 +83,18 @@ impl ConnectionManager {
     pub fn connect(
         &mut self,
         port: u16,
+        ui_token_opt: Option<String>,
         broadcast_handle: Box<dyn BroadcastHandle>,
         timeout_millis: u64,
     ) -> Result<(), ClientListenerError> {
         let (demand_tx, demand_rx) = unbounded();
         let (listener_to_manager_tx, listener_to_manager_rx) = unbounded();
-        let talker_half = make_client_listener(port, listener_to_manager_tx, timeout_millis)?;
+        let talker_half =
+            make_client_listener(port, &ui_token_opt, listener_to_manager_tx, timeout_millis)?;
         let (conversation_return_tx, conversation_return_rx) = unbounded();
         let (redirect_order_tx, redirect_order_rx) = unbounded();
         let (redirect_response_tx, redirect_response_rx) = unbounded();
         let (active_port_response_tx, active_port_response_rx) = unbounded();
-        let redirect_broadcast_handler =
-            RedirectBroadcastHandler::new(broadcast_handle, redirect_order_tx);
         self.demand_tx = demand_tx;
         self.conversation_return_rx = conversation_return_rx;
         self.redirect_response_rx = redirect_response_rx;
@@ -104,6 +103,7 @@ impl ConnectionManager {
             active_port: Some(port),
             daemon_port: port,
             node_port: None,
+            ui_token_opt,
             conversations: HashMap::new(),
             conversations_waiting: HashSet::new(),
             next_context_id: 1,
@@ -113,7 +113,10 @@ impl ConnectionManager {
             conversations_to_manager_rx: unbounded().1,
             listener_to_manager_rx,
             talker_half,
-            broadcast_handle: redirect_broadcast_handler.start(Box::new(StreamFactoryReal::new())),
+            broadcast_handle: Box::new(BroadcastHandleRedirect::new(
+                broadcast_handle,
+                redirect_order_tx,
+            )),
             redirect_order_rx,
             redirect_response_tx,
             active_port_response_tx,
@@ -152,13 +155,19 @@ impl ConnectionManager {
     }
 }
 
+// The Daemon and the Node share a UI token, so the same one is presented across redirects.
 fn make_client_listener(
     port: u16,
+    ui_token_opt: &Option<String>,
     listener_to_manager_tx: Sender<Result<MessageBody, ClientListenerError>>,
     timeout_millis: u64,
 ) -> Result<Writer<TcpStream>, ClientListenerError> {
     let url = format!("ws://{}:{}", localhost(), port);
     let builder = ClientBuilder::new(url.as_str()).expect("Bad URL");
+    let builder = match ui_token_opt {
+        Some(ui_token) => builder.custom_headers(&ui_token_headers(ui_token)),
+        None => builder,
+    };
     let result = builder.add_protocol(NODE_UI_PROTOCOL);
     let result = match connect_insecure_timeout(result, timeout_millis) {
         Err(RecvTimeoutError::Disconnected) => return Err(ClientListenerError::Closed),
@@ -191,6 +200,7 @@ struct CmsInner {
     active_port: Option<u16>,
     daemon_port: u16,
     node_port: Option<u16>,
+    ui_token_opt: Option<String>,
     conversations: HashMap<u64, Sender<Result<MessageBody, NodeConversationTermination>>>,
     conversations_waiting: HashSet<u64>,
     next_context_id: u64,
@@ -377,6 +387,7 @@ impl ConnectionManagerThread {
         let (listener_to_manager_tx, listener_to_manager_rx) = unbounded();
         let talker_half = match make_client_listener(
             redirect_order.port,
+            &inner.ui_token_opt,
             listener_to_manager_tx,
             redirect_order.timeout_millis,
         ) {
@@ -448,6 +459,7 @@ impl ConnectionManagerThread {
         inner.listener_to_manager_rx = listener_to_manager_rx;
         match make_client_listener(
             inner.active_port.expect("Active port disappeared!"),
+            &inner.ui_token_opt,
             listener_to_manager_tx,
             FALLBACK_TIMEOUT_MILLIS,
         ) {
@@ -511,21 +523,7 @@ impl BroadcastHandle for BroadcastHandleRedirect {
     }
 }
 
-struct RedirectBroadcastHandler {
-    next_handle: Box<dyn BroadcastHandle>,
-    redirect_order_tx: Sender<RedirectOrder>,
-}
-
-impl BroadcastHandler for RedirectBroadcastHandler {
-    fn start(self, _stream_factory: Box<dyn StreamFactory>) -> Box<dyn BroadcastHandle> {
-        Box::new(BroadcastHandleRedirect {
-            next_handle: self.next_handle,
-            redirect_order_tx: self.redirect_order_tx,
-        })
-    }
-}
-
-impl RedirectBroadcastHandler {
+impl BroadcastHandleRedirect {
     pub fn new(
         next_handle: Box<dyn BroadcastHandle>,
         redirect_order_tx: Sender<RedirectOrder>,
@@ -540,22 +538,21 @@ impl RedirectBroadcastHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::communications::broadcast_handler::{BroadcastHandler, StreamFactoryReal};
-    use crate::communications::node_conversation::ClientError;
-    use crate::test_utils::client_utils::make_client;
-    use crossbeam_channel::TryRecvError;
-    use masq_lib::messages::{CrashReason, FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast};
-    use masq_lib::messages::{
+    use crate::messages::{CrashReason, FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast};
+    use crate::messages::{
         UiFinancialsRequest, UiFinancialsResponse, UiRedirect, UiSetupBroadcast, UiSetupRequest,
         UiSetupResponse, UiShutdownRequest, UiShutdownResponse, UiStartOrder, UiStartResponse,
         UiUnmarshalError,
     };
-    use masq_lib::test_utils::mock_websockets_server::{
+    use crate::test_utils::client_utils::make_client;
+    use crate::test_utils::mock_websockets_server::{
         MockWebSocketsServer, MockWebSocketsServerStopHandle,
     };
     #[cfg(target_os = "windows")]
-    use masq_lib::test_utils::utils::is_running_under_github_actions;
-    use masq_lib::utils::{find_free_port, running_test};
+    use crate::test_utils::utils::is_running_under_github_actions;
+    use crate::ui_client::node_conversation::ClientError;
+    use crate::utils::{find_free_port, running_test};
+    use crossbeam_channel::TryRecvError;
     use std::hash::Hash;
     use std::sync::{Arc, Mutex};
     use std::thread;
@@ -592,7 +589,7 @@ mod tests {
         thread::sleep(Duration::from_millis(500)); // let the server get started
         let mut subject = ConnectionManager::new();
         subject
-            .connect(port, Box::new(BroadcastHandleMock::new()), 1000)
+            .connect(port, None, Box::new(BroadcastHandleMock::new()), 1000)
             .unwrap();
         (subject, stop_handle)
     }
@@ -1119,9 +1116,10 @@ mod tests {
         let mut inner = make_inner();
         inner.conversations.insert(4, conversation_tx);
         inner.conversations_waiting.insert(4);
-        inner.broadcast_handle =
-            RedirectBroadcastHandler::new(Box::new(broadcast_handler), unbounded().0)
-                .start(Box::new(StreamFactoryReal::new()));
+        inner.broadcast_handle = Box::new(BroadcastHandleRedirect::new(
+            Box::new(broadcast_handler),
+            unbounded().0,
+        ));
 
         let inner = ConnectionManagerThread::handle_incoming_message_body(
             inner,
@@ -1174,7 +1172,7 @@ mod tests {
         let broadcast_handler = BroadcastHandleMock::new().send_params(&send_params_arc);
         let mut subject = ConnectionManager::new();
         subject
-            .connect(daemon_port, Box::new(broadcast_handler), 1000)
+            .connect(daemon_port, None, Box::new(broadcast_handler), 1000)
             .unwrap();
         let conversation = subject.start_conversation();
 
@@ -1434,7 +1432,7 @@ mod tests {
         let mut subject = ConnectionManager::new();
         thread::sleep(Duration::from_millis(500)); // let the server get started
         subject
-            .connect(port, Box::new(BroadcastHandleMock::new()), 1000)
+            .connect(port, None, Box::new(BroadcastHandleMock::new()), 1000)
             .unwrap();
         let conversation1 = subject.start_conversation();
         let conversation2 = subject.start_conversation();
@@ -1460,6 +1458,7 @@ mod tests {
             active_port: Some(0),
             daemon_port: 0,
             node_port: None,
+            ui_token_opt: None,
             conversations: HashMap::new(),
             conversations_waiting: HashSet::new(),
             next_context_id: 0,
@@ -0,0 +1,509 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+mod client_listener_thread;
+pub mod connection_manager;
+pub mod node_conversation;
+mod transaction_worker;
+
+pub use crate::ui_client::client_listener_thread::ClientListenerError;
+
+use crate::messages::{FromMessageBody, ToMessageBody, UiMessageError};
+use crate::ui_client::connection_manager::{ConnectionManager, REDIRECT_TIMEOUT_MILLIS};
+use crate::ui_client::node_conversation::ClientError;
+use crate::ui_client::transaction_worker::TransactionWorker;
+use crate::ui_gateway::MessageBody;
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use futures::Future;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+// How many broadcasts a subscription holds before it starts dropping the oldest of them
+pub const BROADCAST_SUBSCRIPTION_CAPACITY: usize = 1000;
+
+pub trait BroadcastHandle: Send {
+    fn send(&self, message_body: MessageBody);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiClientError {
+    Connection(ClientError),
+    Payload(u64, String),
+    UnexpectedResponse(UiMessageError),
+}
+
+impl From<ClientError> for UiClientError {
+    fn from(client_error: ClientError) -> Self {
+        UiClientError::Connection(client_error)
+    }
+}
+
+// A UI protocol client for the Daemon and the Node. It connects to the Daemon, follows the
+// Daemon's redirects to the Node whenever the Node is the one that can answer, and falls back to
+// the Daemon if the Node goes away.
+pub struct UiClient {
+    connection: ConnectionManager,
+    broadcast_routes: Arc<Mutex<Vec<BroadcastRoute>>>,
+    transaction_worker: TransactionWorker,
+}
+
+impl Debug for UiClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "UiClient")
+    }
+}
+
+impl UiClient {
+    // Broadcasts that no subscription claims go to unclaimed_broadcast_handle_opt, if supplied.
+    pub fn connect(
+        daemon_ui_port: u16,
+        ui_token_opt: Option<String>,
+        unclaimed_broadcast_handle_opt: Option<Box<dyn BroadcastHandle>>,
+    ) -> Result<Self, ClientListenerError> {
+        let broadcast_routes = Arc::new(Mutex::new(vec![]));
+        let broadcast_router = BroadcastRouter {
+            routes: broadcast_routes.clone(),
+            unclaimed_handle_opt: unclaimed_broadcast_handle_opt,
+        };
+        let mut connection = ConnectionManager::new();
+        connection.connect(
+            daemon_ui_port,
+            ui_token_opt,
+            Box::new(broadcast_router),
+            REDIRECT_TIMEOUT_MILLIS,
+        )?;
+        Ok(Self {
+            connection,
+            broadcast_routes,
+            transaction_worker: TransactionWorker::start(),
+        })
+    }
+
+    pub fn active_port(&self) -> Option<u16> {
+        self.connection.active_ui_port()
+    }
+
+    pub fn transact<Q, R>(&self, request: Q, timeout_millis: u64) -> Result<R, UiClientError>
+    where
+        Q: ToMessageBody,
+        R: FromMessageBody,
+    {
+        let response = self.transact_raw(request.tmb(0), timeout_millis)?;
+        decode_response(response)
+    }
+
+    // Every UiClient has one worker thread that waits on all of its asynchronous transactions,
+    // however many are in flight; calling this doesn't start a thread of its own.
+    pub fn transact_async<Q, R>(
+        &self,
+        request: Q,
+        timeout_millis: u64,
+    ) -> Box<dyn Future<Item = R, Error = UiClientError> + Send>
+    where
+        Q: ToMessageBody,
+        R: FromMessageBody + Send + 'static,
+    {
+        let result_rx = self.transaction_worker.transact(
+            self.connection.start_conversation(),
+            request.tmb(0),
+            timeout_millis,
+        );
+        Box::new(
+            result_rx
+                .map_err(|_| UiClientError::Connection(ClientError::ConnectionDropped))
+                .and_then(|result| result.map_err(UiClientError::from))
+                .and_then(decode_response),
+        )
+    }
+
+    pub fn send<M: ToMessageBody>(&self, message: M) -> Result<(), UiClientError> {
+        Ok(self.send_raw(message.tmb(0))?)
+    }
+
+    pub fn transact_raw(
+        &self,
+        request: MessageBody,
+        timeout_millis: u64,
+    ) -> Result<MessageBody, ClientError> {
+        self.connection
+            .start_conversation()
+            .transact(request, timeout_millis)
+    }
+
+    pub fn send_raw(&self, message: MessageBody) -> Result<(), ClientError> {
+        self.connection.start_conversation().send(message)
+    }
+
+    // A subscription holds up to BROADCAST_SUBSCRIPTION_CAPACITY broadcasts that haven't been
+    // received yet; when another arrives, the oldest one is dropped to make room for it, so a
+    // subscriber that falls behind loses history instead of making masq's memory grow forever.
+    pub fn subscribe<T>(&self) -> BroadcastSubscription<T>
+    where
+        T: FromMessageBody + Send + 'static,
+    {
+        let (route, subscription) = broadcast_channel::<T>(BROADCAST_SUBSCRIPTION_CAPACITY);
+        self.broadcast_routes
+            .lock()
+            .expect("Broadcast routes are poisoned")
+            .push(route);
+        subscription
+    }
+
+    pub fn close(&self) {
+        self.connection.close();
+    }
+}
+
+pub struct BroadcastSubscription<T> {
+    broadcast_rx: Arc<Receiver<T>>,
+}
+
+impl<T> BroadcastSubscription<T> {
+    pub fn recv_timeout(&self, timeout_millis: u64) -> Result<T, UiClientError> {
+        match self
+            .broadcast_rx
+            .recv_timeout(Duration::from_millis(timeout_millis))
+        {
+            Ok(broadcast) => Ok(broadcast),
+            Err(RecvTimeoutError::Timeout) => Err(ClientError::Timeout(timeout_millis).into()),
+            Err(RecvTimeoutError::Disconnected) => Err(ClientError::ConnectionDropped.into()),
+        }
+    }
+
+    pub fn try_recv(&self) -> Option<T> {
+        self.broadcast_rx.try_recv().ok()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum RouteOutcome {
+    Claimed,
+    Ignored,
+    Abandoned,
+}
+
+type BroadcastRoute = Box<dyn Fn(&MessageBody) -> RouteOutcome + Send>;
+
+fn broadcast_channel<T>(capacity: usize) -> (BroadcastRoute, BroadcastSubscription<T>)
+where
+    T: FromMessageBody + Send + 'static,
+{
+    let (broadcast_tx, broadcast_rx) = bounded(capacity);
+    let broadcast_rx = Arc::new(broadcast_rx);
+    let route = make_broadcast_route(broadcast_tx, Arc::downgrade(&broadcast_rx));
+    (route, BroadcastSubscription { broadcast_rx })
+}
+
+// The route only borrows the subscription's receiver, to drop the oldest broadcast when the
+// channel is full; once the subscription is dropped, the receiver is gone and so is the route.
+fn make_broadcast_route<T>(
+    broadcast_tx: Sender<T>,
+    broadcast_rx: Weak<Receiver<T>>,
+) -> BroadcastRoute
+where
+    T: FromMessageBody + Send + 'static,
+{
+    Box::new(move |message_body| match T::fmb(message_body.clone()) {
+        Ok((mut broadcast, _)) => loop {
+            match broadcast_tx.try_send(broadcast) {
+                Ok(_) => return RouteOutcome::Claimed,
+                Err(TrySendError::Full(returned)) => match broadcast_rx.upgrade() {
+                    Some(broadcast_rx) => {
+                        let _ = broadcast_rx.try_recv();
+                        broadcast = returned;
+                    }
+                    None => return RouteOutcome::Abandoned,
+                },
+                Err(TrySendError::Disconnected(_)) => return RouteOutcome::Abandoned,
+            }
+        },
+        Err(_) => RouteOutcome::Ignored,
+    })
+}
+
+fn decode_response<R: FromMessageBody>(response: MessageBody) -> Result<R, UiClientError> {
+    match R::fmb(response) {
+        Ok((payload, _)) => Ok(payload),
+        Err(UiMessageError::PayloadError(code, message)) => {
+            Err(UiClientError::Payload(code, message))
+        }
+        Err(e) => Err(UiClientError::UnexpectedResponse(e)),
+    }
+}
+
+// Every subscription whose type a broadcast decodes into gets a copy of it; subscriptions whose
+// receivers have been dropped are forgotten the first time they would have received something.
+struct BroadcastRouter {
+    routes: Arc<Mutex<Vec<BroadcastRoute>>>,
+    unclaimed_handle_opt: Option<Box<dyn BroadcastHandle>>,
+}
+
+impl BroadcastHandle for BroadcastRouter {
+    fn send(&self, message_body: MessageBody) {
+        let mut claimed = false;
+        self.routes
+            .lock()
+            .expect("Broadcast routes are poisoned")
+            .retain(|route| match route(&message_body) {
+                RouteOutcome::Claimed => {
+                    claimed = true;
+                    true
+                }
+                RouteOutcome::Ignored => true,
+                RouteOutcome::Abandoned => false,
+            });
+        if let (false, Some(unclaimed_handle)) = (claimed, &self.unclaimed_handle_opt) {
+            unclaimed_handle.send(message_body);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{
+        UiFinancialsRequest, UiFinancialsResponse, UiNewPasswordBroadcast, UiRedirect,
+        UiSetupBroadcast, UiShutdownRequest, UiShutdownResponse, NODE_NOT_RUNNING_ERROR,
+    };
+    use crate::test_utils::mock_websockets_server::MockWebSocketsServer;
+    use crate::ui_gateway::MessagePath::Conversation;
+    use crate::utils::find_free_port;
+
+    struct BroadcastHandleMock {
+        send_params: Arc<Mutex<Vec<MessageBody>>>,
+    }
+
+    impl BroadcastHandle for BroadcastHandleMock {
+        fn send(&self, message_body: MessageBody) {
+            self.send_params.lock().unwrap().push(message_body);
+        }
+    }
+
+    impl BroadcastHandleMock {
+        fn new() -> Self {
+            Self {
+                send_params: Arc::new(Mutex::new(vec![])),
+            }
+        }
+
+        fn send_params(mut self, params: &Arc<Mutex<Vec<MessageBody>>>) -> Self {
+            self.send_params = params.clone();
+            self
+        }
+    }
+
+    #[test]
+    fn transact_decodes_typed_responses() {
+        let port = find_free_port();
+        let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
+        let stop_handle = server.start();
+        let subject = UiClient::connect(port, None, None).unwrap();
+
+        let result: Result<UiShutdownResponse, UiClientError> =
+            subject.transact(UiShutdownRequest {}, 1000);
+
+        assert_eq!(result, Ok(UiShutdownResponse {}));
+        let requests = stop_handle.stop();
+        assert_eq!(requests, vec![Ok(UiShutdownRequest {}.tmb(1))]);
+    }
+
+    #[test]
+    fn transact_reports_error_payloads() {
+        let port = find_free_port();
+        let server = MockWebSocketsServer::new(port).queue_response(MessageBody {
+            opcode: "shutdown".to_string(),
+            path: Conversation(1),
+            payload: Err((NODE_NOT_RUNNING_ERROR, "Node is not running".to_string())),
+        });
+        let stop_handle = server.start();
+        let subject = UiClient::connect(port, None, None).unwrap();
+
+        let result: Result<UiShutdownResponse, UiClientError> =
+            subject.transact(UiShutdownRequest {}, 1000);
+
+        assert_eq!(
+            result,
+            Err(UiClientError::Payload(
+                NODE_NOT_RUNNING_ERROR,
+                "Node is not running".to_string()
+            ))
+        );
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn transact_complains_about_responses_of_the_wrong_type() {
+        let port = find_free_port();
+        let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
+        let stop_handle = server.start();
+        let subject = UiClient::connect(port, None, None).unwrap();
+
+        let result: Result<UiFinancialsResponse, UiClientError> =
+            subject.transact(UiShutdownRequest {}, 1000);
+
+        assert_eq!(
+            result,
+            Err(UiClientError::UnexpectedResponse(
+                UiMessageError::UnexpectedMessage("shutdown".to_string(), Conversation(1))
+            ))
+        );
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn transact_async_resolves_to_the_typed_response() {
+        let port = find_free_port();
+        let server = MockWebSocketsServer::new(port).queue_response(UiShutdownResponse {}.tmb(1));
+        let stop_handle = server.start();
+        let subject = UiClient::connect(port, None, None).unwrap();
+
+        let future = subject.transact_async::<_, UiShutdownResponse>(UiShutdownRequest {}, 1000);
+
+        assert_eq!(future.wait(), Ok(UiShutdownResponse {}));
+        stop_handle.stop();
+    }
+
+    #[test]
+    fn transact_follows_redirects_from_the_daemon_to_the_node() {
+        let node_port = find_free_port();
+        let node_server = MockWebSocketsServer::new(node_port).queue_response(
+            UiFinancialsResponse {
+                payables: vec![],
+                total_payable: 21,
+                receivables: vec![],
+                total_receivable: 32,
+            }
+            .tmb(1),
+        );
+        let node_stop_handle = node_server.start();
+        let daemon_port = find_free_port();
+        let daemon_server = MockWebSocketsServer::new(daemon_port).queue_response(
+            UiRedirect {
+                port: node_port,
+                opcode: "financials".to_string(),
+                context_id: Some(1),
+                payload: r#"{"payableMinimumAmount":12,"payableMaximumAge":23,"receivableMinimumAmount":34,"receivableMaximumAge":45}"#.to_string(),
+            }
+            .tmb(0),
+        );
+        let daemon_stop_handle = daemon_server.start();
+        let request = UiFinancialsRequest {
+            payable_minimum_amount: 12,
+            payable_maximum_age: 23,
+            receivable_minimum_amount: 34,
+            receivable_maximum_age: 45,
+        };
+        let subject = UiClient::connect(daemon_port, None, None).unwrap();
+
+        let result: Result<UiFinancialsResponse, UiClientError> =
+            subject.transact(request.clone(), 1000);
+
+        assert_eq!(
+            result,
+            Ok(UiFinancialsResponse {
+                payables: vec![],
+                total_payable: 21,
+                receivables: vec![],
+                total_receivable: 32,
+            })
+        );
+        assert_eq!(subject.active_port(), Some(node_port));
+        let node_request = node_stop_handle.stop()[0].clone().unwrap();
+        assert_eq!(UiFinancialsRequest::fmb(node_request), Ok((request, 1)));
+        daemon_stop_handle.stop();
+    }
+
+    #[test]
+    fn broadcasts_go_to_subscribers_of_their_type_and_unclaimed_ones_go_to_the_fallback() {
+        let routes = Arc::new(Mutex::new(vec![]));
+        let (password_route, password_subscription) =
+            broadcast_channel::<UiNewPasswordBroadcast>(10);
+        let (other_password_route, other_password_subscription) =
+            broadcast_channel::<UiNewPasswordBroadcast>(10);
+        routes.lock().unwrap().push(password_route);
+        routes.lock().unwrap().push(other_password_route);
+        let send_params_arc = Arc::new(Mutex::new(vec![]));
+        let subject = BroadcastRouter {
+            routes: routes.clone(),
+            unclaimed_handle_opt: Some(Box::new(
+                BroadcastHandleMock::new().send_params(&send_params_arc),
+            )),
+        };
+        let setup_broadcast = UiSetupBroadcast {
+            running: false,
+            values: vec![],
+            errors: vec![],
+        }
+        .tmb(0);
+
+        subject.send(UiNewPasswordBroadcast {}.tmb(0));
+        subject.send(setup_broadcast.clone());
+
+        assert_eq!(
+            password_subscription.try_recv(),
+            Some(UiNewPasswordBroadcast {})
+        );
+        assert_eq!(
+            other_password_subscription.try_recv(),
+            Some(UiNewPasswordBroadcast {})
+        );
+        assert_eq!(password_subscription.try_recv(), None);
+        let send_params = send_params_arc.lock().unwrap();
+        assert_eq!(*send_params, vec![setup_broadcast]);
+    }
+
+    #[test]
+    fn abandoned_subscriptions_are_forgotten() {
+        let routes = Arc::new(Mutex::new(vec![]));
+        let (password_route, password_subscription) =
+            broadcast_channel::<UiNewPasswordBroadcast>(10);
+        routes.lock().unwrap().push(password_route);
+        let send_params_arc = Arc::new(Mutex::new(vec![]));
+        let subject = BroadcastRouter {
+            routes: routes.clone(),
+            unclaimed_handle_opt: Some(Box::new(
+                BroadcastHandleMock::new().send_params(&send_params_arc),
+            )),
+        };
+        drop(password_subscription);
+
+        subject.send(UiNewPasswordBroadcast {}.tmb(0));
+
+        assert_eq!(routes.lock().unwrap().is_empty(), true);
+        let send_params = send_params_arc.lock().unwrap();
+        assert_eq!(*send_params, vec![UiNewPasswordBroadcast {}.tmb(0)]);
+    }
+
+    #[test]
+    fn full_subscriptions_drop_their_oldest_broadcasts() {
+        let (route, subject) = broadcast_channel::<UiSetupBroadcast>(2);
+        let setup_broadcast = |running| UiSetupBroadcast {
+            running,
+            values: vec![],
+            errors: vec![],
+        };
+
+        let outcomes = vec![false, true, false]
+            .into_iter()
+            .map(|running| route(&setup_broadcast(running).tmb(0)))
+            .collect::<Vec<RouteOutcome>>();
+
+        assert_eq!(outcomes, vec![RouteOutcome::Claimed; 3]);
+        assert_eq!(subject.try_recv(), Some(setup_broadcast(true)));
+        assert_eq!(subject.try_recv(), Some(setup_broadcast(false)));
+        assert_eq!(subject.try_recv(), None);
+    }
+
+    #[test]
+    fn broadcast_subscription_times_out() {
+        let (_route, subject) = broadcast_channel::<UiNewPasswordBroadcast>(10);
+
+        let result = subject.recv_timeout(10);
+
+        assert_eq!(
+            result,
+            Err(UiClientError::Connection(ClientError::Timeout(10)))
+        );
+        assert_eq!(subject.try_recv(), None);
+    }
+}
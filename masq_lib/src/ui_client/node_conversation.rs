@@ -1,9 +1,9 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
-use crate::communications::connection_manager::OutgoingMessageType;
+use crate::ui_client::connection_manager::OutgoingMessageType;
+use crate::ui_gateway::{MessageBody, MessagePath};
+use crate::ui_traffic_converter::UnmarshalError;
 use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
-use masq_lib::ui_gateway::{MessageBody, MessagePath};
-use masq_lib::ui_traffic_converter::UnmarshalError;
 use std::fmt::{Debug, Formatter};
 use std::time::Duration;
 
@@ -87,9 +87,22 @@ impl NodeConversation {
 
     pub fn transact(
         &self,
-        mut outgoing_msg: MessageBody,
+        outgoing_msg: MessageBody,
         timeout_millis: u64,
     ) -> Result<MessageBody, ClientError> {
+        let outgoing_msg = self.send_request(outgoing_msg)?;
+        let recv_result = self
+            .manager_to_conversation_rx
+            .recv_timeout(Duration::from_millis(timeout_millis));
+        match Self::interpret_response(recv_result, timeout_millis) {
+            Some(result) => result,
+            None => self.transact(outgoing_msg, timeout_millis),
+        }
+    }
+
+    // The sending half of transact(): the response arrives on responses(), and the request comes
+    // back addressed to this conversation, ready to be sent again if the response says Resend.
+    pub fn send_request(&self, mut outgoing_msg: MessageBody) -> Result<MessageBody, ClientError> {
         if outgoing_msg.path == MessagePath::FireAndForget {
             panic! ("Cannot use NodeConversation::transact() to send message with MessagePath::FireAndForget. Use NodeCoversation::send() instead.")
         }
@@ -99,32 +112,37 @@ impl NodeConversation {
             .send(OutgoingMessageType::ConversationMessage(
                 outgoing_msg.clone(),
             )) {
-            Ok(_) => {
-                let recv_result = self
-                    .manager_to_conversation_rx
-                    .recv_timeout(Duration::from_millis(timeout_millis));
-                match recv_result {
-                    Ok(Ok(body)) => Ok(body),
-                    Ok(Err(NodeConversationTermination::Graceful)) => {
-                        Err(ClientError::ConnectionDropped)
-                    }
-                    Ok(Err(NodeConversationTermination::Resend)) => {
-                        self.transact(outgoing_msg, timeout_millis)
-                    }
-                    Ok(Err(NodeConversationTermination::Fatal)) => {
-                        Err(ClientError::ConnectionDropped)
-                    }
-                    Ok(Err(NodeConversationTermination::FiredAndForgotten)) => {
-                        panic!("Two-way transaction should never result in FiredAndForgotten")
-                    }
-                    Err(RecvTimeoutError::Timeout) => Err(ClientError::Timeout(timeout_millis)),
-                    Err(_) => Err(ClientError::ConnectionDropped),
-                }
-            }
+            Ok(_) => Ok(outgoing_msg),
             Err(_) => Err(ClientError::ConnectionDropped),
         }
     }
 
+    pub fn responses(&self) -> &Receiver<Result<MessageBody, NodeConversationTermination>> {
+        &self.manager_to_conversation_rx
+    }
+
+    // None means the request has to be sent again.
+    pub fn interpret_response(
+        recv_result: Result<Result<MessageBody, NodeConversationTermination>, RecvTimeoutError>,
+        timeout_millis: u64,
+    ) -> Option<Result<MessageBody, ClientError>> {
+        match recv_result {
+            Ok(Ok(body)) => Some(Ok(body)),
+            Ok(Err(NodeConversationTermination::Graceful)) => {
+                Some(Err(ClientError::ConnectionDropped))
+            }
+            Ok(Err(NodeConversationTermination::Resend)) => None,
+            Ok(Err(NodeConversationTermination::Fatal)) => {
+                Some(Err(ClientError::ConnectionDropped))
+            }
+            Ok(Err(NodeConversationTermination::FiredAndForgotten)) => {
+                panic!("Two-way transaction should never result in FiredAndForgotten")
+            }
+            Err(RecvTimeoutError::Timeout) => Some(Err(ClientError::Timeout(timeout_millis))),
+            Err(_) => Some(Err(ClientError::ConnectionDropped)),
+        }
+    }
+
     #[cfg(test)]
     pub fn tx_rx(
         &self,
@@ -142,12 +160,12 @@ impl NodeConversation {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::communications::node_conversation::NodeConversationTermination::FiredAndForgotten;
+    use crate::messages::FromMessageBody;
+    use crate::messages::ToMessageBody;
+    use crate::messages::{UiShutdownRequest, UiShutdownResponse, UiUnmarshalError};
+    use crate::ui_client::node_conversation::NodeConversationTermination::FiredAndForgotten;
     use crossbeam_channel::unbounded;
     use crossbeam_channel::TryRecvError;
-    use masq_lib::messages::FromMessageBody;
-    use masq_lib::messages::ToMessageBody;
-    use masq_lib::messages::{UiShutdownRequest, UiShutdownResponse, UiUnmarshalError};
 
     fn make_subject() -> (
         NodeConversation,
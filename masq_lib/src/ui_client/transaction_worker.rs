@@ -0,0 +1,256 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::ui_client::node_conversation::{
+    ClientError, NodeConversation, NodeConversationTermination,
+};
+use crate::ui_gateway::MessageBody;
+use crossbeam_channel::{unbounded, Receiver, RecvError, RecvTimeoutError, Select, Sender};
+use futures::sync::oneshot;
+use std::thread;
+use std::time::{Duration, Instant};
+
+type TransactionResult = Result<MessageBody, ClientError>;
+
+struct TransactionJob {
+    conversation: NodeConversation,
+    request: MessageBody,
+    timeout_millis: u64,
+    result_tx: oneshot::Sender<TransactionResult>,
+}
+
+struct PendingTransaction {
+    conversation: NodeConversation,
+    request: MessageBody,
+    timeout_millis: u64,
+    deadline: Instant,
+    result_tx: oneshot::Sender<TransactionResult>,
+}
+
+impl PendingTransaction {
+    fn finish(self, result: TransactionResult) {
+        // Whoever was waiting may have given up; that's their business
+        let _ = self.result_tx.send(result);
+    }
+}
+
+enum WorkerEvent {
+    Job(Result<TransactionJob, RecvError>),
+    Response(
+        usize,
+        Result<Result<MessageBody, NodeConversationTermination>, RecvError>,
+    ),
+    Timeout,
+}
+
+// One thread that waits on the conversations of every asynchronous transaction a UiClient has in
+// flight, so that outstanding requests don't each hold a thread of their own. It stops once the
+// UiClient is gone and the last of its transactions has finished.
+pub struct TransactionWorker {
+    job_tx: Sender<TransactionJob>,
+}
+
+impl TransactionWorker {
+    pub fn start() -> Self {
+        let (job_tx, job_rx) = unbounded();
+        thread::spawn(move || Self::thread_loop(job_rx));
+        Self { job_tx }
+    }
+
+    pub fn transact(
+        &self,
+        conversation: NodeConversation,
+        request: MessageBody,
+        timeout_millis: u64,
+    ) -> oneshot::Receiver<TransactionResult> {
+        let (result_tx, result_rx) = oneshot::channel();
+        // If the worker is gone, result_tx goes with the job and the receiver sees it canceled
+        let _ = self.job_tx.send(TransactionJob {
+            conversation,
+            request,
+            timeout_millis,
+            result_tx,
+        });
+        result_rx
+    }
+
+    fn thread_loop(job_rx: Receiver<TransactionJob>) {
+        let mut accepting_jobs = true;
+        let mut pending: Vec<PendingTransaction> = vec![];
+        while accepting_jobs || !pending.is_empty() {
+            let now = Instant::now();
+            let (expired, live): (Vec<PendingTransaction>, Vec<PendingTransaction>) =
+                pending.into_iter().partition(|txn| txn.deadline <= now);
+            pending = live;
+            expired.into_iter().for_each(|txn| {
+                let timeout_millis = txn.timeout_millis;
+                txn.finish(Err(ClientError::Timeout(timeout_millis)))
+            });
+            match Self::wait_for_event(&job_rx, accepting_jobs, &pending, now) {
+                WorkerEvent::Job(Ok(job)) => {
+                    if let Some(txn) = Self::begin(job) {
+                        pending.push(txn);
+                    }
+                }
+                WorkerEvent::Job(Err(_)) => accepting_jobs = false,
+                WorkerEvent::Response(index, recv_result) => {
+                    let txn = pending.remove(index);
+                    let recv_result = recv_result.map_err(|_| RecvTimeoutError::Disconnected);
+                    match NodeConversation::interpret_response(recv_result, txn.timeout_millis) {
+                        Some(result) => txn.finish(result),
+                        None => pending.extend(Self::begin(TransactionJob {
+                            conversation: txn.conversation,
+                            request: txn.request,
+                            timeout_millis: txn.timeout_millis,
+                            result_tx: txn.result_tx,
+                        })),
+                    }
+                }
+                WorkerEvent::Timeout => (),
+            }
+        }
+    }
+
+    // Like NodeConversation::transact(), the timeout starts over whenever the request is resent
+    fn begin(job: TransactionJob) -> Option<PendingTransaction> {
+        match job.conversation.send_request(job.request) {
+            Ok(request) => Some(PendingTransaction {
+                conversation: job.conversation,
+                request,
+                timeout_millis: job.timeout_millis,
+                deadline: Instant::now() + Duration::from_millis(job.timeout_millis),
+                result_tx: job.result_tx,
+            }),
+            Err(e) => {
+                let _ = job.result_tx.send(Err(e));
+                None
+            }
+        }
+    }
+
+    fn wait_for_event(
+        job_rx: &Receiver<TransactionJob>,
+        accepting_jobs: bool,
+        pending: &[PendingTransaction],
+        now: Instant,
+    ) -> WorkerEvent {
+        let mut select = Select::new();
+        let job_index_opt = if accepting_jobs {
+            Some(select.recv(job_rx))
+        } else {
+            None
+        };
+        let response_indexes = pending
+            .iter()
+            .map(|txn| select.recv(txn.conversation.responses()))
+            .collect::<Vec<usize>>();
+        let operation = match pending.iter().map(|txn| txn.deadline).min() {
+            Some(deadline) => match select.select_timeout(deadline - now) {
+                Ok(operation) => operation,
+                Err(_) => return WorkerEvent::Timeout,
+            },
+            None => select.select(),
+        };
+        if Some(operation.index()) == job_index_opt {
+            return WorkerEvent::Job(operation.recv(job_rx));
+        }
+        let position = response_indexes
+            .iter()
+            .position(|index| *index == operation.index())
+            .expect("Select chose an operation it wasn't given");
+        WorkerEvent::Response(
+            position,
+            operation.recv(pending[position].conversation.responses()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::ToMessageBody;
+    use crate::messages::{UiShutdownRequest, UiShutdownResponse};
+    use crate::ui_client::connection_manager::OutgoingMessageType;
+    use futures::Future;
+
+    fn make_conversation(
+        context_id: u64,
+    ) -> (
+        NodeConversation,
+        Sender<Result<MessageBody, NodeConversationTermination>>,
+        Receiver<OutgoingMessageType>,
+    ) {
+        let (conversations_to_manager_tx, conversations_to_manager_rx) = unbounded();
+        let (manager_to_conversation_tx, manager_to_conversation_rx) = unbounded();
+        let conversation = NodeConversation::new(
+            context_id,
+            conversations_to_manager_tx,
+            manager_to_conversation_rx,
+        );
+        (
+            conversation,
+            manager_to_conversation_tx,
+            conversations_to_manager_rx,
+        )
+    }
+
+    #[test]
+    fn one_worker_waits_on_several_transactions_at_once() {
+        let (first_conversation, first_response_tx, first_request_rx) = make_conversation(1);
+        let (second_conversation, second_response_tx, second_request_rx) = make_conversation(2);
+        let subject = TransactionWorker::start();
+
+        let first_future = subject.transact(first_conversation, UiShutdownRequest {}.tmb(0), 5000);
+        let second_future =
+            subject.transact(second_conversation, UiShutdownRequest {}.tmb(0), 5000);
+        assert_eq!(
+            second_request_rx.recv().unwrap(),
+            OutgoingMessageType::ConversationMessage(UiShutdownRequest {}.tmb(2))
+        );
+        second_response_tx
+            .send(Ok(UiShutdownResponse {}.tmb(2)))
+            .unwrap();
+
+        assert_eq!(second_future.wait(), Ok(Ok(UiShutdownResponse {}.tmb(2))));
+        assert_eq!(
+            first_request_rx.recv().unwrap(),
+            OutgoingMessageType::ConversationMessage(UiShutdownRequest {}.tmb(1))
+        );
+        first_response_tx
+            .send(Ok(UiShutdownResponse {}.tmb(1)))
+            .unwrap();
+        assert_eq!(first_future.wait(), Ok(Ok(UiShutdownResponse {}.tmb(1))));
+    }
+
+    #[test]
+    fn worker_resends_when_told_to_and_times_out_when_nothing_comes() {
+        let (conversation, response_tx, request_rx) = make_conversation(1);
+        let subject = TransactionWorker::start();
+
+        let future = subject.transact(conversation, UiShutdownRequest {}.tmb(0), 100);
+        response_tx
+            .send(Err(NodeConversationTermination::Resend))
+            .unwrap();
+
+        assert_eq!(future.wait(), Ok(Err(ClientError::Timeout(100))));
+        let requests = request_rx.try_iter().collect::<Vec<OutgoingMessageType>>();
+        assert_eq!(
+            requests[..2].to_vec(),
+            vec![
+                OutgoingMessageType::ConversationMessage(UiShutdownRequest {}.tmb(1)),
+                OutgoingMessageType::ConversationMessage(UiShutdownRequest {}.tmb(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn worker_keeps_its_transactions_after_its_owner_is_gone() {
+        let (conversation, response_tx, _request_rx) = make_conversation(1);
+        let subject = TransactionWorker::start();
+
+        let future = subject.transact(conversation, UiShutdownRequest {}.tmb(0), 5000);
+        drop(subject);
+        response_tx.send(Ok(UiShutdownResponse {}.tmb(1))).unwrap();
+
+        assert_eq!(future.wait(), Ok(Ok(UiShutdownResponse {}.tmb(1))));
+    }
+}
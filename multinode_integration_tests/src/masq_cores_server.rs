@@ -7,6 +7,7 @@ use node_lib::discriminator::Discriminator;
 use node_lib::discriminator::DiscriminatorFactory;
 use node_lib::discriminator::UnmaskedChunk;
 use node_lib::hopper::live_cores_package::LiveCoresPackage;
+use node_lib::hopper::session_keys::HopSessionKeys;
 use node_lib::http_request_start_finder::HttpRequestDiscriminatorFactory;
 use node_lib::json_discriminator_factory::JsonDiscriminatorFactory;
 use node_lib::sub_lib::cryptde::CryptDE;
@@ -28,6 +29,7 @@ use std::sync::mpsc::Receiver;
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::Duration;
+use std::time::Instant;
 
 // TODO: Cover this with tests and put it in the production tree.
 pub struct DiscriminatorCluster {
@@ -65,6 +67,7 @@ pub struct MASQCoresServer {
     discriminators: RefCell<DiscriminatorCluster>,
     main_cryptde: CryptDENull,
     alias_cryptde: CryptDENull,
+    hop_session_keys: RefCell<HopSessionKeys>,
     io_receiver: Receiver<io::Result<Vec<u8>>>,
     socket_addr: SocketAddr,
     _join_handle: JoinHandle<()>,
@@ -123,6 +126,7 @@ impl MASQCoresServer {
             discriminators: RefCell::new(DiscriminatorCluster::new(Self::default_factories())),
             main_cryptde,
             alias_cryptde,
+            hop_session_keys: RefCell::new(HopSessionKeys::new()),
             io_receiver: io_rx,
             socket_addr: local_addr,
             _join_handle: join_handle,
@@ -165,12 +169,14 @@ impl MASQCoresServer {
 
     pub fn wait_for_package(&self, timeout: Duration) -> LiveCoresPackage {
         let chunk = self.get_next_chunk(timeout);
-        let decoded_chunk = self
-            .main_cryptde
-            .decode(&CryptData::new(&chunk.chunk[..]))
-            .unwrap();
-        serde_cbor::de::from_slice::<LiveCoresPackage>(decoded_chunk.as_slice())
-            .unwrap_or_else(|_| panic!("Error deserializing LCP from {:?}", chunk.chunk))
+        self.hop_session_keys
+            .borrow_mut()
+            .decode::<LiveCoresPackage>(
+                &self.main_cryptde,
+                &CryptData::new(&chunk.chunk[..]),
+                Instant::now(),
+            )
+            .unwrap_or_else(|_| panic!("Error decoding LCP from {:?}", chunk.chunk))
     }
 
     fn default_factories() -> Vec<Box<dyn DiscriminatorFactory>> {
@@ -7,6 +7,7 @@ use crate::masq_node::NodeReference;
 use crate::masq_node::PortSelector;
 use crate::multinode_gossip::{Introduction, MultinodeGossip, SingleNode};
 use node_lib::hopper::live_cores_package::LiveCoresPackage;
+use node_lib::hopper::session_keys::HopSessionKeys;
 use node_lib::json_masquerader::JsonMasquerader;
 use node_lib::masquerader::{MasqueradeError, Masquerader};
 use node_lib::neighborhood::gossip::Gossip_0v1;
@@ -185,6 +186,7 @@ impl MASQMockNode {
             rate_pack: DEFAULT_RATE_PACK.clone(),
             cryptde_enum,
             framer,
+            hop_session_keys: RefCell::new(HopSessionKeys::new()),
             chain: None,
         });
         MASQMockNode {
@@ -348,13 +350,16 @@ impl MASQMockNode {
                 }
             }
         };
-        let decrypted_data = self
-            .signing_cryptde()
-            .unwrap()
-            .decode(&CryptData::new(&unmasked_chunk[..]))
+        let live_cores_package = self
+            .guts
+            .hop_session_keys
+            .borrow_mut()
+            .decode::<LiveCoresPackage>(
+                self.signing_cryptde().unwrap(),
+                &CryptData::new(&unmasked_chunk[..]),
+                Instant::now(),
+            )
             .unwrap();
-        let live_cores_package =
-            serde_cbor::de::from_slice::<LiveCoresPackage>(decrypted_data.as_slice()).unwrap();
         Ok((from_opt.unwrap(), to_opt.unwrap(), live_cores_package))
     }
 
@@ -492,6 +497,7 @@ struct MASQMockNodeGuts {
     rate_pack: RatePack,
     cryptde_enum: CryptDEEnum,
     framer: RefCell<DataHunkFramer>,
+    hop_session_keys: RefCell<HopSessionKeys>,
     chain: Option<String>,
 }
 
@@ -182,6 +182,9 @@ impl NodeStartupConfig {
         }
         args.push("--log-level".to_string());
         args.push("trace".to_string());
+        // The test servers live on the Docker network's private addresses.
+        args.push("--allow-local-destinations".to_string());
+        args.push("true".to_string());
         args.push("--data-directory".to_string());
         args.push(DATA_DIRECTORY.to_string());
         if let EarningWalletInfo::Address(ref address) = self.earning_wallet_info {
@@ -1380,6 +1383,8 @@ mod tests {
                 format!("{},{}", one_neighbor, another_neighbor).as_str(),
                 "--log-level",
                 "trace",
+                "--allow-local-destinations",
+                "true",
                 "--data-directory",
                 DATA_DIRECTORY,
                 "--consuming-private-key",
@@ -24,6 +24,7 @@ use crate::sub_lib::accountant::ReportRoutingServiceConsumedMessage;
 use crate::sub_lib::accountant::ReportRoutingServiceProvidedMessage;
 use crate::sub_lib::blockchain_bridge::ReportAccountsPayable;
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::{metrics, Metrics};
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
 use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
 use crate::sub_lib::wallet::Wallet;
@@ -44,6 +45,7 @@ use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{NodeFromUiMessage, NodeToUiMessage};
 use payable_dao::PayableDao;
 use receivable_dao::ReceivableDao;
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, SystemTime};
 
@@ -102,6 +104,9 @@ pub struct Accountant {
     report_new_payments_sub: Option<Recipient<ReceivedPayments>>,
     report_sent_payments_sub: Option<Recipient<SentPayments>>,
     ui_message_sub: Option<Recipient<NodeToUiMessage>>,
+    metrics: Arc<Metrics>,
+    // Totaling the balances costs database queries, so we only do it if someone's looking
+    balance_metrics_enabled: bool,
     logger: Logger,
 }
 
@@ -243,6 +248,8 @@ impl Accountant {
             report_new_payments_sub: None,
             report_sent_payments_sub: None,
             ui_message_sub: None,
+            metrics: metrics(),
+            balance_metrics_enabled: config.metrics_port_opt.is_some(),
             logger: Logger::new("Accountant"),
         }
     }
@@ -271,6 +278,9 @@ impl Accountant {
 
     fn scan_for_payables(&mut self) {
         debug!(self.logger, "Scanning for payables");
+        if self.balance_metrics_enabled {
+            self.metrics.payable_total.set(self.payable_dao.total());
+        }
         let future_logger = self.logger.clone();
 
         let payables = self
@@ -314,6 +324,11 @@ impl Accountant {
 
     fn scan_for_delinquencies(&mut self) {
         debug!(self.logger, "Scanning for delinquencies");
+        if self.balance_metrics_enabled {
+            self.metrics
+                .receivable_total
+                .set(self.receivable_dao.total());
+        }
 
         let now = SystemTime::now();
         self.receivable_dao
@@ -577,6 +592,7 @@ impl Accountant {
             self.logger,
             "Charging routing of {} bytes to wallet {}", msg.payload_size, msg.paying_wallet
         );
+        self.metrics.routing_bytes.add(msg.payload_size as u64);
         self.record_service_provided(
             msg.service_rate,
             msg.byte_rate,
@@ -597,6 +613,7 @@ impl Accountant {
             msg.service_rate,
             msg.byte_rate
         );
+        self.metrics.exit_bytes.add(msg.payload_size as u64);
         self.record_service_provided(
             msg.service_rate,
             msg.byte_rate,
@@ -2280,6 +2297,88 @@ pub mod tests {
         ));
     }
 
+    #[test]
+    fn service_provided_messages_count_routed_and_exited_bytes() {
+        let receivable_dao_mock = ReceivableDaoMock::new()
+            .more_money_receivable_result(Ok(()))
+            .more_money_receivable_result(Ok(()))
+            .more_money_receivable_result(Ok(()));
+        let mut subject = make_subject(None, None, Some(receivable_dao_mock), None, None);
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+
+        subject.handle_report_routing_service_provided_message(
+            ReportRoutingServiceProvidedMessage {
+                paying_wallet: make_wallet("booga"),
+                payload_size: 1234,
+                service_rate: 42,
+                byte_rate: 24,
+            },
+        );
+        subject.handle_report_routing_service_provided_message(
+            ReportRoutingServiceProvidedMessage {
+                paying_wallet: make_wallet("booga"),
+                payload_size: 1000,
+                service_rate: 42,
+                byte_rate: 24,
+            },
+        );
+        subject.handle_report_exit_service_provided_message(ReportExitServiceProvidedMessage {
+            paying_wallet: make_wallet("booga"),
+            payload_size: 4321,
+            service_rate: 42,
+            byte_rate: 24,
+        });
+
+        assert_eq!(metrics.routing_bytes.get(), 2234);
+        assert_eq!(metrics.exit_bytes.get(), 4321);
+    }
+
+    #[test]
+    fn scans_report_balance_totals_when_metrics_are_enabled() {
+        let mut config = BootstrapperConfig::new();
+        config.metrics_port_opt = Some(9333);
+        let payable_dao = PayableDaoMock::new()
+            .non_pending_payables_result(vec![])
+            .total_result(1234);
+        let receivable_dao = ReceivableDaoMock::new()
+            .new_delinquencies_result(vec![])
+            .paid_delinquencies_result(vec![])
+            .total_result(4321);
+        let mut subject = make_subject(
+            Some(config),
+            Some(payable_dao),
+            Some(receivable_dao),
+            None,
+            None,
+        );
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+
+        subject.scan_for_payables();
+        subject.scan_for_delinquencies();
+
+        assert_eq!(metrics.payable_total.get(), 1234);
+        assert_eq!(metrics.receivable_total.get(), 4321);
+    }
+
+    #[test]
+    fn scans_leave_balance_totals_alone_when_metrics_are_disabled() {
+        let payable_dao = PayableDaoMock::new().non_pending_payables_result(vec![]);
+        let receivable_dao = ReceivableDaoMock::new()
+            .new_delinquencies_result(vec![])
+            .paid_delinquencies_result(vec![]);
+        let mut subject = make_subject(None, Some(payable_dao), Some(receivable_dao), None, None);
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+
+        subject.scan_for_payables();
+        subject.scan_for_delinquencies();
+
+        assert_eq!(metrics.payable_total.get(), 0);
+        assert_eq!(metrics.receivable_total.get(), 0);
+    }
+
     #[test]
     fn report_routing_service_provided_message_is_received_from_our_consuming_wallet() {
         init_test_logging();
@@ -2,7 +2,6 @@
 use super::accountant::Accountant;
 use super::bootstrapper;
 use super::bootstrapper::BootstrapperConfig;
-use super::discriminator::DiscriminatorFactory;
 use super::dispatcher::Dispatcher;
 use super::hopper::Hopper;
 use super::neighborhood::Neighborhood;
@@ -25,6 +24,7 @@ use crate::db_config::config_dao::ConfigDaoReal;
 use crate::db_config::persistent_configuration::PersistentConfigurationReal;
 use crate::node_configurator::configurator::Configurator;
 use crate::sub_lib::accountant::AccountantSubs;
+use crate::sub_lib::bandwidth::bandwidth_throttle;
 use crate::sub_lib::blockchain_bridge::BlockchainBridgeSubs;
 use crate::sub_lib::configurator::ConfiguratorSubs;
 use crate::sub_lib::cryptde::CryptDE;
@@ -36,7 +36,7 @@ use crate::sub_lib::peer_actors::PeerActors;
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
 use crate::sub_lib::proxy_client::ProxyClientConfig;
 use crate::sub_lib::proxy_client::ProxyClientSubs;
-use crate::sub_lib::proxy_server::ProxyServerSubs;
+use crate::sub_lib::proxy_server::{PortMapping, ProxyServerSubs};
 use crate::sub_lib::ui_gateway::UiGatewayConfig;
 use crate::sub_lib::ui_gateway::UiGatewaySubs;
 use actix::Addr;
@@ -89,6 +89,7 @@ impl ActorSystemFactoryReal {
         tx: Sender<StreamHandlerPoolSubs>,
     ) {
         let db_initializer = DbInitializerReal::new();
+        bandwidth_throttle().set_limits(config.bandwidth_limits);
         // make all the actors
         let (dispatcher_subs, pool_bind_sub) = actor_factory.make_and_start_dispatcher(&config);
         let proxy_server_subs = actor_factory.make_and_start_proxy_server(
@@ -100,10 +101,13 @@ impl ActorSystemFactoryReal {
             } else {
                 Some(0)
             },
+            config.explicit_proxy_port_opt,
+            config.port_mappings.clone(),
         );
         let proxy_client_subs = actor_factory.make_and_start_proxy_client(ProxyClientConfig {
             cryptde: main_cryptde,
             dns_servers: config.dns_servers.clone(),
+            dns_protocol: config.dns_protocol.clone(),
             exit_service_rate: config
                 .neighborhood_config
                 .mode
@@ -111,6 +115,10 @@ impl ActorSystemFactoryReal {
                 .clone()
                 .exit_service_rate,
             exit_byte_rate: config.neighborhood_config.mode.rate_pack().exit_byte_rate,
+            exit_idle_timeout: config.exit_idle_timeout,
+            exit_absolute_timeout: config.exit_absolute_timeout,
+            exit_ports_opt: config.exit_ports_opt.clone(),
+            local_destinations_allowed: config.local_destinations_allowed,
         });
         let hopper_subs = actor_factory.make_and_start_hopper(HopperConfig {
             main_cryptde,
@@ -140,8 +148,7 @@ impl ActorSystemFactoryReal {
         );
         let ui_gateway_subs =
             actor_factory.make_and_start_ui_gateway(config.ui_gateway_config.clone());
-        let stream_handler_pool_subs = actor_factory
-            .make_and_start_stream_handler_pool(config.clandestine_discriminator_factories.clone());
+        let stream_handler_pool_subs = actor_factory.make_and_start_stream_handler_pool(&config);
         let configurator_subs = actor_factory.make_and_start_configurator(&config);
 
         // collect all the subs
@@ -202,6 +209,8 @@ pub trait ActorFactory: Send {
         alias_cryptde: &'static dyn CryptDE,
         is_decentralized: bool,
         consuming_wallet_balance: Option<i64>,
+        explicit_proxy_port_opt: Option<u16>,
+        port_mappings: Vec<PortMapping>,
     ) -> ProxyServerSubs;
     fn make_and_start_hopper(&self, config: HopperConfig) -> HopperSubs;
     fn make_and_start_neighborhood(
@@ -219,7 +228,7 @@ pub trait ActorFactory: Send {
     fn make_and_start_ui_gateway(&self, config: UiGatewayConfig) -> UiGatewaySubs;
     fn make_and_start_stream_handler_pool(
         &self,
-        clandestine_discriminator_factories: Vec<Box<dyn DiscriminatorFactory>>,
+        config: &BootstrapperConfig,
     ) -> StreamHandlerPoolSubs;
     fn make_and_start_proxy_client(&self, config: ProxyClientConfig) -> ProxyClientSubs;
     fn make_and_start_blockchain_bridge(
@@ -253,6 +262,8 @@ impl ActorFactory for ActorFactoryReal {
         alias_cryptde: &'static dyn CryptDE,
         is_decentralized: bool,
         consuming_wallet_balance: Option<i64>,
+        explicit_proxy_port_opt: Option<u16>,
+        port_mappings: Vec<PortMapping>,
     ) -> ProxyServerSubs {
         let addr: Addr<ProxyServer> = Arbiter::start(move |_| {
             ProxyServer::new(
@@ -260,6 +271,8 @@ impl ActorFactory for ActorFactoryReal {
                 alias_cryptde,
                 is_decentralized,
                 consuming_wallet_balance,
+                explicit_proxy_port_opt,
+                port_mappings,
             )
         });
         ProxyServer::make_subs_from(&addr)
@@ -336,10 +349,14 @@ impl ActorFactory for ActorFactoryReal {
 
     fn make_and_start_stream_handler_pool(
         &self,
-        clandestine_discriminator_factories: Vec<Box<dyn DiscriminatorFactory>>,
+        config: &BootstrapperConfig,
     ) -> StreamHandlerPoolSubs {
-        let addr: Addr<StreamHandlerPool> =
-            Arbiter::start(|_| StreamHandlerPool::new(clandestine_discriminator_factories));
+        let clandestine_discriminator_factories =
+            config.clandestine_discriminator_factories.clone();
+        let masquerade = config.masquerade;
+        let addr: Addr<StreamHandlerPool> = Arbiter::start(move |_| {
+            StreamHandlerPool::new(clandestine_discriminator_factories, masquerade)
+        });
         StreamHandlerPool::make_subs_from(&addr)
     }
 
@@ -416,8 +433,10 @@ mod tests {
     use crate::bootstrapper::{Bootstrapper, RealUser};
     use crate::database::connection_wrapper::ConnectionWrapper;
     use crate::database::db_initializer::test_utils::DbInitializerMock;
+    use crate::masquerader::MasqueradeKind;
     use crate::neighborhood::gossip::Gossip_0v1;
     use crate::stream_messages::AddStreamMsg;
+    use crate::stream_messages::MasqueradeDetectedMsg;
     use crate::stream_messages::RemoveStreamMsg;
     use crate::sub_lib::accountant::AccountantConfig;
     use crate::sub_lib::accountant::ReportRoutingServiceConsumedMessage;
@@ -425,28 +444,35 @@ mod tests {
     use crate::sub_lib::accountant::{
         ReportExitServiceConsumedMessage, ReportExitServiceProvidedMessage,
     };
+    use crate::sub_lib::bandwidth::BandwidthLimits;
     use crate::sub_lib::blockchain_bridge::{BlockchainBridgeConfig, ReportAccountsPayable};
     use crate::sub_lib::configurator::NewPasswordMessage;
     use crate::sub_lib::cryptde::PlainData;
-    use crate::sub_lib::dispatcher::{InboundClientData, StreamShutdownMsg};
+    use crate::sub_lib::dispatcher::{InboundClientData, StreamShutdownMsg, WritePressureMsg};
     use crate::sub_lib::hopper::IncipientCoresPackage;
-    use crate::sub_lib::hopper::{ExpiredCoresPackage, NoLookupIncipientCoresPackage};
+    use crate::sub_lib::hopper::{
+        ExpiredCoresPackage, NoLookupIncipientCoresPackage, SessionKeyPeersMsg,
+    };
+    use crate::sub_lib::logger::LogFormat;
     use crate::sub_lib::neighborhood::RouteQueryMessage;
     use crate::sub_lib::neighborhood::{
-        DispatcherNodeQueryMessage, GossipFailure_0v1, NodeRecordMetadataMessage,
+        DispatcherNodeQueryMessage, GossipFailure_0v1, NeighborhoodStatusQueryMessage,
+        NodeRecordMetadataMessage,
     };
     use crate::sub_lib::neighborhood::{NeighborhoodConfig, NodeQueryMessage};
     use crate::sub_lib::neighborhood::{NeighborhoodMode, RemoveNeighborMessage};
     use crate::sub_lib::node_addr::NodeAddr;
     use crate::sub_lib::peer_actors::StartMessage;
     use crate::sub_lib::proxy_client::{
-        ClientResponsePayload_0v1, DnsResolveFailure_0v1, InboundServerData,
+        ClientResponsePayload_0v1, DnsProtocol, DnsResolveFailure_0v1, ExitPorts,
+        InboundServerData, StreamPressure_0v1,
     };
     use crate::sub_lib::proxy_server::{
-        AddReturnRouteMessage, AddRouteMessage, ClientRequestPayload_0v1,
+        AddReturnRouteMessage, AddRouteMessage, ClientRequestPayload_0v1, RetryRouteQueryMessage,
     };
     use crate::sub_lib::set_consuming_wallet_message::SetConsumingWalletMessage;
     use crate::sub_lib::stream_handler_pool::DispatcherNodeQueryResponse;
+    use crate::sub_lib::stream_handler_pool::ReadPressureMsg;
     use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
     use crate::sub_lib::ui_gateway::UiGatewayConfig;
     use crate::test_utils::recorder::Recorder;
@@ -464,6 +490,7 @@ mod tests {
     use std::net::IpAddr;
     use std::net::Ipv4Addr;
     use std::path::PathBuf;
+    use std::str::FromStr;
     use std::sync::Arc;
     use std::sync::Mutex;
     use std::thread;
@@ -506,6 +533,8 @@ mod tests {
                 bind: recipient!(addr, BindMessage),
                 from_dispatcher_client: recipient!(addr, TransmitDataMsg),
                 stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
+                write_pressure_sub: recipient!(addr, WritePressureMsg),
+                read_pressure_sub: recipient!(addr, ReadPressureMsg),
                 ui_sub: recipient!(addr, NodeFromUiMessage),
             };
             (dispatcher_subs, addr.recipient::<PoolBindMessage>())
@@ -517,6 +546,8 @@ mod tests {
             alias_cryptde: &'a dyn CryptDE,
             is_decentralized: bool,
             consuming_wallet_balance: Option<i64>,
+            explicit_proxy_port_opt: Option<u16>,
+            port_mappings: Vec<PortMapping>,
         ) -> ProxyServerSubs {
             self.parameters
                 .proxy_server_params
@@ -527,6 +558,8 @@ mod tests {
                     alias_cryptde,
                     is_decentralized,
                     consuming_wallet_balance,
+                    explicit_proxy_port_opt,
+                    port_mappings,
                 ));
             let addr: Addr<Recorder> = ActorFactoryMock::start_recorder(&self.proxy_server);
             ProxyServerSubs {
@@ -538,9 +571,14 @@ mod tests {
                 dns_failure_from_hopper: addr
                     .clone()
                     .recipient::<ExpiredCoresPackage<DnsResolveFailure_0v1>>(),
+                stream_pressure_from_hopper: addr
+                    .clone()
+                    .recipient::<ExpiredCoresPackage<StreamPressure_0v1>>(),
                 add_return_route: recipient!(addr, AddReturnRouteMessage),
                 add_route: recipient!(addr, AddRouteMessage),
+                retry_route_query: recipient!(addr, RetryRouteQueryMessage),
                 stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
+                write_pressure_sub: recipient!(addr, WritePressureMsg),
                 set_consuming_wallet_sub: recipient!(addr, SetConsumingWalletMessage),
             }
         }
@@ -559,6 +597,7 @@ mod tests {
                     .clone()
                     .recipient::<NoLookupIncipientCoresPackage>(),
                 from_dispatcher: recipient!(addr, InboundClientData),
+                session_key_peers: recipient!(addr, SessionKeyPeersMsg),
             }
         }
 
@@ -578,6 +617,7 @@ mod tests {
                 start: recipient!(addr, StartMessage),
                 node_query: recipient!(addr, NodeQueryMessage),
                 route_query: recipient!(addr, RouteQueryMessage),
+                status_query: recipient!(addr, NeighborhoodStatusQueryMessage),
                 update_node_record_metadata: recipient!(addr, NodeRecordMetadataMessage),
                 from_hopper: addr.clone().recipient::<ExpiredCoresPackage<Gossip_0v1>>(),
                 gossip_failure: addr
@@ -642,7 +682,7 @@ mod tests {
 
         fn make_and_start_stream_handler_pool(
             &self,
-            _: Vec<Box<dyn DiscriminatorFactory>>,
+            _: &BootstrapperConfig,
         ) -> StreamHandlerPoolSubs {
             let addr: Addr<Recorder> = ActorFactoryMock::start_recorder(&self.stream_handler_pool);
             StreamHandlerPoolSubs {
@@ -651,6 +691,8 @@ mod tests {
                 remove_sub: recipient!(addr, RemoveStreamMsg),
                 bind: recipient!(addr, PoolBindMessage),
                 node_query_response: recipient!(addr, DispatcherNodeQueryResponse),
+                masquerade_detected_sub: recipient!(addr, MasqueradeDetectedMsg),
+                read_pressure_sub: recipient!(addr, ReadPressureMsg),
             }
         }
 
@@ -668,6 +710,11 @@ mod tests {
                     .recipient::<ExpiredCoresPackage<ClientRequestPayload_0v1>>(),
                 inbound_server_data: recipient!(addr, InboundServerData),
                 dns_resolve_failed: recipient!(addr, DnsResolveFailure_0v1),
+                stream_pressure: recipient!(addr, StreamPressure_0v1),
+                stream_pressure_from_hopper: addr
+                    .clone()
+                    .recipient::<ExpiredCoresPackage<StreamPressure_0v1>>(),
+                ui_sub: recipient!(addr, NodeFromUiMessage),
             }
         }
 
@@ -717,11 +764,19 @@ mod tests {
         configurator: Arc<Mutex<Recording>>,
     }
 
+    type ProxyServerParams<'a> = (
+        &'a dyn CryptDE,
+        &'a dyn CryptDE,
+        bool,
+        Option<i64>,
+        Option<u16>,
+        Vec<PortMapping>,
+    );
+
     #[derive(Clone)]
     struct Parameters<'a> {
         proxy_client_params: Arc<Mutex<Option<ProxyClientConfig>>>,
-        proxy_server_params:
-            Arc<Mutex<Option<(&'a dyn CryptDE, &'a dyn CryptDE, bool, Option<i64>)>>>,
+        proxy_server_params: Arc<Mutex<Option<ProxyServerParams<'a>>>>,
         hopper_params: Arc<Mutex<Option<HopperConfig>>>,
         neighborhood_params: Arc<Mutex<Option<(&'a dyn CryptDE, BootstrapperConfig)>>>,
         accountant_params: Arc<Mutex<Option<(BootstrapperConfig, PathBuf)>>>,
@@ -823,15 +878,19 @@ mod tests {
         let recordings = actor_factory.get_recordings();
         let config = BootstrapperConfig {
             log_level: LevelFilter::Off,
+            log_format: LogFormat::default(),
             crash_point: CrashPoint::None,
             dns_servers: vec![],
+            dns_protocol: DnsProtocol::Https("dns.google".to_string()),
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
             },
             clandestine_discriminator_factories: Vec::new(),
+            masquerade: MasqueradeKind::default(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: 5335,
+                ui_token_opt: None,
                 node_descriptor: String::from("uninitialized"),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
@@ -840,6 +899,14 @@ mod tests {
                 gas_price: 1,
             },
             port_configurations: HashMap::new(),
+            explicit_proxy_port_opt: None,
+            metrics_port_opt: None,
+            port_mappings: vec![],
+            exit_idle_timeout: Duration::from_secs(123),
+            exit_absolute_timeout: Duration::from_secs(4567),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+            bandwidth_limits: BandwidthLimits::default(),
             db_password_opt: None,
             clandestine_port_opt: None,
             earning_wallet: make_wallet("earning"),
@@ -888,15 +955,19 @@ mod tests {
         let parameters = actor_factory.make_parameters();
         let config = BootstrapperConfig {
             log_level: LevelFilter::Off,
+            log_format: LogFormat::default(),
             crash_point: CrashPoint::None,
             dns_servers: vec![],
+            dns_protocol: DnsProtocol::Https("dns.google".to_string()),
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
             },
             clandestine_discriminator_factories: Vec::new(),
+            masquerade: MasqueradeKind::default(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: 5335,
+                ui_token_opt: None,
                 node_descriptor: String::from("NODE-DESCRIPTOR"),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
@@ -905,6 +976,18 @@ mod tests {
                 gas_price: 1,
             },
             port_configurations: HashMap::new(),
+            explicit_proxy_port_opt: Some(8080),
+            metrics_port_opt: None,
+            port_mappings: vec![PortMapping {
+                local_port: 2222,
+                target_hostname: "ssh.example.com".to_string(),
+                target_port: 22,
+            }],
+            exit_idle_timeout: Duration::from_secs(123),
+            exit_absolute_timeout: Duration::from_secs(4567),
+            exit_ports_opt: Some(ExitPorts::from_str("22,8000-8100").unwrap()),
+            local_destinations_allowed: true,
+            bandwidth_limits: BandwidthLimits::default(),
             db_password_opt: None,
             clandestine_port_opt: None,
             earning_wallet: make_wallet("earning"),
@@ -947,11 +1030,30 @@ mod tests {
         assert_eq!(proxy_client_config.exit_service_rate, 0);
         assert_eq!(proxy_client_config.exit_byte_rate, 0);
         assert_eq!(proxy_client_config.dns_servers, config.dns_servers);
+        assert_eq!(
+            proxy_client_config.dns_protocol,
+            DnsProtocol::Https("dns.google".to_string())
+        );
+        assert_eq!(
+            proxy_client_config.exit_idle_timeout,
+            Duration::from_secs(123)
+        );
+        assert_eq!(
+            proxy_client_config.exit_absolute_timeout,
+            Duration::from_secs(4567)
+        );
+        assert_eq!(
+            proxy_client_config.exit_ports_opt,
+            Some(ExitPorts::from_str("22,8000-8100").unwrap())
+        );
+        assert_eq!(proxy_client_config.local_destinations_allowed, true);
         let (
             actual_main_cryptde,
             actual_alias_cryptde,
             actual_is_decentralized,
             consuming_wallet_balance,
+            explicit_proxy_port_opt,
+            port_mappings,
         ) = Parameters::get(parameters.proxy_server_params);
         check_cryptde(actual_main_cryptde);
         check_cryptde(actual_alias_cryptde);
@@ -961,6 +1063,8 @@ mod tests {
         );
         assert_eq!(actual_is_decentralized, false);
         assert_eq!(consuming_wallet_balance, Some(0));
+        assert_eq!(explicit_proxy_port_opt, Some(8080));
+        assert_eq!(port_mappings, config.port_mappings);
         let (cryptde, neighborhood_config) = Parameters::get(parameters.neighborhood_params);
         check_cryptde(cryptde);
         assert_eq!(
@@ -998,15 +1102,19 @@ mod tests {
         let parameters = actor_factory.make_parameters();
         let config = BootstrapperConfig {
             log_level: LevelFilter::Off,
+            log_format: LogFormat::default(),
             crash_point: CrashPoint::None,
             dns_servers: vec![],
+            dns_protocol: DnsProtocol::Https("dns.google".to_string()),
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(100),
                 payment_received_scan_interval: Duration::from_secs(100),
             },
             clandestine_discriminator_factories: Vec::new(),
+            masquerade: MasqueradeKind::default(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: 5335,
+                ui_token_opt: None,
                 node_descriptor: String::from("NODE-DESCRIPTOR"),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
@@ -1015,6 +1123,14 @@ mod tests {
                 gas_price: 1,
             },
             port_configurations: HashMap::new(),
+            explicit_proxy_port_opt: None,
+            metrics_port_opt: None,
+            port_mappings: vec![],
+            exit_idle_timeout: Duration::from_secs(123),
+            exit_absolute_timeout: Duration::from_secs(4567),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+            bandwidth_limits: BandwidthLimits::default(),
             db_password_opt: None,
             clandestine_port_opt: None,
             earning_wallet: make_wallet("earning"),
@@ -1044,7 +1160,8 @@ mod tests {
 
         System::current().stop();
         system.run();
-        let (_, _, _, consuming_wallet_balance) = Parameters::get(parameters.proxy_server_params);
+        let (_, _, _, consuming_wallet_balance, _, _) =
+            Parameters::get(parameters.proxy_server_params);
         assert_eq!(consuming_wallet_balance, None);
     }
 
@@ -11,10 +11,11 @@ use crate::db_config::persistent_configuration::{
     PersistentConfiguration, PersistentConfigurationReal,
 };
 use crate::discriminator::DiscriminatorFactory;
-use crate::json_discriminator_factory::JsonDiscriminatorFactory;
 use crate::listener_handler::ListenerHandler;
 use crate::listener_handler::ListenerHandlerFactory;
 use crate::listener_handler::ListenerHandlerFactoryReal;
+use crate::masquerader::MasqueradeKind;
+use crate::metrics_server::MetricsServer;
 use crate::node_configurator::node_configurator_standard::{
     NodeConfiguratorStandardPrivileged, NodeConfiguratorStandardUnprivileged,
 };
@@ -23,14 +24,20 @@ use crate::privilege_drop::{IdWrapper, IdWrapperReal};
 use crate::server_initializer::LoggerInitializerWrapper;
 use crate::sub_lib::accountant;
 use crate::sub_lib::accountant::AccountantConfig;
+use crate::sub_lib::bandwidth::BandwidthLimits;
 use crate::sub_lib::blockchain_bridge::BlockchainBridgeConfig;
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::cryptde_null::CryptDENull;
 use crate::sub_lib::cryptde_real::CryptDEReal;
-use crate::sub_lib::logger::Logger;
+use crate::sub_lib::logger::{LogFormat, Logger};
+use crate::sub_lib::metrics::metrics;
 use crate::sub_lib::neighborhood::NodeDescriptor;
 use crate::sub_lib::neighborhood::{NeighborhoodConfig, NeighborhoodMode};
 use crate::sub_lib::node_addr::NodeAddr;
+use crate::sub_lib::proxy_client::{
+    DnsProtocol, ExitPorts, DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS, DEFAULT_EXIT_IDLE_TIMEOUT_SECS,
+};
+use crate::sub_lib::proxy_server::PortMapping;
 use crate::sub_lib::socket_server::SocketServer;
 use crate::sub_lib::ui_gateway::UiGatewayConfig;
 use crate::sub_lib::wallet::Wallet;
@@ -287,13 +294,24 @@ impl Display for RealUser {
 pub struct BootstrapperConfig {
     // These fields can be set while privileged without penalty
     pub log_level: LevelFilter,
+    pub log_format: LogFormat,
     pub dns_servers: Vec<SocketAddr>,
+    pub dns_protocol: DnsProtocol,
     pub accountant_config: AccountantConfig,
     pub crash_point: CrashPoint,
     pub clandestine_discriminator_factories: Vec<Box<dyn DiscriminatorFactory>>,
+    pub masquerade: MasqueradeKind,
     pub ui_gateway_config: UiGatewayConfig,
     pub blockchain_bridge_config: BlockchainBridgeConfig,
     pub port_configurations: HashMap<u16, PortConfiguration>,
+    pub explicit_proxy_port_opt: Option<u16>,
+    pub metrics_port_opt: Option<u16>,
+    pub port_mappings: Vec<PortMapping>,
+    pub exit_idle_timeout: Duration,
+    pub exit_absolute_timeout: Duration,
+    pub exit_ports_opt: Option<ExitPorts>,
+    pub local_destinations_allowed: bool,
+    pub bandwidth_limits: BandwidthLimits,
     pub data_directory: PathBuf,
     pub main_cryptde_null_opt: Option<CryptDENull>,
     pub alias_cryptde_null_opt: Option<CryptDENull>,
@@ -318,7 +336,9 @@ impl BootstrapperConfig {
         BootstrapperConfig {
             // These fields can be set while privileged without penalty
             log_level: LevelFilter::Off,
+            log_format: LogFormat::default(),
             dns_servers: vec![],
+            dns_protocol: DnsProtocol::default(),
             accountant_config: AccountantConfig {
                 payable_scan_interval: Duration::from_secs(DEFAULT_PAYABLE_SCAN_INTERVAL),
                 payment_received_scan_interval: Duration::from_secs(
@@ -327,8 +347,10 @@ impl BootstrapperConfig {
             },
             crash_point: CrashPoint::None,
             clandestine_discriminator_factories: vec![],
+            masquerade: MasqueradeKind::default(),
             ui_gateway_config: UiGatewayConfig {
                 ui_port: DEFAULT_UI_PORT,
+                ui_token_opt: None,
                 node_descriptor: String::from(""),
             },
             blockchain_bridge_config: BlockchainBridgeConfig {
@@ -337,6 +359,14 @@ impl BootstrapperConfig {
                 gas_price: 1,
             },
             port_configurations: HashMap::new(),
+            explicit_proxy_port_opt: None,
+            metrics_port_opt: None,
+            port_mappings: vec![],
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+            bandwidth_limits: BandwidthLimits::default(),
             data_directory: PathBuf::new(),
             main_cryptde_null_opt: None,
             alias_cryptde_null_opt: None,
@@ -403,6 +433,7 @@ impl SocketServer<BootstrapperConfig> for Bootstrapper {
             self.config.data_directory.clone(),
             &self.config.real_user,
             self.config.log_level,
+            self.config.log_format,
             None,
         );
         self.listener_handlers =
@@ -453,6 +484,7 @@ impl SocketServer<BootstrapperConfig> for Bootstrapper {
         for f in self.listener_handlers.iter_mut() {
             f.bind_subs(stream_handler_pool_subs.add_sub.clone());
         }
+        self.start_metrics_server();
         Ok(())
     }
 }
@@ -528,6 +560,14 @@ impl Bootstrapper {
         descriptor
     }
 
+    fn start_metrics_server(&self) {
+        if let Some(metrics_port) = self.config.metrics_port_opt {
+            if let Err(e) = MetricsServer::start(metrics_port, metrics()) {
+                panic!("Could not serve metrics on port {}: {}", metrics_port, e)
+            }
+        }
+    }
+
     fn set_up_clandestine_port(&mut self) {
         if let NeighborhoodMode::Standard(node_addr, neighbor_configs, rate_pack) =
             &self.config.neighborhood_config.mode
@@ -547,15 +587,19 @@ impl Bootstrapper {
                 .bind_port_and_configuration(
                     clandestine_port,
                     PortConfiguration {
-                        discriminator_factories: vec![Box::new(JsonDiscriminatorFactory::new())],
+                        discriminator_factories: Self::clandestine_discriminator_factories(),
                         is_clandestine: true,
                     },
                 )
                 .expect("Failed to bind ListenerHandler to clandestine port");
             self.listener_handlers.push(listener_handler);
+            let clandestine_node_addr = NodeAddr::new(&node_addr.ip_addr(), &[clandestine_port]);
             self.config.neighborhood_config = NeighborhoodConfig {
                 mode: NeighborhoodMode::Standard(
-                    NodeAddr::new(&node_addr.ip_addr(), &[clandestine_port]),
+                    match node_addr.alternate_ip_addr_opt() {
+                        Some(alternate) => clandestine_node_addr.with_alternate_ip_addr(&alternate),
+                        None => clandestine_node_addr,
+                    },
                     neighbor_configs.clone(),
                     rate_pack.clone(),
                 ),
@@ -563,7 +607,16 @@ impl Bootstrapper {
         }
         self.config
             .clandestine_discriminator_factories
-            .push(Box::new(JsonDiscriminatorFactory::new()));
+            .extend(Self::clandestine_discriminator_factories());
+    }
+
+    // We listen for every masquerade we know, whichever one we use ourselves, so that our
+    // neighbors can each choose their own disguise.
+    fn clandestine_discriminator_factories() -> Vec<Box<dyn DiscriminatorFactory>> {
+        MasqueradeKind::all()
+            .into_iter()
+            .map(|masquerade| masquerade.discriminator_factory())
+            .collect()
     }
 
     fn establish_clandestine_port(
@@ -633,11 +686,14 @@ mod tests {
     use masq_lib::test_utils::environment_guard::ClapGuard;
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
     use masq_lib::test_utils::utils::{ensure_node_home_directory_exists, DEFAULT_CHAIN_ID};
+    use masq_lib::utils::{find_free_port, localhost};
     use regex::Regex;
     use std::cell::RefCell;
     use std::io;
     use std::io::ErrorKind;
+    use std::io::{Read, Write};
     use std::marker::Sync;
+    use std::net::TcpStream;
     use std::net::{IpAddr, SocketAddr};
     use std::ops::DerefMut;
     use std::str::FromStr;
@@ -1020,6 +1076,7 @@ mod tests {
                 data_dir,
                 RealUser::new(Some(123), Some(456), Some("/home/booga".into())),
                 LevelFilter::Warn,
+                LogFormat::Text,
                 None,
             )]
         )
@@ -1084,6 +1141,49 @@ mod tests {
         assert!(!config.ui_gateway_config.node_descriptor.is_empty());
     }
 
+    #[test]
+    fn initialize_as_unprivileged_starts_metrics_server_when_asked() {
+        let _lock = INITIALIZATION.lock();
+        let data_dir = ensure_node_home_directory_exists(
+            "bootstrapper",
+            "initialize_as_unprivileged_starts_metrics_server_when_asked",
+        );
+        let metrics_port = find_free_port();
+        let mut config = BootstrapperConfig::new();
+        config.clandestine_port_opt = Some(1234);
+        config.data_directory = data_dir.clone();
+        config.metrics_port_opt = Some(metrics_port);
+        let mut subject = BootstrapperBuilder::new()
+            .add_listener_handler(Box::new(
+                ListenerHandlerNull::new(vec![]).bind_port_result(Ok(())),
+            ))
+            .config(config)
+            .build();
+
+        subject
+            .initialize_as_unprivileged(
+                &[
+                    "MASQNode".to_string(),
+                    String::from("--ip"),
+                    String::from("1.2.3.4"),
+                    String::from("--data-directory"),
+                    data_dir.to_str().unwrap().to_string(),
+                ],
+                &mut FakeStreamHolder::new().streams(),
+            )
+            .unwrap();
+
+        let mut stream = TcpStream::connect(SocketAddr::new(localhost(), metrics_port)).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!(response.starts_with("HTTP/1.1 200 OK\r\n"), true);
+        assert_eq!(
+            response.contains("# TYPE masq_routing_bytes_total counter\n"),
+            true
+        );
+    }
+
     #[test]
     fn initialize_as_unprivileged_sets_gas_price_on_blockchain_config() {
         let _lock = INITIALIZATION.lock();
@@ -1630,6 +1730,18 @@ mod tests {
         assert_eq!(1, subject.listener_handlers.len());
 
         let config = subject.config;
+        assert_eq!(
+            config
+                .clandestine_discriminator_factories
+                .iter()
+                .map(|factory| factory.masquerade_opt())
+                .collect::<Vec<Option<MasqueradeKind>>>(),
+            vec![
+                Some(MasqueradeKind::Json),
+                Some(MasqueradeKind::Http),
+                Some(MasqueradeKind::Tls)
+            ]
+        );
         let mut clandestine_discriminators = config
             .clandestine_discriminator_factories
             .into_iter()
@@ -1645,7 +1757,7 @@ mod tests {
             }),
             discriminator.take_chunk(),
         );
-        assert_eq!(0, clandestine_discriminators.len()); // Used to be 1, now 0 after removal
+        assert_eq!(2, clandestine_discriminators.len()); // Used to be 3, now 2 after removal
     }
 
     #[test]
@@ -1698,6 +1810,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_up_clandestine_port_keeps_the_alternate_ip_address_in_standard_mode() {
+        let data_dir = ensure_node_home_directory_exists(
+            "bootstrapper",
+            "set_up_clandestine_port_keeps_the_alternate_ip_address_in_standard_mode",
+        );
+        let mut config = BootstrapperConfig::new();
+        config.neighborhood_config = NeighborhoodConfig {
+            mode: NeighborhoodMode::Standard(
+                NodeAddr::new(&IpAddr::from_str("1.2.3.4").unwrap(), &[])
+                    .with_alternate_ip_addr(&IpAddr::from_str("2001:db8::5").unwrap()),
+                vec![],
+                rate_pack(100),
+            ),
+        };
+        config.data_directory = data_dir;
+        config.clandestine_port_opt = Some(1234);
+        let listener_handler = ListenerHandlerNull::new(vec![]).bind_port_result(Ok(()));
+        let mut subject = BootstrapperBuilder::new()
+            .add_listener_handler(Box::new(listener_handler))
+            .config(config)
+            .build();
+
+        subject.set_up_clandestine_port();
+
+        assert_eq!(
+            subject.config.neighborhood_config.mode.node_addr_opt(),
+            Some(
+                NodeAddr::new(&IpAddr::from_str("1.2.3.4").unwrap(), &[1234])
+                    .with_alternate_ip_addr(&IpAddr::from_str("2001:db8::5").unwrap())
+            ),
+        );
+    }
+
     #[test]
     fn set_up_clandestine_port_handles_originate_only() {
         let cryptde_actual = CryptDENull::from(&PublicKey::new(&[1, 2, 3, 4]), DEFAULT_CHAIN_ID);
@@ -0,0 +1,377 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use chrono::{DateTime, Local};
+use masq_lib::messages::{UiCrashReport, UiCrashReportSummary};
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+pub const PANIC_FILE_NAME: &str = "MASQNode_panic.json";
+pub const SHUTDOWN_FILE_NAME: &str = "MASQNode_shutdown.json";
+pub const CRASH_REPORTS_DIRECTORY: &str = "crash-reports";
+
+// Left in the data directory by the Node's panic hook, for the Daemon to pick up when the Node dies
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NodePanic {
+    pub process_id: u32,
+    pub message: String,
+    pub location: String,
+    pub backtrace: String,
+}
+
+impl NodePanic {
+    pub fn write_to(&self, directory: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).expect("NodePanic serialization failed");
+        fs::write(directory.join(PANIC_FILE_NAME), json)
+    }
+}
+
+// Left in the data directory by the Node when a UI orders it to shut down, so that the Daemon
+// knows not to restart it
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NodeShutdown {
+    pub process_id: u32,
+}
+
+impl NodeShutdown {
+    pub fn write_to(&self, directory: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).expect("NodeShutdown serialization failed");
+        fs::write(directory.join(SHUTDOWN_FILE_NAME), json)
+    }
+}
+
+pub trait CrashReporter {
+    fn take_node_panic(&self, data_directory: &Path, process_id: u32) -> Option<NodePanic>;
+    fn take_node_shutdown(&self, data_directory: &Path, process_id: u32) -> bool;
+    fn record(&self, data_directory: &Path, report: &UiCrashReport) -> Result<(), String>;
+    fn list(&self, data_directory: &Path) -> Result<Vec<UiCrashReportSummary>, String>;
+    fn get(&self, data_directory: &Path, id: &str) -> Result<UiCrashReport, String>;
+    fn delete(&self, data_directory: &Path, ids: &[String]) -> Result<(), String>;
+}
+
+pub fn crash_report_id(time: &DateTime<Local>, process_id: u32) -> String {
+    format!("{}-{}", time.format("%Y%m%d%H%M%S"), process_id)
+}
+
+#[derive(Default)]
+pub struct CrashReporterReal {}
+
+impl CrashReporter for CrashReporterReal {
+    fn take_node_panic(&self, data_directory: &Path, process_id: u32) -> Option<NodePanic> {
+        let path = data_directory.join(PANIC_FILE_NAME);
+        let json = fs::read_to_string(&path).ok()?;
+        let _ = fs::remove_file(&path);
+        match serde_json::from_str::<NodePanic>(&json) {
+            Ok(node_panic) if node_panic.process_id == process_id => Some(node_panic),
+            _ => None,
+        }
+    }
+
+    fn take_node_shutdown(&self, data_directory: &Path, process_id: u32) -> bool {
+        let path = data_directory.join(SHUTDOWN_FILE_NAME);
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(_) => return false,
+        };
+        let _ = fs::remove_file(&path);
+        match serde_json::from_str::<NodeShutdown>(&json) {
+            Ok(node_shutdown) => node_shutdown.process_id == process_id,
+            Err(_) => false,
+        }
+    }
+
+    fn record(&self, data_directory: &Path, report: &UiCrashReport) -> Result<(), String> {
+        let directory = Self::reports_directory(data_directory);
+        if let Err(e) = fs::create_dir_all(&directory) {
+            return Err(format!("Couldn't create {:?}: {}", directory, e));
+        }
+        let path = Self::report_path(data_directory, &report.id)?;
+        let json =
+            serde_json::to_string_pretty(report).expect("UiCrashReport serialization failed");
+        fs::write(&path, json).map_err(|e| format!("Couldn't write {:?}: {}", path, e))
+    }
+
+    fn list(&self, data_directory: &Path) -> Result<Vec<UiCrashReportSummary>, String> {
+        let directory = Self::reports_directory(data_directory);
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("Couldn't read {:?}: {}", directory, e)),
+        };
+        let mut summaries = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+            .flat_map(|path| Self::read_report(&path).ok())
+            .map(|report| UiCrashReportSummary::from(&report))
+            .collect::<Vec<UiCrashReportSummary>>();
+        summaries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(summaries)
+    }
+
+    fn get(&self, data_directory: &Path, id: &str) -> Result<UiCrashReport, String> {
+        let path = Self::report_path(data_directory, id)?;
+        if !path.exists() {
+            return Err(Self::no_such_report(id));
+        }
+        Self::read_report(&path)
+    }
+
+    fn delete(&self, data_directory: &Path, ids: &[String]) -> Result<(), String> {
+        let paths = ids
+            .iter()
+            .map(|id| Self::report_path(data_directory, id))
+            .collect::<Result<Vec<PathBuf>, String>>()?;
+        if let Some((id, _)) = ids
+            .iter()
+            .zip(paths.iter())
+            .find(|(_, path)| !path.exists())
+        {
+            return Err(Self::no_such_report(id));
+        }
+        paths.iter().try_for_each(|path| {
+            fs::remove_file(path).map_err(|e| format!("Couldn't delete {:?}: {}", path, e))
+        })
+    }
+}
+
+impl CrashReporterReal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn reports_directory(data_directory: &Path) -> PathBuf {
+        data_directory.join(CRASH_REPORTS_DIRECTORY)
+    }
+
+    fn report_path(data_directory: &Path, id: &str) -> Result<PathBuf, String> {
+        // IDs come from UIs; don't let them wander out of the reports directory
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(Self::no_such_report(id));
+        }
+        Ok(Self::reports_directory(data_directory).join(format!("{}.json", id)))
+    }
+
+    fn read_report(path: &Path) -> Result<UiCrashReport, String> {
+        let json =
+            fs::read_to_string(path).map_err(|e| format!("Couldn't read {:?}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Couldn't parse {:?}: {}", path, e))
+    }
+
+    fn no_such_report(id: &str) -> String {
+        format!("There is no crash report with ID '{}'", id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use masq_lib::messages::UiSetupResponseValueStatus::Set;
+    use masq_lib::messages::{CrashReason, UiSetupResponseValue};
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+
+    fn make_report(id: &str, process_id: u32) -> UiCrashReport {
+        UiCrashReport {
+            id: id.to_string(),
+            time: "2021-02-03T04:05:06+00:00".to_string(),
+            process_id,
+            exit_code: Some(101),
+            crash_reason: CrashReason::Unrecognized("Standard error".to_string()),
+            panic_message: Some("Intentional crash".to_string()),
+            panic_location: Some("src/dispatcher.rs:42:13".to_string()),
+            backtrace: Some("   0: backtrace::backtrace".to_string()),
+            setup: vec![UiSetupResponseValue::new(
+                "neighborhood-mode",
+                "zero-hop",
+                Set,
+            )],
+        }
+    }
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(PANIC_FILE_NAME, "MASQNode_panic.json");
+        assert_eq!(SHUTDOWN_FILE_NAME, "MASQNode_shutdown.json");
+        assert_eq!(CRASH_REPORTS_DIRECTORY, "crash-reports");
+    }
+
+    #[test]
+    fn crash_report_id_combines_time_and_process_id() {
+        let time = Local.ymd(2021, 2, 3).and_hms(4, 5, 6);
+
+        let result = crash_report_id(&time, 1234);
+
+        assert_eq!(result, "20210203040506-1234".to_string());
+    }
+
+    #[test]
+    fn node_panic_can_be_written_and_taken_once() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "node_panic_can_be_written_and_taken_once",
+        );
+        let node_panic = NodePanic {
+            process_id: 1234,
+            message: "Intentional crash".to_string(),
+            location: "src/dispatcher.rs:42:13".to_string(),
+            backtrace: "   0: backtrace::backtrace".to_string(),
+        };
+        node_panic.write_to(&data_directory).unwrap();
+        let subject = CrashReporterReal::new();
+
+        let first = subject.take_node_panic(&data_directory, 1234);
+        let second = subject.take_node_panic(&data_directory, 1234);
+
+        assert_eq!(first, Some(node_panic));
+        assert_eq!(second, None);
+    }
+
+    #[test]
+    fn node_panic_from_another_process_is_discarded() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "node_panic_from_another_process_is_discarded",
+        );
+        NodePanic {
+            process_id: 4321,
+            message: "Stale".to_string(),
+            location: "<unknown location>".to_string(),
+            backtrace: "".to_string(),
+        }
+        .write_to(&data_directory)
+        .unwrap();
+        let subject = CrashReporterReal::new();
+
+        let result = subject.take_node_panic(&data_directory, 1234);
+
+        assert_eq!(result, None);
+        assert_eq!(data_directory.join(PANIC_FILE_NAME).exists(), false);
+    }
+
+    #[test]
+    fn node_shutdown_can_be_written_and_taken_once() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "node_shutdown_can_be_written_and_taken_once",
+        );
+        NodeShutdown { process_id: 1234 }
+            .write_to(&data_directory)
+            .unwrap();
+        let subject = CrashReporterReal::new();
+
+        let first = subject.take_node_shutdown(&data_directory, 1234);
+        let second = subject.take_node_shutdown(&data_directory, 1234);
+
+        assert_eq!(first, true);
+        assert_eq!(second, false);
+    }
+
+    #[test]
+    fn node_shutdown_from_another_process_is_discarded() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "node_shutdown_from_another_process_is_discarded",
+        );
+        NodeShutdown { process_id: 4321 }
+            .write_to(&data_directory)
+            .unwrap();
+        let subject = CrashReporterReal::new();
+
+        let result = subject.take_node_shutdown(&data_directory, 1234);
+
+        assert_eq!(result, false);
+        assert_eq!(data_directory.join(SHUTDOWN_FILE_NAME).exists(), false);
+    }
+
+    #[test]
+    fn recorded_reports_can_be_listed_retrieved_and_deleted() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "recorded_reports_can_be_listed_retrieved_and_deleted",
+        );
+        let subject = CrashReporterReal::new();
+        let later = make_report("20210203040506-2345", 2345);
+        let earlier = make_report("20210102030405-1234", 1234);
+        subject.record(&data_directory, &later).unwrap();
+        subject.record(&data_directory, &earlier).unwrap();
+
+        let list = subject.list(&data_directory).unwrap();
+        let retrieved = subject.get(&data_directory, "20210203040506-2345").unwrap();
+        subject
+            .delete(&data_directory, &["20210102030405-1234".to_string()])
+            .unwrap();
+        let list_after_delete = subject.list(&data_directory).unwrap();
+
+        assert_eq!(
+            list,
+            vec![
+                UiCrashReportSummary::from(&earlier),
+                UiCrashReportSummary::from(&later)
+            ]
+        );
+        assert_eq!(retrieved, later);
+        assert_eq!(list_after_delete, vec![UiCrashReportSummary::from(&later)]);
+    }
+
+    #[test]
+    fn list_is_empty_when_nothing_has_crashed() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "list_is_empty_when_nothing_has_crashed",
+        );
+        let subject = CrashReporterReal::new();
+
+        let result = subject.list(&data_directory);
+
+        assert_eq!(result, Ok(vec![]));
+    }
+
+    #[test]
+    fn get_complains_about_unknown_and_malformed_ids() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "get_complains_about_unknown_and_malformed_ids",
+        );
+        let subject = CrashReporterReal::new();
+
+        let unknown = subject.get(&data_directory, "20210203040506-2345");
+        let malformed = subject.get(&data_directory, "../MASQNode_panic");
+
+        assert_eq!(
+            unknown,
+            Err("There is no crash report with ID '20210203040506-2345'".to_string())
+        );
+        assert_eq!(
+            malformed,
+            Err("There is no crash report with ID '../MASQNode_panic'".to_string())
+        );
+    }
+
+    #[test]
+    fn delete_removes_nothing_if_any_id_is_unknown() {
+        let data_directory = ensure_node_home_directory_exists(
+            "crash_report",
+            "delete_removes_nothing_if_any_id_is_unknown",
+        );
+        let subject = CrashReporterReal::new();
+        let report = make_report("20210203040506-2345", 2345);
+        subject.record(&data_directory, &report).unwrap();
+
+        let result = subject.delete(
+            &data_directory,
+            &["20210203040506-2345".to_string(), "booga".to_string()],
+        );
+
+        assert_eq!(
+            result,
+            Err("There is no crash report with ID 'booga'".to_string())
+        );
+        assert_eq!(
+            subject.list(&data_directory).unwrap(),
+            vec![UiCrashReportSummary::from(&report)]
+        );
+    }
+}
@@ -2,12 +2,16 @@
 
 use crate::bootstrapper::RealUser;
 use crate::daemon::launcher::LauncherReal;
+use crate::daemon::restart_policy::RestartPolicy;
+use crate::daemon::ui_token::{create_ui_token, daemon_ui_token_path};
 use crate::daemon::{
     ChannelFactory, ChannelFactoryReal, Daemon, DaemonBindMessage, Launcher, Recipients,
 };
 use crate::node_configurator::node_configurator_initialization::InitializationConfig;
 use crate::node_configurator::{port_is_busy, DirsWrapper};
+use crate::privilege_drop::{PrivilegeDropper, PrivilegeDropperReal};
 use crate::server_initializer::LoggerInitializerWrapper;
+use crate::sub_lib::logger::LogFormat;
 use crate::sub_lib::main_tools::main_with_args;
 use crate::sub_lib::ui_gateway::UiGatewayConfig;
 use crate::ui_gateway::UiGateway;
@@ -16,23 +20,39 @@ use crossbeam_channel::{unbounded, Receiver, Sender};
 use flexi_logger::LevelFilter;
 use itertools::Itertools;
 use masq_lib::command::{Command, StdStreams};
+use masq_lib::ui_auth::UI_TOKEN_FILE_ENV_VAR;
 use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
 
 pub trait RecipientsFactory {
-    fn make(&self, launcher: Box<dyn Launcher>, ui_port: u16) -> Recipients;
+    fn make(
+        &self,
+        launcher: Box<dyn Launcher>,
+        ui_port: u16,
+        restart_policy: RestartPolicy,
+        ui_token_opt: Option<String>,
+    ) -> Recipients;
 }
 
 #[derive(Default)]
 pub struct RecipientsFactoryReal {}
 
 impl RecipientsFactory for RecipientsFactoryReal {
-    fn make(&self, launcher: Box<dyn Launcher>, ui_port: u16) -> Recipients {
+    fn make(
+        &self,
+        launcher: Box<dyn Launcher>,
+        ui_port: u16,
+        restart_policy: RestartPolicy,
+        ui_token_opt: Option<String>,
+    ) -> Recipients {
         let ui_gateway_addr = UiGateway::new(&UiGatewayConfig {
             ui_port,
+            ui_token_opt,
             node_descriptor: "".to_string(), // irrelevant; field should be removed
         })
         .start();
-        let daemon_addr = Daemon::new(launcher).start();
+        let daemon_addr = Daemon::new(launcher, restart_policy).start();
         Recipients {
             ui_gateway_from_sub: ui_gateway_addr.clone().recipient(),
             ui_gateway_to_sub: ui_gateway_addr.clone().recipient(),
@@ -63,8 +83,11 @@ impl ChannelFactory for ChannelFactoryReal {
 
 pub struct DaemonInitializer {
     config: InitializationConfig,
+    real_user: RealUser,
+    ui_token_path: PathBuf,
     channel_factory: Box<dyn ChannelFactory>,
     recipients_factory: Box<dyn RecipientsFactory>,
+    privilege_dropper: Box<dyn PrivilegeDropper>,
     rerunner: Box<dyn Rerunner>,
 }
 
@@ -74,10 +97,27 @@ impl Command for DaemonInitializer {
             writeln! (streams.stderr, "There appears to be a process already listening on port {}; are you sure there's not a Daemon already running?", self.config.ui_port).unwrap();
             return 1;
         }
+        let ui_token_opt = match self.make_ui_token_opt() {
+            Ok(ui_token_opt) => ui_token_opt,
+            Err(e) => {
+                writeln!(
+                    streams.stderr,
+                    "Couldn't create UI token file {}: {}",
+                    self.ui_token_path.display(),
+                    e
+                )
+                .unwrap();
+                return 1;
+            }
+        };
+        if ui_token_opt.is_some() {
+            // Nodes started by this Daemon, whether rerun in this process or spawned, inherit this
+            std::env::set_var(UI_TOKEN_FILE_ENV_VAR, &self.ui_token_path);
+        }
         let system = System::new("daemon");
         let (sender, receiver) = self.channel_factory.make();
 
-        self.bind(sender);
+        self.bind(sender, ui_token_opt);
 
         self.split(system, receiver);
         0
@@ -115,28 +155,53 @@ impl DaemonInitializer {
         recipients_factory: Box<dyn RecipientsFactory>,
         rerunner: Box<dyn Rerunner>,
     ) -> DaemonInitializer {
+        let real_user = RealUser::new(None, None, None).populate(dirs_wrapper);
         logger_initializer_wrapper.init(
             dirs_wrapper
                 .data_dir()
                 .expect("No data directory")
                 .join("MASQ"),
-            &RealUser::new(None, None, None).populate(dirs_wrapper),
+            &real_user,
             LevelFilter::Trace,
+            LogFormat::Text,
             Some("daemon"),
         );
+        let ui_token_path = daemon_ui_token_path(dirs_wrapper, &real_user);
         DaemonInitializer {
             config,
+            real_user,
+            ui_token_path,
             channel_factory,
             recipients_factory,
+            privilege_dropper: Box::new(PrivilegeDropperReal::new()),
             rerunner,
         }
     }
 
-    fn bind(&mut self, sender: Sender<HashMap<String, String>>) -> u8 {
+    fn make_ui_token_opt(&self) -> io::Result<Option<String>> {
+        if !self.config.ui_auth {
+            return Ok(None);
+        }
+        create_ui_token(
+            &self.ui_token_path,
+            &self.real_user,
+            self.privilege_dropper.as_ref(),
+        )
+        .map(Some)
+    }
+
+    fn bind(
+        &mut self,
+        sender: Sender<HashMap<String, String>>,
+        ui_token_opt: Option<String>,
+    ) -> u8 {
         let launcher = LauncherReal::new(sender);
-        let recipients = self
-            .recipients_factory
-            .make(Box::new(launcher), self.config.ui_port);
+        let recipients = self.recipients_factory.make(
+            Box::new(launcher),
+            self.config.ui_port,
+            self.config.restart_policy.clone(),
+            ui_token_opt,
+        );
         let bind_message = DaemonBindMessage {
             to_ui_message_recipient: recipients.ui_gateway_to_sub,
             from_ui_message_recipient: recipients.ui_gateway_from_sub,
@@ -165,29 +230,45 @@ impl DaemonInitializer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::daemon::restart_policy::RestartMode;
     use crate::daemon::{ChannelFactory, Recipients};
     use crate::node_configurator::node_configurator_initialization::InitializationConfig;
     use crate::node_test_utils::MockDirsWrapper;
-    use crate::server_initializer::test_utils::LoggerInitializerWrapperMock;
+    use crate::server_initializer::test_utils::{
+        LoggerInitializerWrapperMock, PrivilegeDropperMock,
+    };
     use crate::test_utils::recorder::{make_recorder, Recorder};
     use actix::System;
     use crossbeam_channel::unbounded;
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
     use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+    use masq_lib::ui_auth::read_ui_token;
     use masq_lib::utils::{find_free_port, localhost};
     use std::cell::RefCell;
     use std::iter::FromIterator;
     use std::net::{SocketAddr, TcpListener};
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     struct RecipientsFactoryMock {
-        make_params: Arc<Mutex<Vec<(Box<dyn Launcher>, u16)>>>,
+        make_params: Arc<Mutex<Vec<(Box<dyn Launcher>, u16, RestartPolicy, Option<String>)>>>,
         make_results: RefCell<Vec<Recipients>>,
     }
 
     impl RecipientsFactory for RecipientsFactoryMock {
-        fn make(&self, launcher: Box<dyn Launcher>, ui_port: u16) -> Recipients {
-            self.make_params.lock().unwrap().push((launcher, ui_port));
+        fn make(
+            &self,
+            launcher: Box<dyn Launcher>,
+            ui_port: u16,
+            restart_policy: RestartPolicy,
+            ui_token_opt: Option<String>,
+        ) -> Recipients {
+            self.make_params.lock().unwrap().push((
+                launcher,
+                ui_port,
+                restart_policy,
+                ui_token_opt,
+            ));
             self.make_results.borrow_mut().remove(0)
         }
     }
@@ -200,6 +281,14 @@ mod tests {
             }
         }
 
+        fn make_params(
+            mut self,
+            params: &Arc<Mutex<Vec<(Box<dyn Launcher>, u16, RestartPolicy, Option<String>)>>>,
+        ) -> Self {
+            self.make_params = params.clone();
+            self
+        }
+
         fn make_result(self, result: Recipients) -> Self {
             self.make_results.borrow_mut().push(result);
             self
@@ -281,9 +370,21 @@ mod tests {
             .data_dir_result(Some(data_dir));
         let logger_initializer_wrapper = LoggerInitializerWrapperMock::new();
         let port = find_free_port();
-        let config = InitializationConfig { ui_port: port };
+        let restart_policy = RestartPolicy {
+            mode: RestartMode::OnFailure,
+            max_restarts: 3,
+            window: Duration::from_secs(30),
+        };
+        let config = InitializationConfig {
+            ui_port: port,
+            restart_policy: restart_policy.clone(),
+            ui_auth: true,
+        };
         let channel_factory = ChannelFactoryMock::new();
-        let addr_factory = RecipientsFactoryMock::new().make_result(recipients);
+        let make_params_arc = Arc::new(Mutex::new(vec![]));
+        let addr_factory = RecipientsFactoryMock::new()
+            .make_params(&make_params_arc)
+            .make_result(recipients);
         let rerunner = RerunnerMock::new();
         let mut subject = DaemonInitializer::new(
             &dirs_wrapper,
@@ -294,7 +395,7 @@ mod tests {
             Box::new(rerunner),
         );
 
-        subject.bind(unbounded().0);
+        subject.bind(unbounded().0, Some("booga".to_string()));
 
         System::current().stop();
         system.run();
@@ -304,6 +405,70 @@ mod tests {
         let _ = daemon_recording.get_record::<DaemonBindMessage>(0);
         assert_eq!(ui_gateway_recording.len(), 1);
         assert_eq!(daemon_recording.len(), 1);
+        let make_params = make_params_arc.lock().unwrap();
+        assert_eq!(make_params[0].1, port);
+        assert_eq!(make_params[0].2, restart_policy);
+        assert_eq!(make_params[0].3, Some("booga".to_string()));
+    }
+
+    #[test]
+    fn make_ui_token_opt_creates_a_token_for_the_real_user_when_ui_auth_is_requested() {
+        let data_dir = ensure_node_home_directory_exists(
+            "daemon_initializer",
+            "make_ui_token_opt_creates_a_token_for_the_real_user_when_ui_auth_is_requested",
+        );
+        let dirs_wrapper = MockDirsWrapper::new()
+            .home_dir_result(Some(data_dir.clone()))
+            .data_dir_result(Some(data_dir.clone()));
+        let chown_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut subject = DaemonInitializer::new(
+            &dirs_wrapper,
+            Box::new(LoggerInitializerWrapperMock::new()),
+            InitializationConfig {
+                ui_auth: true,
+                ..InitializationConfig::default()
+            },
+            Box::new(ChannelFactoryMock::new()),
+            Box::new(RecipientsFactoryMock::new()),
+            Box::new(RerunnerMock::new()),
+        );
+        subject.privilege_dropper =
+            Box::new(PrivilegeDropperMock::new().chown_params(&chown_params_arc));
+
+        let result = subject.make_ui_token_opt().unwrap();
+
+        let expected_path = data_dir.join("MASQ").join("ui-token");
+        assert_eq!(subject.ui_token_path, expected_path);
+        assert_eq!(result, Some(read_ui_token(&expected_path).unwrap()));
+        let chown_params = chown_params_arc.lock().unwrap();
+        assert_eq!(
+            *chown_params,
+            vec![(expected_path, subject.real_user.clone())]
+        );
+    }
+
+    #[test]
+    fn make_ui_token_opt_creates_nothing_without_ui_auth() {
+        let data_dir = ensure_node_home_directory_exists(
+            "daemon_initializer",
+            "make_ui_token_opt_creates_nothing_without_ui_auth",
+        );
+        let dirs_wrapper = MockDirsWrapper::new()
+            .home_dir_result(Some(data_dir.clone()))
+            .data_dir_result(Some(data_dir.clone()));
+        let subject = DaemonInitializer::new(
+            &dirs_wrapper,
+            Box::new(LoggerInitializerWrapperMock::new()),
+            InitializationConfig::default(),
+            Box::new(ChannelFactoryMock::new()),
+            Box::new(RecipientsFactoryMock::new()),
+            Box::new(RerunnerMock::new()),
+        );
+
+        let result = subject.make_ui_token_opt().unwrap();
+
+        assert_eq!(result, None);
+        assert_eq!(data_dir.join("MASQ").join("ui-token").exists(), false);
     }
 
     #[test]
@@ -319,7 +484,10 @@ mod tests {
             .data_dir_result(Some(data_dir));
         let logger_initializer_wrapper = LoggerInitializerWrapperMock::new();
         let port = find_free_port();
-        let config = InitializationConfig { ui_port: port };
+        let config = InitializationConfig {
+            ui_port: port,
+            ..InitializationConfig::default()
+        };
         let (sender, receiver) = unbounded();
         let channel_factory = ChannelFactoryMock::new();
         let addr_factory = RecipientsFactoryMock::new();
@@ -370,7 +538,10 @@ mod tests {
         let mut subject = DaemonInitializer::new(
             &dirs_wrapper,
             Box::new(logger_initializer_wrapper),
-            InitializationConfig { ui_port: port },
+            InitializationConfig {
+                ui_port: port,
+                ..InitializationConfig::default()
+            },
             Box::new(ChannelFactoryMock::new()),
             Box::new(RecipientsFactoryMock::new()),
             Box::new(RerunnerMock::new()),
@@ -1,7 +1,10 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
+use crate::daemon::crash_report::{CrashReporter, NodePanic};
 use crate::daemon::launch_verifier::{LaunchVerification, LaunchVerifier, VerifierTools};
+use masq_lib::messages::{UiCrashReport, UiCrashReportSummary};
 use std::cell::RefCell;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 pub struct LaunchVerifierMock {
@@ -119,3 +122,153 @@ impl VerifierToolsMock {
         self
     }
 }
+
+pub struct CrashReporterMock {
+    take_node_panic_params: Arc<Mutex<Vec<(PathBuf, u32)>>>,
+    take_node_panic_results: RefCell<Vec<Option<NodePanic>>>,
+    take_node_shutdown_params: Arc<Mutex<Vec<(PathBuf, u32)>>>,
+    take_node_shutdown_results: RefCell<Vec<bool>>,
+    record_params: Arc<Mutex<Vec<(PathBuf, UiCrashReport)>>>,
+    record_results: RefCell<Vec<Result<(), String>>>,
+    list_params: Arc<Mutex<Vec<PathBuf>>>,
+    list_results: RefCell<Vec<Result<Vec<UiCrashReportSummary>, String>>>,
+    get_params: Arc<Mutex<Vec<(PathBuf, String)>>>,
+    get_results: RefCell<Vec<Result<UiCrashReport, String>>>,
+    delete_params: Arc<Mutex<Vec<(PathBuf, Vec<String>)>>>,
+    delete_results: RefCell<Vec<Result<(), String>>>,
+}
+
+impl CrashReporter for CrashReporterMock {
+    fn take_node_panic(&self, data_directory: &Path, process_id: u32) -> Option<NodePanic> {
+        self.take_node_panic_params
+            .lock()
+            .unwrap()
+            .push((data_directory.to_path_buf(), process_id));
+        self.take_node_panic_results.borrow_mut().remove(0)
+    }
+
+    // Most tests aren't about shutdowns, so with no result prepared, the Node wasn't shut down.
+    fn take_node_shutdown(&self, data_directory: &Path, process_id: u32) -> bool {
+        self.take_node_shutdown_params
+            .lock()
+            .unwrap()
+            .push((data_directory.to_path_buf(), process_id));
+        let mut results = self.take_node_shutdown_results.borrow_mut();
+        if results.is_empty() {
+            false
+        } else {
+            results.remove(0)
+        }
+    }
+
+    fn record(&self, data_directory: &Path, report: &UiCrashReport) -> Result<(), String> {
+        self.record_params
+            .lock()
+            .unwrap()
+            .push((data_directory.to_path_buf(), report.clone()));
+        self.record_results.borrow_mut().remove(0)
+    }
+
+    fn list(&self, data_directory: &Path) -> Result<Vec<UiCrashReportSummary>, String> {
+        self.list_params
+            .lock()
+            .unwrap()
+            .push(data_directory.to_path_buf());
+        self.list_results.borrow_mut().remove(0)
+    }
+
+    fn get(&self, data_directory: &Path, id: &str) -> Result<UiCrashReport, String> {
+        self.get_params
+            .lock()
+            .unwrap()
+            .push((data_directory.to_path_buf(), id.to_string()));
+        self.get_results.borrow_mut().remove(0)
+    }
+
+    fn delete(&self, data_directory: &Path, ids: &[String]) -> Result<(), String> {
+        self.delete_params
+            .lock()
+            .unwrap()
+            .push((data_directory.to_path_buf(), ids.to_vec()));
+        self.delete_results.borrow_mut().remove(0)
+    }
+}
+
+impl CrashReporterMock {
+    pub fn new() -> Self {
+        CrashReporterMock {
+            take_node_panic_params: Arc::new(Mutex::new(vec![])),
+            take_node_panic_results: RefCell::new(vec![]),
+            take_node_shutdown_params: Arc::new(Mutex::new(vec![])),
+            take_node_shutdown_results: RefCell::new(vec![]),
+            record_params: Arc::new(Mutex::new(vec![])),
+            record_results: RefCell::new(vec![]),
+            list_params: Arc::new(Mutex::new(vec![])),
+            list_results: RefCell::new(vec![]),
+            get_params: Arc::new(Mutex::new(vec![])),
+            get_results: RefCell::new(vec![]),
+            delete_params: Arc::new(Mutex::new(vec![])),
+            delete_results: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn take_node_panic_params(mut self, params: &Arc<Mutex<Vec<(PathBuf, u32)>>>) -> Self {
+        self.take_node_panic_params = params.clone();
+        self
+    }
+
+    pub fn take_node_panic_result(self, result: Option<NodePanic>) -> Self {
+        self.take_node_panic_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn take_node_shutdown_params(mut self, params: &Arc<Mutex<Vec<(PathBuf, u32)>>>) -> Self {
+        self.take_node_shutdown_params = params.clone();
+        self
+    }
+
+    pub fn take_node_shutdown_result(self, result: bool) -> Self {
+        self.take_node_shutdown_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn record_params(mut self, params: &Arc<Mutex<Vec<(PathBuf, UiCrashReport)>>>) -> Self {
+        self.record_params = params.clone();
+        self
+    }
+
+    pub fn record_result(self, result: Result<(), String>) -> Self {
+        self.record_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn list_params(mut self, params: &Arc<Mutex<Vec<PathBuf>>>) -> Self {
+        self.list_params = params.clone();
+        self
+    }
+
+    pub fn list_result(self, result: Result<Vec<UiCrashReportSummary>, String>) -> Self {
+        self.list_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn get_params(mut self, params: &Arc<Mutex<Vec<(PathBuf, String)>>>) -> Self {
+        self.get_params = params.clone();
+        self
+    }
+
+    pub fn get_result(self, result: Result<UiCrashReport, String>) -> Self {
+        self.get_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn delete_params(mut self, params: &Arc<Mutex<Vec<(PathBuf, Vec<String>)>>>) -> Self {
+        self.delete_params = params.clone();
+        self
+    }
+
+    pub fn delete_result(self, result: Result<(), String>) -> Self {
+        self.delete_results.borrow_mut().push(result);
+        self
+    }
+}
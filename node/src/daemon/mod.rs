@@ -1,28 +1,37 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai). All rights reserved.
 
 pub mod crash_notification;
+pub mod crash_report;
 pub mod daemon_initializer;
 pub mod launch_verifier;
 mod launcher;
+pub mod restart_policy;
 mod setup_reporter;
+pub mod ui_token;
 
 #[cfg(test)]
 mod mocks;
 
 use crate::daemon::crash_notification::CrashNotification;
+use crate::daemon::crash_report::{crash_report_id, CrashReporter, CrashReporterReal};
 use crate::daemon::launch_verifier::{VerifierTools, VerifierToolsReal};
+use crate::daemon::restart_policy::{RestartPolicy, RestartTracker};
 use crate::daemon::setup_reporter::{SetupCluster, SetupReporter, SetupReporterReal};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
 use actix::Recipient;
-use actix::{Actor, Context, Handler, Message};
+use actix::{Actor, AsyncContext, Context, Handler, Message};
+use chrono::Local;
 use crossbeam_channel::{Receiver, Sender};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use masq_lib::messages::UiSetupResponseValueStatus::{Configured, Set};
 use masq_lib::messages::{
-    FromMessageBody, ToMessageBody, UiNodeCrashedBroadcast, UiRedirect, UiSetupBroadcast,
-    UiSetupRequest, UiSetupResponse, UiSetupResponseValue, UiStartOrder, UiStartResponse,
+    CrashReason, FromMessageBody, RestartOutcome, ToMessageBody, UiClientDisconnectedNotice,
+    UiCrashReport, UiCrashReportRequest, UiCrashReportResponse, UiCrashReportsRequest,
+    UiCrashReportsResponse, UiDeleteCrashReportsRequest, UiDeleteCrashReportsResponse,
+    UiNodeCrashedBroadcast, UiNodeRestartBroadcast, UiRedirect, UiSetupBroadcast, UiSetupRequest,
+    UiSetupResponse, UiSetupResponseValue, UiStartOrder, UiStartResponse, CRASH_REPORT_ERROR,
     NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR,
 };
 use masq_lib::shared_schema::ConfiguratorError;
@@ -30,6 +39,8 @@ use masq_lib::ui_gateway::MessagePath::{Conversation, FireAndForget};
 use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{MessageBody, MessageTarget, NodeFromUiMessage, NodeToUiMessage};
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 pub struct Recipients {
     ui_gateway_from_sub: Recipient<NodeFromUiMessage>,
@@ -91,6 +102,11 @@ pub struct DaemonBindMessage {
     pub crash_notification_recipient: Recipient<CrashNotification>, // the Daemon itself, for crash notifications
 }
 
+#[derive(Message, PartialEq, Clone, Debug)]
+pub struct RestartNode {
+    pub attempt: u32,
+}
+
 pub struct Daemon {
     launcher: Box<dyn Launcher>,
     params: SetupCluster,
@@ -100,6 +116,8 @@ pub struct Daemon {
     node_ui_port: Option<u16>,
     verifier_tools: Box<dyn VerifierTools>,
     setup_reporter: Box<dyn SetupReporter>,
+    restart_tracker: RestartTracker,
+    crash_reporter: Box<dyn CrashReporter>,
     logger: Logger,
 }
 
@@ -132,6 +150,15 @@ impl Handler<NodeFromUiMessage> for Daemon {
             self.handle_setup(client_id, context_id, setup_request);
         } else if let Ok((_, context_id)) = UiStartOrder::fmb(msg.body.clone()) {
             self.handle_start_order(client_id, context_id);
+        } else if let Ok((_, context_id)) = UiCrashReportsRequest::fmb(msg.body.clone()) {
+            self.handle_crash_reports_request(client_id, context_id);
+        } else if let Ok((request, context_id)) = UiCrashReportRequest::fmb(msg.body.clone()) {
+            self.handle_crash_report_request(client_id, context_id, request.id);
+        } else if let Ok((request, context_id)) = UiDeleteCrashReportsRequest::fmb(msg.body.clone())
+        {
+            self.handle_delete_crash_reports_request(client_id, context_id, request.ids);
+        } else if UiClientDisconnectedNotice::fmb(msg.body.clone()).is_ok() {
+            // The Daemon keeps nothing about its UIs that would need to be forgotten
         } else {
             self.handle_unexpected_message(client_id, msg.body);
         }
@@ -142,15 +169,32 @@ impl Handler<NodeFromUiMessage> for Daemon {
 impl Handler<CrashNotification> for Daemon {
     type Result = ();
 
-    fn handle(&mut self, msg: CrashNotification, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: CrashNotification, ctx: &mut Self::Context) -> Self::Result {
         debug!(&self.logger, "Handling CrashNotification");
-        self.handle_crash_notification(msg);
+        if self.handle_crash_notification(msg) {
+            self.schedule_restart(ctx);
+        }
         debug!(&self.logger, "CrashNotification handled");
     }
 }
 
+impl Handler<RestartNode> for Daemon {
+    type Result = ();
+
+    fn handle(&mut self, msg: RestartNode, ctx: &mut Self::Context) -> Self::Result {
+        debug!(
+            &self.logger,
+            "Handling RestartNode for attempt {}", msg.attempt
+        );
+        if self.handle_restart_node(msg) {
+            self.schedule_restart(ctx);
+        }
+        debug!(&self.logger, "RestartNode handled");
+    }
+}
+
 impl Daemon {
-    pub fn new(launcher: Box<dyn Launcher>) -> Daemon {
+    pub fn new(launcher: Box<dyn Launcher>, restart_policy: RestartPolicy) -> Daemon {
         Daemon {
             launcher,
             params: HashMap::new(),
@@ -160,6 +204,8 @@ impl Daemon {
             node_ui_port: None,
             verifier_tools: Box::new(VerifierToolsReal::new()),
             setup_reporter: Box::new(SetupReporterReal::new()),
+            restart_tracker: RestartTracker::new(restart_policy),
+            crash_reporter: Box::new(CrashReporterReal::new()),
             logger: Logger::new("Daemon"),
         }
     }
@@ -204,26 +250,15 @@ impl Daemon {
                     )),
                 },
             ),
-            None => match self.launcher.launch(
-                self.params
-                    .iter()
-                    .filter(|(_, v)| v.status == Set || v.status == Configured)
-                    .map(|(k, v)| (k.to_string(), v.value.to_string()))
-                    .collect(),
-                self.crash_notification_sub.clone().expect("Daemon unbound"),
-            ) {
-                Ok(Some(success)) => {
-                    self.node_process_id = Some(success.new_process_id);
-                    self.node_ui_port = Some(success.redirect_ui_port);
-                    self.respond_to_ui(
-                        client_id,
-                        UiStartResponse {
-                            new_process_id: success.new_process_id,
-                            redirect_ui_port: success.redirect_ui_port,
-                        }
-                        .tmb(context_id),
-                    )
-                }
+            None => match self.launch() {
+                Ok(Some(success)) => self.respond_to_ui(
+                    client_id,
+                    UiStartResponse {
+                        new_process_id: success.new_process_id,
+                        redirect_ui_port: success.redirect_ui_port,
+                    }
+                    .tmb(context_id),
+                ),
                 Ok(None) => (),
                 Err(s) => self.respond_to_ui(
                     client_id,
@@ -268,19 +303,238 @@ impl Daemon {
         }
     }
 
-    fn handle_crash_notification(&mut self, msg: CrashNotification) {
+    fn handle_crash_notification(&mut self, msg: CrashNotification) -> bool {
         if self.node_ui_port.is_some() || self.node_process_id.is_some() {
             self.node_process_id = None;
             self.node_ui_port = None;
+            let shut_down_on_request = self.take_node_shutdown(&msg);
+            let crash_reason = msg.analyze();
+            self.record_crash(&msg, crash_reason.clone());
             self.send_ui_message(
                 UiNodeCrashedBroadcast {
                     process_id: msg.process_id,
-                    crash_reason: msg.analyze(),
+                    crash_reason,
                 }
                 .tmb(0),
                 MessageTarget::AllClients,
             );
+            if shut_down_on_request {
+                info!(
+                    &self.logger,
+                    "Node process {} was shut down on request; not restarting it", msg.process_id
+                );
+                false
+            } else {
+                self.restart_tracker.policy().wants_restart(msg.exit_code)
+            }
+        } else {
+            false
+        }
+    }
+
+    fn take_node_shutdown(&self, msg: &CrashNotification) -> bool {
+        match self.params.get("data-directory") {
+            Some(value) => self
+                .crash_reporter
+                .take_node_shutdown(&PathBuf::from(&value.value), msg.process_id),
+            None => false,
+        }
+    }
+
+    fn record_crash(&self, msg: &CrashNotification, crash_reason: CrashReason) {
+        let data_directory = match self.params.get("data-directory") {
+            Some(value) => PathBuf::from(&value.value),
+            None => {
+                warning!(
+                    &self.logger,
+                    "No data directory is known for the Node; crash of process {} will not be recorded",
+                    msg.process_id
+                );
+                return;
+            }
+        };
+        let node_panic_opt = self
+            .crash_reporter
+            .take_node_panic(&data_directory, msg.process_id);
+        let time = Local::now();
+        let report = UiCrashReport {
+            id: crash_report_id(&time, msg.process_id),
+            time: time.to_rfc3339(),
+            process_id: msg.process_id,
+            exit_code: msg.exit_code,
+            crash_reason,
+            panic_message: node_panic_opt.as_ref().map(|np| np.message.clone()),
+            panic_location: node_panic_opt.as_ref().map(|np| np.location.clone()),
+            backtrace: node_panic_opt.map(|np| np.backtrace),
+            setup: self
+                .censored_params()
+                .into_iter()
+                .sorted_by(|a, b| Ord::cmp(&a.0, &b.0))
+                .map(|(_, v)| v)
+                .collect(),
+        };
+        match self.crash_reporter.record(&data_directory, &report) {
+            Ok(()) => info!(&self.logger, "Recorded crash report {}", report.id),
+            Err(e) => warning!(&self.logger, "Could not record crash report: {}", e),
+        }
+    }
+
+    fn handle_crash_reports_request(&mut self, client_id: u64, context_id: u64) {
+        let body = match self
+            .crash_report_directory()
+            .and_then(|directory| self.crash_reporter.list(&directory))
+        {
+            Ok(reports) => UiCrashReportsResponse { reports }.tmb(context_id),
+            Err(e) => Self::crash_report_error("crashReports", context_id, e),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn handle_crash_report_request(&mut self, client_id: u64, context_id: u64, id: String) {
+        let body = match self
+            .crash_report_directory()
+            .and_then(|directory| self.crash_reporter.get(&directory, &id))
+        {
+            Ok(report) => UiCrashReportResponse { report }.tmb(context_id),
+            Err(e) => Self::crash_report_error("crashReport", context_id, e),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn handle_delete_crash_reports_request(
+        &mut self,
+        client_id: u64,
+        context_id: u64,
+        ids: Vec<String>,
+    ) {
+        let body = match self
+            .crash_report_directory()
+            .and_then(|directory| self.crash_reporter.delete(&directory, &ids))
+        {
+            Ok(()) => UiDeleteCrashReportsResponse { deleted: ids }.tmb(context_id),
+            Err(e) => Self::crash_report_error("deleteCrashReports", context_id, e),
+        };
+        self.respond_to_ui(client_id, body);
+    }
+
+    fn crash_report_directory(&self) -> Result<PathBuf, String> {
+        let setup = if self.params.is_empty() {
+            match self
+                .setup_reporter
+                .get_modified_setup(HashMap::new(), vec![])
+            {
+                Ok(setup) => setup,
+                Err((lame_setup, _)) => lame_setup,
+            }
+        } else {
+            self.params.clone()
+        };
+        match setup.get("data-directory") {
+            Some(value) => Ok(PathBuf::from(&value.value)),
+            None => Err("Cannot find crash reports: the data directory is unknown".to_string()),
+        }
+    }
+
+    fn crash_report_error(opcode: &str, context_id: u64, message: String) -> MessageBody {
+        MessageBody {
+            opcode: opcode.to_string(),
+            path: Conversation(context_id),
+            payload: Err((CRASH_REPORT_ERROR, message)),
+        }
+    }
+
+    fn handle_restart_node(&mut self, msg: RestartNode) -> bool {
+        if self.port_if_node_is_running().is_some() {
+            info!(
+                &self.logger,
+                "Node is already running; abandoning restart attempt {}", msg.attempt
+            );
+            return false;
+        }
+        let (outcome, try_again) = match self.launch() {
+            Ok(Some(success)) => {
+                info!(
+                    &self.logger,
+                    "Restarted Node as process {} on attempt {}",
+                    success.new_process_id,
+                    msg.attempt
+                );
+                (RestartOutcome::Restarted(success.new_process_id), false)
+            }
+            Ok(None) => return false,
+            Err(s) => {
+                warning!(
+                    &self.logger,
+                    "Restart attempt {} failed: {}",
+                    msg.attempt,
+                    s
+                );
+                (RestartOutcome::Failed(s), true)
+            }
+        };
+        self.send_ui_message(
+            UiNodeRestartBroadcast {
+                attempt: msg.attempt,
+                outcome,
+            }
+            .tmb(0),
+            MessageTarget::AllClients,
+        );
+        try_again
+    }
+
+    fn schedule_restart(&mut self, ctx: &mut Context<Daemon>) {
+        if let Some((attempt, delay)) = self.next_restart() {
+            ctx.notify_later(RestartNode { attempt }, delay);
+        }
+    }
+
+    fn next_restart(&mut self) -> Option<(u32, Duration)> {
+        match self.restart_tracker.next_attempt(Instant::now()) {
+            Some((attempt, delay)) => {
+                info!(
+                    &self.logger,
+                    "Will try to restart Node in {}ms (attempt {})",
+                    delay.as_millis(),
+                    attempt
+                );
+                Some((attempt, delay))
+            }
+            None => {
+                let attempts = self.restart_tracker.attempts_in_window();
+                warning!(
+                    &self.logger,
+                    "Node has been restarted {} times within {}s; giving up",
+                    attempts,
+                    self.restart_tracker.policy().window.as_secs()
+                );
+                self.send_ui_message(
+                    UiNodeRestartBroadcast {
+                        attempt: attempts + 1,
+                        outcome: RestartOutcome::Abandoned,
+                    }
+                    .tmb(0),
+                    MessageTarget::AllClients,
+                );
+                None
+            }
+        }
+    }
+
+    fn launch(&mut self) -> Result<Option<LaunchSuccess>, String> {
+        let result = self.launcher.launch(
+            self.params
+                .iter()
+                .filter(|(_, v)| v.status == Set || v.status == Configured)
+                .map(|(k, v)| (k.to_string(), v.value.to_string()))
+                .collect(),
+            self.crash_notification_sub.clone().expect("Daemon unbound"),
+        );
+        if let Ok(Some(success)) = &result {
+            self.node_process_id = Some(success.new_process_id);
+            self.node_ui_port = Some(success.redirect_ui_port);
         }
+        result
     }
 
     fn port_if_node_is_running(&mut self) -> Option<u16> {
@@ -424,17 +678,20 @@ impl Daemon {
 mod tests {
     use super::*;
     use crate::daemon::crash_notification::CrashNotification;
-    use crate::daemon::mocks::VerifierToolsMock;
+    use crate::daemon::crash_report::NodePanic;
+    use crate::daemon::mocks::{CrashReporterMock, VerifierToolsMock};
+    use crate::daemon::restart_policy::RestartMode;
     use crate::daemon::setup_reporter::{setup_cluster_from, SetupCluster};
     use crate::daemon::LaunchSuccess;
+    use crate::test_utils::logging::{init_test_logging, TestLogHandler};
     use crate::test_utils::recorder::{make_recorder, Recorder};
     use actix::System;
     use masq_lib::messages::UiSetupResponseValueStatus::{Blank, Required, Set};
     use masq_lib::messages::{
-        CrashReason, UiFinancialsRequest, UiNodeCrashedBroadcast, UiRedirect, UiSetupBroadcast,
-        UiSetupRequest, UiSetupRequestValue, UiSetupResponse, UiSetupResponseValue,
-        UiSetupResponseValueStatus, UiShutdownRequest, UiStartOrder, UiStartResponse,
-        NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR,
+        CrashReason, UiCrashReportSummary, UiFinancialsRequest, UiNodeCrashedBroadcast, UiRedirect,
+        UiSetupBroadcast, UiSetupRequest, UiSetupRequestValue, UiSetupResponse,
+        UiSetupResponseValue, UiSetupResponseValueStatus, UiShutdownRequest, UiStartOrder,
+        UiStartResponse, NODE_ALREADY_RUNNING_ERROR, NODE_LAUNCH_ERROR, NODE_NOT_RUNNING_ERROR,
     };
     use masq_lib::shared_schema::ConfiguratorError;
     use masq_lib::test_utils::environment_guard::{ClapGuard, EnvironmentGuard};
@@ -560,7 +817,7 @@ mod tests {
 
     #[test]
     fn censorship_works() {
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.params = make_setup_cluster(vec![
             ("one-non-censorable", "one value", Set),
             ("db-password", "super-secret value", Configured),
@@ -591,7 +848,7 @@ mod tests {
         let verifier_tools = VerifierToolsMock::new().process_is_running_result(true);
         let setup_reporter = SetupReporterMock::new(); // will panic if called
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.verifier_tools = Box::new(verifier_tools);
         subject.setup_reporter = Box::new(setup_reporter);
         subject.params = make_setup_cluster(vec![
@@ -656,7 +913,7 @@ mod tests {
         ]);
         let setup_reporter = SetupReporterMock::new().get_modified_setup_result(Ok(combined_setup));
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.verifier_tools = Box::new(verifier_tools);
         subject.setup_reporter = Box::new(setup_reporter);
         subject.params = make_setup_cluster(vec![
@@ -725,7 +982,7 @@ mod tests {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
         let system = System::new("test");
         let verifier_tools = VerifierToolsMock::new();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.node_ui_port = None;
         subject.node_process_id = None;
         subject.verifier_tools = Box::new(verifier_tools);
@@ -786,7 +1043,7 @@ mod tests {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
         let system = System::new("test");
         let verifier_tools = VerifierToolsMock::new().process_is_running_result(false); // only consulted once; second time, we already know
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.node_ui_port = Some(1234);
         subject.node_process_id = Some(4321);
         subject.verifier_tools = Box::new(verifier_tools);
@@ -854,7 +1111,7 @@ mod tests {
         )]
         .into_iter()
         .collect::<SetupCluster>();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.setup_reporter =
             Box::new(SetupReporterMock::new().get_modified_setup_result(Err((
                 lame_setup,
@@ -886,7 +1143,7 @@ mod tests {
     #[test]
     fn handle_setup_responds_but_does_not_broadcast_if_setup_changes_from_nothing() {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.params.clear(); // nothing
         let existing_setup = subject.params.clone();
         let modified_setup = {
@@ -931,7 +1188,7 @@ mod tests {
     #[test]
     fn handle_setup_responds_but_does_not_broadcast_if_setup_is_not_changed() {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.params.insert(
             "db-password".to_string(),
             UiSetupResponseValue::new("db-password", "secret value", Configured),
@@ -978,7 +1235,7 @@ mod tests {
     #[test]
     fn handle_setup_responds_and_broadcasts_if_setup_is_changed() {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.params.insert(
             "booga".to_string(),
             UiSetupResponseValue::new("booga", "agoob", Configured),
@@ -1051,7 +1308,7 @@ mod tests {
             })));
         let verifier_tools = VerifierToolsMock::new();
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(launcher));
+        let mut subject = Daemon::new(Box::new(launcher), RestartPolicy::default());
         subject.params.insert(
             "db-password".to_string(),
             UiSetupResponseValue::new("db-password", "goober", Set),
@@ -1106,7 +1363,7 @@ mod tests {
         let launcher = LauncherMock::new().launch_result(Ok(None));
         let verifier_tools = VerifierToolsMock::new();
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(launcher));
+        let mut subject = Daemon::new(Box::new(launcher), RestartPolicy::default());
         subject.params.insert(
             "db-password".to_string(),
             UiSetupResponseValue::new("db-password", "goober", Set),
@@ -1148,7 +1405,7 @@ mod tests {
             .process_is_running_result(false)
             .process_is_running_result(false);
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(launcher));
+        let mut subject = Daemon::new(Box::new(launcher), RestartPolicy::default());
         subject.params.insert(
             "db-password".to_string(),
             UiSetupResponseValue::new("db-password", "goober", Set),
@@ -1220,7 +1477,7 @@ mod tests {
         let launcher = LauncherMock::new().launch_result(Err("booga".to_string()));
         let verifier_tools = VerifierToolsMock::new();
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(launcher));
+        let mut subject = Daemon::new(Box::new(launcher), RestartPolicy::default());
         subject.params.insert(
             "db-password".to_string(),
             UiSetupResponseValue::new("db-password", "goober", Set),
@@ -1256,7 +1513,7 @@ mod tests {
         let launcher = LauncherMock::new().launch_result(Err("booga".to_string()));
         let verifier_tools = VerifierToolsMock::new().process_is_running_result(true);
         let system = System::new("test");
-        let mut subject = Daemon::new(Box::new(launcher));
+        let mut subject = Daemon::new(Box::new(launcher), RestartPolicy::default());
         subject.params.insert(
             "db-password".to_string(),
             UiSetupResponseValue::new("db-password", "goober", Set),
@@ -1306,7 +1563,7 @@ mod tests {
                 redirect_ui_port: 7777,
             })));
         let verifier_tools = VerifierToolsMock::new();
-        let mut subject = Daemon::new(Box::new(launcher));
+        let mut subject = Daemon::new(Box::new(launcher), RestartPolicy::default());
         subject.ui_gateway_sub = Some(gateway_recipient.clone());
         subject.crash_notification_sub = Some(crash_notification_recipient);
         subject.verifier_tools = Box::new(verifier_tools);
@@ -1364,7 +1621,7 @@ mod tests {
         let verifier_tools = VerifierToolsMock::new()
             .process_is_running_params(&process_is_running_params_arc)
             .process_is_running_result(true);
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.node_ui_port = Some(7777);
         subject.node_process_id = Some(8888);
         subject.verifier_tools = Box::new(verifier_tools);
@@ -1412,7 +1669,7 @@ mod tests {
         let verifier_tools = VerifierToolsMock::new()
             .process_is_running_params(&process_is_running_params_arc)
             .process_is_running_result(false); // only consulted once; second time, we already know
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.node_ui_port = Some(7777);
         subject.node_process_id = Some(8888);
         subject.verifier_tools = Box::new(verifier_tools);
@@ -1445,7 +1702,7 @@ mod tests {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
         let system = System::new("test");
         let verifier_tools = VerifierToolsMock::new();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.node_ui_port = None;
         subject.node_process_id = None;
         subject.verifier_tools = Box::new(verifier_tools);
@@ -1484,12 +1741,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn client_disconnected_notice_is_not_redirected_to_the_node() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let verifier_tools = VerifierToolsMock::new();
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
+        subject.node_ui_port = Some(7777);
+        subject.node_process_id = Some(8888);
+        subject.verifier_tools = Box::new(verifier_tools);
+        let subject_addr = subject.start();
+        subject_addr
+            .try_send(make_bind_message(ui_gateway))
+            .unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiClientDisconnectedNotice {}.tmb(0),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 0);
+    }
+
     #[test]
     fn accepts_crash_notification_when_not_in_setup_mode_and_sends_ui_notification() {
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
         let system = System::new("test");
         let verifier_tools = VerifierToolsMock::new();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.node_ui_port = Some(1234);
         subject.node_process_id = Some(12345);
         subject.verifier_tools = Box::new(verifier_tools);
@@ -1550,7 +1834,7 @@ mod tests {
         let system = System::new("test");
         let ui_gateway_sub = ui_gateway.start().recipient();
         let verifier_tools = VerifierToolsMock::new();
-        let mut subject = Daemon::new(Box::new(LauncherMock::new()));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
         subject.ui_gateway_sub = Some(ui_gateway_sub);
         subject.verifier_tools = Box::new(verifier_tools);
         subject.node_ui_port = None;
@@ -1567,4 +1851,508 @@ mod tests {
         let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
         assert_eq!(ui_gateway_recording.len(), 0);
     }
+
+    fn make_restartable_subject(
+        launcher: LauncherMock,
+        mode: RestartMode,
+        ui_gateway: Recorder,
+    ) -> Daemon {
+        let (crash_notification_target, _, _) = make_recorder();
+        let mut subject = Daemon::new(
+            Box::new(launcher),
+            RestartPolicy {
+                mode,
+                max_restarts: 2,
+                window: Duration::from_secs(60),
+            },
+        );
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.crash_notification_sub = Some(crash_notification_target.start().recipient());
+        subject
+    }
+
+    #[test]
+    fn crash_notification_calls_for_restart_only_as_the_policy_dictates() {
+        let system = System::new("test");
+        let crash = |exit_code| CrashNotification {
+            process_id: 54321,
+            exit_code,
+            stderr: None,
+        };
+        let check = |mode, exit_code| {
+            let (ui_gateway, _, _) = make_recorder();
+            let mut subject = make_restartable_subject(LauncherMock::new(), mode, ui_gateway);
+            subject.node_ui_port = Some(1234);
+            subject.node_process_id = Some(54321);
+            subject.handle_crash_notification(crash(exit_code))
+        };
+
+        let results = vec![
+            check(RestartMode::Never, Some(1)),
+            check(RestartMode::Always, Some(0)),
+            check(RestartMode::Always, None),
+            check(RestartMode::OnFailure, Some(0)),
+            check(RestartMode::OnFailure, Some(1)),
+            check(RestartMode::OnFailure, None),
+        ];
+
+        System::current().stop();
+        system.run();
+        assert_eq!(results, vec![false, true, true, false, true, true]);
+    }
+
+    #[test]
+    fn crash_notification_does_not_call_for_restart_after_a_requested_shutdown() {
+        let (ui_gateway, _, _) = make_recorder();
+        let system = System::new("test");
+        let take_node_shutdown_params_arc = Arc::new(Mutex::new(vec![]));
+        let crash_reporter = CrashReporterMock::new()
+            .take_node_shutdown_params(&take_node_shutdown_params_arc)
+            .take_node_shutdown_result(true)
+            .take_node_panic_result(None)
+            .record_result(Ok(()));
+        let mut subject = make_subject_with_data_directory(crash_reporter);
+        subject.restart_tracker = RestartTracker::new(RestartPolicy {
+            mode: RestartMode::Always,
+            max_restarts: 2,
+            window: Duration::from_secs(60),
+        });
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(54321);
+
+        let result = subject.handle_crash_notification(CrashNotification {
+            process_id: 54321,
+            exit_code: Some(0),
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, false);
+        let take_node_shutdown_params = take_node_shutdown_params_arc.lock().unwrap();
+        assert_eq!(
+            *take_node_shutdown_params,
+            vec![(PathBuf::from("/home/booga/data"), 54321)]
+        );
+    }
+
+    #[test]
+    fn crash_notification_in_setup_mode_does_not_call_for_restart() {
+        let (ui_gateway, _, _) = make_recorder();
+        let system = System::new("test");
+        let mut subject =
+            make_restartable_subject(LauncherMock::new(), RestartMode::Always, ui_gateway);
+
+        let result = subject.handle_crash_notification(CrashNotification {
+            process_id: 54321,
+            exit_code: Some(1),
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, false);
+    }
+
+    #[test]
+    fn restart_node_relaunches_with_last_setup_and_broadcasts_success() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let launch_params_arc = Arc::new(Mutex::new(vec![]));
+        let launcher = LauncherMock::new()
+            .launch_params(&launch_params_arc)
+            .launch_result(Ok(Some(LaunchSuccess {
+                new_process_id: 2345,
+                redirect_ui_port: 5432,
+            })));
+        let mut subject = make_restartable_subject(launcher, RestartMode::Always, ui_gateway);
+        subject.params.insert(
+            "db-password".to_string(),
+            UiSetupResponseValue::new("db-password", "goober", Set),
+        );
+        subject.params.insert(
+            "ip".to_string(),
+            UiSetupResponseValue::new("ip", "1.2.3.4", Blank),
+        );
+
+        let result = subject.handle_restart_node(RestartNode { attempt: 1 });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, false);
+        assert_eq!(subject.node_process_id, Some(2345));
+        assert_eq!(subject.node_ui_port, Some(5432));
+        let launch_params = launch_params_arc.lock().unwrap();
+        assert_eq!(
+            launch_params[0].0,
+            HashMap::from_iter(vec![("db-password".to_string(), "goober".to_string())])
+        );
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(record.target, MessageTarget::AllClients);
+        assert_eq!(
+            record.body,
+            UiNodeRestartBroadcast {
+                attempt: 1,
+                outcome: RestartOutcome::Restarted(2345),
+            }
+            .tmb(0)
+        );
+    }
+
+    #[test]
+    fn restart_node_broadcasts_failure_and_calls_for_another_attempt() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let launcher = LauncherMock::new().launch_result(Err("booga".to_string()));
+        let mut subject = make_restartable_subject(launcher, RestartMode::Always, ui_gateway);
+
+        let result = subject.handle_restart_node(RestartNode { attempt: 2 });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, true);
+        assert_eq!(subject.node_process_id, None);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(
+            record.body,
+            UiNodeRestartBroadcast {
+                attempt: 2,
+                outcome: RestartOutcome::Failed("booga".to_string()),
+            }
+            .tmb(0)
+        );
+    }
+
+    #[test]
+    fn restart_node_stands_down_if_node_has_already_been_started() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject =
+            make_restartable_subject(LauncherMock::new(), RestartMode::Always, ui_gateway);
+        subject.node_ui_port = Some(5432);
+        subject.node_process_id = Some(2345);
+        subject.verifier_tools = Box::new(VerifierToolsMock::new().process_is_running_result(true));
+
+        let result = subject.handle_restart_node(RestartNode { attempt: 1 });
+
+        System::current().stop();
+        system.run();
+        assert_eq!(result, false);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 0);
+    }
+
+    #[test]
+    fn next_restart_broadcasts_abandonment_when_the_limit_is_reached() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let mut subject =
+            make_restartable_subject(LauncherMock::new(), RestartMode::Always, ui_gateway);
+
+        let first = subject.next_restart();
+        let second = subject.next_restart();
+        let third = subject.next_restart();
+
+        System::current().stop();
+        system.run();
+        assert_eq!(first, Some((1, Duration::from_secs(1))));
+        assert_eq!(second, Some((2, Duration::from_secs(2))));
+        assert_eq!(third, None);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 1);
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(record.target, MessageTarget::AllClients);
+        assert_eq!(
+            record.body,
+            UiNodeRestartBroadcast {
+                attempt: 3,
+                outcome: RestartOutcome::Abandoned,
+            }
+            .tmb(0)
+        );
+    }
+
+    fn make_crash_report(id: &str) -> UiCrashReport {
+        UiCrashReport {
+            id: id.to_string(),
+            time: "2021-02-03T04:05:06+00:00".to_string(),
+            process_id: 2345,
+            exit_code: Some(101),
+            crash_reason: CrashReason::NoInformation,
+            panic_message: Some("Intentional crash".to_string()),
+            panic_location: Some("src/dispatcher.rs:42:13".to_string()),
+            backtrace: Some("   0: backtrace::backtrace".to_string()),
+            setup: vec![],
+        }
+    }
+
+    fn make_subject_with_data_directory(crash_reporter: CrashReporterMock) -> Daemon {
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
+        subject.params.insert(
+            "data-directory".to_string(),
+            UiSetupResponseValue::new("data-directory", "/home/booga/data", Set),
+        );
+        subject.crash_reporter = Box::new(crash_reporter);
+        subject
+    }
+
+    #[test]
+    fn crash_notification_records_crash_report_with_node_panic_and_censored_setup() {
+        let (ui_gateway, _, _) = make_recorder();
+        let system = System::new("test");
+        let take_node_panic_params_arc = Arc::new(Mutex::new(vec![]));
+        let record_params_arc = Arc::new(Mutex::new(vec![]));
+        let crash_reporter = CrashReporterMock::new()
+            .take_node_panic_params(&take_node_panic_params_arc)
+            .take_node_panic_result(Some(NodePanic {
+                process_id: 54321,
+                message: "Intentional crash".to_string(),
+                location: "src/dispatcher.rs:42:13".to_string(),
+                backtrace: "   0: backtrace::backtrace".to_string(),
+            }))
+            .record_params(&record_params_arc)
+            .record_result(Ok(()));
+        let mut subject = make_subject_with_data_directory(crash_reporter);
+        subject.params.insert(
+            "db-password".to_string(),
+            UiSetupResponseValue::new("db-password", "goober", Set),
+        );
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(54321);
+
+        subject.handle_crash_notification(CrashNotification {
+            process_id: 54321,
+            exit_code: Some(101),
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        let take_node_panic_params = take_node_panic_params_arc.lock().unwrap();
+        assert_eq!(
+            *take_node_panic_params,
+            vec![(PathBuf::from("/home/booga/data"), 54321)]
+        );
+        let record_params = record_params_arc.lock().unwrap();
+        let (data_directory, report) = &record_params[0];
+        assert_eq!(data_directory, &PathBuf::from("/home/booga/data"));
+        assert_eq!(report.id.ends_with("-54321"), true, "{}", report.id);
+        assert_eq!(report.process_id, 54321);
+        assert_eq!(report.exit_code, Some(101));
+        assert_eq!(
+            report.crash_reason,
+            CrashReason::Unrecognized(String::new())
+        );
+        assert_eq!(report.panic_message, Some("Intentional crash".to_string()));
+        assert_eq!(
+            report.panic_location,
+            Some("src/dispatcher.rs:42:13".to_string())
+        );
+        assert_eq!(
+            report.backtrace,
+            Some("   0: backtrace::backtrace".to_string())
+        );
+        assert_eq!(
+            report.setup,
+            vec![
+                UiSetupResponseValue::new("data-directory", "/home/booga/data", Set),
+                UiSetupResponseValue::new("db-password", "****************", Set),
+            ]
+        );
+    }
+
+    #[test]
+    fn crash_notification_logs_failure_to_record_crash_report() {
+        init_test_logging();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let crash_reporter = CrashReporterMock::new()
+            .take_node_panic_result(None)
+            .record_result(Err("Disk full".to_string()));
+        let mut subject = make_subject_with_data_directory(crash_reporter);
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        subject.node_ui_port = Some(1234);
+        subject.node_process_id = Some(54321);
+
+        subject.handle_crash_notification(CrashNotification {
+            process_id: 54321,
+            exit_code: None,
+            stderr: None,
+        });
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(ui_gateway_recording.len(), 1);
+        TestLogHandler::new()
+            .exists_log_containing("WARN: Daemon: Could not record crash report: Disk full");
+    }
+
+    #[test]
+    fn accepts_crash_reports_request_and_lists_reports_from_data_directory() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let list_params_arc = Arc::new(Mutex::new(vec![]));
+        let summary = UiCrashReportSummary::from(&make_crash_report("20210203040506-2345"));
+        let crash_reporter = CrashReporterMock::new()
+            .list_params(&list_params_arc)
+            .list_result(Ok(vec![summary.clone()]));
+        let subject = make_subject_with_data_directory(crash_reporter);
+        let subject_addr = subject.start();
+        subject_addr
+            .try_send(make_bind_message(ui_gateway))
+            .unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiCrashReportsRequest {}.tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let list_params = list_params_arc.lock().unwrap();
+        assert_eq!(*list_params, vec![PathBuf::from("/home/booga/data")]);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(record.target, ClientId(1234));
+        assert_eq!(
+            record.body,
+            UiCrashReportsResponse {
+                reports: vec![summary]
+            }
+            .tmb(4321)
+        );
+    }
+
+    #[test]
+    fn crash_reports_request_uses_default_data_directory_before_setup() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let list_params_arc = Arc::new(Mutex::new(vec![]));
+        let crash_reporter = CrashReporterMock::new()
+            .list_params(&list_params_arc)
+            .list_result(Ok(vec![]));
+        let mut subject = Daemon::new(Box::new(LauncherMock::new()), RestartPolicy::default());
+        subject.setup_reporter = Box::new(SetupReporterMock::new().get_modified_setup_result(Ok(
+            HashMap::from_iter(vec![(
+                "data-directory".to_string(),
+                UiSetupResponseValue::new(
+                    "data-directory",
+                    "/default/data",
+                    UiSetupResponseValueStatus::Default,
+                ),
+            )]),
+        )));
+        subject.crash_reporter = Box::new(crash_reporter);
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+
+        subject.handle_crash_reports_request(1234, 4321);
+
+        System::current().stop();
+        system.run();
+        let list_params = list_params_arc.lock().unwrap();
+        assert_eq!(*list_params, vec![PathBuf::from("/default/data")]);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(
+            record.body,
+            UiCrashReportsResponse { reports: vec![] }.tmb(4321)
+        );
+    }
+
+    #[test]
+    fn crash_report_request_returns_requested_report() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let get_params_arc = Arc::new(Mutex::new(vec![]));
+        let report = make_crash_report("20210203040506-2345");
+        let crash_reporter = CrashReporterMock::new()
+            .get_params(&get_params_arc)
+            .get_result(Ok(report.clone()));
+        let mut subject = make_subject_with_data_directory(crash_reporter);
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+
+        subject.handle_crash_report_request(1234, 4321, "20210203040506-2345".to_string());
+
+        System::current().stop();
+        system.run();
+        let get_params = get_params_arc.lock().unwrap();
+        assert_eq!(
+            *get_params,
+            vec![(
+                PathBuf::from("/home/booga/data"),
+                "20210203040506-2345".to_string()
+            )]
+        );
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(record.target, ClientId(1234));
+        assert_eq!(record.body, UiCrashReportResponse { report }.tmb(4321));
+    }
+
+    #[test]
+    fn crash_report_request_reports_unknown_id() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let crash_reporter = CrashReporterMock::new()
+            .get_result(Err("There is no crash report with ID 'booga'".to_string()));
+        let mut subject = make_subject_with_data_directory(crash_reporter);
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+
+        subject.handle_crash_report_request(1234, 4321, "booga".to_string());
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(
+            record.body,
+            MessageBody {
+                opcode: "crashReport".to_string(),
+                path: Conversation(4321),
+                payload: Err((
+                    CRASH_REPORT_ERROR,
+                    "There is no crash report with ID 'booga'".to_string()
+                )),
+            }
+        );
+    }
+
+    #[test]
+    fn delete_crash_reports_request_deletes_reports() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("test");
+        let delete_params_arc = Arc::new(Mutex::new(vec![]));
+        let crash_reporter = CrashReporterMock::new()
+            .delete_params(&delete_params_arc)
+            .delete_result(Ok(()));
+        let mut subject = make_subject_with_data_directory(crash_reporter);
+        subject.ui_gateway_sub = Some(ui_gateway.start().recipient());
+        let ids = vec![
+            "20210102030405-1234".to_string(),
+            "20210203040506-2345".to_string(),
+        ];
+
+        subject.handle_delete_crash_reports_request(1234, 4321, ids.clone());
+
+        System::current().stop();
+        system.run();
+        let delete_params = delete_params_arc.lock().unwrap();
+        assert_eq!(
+            *delete_params,
+            vec![(PathBuf::from("/home/booga/data"), ids.clone())]
+        );
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let record = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(
+            record.body,
+            UiDeleteCrashReportsResponse { deleted: ids }.tmb(4321)
+        );
+    }
 }
@@ -0,0 +1,240 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_RESTART_LIMIT: u32 = 5;
+pub const DEFAULT_RESTART_WINDOW_SECS: u64 = 300;
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RestartMode {
+    Never,
+    Always,
+    OnFailure,
+}
+
+impl FromStr for RestartMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(RestartMode::Never),
+            "always" => Ok(RestartMode::Always),
+            "on-failure" => Ok(RestartMode::OnFailure),
+            other => Err(format!(
+                "Restart policy must be never, always, or on-failure, not '{}'",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    pub max_restarts: u32,
+    pub window: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            mode: RestartMode::Never,
+            max_restarts: DEFAULT_RESTART_LIMIT,
+            window: Duration::from_secs(DEFAULT_RESTART_WINDOW_SECS),
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn wants_restart(&self, exit_code: Option<i32>) -> bool {
+        match self.mode {
+            RestartMode::Never => false,
+            RestartMode::Always => true,
+            RestartMode::OnFailure => exit_code != Some(0),
+        }
+    }
+}
+
+pub struct RestartTracker {
+    policy: RestartPolicy,
+    attempts: VecDeque<Instant>,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RestartTracker {
+    pub fn new(policy: RestartPolicy) -> Self {
+        RestartTracker {
+            policy,
+            attempts: VecDeque::new(),
+            initial_delay: INITIAL_RESTART_DELAY,
+            max_delay: MAX_RESTART_DELAY,
+        }
+    }
+
+    pub fn policy(&self) -> &RestartPolicy {
+        &self.policy
+    }
+
+    // Returns the number of the next attempt within the window and how long to wait before making it,
+    // or None if the window's quota of restarts is exhausted.
+    pub fn next_attempt(&mut self, now: Instant) -> Option<(u32, Duration)> {
+        let window = self.policy.window;
+        while let Some(oldest) = self.attempts.front() {
+            if now.duration_since(*oldest) >= window {
+                self.attempts.pop_front();
+            } else {
+                break;
+            }
+        }
+        let previous_attempts = self.attempts.len() as u32;
+        if previous_attempts >= self.policy.max_restarts {
+            return None;
+        }
+        let delay = self
+            .initial_delay
+            .checked_mul(2u32.saturating_pow(previous_attempts))
+            .map(|delay| delay.min(self.max_delay))
+            .unwrap_or(self.max_delay);
+        self.attempts.push_back(now);
+        Some((previous_attempts + 1, delay))
+    }
+
+    pub fn attempts_in_window(&self) -> u32 {
+        self.attempts.len() as u32
+    }
+
+    #[cfg(test)]
+    pub fn set_delays(&mut self, initial_delay: Duration, max_delay: Duration) {
+        self.initial_delay = initial_delay;
+        self.max_delay = max_delay;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restart_mode_parses_valid_values() {
+        assert_eq!(RestartMode::from_str("never"), Ok(RestartMode::Never));
+        assert_eq!(RestartMode::from_str("always"), Ok(RestartMode::Always));
+        assert_eq!(
+            RestartMode::from_str("on-failure"),
+            Ok(RestartMode::OnFailure)
+        );
+    }
+
+    #[test]
+    fn restart_mode_rejects_invalid_values() {
+        assert_eq!(
+            RestartMode::from_str("sometimes"),
+            Err("Restart policy must be never, always, or on-failure, not 'sometimes'".to_string())
+        );
+    }
+
+    #[test]
+    fn default_policy_never_restarts() {
+        let subject = RestartPolicy::default();
+
+        assert_eq!(subject.mode, RestartMode::Never);
+        assert_eq!(subject.max_restarts, DEFAULT_RESTART_LIMIT);
+        assert_eq!(
+            subject.window,
+            Duration::from_secs(DEFAULT_RESTART_WINDOW_SECS)
+        );
+        assert_eq!(subject.wants_restart(Some(1)), false);
+        assert_eq!(subject.wants_restart(None), false);
+    }
+
+    #[test]
+    fn always_policy_restarts_after_any_exit() {
+        let subject = RestartPolicy {
+            mode: RestartMode::Always,
+            ..RestartPolicy::default()
+        };
+
+        assert_eq!(subject.wants_restart(Some(0)), true);
+        assert_eq!(subject.wants_restart(Some(1)), true);
+        assert_eq!(subject.wants_restart(None), true);
+    }
+
+    #[test]
+    fn on_failure_policy_restarts_after_anything_but_a_clean_exit() {
+        let subject = RestartPolicy {
+            mode: RestartMode::OnFailure,
+            ..RestartPolicy::default()
+        };
+
+        assert_eq!(subject.wants_restart(Some(0)), false);
+        assert_eq!(subject.wants_restart(Some(101)), true);
+        assert_eq!(subject.wants_restart(None), true);
+    }
+
+    #[test]
+    fn tracker_backs_off_exponentially_up_to_the_maximum_delay() {
+        let mut subject = RestartTracker::new(RestartPolicy {
+            mode: RestartMode::Always,
+            max_restarts: 10,
+            window: Duration::from_secs(3600),
+        });
+        let now = Instant::now();
+
+        let delays = (0..8)
+            .map(|_| subject.next_attempt(now).unwrap())
+            .collect::<Vec<(u32, Duration)>>();
+
+        assert_eq!(
+            delays,
+            vec![
+                (1, Duration::from_secs(1)),
+                (2, Duration::from_secs(2)),
+                (3, Duration::from_secs(4)),
+                (4, Duration::from_secs(8)),
+                (5, Duration::from_secs(16)),
+                (6, Duration::from_secs(32)),
+                (7, Duration::from_secs(60)),
+                (8, Duration::from_secs(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn tracker_refuses_attempts_beyond_the_limit_in_a_window() {
+        let mut subject = RestartTracker::new(RestartPolicy {
+            mode: RestartMode::Always,
+            max_restarts: 2,
+            window: Duration::from_secs(60),
+        });
+        let now = Instant::now();
+
+        let first = subject.next_attempt(now);
+        let second = subject.next_attempt(now + Duration::from_secs(10));
+        let third = subject.next_attempt(now + Duration::from_secs(20));
+
+        assert_eq!(first, Some((1, Duration::from_secs(1))));
+        assert_eq!(second, Some((2, Duration::from_secs(2))));
+        assert_eq!(third, None);
+        assert_eq!(subject.attempts_in_window(), 2);
+    }
+
+    #[test]
+    fn tracker_forgets_attempts_that_fall_out_of_the_window() {
+        let mut subject = RestartTracker::new(RestartPolicy {
+            mode: RestartMode::Always,
+            max_restarts: 2,
+            window: Duration::from_secs(60),
+        });
+        let now = Instant::now();
+        subject.next_attempt(now).unwrap();
+        subject.next_attempt(now + Duration::from_secs(30)).unwrap();
+
+        let result = subject.next_attempt(now + Duration::from_secs(60));
+
+        assert_eq!(result, Some((2, Duration::from_secs(2))));
+    }
+}
@@ -6,12 +6,14 @@ use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
 use crate::db_config::persistent_configuration::{
     PersistentConfiguration, PersistentConfigurationReal,
 };
+use crate::masquerader::MasqueradeKind;
 use crate::node_configurator::node_configurator_standard::standard::{
     privileged_parse_args, unprivileged_parse_args,
 };
 use crate::node_configurator::{
     app_head, data_directory_from_context, determine_config_file_path, DirsWrapper, RealDirsWrapper,
 };
+use crate::sub_lib::logger::LogFormat as LogFormatKind;
 use crate::sub_lib::neighborhood::NodeDescriptor;
 use crate::sub_lib::utils::make_new_multi_config;
 use crate::test_utils::main_cryptde;
@@ -635,6 +637,33 @@ impl ValueRetriever for DbPassword {
     }
 }
 
+struct DnsBypass {}
+impl ValueRetriever for DnsBypass {
+    fn value_name(&self) -> &'static str {
+        "dns-bypass"
+    }
+}
+
+struct DnsProtocol {}
+impl ValueRetriever for DnsProtocol {
+    fn value_name(&self) -> &'static str {
+        "dns-protocol"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((bootstrapper_config.dns_protocol.to_string(), Default))
+    }
+
+    fn is_required(&self, _params: &SetupCluster) -> bool {
+        true
+    }
+}
+
 struct DnsServers {}
 impl ValueRetriever for DnsServers {
     fn value_name(&self) -> &'static str {
@@ -662,6 +691,69 @@ impl ValueRetriever for EarningWallet {
     }
 }
 
+struct ExitAbsoluteTimeout {}
+impl ValueRetriever for ExitAbsoluteTimeout {
+    fn value_name(&self) -> &'static str {
+        "exit-absolute-timeout"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((
+            bootstrapper_config
+                .exit_absolute_timeout
+                .as_secs()
+                .to_string(),
+            Default,
+        ))
+    }
+
+    fn is_required(&self, _params: &SetupCluster) -> bool {
+        true
+    }
+}
+
+struct ExitBandwidth {}
+impl ValueRetriever for ExitBandwidth {
+    fn value_name(&self) -> &'static str {
+        "exit-bandwidth"
+    }
+}
+
+struct ExitIdleTimeout {}
+impl ValueRetriever for ExitIdleTimeout {
+    fn value_name(&self) -> &'static str {
+        "exit-idle-timeout"
+    }
+
+    fn computed_default(
+        &self,
+        bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((
+            bootstrapper_config.exit_idle_timeout.as_secs().to_string(),
+            Default,
+        ))
+    }
+
+    fn is_required(&self, _params: &SetupCluster) -> bool {
+        true
+    }
+}
+
+struct ExitPorts {}
+impl ValueRetriever for ExitPorts {
+    fn value_name(&self) -> &'static str {
+        "exit-ports"
+    }
+}
+
 struct GasPrice {}
 impl ValueRetriever for GasPrice {
     fn value_name(&self) -> &'static str {
@@ -703,6 +795,26 @@ impl ValueRetriever for Ip {
     }
 }
 
+struct LogFormat {}
+impl ValueRetriever for LogFormat {
+    fn value_name(&self) -> &'static str {
+        "log-format"
+    }
+
+    fn computed_default(
+        &self,
+        _bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((LogFormatKind::default().to_string(), Default))
+    }
+
+    fn is_required(&self, _params: &SetupCluster) -> bool {
+        true
+    }
+}
+
 struct LogLevel {}
 impl ValueRetriever for LogLevel {
     fn value_name(&self) -> &'static str {
@@ -723,6 +835,33 @@ impl ValueRetriever for LogLevel {
     }
 }
 
+struct Masquerade {}
+impl ValueRetriever for Masquerade {
+    fn value_name(&self) -> &'static str {
+        "masquerade"
+    }
+
+    fn computed_default(
+        &self,
+        _bootstrapper_config: &BootstrapperConfig,
+        _persistent_config_opt: &Option<Box<dyn PersistentConfiguration>>,
+        _db_password_opt: &Option<String>,
+    ) -> Option<(String, UiSetupResponseValueStatus)> {
+        Some((MasqueradeKind::default().to_string(), Default))
+    }
+
+    fn is_required(&self, _params: &SetupCluster) -> bool {
+        true
+    }
+}
+
+struct MetricsPort {}
+impl ValueRetriever for MetricsPort {
+    fn value_name(&self) -> &'static str {
+        "metrics-port"
+    }
+}
+
 struct NeighborhoodMode {}
 impl ValueRetriever for NeighborhoodMode {
     fn value_name(&self) -> &'static str {
@@ -781,6 +920,20 @@ impl ValueRetriever for Neighbors {
     }
 }
 
+struct PortMappings {}
+impl ValueRetriever for PortMappings {
+    fn value_name(&self) -> &'static str {
+        "port-mappings"
+    }
+}
+
+struct ProxyPort {}
+impl ValueRetriever for ProxyPort {
+    fn value_name(&self) -> &'static str {
+        "proxy-port"
+    }
+}
+
 struct RealUser {
     #[allow(dead_code)]
     dirs_wrapper: Box<dyn DirsWrapper>,
@@ -824,6 +977,13 @@ impl RealUser {
     }
 }
 
+struct RoutingBandwidth {}
+impl ValueRetriever for RoutingBandwidth {
+    fn value_name(&self) -> &'static str {
+        "routing-bandwidth"
+    }
+}
+
 fn value_retrievers(dirs_wrapper: &dyn DirsWrapper) -> Vec<Box<dyn ValueRetriever>> {
     vec![
         Box::new(BlockchainServiceUrl {}),
@@ -834,15 +994,27 @@ fn value_retrievers(dirs_wrapper: &dyn DirsWrapper) -> Vec<Box<dyn ValueRetrieve
         Box::new(CrashPoint {}),
         Box::new(DataDirectory::new(dirs_wrapper)),
         Box::new(DbPassword {}),
+        Box::new(DnsBypass {}),
+        Box::new(DnsProtocol {}),
         Box::new(DnsServers {}),
         Box::new(EarningWallet {}),
+        Box::new(ExitAbsoluteTimeout {}),
+        Box::new(ExitBandwidth {}),
+        Box::new(ExitIdleTimeout {}),
+        Box::new(ExitPorts {}),
         Box::new(GasPrice {}),
         Box::new(Ip {}),
+        Box::new(LogFormat {}),
         Box::new(LogLevel {}),
+        Box::new(Masquerade {}),
+        Box::new(MetricsPort {}),
         Box::new(NeighborhoodMode {}),
         Box::new(Neighbors {}),
+        Box::new(PortMappings {}),
+        Box::new(ProxyPort {}),
         #[cfg(not(target_os = "windows"))]
         Box::new(RealUser::new(dirs_wrapper)),
+        Box::new(RoutingBandwidth {}),
     ]
 }
 
@@ -962,17 +1134,28 @@ mod tests {
             ("crash-point", "", Blank),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "", Blank),
+            ("dns-protocol", "udp", Default),
             ("dns-servers", "1.1.1.1", Default),
             ("earning-wallet", "", Blank),
+            ("exit-absolute-timeout", "86400", Default),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "300", Default),
+            ("exit-ports", "", Blank),
             ("gas-price", "1234567890", Default),
             ("ip", "4.3.2.1", Set),
+            ("log-format", "text", Default),
             ("log-level", "warn", Default),
+            ("masquerade", "json", Default),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "standard", Default),
             (
                 "neighbors",
                 "QUJDRA@1.2.3.4:1234,RUZHSA@5.6.7.8:5678",
                 Configured,
             ),
+            ("port-mappings", "", Blank),
+            ("proxy-port", "", Blank),
             #[cfg(not(target_os = "windows"))]
             (
                 "real-user",
@@ -981,6 +1164,7 @@ mod tests {
                     .to_string(),
                 Default,
             ),
+            ("routing-bandwidth", "", Blank),
         ]
         .into_iter()
         .map(|(name, value, status)| {
@@ -1012,15 +1196,27 @@ mod tests {
             ("crash-point", "Message", Set),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "lan,home.arpa", Set),
+            ("dns-protocol", "tls:dns.google", Set),
             ("dns-servers", "8.8.8.8", Set),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Set),
+            ("exit-absolute-timeout", "7200", Set),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "120", Set),
+            ("exit-ports", "22,8000-8100", Set),
             ("gas-price", "50", Set),
             ("ip", "4.3.2.1", Set),
+            ("log-format", "text", Default),
             ("log-level", "error", Set),
+            ("masquerade", "http", Set),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Set),
+            ("port-mappings", "2222:ssh.example.com:22", Set),
+            ("proxy-port", "8080", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Set),
+            ("routing-bandwidth", "", Blank),
         ]);
         let subject = SetupReporterReal::new();
 
@@ -1035,15 +1231,27 @@ mod tests {
             ("crash-point", "Message", Set),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "lan,home.arpa", Set),
+            ("dns-protocol", "tls:dns.google", Set),
             ("dns-servers", "8.8.8.8", Set),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Set),
+            ("exit-absolute-timeout", "7200", Set),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "120", Set),
+            ("exit-ports", "22,8000-8100", Set),
             ("gas-price", "50", Set),
             ("ip", "4.3.2.1", Set),
+            ("log-format", "text", Default),
             ("log-level", "error", Set),
+            ("masquerade", "http", Set),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Set),
+            ("port-mappings", "2222:ssh.example.com:22", Set),
+            ("proxy-port", "8080", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Set),
+            ("routing-bandwidth", "", Blank),
         ].into_iter()
             .map (|(name, value, status)| (name.to_string(), UiSetupResponseValue::new(name, value, status)))
             .collect_vec();
@@ -1069,13 +1277,21 @@ mod tests {
             ("crash-point", "Message"),
             ("data-directory", home_dir.to_str().unwrap()),
             ("db-password", "password"),
+            ("dns-bypass", "lan,home.arpa"),
+            ("dns-protocol", "tls:dns.google"),
             ("dns-servers", "8.8.8.8"),
             ("earning-wallet", "0x0123456789012345678901234567890123456789"),
+            ("exit-absolute-timeout", "7200"),
+            ("exit-idle-timeout", "120"),
+            ("exit-ports", "22,8000-8100"),
             ("gas-price", "50"),
             ("ip", "4.3.2.1"),
             ("log-level", "error"),
+            ("masquerade", "http"),
             ("neighborhood-mode", "originate-only"),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678"),
+            ("port-mappings", "2222:ssh.example.com:22"),
+            ("proxy-port", "8080"),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga"),
         ].into_iter()
@@ -1096,15 +1312,27 @@ mod tests {
             ("crash-point", "Message", Set),
             ("data-directory", home_dir.to_str().unwrap(), Set),
             ("db-password", "password", Set),
+            ("dns-bypass", "lan,home.arpa", Set),
+            ("dns-protocol", "tls:dns.google", Set),
             ("dns-servers", "8.8.8.8", Set),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Set),
+            ("exit-absolute-timeout", "7200", Set),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "120", Set),
+            ("exit-ports", "22,8000-8100", Set),
             ("gas-price", "50", Set),
             ("ip", "4.3.2.1", Set),
+            ("log-format", "text", Default),
             ("log-level", "error", Set),
+            ("masquerade", "http", Set),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Set),
+            ("port-mappings", "2222:ssh.example.com:22", Set),
+            ("proxy-port", "8080", Set),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Set),
+            ("routing-bandwidth", "", Blank),
         ].into_iter()
             .map (|(name, value, status)| (name.to_string(), UiSetupResponseValue::new(name, value, status)))
             .collect_vec();
@@ -1131,13 +1359,21 @@ mod tests {
             ("MASQ_CRASH_POINT", "Error"),
             ("MASQ_DATA_DIRECTORY", home_dir.to_str().unwrap()),
             ("MASQ_DB_PASSWORD", "password"),
+            ("MASQ_DNS_BYPASS", "lan,home.arpa"),
+            ("MASQ_DNS_PROTOCOL", "tls:dns.google"),
             ("MASQ_DNS_SERVERS", "8.8.8.8"),
             ("MASQ_EARNING_WALLET", "0x0123456789012345678901234567890123456789"),
+            ("MASQ_EXIT_ABSOLUTE_TIMEOUT", "7200"),
+            ("MASQ_EXIT_IDLE_TIMEOUT", "120"),
+            ("MASQ_EXIT_PORTS", "22,8000-8100"),
             ("MASQ_GAS_PRICE", "50"),
             ("MASQ_IP", "4.3.2.1"),
             ("MASQ_LOG_LEVEL", "error"),
+            ("MASQ_MASQUERADE", "http"),
             ("MASQ_NEIGHBORHOOD_MODE", "originate-only"),
             ("MASQ_NEIGHBORS", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678"),
+            ("MASQ_PORT_MAPPINGS", "2222:ssh.example.com:22"),
+            ("MASQ_PROXY_PORT", "8080"),
             #[cfg(not(target_os = "windows"))]
             ("MASQ_REAL_USER", "9999:9999:booga"),
         ].into_iter()
@@ -1156,15 +1392,27 @@ mod tests {
             ("crash-point", "Error", Configured),
             ("data-directory", home_dir.to_str().unwrap(), Configured),
             ("db-password", "password", Configured),
+            ("dns-bypass", "lan,home.arpa", Configured),
+            ("dns-protocol", "tls:dns.google", Configured),
             ("dns-servers", "8.8.8.8", Configured),
             ("earning-wallet", "0x0123456789012345678901234567890123456789", Configured),
+            ("exit-absolute-timeout", "7200", Configured),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "120", Configured),
+            ("exit-ports", "22,8000-8100", Configured),
             ("gas-price", "50", Configured),
             ("ip", "4.3.2.1", Configured),
+            ("log-format", "text", Default),
             ("log-level", "error", Configured),
+            ("masquerade", "http", Configured),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "originate-only", Configured),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Configured),
+            ("port-mappings", "2222:ssh.example.com:22", Configured),
+            ("proxy-port", "8080", Configured),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Configured),
+            ("routing-bandwidth", "", Blank),
         ].into_iter()
             .map (|(name, value, status)| (name.to_string(), UiSetupResponseValue::new(name, value, status)))
             .collect_vec();
@@ -1270,17 +1518,28 @@ mod tests {
                 Default,
             ),
             ("db-password", TEST_DEFAULT_CHAIN_NAME, Configured),
+            ("dns-bypass", "", Blank),
+            ("dns-protocol", "udp", Default),
             ("dns-servers", "8.7.6.5", Configured),
             (
                 "earning-wallet",
                 "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
                 Configured,
             ),
+            ("exit-absolute-timeout", "86400", Default),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "300", Default),
+            ("exit-ports", "", Blank),
             ("gas-price", "88", Configured),
             ("ip", "", Blank),
+            ("log-format", "text", Default),
             ("log-level", "debug", Configured),
+            ("masquerade", "json", Default),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "zero-hop", Configured),
             ("neighbors", "", Blank),
+            ("port-mappings", "", Blank),
+            ("proxy-port", "", Blank),
             #[cfg(not(target_os = "windows"))]
             (
                 "real-user",
@@ -1289,6 +1548,7 @@ mod tests {
                     .to_string(),
                 Default,
             ),
+            ("routing-bandwidth", "", Blank),
         ]
         .into_iter()
         .map(|(name, value, status)| {
@@ -1320,13 +1580,21 @@ mod tests {
             ("MASQ_CRASH_POINT", "Panic"),
             ("MASQ_DATA_DIRECTORY", home_dir.to_str().unwrap()),
             ("MASQ_DB_PASSWORD", "password"),
+            ("MASQ_DNS_BYPASS", "lan,home.arpa"),
+            ("MASQ_DNS_PROTOCOL", "tls:dns.google"),
             ("MASQ_DNS_SERVERS", "8.8.8.8"),
             ("MASQ_EARNING_WALLET", "0x0123456789012345678901234567890123456789"),
+            ("MASQ_EXIT_ABSOLUTE_TIMEOUT", "7200"),
+            ("MASQ_EXIT_IDLE_TIMEOUT", "120"),
+            ("MASQ_EXIT_PORTS", "22,8000-8100"),
             ("MASQ_GAS_PRICE", "50"),
             ("MASQ_IP", "4.3.2.1"),
             ("MASQ_LOG_LEVEL", "error"),
+            ("MASQ_MASQUERADE", "http"),
             ("MASQ_NEIGHBORHOOD_MODE", "originate-only"),
             ("MASQ_NEIGHBORS", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678"),
+            ("MASQ_PORT_MAPPINGS", "2222:ssh.example.com:22"),
+            ("MASQ_PROXY_PORT", "8080"),
             #[cfg(not(target_os = "windows"))]
             ("MASQ_REAL_USER", "9999:9999:booga"),
         ].into_iter()
@@ -1392,19 +1660,31 @@ mod tests {
             ("crash-point", "Panic", Configured),
             ("data-directory", home_dir.to_str().unwrap(), Configured),
             ("db-password", "password", Configured),
+            ("dns-bypass", "lan,home.arpa", Configured),
+            ("dns-protocol", "tls:dns.google", Configured),
             ("dns-servers", "8.8.8.8", Configured),
             (
                 "earning-wallet",
                 "0x0123456789012345678901234567890123456789",
                 Configured,
             ),
+            ("exit-absolute-timeout", "7200", Configured),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "120", Configured),
+            ("exit-ports", "22,8000-8100", Configured),
             ("gas-price", "50", Configured),
             ("ip", "4.3.2.1", Configured),
+            ("log-format", "text", Default),
             ("log-level", "error", Configured),
+            ("masquerade", "http", Configured),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "originate-only", Configured),
             ("neighbors", "MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:1.2.3.4:1234,MTIzNDU2Nzg5MTEyMzQ1Njc4OTIxMjM0NTY3ODkzMTI:5.6.7.8:5678", Configured),
+            ("port-mappings", "2222:ssh.example.com:22", Configured),
+            ("proxy-port", "8080", Configured),
             #[cfg(not(target_os = "windows"))]
             ("real-user", "9999:9999:booga", Configured),
+            ("routing-bandwidth", "", Blank),
         ]
         .into_iter()
         .map(|(name, value, status)| {
@@ -1486,17 +1766,28 @@ mod tests {
                 Default,
             ),
             ("db-password", "", Required),
+            ("dns-bypass", "", Blank),
+            ("dns-protocol", "udp", Default),
             ("dns-servers", "1.1.1.1", Default),
             (
                 "earning-wallet",
                 "0x47fb8671db83008d382c2e6ea67fa377378c0cea",
                 Default,
             ),
+            ("exit-absolute-timeout", "86400", Default),
+            ("exit-bandwidth", "", Blank),
+            ("exit-idle-timeout", "300", Default),
+            ("exit-ports", "", Blank),
             ("gas-price", "1", Default),
             ("ip", "1.2.3.4", Set),
+            ("log-format", "text", Default),
             ("log-level", "warn", Default),
+            ("masquerade", "json", Default),
+            ("metrics-port", "", Blank),
             ("neighborhood-mode", "originate-only", Set),
             ("neighbors", "", Blank),
+            ("port-mappings", "", Blank),
+            ("proxy-port", "", Blank),
             (
                 "real-user",
                 &crate::bootstrapper::RealUser::new(None, None, None)
@@ -1504,6 +1795,7 @@ mod tests {
                     .to_string(),
                 Default,
             ),
+            ("routing-bandwidth", "", Blank),
         ]);
         let incoming_setup = vec![("chain", TEST_DEFAULT_CHAIN_NAME)]
             .into_iter()
@@ -2053,6 +2345,15 @@ mod tests {
         assert_eq!(result, Some((expected, Default)))
     }
 
+    #[test]
+    fn dns_protocol_computed_default() {
+        let subject = DnsProtocol {};
+
+        let result = subject.computed_default(&BootstrapperConfig::new(), &None, &None);
+
+        assert_eq!(result, Some(("udp".to_string(), Default)))
+    }
+
     #[test]
     fn dns_servers_computed_default() {
         let subject = DnsServers {};
@@ -2116,6 +2417,24 @@ mod tests {
         assert_eq!(result, Some(("warn".to_string(), Default)))
     }
 
+    #[test]
+    fn masquerade_computed_default() {
+        let subject = Masquerade {};
+
+        let result = subject.computed_default(&BootstrapperConfig::new(), &None, &None);
+
+        assert_eq!(result, Some(("json".to_string(), Default)))
+    }
+
+    #[test]
+    fn log_format_computed_default() {
+        let subject = LogFormat {};
+
+        let result = subject.computed_default(&BootstrapperConfig::new(), &None, &None);
+
+        assert_eq!(result, Some(("text".to_string(), Default)))
+    }
+
     #[test]
     fn neighborhood_mode_computed_default() {
         let subject = NeighborhoodMode {};
@@ -0,0 +1,131 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::bootstrapper::RealUser;
+use crate::node_configurator::{data_directory_from_context, DirsWrapper};
+use crate::privilege_drop::PrivilegeDropper;
+use masq_lib::constants::DEFAULT_CHAIN_NAME;
+use masq_lib::ui_auth::ui_token_path;
+use rand::Rng;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const UI_TOKEN_BYTES: usize = 32;
+
+// The token belongs in the real user's MASQ directory rather than root's, so that masq can find
+// it even when the Daemon runs under sudo.
+pub fn daemon_ui_token_path(dirs_wrapper: &dyn DirsWrapper, real_user: &RealUser) -> PathBuf {
+    let chain_directory =
+        data_directory_from_context(dirs_wrapper, real_user, &None, DEFAULT_CHAIN_NAME);
+    ui_token_path(
+        chain_directory
+            .parent()
+            .expect("Data directory has no parent"),
+    )
+}
+
+// Replaces any token left over from an earlier run, so that UIs holding the old one lose access.
+pub fn create_ui_token(
+    path: &Path,
+    real_user: &RealUser,
+    privilege_dropper: &dyn PrivilegeDropper,
+) -> io::Result<String> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+        _ => (),
+    }
+    let token = make_token();
+    let mut file = create_owner_only(path)?;
+    file.write_all(token.as_bytes())?;
+    privilege_dropper.chown(&path.to_path_buf(), real_user);
+    Ok(token)
+}
+
+fn make_token() -> String {
+    let mut rng = rand::thread_rng();
+    (0..UI_TOKEN_BYTES)
+        .map(|_| format!("{:02x}", rng.gen::<u8>()))
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_owner_only(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(target_os = "windows")]
+fn create_owner_only(path: &Path) -> io::Result<File> {
+    // Files in the user's local data directory are already private to that user.
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_test_utils::MockDirsWrapper;
+    use crate::server_initializer::test_utils::PrivilegeDropperMock;
+    use masq_lib::test_utils::utils::ensure_node_home_directory_exists;
+    use masq_lib::ui_auth::read_ui_token;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(UI_TOKEN_BYTES, 32);
+    }
+
+    #[test]
+    fn daemon_ui_token_path_is_in_the_real_users_masq_directory() {
+        let dirs_wrapper = MockDirsWrapper::new()
+            .home_dir_result(Some(PathBuf::from("/root")))
+            .data_dir_result(Some(PathBuf::from("/root/.local/share")));
+        let real_user = RealUser::new(Some(1000), Some(1000), Some(PathBuf::from("/home/booga")));
+
+        let result = daemon_ui_token_path(&dirs_wrapper, &real_user);
+
+        assert_eq!(
+            result,
+            PathBuf::from("/home/booga/.local/share/MASQ/ui-token")
+        );
+    }
+
+    #[test]
+    fn create_ui_token_writes_a_fresh_owner_only_token_and_hands_it_to_the_real_user() {
+        let home_dir = ensure_node_home_directory_exists(
+            "ui_token",
+            "create_ui_token_writes_a_fresh_owner_only_token_and_hands_it_to_the_real_user",
+        );
+        let path = home_dir.join("MASQ").join("ui-token");
+        let real_user = RealUser::new(Some(1000), Some(1000), Some(home_dir.clone()));
+        let chown_params_arc = Arc::new(Mutex::new(vec![]));
+        let privilege_dropper = PrivilegeDropperMock::new().chown_params(&chown_params_arc);
+
+        let first_token = create_ui_token(&path, &real_user, &privilege_dropper).unwrap();
+        let second_token = create_ui_token(&path, &real_user, &privilege_dropper).unwrap();
+
+        assert_eq!(second_token.len(), UI_TOKEN_BYTES * 2);
+        assert_eq!(second_token.chars().all(|c| c.is_ascii_hexdigit()), true);
+        assert_ne!(first_token, second_token);
+        assert_eq!(read_ui_token(&path).unwrap(), second_token);
+        #[cfg(not(target_os = "windows"))]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let chown_params = chown_params_arc.lock().unwrap();
+        assert_eq!(
+            *chown_params,
+            vec![(path.clone(), real_user.clone()), (path, real_user)]
+        );
+    }
+}
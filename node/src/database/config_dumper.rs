@@ -149,6 +149,7 @@ mod tests {
            "earningWalletAddress": null,
            "exampleEncrypted": null,
            "gasPrice": "1",
+           "neighborhoodSnapshot": null,
            "pastNeighbors": null,
            "schemaVersion": CURRENT_SCHEMA_VERSION,
            "seed": null,
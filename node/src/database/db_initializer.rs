@@ -19,7 +19,7 @@ use std::path::PathBuf;
 use tokio::net::TcpListener;
 
 pub const DATABASE_FILE: &str = "node-data.db";
-pub const CURRENT_SCHEMA_VERSION: &str = "0.0.10";
+pub const CURRENT_SCHEMA_VERSION: &str = "0.0.11";
 
 #[derive(Debug, PartialEq)]
 pub enum InitializationError {
@@ -56,9 +56,13 @@ impl DbInitializer for DbInitializerReal {
         flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
         let database_file_path = &path.join(DATABASE_FILE);
         match Connection::open_with_flags(database_file_path, flags) {
-            Ok(conn) => {
+            Ok(mut conn) => {
                 eprintln!("Opened existing database at {:?}", database_file_path);
-                let config = self.extract_configurations(&conn);
+                let mut config = self.extract_configurations(&conn);
+                if config.get("schema_version") == Some(&Some("0.0.10".to_string())) {
+                    self.migrate_from_0_0_10(&mut conn)?;
+                    config = self.extract_configurations(&conn);
+                }
                 match self.check_version(config.get("schema_version")) {
                     Ok(_) => Ok(Box::new(ConnectionWrapperReal::new(conn))),
                     Err(e) => Err(e),
@@ -199,6 +203,13 @@ impl DbInitializerReal {
             "gas price",
         );
         Self::set_config_value(conn, "past_neighbors", None, true, "past neighbors");
+        Self::set_config_value(
+            conn,
+            "neighborhood_snapshot",
+            None,
+            false,
+            "neighborhood snapshot",
+        );
         Ok(())
     }
 
@@ -291,6 +302,37 @@ impl DbInitializerReal {
         }
     }
 
+    // 0.0.11 only added the neighborhood_snapshot row, which starts out empty.
+    fn migrate_from_0_0_10(&self, conn: &mut Connection) -> Result<(), InitializationError> {
+        let transaction = conn
+            .transaction()
+            .map_err(InitializationError::SqliteError)?;
+        Self::set_config_value(
+            &transaction,
+            "neighborhood_snapshot",
+            None,
+            false,
+            "neighborhood snapshot",
+        );
+        transaction
+            .execute(
+                format!(
+                    "update config set value = '{}' where name = 'schema_version'",
+                    CURRENT_SCHEMA_VERSION
+                )
+                .as_str(),
+                NO_PARAMS,
+            )
+            .map_err(InitializationError::SqliteError)?;
+        eprintln!(
+            "Migrated database from schema 0.0.10 to {}",
+            CURRENT_SCHEMA_VERSION
+        );
+        transaction
+            .commit()
+            .map_err(InitializationError::SqliteError)
+    }
+
     fn choose_clandestine_port() -> u16 {
         let mut rng = SmallRng::from_entropy();
         loop {
@@ -610,6 +652,7 @@ mod tests {
         verify(&mut config_vec, "earning_wallet_address", None);
         verify(&mut config_vec, EXAMPLE_ENCRYPTED, None);
         verify(&mut config_vec, "gas_price", Some(DEFAULT_GAS_PRICE));
+        verify(&mut config_vec, "neighborhood_snapshot", None);
         verify(&mut config_vec, "past_neighbors", None);
         verify(&mut config_vec, "preexisting", Some("yes")); // makes sure we just created this database
         verify(
@@ -693,6 +736,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn existing_database_at_0_0_10_is_migrated() {
+        let home_dir = ensure_node_home_directory_exists(
+            "db_initializer",
+            "existing_database_at_0_0_10_is_migrated",
+        );
+        {
+            DbInitializerReal::new()
+                .initialize(&home_dir, DEFAULT_CHAIN_ID, true)
+                .unwrap();
+            let mut flags = OpenFlags::empty();
+            flags.insert(OpenFlags::SQLITE_OPEN_READ_WRITE);
+            let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+            conn.execute(
+                "update config set value = '0.0.10' where name = 'schema_version'",
+                NO_PARAMS,
+            )
+            .unwrap();
+            conn.execute(
+                "delete from config where name = 'neighborhood_snapshot'",
+                NO_PARAMS,
+            )
+            .unwrap();
+        }
+        let subject = DbInitializerReal::new();
+
+        let result = subject.initialize(&home_dir, DEFAULT_CHAIN_ID, true);
+
+        assert!(result.is_ok());
+        let mut flags = OpenFlags::empty();
+        flags.insert(OpenFlags::SQLITE_OPEN_READ_ONLY);
+        let conn = Connection::open_with_flags(&home_dir.join(DATABASE_FILE), flags).unwrap();
+        let config = subject.extract_configurations(&conn);
+        assert_eq!(
+            config.get("schema_version"),
+            Some(&Some(CURRENT_SCHEMA_VERSION.to_string()))
+        );
+        assert_eq!(config.get("neighborhood_snapshot"), Some(&None));
+    }
+
     #[test]
     fn choose_clandestine_port_chooses_different_unused_ports_each_time() {
         let _listeners = (0..10)
@@ -7,6 +7,7 @@ use crate::db_config::secure_config_layer::{SecureConfigLayer, SecureConfigLayer
 use crate::db_config::typed_config_layer::{
     decode_bytes, decode_u64, encode_bytes, encode_u64, TypedConfigLayerError,
 };
+use crate::neighborhood::neighborhood_snapshot::NeighborhoodSnapshot;
 use crate::sub_lib::cryptde::PlainData;
 use crate::sub_lib::neighborhood::NodeDescriptor;
 use crate::sub_lib::wallet::Wallet;
@@ -107,6 +108,11 @@ pub trait PersistentConfiguration {
         node_descriptors_opt: Option<Vec<NodeDescriptor>>,
         db_password: &str,
     ) -> Result<(), PersistentConfigError>;
+    fn neighborhood_snapshot(&self) -> Result<Option<NeighborhoodSnapshot>, PersistentConfigError>;
+    fn set_neighborhood_snapshot(
+        &mut self,
+        snapshot_opt: Option<NeighborhoodSnapshot>,
+    ) -> Result<(), PersistentConfigError>;
     fn start_block(&self) -> Result<Option<u64>, PersistentConfigError>;
     fn set_start_block(&mut self, value: u64) -> Result<(), PersistentConfigError>;
 }
@@ -340,6 +346,34 @@ impl PersistentConfiguration for PersistentConfigurationReal {
         Ok(writer.commit()?)
     }
 
+    fn neighborhood_snapshot(&self) -> Result<Option<NeighborhoodSnapshot>, PersistentConfigError> {
+        let bytes_opt = decode_bytes(self.dao.get("neighborhood_snapshot")?.value_opt)?;
+        match bytes_opt {
+            None => Ok(None),
+            Some(bytes) => {
+                match serde_cbor::de::from_slice::<NeighborhoodSnapshot>(bytes.as_slice()) {
+                    Ok(snapshot) => Ok(Some(snapshot)),
+                    Err(e) => Err(PersistentConfigError::DatabaseError(format!(
+                        "Neighborhood snapshot is corrupt and cannot be deserialized: {}",
+                        e
+                    ))),
+                }
+            }
+        }
+    }
+
+    fn set_neighborhood_snapshot(
+        &mut self,
+        snapshot_opt: Option<NeighborhoodSnapshot>,
+    ) -> Result<(), PersistentConfigError> {
+        let plain_data_opt = snapshot_opt.map(|snapshot| {
+            PlainData::new(&serde_cbor::ser::to_vec(&snapshot).expect("Serialization failed"))
+        });
+        let mut writer = self.dao.start_transaction()?;
+        writer.set("neighborhood_snapshot", encode_bytes(plain_data_opt)?)?;
+        Ok(writer.commit()?)
+    }
+
     fn start_block(&self) -> Result<Option<u64>, PersistentConfigError> {
         Ok(decode_u64(self.dao.get("start_block")?.value_opt)?)
     }
@@ -394,6 +428,7 @@ mod tests {
     use crate::db_config::config_dao::ConfigDaoRecord;
     use crate::db_config::mocks::{ConfigDaoMock, ConfigDaoWriteableMock};
     use crate::db_config::secure_config_layer::EXAMPLE_ENCRYPTED;
+    use crate::sub_lib::cryptde::PublicKey;
     use crate::test_utils::main_cryptde;
     use bip39::{Language, MnemonicType};
     use masq_lib::utils::find_free_port;
@@ -1498,4 +1533,98 @@ mod tests {
         assert_eq!(actual_node_descriptors, node_descriptors);
         assert_eq!(set_params.len(), 1);
     }
+
+    #[test]
+    fn neighborhood_snapshot_success() {
+        let snapshot = NeighborhoodSnapshot {
+            taken_at: 1234,
+            records: vec![],
+            root_neighbors: vec![PublicKey::new(&[1, 2, 3, 4])],
+        };
+        let snapshot_string = encode_bytes(Some(PlainData::new(
+            &serde_cbor::ser::to_vec(&snapshot).unwrap(),
+        )))
+        .unwrap();
+        let get_params_arc = Arc::new(Mutex::new(vec![]));
+        let config_dao = Box::new(
+            ConfigDaoMock::new()
+                .get_params(&get_params_arc)
+                .get_result(Ok(ConfigDaoRecord::new(
+                    "neighborhood_snapshot",
+                    snapshot_string.as_deref(),
+                    false,
+                ))),
+        );
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.neighborhood_snapshot();
+
+        assert_eq!(result, Ok(Some(snapshot)));
+        let get_params = get_params_arc.lock().unwrap();
+        assert_eq!(*get_params, vec!["neighborhood_snapshot".to_string()]);
+    }
+
+    #[test]
+    fn neighborhood_snapshot_handles_absence() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "neighborhood_snapshot",
+            None,
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.neighborhood_snapshot();
+
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn neighborhood_snapshot_complains_about_corruption() {
+        let config_dao = Box::new(ConfigDaoMock::new().get_result(Ok(ConfigDaoRecord::new(
+            "neighborhood_snapshot",
+            Some("0102030405"),
+            false,
+        ))));
+        let subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.neighborhood_snapshot();
+
+        match result {
+            Err(PersistentConfigError::DatabaseError(msg)) => assert!(
+                msg.starts_with("Neighborhood snapshot is corrupt and cannot be deserialized: "),
+                "{}",
+                msg
+            ),
+            x => panic!("Expected DatabaseError, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn set_neighborhood_snapshot_success() {
+        let snapshot = NeighborhoodSnapshot {
+            taken_at: 2345,
+            records: vec![],
+            root_neighbors: vec![PublicKey::new(&[1, 2, 3, 4])],
+        };
+        let set_params_arc = Arc::new(Mutex::new(vec![]));
+        let writer = Box::new(
+            ConfigDaoWriteableMock::new()
+                .set_params(&set_params_arc)
+                .set_result(Ok(()))
+                .commit_result(Ok(())),
+        );
+        let config_dao = Box::new(ConfigDaoMock::new().start_transaction_result(Ok(writer)));
+        let mut subject = PersistentConfigurationReal::new(config_dao);
+
+        let result = subject.set_neighborhood_snapshot(Some(snapshot.clone()));
+
+        assert_eq!(result, Ok(()));
+        let set_params = set_params_arc.lock().unwrap();
+        assert_eq!(set_params[0].0, "neighborhood_snapshot".to_string());
+        let bytes = decode_bytes(set_params[0].1.clone()).unwrap().unwrap();
+        let actual_snapshot =
+            serde_cbor::de::from_slice::<NeighborhoodSnapshot>(bytes.as_slice()).unwrap();
+        assert_eq!(actual_snapshot, snapshot);
+        assert_eq!(set_params.len(), 1);
+    }
 }
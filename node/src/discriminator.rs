@@ -1,5 +1,5 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
-use crate::masquerader::{MasqueradeError, Masquerader};
+use crate::masquerader::{MasqueradeError, MasqueradeKind, Masquerader};
 use crate::sub_lib::framer::Framer;
 use crate::sub_lib::logger::Logger;
 use serde::export::fmt::Debug;
@@ -24,6 +24,9 @@ impl UnmaskedChunk {
 pub trait DiscriminatorFactory: Send + Sync + Debug {
     fn make(&self) -> Discriminator;
     fn duplicate(&self) -> Box<dyn DiscriminatorFactory>;
+    fn masquerade_opt(&self) -> Option<MasqueradeKind> {
+        None
+    }
 }
 
 impl Clone for Box<dyn DiscriminatorFactory> {
@@ -1,10 +1,10 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::stream_messages::{PoolBindMessage, RemovedStreamType};
 use crate::sub_lib::dispatcher::InboundClientData;
-use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg};
+use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg, WritePressureMsg};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
+use crate::sub_lib::stream_handler_pool::{ReadPressureMsg, TransmitDataMsg};
 use crate::sub_lib::utils::{handle_ui_crash_request, NODE_MAILBOX_CAPACITY};
 use actix::Actor;
 use actix::Addr;
@@ -23,6 +23,7 @@ struct DispatcherOutSubs {
     to_proxy_server: Recipient<InboundClientData>,
     to_hopper: Recipient<InboundClientData>,
     proxy_server_stream_shutdown_sub: Recipient<StreamShutdownMsg>,
+    proxy_server_write_pressure_sub: Recipient<WritePressureMsg>,
     neighborhood_stream_shutdown_sub: Recipient<StreamShutdownMsg>,
     ui_gateway_sub: Recipient<NodeToUiMessage>,
 }
@@ -32,6 +33,7 @@ pub struct Dispatcher {
     crashable: bool,
     node_descriptor: String,
     to_stream: Option<Recipient<TransmitDataMsg>>,
+    read_pressure_to_stream: Option<Recipient<ReadPressureMsg>>,
     logger: Logger,
 }
 
@@ -48,6 +50,7 @@ impl Handler<BindMessage> for Dispatcher {
             to_proxy_server: msg.peer_actors.proxy_server.from_dispatcher,
             to_hopper: msg.peer_actors.hopper.from_dispatcher,
             proxy_server_stream_shutdown_sub: msg.peer_actors.proxy_server.stream_shutdown_sub,
+            proxy_server_write_pressure_sub: msg.peer_actors.proxy_server.write_pressure_sub,
             neighborhood_stream_shutdown_sub: msg.peer_actors.neighborhood.stream_shutdown_sub,
             ui_gateway_sub: msg.peer_actors.ui_gateway.node_to_ui_message_sub,
         };
@@ -60,6 +63,7 @@ impl Handler<PoolBindMessage> for Dispatcher {
 
     fn handle(&mut self, msg: PoolBindMessage, _ctx: &mut Self::Context) {
         self.to_stream = Some(msg.stream_handler_pool_subs.transmit_sub);
+        self.read_pressure_to_stream = Some(msg.stream_handler_pool_subs.read_pressure_sub);
     }
 }
 
@@ -111,6 +115,35 @@ impl Handler<StreamShutdownMsg> for Dispatcher {
     }
 }
 
+impl Handler<WritePressureMsg> for Dispatcher {
+    type Result = ();
+
+    fn handle(&mut self, msg: WritePressureMsg, _ctx: &mut Self::Context) -> Self::Result {
+        self.subs
+            .as_ref()
+            .expect("ProxyServer unbound in Dispatcher")
+            .proxy_server_write_pressure_sub
+            .try_send(msg)
+            .expect("ProxyServer is dead");
+    }
+}
+
+impl Handler<ReadPressureMsg> for Dispatcher {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReadPressureMsg, _ctx: &mut Self::Context) -> Self::Result {
+        debug!(
+            self.logger,
+            "Relaying {:?} for {} to StreamHandlerPool", msg.pressure, msg.peer_addr
+        );
+        self.read_pressure_to_stream
+            .as_ref()
+            .expect("StreamHandlerPool unbound in Dispatcher")
+            .try_send(msg)
+            .expect("StreamHandlerPool is dead");
+    }
+}
+
 impl Handler<NodeFromUiMessage> for Dispatcher {
     type Result = ();
 
@@ -130,6 +163,7 @@ impl Dispatcher {
             crashable: crash_point == CrashPoint::Message,
             node_descriptor,
             to_stream: None,
+            read_pressure_to_stream: None,
             logger: Logger::new("Dispatcher"),
         }
     }
@@ -140,6 +174,8 @@ impl Dispatcher {
             bind: addr.clone().recipient::<BindMessage>(),
             from_dispatcher_client: addr.clone().recipient::<TransmitDataMsg>(),
             stream_shutdown_sub: addr.clone().recipient::<StreamShutdownMsg>(),
+            write_pressure_sub: addr.clone().recipient::<WritePressureMsg>(),
+            read_pressure_sub: addr.clone().recipient::<ReadPressureMsg>(),
             ui_sub: addr.clone().recipient::<NodeFromUiMessage>(),
         }
     }
@@ -179,6 +215,7 @@ mod tests {
     use crate::node_test_utils::make_stream_handler_pool_subs_from;
     use crate::stream_messages::NonClandestineAttributes;
     use crate::sub_lib::dispatcher::Endpoint;
+    use crate::sub_lib::stream_buffer::Pressure;
     use crate::test_utils::recorder::Recorder;
     use crate::test_utils::recorder::{make_recorder, peer_actors_builder};
     use actix::Addr;
@@ -387,6 +424,62 @@ mod tests {
         assert_eq!(recording.len(), 1);
     }
 
+    #[test]
+    fn forwards_read_pressure_to_stream_handler_pool() {
+        let system = System::new("test");
+        let subject = Dispatcher::new(CrashPoint::None, "descriptor".to_string());
+        let subject_addr: Addr<Dispatcher> = subject.start();
+        let (stream_handler_pool, _, recording_arc) = make_recorder();
+        let mut peer_actors = peer_actors_builder().build();
+        peer_actors.dispatcher = Dispatcher::make_subs_from(&subject_addr);
+        let stream_handler_pool_subs =
+            make_stream_handler_pool_subs_from(Some(stream_handler_pool));
+        subject_addr
+            .try_send(PoolBindMessage {
+                dispatcher_subs: peer_actors.dispatcher.clone(),
+                stream_handler_pool_subs,
+                neighborhood_subs: peer_actors.neighborhood.clone(),
+            })
+            .unwrap();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+        let msg = ReadPressureMsg {
+            peer_addr: SocketAddr::from_str("127.0.0.1:5678").unwrap(),
+            pressure: Pressure::Pause,
+        };
+
+        subject_addr.try_send(msg).unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        let recording = recording_arc.lock().unwrap();
+        assert_eq!(recording.get_record::<ReadPressureMsg>(0), &msg);
+        assert_eq!(recording.len(), 1);
+    }
+
+    #[test]
+    fn forwards_write_pressure_to_proxy_server() {
+        let system = System::new("test");
+        let subject = Dispatcher::new(CrashPoint::None, "descriptor".to_string());
+        let addr = subject.start();
+        let (proxy_server, _, proxy_server_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().proxy_server(proxy_server).build();
+        addr.try_send(BindMessage { peer_actors }).unwrap();
+        let msg = WritePressureMsg {
+            peer_addr: SocketAddr::from_str("127.0.0.1:5678").unwrap(),
+            pressure: Pressure::Resume,
+        };
+
+        addr.try_send(msg).unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        let proxy_server_recording = proxy_server_recording_arc.lock().unwrap();
+        assert_eq!(
+            proxy_server_recording.get_record::<WritePressureMsg>(0),
+            &msg
+        );
+    }
+
     #[test]
     fn handle_stream_shutdown_msg_routes_non_clandestine_to_proxy_server() {
         let system = System::new("test");
@@ -1,22 +1,37 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::{metrics, Metrics};
 use crate::sub_lib::socket_server::SocketServer;
 use masq_lib::command::StdStreams;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
 use tokio::prelude::Async;
 use tokio::prelude::Future;
 
 const DNS_PORT: u16 = 53;
 
+use crate::entry_dns::forwarder::DnsForwarder;
 use crate::entry_dns::processing;
+use crate::entry_dns::processing::Transport;
+use crate::entry_dns::tcp_connection;
+use crate::node_configurator::node_configurator_standard::{app, standard};
+use crate::node_configurator::RealDirsWrapper;
+use crate::sub_lib::tokio_wrappers::{TokioListenerWrapper, TokioListenerWrapperReal};
 use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperReal;
 use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperTrait;
+use dns_utility_lib::dns_modifier_factory::{DnsModifierFactory, DnsModifierFactoryReal};
 use masq_lib::shared_schema::ConfiguratorError;
 use masq_lib::utils::localhost;
 
 pub struct DnsSocketServer {
     socket_wrapper: Box<dyn UdpSocketWrapperTrait>,
+    listener_wrapper: Box<dyn TokioListenerWrapper>,
     buf: [u8; 65536],
+    bypass_domains: Vec<String>,
+    forwarder_opt: Option<DnsForwarder>,
+    dns_modifier_factory: Box<dyn DnsModifierFactory + Send>,
+    metrics: Arc<Metrics>,
 }
 
 impl Future for DnsSocketServer {
@@ -27,8 +42,42 @@ impl Future for DnsSocketServer {
         let logger = Logger::new("EntryDnsServer");
         loop {
             let mut buffer = self.buf;
+            let mut progress = false;
+            match self.listener_wrapper.poll_accept() {
+                Ok(Async::Ready((stream, client_addr))) => {
+                    progress = true;
+                    tokio::spawn(tcp_connection::serve(
+                        stream,
+                        client_addr,
+                        self.bypass_domains.clone(),
+                        self.forwarder_upstreams(),
+                        self.metrics.clone(),
+                    ));
+                }
+                Ok(Async::NotReady) => (),
+                Err(e) => warning!(logger, "Error accepting DNS over TCP connection: {}", e),
+            }
+            if let Some(forwarder) = self.forwarder_opt.as_mut() {
+                match forwarder.poll_response(&mut buffer) {
+                    Ok(Async::Ready(Some((len, client_addr)))) => {
+                        progress = true;
+                        if let Err(e) = self.socket_wrapper.send_to(&buffer[0..len], client_addr) {
+                            error!(logger, "Unrecoverable error sending to UdpSocket: {}", e);
+                            return Err(());
+                        }
+                    }
+                    Ok(Async::Ready(None)) => progress = true,
+                    Ok(Async::NotReady) => (),
+                    Err(e) => {
+                        // Often just an ICMP complaint about an earlier query; keep going.
+                        progress = true;
+                        warning!(logger, "Error receiving from upstream DNS server: {}", e);
+                    }
+                }
+            }
             let (len, socket_addr) = match self.socket_wrapper.recv_from(&mut buffer) {
                 Ok(Async::Ready((len, socket_addr))) => (len, socket_addr),
+                Ok(Async::NotReady) if progress => continue,
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Err(e) => {
                     error!(
@@ -38,7 +87,27 @@ impl Future for DnsSocketServer {
                     return Err(());
                 }
             };
-            let response_length = processing::process(&mut buffer, len, &socket_addr, &logger);
+            if let Some(forwarder) = self.forwarder_opt.as_mut() {
+                if processing::should_forward(&mut buffer, len, &self.bypass_domains) {
+                    if let Err(e) = forwarder.forward(&mut buffer, len, socket_addr) {
+                        warning!(
+                            logger,
+                            "Couldn't forward DNS query from {}: {}",
+                            socket_addr,
+                            e
+                        );
+                    }
+                    continue;
+                }
+            }
+            let response_length = processing::process(
+                &mut buffer,
+                len,
+                &socket_addr,
+                Transport::Udp,
+                &logger,
+                &self.metrics,
+            );
             if let Err(e) = self
                 .socket_wrapper
                 .send_to(&buffer[0..response_length], socket_addr)
@@ -57,13 +126,29 @@ impl SocketServer<()> for DnsSocketServer {
 
     fn initialize_as_privileged(
         &mut self,
-        _args: &[String],
-        _streams: &mut StdStreams<'_>,
+        args: &[String],
+        streams: &mut StdStreams<'_>,
     ) -> Result<(), ConfiguratorError> {
+        let logger = Logger::new("EntryDnsServer");
         let socket_addr = SocketAddr::new(localhost(), DNS_PORT);
         self.socket_wrapper
             .bind(socket_addr)
             .unwrap_or_else(|e| panic!("Cannot bind socket to {:?}: {:?}", socket_addr, e));
+        self.listener_wrapper
+            .bind(socket_addr)
+            .unwrap_or_else(|e| panic!("Cannot bind listener to {:?}: {:?}", socket_addr, e));
+        let app = app();
+        self.bypass_domains = match standard::make_service_mode_multi_config(
+            &RealDirsWrapper {},
+            &app,
+            args,
+            streams,
+        ) {
+            Ok(multi_config) => standard::dns_bypass_domains(&multi_config),
+            // The Bootstrapper will complain about the configuration; no need to do it twice.
+            Err(_) => vec![],
+        };
+        self.forwarder_opt = self.make_forwarder(&logger);
         Ok(())
     }
 
@@ -81,7 +166,75 @@ impl DnsSocketServer {
     pub fn new() -> DnsSocketServer {
         DnsSocketServer {
             socket_wrapper: Box::new(UdpSocketWrapperReal::new()),
+            listener_wrapper: Box::new(TokioListenerWrapperReal::new()),
             buf: [0; 65536],
+            bypass_domains: vec![],
+            forwarder_opt: None,
+            dns_modifier_factory: Box::new(DnsModifierFactoryReal::new()),
+            metrics: metrics(),
+        }
+    }
+
+    fn forwarder_upstreams(&self) -> Vec<SocketAddr> {
+        match &self.forwarder_opt {
+            Some(forwarder) => forwarder.upstreams().to_vec(),
+            None => vec![],
+        }
+    }
+
+    fn make_forwarder(&self, logger: &Logger) -> Option<DnsForwarder> {
+        let upstreams = self.find_upstreams(logger);
+        if upstreams.is_empty() {
+            return None;
+        }
+        let mut forwarder = DnsForwarder::new(Box::new(UdpSocketWrapperReal::new()), upstreams);
+        match forwarder.bind() {
+            Ok(_) => {
+                info!(
+                    logger,
+                    "Forwarding bypassed DNS queries to {:?}",
+                    forwarder.upstreams()
+                );
+                Some(forwarder)
+            }
+            Err(e) => {
+                warning!(
+                    logger,
+                    "Cannot bind socket to forward DNS queries; no DNS queries will be forwarded: {:?}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    fn find_upstreams(&self, logger: &Logger) -> Vec<SocketAddr> {
+        let dns_modifier = match self.dns_modifier_factory.make() {
+            Some(dns_modifier) => dns_modifier,
+            None => {
+                warning!(
+                    logger,
+                    "Don't know how to find this system's DNS servers; no DNS queries will be forwarded"
+                );
+                return vec![];
+            }
+        };
+        match dns_modifier.saved_nameservers() {
+            Ok(nameservers) => nameservers
+                .into_iter()
+                .flat_map(|nameserver| IpAddr::from_str(&nameserver).ok())
+                // That's us; don't forward queries in a circle
+                .filter(|ip_addr| *ip_addr != localhost())
+                .map(|ip_addr| SocketAddr::new(ip_addr, DNS_PORT))
+                .collect(),
+            Err(e) => {
+                warning!(
+                    logger,
+                    "Can't find this system's DNS servers; no DNS queries will be forwarded: {}",
+                    e
+                );
+                vec![]
+            }
         }
     }
 }
@@ -99,7 +252,10 @@ mod tests {
     use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperTrait;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
+    use crate::test_utils::tokio_wrapper_mocks::TokioListenerWrapperMock;
+    use dns_utility_lib::dns_modifier::DnsModifier;
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
+    use masq_lib::utils::find_free_port;
     use std::borrow::Borrow;
     use std::borrow::BorrowMut;
     use std::clone::Clone;
@@ -111,8 +267,56 @@ mod tests {
     use std::str::FromStr;
     use std::sync::Arc;
     use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
     use tokio;
-    use trust_dns::op::ResponseCode;
+    use trust_dns::client::{Client, SyncClient};
+    use trust_dns::op::{OpCode, ResponseCode};
+    use trust_dns::rr::{DNSClass, Name, RData, RecordType};
+    use trust_dns::tcp::TcpClientConnection;
+    use trust_dns::udp::UdpClientConnection;
+
+    struct DnsModifierMock {
+        saved_nameservers_result: Result<Vec<String>, String>,
+    }
+
+    impl DnsModifier for DnsModifierMock {
+        fn type_name(&self) -> &'static str {
+            "DnsModifierMock"
+        }
+
+        fn subvert(&self) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn revert(&self) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn inspect(&self, _stdout: &mut (dyn io::Write + Send)) -> Result<(), String> {
+            unimplemented!()
+        }
+
+        fn saved_nameservers(&self) -> Result<Vec<String>, String> {
+            self.saved_nameservers_result.clone()
+        }
+    }
+
+    struct DnsModifierFactoryMock {
+        saved_nameservers_result_opt: Option<Result<Vec<String>, String>>,
+    }
+
+    impl DnsModifierFactory for DnsModifierFactoryMock {
+        fn make(&self) -> Option<Box<dyn DnsModifier>> {
+            self.saved_nameservers_result_opt
+                .clone()
+                .map(|saved_nameservers_result| {
+                    Box::new(DnsModifierMock {
+                        saved_nameservers_result,
+                    }) as Box<dyn DnsModifier>
+                })
+        }
+    }
 
     struct UdpSocketWrapperMockGuts {
         log: Vec<String>,
@@ -198,16 +402,100 @@ mod tests {
     #[test]
     fn uses_standard_dns_port() {
         let socket_wrapper = make_socket_wrapper_mock();
+        let listener_bind_params_arc = Arc::new(Mutex::new(vec![]));
         let mut subject = make_instrumented_subject(socket_wrapper.clone());
+        subject.listener_wrapper = Box::new(
+            TokioListenerWrapperMock::new()
+                .bind_params(&listener_bind_params_arc)
+                .bind_result(Ok(())),
+        );
 
         subject
-            .initialize_as_privileged(&[], &mut FakeStreamHolder::new().streams())
+            .initialize_as_privileged(
+                &["MASQNode".to_string()],
+                &mut FakeStreamHolder::new().streams(),
+            )
             .unwrap();
 
         let unwrapped_guts = socket_wrapper.guts.lock().unwrap();
         let borrowed_guts = unwrapped_guts.borrow();
         let log = &borrowed_guts.log;
-        assert_eq!(log[0], "bind ('127.0.0.1:53')")
+        assert_eq!(log[0], "bind ('127.0.0.1:53')");
+        let listener_bind_params = listener_bind_params_arc.lock().unwrap();
+        assert_eq!(
+            *listener_bind_params,
+            vec![SocketAddr::from_str("127.0.0.1:53").unwrap()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot bind listener to 127.0.0.1:53")]
+    fn complains_if_the_tcp_listener_cannot_bind() {
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.listener_wrapper = Box::new(
+            TokioListenerWrapperMock::new().bind_result(Err(Error::from(ErrorKind::AddrInUse))),
+        );
+
+        let _ = subject.initialize_as_privileged(
+            &["MASQNode".to_string()],
+            &mut FakeStreamHolder::new().streams(),
+        );
+    }
+
+    #[test]
+    fn logs_tcp_accept_errors_without_giving_up() {
+        init_test_logging();
+        let socket_wrapper = make_socket_wrapper_mock();
+        socket_wrapper
+            .recv_from_results
+            .lock()
+            .unwrap()
+            .push(Err(Error::from(ErrorKind::BrokenPipe)));
+        let mut subject = make_instrumented_subject(socket_wrapper);
+        subject.listener_wrapper = Box::new(
+            TokioListenerWrapperMock::new()
+                .poll_accept_result(Err(Error::from(ErrorKind::ConnectionAborted))),
+        );
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        TestLogHandler::new().exists_log_containing(
+            "WARN: EntryDnsServer: Error accepting DNS over TCP connection: connection aborted",
+        );
+    }
+
+    #[test]
+    fn answers_edns_queries_over_udp_and_tcp_on_the_same_port() {
+        let server_addr = SocketAddr::new(localhost(), find_free_port());
+        let mut socket_wrapper = UdpSocketWrapperReal::new();
+        socket_wrapper.bind(server_addr).unwrap();
+        let mut listener_wrapper = TokioListenerWrapperReal::new();
+        listener_wrapper.bind(server_addr).unwrap();
+        let mut subject = DnsSocketServer::new();
+        subject.socket_wrapper = Box::new(socket_wrapper);
+        subject.listener_wrapper = Box::new(listener_wrapper);
+        thread::spawn(move || tokio::run(subject));
+        let name = Name::from_str("booga.com.").unwrap();
+        let udp_client = SyncClient::new(UdpClientConnection::new(server_addr).unwrap());
+        let tcp_client = SyncClient::new(TcpClientConnection::new(server_addr).unwrap());
+
+        let udp_response = udp_client
+            .query(&name, DNSClass::IN, RecordType::A)
+            .unwrap();
+        let tcp_response = tcp_client
+            .query(&name, DNSClass::IN, RecordType::A)
+            .unwrap();
+
+        for response in &[udp_response, tcp_response] {
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+            assert_eq!(response.answers().len(), 1);
+            assert_eq!(
+                response.answers()[0].rdata(),
+                &RData::A(std::net::Ipv4Addr::LOCALHOST)
+            );
+            assert_eq!(response.edns().unwrap().max_payload(), 1232);
+        }
     }
 
     #[test]
@@ -365,10 +653,168 @@ mod tests {
         ]))
     }
 
+    #[test]
+    fn initialize_as_privileged_prepares_to_forward_to_saved_nameservers() {
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.dns_modifier_factory = Box::new(DnsModifierFactoryMock {
+            saved_nameservers_result_opt: Some(Ok(vec![
+                "192.168.1.1".to_string(),
+                "127.0.0.1".to_string(),
+                "booga".to_string(),
+                "10.0.0.1".to_string(),
+            ])),
+        });
+
+        subject
+            .initialize_as_privileged(
+                &[
+                    "MASQNode".to_string(),
+                    "--dns-bypass".to_string(),
+                    "LAN,home.arpa".to_string(),
+                ],
+                &mut FakeStreamHolder::new().streams(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            subject.bypass_domains,
+            vec!["lan".to_string(), "home.arpa".to_string()]
+        );
+        assert_eq!(
+            subject.forwarder_opt.unwrap().upstreams(),
+            &[
+                SocketAddr::from_str("192.168.1.1:53").unwrap(),
+                SocketAddr::from_str("10.0.0.1:53").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn initialize_as_privileged_does_without_forwarding_if_saved_nameservers_are_unavailable() {
+        init_test_logging();
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+        subject.dns_modifier_factory = Box::new(DnsModifierFactoryMock {
+            saved_nameservers_result_opt: Some(Err("This system has no DNS settings".to_string())),
+        });
+
+        subject
+            .initialize_as_privileged(
+                &["MASQNode".to_string()],
+                &mut FakeStreamHolder::new().streams(),
+            )
+            .unwrap();
+
+        assert_eq!(subject.forwarder_opt.is_none(), true);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: EntryDnsServer: Can't find this system's DNS servers; no DNS queries will be forwarded: This system has no DNS settings",
+        );
+    }
+
+    #[test]
+    fn initialize_as_privileged_does_without_forwarding_on_unrecognized_systems() {
+        let mut subject = make_instrumented_subject(make_socket_wrapper_mock());
+
+        subject
+            .initialize_as_privileged(
+                &["MASQNode".to_string()],
+                &mut FakeStreamHolder::new().streams(),
+            )
+            .unwrap();
+
+        assert_eq!(subject.forwarder_opt.is_none(), true);
+    }
+
+    #[test]
+    fn forwards_bypassed_and_non_address_queries_and_answers_the_rest_locally() {
+        let upstream = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        upstream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let server_addr = SocketAddr::new(localhost(), find_free_port());
+        let mut socket_wrapper = UdpSocketWrapperReal::new();
+        socket_wrapper.bind(server_addr).unwrap();
+        let mut forwarder = DnsForwarder::new(
+            Box::new(UdpSocketWrapperReal::new()),
+            vec![upstream.local_addr().unwrap()],
+        );
+        forwarder.bind().unwrap();
+        let subject = DnsSocketServer {
+            socket_wrapper: Box::new(socket_wrapper),
+            listener_wrapper: Box::new(TokioListenerWrapperMock::new()),
+            buf: [0; 65536],
+            bypass_domains: vec!["lan".to_string()],
+            forwarder_opt: Some(forwarder),
+            dns_modifier_factory: Box::new(DnsModifierFactoryMock {
+                saved_nameservers_result_opt: None,
+            }),
+            metrics: Arc::new(Metrics::default()),
+        };
+        thread::spawn(move || tokio::run(subject));
+        let client = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buf = [0u8; 512];
+
+        for (transaction_id, name, record_type) in &[
+            (0x1111u16, "printer.lan", RecordType::A),
+            (0x2222u16, "booga.com", RecordType::MX),
+        ] {
+            let length = make_query(&mut buf, *transaction_id, name, *record_type);
+            client.send_to(&buf[0..length], server_addr).unwrap();
+            let (length, from) = upstream.recv_from(&mut buf).unwrap();
+            let mut facade = PacketFacade::new(&mut buf, length);
+            assert_eq!(facade.get_queries().unwrap()[0].get_query_name(), *name);
+            facade.set_query(false);
+            facade.set_rcode(ResponseCode::NXDomain.low());
+            upstream.send_to(&buf[0..length], from).unwrap();
+            let (length, _) = client.recv_from(&mut buf).unwrap();
+            let facade = PacketFacade::new(&mut buf, length);
+            assert_eq!(facade.get_transaction_id(), Some(*transaction_id));
+            assert_eq!(facade.get_rcode(), Some(ResponseCode::NXDomain.low()));
+        }
+
+        let length = make_query(&mut buf, 0x3333, "booga.com", RecordType::A);
+        client.send_to(&buf[0..length], server_addr).unwrap();
+        let (length, _) = client.recv_from(&mut buf).unwrap();
+        let facade = PacketFacade::new(&mut buf, length);
+        assert_eq!(facade.get_transaction_id(), Some(0x3333));
+        assert_eq!(facade.get_rcode(), Some(ResponseCode::NoError.low()));
+        assert_eq!(
+            facade.get_answers().unwrap()[0].get_rdata(),
+            &[127, 0, 0, 1]
+        );
+    }
+
+    fn make_query(
+        buf: &mut [u8],
+        transaction_id: u16,
+        name: &str,
+        record_type: RecordType,
+    ) -> usize {
+        for byte in buf.iter_mut() {
+            *byte = 0;
+        }
+        let mut facade = PacketFacade::new(buf, 12);
+        facade.set_transaction_id(transaction_id);
+        facade.set_query(true);
+        facade.set_opcode(OpCode::Query.into());
+        facade.set_recursion_desired(true);
+        facade.add_query(name, record_type.into(), DNSClass::IN.into());
+        facade.get_length()
+    }
+
     fn make_instrumented_subject(socket_wrapper: Box<UdpSocketWrapperMock>) -> DnsSocketServer {
         DnsSocketServer {
             socket_wrapper,
+            listener_wrapper: Box::new(TokioListenerWrapperMock::new().bind_result(Ok(()))),
             buf: [0; 65536],
+            bypass_domains: vec![],
+            forwarder_opt: None,
+            dns_modifier_factory: Box::new(DnsModifierFactoryMock {
+                saved_nameservers_result_opt: None,
+            }),
+            metrics: Arc::new(Metrics::default()),
         }
     }
 }
@@ -0,0 +1,396 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::entry_dns::packet_facade::PacketFacade;
+use crate::sub_lib::logger::Logger;
+use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperTrait;
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::prelude::Async;
+
+pub const FORWARDED_QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_PENDING_QUERIES: usize = 4096;
+
+struct PendingQuery {
+    client_transaction_id: u16,
+    client_addr: SocketAddr,
+    upstream: SocketAddr,
+    sent_at: Instant,
+}
+
+// Relays DNS queries to the resolvers the system used before it was subverted, and their answers
+// back. Each forwarded query gets a fresh random transaction ID, so that answers can be matched
+// to clients and so that upstream answers can't easily be spoofed.
+pub struct DnsForwarder {
+    socket_wrapper: Box<dyn UdpSocketWrapperTrait>,
+    upstreams: Vec<SocketAddr>,
+    pending: HashMap<u16, PendingQuery>,
+    next_upstream: usize,
+    timeout: Duration,
+    logger: Logger,
+}
+
+impl DnsForwarder {
+    pub fn new(socket_wrapper: Box<dyn UdpSocketWrapperTrait>, upstreams: Vec<SocketAddr>) -> Self {
+        // One socket can only talk to one address family; prefer IPv4 if we have a choice.
+        let use_ipv4 = upstreams.iter().any(|upstream| upstream.is_ipv4());
+        DnsForwarder {
+            socket_wrapper,
+            upstreams: upstreams
+                .into_iter()
+                .filter(|upstream| upstream.is_ipv4() == use_ipv4)
+                .collect(),
+            pending: HashMap::new(),
+            next_upstream: 0,
+            timeout: FORWARDED_QUERY_TIMEOUT,
+            logger: Logger::new("EntryDnsServer"),
+        }
+    }
+
+    pub fn upstreams(&self) -> &[SocketAddr] {
+        &self.upstreams
+    }
+
+    pub fn bind(&mut self) -> io::Result<bool> {
+        let ip_addr = match self.upstreams.first() {
+            Some(upstream) if upstream.is_ipv6() => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            _ => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        };
+        self.socket_wrapper.bind(SocketAddr::new(ip_addr, 0))
+    }
+
+    pub fn forward(
+        &mut self,
+        buf: &mut [u8],
+        length: usize,
+        client_addr: SocketAddr,
+    ) -> io::Result<()> {
+        self.remove_expired_queries();
+        if self.pending.len() >= MAX_PENDING_QUERIES {
+            warning!(
+                self.logger,
+                "{} DNS queries are already waiting for upstream answers; dropping query from {}",
+                self.pending.len(),
+                client_addr
+            );
+            return Ok(());
+        }
+        let transaction_id = self.make_transaction_id();
+        let client_transaction_id = {
+            let mut facade = PacketFacade::new(buf, length);
+            match facade.get_transaction_id() {
+                Some(client_transaction_id) => {
+                    facade.set_transaction_id(transaction_id);
+                    client_transaction_id
+                }
+                None => return Ok(()),
+            }
+        };
+        let upstream = self.upstreams[self.next_upstream];
+        self.next_upstream = (self.next_upstream + 1) % self.upstreams.len();
+        match self.socket_wrapper.send_to(&buf[0..length], upstream)? {
+            Async::Ready(_) => {
+                self.pending.insert(
+                    transaction_id,
+                    PendingQuery {
+                        client_transaction_id,
+                        client_addr,
+                        upstream,
+                        sent_at: Instant::now(),
+                    },
+                );
+            }
+            Async::NotReady => debug!(
+                self.logger,
+                "Couldn't forward DNS query from {} to {} without blocking; dropping it",
+                client_addr,
+                upstream
+            ),
+        }
+        Ok(())
+    }
+
+    // Ready(None) means something arrived, but it wasn't an answer to any query we're waiting on.
+    pub fn poll_response(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<Async<Option<(usize, SocketAddr)>>, io::Error> {
+        let (length, from) = match self.socket_wrapper.recv_from(buf)? {
+            Async::Ready(pair) => pair,
+            Async::NotReady => return Ok(Async::NotReady),
+        };
+        let mut facade = PacketFacade::new(buf, length);
+        let transaction_id = match facade.get_transaction_id() {
+            Some(transaction_id) => transaction_id,
+            None => return Ok(Async::Ready(None)),
+        };
+        let expected = match self.pending.get(&transaction_id) {
+            Some(pending) => pending.upstream == from,
+            None => false,
+        };
+        if !expected {
+            debug!(
+                self.logger,
+                "Ignoring unexpected DNS response {:04X} from {}", transaction_id, from
+            );
+            return Ok(Async::Ready(None));
+        }
+        let pending = self
+            .pending
+            .remove(&transaction_id)
+            .expect("Pending query disappeared");
+        facade.set_transaction_id(pending.client_transaction_id);
+        Ok(Async::Ready(Some((length, pending.client_addr))))
+    }
+
+    fn make_transaction_id(&self) -> u16 {
+        loop {
+            let transaction_id = rand::random::<u16>();
+            if !self.pending.contains_key(&transaction_id) {
+                return transaction_id;
+            }
+        }
+    }
+
+    fn remove_expired_queries(&mut self) {
+        let timeout = self.timeout;
+        self.pending
+            .retain(|_, pending| pending.sent_at.elapsed() < timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::udp_socket_wrapper_mock::UdpSocketWrapperMock;
+    use std::io::ErrorKind;
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+
+    fn make_packet(transaction_id: u16) -> Vec<u8> {
+        let mut packet = vec![0u8; 12];
+        PacketFacade::new(&mut packet, 12).set_transaction_id(transaction_id);
+        packet
+    }
+
+    fn transaction_id_of(packet: &[u8]) -> u16 {
+        let mut packet = packet.to_vec();
+        let length = packet.len();
+        PacketFacade::new(&mut packet, length)
+            .get_transaction_id()
+            .unwrap()
+    }
+
+    fn pending_query(client_transaction_id: u16, client: &str, upstream: &str) -> PendingQuery {
+        PendingQuery {
+            client_transaction_id,
+            client_addr: SocketAddr::from_str(client).unwrap(),
+            upstream: SocketAddr::from_str(upstream).unwrap(),
+            sent_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn bind_uses_an_ephemeral_port_in_the_upstreams_address_family() {
+        let bind_params_arc = Arc::new(Mutex::new(vec![]));
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .bind_params(&bind_params_arc)
+            .bind_result(Ok(true));
+        let mut subject = DnsForwarder::new(
+            Box::new(socket_wrapper),
+            vec![
+                SocketAddr::from_str("[2001:4860:4860::8888]:53").unwrap(),
+                SocketAddr::from_str("192.168.1.1:53").unwrap(),
+            ],
+        );
+
+        let result = subject.bind();
+
+        assert_eq!(result.unwrap(), true);
+        assert_eq!(
+            subject.upstreams(),
+            &[SocketAddr::from_str("192.168.1.1:53").unwrap()]
+        );
+        assert_eq!(
+            *bind_params_arc.lock().unwrap(),
+            vec![SocketAddr::from_str("0.0.0.0:0").unwrap()]
+        );
+    }
+
+    #[test]
+    fn bind_uses_ipv6_if_there_are_only_ipv6_upstreams() {
+        let bind_params_arc = Arc::new(Mutex::new(vec![]));
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .bind_params(&bind_params_arc)
+            .bind_result(Ok(true));
+        let mut subject = DnsForwarder::new(
+            Box::new(socket_wrapper),
+            vec![SocketAddr::from_str("[2001:4860:4860::8888]:53").unwrap()],
+        );
+
+        subject.bind().unwrap();
+
+        assert_eq!(
+            *bind_params_arc.lock().unwrap(),
+            vec![SocketAddr::from_str("[::]:0").unwrap()]
+        );
+    }
+
+    #[test]
+    fn forward_disguises_transaction_ids_and_takes_turns_among_upstreams() {
+        let send_to_params_arc = Arc::new(Mutex::new(vec![]));
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .send_to_params(&send_to_params_arc)
+            .send_to_result(Ok(Async::Ready(12)))
+            .send_to_result(Ok(Async::Ready(12)))
+            .send_to_result(Ok(Async::Ready(12)));
+        let upstream_1 = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let upstream_2 = SocketAddr::from_str("192.168.1.2:53").unwrap();
+        let client = SocketAddr::from_str("127.0.0.1:5678").unwrap();
+        let mut subject = DnsForwarder::new(Box::new(socket_wrapper), vec![upstream_1, upstream_2]);
+
+        for client_transaction_id in &[0x1234u16, 0x2345, 0x3456] {
+            let mut packet = make_packet(*client_transaction_id);
+            subject.forward(&mut packet, 12, client).unwrap();
+        }
+
+        let send_to_params = send_to_params_arc.lock().unwrap();
+        assert_eq!(
+            send_to_params
+                .iter()
+                .map(|(_, upstream)| *upstream)
+                .collect::<Vec<SocketAddr>>(),
+            vec![upstream_1, upstream_2, upstream_1]
+        );
+        assert_eq!(subject.pending.len(), 3);
+        send_to_params
+            .iter()
+            .zip(&[0x1234u16, 0x2345, 0x3456])
+            .for_each(|((packet, upstream), client_transaction_id)| {
+                let pending = subject.pending.get(&transaction_id_of(packet)).unwrap();
+                assert_eq!(pending.client_transaction_id, *client_transaction_id);
+                assert_eq!(pending.client_addr, client);
+                assert_eq!(pending.upstream, *upstream);
+            });
+    }
+
+    #[test]
+    fn forward_reports_errors_from_the_socket() {
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .send_to_result(Err(io::Error::from(ErrorKind::ConnectionRefused)));
+        let mut subject = DnsForwarder::new(
+            Box::new(socket_wrapper),
+            vec![SocketAddr::from_str("192.168.1.1:53").unwrap()],
+        );
+        let mut packet = make_packet(0x1234);
+
+        let result = subject.forward(
+            &mut packet,
+            12,
+            SocketAddr::from_str("127.0.0.1:5678").unwrap(),
+        );
+
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::ConnectionRefused);
+        assert_eq!(subject.pending.is_empty(), true);
+    }
+
+    #[test]
+    fn forward_forgets_queries_that_upstreams_never_answered() {
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .send_to_result(Ok(Async::Ready(12)))
+            .send_to_result(Ok(Async::Ready(12)));
+        let mut subject = DnsForwarder::new(
+            Box::new(socket_wrapper),
+            vec![SocketAddr::from_str("192.168.1.1:53").unwrap()],
+        );
+        subject.timeout = Duration::from_millis(0);
+        let client = SocketAddr::from_str("127.0.0.1:5678").unwrap();
+
+        subject
+            .forward(&mut make_packet(0x1234), 12, client)
+            .unwrap();
+        subject
+            .forward(&mut make_packet(0x2345), 12, client)
+            .unwrap();
+
+        assert_eq!(subject.pending.len(), 1);
+        assert_eq!(
+            subject
+                .pending
+                .values()
+                .next()
+                .unwrap()
+                .client_transaction_id,
+            0x2345
+        );
+    }
+
+    #[test]
+    fn poll_response_restores_the_client_transaction_id_and_finds_the_client() {
+        let upstream = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .recv_from_result(make_packet(0xABCD), Ok(Async::Ready((12, upstream))));
+        let mut subject = DnsForwarder::new(Box::new(socket_wrapper), vec![upstream]);
+        subject.pending.insert(
+            0xABCD,
+            pending_query(0x1234, "127.0.0.1:5678", "192.168.1.1:53"),
+        );
+        let mut buf = [0u8; 100];
+
+        let result = subject.poll_response(&mut buf).unwrap();
+
+        assert_eq!(
+            result,
+            Async::Ready(Some((12, SocketAddr::from_str("127.0.0.1:5678").unwrap())))
+        );
+        assert_eq!(transaction_id_of(&buf[0..12]), 0x1234);
+        assert_eq!(subject.pending.is_empty(), true);
+    }
+
+    #[test]
+    fn poll_response_ignores_unexpected_transaction_ids_and_sources() {
+        let upstream = SocketAddr::from_str("192.168.1.1:53").unwrap();
+        let impostor = SocketAddr::from_str("6.6.6.6:53").unwrap();
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .recv_from_result(make_packet(0x9999), Ok(Async::Ready((12, upstream))))
+            .recv_from_result(make_packet(0xABCD), Ok(Async::Ready((12, impostor))))
+            .recv_from_result(vec![0xAB], Ok(Async::Ready((1, upstream))));
+        let mut subject = DnsForwarder::new(Box::new(socket_wrapper), vec![upstream]);
+        subject.pending.insert(
+            0xABCD,
+            pending_query(0x1234, "127.0.0.1:5678", "192.168.1.1:53"),
+        );
+        let mut buf = [0u8; 100];
+
+        let results = (0..3)
+            .map(|_| subject.poll_response(&mut buf).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            vec![Async::Ready(None), Async::Ready(None), Async::Ready(None)]
+        );
+        assert_eq!(subject.pending.contains_key(&0xABCD), true);
+    }
+
+    #[test]
+    fn poll_response_passes_along_not_ready_and_errors() {
+        let socket_wrapper = UdpSocketWrapperMock::new()
+            .recv_from_result(vec![], Ok(Async::NotReady))
+            .recv_from_result(vec![], Err(io::Error::from(ErrorKind::ConnectionReset)));
+        let mut subject = DnsForwarder::new(
+            Box::new(socket_wrapper),
+            vec![SocketAddr::from_str("192.168.1.1:53").unwrap()],
+        );
+        let mut buf = [0u8; 100];
+
+        let first_result = subject.poll_response(&mut buf);
+        let second_result = subject.poll_response(&mut buf);
+
+        assert_eq!(first_result.unwrap(), Async::NotReady);
+        assert_eq!(
+            second_result.err().unwrap().kind(),
+            ErrorKind::ConnectionReset
+        );
+    }
+}
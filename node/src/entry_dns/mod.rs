@@ -3,4 +3,6 @@
 #[macro_use]
 pub mod packet_facade; // public only so that it can be used by the integration test
 pub mod dns_socket_server;
+mod forwarder;
 mod processing;
+mod tcp_connection;
@@ -484,6 +484,16 @@ impl<'a> PacketFacade<'a> {
         self.length = 12
     }
 
+    // Keeps the header and the queries, but drops the answers, authorities, and additionals
+    pub fn clear_resource_records(&mut self) -> bool {
+        let queries_end = try_flg!(self.find_queries_end());
+        PacketFacade::u16_to(0x0000, &mut self.buf, 6);
+        PacketFacade::u16_to(0x0000, &mut self.buf, 8);
+        PacketFacade::u16_to(0x0000, &mut self.buf, 10);
+        self.length = queries_end;
+        true
+    }
+
     fn establish_high_water(&mut self, candidate: usize) {
         self.length = max(self.length, candidate);
     }
@@ -1440,6 +1450,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn clears_resource_records_but_keeps_queries() {
+        let mut buf: [u8; 200] = [0; 200];
+        let length = {
+            let mut subject = PacketFacade::new(&mut buf, 12);
+            subject.set_transaction_id(0x1234);
+            subject.add_query("a.b.c", 0x0001, 0x0001);
+            subject.add_answer("b.c.d", 1001, 1002, 1003, &[0x01, 0x02]);
+            subject.add_authority("c.d.e", 1004, 1005, 1006, &[0x03, 0x04]);
+            subject.add_additional("", 41, 4096, 0x8000, &[]);
+            subject.get_length()
+        };
+        let mut subject = PacketFacade::new(&mut buf, length);
+
+        let result = subject.clear_resource_records();
+
+        assert_eq!(result, true);
+        assert_eq!(subject.get_transaction_id(), Some(0x1234));
+        let queries = subject.get_queries().unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].get_query_name(), "a.b.c");
+        assert_eq!(subject.get_answers().unwrap().len(), 0);
+        assert_eq!(subject.get_authorities().unwrap().len(), 0);
+        assert_eq!(subject.get_additionals().unwrap().len(), 0);
+        assert_eq!(subject.get_length(), 12 + 7 + 4);
+    }
+
+    #[test]
+    fn clear_resource_records_complains_about_unparseable_queries() {
+        let mut buf: [u8; 100] = [0; 100];
+        PacketFacade::u16_to(0x0001, &mut buf, 4);
+        let mut subject = PacketFacade::new(&mut buf, 12);
+
+        let result = subject.clear_resource_records();
+
+        assert_eq!(result, false);
+    }
+
+    #[test]
+    fn handles_opt_record_in_additionals() {
+        let mut buf: [u8; 200] = [0; 200];
+        let length = {
+            let mut subject = PacketFacade::new(&mut buf, 12);
+            subject.add_query("a.b.c", 0x0001, 0x0001);
+            subject.add_additional("", 41, 1452, 0x0000_8000, &[0x00, 0x0A, 0x00, 0x00]);
+            subject.get_length()
+        };
+        let subject = PacketFacade::new(&mut buf, length);
+
+        let additionals = subject.get_additionals().unwrap();
+
+        assert_eq!(additionals.len(), 1);
+        assert_eq!(additionals[0].get_name(), "");
+        assert_eq!(additionals[0].get_resource_type(), 41);
+        assert_eq!(additionals[0].get_resource_class(), 1452);
+        assert_eq!(additionals[0].get_time_to_live(), 0x0000_8000);
+        assert_eq!(additionals[0].get_rdata(), &[0x00, 0x0A, 0x00, 0x00]);
+    }
+
     #[test]
     fn returns_none_if_getting_transaction_id_busts_length() {
         let mut buf: [u8; 100] = [0; 100];
@@ -3,6 +3,8 @@ use super::packet_facade::PacketFacade;
 use super::packet_facade::Query;
 use super::packet_facade::ResourceRecord;
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::Metrics;
+use std::cmp::max;
 use std::convert::From;
 use std::convert::TryFrom;
 use std::net::SocketAddr;
@@ -15,18 +17,76 @@ use trust_dns::rr::RecordType;
 
 const HEADER_BYTES: usize = 12;
 const UNKNOWN: &str = "<unknown>";
+const MAX_UDP_RESPONSE_WITHOUT_EDNS: usize = 512;
+const MAX_TCP_RESPONSE: usize = 65535;
+// What we tell EDNS clients we'll accept over UDP: small enough to avoid IP fragmentation
+const EDNS_UDP_PAYLOAD_SIZE: u16 = 1232;
+const EDNS_VERSION: u8 = 0;
+const EDNS_DNSSEC_OK: u32 = 0x0000_8000;
+// BADVERS is RCODE 16: 1 in the OPT record's extended RCODE, 0 in the header
+const EDNS_BADVERS_HIGH: u8 = 1;
 
-pub fn process(buf: &mut [u8], length: usize, addr: &SocketAddr, logger: &Logger) -> usize {
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transport {
+    Udp,
+    Tcp,
+}
+
+struct Edns {
+    max_payload: u16,
+    version: u8,
+    dnssec_ok: bool,
+}
+
+impl Edns {
+    fn from_request(facade: &PacketFacade) -> Option<Edns> {
+        let additionals = facade.get_additionals()?;
+        let opt = additionals
+            .iter()
+            .find(|record| record.get_resource_type() == u16::from(RecordType::OPT))?;
+        let flags = opt.get_time_to_live();
+        Some(Edns {
+            max_payload: opt.get_resource_class(),
+            version: (flags >> 16) as u8,
+            dnssec_ok: (flags & EDNS_DNSSEC_OK) != 0,
+        })
+    }
+}
+
+pub fn process(
+    buf: &mut [u8],
+    length: usize,
+    addr: &SocketAddr,
+    transport: Transport,
+    logger: &Logger,
+    metrics: &Metrics,
+) -> usize {
     let mut facade = PacketFacade::new(buf, length);
     let request_record = RequestRecord {
         timestamp: Instant::now(),
         opcode: facade.get_opcode().unwrap_or(0xFF),
         queries: facade.get_queries().unwrap_or_default(),
     };
+    let edns_opt = Edns::from_request(&facade);
 
-    let response_size = make_response(&mut facade);
+    let mut response_size = match &edns_opt {
+        Some(edns) if edns.version != EDNS_VERSION => {
+            make_error(&mut facade, ResponseCode::NoError.low());
+            add_opt(&mut facade, edns, EDNS_BADVERS_HIGH)
+        }
+        Some(edns) => {
+            make_response(&mut facade);
+            add_opt(&mut facade, edns, 0)
+        }
+        None => make_response(&mut facade),
+    };
+    let max_response_size = max_response_size(transport, &edns_opt);
+    if response_size > max_response_size {
+        response_size = truncate(&mut facade, &edns_opt, max_response_size);
+    }
 
     let latency = request_record.timestamp.elapsed();
+    metrics.record_dns_query(latency);
     let response_record = ResponseRecord {
         latency_ns: latency.as_nanos() as u64,
         rcode: facade.get_rcode().unwrap_or(0xFF),
@@ -36,6 +96,43 @@ pub fn process(buf: &mut [u8], length: usize, addr: &SocketAddr, logger: &Logger
     response_size
 }
 
+pub fn make_server_failure(buf: &mut [u8], length: usize) -> usize {
+    make_error(
+        &mut PacketFacade::new(buf, length),
+        ResponseCode::ServFail.low(),
+    )
+}
+
+// Queries we can't usefully answer with localhost: names the user wants kept off the MASQ
+// Network, and record types other than addresses. These go to the system's original resolvers.
+pub fn should_forward(buf: &mut [u8], length: usize, bypass_domains: &[String]) -> bool {
+    let facade = PacketFacade::new(buf, length);
+    if facade.get_opcode() != Some(u8::from(OpCode::Query)) {
+        return false;
+    }
+    let queries = match facade.get_queries() {
+        None => return false,
+        Some(q) => q,
+    };
+    queries.iter().any(|query| {
+        if query.get_query_class() != u16::from(DNSClass::IN) {
+            return false;
+        }
+        match RecordType::from(query.get_query_type()) {
+            RecordType::A | RecordType::AAAA => is_bypassed(query.get_query_name(), bypass_domains),
+            _ => true,
+        }
+    })
+}
+
+fn is_bypassed(name: &str, bypass_domains: &[String]) -> bool {
+    let name = name.trim_end_matches('.').to_lowercase();
+    bypass_domains.iter().any(|domain| {
+        name == *domain
+            || (name.ends_with(domain.as_str()) && name[..name.len() - domain.len()].ends_with('.'))
+    })
+}
+
 fn make_response(mut facade: &mut PacketFacade) -> usize {
     match facade.get_opcode() {
         None => return make_format_error(facade),
@@ -47,7 +144,8 @@ fn make_response(mut facade: &mut PacketFacade) -> usize {
         && facade.set_truncated(false)
         && facade.set_recursion_available(true)
         && facade.set_authenticated_data(false)
-        && facade.set_checking_disabled(false))
+        && facade.set_checking_disabled(false)
+        && facade.clear_resource_records())
     {
         return make_format_error(facade);
     }
@@ -82,6 +180,42 @@ fn make_response(mut facade: &mut PacketFacade) -> usize {
     facade.get_length()
 }
 
+fn add_opt(facade: &mut PacketFacade, edns: &Edns, extended_rcode: u8) -> usize {
+    let flags = (u32::from(extended_rcode) << 24)
+        | (u32::from(EDNS_VERSION) << 16)
+        | if edns.dnssec_ok { EDNS_DNSSEC_OK } else { 0 };
+    facade.add_additional(
+        "",
+        RecordType::OPT.into(),
+        EDNS_UDP_PAYLOAD_SIZE,
+        flags,
+        &[],
+    );
+    facade.get_length()
+}
+
+fn max_response_size(transport: Transport, edns_opt: &Option<Edns>) -> usize {
+    match (transport, edns_opt) {
+        (Transport::Tcp, _) => MAX_TCP_RESPONSE,
+        (Transport::Udp, Some(edns)) => {
+            max(MAX_UDP_RESPONSE_WITHOUT_EDNS, edns.max_payload as usize)
+        }
+        (Transport::Udp, None) => MAX_UDP_RESPONSE_WITHOUT_EDNS,
+    }
+}
+
+// Too big to send: keep the queries if they fit and tell the client to ask again over TCP
+fn truncate(facade: &mut PacketFacade, edns_opt: &Option<Edns>, max_response_size: usize) -> usize {
+    if !facade.clear_resource_records() || (facade.get_length() > max_response_size) {
+        facade.clear();
+    }
+    facade.set_truncated(true);
+    match edns_opt {
+        Some(edns) => add_opt(facade, edns, 0),
+        None => facade.get_length(),
+    }
+}
+
 fn display(opcode: u8) -> &'static str {
     match OpCode::from_u8(opcode) {
         Ok(OpCode::Notify) => "Notify",
@@ -186,8 +320,94 @@ mod tests {
     use std::net::Ipv4Addr;
     use std::net::SocketAddr;
     use std::net::SocketAddrV4;
+    use std::str::FromStr;
     use std::time::Instant;
 
+    fn make_query(buf: &mut [u8], opcode: OpCode, queries: &[(&str, RecordType)]) -> usize {
+        let mut facade = PacketFacade::new(buf, HEADER_BYTES);
+        facade.set_transaction_id(0x1234);
+        facade.set_query(true);
+        facade.set_opcode(opcode.into());
+        for (name, record_type) in queries {
+            facade.add_query(name, (*record_type).into(), DNSClass::IN.into());
+        }
+        facade.get_length()
+    }
+
+    #[test]
+    fn should_forward_address_queries_only_for_bypassed_domains_and_their_subdomains() {
+        let bypass_domains = vec!["lan".to_string(), "example.com".to_string()];
+        vec![
+            ("lan", true),
+            ("printer.lan", true),
+            ("Printer.LAN", true),
+            ("example.com", true),
+            ("www.example.com", true),
+            ("plan", false),
+            ("notexample.com", false),
+            ("example.com.au", false),
+            ("booga.com", false),
+        ]
+        .into_iter()
+        .for_each(|(name, expected)| {
+            vec![RecordType::A, RecordType::AAAA]
+                .into_iter()
+                .for_each(|record_type| {
+                    let mut buf: [u8; 500] = [0; 500];
+                    let length = make_query(&mut buf, OpCode::Query, &[(name, record_type)]);
+
+                    let result = should_forward(&mut buf, length, &bypass_domains);
+
+                    assert_eq!(result, expected, "{} {}", name, record_type);
+                })
+        });
+    }
+
+    #[test]
+    fn should_forward_queries_for_records_other_than_addresses() {
+        vec![
+            RecordType::MX,
+            RecordType::TXT,
+            RecordType::SRV,
+            RecordType::NS,
+            RecordType::PTR,
+        ]
+        .into_iter()
+        .for_each(|record_type| {
+            let mut buf: [u8; 500] = [0; 500];
+            let length = make_query(&mut buf, OpCode::Query, &[("booga.com", record_type)]);
+
+            let result = should_forward(&mut buf, length, &[]);
+
+            assert_eq!(result, true, "{}", record_type);
+        });
+    }
+
+    #[test]
+    fn should_forward_if_any_query_needs_it() {
+        let mut buf: [u8; 500] = [0; 500];
+        let length = make_query(
+            &mut buf,
+            OpCode::Query,
+            &[("booga.com", RecordType::A), ("booga.com", RecordType::MX)],
+        );
+
+        let result = should_forward(&mut buf, length, &[]);
+
+        assert_eq!(result, true);
+    }
+
+    #[test]
+    fn should_not_forward_other_opcodes_or_malformed_packets() {
+        let mut buf: [u8; 500] = [0; 500];
+        let length = make_query(&mut buf, OpCode::Status, &[("booga.com", RecordType::MX)]);
+        assert_eq!(should_forward(&mut buf, length, &[]), false);
+
+        let mut buf: [u8; 500] = [0; 500];
+        let length = make_query(&mut buf, OpCode::Query, &[("booga.com", RecordType::MX)]);
+        assert_eq!(should_forward(&mut buf, length - 1, &[]), false);
+    }
+
     #[test]
     fn returns_format_error_if_queries_overrun() {
         let memory: [u8; 100] = [0; 100];
@@ -206,7 +426,14 @@ mod tests {
         let truncated_buf = &mut correct_buf[0..truncated_length];
         let addr = SocketAddr::from(SocketAddrV4::new(Ipv4Addr::new(101, 102, 103, 104), 53));
 
-        let result = process(truncated_buf, truncated_length, &addr, &Logger::new(""));
+        let result = process(
+            truncated_buf,
+            truncated_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
 
         check_format_error_message(truncated_buf, 0x1234);
         assert_eq!(result, HEADER_BYTES);
@@ -228,7 +455,14 @@ mod tests {
         };
         let addr = SocketAddr::from(SocketAddrV4::new(Ipv4Addr::new(101, 102, 103, 104), 53));
 
-        let rsp_length = process(&mut buf, req_length, &addr, &Logger::new(""));
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
 
         check_not_implemented_error_message(&mut buf, 0x1234, OpCode::Status.into());
         assert_eq!(rsp_length, HEADER_BYTES);
@@ -249,7 +483,14 @@ mod tests {
         };
         let addr = SocketAddr::from(SocketAddrV4::new(Ipv4Addr::new(101, 102, 103, 104), 53));
 
-        let rsp_length = process(&mut buf, req_length, &addr, &Logger::new(""));
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
 
         check_not_implemented_error_message(&mut buf, 0x1234, OpCode::Query.into());
         assert_eq!(rsp_length, HEADER_BYTES);
@@ -270,7 +511,14 @@ mod tests {
         };
         let addr = SocketAddr::from(SocketAddrV4::new(Ipv4Addr::new(101, 102, 103, 104), 53));
 
-        let rsp_length = process(&mut buf, req_length, &addr, &Logger::new(""));
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
 
         check_not_implemented_error_message(&mut buf, 0x1234, OpCode::Query.into());
         assert_eq!(rsp_length, HEADER_BYTES);
@@ -304,7 +552,9 @@ mod tests {
                 &mut buf,
                 req_length,
                 &addr,
+                Transport::Udp,
                 &Logger::new("two_queries_are_answered"),
+                &Metrics::default(),
             )
         };
 
@@ -538,6 +788,222 @@ mod tests {
         );
     }
 
+    fn make_edns_request(buf: &mut [u8], max_payload: u16, flags: u32) -> usize {
+        let mut request = PacketFacade::new(buf, HEADER_BYTES);
+        request.set_transaction_id(0x1234);
+        request.set_query(true);
+        request.set_opcode(OpCode::Query.into());
+        request.add_query("booga.com", RecordType::A.into(), DNSClass::IN.into());
+        request.add_additional("", RecordType::OPT.into(), max_payload, flags, &[]);
+        request.get_length()
+    }
+
+    fn make_big_request(buf: &mut [u8], query_count: usize) -> usize {
+        let mut request = PacketFacade::new(buf, HEADER_BYTES);
+        request.set_transaction_id(0x1234);
+        request.set_query(true);
+        request.set_opcode(OpCode::Query.into());
+        for i in 0..query_count {
+            request.add_query(
+                &format!("name-{:02}.example.com", i),
+                RecordType::A.into(),
+                DNSClass::IN.into(),
+            );
+        }
+        request.get_length()
+    }
+
+    #[test]
+    fn edns_request_gets_an_answer_and_an_opt_record() {
+        let mut buf: [u8; 500] = [0; 500];
+        let req_length = make_edns_request(&mut buf, 1452, EDNS_DNSSEC_OK);
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
+
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.get_transaction_id(), Some(0x1234));
+        assert_eq!(response.get_rcode(), Some(ResponseCode::NoError.low()));
+        assert_eq!(response.is_truncated(), Some(false));
+        let answers = response.get_answers().unwrap();
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0].get_name(), "booga.com");
+        assert_eq!(answers[0].get_rdata(), &[127, 0, 0, 1]);
+        let additionals = response.get_additionals().unwrap();
+        assert_eq!(additionals.len(), 1);
+        assert_eq!(additionals[0].get_name(), "");
+        assert_eq!(
+            additionals[0].get_resource_type(),
+            u16::from(RecordType::OPT)
+        );
+        assert_eq!(additionals[0].get_resource_class(), EDNS_UDP_PAYLOAD_SIZE);
+        assert_eq!(additionals[0].get_time_to_live(), EDNS_DNSSEC_OK);
+        assert_eq!(additionals[0].get_rdata().len(), 0);
+    }
+
+    #[test]
+    fn edns_request_with_unknown_version_gets_badvers() {
+        let mut buf: [u8; 500] = [0; 500];
+        let req_length = make_edns_request(&mut buf, 1452, 0x0001_0000);
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
+
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.get_rcode(), Some(ResponseCode::NoError.low()));
+        assert_eq!(response.get_answers().unwrap().len(), 0);
+        let additionals = response.get_additionals().unwrap();
+        assert_eq!(additionals.len(), 1);
+        assert_eq!(
+            additionals[0].get_time_to_live(),
+            u32::from(EDNS_BADVERS_HIGH) << 24
+        );
+        assert_eq!(
+            (u16::from(EDNS_BADVERS_HIGH) << 4) | u16::from(response.get_rcode().unwrap()),
+            u16::from(ResponseCode::BADVERS)
+        );
+    }
+
+    #[test]
+    fn oversized_udp_response_is_truncated_to_its_queries() {
+        let mut buf: [u8; 2000] = [0; 2000];
+        let req_length = make_big_request(&mut buf, 12);
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
+
+        assert_eq!(rsp_length, req_length);
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.is_truncated(), Some(true));
+        assert_eq!(response.get_rcode(), Some(ResponseCode::NoError.low()));
+        assert_eq!(response.get_queries().unwrap().len(), 12);
+        assert_eq!(response.get_answers().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn udp_response_too_big_even_for_its_queries_is_truncated_to_its_header() {
+        let mut buf: [u8; 2000] = [0; 2000];
+        let req_length = make_big_request(&mut buf, 21);
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
+
+        assert_eq!(rsp_length, HEADER_BYTES);
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.is_truncated(), Some(true));
+        assert_eq!(response.get_queries().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn big_response_fits_if_edns_makes_room_for_it() {
+        let mut buf: [u8; 2000] = [0; 2000];
+        let req_length = {
+            let length = make_big_request(&mut buf, 12);
+            let mut request = PacketFacade::new(&mut buf, length);
+            request.add_additional("", RecordType::OPT.into(), 4096, 0, &[]);
+            request.get_length()
+        };
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
+
+        assert_eq!(rsp_length > MAX_UDP_RESPONSE_WITHOUT_EDNS, true);
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.is_truncated(), Some(false));
+        assert_eq!(response.get_answers().unwrap().len(), 12);
+        assert_eq!(response.get_additionals().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn big_response_is_not_truncated_over_tcp() {
+        let mut buf: [u8; 2000] = [0; 2000];
+        let req_length = make_big_request(&mut buf, 12);
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+
+        let rsp_length = process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Tcp,
+            &Logger::new(""),
+            &Metrics::default(),
+        );
+
+        assert_eq!(rsp_length > MAX_UDP_RESPONSE_WITHOUT_EDNS, true);
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.is_truncated(), Some(false));
+        assert_eq!(response.get_answers().unwrap().len(), 12);
+    }
+
+    #[test]
+    fn process_counts_the_query_and_its_latency_in_metrics() {
+        let mut buf: [u8; 500] = [0; 500];
+        let req_length = make_query(&mut buf, OpCode::Query, &[("name", RecordType::A)]);
+        let addr = SocketAddr::from_str("101.102.103.104:53").unwrap();
+        let metrics = Metrics::default();
+
+        process(
+            &mut buf,
+            req_length,
+            &addr,
+            Transport::Udp,
+            &Logger::new(""),
+            &metrics,
+        );
+
+        assert_eq!(metrics.dns_queries.get(), 1);
+        assert_eq!(metrics.dns_latency_nanos.get() > 0, true);
+    }
+
+    #[test]
+    fn make_server_failure_produces_servfail() {
+        let mut buf: [u8; 500] = [0; 500];
+        let req_length = make_edns_request(&mut buf, 1452, 0);
+
+        let rsp_length = make_server_failure(&mut buf, req_length);
+
+        assert_eq!(rsp_length, HEADER_BYTES);
+        let response = PacketFacade::new(&mut buf, rsp_length);
+        assert_eq!(response.get_transaction_id(), Some(0x1234));
+        assert_eq!(response.is_query(), Some(false));
+        assert_eq!(response.get_rcode(), Some(ResponseCode::ServFail.low()));
+    }
+
     fn check_format_error_message(mut buf: &mut [u8], transaction_id: u16) {
         let facade = PacketFacade::new(&mut buf, HEADER_BYTES);
         assert_eq!(facade.get_transaction_id(), Some(transaction_id));
@@ -0,0 +1,292 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::entry_dns::forwarder::FORWARDED_QUERY_TIMEOUT;
+use crate::entry_dns::processing;
+use crate::entry_dns::processing::Transport;
+use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::Metrics;
+use bytes::{Bytes, BytesMut};
+use std::io;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::codec::length_delimited;
+use tokio::codec::{Framed, LengthDelimitedCodec};
+use tokio::net::TcpStream;
+use tokio::prelude::future;
+use tokio::prelude::*;
+use tokio::timer::timeout;
+
+pub const TCP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// DNS over TCP (RFC 7766) puts a two-byte length in front of every message. Queries are answered
+// in the order they arrive; the ones that belong to the system's original DNS servers are relayed
+// to them over TCP connections of their own, trying each in turn until one answers.
+pub fn serve(
+    stream: TcpStream,
+    client_addr: SocketAddr,
+    bypass_domains: Vec<String>,
+    upstreams: Vec<SocketAddr>,
+    metrics: Arc<Metrics>,
+) -> impl Future<Item = (), Error = ()> + Send {
+    let logger = Logger::new("EntryDnsServer");
+    let error_logger = logger.clone();
+    let (responses, queries) = frame(stream).split();
+    queries
+        .timeout(TCP_IDLE_TIMEOUT)
+        .map_err(from_timeout_error)
+        .and_then(move |query| {
+            answer(
+                query,
+                client_addr,
+                &bypass_domains,
+                &upstreams,
+                &logger,
+                &metrics,
+            )
+        })
+        .forward(responses)
+        .map(|_| ())
+        .map_err(move |e| {
+            debug!(
+                error_logger,
+                "DNS over TCP connection from {} closed: {}", client_addr, e
+            )
+        })
+}
+
+fn answer(
+    query: BytesMut,
+    client_addr: SocketAddr,
+    bypass_domains: &[String],
+    upstreams: &[SocketAddr],
+    logger: &Logger,
+    metrics: &Metrics,
+) -> Box<dyn Future<Item = Bytes, Error = io::Error> + Send> {
+    let length = query.len();
+    let mut buf = vec![0u8; 65536];
+    buf[0..length].copy_from_slice(&query);
+    if !upstreams.is_empty() && processing::should_forward(&mut buf, length, bypass_domains) {
+        return forward(query.freeze(), upstreams.to_vec(), logger.clone());
+    }
+    let response_length = processing::process(
+        &mut buf,
+        length,
+        &client_addr,
+        Transport::Tcp,
+        logger,
+        metrics,
+    );
+    Box::new(future::ok(Bytes::from(&buf[0..response_length])))
+}
+
+// Answers SERVFAIL once every upstream has failed
+fn forward(
+    query: Bytes,
+    upstreams: Vec<SocketAddr>,
+    logger: Logger,
+) -> Box<dyn Future<Item = Bytes, Error = io::Error> + Send> {
+    let (upstream, remaining_upstreams) = match upstreams.split_first() {
+        Some((upstream, remaining_upstreams)) => (*upstream, remaining_upstreams.to_vec()),
+        None => {
+            let mut failure = query.to_vec();
+            let length = failure.len();
+            let response_length = processing::make_server_failure(&mut failure, length);
+            return Box::new(future::ok(Bytes::from(&failure[0..response_length])));
+        }
+    };
+    let retry_query = query.clone();
+    Box::new(exchange(query, upstream).or_else(move |e| {
+        warning!(
+            logger,
+            "Couldn't forward DNS query to {} over TCP: {}",
+            upstream,
+            e
+        );
+        forward(retry_query, remaining_upstreams, logger)
+    }))
+}
+
+fn exchange(
+    query: Bytes,
+    upstream: SocketAddr,
+) -> impl Future<Item = Bytes, Error = io::Error> + Send {
+    TcpStream::connect(&upstream)
+        .and_then(move |stream| frame(stream).send(query))
+        .and_then(|framed| framed.into_future().map_err(|(e, _)| e))
+        .and_then(|(response_opt, _)| match response_opt {
+            Some(response) => Ok(response.freeze()),
+            None => Err(io::Error::from(ErrorKind::UnexpectedEof)),
+        })
+        .timeout(FORWARDED_QUERY_TIMEOUT)
+        .map_err(from_timeout_error)
+}
+
+fn frame(stream: TcpStream) -> Framed<TcpStream, LengthDelimitedCodec> {
+    length_delimited::Builder::new()
+        .length_field_length(2)
+        .new_framed(stream)
+}
+
+fn from_timeout_error(e: timeout::Error<io::Error>) -> io::Error {
+    if e.is_elapsed() {
+        io::Error::from(ErrorKind::TimedOut)
+    } else if e.is_inner() {
+        e.into_inner().expect("Inner error disappeared")
+    } else {
+        io::Error::new(ErrorKind::Other, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entry_dns::packet_facade::PacketFacade;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::str::FromStr;
+    use std::thread;
+    use trust_dns::client::{Client, SyncClient};
+    use trust_dns::op::ResponseCode;
+    use trust_dns::rr::{DNSClass, Name, RData, RecordType};
+    use trust_dns::tcp::TcpClientConnection;
+
+    fn start_subject(bypass_domains: Vec<String>, upstreams: Vec<SocketAddr>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let listener =
+                tokio::net::TcpListener::from_std(listener, &tokio::reactor::Handle::default())
+                    .unwrap();
+            let server = listener.incoming().map_err(|_| ()).for_each(move |stream| {
+                let client_addr = stream.peer_addr().unwrap();
+                tokio::spawn(serve(
+                    stream,
+                    client_addr,
+                    bypass_domains.clone(),
+                    upstreams.clone(),
+                    Arc::new(Metrics::default()),
+                ));
+                Ok(())
+            });
+            tokio::run(server);
+        });
+        server_addr
+    }
+
+    // Answers one query on one connection with NXDOMAIN, and reports the query it got
+    fn start_upstream() -> (SocketAddr, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let upstream_addr = listener.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut length_bytes = [0u8; 2];
+            stream.read_exact(&mut length_bytes).unwrap();
+            let length = ((length_bytes[0] as usize) << 8) | (length_bytes[1] as usize);
+            let mut query = vec![0u8; length];
+            stream.read_exact(&mut query).unwrap();
+            let mut response = query.clone();
+            {
+                let mut facade = PacketFacade::new(&mut response, length);
+                facade.set_query(false);
+                facade.set_rcode(ResponseCode::NXDomain.low());
+            }
+            stream.write_all(&length_bytes).unwrap();
+            stream.write_all(&response).unwrap();
+            query
+        });
+        (upstream_addr, handle)
+    }
+
+    #[test]
+    fn answers_address_queries_with_localhost_over_tcp() {
+        let server_addr = start_subject(vec![], vec![]);
+        let client = SyncClient::new(TcpClientConnection::new(server_addr).unwrap());
+        let name = Name::from_str("booga.com.").unwrap();
+
+        let ipv4_response = client.query(&name, DNSClass::IN, RecordType::A).unwrap();
+        let ipv6_response = client.query(&name, DNSClass::IN, RecordType::AAAA).unwrap();
+
+        assert_eq!(ipv4_response.response_code(), ResponseCode::NoError);
+        assert_eq!(
+            ipv4_response.answers()[0].rdata(),
+            &RData::A(std::net::Ipv4Addr::LOCALHOST)
+        );
+        assert_eq!(ipv4_response.edns().unwrap().max_payload(), 1232);
+        assert_eq!(
+            ipv6_response.answers()[0].rdata(),
+            &RData::AAAA(std::net::Ipv6Addr::LOCALHOST)
+        );
+    }
+
+    #[test]
+    fn forwards_queries_for_bypassed_domains_to_an_upstream_over_tcp() {
+        let (upstream_addr, upstream_handle) = start_upstream();
+        let server_addr = start_subject(vec!["lan".to_string()], vec![upstream_addr]);
+        let client = SyncClient::new(TcpClientConnection::new(server_addr).unwrap());
+
+        let response = client
+            .query(
+                &Name::from_str("printer.lan.").unwrap(),
+                DNSClass::IN,
+                RecordType::A,
+            )
+            .unwrap();
+
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        let mut query = upstream_handle.join().unwrap();
+        let length = query.len();
+        let facade = PacketFacade::new(&mut query, length);
+        assert_eq!(facade.get_transaction_id(), Some(response.id()));
+        assert_eq!(
+            facade.get_queries().unwrap()[0].get_query_name(),
+            "printer.lan"
+        );
+    }
+
+    #[test]
+    fn forwards_to_the_next_upstream_if_the_first_cant_be_reached() {
+        let dead_upstream = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let (upstream_addr, upstream_handle) = start_upstream();
+        let server_addr =
+            start_subject(vec!["lan".to_string()], vec![dead_upstream, upstream_addr]);
+        let client = SyncClient::new(TcpClientConnection::new(server_addr).unwrap());
+
+        let response = client
+            .query(
+                &Name::from_str("printer.lan.").unwrap(),
+                DNSClass::IN,
+                RecordType::A,
+            )
+            .unwrap();
+
+        assert_eq!(response.response_code(), ResponseCode::NXDomain);
+        let mut query = upstream_handle.join().unwrap();
+        let length = query.len();
+        let facade = PacketFacade::new(&mut query, length);
+        assert_eq!(facade.get_transaction_id(), Some(response.id()));
+    }
+
+    #[test]
+    fn answers_servfail_if_the_upstream_cant_be_reached() {
+        let dead_upstream = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let server_addr = start_subject(vec![], vec![dead_upstream]);
+        let client = SyncClient::new(TcpClientConnection::new(server_addr).unwrap());
+
+        let response = client
+            .query(
+                &Name::from_str("booga.com.").unwrap(),
+                DNSClass::IN,
+                RecordType::MX,
+            )
+            .unwrap();
+
+        assert_eq!(response.response_code(), ResponseCode::ServFail);
+    }
+}
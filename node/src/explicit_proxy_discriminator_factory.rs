@@ -0,0 +1,168 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::Discriminator;
+use crate::discriminator::DiscriminatorFactory;
+use crate::http_request_start_finder::HttpRequestStartFinder;
+use crate::null_masquerader::NullMasquerader;
+use crate::opaque_discriminator_factory::OpaqueFramer;
+use crate::proxy_server::http_protocol_pack::HttpProtocolPack;
+use crate::sub_lib::framer::FramedChunk;
+use crate::sub_lib::framer::Framer;
+use crate::sub_lib::http_packet_framer::HttpPacketFramer;
+
+// A browser talking to an explicit proxy sends HTTP requests until it sends a CONNECT; after
+// that, the stream is a tunnel, and whatever comes through it is passed along exactly as it
+// arrives.
+pub struct ExplicitProxyFramer {
+    http_framer: HttpPacketFramer,
+    tunneling: bool,
+    tunnel_framer: OpaqueFramer,
+}
+
+impl Framer for ExplicitProxyFramer {
+    fn add_data(&mut self, data: &[u8]) {
+        if self.tunneling {
+            self.tunnel_framer.add_data(data);
+        } else {
+            self.http_framer.add_data(data);
+        }
+    }
+
+    fn take_frame(&mut self) -> Option<FramedChunk> {
+        if self.tunneling {
+            return self.tunnel_framer.take_frame();
+        }
+        let frame_opt = self.http_framer.take_frame();
+        if let Some(ref frame) = frame_opt {
+            if HttpProtocolPack::is_connect(&frame.chunk) {
+                // The client may not have waited for our 200 before starting its conversation
+                // with the server; those bytes belong to the tunnel, not to the HTTP framer.
+                self.tunneling = true;
+                self.tunnel_framer
+                    .add_data(&self.http_framer.take_unframed_data());
+            }
+        }
+        frame_opt
+    }
+}
+
+impl Default for ExplicitProxyFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExplicitProxyFramer {
+    pub fn new() -> ExplicitProxyFramer {
+        ExplicitProxyFramer {
+            http_framer: HttpPacketFramer::new(Box::new(HttpRequestStartFinder {})),
+            tunneling: false,
+            tunnel_framer: OpaqueFramer::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ExplicitProxyDiscriminatorFactory {}
+
+impl DiscriminatorFactory for ExplicitProxyDiscriminatorFactory {
+    fn make(&self) -> Discriminator {
+        Discriminator::new(
+            Box::new(ExplicitProxyFramer::new()),
+            vec![Box::new(NullMasquerader::new())],
+        )
+    }
+
+    fn duplicate(&self) -> Box<dyn DiscriminatorFactory> {
+        Box::new(ExplicitProxyDiscriminatorFactory {})
+    }
+}
+
+impl ExplicitProxyDiscriminatorFactory {
+    pub fn new() -> ExplicitProxyDiscriminatorFactory {
+        Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discriminator::UnmaskedChunk;
+
+    #[test]
+    fn discriminator_factory_duplicate_works() {
+        let subject = ExplicitProxyDiscriminatorFactory::new();
+
+        subject.duplicate();
+
+        // no panic; test passes
+    }
+
+    #[test]
+    fn frames_absolute_uri_requests_as_http() {
+        let data = b"GET http://example.com/index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let mut subject = ExplicitProxyDiscriminatorFactory::new().make();
+
+        subject.add_data(&data[..]);
+
+        assert_eq!(
+            subject.take_chunk(),
+            Some(UnmaskedChunk::new(data.to_vec(), true, true))
+        );
+        assert_eq!(subject.take_chunk(), None);
+    }
+
+    #[test]
+    fn passes_data_through_unframed_after_a_connect() {
+        let connect = b"CONNECT example.com:22 HTTP/1.1\r\nHost: example.com:22\r\n\r\n";
+        let tunneled = b"SSH-2.0-OpenSSH_8.2p1\r\n";
+        let mut subject = ExplicitProxyDiscriminatorFactory::new().make();
+
+        subject.add_data(&connect[..]);
+        let connect_chunk = subject.take_chunk();
+        subject.add_data(&tunneled[..]);
+        let first_tunneled_chunk = subject.take_chunk();
+        subject.add_data(&b"GET / HTTP/1.1\r\n"[..]);
+        let second_tunneled_chunk = subject.take_chunk();
+
+        assert_eq!(
+            connect_chunk,
+            Some(UnmaskedChunk::new(connect.to_vec(), true, true))
+        );
+        assert_eq!(
+            first_tunneled_chunk,
+            Some(UnmaskedChunk::new(tunneled.to_vec(), true, true))
+        );
+        assert_eq!(
+            second_tunneled_chunk,
+            Some(UnmaskedChunk::new(
+                b"GET / HTTP/1.1\r\n".to_vec(),
+                true,
+                true
+            ))
+        );
+        assert_eq!(subject.take_chunk(), None);
+    }
+
+    #[test]
+    fn tunnels_data_that_arrives_in_the_same_read_as_the_connect() {
+        let connect = b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n";
+        let client_hello = b"\x16\x03\x01\x00\x05hello";
+        let mut data = connect.to_vec();
+        data.extend_from_slice(&client_hello[..]);
+        let mut subject = ExplicitProxyDiscriminatorFactory::new().make();
+
+        subject.add_data(&data);
+        let connect_chunk = subject.take_chunk();
+        let client_hello_chunk = subject.take_chunk();
+
+        assert_eq!(
+            connect_chunk,
+            Some(UnmaskedChunk::new(connect.to_vec(), true, true))
+        );
+        assert_eq!(
+            client_hello_chunk,
+            Some(UnmaskedChunk::new(client_hello.to_vec(), true, true))
+        );
+        assert_eq!(subject.take_chunk(), None);
+    }
+}
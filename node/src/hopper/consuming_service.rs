@@ -1,20 +1,26 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 use super::live_cores_package::LiveCoresPackage;
+use super::session_keys::HopSessionKeys;
+use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::cryptde::CryptData;
-use crate::sub_lib::cryptde::{encodex, CryptDE};
+use crate::sub_lib::cryptde::PublicKey;
 use crate::sub_lib::dispatcher::{Endpoint, InboundClientData};
 use crate::sub_lib::hopper::{IncipientCoresPackage, NoLookupIncipientCoresPackage};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
 use actix::Recipient;
 use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Instant;
 
 pub struct ConsumingService {
     cryptde: &'static dyn CryptDE,
     to_dispatcher: Recipient<TransmitDataMsg>,
     to_hopper: Recipient<InboundClientData>,
+    hop_session_keys: RefCell<HopSessionKeys>,
     logger: Logger,
 }
 
@@ -28,10 +34,15 @@ impl ConsumingService {
             cryptde,
             to_dispatcher,
             to_hopper,
+            hop_session_keys: RefCell::new(HopSessionKeys::new()),
             logger: Logger::new("ConsumingService"),
         }
     }
 
+    pub fn set_session_key_peers(&self, keys: HashSet<PublicKey>) {
+        self.hop_session_keys.borrow_mut().set_peers(keys);
+    }
+
     pub fn consume_no_lookup(&self, incipient_cores_package: NoLookupIncipientCoresPackage) {
         debug!(
             self.logger,
@@ -42,7 +53,12 @@ impl ConsumingService {
         let target_node_addr = incipient_cores_package.node_addr.clone();
         match LiveCoresPackage::from_no_lookup_incipient(incipient_cores_package, self.cryptde) {
             Ok((live_package, _)) => {
-                let encrypted_package = match encodex(self.cryptde, &target_key, &live_package) {
+                let encrypted_package = match self.hop_session_keys.borrow_mut().encode(
+                    self.cryptde,
+                    &target_key,
+                    &live_package,
+                    Instant::now(),
+                ) {
                     Ok(p) => p,
                     Err(e) => {
                         error!(
@@ -52,9 +68,7 @@ impl ConsumingService {
                         return;
                     }
                 };
-                // This port should eventually be chosen by the Traffic Analyzer somehow.
-                let socket_addrs: Vec<SocketAddr> = target_node_addr.into();
-                self.launch_lcp(encrypted_package, Endpoint::Socket(socket_addrs[0]));
+                self.launch_lcp(encrypted_package, Endpoint::Node(target_node_addr));
             }
             Err(e) => {
                 error!(
@@ -73,14 +87,18 @@ impl ConsumingService {
         );
         match LiveCoresPackage::from_incipient(incipient_cores_package, self.cryptde.borrow()) {
             Ok((live_package, next_hop)) => {
-                let encrypted_package =
-                    match encodex(self.cryptde, &next_hop.public_key, &live_package) {
-                        Ok(p) => p,
-                        Err(e) => {
-                            error!(self.logger, "Couldn't encode package: {:?}", e);
-                            return;
-                        }
-                    };
+                let encrypted_package = match self.hop_session_keys.borrow_mut().encode(
+                    self.cryptde,
+                    &next_hop.public_key,
+                    &live_package,
+                    Instant::now(),
+                ) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        error!(self.logger, "Couldn't encode package: {:?}", e);
+                        return;
+                    }
+                };
                 if &next_hop.public_key == self.cryptde.public_key() {
                     self.zero_hop(encrypted_package);
                 } else {
@@ -132,6 +150,7 @@ mod tests {
     use super::*;
     use crate::blockchain::blockchain_interface::contract_address;
     use crate::sub_lib::cryptde::PublicKey;
+    use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::dispatcher::{Component, InboundClientData};
     use crate::sub_lib::node_addr::NodeAddr;
     use crate::sub_lib::route::Route;
@@ -173,15 +192,18 @@ mod tests {
         let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
         let transmit_data_msg = dispatcher_recording.get_record::<TransmitDataMsg>(0);
         let (lcp, _) = LiveCoresPackage::from_no_lookup_incipient(package, main_cryptde()).unwrap();
-        assert_eq!(
-            &TransmitDataMsg {
-                endpoint: Endpoint::Socket(SocketAddr::from_str("1.2.1.2:1212").unwrap()),
-                last_data: false,
-                sequence_number: None,
-                data: encodex(main_cryptde(), &target_key, &lcp).unwrap().into(),
-            },
-            transmit_data_msg
-        );
+        assert_eq!(transmit_data_msg.endpoint, Endpoint::Node(target_node_addr));
+        assert_eq!(transmit_data_msg.last_data, false);
+        assert_eq!(transmit_data_msg.sequence_number, None);
+        let target_cryptde = CryptDENull::from(&target_key, DEFAULT_CHAIN_ID);
+        let actual_lcp = HopSessionKeys::new()
+            .decode::<LiveCoresPackage>(
+                &target_cryptde,
+                &CryptData::new(&transmit_data_msg.data),
+                Instant::now(),
+            )
+            .unwrap();
+        assert_eq!(actual_lcp, lcp);
     }
 
     #[test]
@@ -244,16 +266,18 @@ mod tests {
         let record = dispatcher_recording.get_record::<TransmitDataMsg>(0);
         let (expected_lcp, _) =
             LiveCoresPackage::from_incipient(incipient_cores_package, cryptde).unwrap();
-        let expected_lcp_enc = encodex(cryptde, &destination_key, &expected_lcp).unwrap();
-        assert_eq!(
-            TransmitDataMsg {
-                endpoint: Endpoint::Key(destination_key.clone()),
-                last_data: false,
-                sequence_number: None,
-                data: expected_lcp_enc.into(),
-            },
-            *record,
-        );
+        assert_eq!(record.endpoint, Endpoint::Key(destination_key.clone()));
+        assert_eq!(record.last_data, false);
+        assert_eq!(record.sequence_number, None);
+        let destination_cryptde = CryptDENull::from(&destination_key, DEFAULT_CHAIN_ID);
+        let actual_lcp = HopSessionKeys::new()
+            .decode::<LiveCoresPackage>(
+                &destination_cryptde,
+                &CryptData::new(&record.data),
+                Instant::now(),
+            )
+            .unwrap();
+        assert_eq!(actual_lcp, expected_lcp);
     }
 
     #[test]
@@ -291,18 +315,18 @@ mod tests {
         let record = hopper_recording.get_record::<InboundClientData>(0);
         let (expected_lcp, _) =
             LiveCoresPackage::from_incipient(incipient_cores_package, cryptde).unwrap();
-        let expected_lcp_enc = encodex(cryptde, &destination_key, &expected_lcp).unwrap();
         assert_eq!(
-            InboundClientData {
-                peer_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
-                reception_port: None,
-                last_data: false,
-                is_clandestine: true,
-                sequence_number: None,
-                data: expected_lcp_enc.into(),
-            },
-            *record,
+            record.peer_addr,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0)
         );
+        assert_eq!(record.reception_port, None);
+        assert_eq!(record.last_data, false);
+        assert_eq!(record.is_clandestine, true);
+        assert_eq!(record.sequence_number, None);
+        let actual_lcp = HopSessionKeys::new()
+            .decode::<LiveCoresPackage>(cryptde, &CryptData::new(&record.data), Instant::now())
+            .unwrap();
+        assert_eq!(actual_lcp, expected_lcp);
     }
 
     #[test]
@@ -3,13 +3,14 @@
 mod consuming_service;
 pub mod live_cores_package;
 mod routing_service;
+pub mod session_keys;
 
 use crate::hopper::routing_service::RoutingServiceSubs;
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::dispatcher::InboundClientData;
 use crate::sub_lib::hopper::HopperSubs;
 use crate::sub_lib::hopper::IncipientCoresPackage;
-use crate::sub_lib::hopper::{HopperConfig, NoLookupIncipientCoresPackage};
+use crate::sub_lib::hopper::{HopperConfig, NoLookupIncipientCoresPackage, SessionKeyPeersMsg};
 use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
 use actix::Actor;
@@ -104,6 +105,21 @@ impl Handler<InboundClientData> for Hopper {
     }
 }
 
+impl Handler<SessionKeyPeersMsg> for Hopper {
+    type Result = ();
+
+    fn handle(&mut self, msg: SessionKeyPeersMsg, _ctx: &mut Self::Context) -> Self::Result {
+        self.consuming_service
+            .as_ref()
+            .expect("Hopper unbound: no ConsumingService")
+            .set_session_key_peers(msg.keys.clone());
+        self.routing_service
+            .as_ref()
+            .expect("Hopper unbound: no RoutingService")
+            .set_session_key_peers(msg.keys);
+    }
+}
+
 impl Hopper {
     pub fn new(config: HopperConfig) -> Hopper {
         Hopper {
@@ -123,6 +139,7 @@ impl Hopper {
             from_hopper_client: recipient!(addr, IncipientCoresPackage),
             from_hopper_client_no_lookup: recipient!(addr, NoLookupIncipientCoresPackage),
             from_dispatcher: recipient!(addr, InboundClientData),
+            session_key_peers: recipient!(addr, SessionKeyPeersMsg),
         }
     }
 }
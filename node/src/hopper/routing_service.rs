@@ -1,23 +1,31 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use super::live_cores_package::LiveCoresPackage;
+use super::session_keys::HopSessionKeys;
 use crate::blockchain::payer::Payer;
 use crate::neighborhood::gossip::Gossip_0v1;
 use crate::sub_lib::accountant::ReportRoutingServiceProvidedMessage;
-use crate::sub_lib::cryptde::{decodex, encodex, CodexError, CryptDE, CryptData, CryptdecError};
+use crate::sub_lib::bandwidth::{bandwidth_throttle, BandwidthService, BandwidthThrottle, Traffic};
+use crate::sub_lib::cryptde::{encodex, CodexError, CryptDE, CryptData, CryptdecError, PublicKey};
 use crate::sub_lib::dispatcher::{Component, Endpoint, InboundClientData};
 use crate::sub_lib::hop::LiveHop;
 use crate::sub_lib::hopper::{ExpiredCoresPackage, HopperSubs, MessageType};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::neighborhood::{GossipFailure_0v1, NeighborhoodSubs};
 use crate::sub_lib::proxy_client::{
-    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ProxyClientSubs,
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, ProxyClientSubs, StreamPressure_0v1,
 };
 use crate::sub_lib::proxy_server::{ClientRequestPayload_0v1, ProxyServerSubs};
 use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
 use actix::Recipient;
+use futures::future::Future;
 use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
 
 pub struct RoutingServiceSubs {
     pub proxy_client_subs: ProxyClientSubs,
@@ -34,6 +42,8 @@ pub struct RoutingService {
     routing_service_subs: RoutingServiceSubs,
     per_routing_service: u64,
     per_routing_byte: u64,
+    hop_session_keys: RefCell<HopSessionKeys>,
+    bandwidth_throttle: Arc<BandwidthThrottle>,
     logger: Logger,
     is_decentralized: bool,
 }
@@ -53,11 +63,17 @@ impl RoutingService {
             routing_service_subs,
             per_routing_service,
             per_routing_byte,
+            hop_session_keys: RefCell::new(HopSessionKeys::new()),
+            bandwidth_throttle: bandwidth_throttle(),
             logger: Logger::new("RoutingService"),
             is_decentralized,
         }
     }
 
+    pub fn set_session_key_peers(&self, keys: HashSet<PublicKey>) {
+        self.hop_session_keys.borrow_mut().set_peers(keys);
+    }
+
     pub fn route(&self, ibcd: InboundClientData) {
         let data_size = ibcd.data.len();
         debug!(
@@ -70,20 +86,27 @@ impl RoutingService {
         let last_data = ibcd.last_data;
         let ibcd_but_data = ibcd.clone_but_data();
 
-        let live_package =
-            match decodex::<LiveCoresPackage>(self.main_cryptde, &CryptData::new(&ibcd.data[..])) {
-                Ok(lcp) => lcp,
-                Err(e) => {
-                    error!(
-                        self.logger,
-                        "Couldn't decode CORES package in {}-byte buffer from {}: {:?}",
-                        ibcd.data.len(),
-                        ibcd.peer_addr,
-                        e
-                    );
-                    return;
-                }
-            };
+        let decode_result = self
+            .hop_session_keys
+            .borrow_mut()
+            .decode::<LiveCoresPackage>(
+                self.main_cryptde,
+                &CryptData::new(&ibcd.data[..]),
+                Instant::now(),
+            );
+        let live_package = match decode_result {
+            Ok(lcp) => lcp,
+            Err(e) => {
+                error!(
+                    self.logger,
+                    "Couldn't decode CORES package in {}-byte buffer from {}: {:?}",
+                    ibcd.data.len(),
+                    ibcd.peer_addr,
+                    e
+                );
+                return;
+            }
+        };
 
         let next_hop = match live_package.route.next_hop(self.main_cryptde.borrow()) {
             Ok(hop) => hop,
@@ -114,7 +137,13 @@ impl RoutingService {
                 live_package.payload.len(),
                 next_hop.public_key
             );
-            self.route_data_externally(live_package, next_hop.payer, last_data);
+            self.route_data_externally(
+                sender_addr,
+                next_hop.public_key,
+                live_package,
+                next_hop.payer,
+                last_data,
+            );
         } else {
             debug!(
                 self.logger,
@@ -397,6 +426,46 @@ impl RoutingService {
                     ))
                     .expect("Neighborhood is dead")
             }
+            (Component::ProxyClient, MessageType::StreamPressure(vd)) => {
+                let stream_pressure = match StreamPressure_0v1::try_from(vd) {
+                    Ok(sp) => sp,
+                    Err(e) => {
+                        error!(self.logger, "Received unmigratable StreamPressure: {:?}", e);
+                        return;
+                    }
+                };
+                self.routing_service_subs
+                    .proxy_client_subs
+                    .stream_pressure_from_hopper
+                    .try_send(ExpiredCoresPackage::new(
+                        expired_package.immediate_neighbor,
+                        expired_package.paying_wallet,
+                        expired_package.remaining_route,
+                        stream_pressure,
+                        expired_package.payload_len,
+                    ))
+                    .expect("ProxyClient is dead")
+            }
+            (Component::ProxyServer, MessageType::StreamPressure(vd)) => {
+                let stream_pressure = match StreamPressure_0v1::try_from(vd) {
+                    Ok(sp) => sp,
+                    Err(e) => {
+                        error!(self.logger, "Received unmigratable StreamPressure: {:?}", e);
+                        return;
+                    }
+                };
+                self.routing_service_subs
+                    .proxy_server_subs
+                    .stream_pressure_from_hopper
+                    .try_send(ExpiredCoresPackage::new(
+                        expired_package.immediate_neighbor,
+                        expired_package.paying_wallet,
+                        expired_package.remaining_route,
+                        stream_pressure,
+                        expired_package.payload_len,
+                    ))
+                    .expect("ProxyServer is dead")
+            }
             (destination, payload) => error!(
                 self.logger,
                 "Attempt to send invalid combination {:?} to {:?}", payload, destination
@@ -406,12 +475,14 @@ impl RoutingService {
 
     fn route_data_externally(
         &self,
+        sender_addr: SocketAddr,
+        next_hop_key: PublicKey,
         live_package: LiveCoresPackage,
         payer: Option<Payer>,
         last_data: bool,
     ) {
         let payload_size = live_package.payload.len();
-        match payer {
+        let delay = match payer {
             Some(payer) => {
                 if !payer.owns_secret_key(&self.main_cryptde.digest()) {
                     warning!(self.logger,
@@ -428,6 +499,7 @@ impl RoutingService {
                     );
                     return;
                 }
+                let delay = self.bandwidth_delay(sender_addr, &next_hop_key, payload_size);
                 match self.routing_service_subs.to_accountant_routing.try_send(
                     ReportRoutingServiceProvidedMessage {
                         paying_wallet: payer.wallet,
@@ -441,6 +513,7 @@ impl RoutingService {
                         fatal!(self.logger, "Accountant is dead: {:?}", e);
                     }
                 }
+                delay
             }
             None => {
                 warning!(
@@ -450,7 +523,7 @@ impl RoutingService {
                 );
                 return;
             }
-        }
+        };
 
         let transmit_msg = match self.to_transmit_data_msg(live_package, last_data) {
             Ok(m) => m,
@@ -460,15 +533,53 @@ impl RoutingService {
             }
         };
 
-        debug!(
-            self.logger,
-            "Relaying {}-byte LiveCoresPackage to Dispatcher inside a TransmitDataMsg",
-            transmit_msg.data.len()
-        );
-        self.routing_service_subs
-            .to_dispatcher
-            .try_send(transmit_msg)
-            .expect("Dispatcher is dead");
+        let to_dispatcher = self.routing_service_subs.to_dispatcher.clone();
+        if delay == Duration::from_secs(0) {
+            debug!(
+                self.logger,
+                "Relaying {}-byte LiveCoresPackage to Dispatcher inside a TransmitDataMsg",
+                transmit_msg.data.len()
+            );
+            to_dispatcher
+                .try_send(transmit_msg)
+                .expect("Dispatcher is dead");
+        } else {
+            debug!(
+                self.logger,
+                "Relaying {}-byte LiveCoresPackage to Dispatcher inside a TransmitDataMsg in {}ms",
+                transmit_msg.data.len(),
+                delay.as_millis()
+            );
+            actix::spawn(Delay::new(Instant::now() + delay).then(move |_| {
+                to_dispatcher
+                    .try_send(transmit_msg)
+                    .expect("Dispatcher is dead");
+                Ok(())
+            }));
+        }
+    }
+
+    // How long the package should wait to stay within the routing bandwidth limits. A relay can't
+    // see which stream a package belongs to, so it can neither pause nor end one; dropping the
+    // package would only leave a gap the far end waits on forever. So it holds every package as
+    // long as the limits require, and its Gossip reports reduced capacity meanwhile so that new
+    // routes go around it. Later packages never wait less than earlier ones between the same
+    // neighbors, so waiting doesn't reorder them.
+    fn bandwidth_delay(
+        &self,
+        sender_addr: SocketAddr,
+        next_hop_key: &PublicKey,
+        payload_size: usize,
+    ) -> Duration {
+        self.bandwidth_throttle.charge_all(
+            BandwidthService::Routing,
+            vec![
+                Traffic::Download(sender_addr.ip()),
+                Traffic::Upload(next_hop_key.clone()),
+            ],
+            payload_size,
+            Instant::now(),
+        )
     }
 
     fn to_transmit_data_msg(
@@ -488,15 +599,20 @@ impl RoutingService {
                 }
                 Ok(p) => p,
             };
-        let next_live_package_enc =
-            match encodex(self.main_cryptde, &next_hop.public_key, &next_live_package) {
-                Ok(nlpe) => nlpe,
-                Err(e) => {
-                    let msg = format!("Couldn't serialize or encrypt outgoing LCP: {:?}", e);
-                    error!(self.logger, "{}", &msg);
-                    return Err(CryptdecError::OtherError(msg));
-                }
-            };
+        let encode_result = self.hop_session_keys.borrow_mut().encode(
+            self.main_cryptde,
+            &next_hop.public_key,
+            &next_live_package,
+            Instant::now(),
+        );
+        let next_live_package_enc = match encode_result {
+            Ok(nlpe) => nlpe,
+            Err(e) => {
+                let msg = format!("Couldn't serialize or encrypt outgoing LCP: {:?}", e);
+                error!(self.logger, "{}", &msg);
+                return Err(CryptdecError::OtherError(msg));
+            }
+        };
         Ok(TransmitDataMsg {
             endpoint: Endpoint::Key(next_hop.public_key),
             last_data,
@@ -513,13 +629,18 @@ mod tests {
     use crate::blockchain::blockchain_interface::contract_address;
     use crate::neighborhood::gossip::{GossipBuilder, Gossip_0v1};
     use crate::sub_lib::accountant::ReportRoutingServiceProvidedMessage;
-    use crate::sub_lib::cryptde::{encodex, PlainData, PublicKey};
+    use crate::sub_lib::bandwidth::{BandwidthLimits, ServiceBandwidthLimits};
+    use crate::sub_lib::cryptde::{encodex, PlainData};
     use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::hopper::{IncipientCoresPackage, MessageType, MessageType::ClientRequest};
     use crate::sub_lib::neighborhood::GossipFailure_0v1;
-    use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+    use crate::sub_lib::peer_actors::PeerActors;
+    use crate::sub_lib::proxy_client::{
+        ClientResponsePayload_0v1, DnsResolveFailure_0v1, StreamPressure_0v1,
+    };
     use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
     use crate::sub_lib::route::{Route, RouteSegment};
+    use crate::sub_lib::stream_buffer::Pressure;
     use crate::sub_lib::versioned_data::VersionedData;
     use crate::sub_lib::wallet::Wallet;
     use crate::test_utils::logging::{init_test_logging, TestLogHandler};
@@ -533,8 +654,9 @@ mod tests {
     use actix::System;
     use masq_lib::test_utils::environment_guard::EnvironmentGuard;
     use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
-    use std::net::SocketAddr;
+    use std::net::{IpAddr, SocketAddr};
     use std::str::FromStr;
+    use std::sync::Arc;
 
     #[test]
     fn dns_resolution_failures_are_reported_to_the_proxy_server() {
@@ -594,6 +716,122 @@ mod tests {
         assert_eq!(dns_resolve_failure, message.payload);
     }
 
+    #[test]
+    fn stream_pressure_for_the_proxy_server_is_routed_to_it() {
+        let main_cryptde = main_cryptde();
+        let alias_cryptde = alias_cryptde();
+        let route = route_to_proxy_server(&main_cryptde.public_key(), main_cryptde);
+        let stream_pressure =
+            StreamPressure_0v1::new(make_meaningless_stream_key(), Pressure::Pause);
+        let lcp = LiveCoresPackage::new(
+            route,
+            encodex(
+                alias_cryptde,
+                &alias_cryptde.public_key(),
+                &MessageType::StreamPressure(VersionedData::new(
+                    &crate::sub_lib::migrations::stream_pressure::MIGRATIONS,
+                    &stream_pressure.clone(),
+                )),
+            )
+            .unwrap(),
+        );
+        let data_enc = encodex(main_cryptde, &main_cryptde.public_key(), &lcp).unwrap();
+        let inbound_client_data = InboundClientData {
+            peer_addr: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+            reception_port: None,
+            sequence_number: None,
+            last_data: false,
+            is_clandestine: false,
+            data: data_enc.into(),
+        };
+        let (proxy_server, _, proxy_server_recording) = make_recorder();
+
+        let system = System::new("stream_pressure_for_the_proxy_server_is_routed_to_it");
+        let peer_actors = peer_actors_builder().proxy_server(proxy_server).build();
+        let subject = RoutingService::new(
+            main_cryptde,
+            alias_cryptde,
+            RoutingServiceSubs {
+                proxy_client_subs: peer_actors.proxy_client,
+                proxy_server_subs: peer_actors.proxy_server,
+                neighborhood_subs: peer_actors.neighborhood,
+                hopper_subs: peer_actors.hopper,
+                to_dispatcher: peer_actors.dispatcher.from_dispatcher_client,
+                to_accountant_routing: peer_actors.accountant.report_routing_service_provided,
+            },
+            100,
+            200,
+            false,
+        );
+
+        subject.route(inbound_client_data);
+
+        System::current().stop();
+        system.run();
+
+        let recordings = proxy_server_recording.lock().unwrap();
+        let message = recordings.get_record::<ExpiredCoresPackage<StreamPressure_0v1>>(0);
+        assert_eq!(stream_pressure, message.payload);
+    }
+
+    #[test]
+    fn stream_pressure_for_the_proxy_client_is_routed_to_it() {
+        let main_cryptde = main_cryptde();
+        let alias_cryptde = alias_cryptde();
+        let route = route_to_proxy_client(&main_cryptde.public_key(), main_cryptde);
+        let stream_pressure =
+            StreamPressure_0v1::new(make_meaningless_stream_key(), Pressure::Pause);
+        let lcp = LiveCoresPackage::new(
+            route,
+            encodex(
+                alias_cryptde,
+                &alias_cryptde.public_key(),
+                &MessageType::StreamPressure(VersionedData::new(
+                    &crate::sub_lib::migrations::stream_pressure::MIGRATIONS,
+                    &stream_pressure.clone(),
+                )),
+            )
+            .unwrap(),
+        );
+        let data_enc = encodex(main_cryptde, &main_cryptde.public_key(), &lcp).unwrap();
+        let inbound_client_data = InboundClientData {
+            peer_addr: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+            reception_port: None,
+            sequence_number: None,
+            last_data: false,
+            is_clandestine: false,
+            data: data_enc.into(),
+        };
+        let (proxy_client, _, proxy_client_recording) = make_recorder();
+
+        let system = System::new("stream_pressure_for_the_proxy_client_is_routed_to_it");
+        let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+        let subject = RoutingService::new(
+            main_cryptde,
+            alias_cryptde,
+            RoutingServiceSubs {
+                proxy_client_subs: peer_actors.proxy_client,
+                proxy_server_subs: peer_actors.proxy_server,
+                neighborhood_subs: peer_actors.neighborhood,
+                hopper_subs: peer_actors.hopper,
+                to_dispatcher: peer_actors.dispatcher.from_dispatcher_client,
+                to_accountant_routing: peer_actors.accountant.report_routing_service_provided,
+            },
+            100,
+            200,
+            false,
+        );
+
+        subject.route(inbound_client_data);
+
+        System::current().stop();
+        system.run();
+
+        let recordings = proxy_client_recording.lock().unwrap();
+        let message = recordings.get_record::<ExpiredCoresPackage<StreamPressure_0v1>>(0);
+        assert_eq!(stream_pressure, message.payload);
+    }
+
     #[test]
     fn logs_and_ignores_message_that_cannot_be_deserialized() {
         init_test_logging();
@@ -1084,17 +1322,18 @@ mod tests {
         let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
         let record = dispatcher_recording.get_record::<TransmitDataMsg>(0);
         let expected_lcp = lcp_a.into_next_live(main_cryptde).unwrap().1;
-        let expected_lcp_ser = PlainData::new(&serde_cbor::ser::to_vec(&expected_lcp).unwrap());
-        let expected_lcp_enc = main_cryptde.encode(&next_key, &expected_lcp_ser).unwrap();
-        assert_eq!(
-            *record,
-            TransmitDataMsg {
-                endpoint: Endpoint::Key(next_key.clone()),
-                last_data: true,
-                sequence_number: None,
-                data: expected_lcp_enc.into(),
-            }
-        );
+        assert_eq!(record.endpoint, Endpoint::Key(next_key.clone()));
+        assert_eq!(record.last_data, true);
+        assert_eq!(record.sequence_number, None);
+        let next_cryptde = CryptDENull::from(&next_key, DEFAULT_CHAIN_ID);
+        let actual_lcp = HopSessionKeys::new()
+            .decode::<LiveCoresPackage>(
+                &next_cryptde,
+                &CryptData::new(&record.data),
+                Instant::now(),
+            )
+            .unwrap();
+        assert_eq!(actual_lcp, expected_lcp);
         let accountant_recording = accountant_recording_arc.lock().unwrap();
         let message = accountant_recording.get_record::<ReportRoutingServiceProvidedMessage>(0);
         assert!(message.paying_wallet.congruent(&paying_wallet));
@@ -1109,6 +1348,79 @@ mod tests {
         )
     }
 
+    #[test]
+    fn passes_on_inbound_client_data_that_arrives_in_a_session_envelope() {
+        let _eg = EnvironmentGuard::new();
+        BAN_CACHE.clear();
+        let main_cryptde = main_cryptde();
+        let alias_cryptde = alias_cryptde();
+        let previous_cryptde = CryptDENull::from(&PublicKey::new(&[66, 66, 66]), DEFAULT_CHAIN_ID);
+        let paying_wallet = make_paying_wallet(b"wallet");
+        let (dispatcher, _, dispatcher_recording_arc) = make_recorder();
+        let next_key = PublicKey::new(&[65, 65, 65]);
+        let route = Route::one_way(
+            RouteSegment::new(
+                vec![&main_cryptde.public_key(), &next_key],
+                Component::Neighborhood,
+            ),
+            main_cryptde,
+            Some(paying_wallet),
+            Some(contract_address(DEFAULT_CHAIN_ID)),
+        )
+        .unwrap();
+        let payload = PlainData::new(&b"abcd"[..]);
+        let lcp = LiveCoresPackage::new(route, main_cryptde.encode(&next_key, &payload).unwrap());
+        let data_enc = HopSessionKeys::with_peers(
+            vec![main_cryptde.public_key().clone()]
+                .into_iter()
+                .collect(),
+        )
+        .encode(
+            &previous_cryptde,
+            main_cryptde.public_key(),
+            &lcp,
+            Instant::now(),
+        )
+        .unwrap();
+        let inbound_client_data = InboundClientData {
+            peer_addr: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+            reception_port: None,
+            last_data: false,
+            is_clandestine: true,
+            sequence_number: None,
+            data: data_enc.into(),
+        };
+        let system =
+            System::new("passes_on_inbound_client_data_that_arrives_in_a_session_envelope");
+        let peer_actors = peer_actors_builder().dispatcher(dispatcher).build();
+        let subject = RoutingService::new(
+            main_cryptde,
+            alias_cryptde,
+            RoutingServiceSubs {
+                proxy_client_subs: peer_actors.proxy_client,
+                proxy_server_subs: peer_actors.proxy_server,
+                neighborhood_subs: peer_actors.neighborhood,
+                hopper_subs: peer_actors.hopper,
+                to_dispatcher: peer_actors.dispatcher.from_dispatcher_client,
+                to_accountant_routing: peer_actors.accountant.report_routing_service_provided,
+            },
+            rate_pack_routing(103),
+            rate_pack_routing_byte(103),
+            false,
+        );
+        subject.set_session_key_peers(vec![next_key.clone()].into_iter().collect());
+
+        subject.route(inbound_client_data);
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        let record = dispatcher_recording.get_record::<TransmitDataMsg>(0);
+        assert_eq!(record.endpoint, Endpoint::Key(next_key));
+        assert_eq!(subject.hop_session_keys.borrow().inbound_count(), 1);
+        assert_eq!(subject.hop_session_keys.borrow().outbound_count(), 1);
+    }
+
     #[test]
     fn reprocesses_inbound_client_data_meant_for_this_node_and_destined_for_hopper() {
         let _eg = EnvironmentGuard::new();
@@ -1453,6 +1765,8 @@ mod tests {
         );
 
         subject.route_data_externally(
+            SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+            destination_key,
             lcp,
             paying_wallet.map(|w| w.as_payer(&PublicKey::new(b"can't pay"), &contract_address)),
             true,
@@ -1829,7 +2143,7 @@ mod tests {
         let proxy_client_recording = proxy_client_recording_arc.lock().unwrap();
         assert_eq!(proxy_client_recording.len(), 0);
         TestLogHandler::new().exists_log_containing(
-            "ERROR: RoutingService: Received unmigratable ClientRequestPayload: MigrationNotFound(DataVersion { major: 0, minor: 0 }, DataVersion { major: 0, minor: 1 })",
+            "ERROR: RoutingService: Received unmigratable ClientRequestPayload: MigrationNotFound(DataVersion { major: 0, minor: 0 }, DataVersion { major: 0, minor: 2 })",
         );
     }
 
@@ -1952,4 +2266,210 @@ mod tests {
             "ERROR: RoutingService: Received unmigratable GossipFailure: MigrationNotFound(DataVersion { major: 0, minor: 0 }, DataVersion { major: 0, minor: 1 })",
         );
     }
+
+    fn make_externally_routed_ibcd(
+        next_key: &PublicKey,
+        peer_addr: &str,
+    ) -> (InboundClientData, usize) {
+        let main_cryptde = main_cryptde();
+        let route = Route::one_way(
+            RouteSegment::new(
+                vec![&main_cryptde.public_key(), next_key],
+                Component::Neighborhood,
+            ),
+            main_cryptde,
+            Some(make_paying_wallet(b"wallet")),
+            Some(contract_address(DEFAULT_CHAIN_ID)),
+        )
+        .unwrap();
+        let payload = PlainData::new(&b"abcd"[..]);
+        let lcp = LiveCoresPackage::new(route, main_cryptde.encode(next_key, &payload).unwrap());
+        let data_ser = PlainData::new(&serde_cbor::ser::to_vec(&lcp).unwrap()[..]);
+        let data_enc = main_cryptde
+            .encode(&main_cryptde.public_key(), &data_ser)
+            .unwrap();
+        let ibcd = InboundClientData {
+            peer_addr: SocketAddr::from_str(peer_addr).unwrap(),
+            reception_port: None,
+            last_data: false,
+            is_clandestine: true,
+            sequence_number: None,
+            data: data_enc.into(),
+        };
+        (ibcd, lcp.payload.len())
+    }
+
+    fn make_throttled_subject(
+        peer_actors: PeerActors,
+        throttle: Arc<BandwidthThrottle>,
+    ) -> RoutingService {
+        let mut subject = RoutingService::new(
+            main_cryptde(),
+            alias_cryptde(),
+            RoutingServiceSubs {
+                proxy_client_subs: peer_actors.proxy_client,
+                proxy_server_subs: peer_actors.proxy_server,
+                neighborhood_subs: peer_actors.neighborhood,
+                hopper_subs: peer_actors.hopper,
+                to_dispatcher: peer_actors.dispatcher.from_dispatcher_client,
+                to_accountant_routing: peer_actors.accountant.report_routing_service_provided,
+            },
+            rate_pack_routing(103),
+            rate_pack_routing_byte(103),
+            false,
+        );
+        subject.bandwidth_throttle = throttle;
+        subject
+    }
+
+    fn make_routing_limits(routing: &str) -> BandwidthLimits {
+        BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str(routing).unwrap(),
+            exit: ServiceBandwidthLimits::default(),
+        }
+    }
+
+    #[test]
+    fn route_holds_package_that_must_wait_long_for_bandwidth_rather_than_dropping_it() {
+        let _eg = EnvironmentGuard::new();
+        BAN_CACHE.clear();
+        let (dispatcher, _, dispatcher_recording_arc) = make_recorder();
+        let (accountant, _, accountant_recording_arc) = make_recorder();
+        let next_key = PublicKey::new(&[65, 65, 66]);
+        let (ibcd, payload_size) = make_externally_routed_ibcd(&next_key, "1.2.3.5:5678");
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_routing_limits("1:0:0:0"));
+        throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Download(IpAddr::from_str("9.9.9.9").unwrap()),
+            10 * 1024,
+            Instant::now(),
+        );
+        let system = System::new(
+            "route_holds_package_that_must_wait_long_for_bandwidth_rather_than_dropping_it",
+        );
+        let peer_actors = peer_actors_builder()
+            .dispatcher(dispatcher)
+            .accountant(accountant)
+            .build();
+        let subject = make_throttled_subject(peer_actors, throttle.clone());
+
+        subject.route(ibcd);
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(dispatcher_recording.len(), 0);
+        let accountant_recording = accountant_recording_arc.lock().unwrap();
+        assert_eq!(accountant_recording.len(), 1);
+        assert_eq!(throttle.delayed_bytes(), 10 * 1024 + payload_size as u64);
+        assert_eq!(throttle.is_throttled(Instant::now()), true);
+    }
+
+    #[test]
+    fn package_held_for_upload_bandwidth_is_charged_for_download_bandwidth_too() {
+        let _eg = EnvironmentGuard::new();
+        BAN_CACHE.clear();
+        let (dispatcher, _, dispatcher_recording_arc) = make_recorder();
+        let next_key = PublicKey::new(&[65, 65, 69]);
+        let (ibcd, payload_size) = make_externally_routed_ibcd(&next_key, "1.2.3.8:5678");
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_routing_limits("1:1:0:0"));
+        throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Upload(PublicKey::new(&[1, 2, 3])),
+            10 * 1024,
+            Instant::now(),
+        );
+        let system =
+            System::new("package_held_for_upload_bandwidth_is_charged_for_download_bandwidth_too");
+        let peer_actors = peer_actors_builder().dispatcher(dispatcher).build();
+        let subject = make_throttled_subject(peer_actors, throttle.clone());
+
+        subject.route(ibcd);
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(dispatcher_recording.len(), 0);
+        let download_delay = throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Download(IpAddr::from_str("1.2.3.8").unwrap()),
+            1024,
+            Instant::now(),
+        );
+        assert_eq!(download_delay > Duration::from_secs(0), true);
+        assert_eq!(
+            throttle.delayed_bytes(),
+            10 * 1024 + payload_size as u64 + 1024
+        );
+    }
+
+    #[test]
+    fn route_holds_package_back_while_its_next_hop_is_over_its_bandwidth() {
+        let _eg = EnvironmentGuard::new();
+        BAN_CACHE.clear();
+        let (dispatcher, _, dispatcher_recording_arc) = make_recorder();
+        let (accountant, _, accountant_recording_arc) = make_recorder();
+        let next_key = PublicKey::new(&[65, 65, 67]);
+        let (ibcd, payload_size) = make_externally_routed_ibcd(&next_key, "1.2.3.6:5678");
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_routing_limits("0:0:0:1"));
+        throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Upload(next_key.clone()),
+            2048,
+            Instant::now(),
+        );
+        let system =
+            System::new("route_holds_package_back_while_its_next_hop_is_over_its_bandwidth");
+        let peer_actors = peer_actors_builder()
+            .dispatcher(dispatcher)
+            .accountant(accountant)
+            .build();
+        let subject = make_throttled_subject(peer_actors, throttle.clone());
+
+        subject.route(ibcd);
+
+        System::current().stop();
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(dispatcher_recording.len(), 0);
+        let accountant_recording = accountant_recording_arc.lock().unwrap();
+        assert_eq!(accountant_recording.len(), 1);
+        assert_eq!(throttle.delayed_bytes(), 2048 + payload_size as u64);
+    }
+
+    #[test]
+    fn route_relays_held_back_package_once_its_bandwidth_is_available() {
+        let _eg = EnvironmentGuard::new();
+        BAN_CACHE.clear();
+        let (dispatcher, _, dispatcher_recording_arc) = make_recorder();
+        let next_key = PublicKey::new(&[65, 65, 68]);
+        let (ibcd, _) = make_externally_routed_ibcd(&next_key, "1.2.3.7:5678");
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_routing_limits("0:1:0:0"));
+        throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Upload(PublicKey::new(&[1, 2, 3])),
+            1024 + 100,
+            Instant::now(),
+        );
+        let system = System::new("route_relays_held_back_package_once_its_bandwidth_is_available");
+        let peer_actors = peer_actors_builder().dispatcher(dispatcher).build();
+        let subject = make_throttled_subject(peer_actors, throttle);
+
+        subject.route(ibcd);
+
+        actix::spawn(
+            Delay::new(Instant::now() + Duration::from_millis(500)).then(|_| {
+                System::current().stop();
+                Ok(())
+            }),
+        );
+        system.run();
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        let record = dispatcher_recording.get_record::<TransmitDataMsg>(0);
+        assert_eq!(record.endpoint, Endpoint::Key(next_key));
+    }
 }
@@ -0,0 +1,732 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::sub_lib::cryptde::{
+    decodex, encodex, CodexError, CryptDE, CryptData, CryptdecError, PlainData, PublicKey,
+    SerdeCborError, SymmetricKey,
+};
+use crate::sub_lib::logger::Logger;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+pub const SESSION_KEY_LIFETIME: Duration = Duration::from_secs(600);
+pub const SESSION_KEY_MAX_PACKETS: u64 = 1_000_000;
+pub const SESSION_KEY_REOFFER_PACKETS: u64 = 64;
+pub const SESSION_KEY_REOFFER_INTERVAL: Duration = Duration::from_secs(10);
+pub const SESSION_KEY_RETENTION: Duration = Duration::from_secs(1200);
+// A sender replaces its key every SESSION_KEY_LIFETIME, so within SESSION_KEY_RETENTION it has no
+// business having more than a few keys alive. Beyond these limits, the least recently used keys
+// are forgotten to make room.
+pub const SESSION_KEY_MAX_INBOUND_PER_SENDER: usize = 4;
+pub const SESSION_KEY_MAX_INBOUND: usize = 4096;
+
+// Carried by a HopEnvelope whenever the receiver may not know the session key yet. Only the
+// symmetric key is encrypted with the receiver's public key, and the sender signs it, so the
+// public-key work is done once per offer instead of once per package.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionKeyOffer {
+    pub sender: PublicKey,
+    pub encrypted_key: CryptData,
+    pub signature: CryptData,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HopEnvelope {
+    pub key_id: u64,
+    pub offer_opt: Option<SessionKeyOffer>,
+    pub package: CryptData,
+}
+
+struct OutboundKey {
+    key_id: u64,
+    key: SymmetricKey,
+    offer: SessionKeyOffer,
+    created: Instant,
+    last_offered: Instant,
+    packets: u64,
+}
+
+struct InboundKey {
+    sender: PublicKey,
+    key: SymmetricKey,
+    last_used: Instant,
+}
+
+pub struct HopSessionKeys {
+    outbound: HashMap<PublicKey, OutboundKey>,
+    inbound: HashMap<u64, InboundKey>,
+    peers: HashSet<PublicKey>,
+    logger: Logger,
+}
+
+impl Default for HopSessionKeys {
+    fn default() -> Self {
+        HopSessionKeys::new()
+    }
+}
+
+impl HopSessionKeys {
+    pub fn new() -> HopSessionKeys {
+        HopSessionKeys::with_peers(HashSet::new())
+    }
+
+    pub fn with_peers(peers: HashSet<PublicKey>) -> HopSessionKeys {
+        HopSessionKeys {
+            outbound: HashMap::new(),
+            inbound: HashMap::new(),
+            peers,
+            logger: Logger::new("HopSessionKeys"),
+        }
+    }
+
+    pub fn encode<T>(
+        &mut self,
+        cryptde: &dyn CryptDE, // must be the main CryptDE of the Node the package is about to leave
+        recipient: &PublicKey,
+        item: &T,
+        now: Instant,
+    ) -> Result<CryptData, CodexError>
+    where
+        T: serde::Serialize,
+    {
+        // Older Nodes can't open envelopes, so they get whole-package public-key encryption.
+        if (recipient != cryptde.public_key()) && !self.peers.contains(recipient) {
+            return encodex(cryptde, recipient, item);
+        }
+        let needs_new_key = match self.outbound.get(recipient) {
+            None => true,
+            Some(outbound) => {
+                (now.duration_since(outbound.created) >= SESSION_KEY_LIFETIME)
+                    || (outbound.packets >= SESSION_KEY_MAX_PACKETS)
+            }
+        };
+        if needs_new_key {
+            let outbound = Self::make_outbound_key(cryptde, recipient, now)?;
+            debug!(
+                self.logger,
+                "Keying hops to {} with session key {:016X}", recipient, outbound.key_id
+            );
+            self.outbound.insert(recipient.clone(), outbound);
+        }
+        let outbound = self
+            .outbound
+            .get_mut(recipient)
+            .expect("Session key disappeared");
+        let serialized = serialize(item)?;
+        let package = cryptde
+            .encode_sym(&outbound.key, &PlainData::from(serialized))
+            .map_err(CodexError::EncryptionError)?;
+        let offer_opt = if (outbound.packets % SESSION_KEY_REOFFER_PACKETS == 0)
+            || (now.duration_since(outbound.last_offered) >= SESSION_KEY_REOFFER_INTERVAL)
+        {
+            outbound.last_offered = now;
+            Some(outbound.offer.clone())
+        } else {
+            None
+        };
+        outbound.packets += 1;
+        let envelope = HopEnvelope {
+            key_id: outbound.key_id,
+            offer_opt,
+            package,
+        };
+        Ok(CryptData::from(serialize(&envelope)?))
+    }
+
+    // Packages that aren't HopEnvelopes are assumed to be whole-package public-key encryptions
+    // from Nodes that don't use session keys, and are decoded that way.
+    pub fn decode<T>(
+        &mut self,
+        cryptde: &dyn CryptDE, // must be the main CryptDE of the Node the package has reached
+        data: &CryptData,
+        now: Instant,
+    ) -> Result<T, CodexError>
+    where
+        for<'de> T: serde::Deserialize<'de>,
+    {
+        let envelope = match serde_cbor::de::from_slice::<HopEnvelope>(data.as_slice()) {
+            Ok(envelope) => envelope,
+            Err(_) => return decodex(cryptde, data),
+        };
+        let key_id = envelope.key_id;
+        if let Some(offer) = envelope.offer_opt {
+            if !self.inbound.contains_key(&key_id) {
+                let key = Self::accept_offer(cryptde, key_id, &offer)?;
+                self.prune_inbound(now);
+                self.make_room_for(&offer.sender);
+                debug!(
+                    self.logger,
+                    "Accepted session key {:016X} from {}", key_id, offer.sender
+                );
+                self.inbound.insert(
+                    key_id,
+                    InboundKey {
+                        sender: offer.sender,
+                        key,
+                        last_used: now,
+                    },
+                );
+            }
+        }
+        let inbound = match self.inbound.get_mut(&key_id) {
+            Some(inbound) => inbound,
+            None => {
+                return Err(CodexError::DecryptionError(CryptdecError::OtherError(
+                    format!("Unknown session key {:016X}", key_id),
+                )))
+            }
+        };
+        inbound.last_used = now;
+        let decrypted = cryptde
+            .decode_sym(&inbound.key, &envelope.package)
+            .map_err(CodexError::DecryptionError)?;
+        deserialize(decrypted.as_slice())
+    }
+
+    // The Neighborhood replaces the whole set whenever Gossip changes it.
+    pub fn set_peers(&mut self, peers: HashSet<PublicKey>) {
+        self.peers = peers;
+    }
+
+    pub fn outbound_count(&self) -> usize {
+        self.outbound.len()
+    }
+
+    pub fn inbound_count(&self) -> usize {
+        self.inbound.len()
+    }
+
+    fn make_outbound_key(
+        cryptde: &dyn CryptDE,
+        recipient: &PublicKey,
+        now: Instant,
+    ) -> Result<OutboundKey, CodexError> {
+        let key = cryptde.gen_key_sym();
+        let key_id = Self::key_id(cryptde, cryptde.public_key(), &key);
+        let encrypted_key = cryptde
+            .encode(recipient, &PlainData::new(key.as_slice()))
+            .map_err(CodexError::EncryptionError)?;
+        let signature = cryptde
+            .sign(&Self::signable(key_id, &encrypted_key))
+            .map_err(CodexError::EncryptionError)?;
+        Ok(OutboundKey {
+            key_id,
+            key,
+            offer: SessionKeyOffer {
+                sender: cryptde.public_key().clone(),
+                encrypted_key,
+                signature,
+            },
+            created: now,
+            last_offered: now,
+            packets: 0,
+        })
+    }
+
+    fn accept_offer(
+        cryptde: &dyn CryptDE,
+        key_id: u64,
+        offer: &SessionKeyOffer,
+    ) -> Result<SymmetricKey, CodexError> {
+        if !cryptde.verify_signature(
+            &Self::signable(key_id, &offer.encrypted_key),
+            &offer.signature,
+            &offer.sender,
+        ) {
+            return Err(CodexError::DecryptionError(CryptdecError::OtherError(
+                format!(
+                    "Session key {:016X} offered by {} has an invalid signature",
+                    key_id, offer.sender
+                ),
+            )));
+        }
+        let key = SymmetricKey::new(
+            cryptde
+                .decode(&offer.encrypted_key)
+                .map_err(CodexError::DecryptionError)?
+                .as_slice(),
+        );
+        // Otherwise a neighbor could claim the key ID of another sender's key, and either hijack
+        // or spoil it.
+        if Self::key_id(cryptde, &offer.sender, &key) != key_id {
+            return Err(CodexError::DecryptionError(CryptdecError::OtherError(
+                format!(
+                    "Session key {:016X} offered by {} doesn't match its ID",
+                    key_id, offer.sender
+                ),
+            )));
+        }
+        Ok(key)
+    }
+
+    // The sender's key is mixed in so that identical keys from different senders (which happen
+    // with CryptDENull) don't collide.
+    fn key_id(cryptde: &dyn CryptDE, sender: &PublicKey, key: &SymmetricKey) -> u64 {
+        let hash = cryptde.hash(&PlainData::from(
+            [sender.as_slice(), key.as_slice()].concat(),
+        ));
+        hash.as_slice()
+            .iter()
+            .take(8)
+            .fold(0u64, |so_far, byte| (so_far << 8) | (*byte as u64))
+    }
+
+    fn signable(key_id: u64, encrypted_key: &CryptData) -> PlainData {
+        PlainData::from([&key_id.to_be_bytes()[..], encrypted_key.as_slice()].concat())
+    }
+
+    fn prune_inbound(&mut self, now: Instant) {
+        self.inbound
+            .retain(|_, inbound| now.duration_since(inbound.last_used) < SESSION_KEY_RETENTION);
+    }
+
+    fn make_room_for(&mut self, sender: &PublicKey) {
+        let sender_key_count = self
+            .inbound
+            .values()
+            .filter(|inbound| &inbound.sender == sender)
+            .count();
+        if sender_key_count >= SESSION_KEY_MAX_INBOUND_PER_SENDER {
+            self.forget_least_recently_used(|inbound| &inbound.sender == sender);
+        }
+        if self.inbound.len() >= SESSION_KEY_MAX_INBOUND {
+            self.forget_least_recently_used(|_| true);
+        }
+    }
+
+    fn forget_least_recently_used<F>(&mut self, candidate: F)
+    where
+        F: Fn(&InboundKey) -> bool,
+    {
+        let key_id_opt = self
+            .inbound
+            .iter()
+            .filter(|(_, inbound)| candidate(inbound))
+            .min_by_key(|(_, inbound)| inbound.last_used)
+            .map(|(key_id, _)| *key_id);
+        if let Some(key_id) = key_id_opt {
+            self.inbound.remove(&key_id);
+        }
+    }
+}
+
+fn serialize<T>(item: &T) -> Result<Vec<u8>, CodexError>
+where
+    T: serde::Serialize,
+{
+    serde_cbor::ser::to_vec(item)
+        .map_err(|e| CodexError::SerializationError(SerdeCborError { delegate: e }))
+}
+
+fn deserialize<T>(data: &[u8]) -> Result<T, CodexError>
+where
+    for<'de> T: serde::Deserialize<'de>,
+{
+    serde_cbor::de::from_slice(data)
+        .map_err(|e| CodexError::DeserializationError(SerdeCborError { delegate: e }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sub_lib::cryptde::encodex;
+    use crate::sub_lib::cryptde_null::CryptDENull;
+    use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
+
+    fn make_pair() -> (CryptDENull, CryptDENull) {
+        (
+            CryptDENull::from(&PublicKey::new(b"sender"), DEFAULT_CHAIN_ID),
+            CryptDENull::from(&PublicKey::new(b"receiver"), DEFAULT_CHAIN_ID),
+        )
+    }
+
+    // Sends envelopes to the receiver, as if the receiver had advertised that it accepts them.
+    fn make_sender(receiver: &PublicKey) -> HopSessionKeys {
+        HopSessionKeys::with_peers(vec![receiver.clone()].into_iter().collect())
+    }
+
+    fn envelope_from(data: &CryptData) -> HopEnvelope {
+        serde_cbor::de::from_slice::<HopEnvelope>(data.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(SESSION_KEY_LIFETIME, Duration::from_secs(600));
+        assert_eq!(SESSION_KEY_MAX_PACKETS, 1_000_000);
+        assert_eq!(SESSION_KEY_REOFFER_PACKETS, 64);
+        assert_eq!(SESSION_KEY_REOFFER_INTERVAL, Duration::from_secs(10));
+        assert_eq!(SESSION_KEY_RETENTION, Duration::from_secs(1200));
+        assert_eq!(SESSION_KEY_MAX_INBOUND_PER_SENDER, 4);
+        assert_eq!(SESSION_KEY_MAX_INBOUND, 4096);
+    }
+
+    #[test]
+    fn packages_survive_a_round_trip_and_only_the_first_carries_an_offer() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+
+        let first = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"first",
+                now,
+            )
+            .unwrap();
+        let second = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"second",
+                now,
+            )
+            .unwrap();
+
+        let first_envelope = envelope_from(&first);
+        let second_envelope = envelope_from(&second);
+        assert!(first_envelope.offer_opt.is_some());
+        assert_eq!(second_envelope.offer_opt, None);
+        assert_eq!(first_envelope.key_id, second_envelope.key_id);
+        assert_eq!(
+            receiver
+                .decode::<String>(&receiver_cryptde, &first, now)
+                .unwrap(),
+            "first".to_string()
+        );
+        assert_eq!(
+            receiver
+                .decode::<String>(&receiver_cryptde, &second, now)
+                .unwrap(),
+            "second".to_string()
+        );
+        assert_eq!(sender.outbound_count(), 1);
+        assert_eq!(receiver.inbound_count(), 1);
+    }
+
+    #[test]
+    fn packages_without_a_known_key_are_rejected() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+        let _lost = sender
+            .encode(&sender_cryptde, receiver_cryptde.public_key(), &"lost", now)
+            .unwrap();
+        let orphan = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"orphan",
+                now,
+            )
+            .unwrap();
+
+        let result = receiver.decode::<String>(&receiver_cryptde, &orphan, now);
+
+        let key_id = envelope_from(&orphan).key_id;
+        assert_eq!(
+            result,
+            Err(CodexError::DecryptionError(CryptdecError::OtherError(
+                format!("Unknown session key {:016X}", key_id)
+            )))
+        );
+    }
+
+    #[test]
+    fn offers_are_repeated_periodically_so_a_restarted_receiver_can_recover() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let now = Instant::now();
+
+        let offers = (0..(SESSION_KEY_REOFFER_PACKETS + 1))
+            .map(|_| {
+                sender
+                    .encode(&sender_cryptde, receiver_cryptde.public_key(), &"data", now)
+                    .unwrap()
+            })
+            .filter(|data| envelope_from(data).offer_opt.is_some())
+            .count();
+        let later = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"data",
+                now + SESSION_KEY_REOFFER_INTERVAL,
+            )
+            .unwrap();
+
+        assert_eq!(offers, 2);
+        assert!(envelope_from(&later).offer_opt.is_some());
+    }
+
+    #[test]
+    fn keys_are_replaced_when_they_get_too_old() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+        let before = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"before",
+                now,
+            )
+            .unwrap();
+
+        let after = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"after",
+                now + SESSION_KEY_LIFETIME,
+            )
+            .unwrap();
+
+        let before_envelope = envelope_from(&before);
+        let after_envelope = envelope_from(&after);
+        assert_ne!(before_envelope.key_id, after_envelope.key_id);
+        assert!(after_envelope.offer_opt.is_some());
+        receiver
+            .decode::<String>(&receiver_cryptde, &before, now)
+            .unwrap();
+        assert_eq!(
+            receiver
+                .decode::<String>(&receiver_cryptde, &after, now + SESSION_KEY_LIFETIME)
+                .unwrap(),
+            "after".to_string()
+        );
+        assert_eq!(receiver.inbound_count(), 2);
+    }
+
+    #[test]
+    fn keys_are_replaced_after_too_many_packets() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let now = Instant::now();
+        let before = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"before",
+                now,
+            )
+            .unwrap();
+        sender
+            .outbound
+            .get_mut(receiver_cryptde.public_key())
+            .unwrap()
+            .packets = SESSION_KEY_MAX_PACKETS;
+
+        let after = sender
+            .encode(
+                &sender_cryptde,
+                receiver_cryptde.public_key(),
+                &"after",
+                now,
+            )
+            .unwrap();
+
+        assert_ne!(envelope_from(&before).key_id, envelope_from(&after).key_id);
+    }
+
+    #[test]
+    fn unused_inbound_keys_are_pruned_when_new_keys_arrive() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let other_cryptde = CryptDENull::from(&PublicKey::new(b"other"), DEFAULT_CHAIN_ID);
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let mut other = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+        let old = sender
+            .encode(&sender_cryptde, receiver_cryptde.public_key(), &"old", now)
+            .unwrap();
+        receiver
+            .decode::<String>(&receiver_cryptde, &old, now)
+            .unwrap();
+        let new = other
+            .encode(
+                &other_cryptde,
+                receiver_cryptde.public_key(),
+                &"new",
+                now + SESSION_KEY_RETENTION,
+            )
+            .unwrap();
+
+        receiver
+            .decode::<String>(&receiver_cryptde, &new, now + SESSION_KEY_RETENTION)
+            .unwrap();
+
+        assert_eq!(receiver.inbound_count(), 1);
+        assert!(receiver.inbound.contains_key(&envelope_from(&new).key_id));
+    }
+
+    #[test]
+    fn forged_offers_are_rejected() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+        let data = sender
+            .encode(&sender_cryptde, receiver_cryptde.public_key(), &"data", now)
+            .unwrap();
+        let mut envelope = envelope_from(&data);
+        let key_id = envelope.key_id;
+        envelope.key_id = key_id + 1;
+        let forged = CryptData::from(serde_cbor::ser::to_vec(&envelope).unwrap());
+
+        let result = receiver.decode::<String>(&receiver_cryptde, &forged, now);
+
+        assert_eq!(
+            result,
+            Err(CodexError::DecryptionError(CryptdecError::OtherError(
+                format!(
+                    "Session key {:016X} offered by {} has an invalid signature",
+                    key_id + 1,
+                    sender_cryptde.public_key()
+                )
+            )))
+        );
+        assert_eq!(receiver.inbound_count(), 0);
+    }
+
+    #[test]
+    fn peers_that_dont_accept_envelopes_get_whole_package_public_key_encryption() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = HopSessionKeys::with_peers(HashSet::new());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+
+        let data = sender
+            .encode(&sender_cryptde, receiver_cryptde.public_key(), &"old", now)
+            .unwrap();
+
+        assert_eq!(
+            data,
+            encodex(&sender_cryptde, receiver_cryptde.public_key(), &"old").unwrap()
+        );
+        assert_eq!(sender.outbound_count(), 0);
+        assert_eq!(
+            receiver.decode::<String>(&receiver_cryptde, &data, now),
+            Ok("old".to_string())
+        );
+    }
+
+    #[test]
+    fn peers_are_replaced_wholesale() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let receiver_key = receiver_cryptde.public_key();
+        let mut subject = HopSessionKeys::new();
+        let now = Instant::now();
+        let whole_package = encodex(&sender_cryptde, receiver_key, &"data").unwrap();
+
+        let initially = subject.encode(&sender_cryptde, receiver_key, &"data", now);
+        subject.set_peers(vec![receiver_key.clone()].into_iter().collect());
+        let after_first = subject.encode(&sender_cryptde, receiver_key, &"data", now);
+        subject.set_peers(vec![PublicKey::new(b"other")].into_iter().collect());
+        let after_second = subject.encode(&sender_cryptde, receiver_key, &"data", now);
+
+        assert_eq!(initially, Ok(whole_package.clone()));
+        assert_ne!(after_first, Ok(whole_package.clone()));
+        assert_eq!(
+            envelope_from(&after_first.unwrap()).offer_opt.is_some(),
+            true
+        );
+        assert_eq!(after_second, Ok(whole_package));
+    }
+
+    #[test]
+    fn properly_signed_offers_with_the_wrong_key_id_are_rejected() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut sender = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+        let data = sender
+            .encode(&sender_cryptde, receiver_cryptde.public_key(), &"data", now)
+            .unwrap();
+        let mut envelope = envelope_from(&data);
+        let claimed_key_id = envelope.key_id ^ 0xFFFF;
+        let mut offer = envelope.offer_opt.take().unwrap();
+        offer.signature = sender_cryptde
+            .sign(&HopSessionKeys::signable(
+                claimed_key_id,
+                &offer.encrypted_key,
+            ))
+            .unwrap();
+        envelope.key_id = claimed_key_id;
+        envelope.offer_opt = Some(offer);
+        let hijacked = CryptData::from(serde_cbor::ser::to_vec(&envelope).unwrap());
+
+        let result = receiver.decode::<String>(&receiver_cryptde, &hijacked, now);
+
+        assert_eq!(
+            result,
+            Err(CodexError::DecryptionError(CryptdecError::OtherError(
+                format!(
+                    "Session key {:016X} offered by {} doesn't match its ID",
+                    claimed_key_id,
+                    sender_cryptde.public_key()
+                )
+            )))
+        );
+        assert_eq!(receiver.inbound_count(), 0);
+    }
+
+    #[test]
+    fn each_sender_keeps_only_its_most_recently_used_keys() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let other_cryptde = CryptDENull::from(&PublicKey::new(b"other"), DEFAULT_CHAIN_ID);
+        let mut other = make_sender(receiver_cryptde.public_key());
+        let mut receiver = HopSessionKeys::new();
+        let now = Instant::now();
+        let others_data = other
+            .encode(&other_cryptde, receiver_cryptde.public_key(), &"other", now)
+            .unwrap();
+        receiver
+            .decode::<String>(&receiver_cryptde, &others_data, now)
+            .unwrap();
+        let key_ids = (0..(SESSION_KEY_MAX_INBOUND_PER_SENDER as u64 + 1))
+            .map(|index| {
+                let mut sender = make_sender(receiver_cryptde.public_key());
+                let later = now + Duration::from_millis(index + 1);
+                let data = sender
+                    .encode(
+                        &sender_cryptde,
+                        receiver_cryptde.public_key(),
+                        &"data",
+                        later,
+                    )
+                    .unwrap();
+                receiver
+                    .decode::<String>(&receiver_cryptde, &data, later)
+                    .unwrap();
+                envelope_from(&data).key_id
+            })
+            .collect::<Vec<u64>>();
+
+        assert_eq!(
+            receiver.inbound_count(),
+            SESSION_KEY_MAX_INBOUND_PER_SENDER + 1
+        );
+        assert!(!receiver.inbound.contains_key(&key_ids[0]));
+        key_ids[1..]
+            .iter()
+            .for_each(|key_id| assert!(receiver.inbound.contains_key(key_id)));
+        assert!(receiver
+            .inbound
+            .contains_key(&envelope_from(&others_data).key_id));
+    }
+
+    #[test]
+    fn packages_encrypted_wholly_with_public_keys_are_still_accepted() {
+        let (sender_cryptde, receiver_cryptde) = make_pair();
+        let mut receiver = HopSessionKeys::new();
+        let data = encodex(&sender_cryptde, receiver_cryptde.public_key(), &"legacy").unwrap();
+
+        let result = receiver.decode::<String>(&receiver_cryptde, &data, Instant::now());
+
+        assert_eq!(result, Ok("legacy".to_string()));
+        assert_eq!(receiver.inbound_count(), 0);
+    }
+}
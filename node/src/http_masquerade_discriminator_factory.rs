@@ -0,0 +1,210 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::Discriminator;
+use crate::discriminator::DiscriminatorFactory;
+use crate::http_masquerader::HttpMasquerader;
+use crate::http_masquerader::HTTP_MASQUERADE_REQUEST_START;
+use crate::http_masquerader::HTTP_MASQUERADE_RESPONSE_START;
+use crate::masquerader::MasqueradeKind;
+use crate::masquerader::MasqueradeRole;
+use crate::sub_lib::framer::FramedChunk;
+use crate::sub_lib::framer::Framer;
+use masq_lib::utils::index_of;
+
+const HEADER_TERMINATOR: &[u8] = b"\r\n\r\n";
+// Anything claiming to be bigger than this is garbage, and we don't want to buffer it forever.
+const MAX_CONTENT_LENGTH: usize = 0x0010_0000;
+
+// Unlike HttpPacketFramer, which has to cope with whatever browsers send, this framer only
+// recognizes the requests and responses HttpMasquerader produces.
+#[derive(Default)]
+pub struct HttpMasqueradeFramer {
+    data_so_far: Vec<u8>,
+}
+
+impl Framer for HttpMasqueradeFramer {
+    fn add_data(&mut self, data: &[u8]) {
+        self.data_so_far.extend(data);
+    }
+
+    fn take_frame(&mut self) -> Option<FramedChunk> {
+        loop {
+            let start = match Self::find_start(&self.data_so_far) {
+                Some(start) => start,
+                None => {
+                    let keep = HTTP_MASQUERADE_REQUEST_START.len() - 1;
+                    if self.data_so_far.len() > keep {
+                        let split_point = self.data_so_far.len() - keep;
+                        self.data_so_far = self.data_so_far.split_off(split_point);
+                    }
+                    return None;
+                }
+            };
+            self.data_so_far = self.data_so_far.split_off(start);
+            let body_start = match index_of(&self.data_so_far, HEADER_TERMINATOR) {
+                Some(offset) => offset + HEADER_TERMINATOR.len(),
+                None => return None,
+            };
+            let content_length =
+                match HttpMasquerader::content_length(&self.data_so_far[..body_start]) {
+                    Ok(content_length) if content_length <= MAX_CONTENT_LENGTH => content_length,
+                    _ => {
+                        // Not one of ours after all; skip past it and look for the next one.
+                        self.data_so_far = self.data_so_far.split_off(1);
+                        continue;
+                    }
+                };
+            if self.data_so_far.len() < body_start + content_length {
+                return None;
+            }
+            let leftovers = self.data_so_far.split_off(body_start + content_length);
+            let chunk = std::mem::replace(&mut self.data_so_far, leftovers);
+            return Some(FramedChunk {
+                chunk,
+                last_chunk: true,
+            });
+        }
+    }
+}
+
+impl HttpMasqueradeFramer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn find_start(data: &[u8]) -> Option<usize> {
+        vec![
+            index_of(data, HTTP_MASQUERADE_REQUEST_START),
+            index_of(data, HTTP_MASQUERADE_RESPONSE_START),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HttpMasqueradeDiscriminatorFactory {}
+
+impl DiscriminatorFactory for HttpMasqueradeDiscriminatorFactory {
+    fn make(&self) -> Discriminator {
+        Discriminator::new(
+            Box::new(HttpMasqueradeFramer::new()),
+            // Unmasking doesn't depend on the role; only masking does.
+            vec![Box::new(HttpMasquerader::new(MasqueradeRole::Acceptor))],
+        )
+    }
+
+    fn duplicate(&self) -> Box<dyn DiscriminatorFactory> {
+        Box::new(HttpMasqueradeDiscriminatorFactory {})
+    }
+
+    fn masquerade_opt(&self) -> Option<MasqueradeKind> {
+        Some(MasqueradeKind::Http)
+    }
+}
+
+impl HttpMasqueradeDiscriminatorFactory {
+    pub fn new() -> HttpMasqueradeDiscriminatorFactory {
+        HttpMasqueradeDiscriminatorFactory {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discriminator::UnmaskedChunk;
+    use crate::masquerader::Masquerader;
+
+    #[test]
+    fn discriminator_factory_duplicate_works() {
+        let subject = HttpMasqueradeDiscriminatorFactory::new();
+
+        subject.duplicate();
+
+        // no panic; test passes
+    }
+
+    #[test]
+    fn framer_returns_none_until_whole_message_arrives() {
+        let masked = HttpMasquerader::new(MasqueradeRole::Initiator)
+            .mask(b"booga")
+            .unwrap();
+        let mut subject = HttpMasqueradeFramer::new();
+
+        subject.add_data(&masked[..masked.len() - 1]);
+        let first_result = subject.take_frame();
+        subject.add_data(&masked[masked.len() - 1..]);
+        let second_result = subject.take_frame();
+
+        assert_eq!(first_result, None);
+        assert_eq!(
+            second_result,
+            Some(FramedChunk {
+                chunk: masked,
+                last_chunk: true
+            })
+        );
+    }
+
+    #[test]
+    fn framer_skips_garbage_and_separates_consecutive_messages() {
+        let request = HttpMasquerader::new(MasqueradeRole::Initiator)
+            .mask(b"booga")
+            .unwrap();
+        let response = HttpMasquerader::new(MasqueradeRole::Acceptor)
+            .mask(b"agoob")
+            .unwrap();
+        let mut subject = HttpMasqueradeFramer::new();
+        subject.add_data(b"garbage");
+        subject.add_data(&request);
+        subject.add_data(&response);
+
+        let first_result = subject.take_frame();
+        let second_result = subject.take_frame();
+        let third_result = subject.take_frame();
+
+        assert_eq!(first_result.unwrap().chunk, request);
+        assert_eq!(second_result.unwrap().chunk, response);
+        assert_eq!(third_result, None);
+    }
+
+    #[test]
+    fn framer_skips_message_without_content_length() {
+        let response = HttpMasquerader::new(MasqueradeRole::Acceptor)
+            .mask(b"booga")
+            .unwrap();
+        let mut subject = HttpMasqueradeFramer::new();
+        subject.add_data(b"HTTP/1.1 200 OK\r\n\r\n");
+        subject.add_data(&response);
+
+        let result = subject.take_frame();
+
+        assert_eq!(result.unwrap().chunk, response);
+    }
+
+    #[test]
+    fn factory_makes_discriminator_that_ignores_json_data() {
+        let subject = HttpMasqueradeDiscriminatorFactory::new();
+        let mut discriminator = subject.make();
+
+        discriminator.add_data(b"{\"component\": \"NBHD\", \"bodyText\": \"Booga\"}");
+        let result = discriminator.take_chunk();
+
+        assert_eq!(result, None)
+    }
+
+    #[test]
+    fn factory_makes_discriminator_that_unmasks_http_masquerade() {
+        let data = &b"I am contained in HTTP!"[..];
+        let masked = HttpMasquerader::new(MasqueradeRole::Acceptor)
+            .mask(data)
+            .unwrap();
+        let subject = HttpMasqueradeDiscriminatorFactory::new();
+        let mut discriminator = subject.make();
+
+        discriminator.add_data(&masked[..]);
+        let result = discriminator.take_chunk();
+
+        assert_eq!(result, Some(UnmaskedChunk::new(data.to_vec(), true, false)))
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::UnmaskedChunk;
+use crate::masquerader::MasqueradeError;
+use crate::masquerader::MasqueradeRole;
+use crate::masquerader::Masquerader;
+use masq_lib::utils::index_of;
+use std::net::SocketAddr;
+
+pub const HTTP_MASQUERADE_REQUEST_START: &[u8] = b"POST /api/v1/sync HTTP/1.1\r\n";
+pub const HTTP_MASQUERADE_RESPONSE_START: &[u8] = b"HTTP/1.1 200 OK\r\n";
+const HEADER_TERMINATOR: &[u8] = b"\r\n\r\n";
+
+// Dresses CORES packages up as the bodies of HTTP/1.1 POST requests (from the Node that opened
+// the stream) or 200 OK responses (from the Node that accepted it).
+pub struct HttpMasquerader {
+    role: MasqueradeRole,
+    host_opt: Option<SocketAddr>,
+}
+
+impl Masquerader for HttpMasquerader {
+    fn try_unmask(&self, item: &[u8]) -> Result<UnmaskedChunk, MasqueradeError> {
+        if !item.starts_with(HTTP_MASQUERADE_REQUEST_START)
+            && !item.starts_with(HTTP_MASQUERADE_RESPONSE_START)
+        {
+            return Err(MasqueradeError::NotThisMasquerader);
+        }
+        let body_start = match index_of(item, HEADER_TERMINATOR) {
+            Some(offset) => offset + HEADER_TERMINATOR.len(),
+            None => {
+                return Err(MasqueradeError::MidLevelDataError(
+                    "HTTP headers are not terminated".to_string(),
+                ))
+            }
+        };
+        let content_length = Self::content_length(&item[..body_start])?;
+        let body = &item[body_start..];
+        if body.len() != content_length {
+            return Err(MasqueradeError::MidLevelDataError(format!(
+                "Content-Length is {}, but body is {} bytes",
+                content_length,
+                body.len()
+            )));
+        }
+        Ok(UnmaskedChunk::new(body.to_vec(), true, false))
+    }
+
+    fn mask(&self, data: &[u8]) -> Result<Vec<u8>, MasqueradeError> {
+        let start_line = match self.role {
+            MasqueradeRole::Initiator => HTTP_MASQUERADE_REQUEST_START,
+            MasqueradeRole::Acceptor => HTTP_MASQUERADE_RESPONSE_START,
+        };
+        let mut masked = start_line.to_vec();
+        if let (MasqueradeRole::Initiator, Some(host)) = (self.role, self.host_opt) {
+            masked.extend(format!("Host: {}\r\n", host).as_bytes());
+        }
+        masked.extend(
+            format!(
+                "Content-Type: application/octet-stream\r\nContent-Length: {}\r\n\r\n",
+                data.len()
+            )
+            .as_bytes(),
+        );
+        masked.extend(data);
+        Ok(masked)
+    }
+}
+
+impl HttpMasquerader {
+    pub fn new(role: MasqueradeRole) -> HttpMasquerader {
+        HttpMasquerader {
+            role,
+            host_opt: None,
+        }
+    }
+
+    // Requests name the Node they're sent to, as HTTP/1.1 requires.
+    pub fn with_host(mut self, host: SocketAddr) -> HttpMasquerader {
+        self.host_opt = Some(host);
+        self
+    }
+
+    pub fn content_length(headers: &[u8]) -> Result<usize, MasqueradeError> {
+        let headers = match std::str::from_utf8(headers) {
+            Ok(headers) => headers,
+            Err(_) => {
+                return Err(MasqueradeError::LowLevelDataError(
+                    "HTTP headers are not UTF-8".to_string(),
+                ))
+            }
+        };
+        match headers.split("\r\n").find_map(|line| {
+            let mut pieces = line.splitn(2, ':');
+            match (pieces.next(), pieces.next()) {
+                (Some(name), Some(value)) if name.eq_ignore_ascii_case("Content-Length") => {
+                    Some(value.trim())
+                }
+                _ => None,
+            }
+        }) {
+            Some(value) => value.parse::<usize>().map_err(|_| {
+                MasqueradeError::MidLevelDataError(format!("Bad Content-Length: '{}'", value))
+            }),
+            None => Err(MasqueradeError::MidLevelDataError(
+                "No Content-Length header".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn initiator_masks_data_as_a_post_request() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Initiator);
+
+        let result = subject.mask(b"booga").unwrap();
+
+        assert_eq!(
+            String::from_utf8(result).unwrap(),
+            "POST /api/v1/sync HTTP/1.1\r\nContent-Type: application/octet-stream\r\nContent-Length: 5\r\n\r\nbooga"
+        );
+    }
+
+    #[test]
+    fn initiator_with_a_host_puts_it_in_the_request() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Initiator)
+            .with_host(SocketAddr::from_str("1.2.3.4:5678").unwrap());
+
+        let result = subject.mask(b"booga").unwrap();
+
+        assert_eq!(
+            String::from_utf8(result).unwrap(),
+            "POST /api/v1/sync HTTP/1.1\r\nHost: 1.2.3.4:5678\r\nContent-Type: application/octet-stream\r\nContent-Length: 5\r\n\r\nbooga"
+        );
+    }
+
+    #[test]
+    fn acceptor_with_a_host_leaves_it_out_of_the_response() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor)
+            .with_host(SocketAddr::from_str("[2001:db8::5]:5678").unwrap());
+
+        let result = subject.mask(b"booga").unwrap();
+
+        assert_eq!(
+            String::from_utf8(result).unwrap(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 5\r\n\r\nbooga"
+        );
+    }
+
+    #[test]
+    fn acceptor_masks_data_as_a_response() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor);
+
+        let result = subject.mask(b"booga").unwrap();
+
+        assert_eq!(
+            String::from_utf8(result).unwrap(),
+            "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: 5\r\n\r\nbooga"
+        );
+    }
+
+    #[test]
+    fn either_role_unmasks_both_requests_and_responses() {
+        let data = [0x00u8, 0x0D, 0x0A, 0x0D, 0x0A, 0xFF];
+        let request = HttpMasquerader::new(MasqueradeRole::Initiator)
+            .mask(&data)
+            .unwrap();
+        let response = HttpMasquerader::new(MasqueradeRole::Acceptor)
+            .mask(&data)
+            .unwrap();
+        let subject = HttpMasquerader::new(MasqueradeRole::Initiator);
+
+        let request_result = subject.try_unmask(&request);
+        let response_result = subject.try_unmask(&response);
+
+        let expected = Ok(UnmaskedChunk::new(data.to_vec(), true, false));
+        assert_eq!(request_result, expected);
+        assert_eq!(response_result, expected);
+    }
+
+    #[test]
+    fn unmask_rejects_other_http_traffic() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor);
+
+        let result = subject.try_unmask(b"GET /index.html HTTP/1.1\r\nHost: booga.com\r\n\r\n");
+
+        assert_eq!(result, Err(MasqueradeError::NotThisMasquerader));
+    }
+
+    #[test]
+    fn unmask_complains_about_unterminated_headers() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor);
+
+        let result = subject.try_unmask(b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n");
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "HTTP headers are not terminated".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unmask_complains_about_missing_content_length() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor);
+
+        let result = subject.try_unmask(b"HTTP/1.1 200 OK\r\n\r\nbooga");
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "No Content-Length header".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unmask_complains_about_bad_content_length() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor);
+
+        let result = subject.try_unmask(b"HTTP/1.1 200 OK\r\nContent-Length: five\r\n\r\nbooga");
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "Bad Content-Length: 'five'".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unmask_complains_about_body_of_wrong_length() {
+        let subject = HttpMasquerader::new(MasqueradeRole::Acceptor);
+
+        let result = subject.try_unmask(b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nbooga");
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "Content-Length is 4, but body is 5 bytes".to_string()
+            ))
+        );
+    }
+}
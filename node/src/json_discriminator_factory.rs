@@ -3,6 +3,7 @@ use crate::discriminator::Discriminator;
 use crate::discriminator::DiscriminatorFactory;
 use crate::json_framer::JsonFramer;
 use crate::json_masquerader::JsonMasquerader;
+use crate::masquerader::MasqueradeKind;
 
 #[derive(Debug, Default)]
 pub struct JsonDiscriminatorFactory {}
@@ -18,6 +19,10 @@ impl DiscriminatorFactory for JsonDiscriminatorFactory {
     fn duplicate(&self) -> Box<dyn DiscriminatorFactory> {
         Box::new(JsonDiscriminatorFactory {})
     }
+
+    fn masquerade_opt(&self) -> Option<MasqueradeKind> {
+        Some(MasqueradeKind::Json)
+    }
 }
 
 impl JsonDiscriminatorFactory {
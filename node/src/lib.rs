@@ -25,16 +25,21 @@ pub mod db_config;
 pub mod discriminator;
 pub mod dispatcher;
 pub mod entry_dns;
+pub mod explicit_proxy_discriminator_factory;
 pub mod hopper;
+pub mod http_masquerade_discriminator_factory;
+pub mod http_masquerader;
 pub mod http_request_start_finder;
 pub mod json_discriminator_factory;
 pub mod json_framer;
 pub mod json_masquerader;
 mod listener_handler;
 pub mod masquerader;
+pub mod metrics_server;
 pub mod neighborhood;
 pub mod node_configurator;
 mod null_masquerader;
+pub mod opaque_discriminator_factory;
 pub mod privilege_drop;
 pub mod proxy_client;
 pub mod proxy_server;
@@ -47,4 +52,6 @@ mod stream_writer_sorted;
 mod stream_writer_unsorted;
 pub mod test_utils;
 pub mod tls_discriminator_factory;
+pub mod tls_masquerade_discriminator_factory;
+pub mod tls_masquerader;
 pub mod ui_gateway;
@@ -11,6 +11,7 @@ use std::io;
 use std::marker::Send;
 use std::net::IpAddr;
 use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
 use std::net::SocketAddr;
 use tokio::prelude::Async;
 use tokio::prelude::Future;
@@ -47,12 +48,28 @@ impl ListenerHandler for ListenerHandlerReal {
         let is_clandestine = port_configuration.is_clandestine;
         self.port_configuration = Some(port_configuration);
         self.logger = Logger::new(&format!("ListenerHandler {}", port));
-        let ip_addr = IpAddr::V4(if is_clandestine {
-            Ipv4Addr::from(0)
-        } else {
-            Ipv4Addr::LOCALHOST
-        });
-        self.listener.bind(SocketAddr::new(ip_addr, port))
+        if !is_clandestine {
+            return self
+                .listener
+                .bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+        }
+        // The wildcard IPv6 socket is bound with IPV6_V6ONLY off, so it accepts IPv4 connections
+        // as well; fall back to IPv4 alone where IPv6 is disabled or unavailable.
+        match self
+            .listener
+            .bind(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port))
+        {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                warning!(
+                    self.logger,
+                    "Could not listen for IPv6 connections ({}); listening for IPv4 only",
+                    e
+                );
+                self.listener
+                    .bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port))
+            }
+        }
     }
 
     fn bind_subs(&mut self, add_stream_sub: Recipient<AddStreamMsg>) {
@@ -244,7 +261,7 @@ mod tests {
         );
 
         assert_eq!(result.unwrap(), ());
-        assert_eq!(listener_log.dump(), vec!(format!("bind (0.0.0.0:2345)")));
+        assert_eq!(listener_log.dump(), vec!(format!("bind ([::]:2345)")));
         assert_eq!(subject.port, Some(2345));
         let mut port_configuration = subject.port_configuration.unwrap();
         let factory = port_configuration.discriminator_factories.remove(0);
@@ -254,6 +271,49 @@ mod tests {
         assert!(port_configuration.is_clandestine);
     }
 
+    #[test]
+    fn falls_back_to_ipv4_for_clandestine_port_when_ipv6_is_unavailable() {
+        init_test_logging();
+        let listener = TokioListenerWrapperMock::new()
+            .bind_result(Err(Error::from(ErrorKind::AddrNotAvailable)))
+            .bind_result(Ok(()));
+        let listener_log = listener.log.clone();
+        let discriminator_factory = NullDiscriminatorFactory::new();
+        let mut subject = ListenerHandlerReal::new();
+        subject.listener = Box::new(listener);
+
+        let result = subject.bind_port_and_configuration(
+            3456,
+            PortConfiguration::new(vec![Box::new(discriminator_factory)], true),
+        );
+
+        assert_eq!(result.unwrap(), ());
+        assert_eq!(
+            listener_log.dump(),
+            vec!(format!("bind ([::]:3456)"), format!("bind (0.0.0.0:3456)"))
+        );
+        TestLogHandler::new().exists_log_containing(
+            "WARN: ListenerHandler 3456: Could not listen for IPv6 connections",
+        );
+    }
+
+    #[test]
+    fn reports_ipv4_failure_for_clandestine_port_when_neither_family_can_be_bound() {
+        let listener = TokioListenerWrapperMock::new()
+            .bind_result(Err(Error::from(ErrorKind::AddrNotAvailable)))
+            .bind_result(Err(Error::from(ErrorKind::AddrInUse)));
+        let discriminator_factory = NullDiscriminatorFactory::new();
+        let mut subject = ListenerHandlerReal::new();
+        subject.listener = Box::new(listener);
+
+        let result = subject.bind_port_and_configuration(
+            4567,
+            PortConfiguration::new(vec![Box::new(discriminator_factory)], true),
+        );
+
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::AddrInUse);
+    }
+
     #[test]
     fn handles_bind_port_and_configuration_success_for_non_clandestine_port() {
         let listener = TokioListenerWrapperMock::new().bind_result(Ok(()));
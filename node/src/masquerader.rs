@@ -1,9 +1,18 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use crate::discriminator::DiscriminatorFactory;
 use crate::discriminator::UnmaskedChunk;
+use crate::http_masquerade_discriminator_factory::HttpMasqueradeDiscriminatorFactory;
+use crate::http_masquerader::HttpMasquerader;
+use crate::json_discriminator_factory::JsonDiscriminatorFactory;
+use crate::json_masquerader::JsonMasquerader;
+use crate::tls_masquerade_discriminator_factory::TlsMasqueradeDiscriminatorFactory;
+use crate::tls_masquerader::TlsMasquerader;
 use std::fmt;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::marker::Send;
+use std::net::SocketAddr;
+use std::str::FromStr;
 
 #[derive(Debug, PartialEq)]
 pub enum MasqueradeError {
@@ -29,6 +38,83 @@ pub trait Masquerader: Send {
     fn mask(&self, data: &[u8]) -> Result<Vec<u8>, MasqueradeError>;
 }
 
+// Which side of a clandestine stream we're on. Some disguises (HTTP, for example) look different
+// coming from the side that opened the connection than from the side that accepted it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MasqueradeRole {
+    Initiator,
+    Acceptor,
+}
+
+// The disguises a Node can wear on its clandestine streams. Every Node listens for all of them;
+// the --masquerade parameter only chooses which one this Node uses on the streams it opens to
+// Nodes that advertise accepting it, and the Node on the other end answers in whatever disguise
+// it was addressed in.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MasqueradeKind {
+    Json,
+    Http,
+    Tls,
+}
+
+impl Default for MasqueradeKind {
+    fn default() -> Self {
+        MasqueradeKind::Json
+    }
+}
+
+impl Display for MasqueradeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MasqueradeKind::Json => write!(f, "json"),
+            MasqueradeKind::Http => write!(f, "http"),
+            MasqueradeKind::Tls => write!(f, "tls"),
+        }
+    }
+}
+
+impl FromStr for MasqueradeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(MasqueradeKind::Json),
+            "http" => Ok(MasqueradeKind::Http),
+            "tls" => Ok(MasqueradeKind::Tls),
+            other => Err(format!(
+                "Unknown masquerade '{}': must be json, http, or tls",
+                other
+            )),
+        }
+    }
+}
+
+impl MasqueradeKind {
+    pub fn all() -> Vec<MasqueradeKind> {
+        vec![
+            MasqueradeKind::Json,
+            MasqueradeKind::Http,
+            MasqueradeKind::Tls,
+        ]
+    }
+
+    pub fn masquerader(self, role: MasqueradeRole, peer_addr: SocketAddr) -> Box<dyn Masquerader> {
+        match self {
+            MasqueradeKind::Json => Box::new(JsonMasquerader::new()),
+            MasqueradeKind::Http => Box::new(HttpMasquerader::new(role).with_host(peer_addr)),
+            MasqueradeKind::Tls => Box::new(TlsMasquerader::new()),
+        }
+    }
+
+    pub fn discriminator_factory(self) -> Box<dyn DiscriminatorFactory> {
+        match self {
+            MasqueradeKind::Json => Box::new(JsonDiscriminatorFactory::new()),
+            MasqueradeKind::Http => Box::new(HttpMasqueradeDiscriminatorFactory::new()),
+            MasqueradeKind::Tls => Box::new(TlsMasqueradeDiscriminatorFactory::new()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +147,50 @@ mod tests {
             "Data not for this masquerader"
         );
     }
+
+    #[test]
+    fn masquerade_kinds_round_trip_through_strings() {
+        MasqueradeKind::all().into_iter().for_each(|kind| {
+            assert_eq!(MasqueradeKind::from_str(&kind.to_string()), Ok(kind));
+        });
+    }
+
+    #[test]
+    fn unknown_masquerade_kind_is_rejected() {
+        let result = MasqueradeKind::from_str("smtp");
+
+        assert_eq!(
+            result,
+            Err("Unknown masquerade 'smtp': must be json, http, or tls".to_string())
+        );
+    }
+
+    #[test]
+    fn default_masquerade_kind_is_json() {
+        assert_eq!(MasqueradeKind::default(), MasqueradeKind::Json);
+    }
+
+    #[test]
+    fn each_masquerade_kind_makes_a_discriminator_that_unmasks_its_own_masquerader() {
+        let data = b"Moo-hoo-hoo-hoo! Here I stand, daring you to move me!";
+        MasqueradeKind::all().into_iter().for_each(|kind| {
+            let masked = kind
+                .masquerader(
+                    MasqueradeRole::Initiator,
+                    SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+                )
+                .mask(data)
+                .unwrap();
+            let mut discriminator = kind.discriminator_factory().make();
+
+            discriminator.add_data(&masked);
+
+            assert_eq!(
+                discriminator.take_chunk(),
+                Some(UnmaskedChunk::new(data.to_vec(), true, false)),
+                "{}",
+                kind
+            );
+        });
+    }
 }
@@ -0,0 +1,284 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::{Metrics, METRICS_PATH};
+use masq_lib::utils::localhost;
+use std::io;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const MAX_REQUEST_HEAD_SIZE: usize = 8192;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const LOCAL_HOST_NAMES: &[&str] = &["localhost", "127.0.0.1", "[::1]"];
+
+// A tiny HTTP server for Prometheus scrapers. It listens only on localhost, answers one request
+// per connection, and serves connections one at a time on its own thread. Requests must name
+// localhost in their Host headers too, so that a web page can't reach it by DNS rebinding.
+pub struct MetricsServer {
+    local_addr: SocketAddr,
+}
+
+impl MetricsServer {
+    pub fn start(port: u16, metrics: Arc<Metrics>) -> io::Result<MetricsServer> {
+        let listener = TcpListener::bind(SocketAddr::new(localhost(), port))?;
+        let local_addr = listener.local_addr()?;
+        let logger = Logger::new("MetricsServer");
+        info!(
+            logger,
+            "Serving metrics at http://{}{}", local_addr, METRICS_PATH
+        );
+        thread::spawn(move || {
+            listener
+                .incoming()
+                .for_each(|stream_result| match stream_result {
+                    Ok(stream) => {
+                        if let Err(e) = Self::serve(stream, &metrics) {
+                            debug!(logger, "Couldn't serve metrics request: {}", e)
+                        }
+                    }
+                    Err(e) => warning!(logger, "Couldn't accept metrics connection: {}", e),
+                })
+        });
+        Ok(MetricsServer { local_addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn serve(mut stream: TcpStream, metrics: &Metrics) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let request_head = Self::read_request_head(&mut stream)?;
+        let port = stream.local_addr()?.port();
+        stream.write_all(respond(&request_head, port, metrics).as_bytes())?;
+        stream.flush()
+    }
+
+    fn read_request_head(stream: &mut TcpStream) -> io::Result<String> {
+        let mut head: Vec<u8> = vec![];
+        let mut buf = [0u8; 1024];
+        while !head.windows(4).any(|window| window == b"\r\n\r\n")
+            && head.len() < MAX_REQUEST_HEAD_SIZE
+        {
+            match stream.read(&mut buf)? {
+                0 => break,
+                len => head.extend_from_slice(&buf[..len]),
+            }
+        }
+        Ok(String::from_utf8_lossy(&head).to_string())
+    }
+}
+
+pub fn respond(request_head: &str, port: u16, metrics: &Metrics) -> String {
+    let mut lines = request_head.lines();
+    let request_line = lines.next().unwrap_or("");
+    let mut words = request_line.split_whitespace();
+    let host_is_local = is_local_host(lines, port);
+    let (status, body) = match (words.next(), words.next()) {
+        (Some("GET"), Some(_)) if !host_is_local => (
+            "403 Forbidden",
+            "Metrics are served only to localhost\n".to_string(),
+        ),
+        (Some("GET"), Some(METRICS_PATH)) => ("200 OK", metrics.render()),
+        (Some("GET"), Some(_)) => (
+            "404 Not Found",
+            format!("Metrics are at {}\n", METRICS_PATH),
+        ),
+        _ => (
+            "405 Method Not Allowed",
+            "Only GET is supported\n".to_string(),
+        ),
+    };
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        PROMETHEUS_CONTENT_TYPE,
+        body.len(),
+        body
+    )
+}
+
+fn is_local_host<'a>(mut header_lines: impl Iterator<Item = &'a str>, port: u16) -> bool {
+    let host_opt = header_lines.find_map(|line| {
+        let mut parts = line.splitn(2, ':');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("host") => {
+                Some(value.trim().to_lowercase())
+            }
+            _ => None,
+        }
+    });
+    match host_opt {
+        Some(host) => LOCAL_HOST_NAMES
+            .iter()
+            .any(|name| host == *name || host == format!("{}:{}", name, port)),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use masq_lib::utils::find_free_port;
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(MAX_REQUEST_HEAD_SIZE, 8192);
+        assert_eq!(READ_TIMEOUT, Duration::from_secs(5));
+        assert_eq!(
+            PROMETHEUS_CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        assert_eq!(LOCAL_HOST_NAMES, &["localhost", "127.0.0.1", "[::1]"]);
+    }
+
+    #[test]
+    fn respond_serves_metrics_for_get_of_metrics_path() {
+        let metrics = Metrics::default();
+        metrics.routing_bytes.add(1234);
+        let body = metrics.render();
+
+        let result = respond(
+            "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            5333,
+            &metrics,
+        );
+
+        assert_eq!(
+            result,
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        );
+        assert_eq!(result.contains("masq_routing_bytes_total 1234\n"), true);
+    }
+
+    #[test]
+    fn respond_rejects_other_paths() {
+        let result = respond(
+            "GET /booga HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            5333,
+            &Metrics::default(),
+        );
+
+        assert_eq!(result.starts_with("HTTP/1.1 404 Not Found\r\n"), true);
+        assert_eq!(result.ends_with("\r\n\r\nMetrics are at /metrics\n"), true);
+    }
+
+    #[test]
+    fn respond_rejects_other_methods_and_garbage() {
+        let post = respond("POST /metrics HTTP/1.1\r\n\r\n", 5333, &Metrics::default());
+        let garbage = respond("", 5333, &Metrics::default());
+
+        assert_eq!(
+            post.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"),
+            true
+        );
+        assert_eq!(
+            garbage.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"),
+            true
+        );
+    }
+
+    #[test]
+    fn respond_accepts_local_host_names_with_and_without_the_port() {
+        let metrics = Metrics::default();
+        let hosts = vec![
+            "localhost",
+            "localhost:5333",
+            "127.0.0.1",
+            "127.0.0.1:5333",
+            "[::1]",
+            "[::1]:5333",
+            "LocalHost:5333",
+        ];
+
+        hosts.into_iter().for_each(|host| {
+            let result = respond(
+                &format!("GET /metrics HTTP/1.1\r\nhost: {}\r\n\r\n", host),
+                5333,
+                &metrics,
+            );
+
+            assert_eq!(result.starts_with("HTTP/1.1 200 OK\r\n"), true, "{}", host);
+        })
+    }
+
+    #[test]
+    fn respond_refuses_foreign_or_missing_host() {
+        let metrics = Metrics::default();
+        let requests = vec![
+            "GET /metrics HTTP/1.1\r\nHost: attacker.example.com\r\n\r\n",
+            "GET /metrics HTTP/1.1\r\nHost: attacker.example.com:5333\r\n\r\n",
+            "GET /metrics HTTP/1.1\r\nHost: localhost:5334\r\n\r\n",
+            "GET /metrics HTTP/1.1\r\nHost: localhost.example.com\r\n\r\n",
+            "GET /metrics HTTP/1.1\r\n\r\n",
+        ];
+
+        requests.into_iter().for_each(|request| {
+            let result = respond(request, 5333, &metrics);
+
+            assert_eq!(
+                result.starts_with("HTTP/1.1 403 Forbidden\r\n"),
+                true,
+                "{}",
+                request
+            );
+            assert_eq!(
+                result.ends_with("\r\n\r\nMetrics are served only to localhost\n"),
+                true
+            );
+        })
+    }
+
+    #[test]
+    fn metrics_server_serves_metrics_on_localhost() {
+        let port = find_free_port();
+        let metrics = Arc::new(Metrics::default());
+        metrics.gossip_received.add(42);
+
+        let subject = MetricsServer::start(port, metrics.clone()).unwrap();
+
+        assert_eq!(subject.local_addr(), SocketAddr::new(localhost(), port));
+        let mut stream = TcpStream::connect(subject.local_addr()).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!(response.starts_with("HTTP/1.1 200 OK\r\n"), true);
+        assert_eq!(response.contains("masq_gossip_received_total 42\n"), true);
+    }
+
+    #[test]
+    fn metrics_server_refuses_request_for_foreign_host() {
+        let port = find_free_port();
+        let subject = MetricsServer::start(port, Arc::new(Metrics::default())).unwrap();
+        let mut stream = TcpStream::connect(subject.local_addr()).unwrap();
+
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: rebound.example.com\r\n\r\n")
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"), true);
+    }
+
+    #[test]
+    fn metrics_server_complains_if_port_is_taken() {
+        let port = find_free_port();
+        let _listener = TcpListener::bind(SocketAddr::new(localhost(), port)).unwrap();
+
+        let result = MetricsServer::start(port, Arc::new(Metrics::default()));
+
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::AddrInUse);
+    }
+}
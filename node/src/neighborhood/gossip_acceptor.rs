@@ -101,7 +101,7 @@ impl GossipHandler for DebutHandler {
                             "Debut from {} for {} contained NodeAddr with no ports",
                             gossip_source, agrs[0].inner.public_key
                         ))
-                    } else if node_addr.ip_addr() == gossip_source.ip() {
+                    } else if node_addr.has_ip_addr(gossip_source.ip()) {
                         Qualification::Matched
                     } else {
                         Qualification::Unmatched
@@ -476,7 +476,7 @@ impl GossipHandler for PassHandler {
                         agrs[0].inner.public_key,
                         node_addr.ip_addr()
                     ))
-                } else if node_addr.ip_addr() == gossip_source.ip() {
+                } else if node_addr.has_ip_addr(gossip_source.ip()) {
                     Qualification::Unmatched
                 } else {
                     Qualification::Matched
@@ -601,26 +601,26 @@ impl IntroductionHandler {
         gossip_source: SocketAddr,
     ) -> Result<bool, Qualification> {
         let first_agr = &agrs_ref[0];
-        let first_ip = match first_agr.node_addr_opt.as_ref() {
+        let first_node_addr = match first_agr.node_addr_opt.as_ref() {
             None => return Err(Qualification::Unmatched),
-            Some(node_addr) => node_addr.ip_addr(),
+            Some(node_addr) => node_addr,
         };
         let second_agr = &agrs_ref[1];
-        let second_ip = match second_agr.node_addr_opt.as_ref() {
+        let second_node_addr = match second_agr.node_addr_opt.as_ref() {
             None => return Err(Qualification::Unmatched),
-            Some(node_addr) => node_addr.ip_addr(),
+            Some(node_addr) => node_addr,
         };
-        if first_ip == gossip_source.ip() {
+        if first_node_addr.has_ip_addr(gossip_source.ip()) {
             Ok(true)
-        } else if second_ip == gossip_source.ip() {
+        } else if second_node_addr.has_ip_addr(gossip_source.ip()) {
             Ok(false)
         } else {
             Err(Qualification::Malformed(format!(
                 "In Introduction, neither {} from {} nor {} from {} claims the source IP {}",
                 first_agr.inner.public_key,
-                first_ip,
+                first_node_addr.ip_addr(),
                 second_agr.inner.public_key,
-                second_ip,
+                second_node_addr.ip_addr(),
                 gossip_source.ip()
             )))
         }
@@ -700,8 +700,7 @@ impl IntroductionHandler {
             .node_addr_opt
             .as_ref()
             .expect("Introducee NodeAddr disappeared")
-            .ip_addr()
-            == gossip_source.ip()
+            .has_ip_addr(gossip_source.ip())
         {
             return Some(Qualification::Malformed(format!(
                 "Introducer {} and introducee {} both claim {}",
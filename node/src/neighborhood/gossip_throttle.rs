@@ -0,0 +1,720 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::neighborhood::gossip::Gossip_0v1;
+use crate::sub_lib::cryptde::PublicKey;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+pub const MAX_GOSSIP_RECORDS: usize = 250;
+pub const MAX_GOSSIP_RECORD_BYTES: usize = 4096;
+pub const GOSSIP_RATE_WINDOW: Duration = Duration::from_secs(10);
+pub const MAX_GOSSIPS_PER_WINDOW_PER_IP: u32 = 20;
+pub const MAX_GOSSIPS_PER_WINDOW_PER_KEY: u32 = 10;
+pub const OFFENSES_BEFORE_BAN: u32 = 5;
+pub const GOSSIP_BAN_DURATION: Duration = Duration::from_secs(300);
+pub const OFFENSE_WINDOW: Duration = Duration::from_secs(300);
+pub const MAX_TRACKED_SOURCES: usize = 10_000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GossipThrottleConfig {
+    pub max_records: usize,
+    pub max_record_bytes: usize,
+    pub rate_window: Duration,
+    pub max_per_window_per_ip: u32,
+    pub max_per_window_per_key: u32,
+    pub offenses_before_ban: u32,
+    pub ban_duration: Duration,
+    pub offense_window: Duration,
+    pub max_tracked_sources: usize,
+}
+
+impl Default for GossipThrottleConfig {
+    fn default() -> Self {
+        GossipThrottleConfig {
+            max_records: MAX_GOSSIP_RECORDS,
+            max_record_bytes: MAX_GOSSIP_RECORD_BYTES,
+            rate_window: GOSSIP_RATE_WINDOW,
+            max_per_window_per_ip: MAX_GOSSIPS_PER_WINDOW_PER_IP,
+            max_per_window_per_key: MAX_GOSSIPS_PER_WINDOW_PER_KEY,
+            offenses_before_ban: OFFENSES_BEFORE_BAN,
+            ban_duration: GOSSIP_BAN_DURATION,
+            offense_window: OFFENSE_WINDOW,
+            max_tracked_sources: MAX_TRACKED_SOURCES,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GossipDropReason {
+    Banned,
+    TooManyRecords(usize),
+    RecordTooLarge(usize),
+    SourceIpRateExceeded,
+    PublicKeyRateExceeded,
+}
+
+impl Display for GossipDropReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GossipDropReason::Banned => write!(f, "source is temporarily banned"),
+            GossipDropReason::TooManyRecords(count) => {
+                write!(f, "Gossip contains {} records", count)
+            }
+            GossipDropReason::RecordTooLarge(size) => {
+                write!(f, "Gossip contains a {}-byte record", size)
+            }
+            GossipDropReason::SourceIpRateExceeded => {
+                write!(f, "source IP address is sending Gossip too fast")
+            }
+            GossipDropReason::PublicKeyRateExceeded => {
+                write!(f, "source public key is sending Gossip too fast")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct GossipDropCounters {
+    pub accepted: u64,
+    pub banned: u64,
+    pub too_many_records: u64,
+    pub record_too_large: u64,
+    pub ip_rate_exceeded: u64,
+    pub key_rate_exceeded: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct RateWindow {
+    started: Instant,
+    count: u32,
+}
+
+// Holds no more than max_keys windows, so a flood of sources can't grow it without bound
+struct RateLimiter<K: Hash + Eq + Clone> {
+    windows: HashMap<K, RateWindow>,
+    max_keys: usize,
+}
+
+impl<K: Hash + Eq + Clone> RateLimiter<K> {
+    fn new(max_keys: usize) -> Self {
+        Self {
+            windows: HashMap::new(),
+            max_keys,
+        }
+    }
+
+    // Counts the arrival; returns false if it puts the source over the limit for the current window.
+    fn admit(&mut self, key: K, now: Instant, window: Duration, limit: u32) -> bool {
+        if !self.windows.contains_key(&key) && self.windows.len() >= self.max_keys {
+            self.make_room(now, window);
+        }
+        let rate_window = self.windows.entry(key).or_insert(RateWindow {
+            started: now,
+            count: 0,
+        });
+        if now.duration_since(rate_window.started) >= window {
+            rate_window.started = now;
+            rate_window.count = 0;
+        }
+        rate_window.count += 1;
+        rate_window.count <= limit
+    }
+
+    // Like admit(), but without counting an arrival: true if the next one would be refused.
+    fn is_full(&self, key: &K, now: Instant, window: Duration, limit: u32) -> bool {
+        match self.windows.get(key) {
+            Some(rate_window) => {
+                now.duration_since(rate_window.started) < window && rate_window.count >= limit
+            }
+            None => false,
+        }
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.windows.remove(key);
+    }
+
+    fn prune(&mut self, now: Instant, window: Duration) {
+        self.windows
+            .retain(|_, rate_window| now.duration_since(rate_window.started) < window);
+    }
+
+    fn make_room(&mut self, now: Instant, window: Duration) {
+        self.prune(now, window);
+        if self.windows.len() < self.max_keys {
+            return;
+        }
+        let oldest_opt = self
+            .windows
+            .iter()
+            .min_by_key(|(_, rate_window)| rate_window.started)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest_opt {
+            self.windows.remove(&oldest);
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.windows.len()
+    }
+}
+
+pub struct GossipThrottle {
+    config: GossipThrottleConfig,
+    by_ip: RateLimiter<IpAddr>,
+    by_key: RateLimiter<PublicKey>,
+    offenses: RateLimiter<IpAddr>,
+    banned_until: HashMap<IpAddr, Instant>,
+    counters: GossipDropCounters,
+    last_pruned: Option<Instant>,
+}
+
+impl Default for GossipThrottle {
+    fn default() -> Self {
+        Self::new(GossipThrottleConfig::default())
+    }
+}
+
+impl GossipThrottle {
+    pub fn new(config: GossipThrottleConfig) -> Self {
+        GossipThrottle {
+            config,
+            by_ip: RateLimiter::new(config.max_tracked_sources),
+            by_key: RateLimiter::new(config.max_tracked_sources),
+            offenses: RateLimiter::new(config.max_tracked_sources),
+            banned_until: HashMap::new(),
+            counters: GossipDropCounters::default(),
+            last_pruned: None,
+        }
+    }
+
+    // Cheap checks only: this runs before any deserialization or signature verification. Only
+    // malformed Gossip counts as an offense; Gossip over the rate limits is just dropped, since a
+    // busy but honest neighbor can exceed them.
+    pub fn check(
+        &mut self,
+        gossip: &Gossip_0v1,
+        source_ip: IpAddr,
+        source_key_opt: Option<&PublicKey>,
+        now: Instant,
+    ) -> Result<(), GossipDropReason> {
+        self.prune_if_necessary(now);
+        let result = self.evaluate(gossip, source_ip, source_key_opt, now);
+        match result {
+            Ok(()) => self.counters.accepted += 1,
+            Err(GossipDropReason::Banned) => self.counters.banned += 1,
+            Err(GossipDropReason::SourceIpRateExceeded) => self.counters.ip_rate_exceeded += 1,
+            Err(GossipDropReason::PublicKeyRateExceeded) => self.counters.key_rate_exceeded += 1,
+            Err(GossipDropReason::TooManyRecords(_)) => {
+                self.counters.too_many_records += 1;
+                self.record_offense(source_ip, now);
+            }
+            Err(GossipDropReason::RecordTooLarge(_)) => {
+                self.counters.record_too_large += 1;
+                self.record_offense(source_ip, now);
+            }
+        }
+        result
+    }
+
+    // For Gossip whose source IP address doesn't belong to a known Node, before its signatures are
+    // verified: refuses it if the key that signed the sender's own record has already used up its
+    // allowance, so that a flood under that key costs no verification. The key isn't charged
+    // here, though, so that forged records can't use up an honest Node's allowance.
+    pub fn screen_signing_key(
+        &mut self,
+        signing_key: &PublicKey,
+        now: Instant,
+    ) -> Result<(), GossipDropReason> {
+        if self.by_key.is_full(
+            signing_key,
+            now,
+            self.config.rate_window,
+            self.config.max_per_window_per_key,
+        ) {
+            self.count_signing_key_drop();
+            return Err(GossipDropReason::PublicKeyRateExceeded);
+        }
+        Ok(())
+    }
+
+    // Once the signatures are verified, the Gossip is charged to that key.
+    pub fn check_signing_key(
+        &mut self,
+        signing_key: &PublicKey,
+        now: Instant,
+    ) -> Result<(), GossipDropReason> {
+        let result = self.admit_key(signing_key, now);
+        if result.is_err() {
+            self.count_signing_key_drop();
+        }
+        result
+    }
+
+    // Returns true if this offense got the source banned. Offenses count toward a ban only within
+    // the offense window, so occasional slips by a well-behaved Node are forgotten.
+    pub fn record_offense(&mut self, source_ip: IpAddr, now: Instant) -> bool {
+        if self.offenses.admit(
+            source_ip,
+            now,
+            self.config.offense_window,
+            self.config.offenses_before_ban.saturating_sub(1),
+        ) {
+            return false;
+        }
+        self.offenses.remove(&source_ip);
+        if !self.banned_until.contains_key(&source_ip)
+            && self.banned_until.len() >= self.config.max_tracked_sources
+        {
+            self.make_room_for_ban(now);
+        }
+        self.banned_until
+            .insert(source_ip, now + self.config.ban_duration);
+        true
+    }
+
+    pub fn is_banned(&self, source_ip: IpAddr, now: Instant) -> bool {
+        match self.banned_until.get(&source_ip) {
+            Some(until) => now < *until,
+            None => false,
+        }
+    }
+
+    pub fn banned_count(&self, now: Instant) -> usize {
+        self.banned_until
+            .values()
+            .filter(|until| now < **until)
+            .count()
+    }
+
+    pub fn counters(&self) -> GossipDropCounters {
+        self.counters
+    }
+
+    fn evaluate(
+        &mut self,
+        gossip: &Gossip_0v1,
+        source_ip: IpAddr,
+        source_key_opt: Option<&PublicKey>,
+        now: Instant,
+    ) -> Result<(), GossipDropReason> {
+        if self.is_banned(source_ip, now) {
+            return Err(GossipDropReason::Banned);
+        }
+        if !self.by_ip.admit(
+            source_ip,
+            now,
+            self.config.rate_window,
+            self.config.max_per_window_per_ip,
+        ) {
+            return Err(GossipDropReason::SourceIpRateExceeded);
+        }
+        if let Some(source_key) = source_key_opt {
+            self.admit_key(source_key, now)?;
+        }
+        let record_count = gossip.node_records.len();
+        if record_count > self.config.max_records {
+            return Err(GossipDropReason::TooManyRecords(record_count));
+        }
+        match gossip
+            .node_records
+            .iter()
+            .map(|gnr| gnr.signed_data.len() + gnr.signature.len())
+            .find(|size| *size > self.config.max_record_bytes)
+        {
+            Some(size) => Err(GossipDropReason::RecordTooLarge(size)),
+            None => Ok(()),
+        }
+    }
+
+    fn admit_key(&mut self, key: &PublicKey, now: Instant) -> Result<(), GossipDropReason> {
+        if self.by_key.admit(
+            key.clone(),
+            now,
+            self.config.rate_window,
+            self.config.max_per_window_per_key,
+        ) {
+            Ok(())
+        } else {
+            Err(GossipDropReason::PublicKeyRateExceeded)
+        }
+    }
+
+    // The Gossip was counted as accepted when it passed check()
+    fn count_signing_key_drop(&mut self) {
+        self.counters.accepted = self.counters.accepted.saturating_sub(1);
+        self.counters.key_rate_exceeded += 1;
+    }
+
+    // Drops expired bans; if every ban is current, lifts the one closest to expiring.
+    fn make_room_for_ban(&mut self, now: Instant) {
+        self.banned_until.retain(|_, until| now < *until);
+        if self.banned_until.len() < self.config.max_tracked_sources {
+            return;
+        }
+        let soonest_opt = self
+            .banned_until
+            .iter()
+            .min_by_key(|(_, until)| **until)
+            .map(|(ip, _)| *ip);
+        if let Some(soonest) = soonest_opt {
+            self.banned_until.remove(&soonest);
+        }
+    }
+
+    fn prune_if_necessary(&mut self, now: Instant) {
+        let due = match self.last_pruned {
+            None => true,
+            Some(last_pruned) => now.duration_since(last_pruned) >= self.config.rate_window,
+        };
+        if !due {
+            return;
+        }
+        self.by_ip.prune(now, self.config.rate_window);
+        self.by_key.prune(now, self.config.rate_window);
+        self.offenses.prune(now, self.config.offense_window);
+        self.banned_until.retain(|_, until| now < *until);
+        self.last_pruned = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neighborhood::gossip::GossipNodeRecord;
+    use crate::sub_lib::cryptde::{CryptData, PlainData};
+    use std::str::FromStr;
+
+    fn make_gossip(record_count: usize, record_size: usize) -> Gossip_0v1 {
+        Gossip_0v1 {
+            node_records: (0..record_count)
+                .map(|_| GossipNodeRecord {
+                    signed_data: PlainData::new(&vec![0u8; record_size]),
+                    signature: CryptData::new(&[]),
+                    node_addr_opt: None,
+                })
+                .collect(),
+        }
+    }
+
+    fn make_config() -> GossipThrottleConfig {
+        GossipThrottleConfig {
+            max_records: 3,
+            max_record_bytes: 10,
+            rate_window: Duration::from_secs(10),
+            max_per_window_per_ip: 2,
+            max_per_window_per_key: 1,
+            offenses_before_ban: 2,
+            ban_duration: Duration::from_secs(60),
+            offense_window: Duration::from_secs(30),
+            max_tracked_sources: 100,
+        }
+    }
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(MAX_GOSSIP_RECORDS, 250);
+        assert_eq!(MAX_GOSSIP_RECORD_BYTES, 4096);
+        assert_eq!(GOSSIP_RATE_WINDOW, Duration::from_secs(10));
+        assert_eq!(MAX_GOSSIPS_PER_WINDOW_PER_IP, 20);
+        assert_eq!(MAX_GOSSIPS_PER_WINDOW_PER_KEY, 10);
+        assert_eq!(OFFENSES_BEFORE_BAN, 5);
+        assert_eq!(GOSSIP_BAN_DURATION, Duration::from_secs(300));
+        assert_eq!(OFFENSE_WINDOW, Duration::from_secs(300));
+        assert_eq!(MAX_TRACKED_SOURCES, 10_000);
+    }
+
+    #[test]
+    fn acceptable_gossip_is_counted_and_accepted() {
+        let mut subject = GossipThrottle::new(make_config());
+        let now = Instant::now();
+
+        let result = subject.check(
+            &make_gossip(3, 10),
+            IpAddr::from_str("1.2.3.4").unwrap(),
+            Some(&PublicKey::new(b"key")),
+            now,
+        );
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            subject.counters(),
+            GossipDropCounters {
+                accepted: 1,
+                ..GossipDropCounters::default()
+            }
+        );
+    }
+
+    #[test]
+    fn gossip_with_too_many_records_is_dropped() {
+        let mut subject = GossipThrottle::new(make_config());
+
+        let result = subject.check(
+            &make_gossip(4, 1),
+            IpAddr::from_str("1.2.3.4").unwrap(),
+            None,
+            Instant::now(),
+        );
+
+        assert_eq!(result, Err(GossipDropReason::TooManyRecords(4)));
+        assert_eq!(subject.counters().too_many_records, 1);
+    }
+
+    #[test]
+    fn gossip_with_oversized_record_is_dropped() {
+        let mut subject = GossipThrottle::new(make_config());
+
+        let result = subject.check(
+            &make_gossip(1, 11),
+            IpAddr::from_str("1.2.3.4").unwrap(),
+            None,
+            Instant::now(),
+        );
+
+        assert_eq!(result, Err(GossipDropReason::RecordTooLarge(11)));
+        assert_eq!(subject.counters().record_too_large, 1);
+    }
+
+    #[test]
+    fn source_ip_rate_is_limited_until_window_expires() {
+        let mut subject = GossipThrottle::new(GossipThrottleConfig {
+            offenses_before_ban: 100,
+            ..make_config()
+        });
+        let ip = IpAddr::from_str("1.2.3.4").unwrap();
+        let gossip = make_gossip(1, 1);
+        let now = Instant::now();
+
+        let first = subject.check(&gossip, ip, None, now);
+        let second = subject.check(&gossip, ip, None, now);
+        let third = subject.check(&gossip, ip, None, now);
+        let other_ip = subject.check(&gossip, IpAddr::from_str("2.3.4.5").unwrap(), None, now);
+        let later = subject.check(&gossip, ip, None, now + Duration::from_secs(10));
+
+        assert_eq!(first, Ok(()));
+        assert_eq!(second, Ok(()));
+        assert_eq!(third, Err(GossipDropReason::SourceIpRateExceeded));
+        assert_eq!(other_ip, Ok(()));
+        assert_eq!(later, Ok(()));
+        assert_eq!(subject.counters().ip_rate_exceeded, 1);
+    }
+
+    #[test]
+    fn public_key_rate_is_limited_across_ip_addresses() {
+        let mut subject = GossipThrottle::new(make_config());
+        let key = PublicKey::new(b"key");
+        let gossip = make_gossip(1, 1);
+        let now = Instant::now();
+
+        let first = subject.check(
+            &gossip,
+            IpAddr::from_str("1.2.3.4").unwrap(),
+            Some(&key),
+            now,
+        );
+        let second = subject.check(
+            &gossip,
+            IpAddr::from_str("2.3.4.5").unwrap(),
+            Some(&key),
+            now,
+        );
+
+        assert_eq!(first, Ok(()));
+        assert_eq!(second, Err(GossipDropReason::PublicKeyRateExceeded));
+        assert_eq!(subject.counters().key_rate_exceeded, 1);
+    }
+
+    #[test]
+    fn gossip_over_the_rate_limits_is_dropped_without_counting_as_an_offense() {
+        let mut subject = GossipThrottle::new(make_config());
+        let ip = IpAddr::from_str("1.2.3.4").unwrap();
+        let key = PublicKey::new(b"key");
+        let gossip = make_gossip(1, 1);
+        let now = Instant::now();
+
+        let results = (0..6)
+            .map(|_| subject.check(&gossip, ip, Some(&key), now))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            vec![
+                Ok(()),
+                Err(GossipDropReason::PublicKeyRateExceeded),
+                Err(GossipDropReason::SourceIpRateExceeded),
+                Err(GossipDropReason::SourceIpRateExceeded),
+                Err(GossipDropReason::SourceIpRateExceeded),
+                Err(GossipDropReason::SourceIpRateExceeded),
+            ]
+        );
+        assert_eq!(subject.is_banned(ip, now), false);
+        assert_eq!(subject.offenses.len(), 0);
+    }
+
+    #[test]
+    fn signing_key_rate_is_limited_along_with_source_keys() {
+        let mut subject = GossipThrottle::new(make_config());
+        let key = PublicKey::new(b"key");
+        let gossip = make_gossip(1, 1);
+        let now = Instant::now();
+        let _ = subject.check(&gossip, IpAddr::from_str("1.2.3.4").unwrap(), None, now);
+
+        let first = subject.check_signing_key(&key, now);
+        let _ = subject.check(&gossip, IpAddr::from_str("2.3.4.5").unwrap(), None, now);
+        let second = subject.check_signing_key(&key, now);
+        let known_source = subject.check(
+            &gossip,
+            IpAddr::from_str("3.4.5.6").unwrap(),
+            Some(&key),
+            now,
+        );
+        let later = subject.check_signing_key(&key, now + Duration::from_secs(10));
+
+        assert_eq!(first, Ok(()));
+        assert_eq!(second, Err(GossipDropReason::PublicKeyRateExceeded));
+        assert_eq!(known_source, Err(GossipDropReason::PublicKeyRateExceeded));
+        assert_eq!(later, Ok(()));
+        assert_eq!(
+            subject.counters(),
+            GossipDropCounters {
+                accepted: 1,
+                key_rate_exceeded: 2,
+                ..GossipDropCounters::default()
+            }
+        );
+        assert_eq!(subject.offenses.len(), 0);
+    }
+
+    #[test]
+    fn signing_key_screening_refuses_only_keys_already_over_the_limit_and_charges_nothing() {
+        let mut subject = GossipThrottle::new(make_config());
+        let key = PublicKey::new(b"key");
+        let gossip = make_gossip(1, 1);
+        let now = Instant::now();
+        let _ = subject.check(&gossip, IpAddr::from_str("1.2.3.4").unwrap(), None, now);
+        let _ = subject.check(&gossip, IpAddr::from_str("2.3.4.5").unwrap(), None, now);
+
+        let first_screening = subject.screen_signing_key(&key, now);
+        let second_screening = subject.screen_signing_key(&key, now);
+        let charge = subject.check_signing_key(&key, now);
+        let third_screening = subject.screen_signing_key(&key, now);
+        let later_screening = subject.screen_signing_key(&key, now + Duration::from_secs(10));
+
+        assert_eq!(first_screening, Ok(()));
+        assert_eq!(second_screening, Ok(()));
+        assert_eq!(charge, Ok(()));
+        assert_eq!(
+            third_screening,
+            Err(GossipDropReason::PublicKeyRateExceeded)
+        );
+        assert_eq!(later_screening, Ok(()));
+        assert_eq!(
+            subject.counters(),
+            GossipDropCounters {
+                accepted: 1,
+                key_rate_exceeded: 1,
+                ..GossipDropCounters::default()
+            }
+        );
+        assert_eq!(subject.offenses.len(), 0);
+    }
+
+    #[test]
+    fn repeated_offenders_are_banned_until_cool_down_expires() {
+        let mut subject = GossipThrottle::new(make_config());
+        let ip = IpAddr::from_str("1.2.3.4").unwrap();
+        let now = Instant::now();
+        let _ = subject.check(&make_gossip(4, 1), ip, None, now);
+        let _ = subject.check(&make_gossip(4, 1), ip, None, now);
+
+        let during_ban = subject.check(&make_gossip(1, 1), ip, None, now + Duration::from_secs(59));
+        let banned_count = subject.banned_count(now + Duration::from_secs(59));
+        let after_ban = subject.check(&make_gossip(1, 1), ip, None, now + Duration::from_secs(60));
+
+        assert_eq!(during_ban, Err(GossipDropReason::Banned));
+        assert_eq!(banned_count, 1);
+        assert_eq!(after_ban, Ok(()));
+        assert_eq!(subject.counters().banned, 1);
+        assert_eq!(subject.banned_count(now + Duration::from_secs(60)), 0);
+    }
+
+    #[test]
+    fn offenses_recorded_from_outside_count_toward_a_ban() {
+        let mut subject = GossipThrottle::new(make_config());
+        let ip = IpAddr::from_str("1.2.3.4").unwrap();
+        let now = Instant::now();
+
+        let first = subject.record_offense(ip, now);
+        let second = subject.record_offense(ip, now);
+
+        assert_eq!(first, false);
+        assert_eq!(second, true);
+        assert_eq!(subject.is_banned(ip, now), true);
+        assert_eq!(
+            subject.is_banned(IpAddr::from_str("2.3.4.5").unwrap(), now),
+            false
+        );
+    }
+
+    #[test]
+    fn offenses_outside_the_offense_window_are_forgotten() {
+        let mut subject = GossipThrottle::new(make_config());
+        let ip = IpAddr::from_str("1.2.3.4").unwrap();
+        let now = Instant::now();
+
+        let first = subject.record_offense(ip, now);
+        let second = subject.record_offense(ip, now + Duration::from_secs(30));
+        let third = subject.record_offense(ip, now + Duration::from_secs(31));
+
+        assert_eq!(first, false);
+        assert_eq!(second, false);
+        assert_eq!(third, true);
+    }
+
+    #[test]
+    fn tracked_sources_are_bounded() {
+        let mut subject = GossipThrottle::new(GossipThrottleConfig {
+            max_tracked_sources: 3,
+            offenses_before_ban: 100,
+            ..make_config()
+        });
+        let gossip = make_gossip(4, 1);
+        let now = Instant::now();
+
+        (0..10u8).for_each(|n| {
+            let ip = IpAddr::from([1, 2, 3, n]);
+            let _ = subject.check(&gossip, ip, Some(&PublicKey::new(&[n])), now);
+        });
+
+        assert_eq!(subject.by_ip.len(), 3);
+        assert_eq!(subject.by_key.len(), 3);
+        assert_eq!(subject.offenses.len(), 3);
+    }
+
+    #[test]
+    fn bans_are_bounded() {
+        let mut subject = GossipThrottle::new(GossipThrottleConfig {
+            max_tracked_sources: 2,
+            offenses_before_ban: 1,
+            ..make_config()
+        });
+        let now = Instant::now();
+        let first_ip = IpAddr::from_str("1.2.3.4").unwrap();
+        let second_ip = IpAddr::from_str("2.3.4.5").unwrap();
+        let third_ip = IpAddr::from_str("3.4.5.6").unwrap();
+
+        subject.record_offense(first_ip, now);
+        subject.record_offense(second_ip, now + Duration::from_secs(1));
+        subject.record_offense(third_ip, now + Duration::from_secs(2));
+
+        let later = now + Duration::from_secs(2);
+        assert_eq!(subject.banned_count(later), 2);
+        assert_eq!(subject.is_banned(first_ip, later), false);
+        assert_eq!(subject.is_banned(second_ip, later), true);
+        assert_eq!(subject.is_banned(third_ip, later), true);
+    }
+}
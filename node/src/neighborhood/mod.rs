@@ -7,26 +7,37 @@ pub mod gossip_acceptor;
 mod gossip_producer;
 #[cfg(feature = "expose_test_privates")]
 pub mod gossip_producer;
+pub mod gossip_throttle;
 pub mod neighborhood_database;
+pub mod neighborhood_snapshot;
 pub mod node_record;
 
 use crate::blockchain::blockchain_interface::{chain_id_from_name, contract_address};
 use crate::bootstrapper::BootstrapperConfig;
+use crate::daemon::crash_report::NodeShutdown;
 use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
 use crate::db_config::persistent_configuration::{
     PersistentConfiguration, PersistentConfigurationReal,
 };
 use crate::neighborhood::gossip::{DotGossipEndpoint, GossipNodeRecord, Gossip_0v1};
 use crate::neighborhood::gossip_acceptor::GossipAcceptanceResult;
+use crate::neighborhood::gossip_throttle::{GossipDropReason, GossipThrottle};
+use crate::neighborhood::neighborhood_snapshot::{
+    NeighborhoodSnapshot, NEIGHBORHOOD_SNAPSHOT_INTERVAL,
+};
 use crate::neighborhood::node_record::NodeRecordInner_0v1;
 use crate::stream_messages::RemovedStreamType;
+use crate::sub_lib::bandwidth::{bandwidth_throttle, BandwidthThrottle};
 use crate::sub_lib::configurator::NewPasswordMessage;
 use crate::sub_lib::cryptde::PublicKey;
 use crate::sub_lib::cryptde::{CryptDE, CryptData, PlainData};
 use crate::sub_lib::dispatcher::{Component, StreamShutdownMsg};
-use crate::sub_lib::hopper::{ExpiredCoresPackage, NoLookupIncipientCoresPackage};
+use crate::sub_lib::hopper::{
+    ExpiredCoresPackage, NoLookupIncipientCoresPackage, SessionKeyPeersMsg,
+};
 use crate::sub_lib::hopper::{IncipientCoresPackage, MessageType};
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::{metrics, Metrics};
 use crate::sub_lib::neighborhood::ExpectedService;
 use crate::sub_lib::neighborhood::ExpectedServices;
 use crate::sub_lib::neighborhood::NeighborhoodSubs;
@@ -38,6 +49,7 @@ use crate::sub_lib::neighborhood::RemoveNeighborMessage;
 use crate::sub_lib::neighborhood::RouteQueryMessage;
 use crate::sub_lib::neighborhood::RouteQueryResponse;
 use crate::sub_lib::neighborhood::{DispatcherNodeQueryMessage, GossipFailure_0v1};
+use crate::sub_lib::neighborhood::{NeighborhoodStatus, NeighborhoodStatusQueryMessage};
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
 use crate::sub_lib::proxy_server::DEFAULT_MINIMUM_HOP_COUNT;
@@ -45,10 +57,11 @@ use crate::sub_lib::route::Route;
 use crate::sub_lib::route::RouteSegment;
 use crate::sub_lib::set_consuming_wallet_message::SetConsumingWalletMessage;
 use crate::sub_lib::stream_handler_pool::DispatcherNodeQueryResponse;
-use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
+use crate::sub_lib::utils::{time_t_timestamp, NODE_MAILBOX_CAPACITY};
 use crate::sub_lib::versioned_data::VersionedData;
 use crate::sub_lib::wallet::Wallet;
 use actix::Addr;
+use actix::AsyncContext;
 use actix::Context;
 use actix::Handler;
 use actix::MessageResult;
@@ -60,18 +73,26 @@ use gossip_producer::GossipProducer;
 use gossip_producer::GossipProducerReal;
 use itertools::Itertools;
 use masq_lib::constants::DEFAULT_CHAIN_NAME;
-use masq_lib::messages::FromMessageBody;
 use masq_lib::messages::UiShutdownRequest;
+use masq_lib::messages::{FromMessageBody, ToMessageBody};
+use masq_lib::messages::{UiGossipStatsRequest, UiGossipStatsResponse};
+use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{NodeFromUiMessage, NodeToUiMessage};
 use masq_lib::utils::exit_process;
 use neighborhood_database::NeighborhoodDatabase;
 use node_record::NodeRecord;
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub const CRASH_KEY: &str = "NEIGHBORHOOD";
+// How often the Neighborhood checks whether bandwidth limits have the Node holding back traffic,
+// so that it can tell its neighbors about it.
+pub const REDUCED_CAPACITY_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
 pub struct Neighborhood {
     cryptde: &'static dyn CryptDE,
@@ -79,9 +100,11 @@ pub struct Neighborhood {
     hopper_no_lookup: Option<Recipient<NoLookupIncipientCoresPackage>>,
     is_connected: bool,
     connected_signal: Option<Recipient<StartMessage>>,
-    _to_ui_message_sub: Option<Recipient<NodeToUiMessage>>,
+    to_ui_message_sub: Option<Recipient<NodeToUiMessage>>,
+    session_key_peers_sub: Option<Recipient<SessionKeyPeersMsg>>,
     gossip_acceptor: Box<dyn GossipAcceptor>,
     gossip_producer: Box<dyn GossipProducer>,
+    gossip_throttle: GossipThrottle,
     neighborhood_database: NeighborhoodDatabase,
     consuming_wallet_opt: Option<Wallet>,
     next_return_route_id: u32,
@@ -90,6 +113,9 @@ pub struct Neighborhood {
     data_directory: PathBuf,
     persistent_config_opt: Option<Box<dyn PersistentConfiguration>>,
     db_password_opt: Option<String>,
+    session_key_peers: HashSet<PublicKey>,
+    metrics: Arc<Metrics>,
+    bandwidth_throttle: Arc<BandwidthThrottle>,
     logger: Logger,
 }
 
@@ -105,14 +131,23 @@ impl Handler<BindMessage> for Neighborhood {
         self.hopper = Some(msg.peer_actors.hopper.from_hopper_client);
         self.hopper_no_lookup = Some(msg.peer_actors.hopper.from_hopper_client_no_lookup);
         self.connected_signal = Some(msg.peer_actors.accountant.start);
+        self.to_ui_message_sub = Some(msg.peer_actors.ui_gateway.node_to_ui_message_sub);
+        self.session_key_peers_sub = Some(msg.peer_actors.hopper.session_key_peers);
     }
 }
 
 impl Handler<StartMessage> for Neighborhood {
     type Result = ();
 
-    fn handle(&mut self, _msg: StartMessage, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, _msg: StartMessage, ctx: &mut Self::Context) -> Self::Result {
         self.handle_start_message();
+
+        ctx.run_interval(NEIGHBORHOOD_SNAPSHOT_INTERVAL, |neighborhood, _ctx| {
+            neighborhood.persist_neighborhood_snapshot();
+        });
+        ctx.run_interval(REDUCED_CAPACITY_CHECK_INTERVAL, |neighborhood, _ctx| {
+            neighborhood.check_reduced_capacity();
+        });
     }
 }
 
@@ -138,14 +173,17 @@ impl Handler<NodeQueryMessage> for Neighborhood {
         };
 
         MessageResult(match node_record_ref_opt {
-            Some(node_record_ref) => Some(NodeQueryResponseMetadata::new(
-                node_record_ref.public_key().clone(),
-                match node_record_ref.node_addr_opt() {
-                    Some(node_addr_ref) => Some(node_addr_ref),
-                    None => None,
-                },
-                node_record_ref.rate_pack().clone(),
-            )),
+            Some(node_record_ref) => Some(NodeQueryResponseMetadata {
+                accepts_masquerades: node_record_ref.accepts_masquerades(),
+                ..NodeQueryResponseMetadata::new(
+                    node_record_ref.public_key().clone(),
+                    match node_record_ref.node_addr_opt() {
+                        Some(node_addr_ref) => Some(node_addr_ref),
+                        None => None,
+                    },
+                    node_record_ref.rate_pack().clone(),
+                )
+            }),
             None => None,
         })
     }
@@ -165,14 +203,17 @@ impl Handler<DispatcherNodeQueryMessage> for Neighborhood {
         };
 
         let node_descriptor = match node_record_ref_opt {
-            Some(node_record_ref) => Some(NodeQueryResponseMetadata::new(
-                node_record_ref.public_key().clone(),
-                match node_record_ref.node_addr_opt() {
-                    Some(node_addr) => Some(node_addr),
-                    None => None,
-                },
-                node_record_ref.rate_pack().clone(),
-            )),
+            Some(node_record_ref) => Some(NodeQueryResponseMetadata {
+                accepts_masquerades: node_record_ref.accepts_masquerades(),
+                ..NodeQueryResponseMetadata::new(
+                    node_record_ref.public_key().clone(),
+                    match node_record_ref.node_addr_opt() {
+                        Some(node_addr) => Some(node_addr),
+                        None => None,
+                    },
+                    node_record_ref.rate_pack().clone(),
+                )
+            }),
             None => None,
         };
 
@@ -200,6 +241,18 @@ impl Handler<RouteQueryMessage> for Neighborhood {
     }
 }
 
+impl Handler<NeighborhoodStatusQueryMessage> for Neighborhood {
+    type Result = MessageResult<NeighborhoodStatusQueryMessage>;
+
+    fn handle(
+        &mut self,
+        _msg: NeighborhoodStatusQueryMessage,
+        _ctx: &mut Self::Context,
+    ) -> <Self as Handler<NeighborhoodStatusQueryMessage>>::Result {
+        MessageResult(self.handle_status_query())
+    }
+}
+
 impl Handler<ExpiredCoresPackage<Gossip_0v1>> for Neighborhood {
     type Result = ();
 
@@ -209,6 +262,9 @@ impl Handler<ExpiredCoresPackage<Gossip_0v1>> for Neighborhood {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let incoming_gossip = msg.payload;
+        if self.gossip_is_throttled(&incoming_gossip, msg.immediate_neighbor) {
+            return;
+        }
         self.log_incoming_gossip(&incoming_gossip, msg.immediate_neighbor);
         self.handle_gossip(incoming_gossip, msg.immediate_neighbor);
     }
@@ -273,8 +329,10 @@ impl Handler<NodeFromUiMessage> for Neighborhood {
 
     fn handle(&mut self, msg: NodeFromUiMessage, _ctx: &mut Self::Context) -> Self::Result {
         let client_id = msg.client_id;
-        if let Ok((body, _)) = UiShutdownRequest::fmb(msg.body) {
+        if let Ok((body, _)) = UiShutdownRequest::fmb(msg.body.clone()) {
             self.handle_shutdown_order(client_id, body);
+        } else if let Ok((_, context_id)) = UiGossipStatsRequest::fmb(msg.body) {
+            self.handle_gossip_stats_request(client_id, context_id);
         }
     }
 }
@@ -337,12 +395,16 @@ impl Neighborhood {
         }
         let gossip_acceptor: Box<dyn GossipAcceptor> = Box::new(GossipAcceptorReal::new(cryptde));
         let gossip_producer = Box::new(GossipProducerReal::new());
-        let neighborhood_database = NeighborhoodDatabase::new(
+        let mut neighborhood_database = NeighborhoodDatabase::new(
             &cryptde.public_key(),
             neighborhood_config.mode.clone(),
             config.earning_wallet.clone(),
             cryptde,
         );
+        let root = neighborhood_database.root_mut();
+        root.set_accepts_session_keys(true);
+        root.set_accepts_masquerades(true);
+        root.regenerate_signed_gossip(cryptde);
         let is_mainnet =
             || config.blockchain_bridge_config.chain_id == chain_id_from_name(DEFAULT_CHAIN_NAME);
         let initial_neighbors: Vec<NodeDescriptor> = neighborhood_config
@@ -366,10 +428,12 @@ impl Neighborhood {
             hopper: None,
             hopper_no_lookup: None,
             connected_signal: None,
-            _to_ui_message_sub: None,
+            to_ui_message_sub: None,
+            session_key_peers_sub: None,
             is_connected: false,
             gossip_acceptor,
             gossip_producer,
+            gossip_throttle: GossipThrottle::default(),
             neighborhood_database,
             consuming_wallet_opt: config.consuming_wallet.clone(),
             next_return_route_id: 0,
@@ -378,6 +442,9 @@ impl Neighborhood {
             data_directory: config.data_directory.clone(),
             persistent_config_opt: None,
             db_password_opt: config.db_password_opt.clone(),
+            session_key_peers: HashSet::new(),
+            metrics: metrics(),
+            bandwidth_throttle: bandwidth_throttle(),
             logger: Logger::new("Neighborhood"),
         }
     }
@@ -388,6 +455,7 @@ impl Neighborhood {
             start: addr.clone().recipient::<StartMessage>(),
             node_query: addr.clone().recipient::<NodeQueryMessage>(),
             route_query: addr.clone().recipient::<RouteQueryMessage>(),
+            status_query: addr.clone().recipient::<NeighborhoodStatusQueryMessage>(),
             update_node_record_metadata: addr.clone().recipient::<NodeRecordMetadataMessage>(),
             from_hopper: addr.clone().recipient::<ExpiredCoresPackage<Gossip_0v1>>(),
             gossip_failure: addr
@@ -404,6 +472,7 @@ impl Neighborhood {
 
     fn handle_start_message(&mut self) {
         self.connect_database();
+        self.restore_neighborhood_snapshot();
         self.send_debut_gossip();
     }
 
@@ -416,6 +485,7 @@ impl Neighborhood {
         };
         match route_result {
             Ok(response) => {
+                self.metrics.route_query_successes.increment();
                 debug!(
                     self.logger,
                     "Processed {} into {}-hop response",
@@ -425,12 +495,47 @@ impl Neighborhood {
                 Some(response)
             }
             Err(msg) => {
+                self.metrics.route_query_failures.increment();
                 error!(self.logger, "Unsatisfied route query: {}", msg);
                 None
             }
         }
     }
 
+    fn handle_status_query(&self) -> NeighborhoodStatus {
+        let root = self.neighborhood_database.root();
+        NeighborhoodStatus {
+            node_descriptor: root
+                .node_descriptor(self.chain_id, self.cryptde)
+                .to_string(self.cryptde),
+            neighbor_count: root.full_neighbor_keys(&self.neighborhood_database).len(),
+            round_trip_route_available: self.round_trip_route_available(),
+        }
+    }
+
+    // Like make_round_trip_route(), but without using up a return route ID
+    fn round_trip_route_available(&self) -> bool {
+        let own_key = self.cryptde.public_key();
+        match self.make_route_segment(
+            own_key,
+            None,
+            DEFAULT_MINIMUM_HOP_COUNT,
+            Component::ProxyClient,
+            RouteDirection::Over,
+        ) {
+            Ok(over) => self
+                .make_route_segment(
+                    over.keys.last().expect("Empty segment"),
+                    Some(own_key),
+                    DEFAULT_MINIMUM_HOP_COUNT,
+                    Component::ProxyServer,
+                    RouteDirection::Back,
+                )
+                .is_ok(),
+            Err(_) => false,
+        }
+    }
+
     fn connect_database(&mut self) {
         if self.persistent_config_opt.is_none() {
             let db_initializer = DbInitializerReal::new();
@@ -441,6 +546,62 @@ impl Neighborhood {
         }
     }
 
+    fn restore_neighborhood_snapshot(&mut self) {
+        let snapshot_result = self
+            .persistent_config_opt
+            .as_ref()
+            .expect("PersistentConfig was not set by StartMessage")
+            .neighborhood_snapshot();
+        match snapshot_result {
+            Ok(None) => debug!(self.logger, "No neighborhood snapshot to restore"),
+            Ok(Some(snapshot)) => {
+                let offered = snapshot.records.len();
+                let restored = snapshot.restore_into(
+                    &mut self.neighborhood_database,
+                    self.cryptde,
+                    time_t_timestamp(),
+                    &self.logger,
+                );
+                info!(
+                    self.logger,
+                    "Restored {} of {} Nodes from neighborhood snapshot", restored, offered
+                );
+            }
+            Err(e) => error!(
+                self.logger,
+                "Could not retrieve neighborhood snapshot: {:?}", e
+            ),
+        }
+    }
+
+    fn persist_neighborhood_snapshot(&mut self) {
+        if self.neighborhood_database.keys().len() < 2 {
+            debug!(
+                self.logger,
+                "Neighborhood is empty; declining to overwrite snapshot"
+            );
+            return;
+        }
+        let snapshot =
+            NeighborhoodSnapshot::from_database(&self.neighborhood_database, time_t_timestamp());
+        let record_count = snapshot.records.len();
+        match self
+            .persistent_config_opt
+            .as_mut()
+            .expect("PersistentConfig was not set by StartMessage")
+            .set_neighborhood_snapshot(Some(snapshot))
+        {
+            Ok(_) => debug!(
+                self.logger,
+                "Persisted neighborhood snapshot of {} Nodes", record_count
+            ),
+            Err(e) => error!(
+                self.logger,
+                "Could not persist neighborhood snapshot: {:?}", e
+            ),
+        }
+    }
+
     fn send_debut_gossip(&mut self) {
         if self.initial_neighbors.is_empty() {
             info!(self.logger, "Empty. No Nodes to report to; continuing");
@@ -485,6 +646,94 @@ impl Neighborhood {
         });
     }
 
+    fn gossip_is_throttled(
+        &mut self,
+        incoming_gossip: &Gossip_0v1,
+        gossip_source: SocketAddr,
+    ) -> bool {
+        let source_key_opt = self
+            .neighborhood_database
+            .node_by_ip(&gossip_source.ip())
+            .map(|node| node.public_key().clone());
+        match self.gossip_throttle.check(
+            incoming_gossip,
+            gossip_source.ip(),
+            source_key_opt.as_ref(),
+            Instant::now(),
+        ) {
+            Ok(()) => false,
+            Err(reason) => {
+                warning!(
+                    self.logger,
+                    "Dropping Gossip about {} Nodes from {}: {}",
+                    incoming_gossip.node_records.len(),
+                    gossip_source,
+                    reason
+                );
+                true
+            }
+        }
+    }
+
+    // Gossip from a known Node was charged to that Node's key before it was deserialized. Gossip
+    // from anywhere else is charged to the key that signed the sender's own record, so that a Node
+    // can't get around its key's limit by sending from new IP addresses. This finds that key.
+    fn unknown_sender_signing_key(
+        &self,
+        agrs: &[AccessibleGossipRecord],
+        gossip_source: SocketAddr,
+    ) -> Option<PublicKey> {
+        if self
+            .neighborhood_database
+            .node_by_ip(&gossip_source.ip())
+            .is_some()
+        {
+            return None;
+        }
+        agrs.iter()
+            .find(|agr| {
+                agr.node_addr_opt
+                    .as_ref()
+                    .map(|node_addr| node_addr.has_ip_addr(gossip_source.ip()))
+                    .unwrap_or(false)
+            })
+            .map(|agr| agr.inner.public_key.clone())
+    }
+
+    fn gossip_signer_is_throttled(
+        &self,
+        signer_check: Result<(), GossipDropReason>,
+        record_count: usize,
+        gossip_source: SocketAddr,
+    ) -> bool {
+        match signer_check {
+            Ok(()) => false,
+            Err(reason) => {
+                warning!(
+                    self.logger,
+                    "Dropping Gossip about {} Nodes from {}: {}",
+                    record_count,
+                    gossip_source,
+                    reason
+                );
+                true
+            }
+        }
+    }
+
+    fn record_gossip_offense(&mut self, gossip_source: SocketAddr) {
+        if self
+            .gossip_throttle
+            .record_offense(gossip_source.ip(), Instant::now())
+        {
+            warning!(
+                self.logger,
+                "Banning Gossip from {} temporarily after repeated offenses",
+                gossip_source.ip()
+            );
+        }
+    }
+
     fn log_incoming_gossip(&self, incoming_gossip: &Gossip_0v1, gossip_source: SocketAddr) {
         let source = match self.neighborhood_database.node_by_ip(&gossip_source.ip()) {
             Some(node) => DotGossipEndpoint::from(node),
@@ -498,6 +747,7 @@ impl Neighborhood {
     }
 
     fn handle_gossip(&mut self, incoming_gossip: Gossip_0v1, gossip_source: SocketAddr) {
+        self.metrics.gossip_received.increment();
         let record_count = incoming_gossip.node_records.len();
         info!(
             self.logger,
@@ -510,15 +760,28 @@ impl Neighborhood {
             .collect();
 
         if agrs.len() < record_count {
-            // TODO: Instead of ignoring non-deserializable Gossip, ban the Node that sent it
             error!(
                 self.logger,
                 "Received non-deserializable Gossip from {}", gossip_source
             );
+            self.record_gossip_offense(gossip_source);
             self.announce_gossip_handling_completion(record_count);
             return;
         }
 
+        // A signing key over its limit is refused before any signature is verified, but charged
+        // only once they all are.
+        let signing_key_opt = self.unknown_sender_signing_key(&agrs, gossip_source);
+        if let Some(signing_key) = signing_key_opt.as_ref() {
+            let screening = self
+                .gossip_throttle
+                .screen_signing_key(signing_key, Instant::now());
+            if self.gossip_signer_is_throttled(screening, record_count, gossip_source) {
+                self.announce_gossip_handling_completion(record_count);
+                return;
+            }
+        }
+
         let signature_invalid = |agr: &AccessibleGossipRecord| {
             !self.cryptde.verify_signature(
                 &agr.signed_gossip,
@@ -527,15 +790,25 @@ impl Neighborhood {
             )
         };
         if agrs.iter().any(signature_invalid) {
-            // TODO: Instead of ignoring badly-signed Gossip, ban the Node that sent it
             error!(
                 self.logger,
                 "Received Gossip with invalid signature from {}", gossip_source
             );
+            self.record_gossip_offense(gossip_source);
             self.announce_gossip_handling_completion(record_count);
             return;
         }
 
+        if let Some(signing_key) = signing_key_opt.as_ref() {
+            let charge = self
+                .gossip_throttle
+                .check_signing_key(signing_key, Instant::now());
+            if self.gossip_signer_is_throttled(charge, record_count, gossip_source) {
+                self.announce_gossip_handling_completion(record_count);
+                return;
+            }
+        }
+
         self.handle_gossip_agrs(agrs, gossip_source);
         self.announce_gossip_handling_completion(record_count);
     }
@@ -546,7 +819,7 @@ impl Neighborhood {
             .iter()
             .find_position(|n| match &n.node_addr_opt {
                 None => false,
-                Some(node_addr) => node_addr.ip_addr() == failure_source.ip(),
+                Some(node_addr) => node_addr.has_ip_addr(failure_source.ip()),
             }) {
             None => unimplemented!("TODO: Test-drive me (or replace me with a panic)"),
             Some((position, node_descriptor)) => {
@@ -588,6 +861,35 @@ impl Neighborhood {
         self.handle_agrs(agrs, gossip_source);
         let neighbor_keys_after = self.neighbor_keys();
         self.handle_database_changes(&neighbor_keys_before, &neighbor_keys_after);
+        self.update_session_key_peers();
+    }
+
+    // Tells the Hopper which Nodes can take hops sealed in session-key envelopes, if that's changed.
+    fn update_session_key_peers(&mut self) {
+        let root_key = self.neighborhood_database.root().public_key().clone();
+        let keys: HashSet<PublicKey> = self
+            .neighborhood_database
+            .keys()
+            .into_iter()
+            .filter(|key| {
+                (**key != root_key)
+                    && self
+                        .neighborhood_database
+                        .node_by_key(key)
+                        .map(|node| node.accepts_session_keys())
+                        .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        if keys == self.session_key_peers {
+            return;
+        }
+        self.session_key_peers = keys.clone();
+        self.session_key_peers_sub
+            .as_ref()
+            .expect("Hopper is unbound")
+            .try_send(SessionKeyPeersMsg { keys })
+            .expect("Hopper is dead");
     }
 
     fn neighbor_keys(&self) -> Vec<PublicKey> {
@@ -606,7 +908,10 @@ impl Neighborhood {
             self.gossip_acceptor
                 .handle(&mut self.neighborhood_database, agrs, gossip_source);
         match acceptance_result {
-            GossipAcceptanceResult::Accepted => self.gossip_to_neighbors(),
+            GossipAcceptanceResult::Accepted => {
+                self.metrics.gossip_accepted.increment();
+                self.gossip_to_neighbors()
+            }
             GossipAcceptanceResult::Reply(next_debut, target_key, target_node_addr) => {
                 self.handle_gossip_reply(next_debut, &target_key, &target_node_addr)
             }
@@ -614,12 +919,23 @@ impl Neighborhood {
                 self.handle_gossip_failed(failure, &target_key, &target_node_addr)
             }
             GossipAcceptanceResult::Ignored => {
-                trace!(self.logger, "Gossip from {} ignored", gossip_source);
+                self.metrics.gossip_ignored.increment();
+                trace!(
+                    self.logger.with_peer_addr(gossip_source),
+                    "Gossip from {} ignored",
+                    gossip_source
+                );
                 self.handle_gossip_ignored(ignored_node_name, gossip_record_count)
             }
             GossipAcceptanceResult::Ban(reason) => {
-                warning!(self.logger, "Malefactor detected at {}, but malefactor bans not yet implemented; ignoring: {}", gossip_source, reason
-            );
+                self.metrics.gossip_ignored.increment();
+                warning!(
+                    self.logger.with_peer_addr(gossip_source),
+                    "Malefactor detected at {}; counting toward a temporary ban: {}",
+                    gossip_source,
+                    reason
+                );
+                self.record_gossip_offense(gossip_source);
                 self.handle_gossip_ignored(ignored_node_name, gossip_record_count);
             }
         }
@@ -719,6 +1035,27 @@ impl Neighborhood {
         });
     }
 
+    fn check_reduced_capacity(&mut self) {
+        let reduced_capacity = self.bandwidth_throttle.is_throttled(Instant::now());
+        let root = self.neighborhood_database.root_mut();
+        if !root.set_reduced_capacity(reduced_capacity) {
+            return;
+        }
+        root.increment_version();
+        if reduced_capacity {
+            info!(
+                self.logger,
+                "Bandwidth limits are holding back traffic; advertising reduced capacity"
+            );
+        } else {
+            info!(
+                self.logger,
+                "Bandwidth limits are no longer holding back traffic; advertising full capacity"
+            );
+        }
+        self.gossip_to_neighbors();
+    }
+
     fn gossip_to_neighbor(&self, neighbor: &PublicKey, gossip: Gossip_0v1) {
         let gossip_len = gossip.node_records.len();
         let route = self.create_single_hop_route(neighbor);
@@ -888,24 +1225,25 @@ impl Neighborhood {
         if node_seqs.is_empty() {
             panic!("Unable to sort routes by desirable exit nodes: Missing routes.");
         }
-        let get_the_exit_nodes_desirable_flag = |vec: &Vec<&PublicKey>| -> Option<bool> {
+        // Desirable exit Nodes come first; among those, ones that aren't throttled by bandwidth
+        // limits come before ones that are.
+        let get_the_exit_nodes_desirable_flags = |vec: &Vec<&PublicKey>| -> Option<(bool, bool)> {
             vec.last()
                 .map(|pk|
                     self.neighborhood_database
                         .node_by_key(pk)
                         .unwrap_or_else(|| panic!("Unable to sort routes by desirable exit nodes: Missing NodeRecord for public key: [{}]", pk))
-                ).map(|node| node.is_desirable())
+                ).map(|node| (node.is_desirable(), !node.reduced_capacity()))
         };
 
         node_seqs.sort_by(|vec1: &Vec<&PublicKey>, vec2: &Vec<&PublicKey>| {
             if vec1.is_empty() || vec2.is_empty() {
                 panic!("Unable to sort routes by desirable exit nodes: Missing route segments.")
             }
-            let is_desirable1 = get_the_exit_nodes_desirable_flag(vec1);
-            let is_desirable2 = get_the_exit_nodes_desirable_flag(vec2);
+            let is_desirable1 = get_the_exit_nodes_desirable_flags(vec1);
+            let is_desirable2 = get_the_exit_nodes_desirable_flags(vec2);
             match (is_desirable1, is_desirable2) {
-                (Some(true), Some(false)) => Ordering::Less,
-                (Some(false), Some(true)) => Ordering::Greater,
+                (Some(flags1), Some(flags2)) => flags2.cmp(&flags1),
                 _ => Ordering::Equal,
             }
         });
@@ -1148,7 +1486,7 @@ impl Neighborhood {
     ) -> String {
         match accessible_gossip.iter().find(|agr| {
             if let Some(ref node_addr) = agr.node_addr_opt {
-                node_addr.ip_addr() == gossip_source.ip()
+                node_addr.has_ip_addr(gossip_source.ip())
             } else {
                 false
             }
@@ -1196,6 +1534,18 @@ impl Neighborhood {
             self.logger,
             "Received shutdown order from client {}: shutting down hard", client_id
         );
+        // Tell the Daemon this exit was ordered, so that it won't restart us
+        let node_shutdown = NodeShutdown {
+            process_id: std::process::id(),
+        };
+        if let Err(e) = node_shutdown.write_to(&self.data_directory) {
+            warning!(
+                self.logger,
+                "Could not record shutdown order in {:?}: {}",
+                self.data_directory,
+                e
+            );
+        }
         exit_process(
             0,
             &format!(
@@ -1208,6 +1558,28 @@ impl Neighborhood {
     fn handle_new_password(&mut self, new_password: String) {
         self.db_password_opt = Some(new_password);
     }
+
+    fn handle_gossip_stats_request(&self, client_id: u64, context_id: u64) {
+        let counters = self.gossip_throttle.counters();
+        let body = UiGossipStatsResponse {
+            accepted: counters.accepted,
+            dropped_banned: counters.banned,
+            dropped_too_many_records: counters.too_many_records,
+            dropped_record_too_large: counters.record_too_large,
+            dropped_ip_rate_exceeded: counters.ip_rate_exceeded,
+            dropped_key_rate_exceeded: counters.key_rate_exceeded,
+            currently_banned: self.gossip_throttle.banned_count(Instant::now()) as u64,
+        }
+        .tmb(context_id);
+        self.to_ui_message_sub
+            .as_ref()
+            .expect("UiGateway is unbound")
+            .try_send(NodeToUiMessage {
+                target: ClientId(client_id),
+                body,
+            })
+            .expect("UiGateway is dead");
+    }
 }
 
 pub fn regenerate_signed_gossip(
@@ -1227,11 +1599,16 @@ pub fn regenerate_signed_gossip(
 mod tests {
     use super::*;
     use crate::blockchain::blockchain_interface::{chain_id_from_name, contract_address};
+    use crate::daemon::crash_report::SHUTDOWN_FILE_NAME;
     use crate::db_config::persistent_configuration::PersistentConfigError;
     use crate::neighborhood::gossip::GossipBuilder;
     use crate::neighborhood::gossip::Gossip_0v1;
+    use crate::neighborhood::gossip_throttle::{GossipThrottleConfig, OFFENSES_BEFORE_BAN};
     use crate::neighborhood::node_record::NodeRecordInner_0v1;
     use crate::stream_messages::{NonClandestineAttributes, RemovedStreamType};
+    use crate::sub_lib::bandwidth::{
+        BandwidthLimits, BandwidthService, ServiceBandwidthLimits, Traffic,
+    };
     use crate::sub_lib::cryptde::{decodex, encodex, CryptData};
     use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::dispatcher::Endpoint;
@@ -1265,7 +1642,8 @@ mod tests {
     use itertools::Itertools;
     use masq_lib::constants::TLS_PORT;
     use masq_lib::test_utils::utils::{
-        ensure_node_home_directory_exists, DEFAULT_CHAIN_ID, TEST_DEFAULT_CHAIN_NAME,
+        ensure_node_home_directory_exists, node_home_directory, DEFAULT_CHAIN_ID,
+        TEST_DEFAULT_CHAIN_NAME,
     };
     use masq_lib::ui_gateway::MessageBody;
     use masq_lib::ui_gateway::MessagePath::Conversation;
@@ -1645,7 +2023,8 @@ mod tests {
         let system =
             System::new("node_query_responds_with_result_when_key_query_matches_configured_data");
         let one_neighbor = make_node_record(2345, true);
-        let another_neighbor = make_node_record(3456, true);
+        let mut another_neighbor = make_node_record(3456, true);
+        another_neighbor.set_accepts_masquerades(true);
         let mut subject = Neighborhood::new(
             cryptde,
             &bc_from_nc_plus(
@@ -1677,11 +2056,14 @@ mod tests {
         let result = future.wait().unwrap();
         assert_eq!(
             result.unwrap(),
-            NodeQueryResponseMetadata::new(
-                another_neighbor.public_key().clone(),
-                Some(another_neighbor.node_addr_opt().unwrap().clone()),
-                another_neighbor.rate_pack().clone(),
-            )
+            NodeQueryResponseMetadata {
+                accepts_masquerades: true,
+                ..NodeQueryResponseMetadata::new(
+                    another_neighbor.public_key().clone(),
+                    Some(another_neighbor.node_addr_opt().unwrap().clone()),
+                    another_neighbor.rate_pack().clone(),
+                )
+            }
         );
     }
 
@@ -1777,6 +2159,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn status_query_reports_descriptor_neighbor_count_and_route_availability() {
+        let cryptde = main_cryptde();
+        let system =
+            System::new("status_query_reports_descriptor_neighbor_count_and_route_availability");
+        let mut subject = make_standard_subject();
+        let p = &subject.neighborhood_database.root().clone();
+        let q = &make_node_record(3456, true);
+        let r = &make_node_record(4567, false);
+        let s = &make_node_record(5678, false);
+        {
+            let db = &mut subject.neighborhood_database;
+            db.add_node(q.clone()).unwrap();
+            db.add_node(r.clone()).unwrap();
+            db.add_node(s.clone()).unwrap();
+            let mut dual_edge = |a: &NodeRecord, b: &NodeRecord| {
+                db.add_arbitrary_full_neighbor(a.public_key(), b.public_key());
+            };
+            dual_edge(p, q);
+            dual_edge(q, r);
+            dual_edge(r, s);
+        }
+        let expected_descriptor = p
+            .node_descriptor(subject.chain_id, cryptde)
+            .to_string(cryptde);
+        let addr: Addr<Neighborhood> = subject.start();
+        let sub: Recipient<NeighborhoodStatusQueryMessage> =
+            addr.recipient::<NeighborhoodStatusQueryMessage>();
+
+        let future = sub.send(NeighborhoodStatusQueryMessage {});
+
+        System::current().stop_with_code(0);
+        system.run();
+        let result = future.wait().unwrap();
+        assert_eq!(
+            result,
+            NeighborhoodStatus {
+                node_descriptor: expected_descriptor,
+                neighbor_count: 1,
+                round_trip_route_available: true,
+            }
+        );
+    }
+
+    #[test]
+    fn status_query_reports_no_route_available_for_node_without_neighbors() {
+        let subject = make_standard_subject();
+
+        let result = subject.handle_status_query();
+
+        assert_eq!(result.neighbor_count, 0);
+        assert_eq!(result.round_trip_route_available, false);
+    }
+
     #[test]
     fn route_query_responds_with_none_when_asked_for_route_with_too_many_hops() {
         let system =
@@ -1942,6 +2378,23 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn route_query_results_are_counted_in_metrics() {
+        let mut subject = make_standard_subject();
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+
+        let success =
+            subject.handle_route_query_message(RouteQueryMessage::data_indefinite_route_request(0));
+        let failure =
+            subject.handle_route_query_message(RouteQueryMessage::data_indefinite_route_request(5));
+
+        assert_eq!(success.is_some(), true);
+        assert_eq!(failure, None);
+        assert_eq!(metrics.route_query_successes.get(), 1);
+        assert_eq!(metrics.route_query_failures.get(), 1);
+    }
+
     #[test]
     fn route_query_responds_with_standard_zero_hop_route_when_requested() {
         let cryptde = main_cryptde();
@@ -2136,6 +2589,139 @@ mod tests {
         assert_eq!(undesirable_node.public_key(), node_sequences[1][2]);
     }
 
+    #[test]
+    fn sort_routes_by_desirable_exit_nodes_puts_exit_nodes_with_reduced_capacity_after_the_others()
+    {
+        let mut subject = make_standard_subject();
+        let us = subject.neighborhood_database.root().clone();
+        let routing_node = make_node_record(0000, true);
+        let full_capacity_node = make_node_record(1111, false);
+        let mut reduced_capacity_node = make_node_record(2222, false);
+        reduced_capacity_node.set_reduced_capacity(true);
+        let mut undesirable_node = make_node_record(3333, false);
+        undesirable_node.set_desirable(false);
+        {
+            let db = &mut subject.neighborhood_database;
+            db.add_node(routing_node.clone()).unwrap();
+            db.add_node(full_capacity_node.clone()).unwrap();
+            db.add_node(reduced_capacity_node.clone()).unwrap();
+            db.add_node(undesirable_node.clone()).unwrap();
+        }
+        let mut node_sequences = vec![
+            vec![
+                us.public_key(),
+                routing_node.public_key(),
+                undesirable_node.public_key(),
+            ],
+            vec![
+                us.public_key(),
+                routing_node.public_key(),
+                reduced_capacity_node.public_key(),
+            ],
+            vec![
+                us.public_key(),
+                routing_node.public_key(),
+                full_capacity_node.public_key(),
+            ],
+        ];
+
+        subject.sort_routes_by_desirable_exit_nodes(&mut node_sequences);
+
+        assert_eq!(full_capacity_node.public_key(), node_sequences[0][2]);
+        assert_eq!(reduced_capacity_node.public_key(), node_sequences[1][2]);
+        assert_eq!(undesirable_node.public_key(), node_sequences[2][2]);
+    }
+
+    fn make_subject_with_a_neighbor() -> Neighborhood {
+        let mut subject = make_standard_subject();
+        let neighbor = make_node_record(4321, true);
+        let root_key = subject.neighborhood_database.root().public_key().clone();
+        subject
+            .neighborhood_database
+            .add_node(neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_arbitrary_full_neighbor(&root_key, neighbor.public_key());
+        subject
+    }
+
+    #[test]
+    fn check_reduced_capacity_advertises_throttling_to_neighbors() {
+        init_test_logging();
+        let mut subject = make_subject_with_a_neighbor();
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str("1:0:0:0").unwrap(),
+            exit: ServiceBandwidthLimits::default(),
+        });
+        throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Download(IpAddr::from_str("1.2.3.4").unwrap()),
+            2048,
+            Instant::now(),
+        );
+        subject.bandwidth_throttle = throttle;
+        let initial_version = subject.neighborhood_database.root().version();
+        let produce_params_arc = Arc::new(Mutex::new(vec![]));
+        let gossip_producer = GossipProducerMock::new()
+            .produce_params(&produce_params_arc)
+            .produce_result(None);
+        subject.gossip_producer = Box::new(gossip_producer);
+
+        subject.check_reduced_capacity();
+
+        let root = subject.neighborhood_database.root();
+        assert_eq!(root.reduced_capacity(), true);
+        assert_eq!(root.version(), initial_version + 1);
+        let produce_params = produce_params_arc.lock().unwrap();
+        assert_eq!(produce_params.len(), 1);
+        assert_eq!(produce_params[0].0.root().reduced_capacity(), true);
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: Bandwidth limits are holding back traffic; advertising reduced capacity",
+        );
+    }
+
+    #[test]
+    fn check_reduced_capacity_does_nothing_while_capacity_is_unchanged() {
+        let mut subject = make_subject_with_a_neighbor();
+        subject.bandwidth_throttle = Arc::new(BandwidthThrottle::new());
+        let initial_version = subject.neighborhood_database.root().version();
+        let produce_params_arc = Arc::new(Mutex::new(vec![]));
+        let gossip_producer = GossipProducerMock::new().produce_params(&produce_params_arc);
+        subject.gossip_producer = Box::new(gossip_producer);
+
+        subject.check_reduced_capacity();
+
+        let root = subject.neighborhood_database.root();
+        assert_eq!(root.reduced_capacity(), false);
+        assert_eq!(root.version(), initial_version);
+        assert_eq!(produce_params_arc.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn check_reduced_capacity_advertises_full_capacity_again_once_throttling_stops() {
+        let mut subject = make_subject_with_a_neighbor();
+        subject.bandwidth_throttle = Arc::new(BandwidthThrottle::new());
+        subject
+            .neighborhood_database
+            .root_mut()
+            .set_reduced_capacity(true);
+        let initial_version = subject.neighborhood_database.root().version();
+        let produce_params_arc = Arc::new(Mutex::new(vec![]));
+        let gossip_producer = GossipProducerMock::new()
+            .produce_params(&produce_params_arc)
+            .produce_result(None);
+        subject.gossip_producer = Box::new(gossip_producer);
+
+        subject.check_reduced_capacity();
+
+        let root = subject.neighborhood_database.root();
+        assert_eq!(root.reduced_capacity(), false);
+        assert_eq!(root.version(), initial_version + 1);
+        assert_eq!(produce_params_arc.lock().unwrap().len(), 1);
+    }
+
     #[test]
     #[should_panic(expected = "Unable to sort routes by desirable exit nodes: Missing routes.")]
     fn sort_routes_by_desirable_exit_nodes_panics_with_empty_node_sequences() {
@@ -2585,7 +3171,10 @@ mod tests {
         let gossip_acceptor = GossipAcceptorMock::new()
             .handle_params(&handle_params_arc)
             .handle_result(GossipAcceptanceResult::Ignored);
-        let subject_node = make_global_cryptde_node_record(1234, true); // 9e7p7un06eHs6frl5A
+        let mut subject_node = make_global_cryptde_node_record(1234, true); // 9e7p7un06eHs6frl5A
+        subject_node.set_accepts_session_keys(true);
+        subject_node.set_accepts_masquerades(true);
+        subject_node.regenerate_signed_gossip(main_cryptde());
         let neighbor = make_node_record(1111, true);
         let mut subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
         subject.gossip_acceptor = Box::new(gossip_acceptor);
@@ -2764,6 +3353,7 @@ mod tests {
         subject.hopper = Some(peer_actors.hopper.from_hopper_client);
         subject.hopper_no_lookup = Some(peer_actors.hopper.from_hopper_client_no_lookup);
         subject.connected_signal = Some(peer_actors.accountant.start);
+        subject.session_key_peers_sub = Some(peer_actors.hopper.session_key_peers);
     }
 
     #[test]
@@ -2989,6 +3579,146 @@ mod tests {
         assert!(set_past_neighbors_params.is_empty());
     }
 
+    #[test]
+    fn neighborhood_persists_snapshot_of_known_nodes() {
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let neighbor = make_node_record(1111, true);
+        let distant = make_node_record(2222, false);
+        let mut subject: Neighborhood = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+        subject
+            .neighborhood_database
+            .add_node(neighbor.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_node(distant.clone())
+            .unwrap();
+        let set_neighborhood_snapshot_params_arc = Arc::new(Mutex::new(vec![]));
+        let persistent_config = PersistentConfigurationMock::new()
+            .set_neighborhood_snapshot_params(&set_neighborhood_snapshot_params_arc)
+            .set_neighborhood_snapshot_result(Ok(()));
+        subject.persistent_config_opt = Some(Box::new(persistent_config));
+
+        subject.persist_neighborhood_snapshot();
+
+        let mut set_neighborhood_snapshot_params =
+            set_neighborhood_snapshot_params_arc.lock().unwrap();
+        let snapshot = set_neighborhood_snapshot_params.remove(0).unwrap();
+        let keys = snapshot
+            .records
+            .into_iter()
+            .map(|record| {
+                NodeRecord::try_from(&record.gossip)
+                    .unwrap()
+                    .public_key()
+                    .clone()
+            })
+            .collect_vec();
+        assert_eq!(
+            keys,
+            vec![neighbor.public_key().clone(), distant.public_key().clone()]
+        );
+        assert!(set_neighborhood_snapshot_params.is_empty());
+    }
+
+    #[test]
+    fn neighborhood_does_not_overwrite_snapshot_when_it_knows_only_itself() {
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let mut subject: Neighborhood = neighborhood_from_nodes(&subject_node, None);
+        let set_neighborhood_snapshot_params_arc = Arc::new(Mutex::new(vec![]));
+        let persistent_config = PersistentConfigurationMock::new()
+            .set_neighborhood_snapshot_params(&set_neighborhood_snapshot_params_arc);
+        subject.persistent_config_opt = Some(Box::new(persistent_config));
+
+        subject.persist_neighborhood_snapshot();
+
+        let set_neighborhood_snapshot_params = set_neighborhood_snapshot_params_arc.lock().unwrap();
+        assert!(set_neighborhood_snapshot_params.is_empty());
+    }
+
+    #[test]
+    fn neighborhood_restores_snapshot_at_startup() {
+        init_test_logging();
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let known = make_node_record(1111, true);
+        let mut stale = make_node_record(2222, true);
+        stale.metadata.last_update = 1;
+        let mut snapshot_source = db_from_node(&subject_node);
+        snapshot_source.add_node(known.clone()).unwrap();
+        snapshot_source.add_node(stale.clone()).unwrap();
+        let snapshot = NeighborhoodSnapshot::from_database(&snapshot_source, time_t_timestamp());
+        let mut subject: Neighborhood = neighborhood_from_nodes(&subject_node, None);
+        let persistent_config =
+            PersistentConfigurationMock::new().neighborhood_snapshot_result(Ok(Some(snapshot)));
+        subject.persistent_config_opt = Some(Box::new(persistent_config));
+
+        subject.restore_neighborhood_snapshot();
+
+        assert_eq!(
+            subject
+                .neighborhood_database
+                .node_by_key(known.public_key())
+                .unwrap()
+                .inner,
+            known.inner
+        );
+        assert_eq!(
+            subject
+                .neighborhood_database
+                .node_by_key(stale.public_key()),
+            None
+        );
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Neighborhood: Restored 1 of 2 Nodes from neighborhood snapshot",
+        );
+    }
+
+    #[test]
+    fn neighborhood_can_route_right_after_restoring_snapshot() {
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let q = make_node_record(3456, true);
+        let r = make_node_record(4567, false);
+        let s = make_node_record(5678, false);
+        let mut snapshot_source = db_from_node(&subject_node);
+        snapshot_source.add_node(q.clone()).unwrap();
+        snapshot_source.add_node(r.clone()).unwrap();
+        snapshot_source.add_node(s.clone()).unwrap();
+        snapshot_source.add_arbitrary_full_neighbor(subject_node.public_key(), q.public_key());
+        snapshot_source.add_arbitrary_full_neighbor(q.public_key(), r.public_key());
+        snapshot_source.add_arbitrary_full_neighbor(r.public_key(), s.public_key());
+        snapshot_source.resign_node(q.public_key());
+        snapshot_source.resign_node(r.public_key());
+        snapshot_source.resign_node(s.public_key());
+        let snapshot = NeighborhoodSnapshot::from_database(&snapshot_source, time_t_timestamp());
+        let mut subject: Neighborhood = neighborhood_from_nodes(&subject_node, None);
+        let persistent_config =
+            PersistentConfigurationMock::new().neighborhood_snapshot_result(Ok(Some(snapshot)));
+        subject.persistent_config_opt = Some(Box::new(persistent_config));
+        assert_eq!(subject.round_trip_route_available(), false);
+
+        subject.restore_neighborhood_snapshot();
+
+        assert_eq!(subject.round_trip_route_available(), true);
+        assert_eq!(subject.handle_status_query().neighbor_count, 1);
+    }
+
+    #[test]
+    fn neighborhood_logs_error_when_snapshot_cannot_be_retrieved() {
+        init_test_logging();
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let mut subject: Neighborhood = neighborhood_from_nodes(&subject_node, None);
+        let persistent_config = PersistentConfigurationMock::new()
+            .neighborhood_snapshot_result(Err(PersistentConfigError::NotPresent));
+        subject.persistent_config_opt = Some(Box::new(persistent_config));
+
+        subject.restore_neighborhood_snapshot();
+
+        assert_eq!(subject.neighborhood_database.keys().len(), 1);
+        TestLogHandler::new().exists_log_containing(
+            "ERROR: Neighborhood: Could not retrieve neighborhood snapshot: NotPresent",
+        );
+    }
+
     #[test]
     fn neighborhood_does_not_updates_past_neighbors_without_password_even_when_neighbor_list_changes(
     ) {
@@ -3263,7 +3993,7 @@ mod tests {
     }
 
     #[test]
-    fn neighborhood_complains_about_inability_to_ban_when_gossip_acceptor_requests_it() {
+    fn neighborhood_counts_offense_toward_ban_when_gossip_acceptor_requests_it() {
         init_test_logging();
         let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
         let neighbor = make_node_record(1111, true);
@@ -3271,6 +4001,10 @@ mod tests {
         let gossip_acceptor = GossipAcceptorMock::new()
             .handle_result(GossipAcceptanceResult::Ban("Bad guy".to_string()));
         subject.gossip_acceptor = Box::new(gossip_acceptor);
+        subject.gossip_throttle = GossipThrottle::new(GossipThrottleConfig {
+            offenses_before_ban: 1,
+            ..GossipThrottleConfig::default()
+        });
         let subject_node = subject.neighborhood_database.root().clone();
         let (hopper, _, hopper_recording_arc) = make_recorder();
         let peer_actors = peer_actors_builder().hopper(hopper).build();
@@ -3286,8 +4020,323 @@ mod tests {
         system.run();
         let hopper_recording = hopper_recording_arc.lock().unwrap();
         assert_eq!(0, hopper_recording.len());
+        assert_eq!(
+            subject
+                .gossip_throttle
+                .is_banned(IpAddr::from_str("5.5.5.5").unwrap(), Instant::now()),
+            true
+        );
         let tlh = TestLogHandler::new();
-        tlh.exists_log_containing("WARN: Neighborhood: Malefactor detected at 5.5.5.5:5555, but malefactor bans not yet implemented; ignoring: Bad guy");
+        tlh.exists_log_containing("WARN: Neighborhood: Malefactor detected at 5.5.5.5:5555; counting toward a temporary ban: Bad guy");
+        tlh.exists_log_containing(
+            "WARN: Neighborhood: Banning Gossip from 5.5.5.5 temporarily after repeated offenses",
+        );
+    }
+
+    #[test]
+    fn neighborhood_advertises_that_it_accepts_session_keys() {
+        let subject_node = make_global_cryptde_node_record(1234, true); // 9e7p7un06eHs6frl5A
+        let neighbor = make_node_record(1111, true);
+
+        let subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+
+        let root = subject.neighborhood_database.root();
+        assert_eq!(root.accepts_session_keys(), true);
+        let agr = AccessibleGossipRecord::from(root);
+        assert_eq!(agr.inner.accepts_session_keys, true);
+    }
+
+    #[test]
+    fn neighborhood_advertises_that_it_accepts_masquerades() {
+        let subject_node = make_global_cryptde_node_record(1234, true); // 9e7p7un06eHs6frl5A
+        let neighbor = make_node_record(1111, true);
+
+        let subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+
+        let root = subject.neighborhood_database.root();
+        assert_eq!(root.accepts_masquerades(), true);
+        let agr = AccessibleGossipRecord::from(root);
+        assert_eq!(agr.inner.accepts_masquerades, true);
+    }
+
+    #[test]
+    fn gossip_tells_the_hopper_which_nodes_accept_session_keys() {
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let neighbor = make_node_record(1111, true);
+        let mut subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+        let mut new_node = make_node_record(2222, true);
+        new_node.set_accepts_session_keys(true);
+        let old_node = make_node_record(3333, true);
+        subject
+            .neighborhood_database
+            .add_node(new_node.clone())
+            .unwrap();
+        subject
+            .neighborhood_database
+            .add_node(old_node.clone())
+            .unwrap();
+        subject.gossip_acceptor = Box::new(
+            GossipAcceptorMock::new()
+                .handle_result(GossipAcceptanceResult::Ignored)
+                .handle_result(GossipAcceptanceResult::Ignored),
+        );
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        bind_subject(&mut subject, peer_actors);
+        let gossip_source = SocketAddr::from_str("1.1.1.1:1111").unwrap();
+        let system = System::new("gossip_tells_the_hopper_which_nodes_accept_session_keys");
+
+        subject.handle_gossip_agrs(vec![], gossip_source);
+        subject.handle_gossip_agrs(vec![], gossip_source);
+
+        System::current().stop();
+        system.run();
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(
+            hopper_recording.get_record::<SessionKeyPeersMsg>(0),
+            &SessionKeyPeersMsg {
+                keys: vec![new_node.public_key().clone()].into_iter().collect()
+            }
+        );
+        assert_eq!(hopper_recording.len(), 1);
+    }
+
+    #[test]
+    fn gossip_acceptance_results_are_counted_in_metrics() {
+        let subject_node = make_global_cryptde_node_record(5555, true); // 9e7p7un06eHs6frl5A
+        let neighbor = make_node_record(1111, true);
+        let mut subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+        let gossip_acceptor = GossipAcceptorMock::new()
+            .handle_result(GossipAcceptanceResult::Accepted)
+            .handle_result(GossipAcceptanceResult::Ignored)
+            .handle_result(GossipAcceptanceResult::Ban("Bad guy".to_string()));
+        subject.gossip_acceptor = Box::new(gossip_acceptor);
+        let gossip_producer = GossipProducerMock::new()
+            .produce_result(Some(Gossip_0v1::new(vec![])))
+            .produce_result(Some(Gossip_0v1::new(vec![])));
+        subject.gossip_producer = Box::new(gossip_producer);
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+        let (hopper, _, _) = make_recorder();
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let system = System::new("");
+        subject.hopper = Some(peer_actors.hopper.from_hopper_client);
+        let gossip_source = SocketAddr::from_str("1.1.1.1:1111").unwrap();
+
+        subject.handle_gossip(Gossip_0v1::new(vec![]), gossip_source);
+        subject.handle_gossip(Gossip_0v1::new(vec![]), gossip_source);
+        subject.handle_gossip(Gossip_0v1::new(vec![]), gossip_source);
+
+        System::current().stop();
+        system.run();
+        assert_eq!(metrics.gossip_received.get(), 3);
+        assert_eq!(metrics.gossip_accepted.get(), 1);
+        assert_eq!(metrics.gossip_ignored.get(), 2);
+    }
+
+    #[test]
+    fn neighborhood_drops_throttled_gossip_without_consulting_gossip_acceptor() {
+        init_test_logging();
+        let handle_params_arc = Arc::new(Mutex::new(vec![]));
+        let gossip_acceptor = GossipAcceptorMock::new().handle_params(&handle_params_arc);
+        let subject_node = make_global_cryptde_node_record(1234, true); // 9e7p7un06eHs6frl5A
+        let neighbor = make_node_record(1111, true);
+        let mut subject = neighborhood_from_nodes(&subject_node, Some(&neighbor));
+        subject.gossip_acceptor = Box::new(gossip_acceptor);
+        subject.gossip_throttle = GossipThrottle::new(GossipThrottleConfig {
+            max_records: 0,
+            ..GossipThrottleConfig::default()
+        });
+        let gossip = GossipBuilder::new(&subject.neighborhood_database)
+            .node(subject_node.public_key(), true)
+            .build();
+        let cores_package = ExpiredCoresPackage {
+            immediate_neighbor: SocketAddr::from_str("2.3.4.5:2345").unwrap(),
+            paying_wallet: None,
+            remaining_route: make_meaningless_route(),
+            payload: gossip,
+            payload_len: 0,
+        };
+        let system = System::new("neighborhood_drops_throttled_gossip");
+        let addr: Addr<Neighborhood> = subject.start();
+        let peer_actors = peer_actors_builder().build();
+        addr.try_send(BindMessage { peer_actors }).unwrap();
+        let sub = addr.recipient::<ExpiredCoresPackage<Gossip_0v1>>();
+
+        sub.try_send(cores_package).unwrap();
+
+        System::current().stop();
+        system.run();
+        let handle_params = handle_params_arc.lock().unwrap();
+        assert!(handle_params.is_empty());
+        TestLogHandler::new().exists_log_containing(
+            "WARN: Neighborhood: Dropping Gossip about 1 Nodes from 2.3.4.5:2345: Gossip contains 1 records",
+        );
+    }
+
+    #[test]
+    fn neighborhood_records_offense_for_badly_signed_gossip() {
+        let mut subject = make_standard_subject();
+        subject.gossip_throttle = GossipThrottle::new(GossipThrottleConfig {
+            offenses_before_ban: 1,
+            ..GossipThrottleConfig::default()
+        });
+        let db = &mut subject.neighborhood_database;
+        let node_key = &db.add_node(make_node_record(2222, true)).unwrap();
+        let mut gossip = GossipBuilder::new(db).node(node_key, true).build();
+        gossip.node_records[0].signature = CryptData::new(&[1, 2, 3, 4]);
+
+        subject.handle_gossip(gossip, SocketAddr::from_str("2.2.2.2:2222").unwrap());
+
+        assert_eq!(
+            subject
+                .gossip_throttle
+                .is_banned(IpAddr::from_str("2.2.2.2").unwrap(), Instant::now()),
+            true
+        );
+    }
+
+    #[test]
+    fn neighborhood_drops_gossip_over_the_signing_key_limit_without_recording_an_offense() {
+        init_test_logging();
+        let handle_params_arc = Arc::new(Mutex::new(vec![]));
+        let gossip_acceptor = GossipAcceptorMock::new()
+            .handle_params(&handle_params_arc)
+            .handle_result(GossipAcceptanceResult::Ignored);
+        let mut subject = make_standard_subject();
+        subject.gossip_acceptor = Box::new(gossip_acceptor);
+        subject.gossip_throttle = GossipThrottle::new(GossipThrottleConfig {
+            max_per_window_per_key: 1,
+            offenses_before_ban: 1,
+            ..GossipThrottleConfig::default()
+        });
+        let stranger = make_node_record(2222, true);
+        let mut stranger_db = db_from_node(&make_node_record(3333, true));
+        let stranger_key = &stranger_db.add_node(stranger).unwrap();
+        let gossip = GossipBuilder::new(&stranger_db)
+            .node(stranger_key, true)
+            .build();
+        let gossip_source = SocketAddr::from_str("2.2.2.2:2222").unwrap();
+
+        subject.handle_gossip(gossip.clone(), gossip_source);
+        subject.handle_gossip(gossip, gossip_source);
+
+        assert_eq!(handle_params_arc.lock().unwrap().len(), 1);
+        assert_eq!(
+            subject
+                .gossip_throttle
+                .is_banned(gossip_source.ip(), Instant::now()),
+            false
+        );
+        assert_eq!(subject.gossip_throttle.counters().key_rate_exceeded, 1);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: Neighborhood: Dropping Gossip about 1 Nodes from 2.2.2.2:2222: source public key is sending Gossip too fast",
+        );
+    }
+
+    #[test]
+    fn neighborhood_refuses_gossip_from_a_signing_key_over_its_limit_before_verifying_it() {
+        let mut subject = make_standard_subject();
+        subject.gossip_acceptor =
+            Box::new(GossipAcceptorMock::new().handle_result(GossipAcceptanceResult::Ignored));
+        subject.gossip_throttle = GossipThrottle::new(GossipThrottleConfig {
+            max_per_window_per_key: 1,
+            offenses_before_ban: 1,
+            ..GossipThrottleConfig::default()
+        });
+        let stranger = make_node_record(2222, true);
+        let mut stranger_db = db_from_node(&make_node_record(3333, true));
+        let stranger_key = &stranger_db.add_node(stranger).unwrap();
+        let gossip = GossipBuilder::new(&stranger_db)
+            .node(stranger_key, true)
+            .build();
+        let mut forged_gossip = gossip.clone();
+        forged_gossip.node_records[0].signature = CryptData::new(&[1, 2, 3, 4]);
+        let gossip_source = SocketAddr::from_str("2.2.2.2:2222").unwrap();
+        subject.handle_gossip(gossip, gossip_source);
+
+        subject.handle_gossip(forged_gossip, gossip_source);
+
+        assert_eq!(
+            subject
+                .gossip_throttle
+                .is_banned(gossip_source.ip(), Instant::now()),
+            false
+        );
+        assert_eq!(subject.gossip_throttle.counters().key_rate_exceeded, 1);
+    }
+
+    #[test]
+    fn neighborhood_does_not_charge_a_signing_key_for_forged_gossip() {
+        let handle_params_arc = Arc::new(Mutex::new(vec![]));
+        let gossip_acceptor = GossipAcceptorMock::new()
+            .handle_params(&handle_params_arc)
+            .handle_result(GossipAcceptanceResult::Ignored);
+        let mut subject = make_standard_subject();
+        subject.gossip_acceptor = Box::new(gossip_acceptor);
+        subject.gossip_throttle = GossipThrottle::new(GossipThrottleConfig {
+            max_per_window_per_key: 1,
+            ..GossipThrottleConfig::default()
+        });
+        let stranger = make_node_record(2222, true);
+        let mut stranger_db = db_from_node(&make_node_record(3333, true));
+        let stranger_key = &stranger_db.add_node(stranger).unwrap();
+        let gossip = GossipBuilder::new(&stranger_db)
+            .node(stranger_key, true)
+            .build();
+        let mut forged_gossip = gossip.clone();
+        forged_gossip.node_records[0].signature = CryptData::new(&[1, 2, 3, 4]);
+        let gossip_source = SocketAddr::from_str("2.2.2.2:2222").unwrap();
+        subject.handle_gossip(forged_gossip, gossip_source);
+
+        subject.handle_gossip(gossip, gossip_source);
+
+        assert_eq!(handle_params_arc.lock().unwrap().len(), 1);
+        assert_eq!(subject.gossip_throttle.counters().key_rate_exceeded, 0);
+    }
+
+    #[test]
+    fn neighborhood_responds_to_gossip_stats_request() {
+        let system = System::new("neighborhood_responds_to_gossip_stats_request");
+        let mut subject = make_standard_subject();
+        let now = Instant::now();
+        let ip_addr = IpAddr::from_str("3.4.5.6").unwrap();
+        let _ = subject
+            .gossip_throttle
+            .check(&Gossip_0v1::new(vec![]), ip_addr, None, now);
+        for _ in 0..OFFENSES_BEFORE_BAN {
+            subject.gossip_throttle.record_offense(ip_addr, now);
+        }
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let addr: Addr<Neighborhood> = subject.start();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        addr.try_send(NodeFromUiMessage {
+            client_id: 1234,
+            body: UiGossipStatsRequest {}.tmb(4321),
+        })
+        .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let response = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(response.target, ClientId(1234));
+        let (body, context_id) = UiGossipStatsResponse::fmb(response.body.clone()).unwrap();
+        assert_eq!(context_id, 4321);
+        assert_eq!(
+            body,
+            UiGossipStatsResponse {
+                accepted: 1,
+                dropped_banned: 0,
+                dropped_too_many_records: 0,
+                dropped_record_too_large: 0,
+                dropped_ip_rate_exceeded: 0,
+                dropped_key_rate_exceeded: 0,
+                currently_banned: 1,
+            }
+        );
     }
 
     #[test]
@@ -4182,6 +5231,15 @@ mod tests {
         assert_eq!(ui_gateway_recording.len(), 0);
         TestLogHandler::new()
             .exists_log_containing("INFO: Neighborhood: Received shutdown order from client 1234");
+        let home_dir = node_home_directory("neighborhood", "shutdown_instruction_generates_log");
+        let json = std::fs::read_to_string(home_dir.join(SHUTDOWN_FILE_NAME)).unwrap();
+        let node_shutdown: NodeShutdown = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            node_shutdown,
+            NodeShutdown {
+                process_id: std::process::id()
+            }
+        );
     }
 
     #[test]
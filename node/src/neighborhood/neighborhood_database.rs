@@ -167,11 +167,14 @@ impl NeighborhoodDatabase {
     }
 
     pub fn remove_neighbor(&mut self, node_key: &PublicKey) -> Result<bool, String> {
-        let ip_addr: Option<IpAddr>;
+        let ip_addrs: Vec<IpAddr>;
         {
             let to_remove = match self.node_by_key_mut(node_key) {
                 Some(node_record) => {
-                    ip_addr = node_record.node_addr_opt().map(|addr| addr.ip_addr());
+                    ip_addrs = node_record
+                        .node_addr_opt()
+                        .map(|addr| addr.ip_addrs())
+                        .unwrap_or_default();
                     node_record
                 }
                 None => {
@@ -183,10 +186,9 @@ impl NeighborhoodDatabase {
             };
             to_remove.unset_node_addr();
         }
-        match ip_addr {
-            Some(ip) => self.by_ip_addr.remove(&ip),
-            None => None,
-        };
+        ip_addrs.iter().for_each(|ip| {
+            self.by_ip_addr.remove(ip);
+        });
 
         if self.root_mut().remove_half_neighbor_key(node_key) {
             self.root_mut().increment_version();
@@ -197,14 +199,17 @@ impl NeighborhoodDatabase {
     }
 
     pub fn remove_node(&mut self, node_key: &PublicKey) {
-        let ip_addr_opt = match self.node_by_key(node_key) {
-            None => None,
-            Some(node) => node.node_addr_opt().map(|na| na.ip_addr()),
+        let ip_addrs = match self.node_by_key(node_key) {
+            None => vec![],
+            Some(node) => node
+                .node_addr_opt()
+                .map(|na| na.ip_addrs())
+                .unwrap_or_default(),
         };
         let _ = self.by_public_key.remove(node_key).is_some();
-        if let Some(ip_addr) = ip_addr_opt {
-            let _ = self.by_ip_addr.remove(&ip_addr).is_some();
-        }
+        ip_addrs.iter().for_each(|ip_addr| {
+            self.by_ip_addr.remove(ip_addr);
+        });
     }
 
     pub fn cull_dead_nodes(&mut self) {
@@ -307,7 +312,9 @@ impl NeighborhoodDatabase {
         let node_addr_opt = node_record.node_addr_opt();
         self.by_public_key.insert(public_key.clone(), node_record);
         if let Some(node_addr) = node_addr_opt {
-            self.by_ip_addr.insert(node_addr.ip_addr(), public_key);
+            node_addr.ip_addrs().into_iter().for_each(|ip_addr| {
+                self.by_ip_addr.insert(ip_addr, public_key.clone());
+            });
         }
     }
 
@@ -504,6 +511,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn node_by_ip_finds_dual_stack_nodes_by_either_address_until_they_are_removed() {
+        let this_node = make_node_record(1234, true);
+        let mut dual_stack_node = make_node_record(4567, true);
+        let ipv4 = dual_stack_node.node_addr_opt().unwrap().ip_addr();
+        let ipv6 = IpAddr::from_str("2001:db8::5").unwrap();
+        dual_stack_node.force_node_addr(
+            &dual_stack_node
+                .node_addr_opt()
+                .unwrap()
+                .with_alternate_ip_addr(&ipv6),
+        );
+        let mut subject = db_from_node(&this_node);
+        subject.add_node(dual_stack_node.clone()).unwrap();
+
+        let by_ipv4 = subject.node_by_ip(&ipv4).cloned();
+        let by_ipv6 = subject.node_by_ip(&ipv6).cloned();
+        subject.remove_node(dual_stack_node.public_key());
+
+        assert_eq!(by_ipv4, Some(dual_stack_node.clone()));
+        assert_eq!(by_ipv6, Some(dual_stack_node));
+        assert_eq!(subject.node_by_ip(&ipv4), None);
+        assert_eq!(subject.node_by_ip(&ipv6), None);
+    }
+
     #[test]
     fn add_half_neighbor_works() {
         let this_node = make_node_record(1234, true);
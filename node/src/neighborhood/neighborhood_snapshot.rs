@@ -0,0 +1,326 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::neighborhood::gossip::GossipNodeRecord;
+use crate::neighborhood::neighborhood_database::NeighborhoodDatabase;
+use crate::neighborhood::node_record::NodeRecord;
+use crate::sub_lib::cryptde::{CryptDE, PublicKey};
+use crate::sub_lib::logger::Logger;
+use itertools::Itertools;
+use serde_derive::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+pub const NEIGHBORHOOD_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(300);
+pub const NEIGHBORHOOD_SNAPSHOT_MAX_AGE_SECS: u32 = 24 * 60 * 60;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SnapshotRecord {
+    pub gossip: GossipNodeRecord,
+    pub last_update: u32,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NeighborhoodSnapshot {
+    pub taken_at: u32,
+    pub records: Vec<SnapshotRecord>,
+    pub root_neighbors: Vec<PublicKey>,
+}
+
+impl NeighborhoodSnapshot {
+    // The root Node's record is deliberately left out: it's regenerated from configuration at every
+    // startup. Its neighbor keys are kept, though; without them nothing restored is reachable.
+    pub fn from_database(database: &NeighborhoodDatabase, taken_at: u32) -> NeighborhoodSnapshot {
+        let root_key = database.root().public_key();
+        let records = database
+            .keys()
+            .into_iter()
+            .filter(|key| *key != root_key)
+            .sorted()
+            .map(|key| {
+                let node_record = database.node_by_key(key).expect("Node disappeared");
+                SnapshotRecord {
+                    gossip: GossipNodeRecord::from(node_record.clone()),
+                    last_update: node_record.last_updated(),
+                }
+            })
+            .collect::<Vec<SnapshotRecord>>();
+        let root_neighbors = database
+            .root()
+            .half_neighbor_keys()
+            .into_iter()
+            .sorted()
+            .cloned()
+            .collect::<Vec<PublicKey>>();
+        NeighborhoodSnapshot {
+            taken_at,
+            records,
+            root_neighbors,
+        }
+    }
+
+    // Returns the number of records restored. A snapshot that is itself too old is ignored
+    // entirely. Records that are too old, that are badly signed, or that collide with Nodes
+    // already in the database are dropped. The root Node regains each of its old neighbors that
+    // was restored, and is re-signed if that changed it.
+    pub fn restore_into(
+        self,
+        database: &mut NeighborhoodDatabase,
+        cryptde: &dyn CryptDE,
+        now: u32,
+        logger: &Logger,
+    ) -> usize {
+        let deadline = now.saturating_sub(NEIGHBORHOOD_SNAPSHOT_MAX_AGE_SECS);
+        if self.taken_at < deadline {
+            info!(
+                logger,
+                "Ignoring neighborhood snapshot taken at {}; it is too old", self.taken_at
+            );
+            return 0;
+        }
+        let root_neighbors = self.root_neighbors;
+        let new_node_records = self
+            .records
+            .into_iter()
+            .filter(|record| {
+                if record.last_update < deadline {
+                    debug!(
+                        logger,
+                        "Expiring snapshot record last updated at {}", record.last_update
+                    );
+                    false
+                } else {
+                    true
+                }
+            })
+            .flat_map(|record| match NodeRecord::try_from(&record.gossip) {
+                Ok(mut node_record) => {
+                    node_record.metadata.last_update = record.last_update;
+                    Some(node_record)
+                }
+                Err(e) => {
+                    warning!(logger, "Dropping undeserializable snapshot record: {}", e);
+                    None
+                }
+            })
+            .filter(|node_record| {
+                let verified = cryptde.verify_signature(
+                    &node_record.signed_gossip,
+                    &node_record.signature,
+                    node_record.public_key(),
+                );
+                if !verified {
+                    warning!(
+                        logger,
+                        "Dropping snapshot record for {} with invalid signature",
+                        node_record.public_key()
+                    );
+                }
+                verified
+            })
+            .filter(|node_record| database.node_by_key(node_record.public_key()).is_none())
+            .collect::<Vec<NodeRecord>>();
+        let restored = new_node_records
+            .into_iter()
+            .map(|node_record| match database.add_node(node_record) {
+                Ok(_) => 1,
+                Err(e) => {
+                    warning!(logger, "Dropping snapshot record: {:?}", e);
+                    0
+                }
+            })
+            .sum();
+        let mut root_changed = false;
+        for key in root_neighbors {
+            if database.node_by_key(&key).is_none() {
+                continue;
+            }
+            match database.add_half_neighbor(&key) {
+                Ok(added) => root_changed |= added,
+                Err(e) => warning!(logger, "Not restoring neighbor {}: {:?}", key, e),
+            }
+        }
+        if root_changed {
+            let root = database.root_mut();
+            root.increment_version();
+            root.regenerate_signed_gossip(cryptde);
+        }
+        restored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sub_lib::cryptde_null::CryptDENull;
+    use crate::test_utils::neighborhood_test_utils::{db_from_node, make_node_record};
+    use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(NEIGHBORHOOD_SNAPSHOT_INTERVAL, Duration::from_secs(300));
+        assert_eq!(NEIGHBORHOOD_SNAPSHOT_MAX_AGE_SECS, 86400);
+    }
+
+    #[test]
+    fn from_database_leaves_out_root_and_keeps_timestamps() {
+        let root = make_node_record(1234, true);
+        let mut database = db_from_node(&root);
+        let mut one = make_node_record(2345, true);
+        one.metadata.last_update = 1000;
+        let mut another = make_node_record(3456, false);
+        another.metadata.last_update = 2000;
+        database.add_node(one.clone()).unwrap();
+        database.add_node(another.clone()).unwrap();
+        database.add_arbitrary_full_neighbor(root.public_key(), one.public_key());
+
+        let result = NeighborhoodSnapshot::from_database(&database, 5000);
+
+        assert_eq!(result.taken_at, 5000);
+        assert_eq!(result.root_neighbors, vec![one.public_key().clone()]);
+        assert_eq!(result.records.len(), 2);
+        assert!(result.records.contains(&SnapshotRecord {
+            gossip: GossipNodeRecord::from(one),
+            last_update: 1000,
+        }));
+        assert!(result.records.contains(&SnapshotRecord {
+            gossip: GossipNodeRecord::from(another),
+            last_update: 2000,
+        }));
+    }
+
+    #[test]
+    fn restore_into_keeps_fresh_well_signed_records_only() {
+        let root = make_node_record(1234, true);
+        let mut source_database = db_from_node(&root);
+        let mut fresh = make_node_record(2345, true);
+        fresh.metadata.last_update = 100_000;
+        let mut stale = make_node_record(3456, true);
+        stale.metadata.last_update = 100_000 - NEIGHBORHOOD_SNAPSHOT_MAX_AGE_SECS - 1;
+        let mut forged = make_node_record(4567, true);
+        forged.metadata.last_update = 100_000;
+        forged.inner.version = 99;
+        forged.signed_gossip = serde_cbor::ser::to_vec(&forged.inner).unwrap().into();
+        source_database.add_node(fresh.clone()).unwrap();
+        source_database.add_node(stale.clone()).unwrap();
+        source_database.add_node(forged.clone()).unwrap();
+        let snapshot = NeighborhoodSnapshot::from_database(&source_database, 100_000);
+        let mut target_database = db_from_node(&root);
+        let cryptde = CryptDENull::from(root.public_key(), DEFAULT_CHAIN_ID);
+
+        let result = snapshot.restore_into(
+            &mut target_database,
+            &cryptde,
+            100_000,
+            &Logger::new("test"),
+        );
+
+        assert_eq!(result, 1);
+        let restored = target_database.node_by_key(fresh.public_key()).unwrap();
+        assert_eq!(restored.inner, fresh.inner);
+        assert_eq!(restored.last_updated(), 100_000);
+        assert_eq!(restored.node_addr_opt(), fresh.node_addr_opt());
+        assert_eq!(target_database.node_by_key(stale.public_key()), None);
+        assert_eq!(target_database.node_by_key(forged.public_key()), None);
+    }
+
+    #[test]
+    fn restore_into_does_not_replace_nodes_already_present() {
+        let root = make_node_record(1234, true);
+        let mut source_database = db_from_node(&root);
+        let mut old_version = make_node_record(2345, true);
+        old_version.metadata.last_update = 100_000;
+        source_database.add_node(old_version.clone()).unwrap();
+        let snapshot = NeighborhoodSnapshot::from_database(&source_database, 100_000);
+        let mut target_database = db_from_node(&root);
+        let mut new_version = make_node_record(2345, true);
+        new_version.inner.version = 5;
+        new_version.resign();
+        target_database.add_node(new_version.clone()).unwrap();
+        let cryptde = CryptDENull::from(root.public_key(), DEFAULT_CHAIN_ID);
+
+        let result = snapshot.restore_into(
+            &mut target_database,
+            &cryptde,
+            100_000,
+            &Logger::new("test"),
+        );
+
+        assert_eq!(result, 0);
+        assert_eq!(
+            target_database
+                .node_by_key(new_version.public_key())
+                .unwrap()
+                .version(),
+            5
+        );
+    }
+
+    #[test]
+    fn restore_into_ignores_a_stale_snapshot() {
+        let root = make_node_record(1234, true);
+        let mut source_database = db_from_node(&root);
+        let mut fresh = make_node_record(2345, true);
+        fresh.metadata.last_update = 100_000;
+        source_database.add_node(fresh.clone()).unwrap();
+        let snapshot = NeighborhoodSnapshot::from_database(
+            &source_database,
+            100_000 - NEIGHBORHOOD_SNAPSHOT_MAX_AGE_SECS - 1,
+        );
+        let mut target_database = db_from_node(&root);
+        let cryptde = CryptDENull::from(root.public_key(), DEFAULT_CHAIN_ID);
+
+        let result = snapshot.restore_into(
+            &mut target_database,
+            &cryptde,
+            100_000,
+            &Logger::new("test"),
+        );
+
+        assert_eq!(result, 0);
+        assert_eq!(target_database.node_by_key(fresh.public_key()), None);
+    }
+
+    #[test]
+    fn restore_into_gives_the_root_back_its_restored_neighbors() {
+        let root = make_node_record(1234, true);
+        let mut source_database = db_from_node(&root);
+        let mut neighbor = make_node_record(2345, true);
+        neighbor.metadata.last_update = 100_000;
+        let mut stale_neighbor = make_node_record(3456, true);
+        stale_neighbor.metadata.last_update = 100_000 - NEIGHBORHOOD_SNAPSHOT_MAX_AGE_SECS - 1;
+        source_database.add_node(neighbor.clone()).unwrap();
+        source_database.add_node(stale_neighbor.clone()).unwrap();
+        source_database.add_arbitrary_full_neighbor(root.public_key(), neighbor.public_key());
+        source_database.add_arbitrary_full_neighbor(root.public_key(), stale_neighbor.public_key());
+        source_database.resign_node(neighbor.public_key());
+        let snapshot = NeighborhoodSnapshot::from_database(&source_database, 100_000);
+        let mut target_database = db_from_node(&root);
+        let cryptde = CryptDENull::from(root.public_key(), DEFAULT_CHAIN_ID);
+
+        let result = snapshot.restore_into(
+            &mut target_database,
+            &cryptde,
+            100_000,
+            &Logger::new("test"),
+        );
+
+        assert_eq!(result, 1);
+        let restored_root = target_database.root();
+        assert_eq!(
+            restored_root.half_neighbor_keys(),
+            vec![neighbor.public_key()].into_iter().collect()
+        );
+        assert_eq!(
+            restored_root.has_full_neighbor(&target_database, neighbor.public_key()),
+            true
+        );
+        assert_eq!(restored_root.version(), root.version() + 1);
+        assert_eq!(
+            cryptde.verify_signature(
+                &restored_root.signed_gossip,
+                &restored_root.signature,
+                restored_root.public_key()
+            ),
+            true
+        );
+    }
+}
@@ -26,6 +26,22 @@ pub struct NodeRecordInner_0v1 {
     pub accepts_connections: bool,
     pub routes_data: bool,
     pub version: u32,
+    // Set while the Node is holding back or dropping traffic to stay under its bandwidth limits.
+    // Left out of the gossip when it's false, so that older Nodes see the same records as always.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub reduced_capacity: bool,
+    // Set by Nodes that can decrypt hops sealed in session-key envelopes. Nodes that don't set it
+    // get their hops encrypted wholly with their public keys.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub accepts_session_keys: bool,
+    // Set by Nodes that can unmask the http and tls masquerades. Streams opened to Nodes that
+    // don't set it wear the json masquerade, whatever --masquerade says.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub accepts_masquerades: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
 impl TryFrom<GossipNodeRecord> for NodeRecordInner_0v1 {
@@ -80,6 +96,9 @@ impl NodeRecord {
                 routes_data,
                 neighbors: BTreeSet::new(),
                 version,
+                reduced_capacity: false,
+                accepts_session_keys: false,
+                accepts_masquerades: false,
             },
             signed_gossip: PlainData::new(&[]),
             signature: CryptData::new(&[]),
@@ -216,6 +235,35 @@ impl NodeRecord {
         self.inner.version
     }
 
+    pub fn reduced_capacity(&self) -> bool {
+        self.inner.reduced_capacity
+    }
+
+    pub fn set_reduced_capacity(&mut self, reduced_capacity: bool) -> bool {
+        if self.inner.reduced_capacity == reduced_capacity {
+            false
+        } else {
+            self.inner.reduced_capacity = reduced_capacity;
+            true
+        }
+    }
+
+    pub fn accepts_session_keys(&self) -> bool {
+        self.inner.accepts_session_keys
+    }
+
+    pub fn set_accepts_session_keys(&mut self, accepts_session_keys: bool) {
+        self.inner.accepts_session_keys = accepts_session_keys;
+    }
+
+    pub fn accepts_masquerades(&self) -> bool {
+        self.inner.accepts_masquerades
+    }
+
+    pub fn set_accepts_masquerades(&mut self, accepts_masquerades: bool) {
+        self.inner.accepts_masquerades = accepts_masquerades;
+    }
+
     pub fn increment_version(&mut self) {
         self.inner.version += 1;
     }
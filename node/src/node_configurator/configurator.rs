@@ -1,11 +1,16 @@
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
 use actix::{Actor, Context, Handler, Recipient};
 
 use masq_lib::messages::{
-    FromMessageBody, ToMessageBody, UiChangePasswordRequest, UiChangePasswordResponse,
-    UiCheckPasswordRequest, UiCheckPasswordResponse, UiGenerateWalletsRequest,
-    UiGenerateWalletsResponse, UiNewPasswordBroadcast,
+    FromMessageBody, ToMessageBody, UiBandwidthLimitsRequest, UiBandwidthLimitsResponse,
+    UiChangePasswordRequest, UiChangePasswordResponse, UiCheckPasswordRequest,
+    UiCheckPasswordResponse, UiClientDisconnectedNotice, UiComponentLogLevel,
+    UiGenerateWalletsRequest, UiGenerateWalletsResponse, UiLogLevelRequest, UiLogLevelResponse,
+    UiLogSubscribeRequest, UiLogSubscribeResponse, UiLogUnsubscribeRequest,
+    UiLogUnsubscribeResponse, UiNewPasswordBroadcast,
 };
 use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{
@@ -19,7 +24,14 @@ use crate::db_config::config_dao::ConfigDaoReal;
 use crate::db_config::persistent_configuration::{
     PersistentConfiguration, PersistentConfigurationReal,
 };
+use crate::sub_lib::bandwidth::{
+    bandwidth_throttle, BandwidthLimits, BandwidthThrottle, ServiceBandwidthLimits,
+};
 use crate::sub_lib::configurator::NewPasswordMessage;
+use crate::sub_lib::log_control::{
+    level_filter_name, parse_level, parse_level_filter, LogController, LogControllerReal,
+    LogLevels, LogStream, LogSubscription,
+};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::wallet::Wallet;
@@ -34,11 +46,16 @@ pub const KEY_PAIR_CONSTRUCTION_ERROR: u64 = CONFIGURATOR_PREFIX | 5;
 pub const BAD_PASSWORD_ERROR: u64 = CONFIGURATOR_PREFIX | 6;
 pub const ALREADY_INITIALIZED_ERROR: u64 = CONFIGURATOR_PREFIX | 7;
 pub const DERIVATION_PATH_ERROR: u64 = CONFIGURATOR_PREFIX | 8;
+pub const LOG_LEVEL_ERROR: u64 = CONFIGURATOR_PREFIX | 9;
+pub const BANDWIDTH_LIMITS_ERROR: u64 = CONFIGURATOR_PREFIX | 10;
 
 pub struct Configurator {
     persistent_config: Box<dyn PersistentConfiguration>,
+    log_controller: Box<dyn LogController>,
     node_to_ui_sub: Option<Recipient<NodeToUiMessage>>,
     new_password_subs: Option<Vec<Recipient<NewPasswordMessage>>>,
+    log_stream_opt: Option<Arc<LogStream>>,
+    bandwidth_throttle: Arc<BandwidthThrottle>,
     logger: Logger,
 }
 
@@ -51,7 +68,10 @@ impl Handler<BindMessage> for Configurator {
 
     fn handle(&mut self, msg: BindMessage, _ctx: &mut Self::Context) -> Self::Result {
         self.node_to_ui_sub = Some(msg.peer_actors.ui_gateway.node_to_ui_message_sub.clone());
-        self.new_password_subs = Some(vec![msg.peer_actors.neighborhood.new_password_sub])
+        self.new_password_subs = Some(vec![msg.peer_actors.neighborhood.new_password_sub]);
+        self.log_stream_opt = Some(Arc::new(LogStream::new(
+            msg.peer_actors.ui_gateway.node_to_ui_message_sub,
+        )));
     }
 }
 
@@ -84,6 +104,39 @@ impl Handler<NodeFromUiMessage> for Configurator {
                 "Sending response to generateWallets command:\n{:?}", response
             );
             self.send_to_ui_gateway(ClientId(msg.client_id), response);
+        } else if let Ok((body, context_id)) = UiLogLevelRequest::fmb(msg.clone().body) {
+            debug!(
+                &self.logger,
+                "Handling {} message from client {}", msg.body.opcode, msg.client_id
+            );
+            let response = self.handle_log_level(body, context_id);
+            self.send_to_ui_gateway(ClientId(msg.client_id), response);
+        } else if let Ok((body, context_id)) = UiBandwidthLimitsRequest::fmb(msg.clone().body) {
+            debug!(
+                &self.logger,
+                "Handling {} message from client {}", msg.body.opcode, msg.client_id
+            );
+            let response = self.handle_bandwidth_limits(body, context_id);
+            self.send_to_ui_gateway(ClientId(msg.client_id), response);
+        } else if let Ok((body, context_id)) = UiLogSubscribeRequest::fmb(msg.clone().body) {
+            debug!(
+                &self.logger,
+                "Handling {} message from client {}", msg.body.opcode, msg.client_id
+            );
+            let response = self.handle_log_subscribe(body, msg.client_id, context_id);
+            self.send_to_ui_gateway(ClientId(msg.client_id), response);
+        } else if let Ok((_, context_id)) = UiLogUnsubscribeRequest::fmb(msg.clone().body) {
+            debug!(
+                &self.logger,
+                "Handling {} message from client {}", msg.body.opcode, msg.client_id
+            );
+            self.forget_log_subscriber(msg.client_id);
+            self.send_to_ui_gateway(
+                ClientId(msg.client_id),
+                UiLogUnsubscribeResponse {}.tmb(context_id),
+            );
+        } else if UiClientDisconnectedNotice::fmb(msg.clone().body).is_ok() {
+            self.forget_log_subscriber(msg.client_id);
         }
     }
 }
@@ -92,8 +145,11 @@ impl From<Box<dyn PersistentConfiguration>> for Configurator {
     fn from(persistent_config: Box<dyn PersistentConfiguration>) -> Self {
         Configurator {
             persistent_config,
+            log_controller: Box::new(LogControllerReal::new()),
             node_to_ui_sub: None,
             new_password_subs: None,
+            log_stream_opt: None,
+            bandwidth_throttle: bandwidth_throttle(),
             logger: Logger::new("Configurator"),
         }
     }
@@ -311,6 +367,136 @@ impl Configurator {
         }
     }
 
+    fn handle_log_level(&mut self, msg: UiLogLevelRequest, context_id: u64) -> MessageBody {
+        let component_opt = msg
+            .component_opt
+            .as_ref()
+            .map(|component| component.as_str());
+        let result = match &msg.level_opt {
+            None => Ok(self.log_controller.log_levels()),
+            Some(level_name) => parse_level_filter(level_name).and_then(|level| {
+                let result = self.log_controller.set_log_level(component_opt, level);
+                if result.is_ok() {
+                    info!(
+                        self.logger,
+                        "Log level for {} changed to {}",
+                        component_opt.unwrap_or("components without their own level"),
+                        level_filter_name(level)
+                    );
+                }
+                result
+            }),
+        };
+        match result {
+            Ok(levels) => Self::log_level_response(levels).tmb(context_id),
+            Err(e) => MessageBody {
+                opcode: msg.opcode().to_string(),
+                path: MessagePath::Conversation(context_id),
+                payload: Err((LOG_LEVEL_ERROR, e)),
+            },
+        }
+    }
+
+    fn log_level_response(levels: LogLevels) -> UiLogLevelResponse {
+        UiLogLevelResponse {
+            default_level: level_filter_name(levels.default_level),
+            component_levels: levels
+                .component_levels
+                .into_iter()
+                .map(|(component, level)| UiComponentLogLevel {
+                    component,
+                    level: level_filter_name(level),
+                })
+                .collect(),
+        }
+    }
+
+    fn handle_bandwidth_limits(
+        &mut self,
+        msg: UiBandwidthLimitsRequest,
+        context_id: u64,
+    ) -> MessageBody {
+        if let Err(e) = msg
+            .routing_opt
+            .iter()
+            .chain(msg.exit_opt.iter())
+            .try_for_each(|limits| limits.check())
+        {
+            return MessageBody {
+                opcode: msg.opcode().to_string(),
+                path: MessagePath::Conversation(context_id),
+                payload: Err((BANDWIDTH_LIMITS_ERROR, e)),
+            };
+        }
+        if msg.routing_opt.is_some() || msg.exit_opt.is_some() {
+            let old_limits = self.bandwidth_throttle.limits();
+            let new_limits = BandwidthLimits {
+                routing: msg
+                    .routing_opt
+                    .map(ServiceBandwidthLimits::from)
+                    .unwrap_or(old_limits.routing),
+                exit: msg
+                    .exit_opt
+                    .map(ServiceBandwidthLimits::from)
+                    .unwrap_or(old_limits.exit),
+            };
+            self.bandwidth_throttle.set_limits(new_limits);
+            info!(
+                self.logger,
+                "Bandwidth limits changed to {} for routing and {} for exit",
+                new_limits.routing,
+                new_limits.exit
+            );
+        }
+        let limits = self.bandwidth_throttle.limits();
+        UiBandwidthLimitsResponse {
+            routing: limits.routing.into(),
+            exit: limits.exit.into(),
+            throttled: self.bandwidth_throttle.is_throttled(Instant::now()),
+        }
+        .tmb(context_id)
+    }
+
+    fn handle_log_subscribe(
+        &mut self,
+        msg: UiLogSubscribeRequest,
+        client_id: u64,
+        context_id: u64,
+    ) -> MessageBody {
+        let level = match parse_level(&msg.level) {
+            Ok(level) => level,
+            Err(e) => {
+                return MessageBody {
+                    opcode: msg.opcode().to_string(),
+                    path: MessagePath::Conversation(context_id),
+                    payload: Err((LOG_LEVEL_ERROR, e)),
+                }
+            }
+        };
+        let log_stream = self
+            .log_stream_opt
+            .as_ref()
+            .expect("Configurator is unbound")
+            .clone();
+        log_stream.subscribe(
+            client_id,
+            LogSubscription {
+                components: msg.components,
+                level,
+            },
+        );
+        self.log_controller.set_log_tap(Some(log_stream));
+        UiLogSubscribeResponse {}.tmb(context_id)
+    }
+
+    fn forget_log_subscriber(&mut self, client_id: u64) {
+        if let Some(log_stream) = self.log_stream_opt.as_ref() {
+            if log_stream.unsubscribe(client_id) && log_stream.is_empty() {
+                self.log_controller.set_log_tap(None);
+            }
+        }
+    }
+
     fn send_to_ui_gateway(&self, target: MessageTarget, body: MessageBody) {
         let msg = NodeToUiMessage { target, body };
         self.node_to_ui_sub
@@ -335,13 +521,16 @@ impl Configurator {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
     use std::sync::{Arc, Mutex};
 
     use actix::System;
+    use log::{Level, LevelFilter};
 
     use masq_lib::messages::{
         ToMessageBody, UiChangePasswordResponse, UiCheckPasswordRequest, UiCheckPasswordResponse,
-        UiGenerateWalletsResponse, UiNewPasswordBroadcast, UiStartOrder,
+        UiGenerateWalletsResponse, UiNewPasswordBroadcast, UiServiceBandwidthLimits, UiStartOrder,
     };
     use masq_lib::ui_gateway::{MessagePath, MessageTarget};
 
@@ -356,10 +545,14 @@ mod tests {
     use crate::blockchain::bip32::Bip32ECKeyPair;
     use crate::blockchain::bip39::Bip39;
     use crate::database::db_initializer::{DbInitializer, DbInitializerReal};
+    use crate::sub_lib::bandwidth::{BandwidthService, Traffic};
     use crate::sub_lib::cryptde::PlainData;
+    use crate::sub_lib::log_control::LogTap;
     use crate::sub_lib::wallet::Wallet;
     use bip39::{Language, Mnemonic};
     use masq_lib::test_utils::utils::{ensure_node_home_directory_exists, DEFAULT_CHAIN_ID};
+    use std::net::IpAddr;
+    use std::str::FromStr;
 
     #[test]
     fn constructor_connects_with_database() {
@@ -841,6 +1034,524 @@ mod tests {
         }
     }
 
+    #[test]
+    fn log_level_request_without_a_level_reports_the_current_levels() {
+        let mut component_levels = BTreeMap::new();
+        component_levels.insert("Neighborhood".to_string(), LevelFilter::Debug);
+        let log_controller = LogControllerMock::new().log_levels_result(LogLevels {
+            default_level: LevelFilter::Warn,
+            component_levels,
+        });
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+
+        let result = subject.handle_log_level(
+            UiLogLevelRequest {
+                component_opt: None,
+                level_opt: None,
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            UiLogLevelResponse {
+                default_level: "warn".to_string(),
+                component_levels: vec![UiComponentLogLevel {
+                    component: "Neighborhood".to_string(),
+                    level: "debug".to_string(),
+                }],
+            }
+            .tmb(4321)
+        );
+    }
+
+    #[test]
+    fn log_level_request_with_a_level_changes_it() {
+        init_test_logging();
+        let system = System::new("test");
+        let set_log_level_params_arc = Arc::new(Mutex::new(vec![]));
+        let mut component_levels = BTreeMap::new();
+        component_levels.insert("ProxyServer".to_string(), LevelFilter::Trace);
+        let log_controller = LogControllerMock::new()
+            .set_log_level_params(&set_log_level_params_arc)
+            .set_log_level_result(Ok(LogLevels {
+                default_level: LevelFilter::Info,
+                component_levels,
+            }));
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+        let subject_addr = subject.start();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiLogLevelRequest {
+                    component_opt: Some("ProxyServer".to_string()),
+                    level_opt: Some("trace".to_string()),
+                }
+                .tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let set_log_level_params = set_log_level_params_arc.lock().unwrap();
+        assert_eq!(
+            *set_log_level_params,
+            vec![(Some("ProxyServer".to_string()), LevelFilter::Trace)]
+        );
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(0),
+            &NodeToUiMessage {
+                target: MessageTarget::ClientId(1234),
+                body: UiLogLevelResponse {
+                    default_level: "info".to_string(),
+                    component_levels: vec![UiComponentLogLevel {
+                        component: "ProxyServer".to_string(),
+                        level: "trace".to_string(),
+                    }],
+                }
+                .tmb(4321)
+            }
+        );
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Configurator: Log level for ProxyServer changed to trace",
+        );
+    }
+
+    #[test]
+    fn log_level_request_rejects_an_unknown_level() {
+        let set_log_level_params_arc = Arc::new(Mutex::new(vec![]));
+        let log_controller =
+            LogControllerMock::new().set_log_level_params(&set_log_level_params_arc);
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+
+        let result = subject.handle_log_level(
+            UiLogLevelRequest {
+                component_opt: None,
+                level_opt: Some("loud".to_string()),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "logLevel".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    LOG_LEVEL_ERROR,
+                    "'loud' is not a log level: use off, error, warn, info, debug, or trace"
+                        .to_string()
+                )),
+            }
+        );
+        assert_eq!(set_log_level_params_arc.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn log_level_request_reports_a_failure_to_change_the_level() {
+        let log_controller = LogControllerMock::new()
+            .set_log_level_result(Err("Logging has not been initialized".to_string()));
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+
+        let result = subject.handle_log_level(
+            UiLogLevelRequest {
+                component_opt: None,
+                level_opt: Some("debug".to_string()),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "logLevel".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    LOG_LEVEL_ERROR,
+                    "Logging has not been initialized".to_string()
+                )),
+            }
+        );
+    }
+
+    fn make_ui_bandwidth_limits(
+        download: u64,
+        upload: u64,
+        neighbor_download: u64,
+        neighbor_upload: u64,
+    ) -> UiServiceBandwidthLimits {
+        UiServiceBandwidthLimits {
+            download,
+            upload,
+            neighbor_download,
+            neighbor_upload,
+        }
+    }
+
+    #[test]
+    fn bandwidth_limits_request_without_limits_reports_the_current_ones() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str("1:2:3:4").unwrap(),
+            exit: ServiceBandwidthLimits::from_str("5:6:7:8").unwrap(),
+        });
+        let mut subject = make_subject(None);
+        subject.bandwidth_throttle = throttle.clone();
+
+        let result = subject.handle_bandwidth_limits(
+            UiBandwidthLimitsRequest {
+                routing_opt: None,
+                exit_opt: None,
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            UiBandwidthLimitsResponse {
+                routing: make_ui_bandwidth_limits(1, 2, 3, 4),
+                exit: make_ui_bandwidth_limits(5, 6, 7, 8),
+                throttled: false,
+            }
+            .tmb(4321)
+        );
+        assert_eq!(
+            throttle.limits().routing,
+            ServiceBandwidthLimits::from_str("1:2:3:4").unwrap()
+        );
+    }
+
+    #[test]
+    fn bandwidth_limits_request_changes_only_the_limits_it_contains() {
+        init_test_logging();
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str("1:2:3:4").unwrap(),
+            exit: ServiceBandwidthLimits::from_str("5:6:7:8").unwrap(),
+        });
+        let mut subject = make_subject(None);
+        subject.bandwidth_throttle = throttle.clone();
+
+        let result = subject.handle_bandwidth_limits(
+            UiBandwidthLimitsRequest {
+                routing_opt: None,
+                exit_opt: Some(make_ui_bandwidth_limits(50, 0, 10, 0)),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            UiBandwidthLimitsResponse {
+                routing: make_ui_bandwidth_limits(1, 2, 3, 4),
+                exit: make_ui_bandwidth_limits(50, 0, 10, 0),
+                throttled: false,
+            }
+            .tmb(4321)
+        );
+        assert_eq!(
+            throttle.limits(),
+            BandwidthLimits {
+                routing: ServiceBandwidthLimits::from_str("1:2:3:4").unwrap(),
+                exit: ServiceBandwidthLimits::from_str("50:0:10:0").unwrap(),
+            }
+        );
+        TestLogHandler::new().exists_log_containing(
+            "INFO: Configurator: Bandwidth limits changed to 1:2:3:4 for routing and 50:0:10:0 for exit",
+        );
+    }
+
+    #[test]
+    fn bandwidth_limits_request_refuses_limits_that_are_too_big() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        let old_limits = BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str("1:2:3:4").unwrap(),
+            exit: ServiceBandwidthLimits::from_str("5:6:7:8").unwrap(),
+        };
+        throttle.set_limits(old_limits);
+        let mut subject = make_subject(None);
+        subject.bandwidth_throttle = throttle.clone();
+
+        let result = subject.handle_bandwidth_limits(
+            UiBandwidthLimitsRequest {
+                routing_opt: Some(make_ui_bandwidth_limits(50, 0, 10, 0)),
+                exit_opt: Some(make_ui_bandwidth_limits(0, u64::MAX, 0, 0)),
+            },
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "bandwidthLimits".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    BANDWIDTH_LIMITS_ERROR,
+                    "Bandwidth limits can be no more than 10000000 kilobytes per second, not 0:18446744073709551615:0:0".to_string()
+                )),
+            }
+        );
+        assert_eq!(throttle.limits(), old_limits);
+    }
+
+    #[test]
+    fn bandwidth_limits_request_reports_whether_the_node_is_throttled() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str("1:0:0:0").unwrap(),
+            exit: ServiceBandwidthLimits::default(),
+        });
+        throttle.charge(
+            BandwidthService::Routing,
+            Traffic::Download(IpAddr::from_str("1.2.3.4").unwrap()),
+            2048,
+            Instant::now(),
+        );
+        let mut subject = make_subject(None);
+        subject.bandwidth_throttle = throttle;
+
+        let result = subject.handle_bandwidth_limits(
+            UiBandwidthLimitsRequest {
+                routing_opt: None,
+                exit_opt: None,
+            },
+            4321,
+        );
+
+        let (response, _) = UiBandwidthLimitsResponse::fmb(result).unwrap();
+        assert_eq!(response.throttled, true);
+    }
+
+    #[test]
+    fn bandwidth_limits_request_is_answered_through_the_ui_gateway() {
+        let system = System::new("test");
+        let mut subject = make_subject(None);
+        subject.bandwidth_throttle = Arc::new(BandwidthThrottle::new());
+        let subject_addr = subject.start();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiBandwidthLimitsRequest {
+                    routing_opt: Some(make_ui_bandwidth_limits(100, 200, 0, 0)),
+                    exit_opt: None,
+                }
+                .tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(0),
+            &NodeToUiMessage {
+                target: MessageTarget::ClientId(1234),
+                body: UiBandwidthLimitsResponse {
+                    routing: make_ui_bandwidth_limits(100, 200, 0, 0),
+                    exit: make_ui_bandwidth_limits(0, 0, 0, 0),
+                    throttled: false,
+                }
+                .tmb(4321)
+            }
+        );
+    }
+
+    #[test]
+    fn log_subscribe_request_installs_a_tap_for_the_subscriber() {
+        let system = System::new("test");
+        let set_log_tap_params_arc = Arc::new(Mutex::new(vec![]));
+        let log_controller = LogControllerMock::new().set_log_tap_params(&set_log_tap_params_arc);
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+        let subject_addr = subject.start();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr
+            .try_send(NodeFromUiMessage {
+                client_id: 1234,
+                body: UiLogSubscribeRequest {
+                    components: vec!["Neighborhood".to_string()],
+                    level: "debug".to_string(),
+                }
+                .tmb(4321),
+            })
+            .unwrap();
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(0),
+            &NodeToUiMessage {
+                target: MessageTarget::ClientId(1234),
+                body: UiLogSubscribeResponse {}.tmb(4321)
+            }
+        );
+        let set_log_tap_params = set_log_tap_params_arc.lock().unwrap();
+        assert_eq!(set_log_tap_params.len(), 1);
+        let tap = set_log_tap_params[0].as_ref().unwrap();
+        assert_eq!(tap.wants("Neighborhood", Level::Debug), true);
+        assert_eq!(tap.wants("Neighborhood", Level::Trace), false);
+        assert_eq!(tap.wants("ProxyServer", Level::Error), false);
+    }
+
+    #[test]
+    fn log_subscribe_request_rejects_an_unknown_level() {
+        let set_log_tap_params_arc = Arc::new(Mutex::new(vec![]));
+        let log_controller = LogControllerMock::new().set_log_tap_params(&set_log_tap_params_arc);
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+
+        let result = subject.handle_log_subscribe(
+            UiLogSubscribeRequest {
+                components: vec![],
+                level: "off".to_string(),
+            },
+            1234,
+            4321,
+        );
+
+        assert_eq!(
+            result,
+            MessageBody {
+                opcode: "logSubscribe".to_string(),
+                path: MessagePath::Conversation(4321),
+                payload: Err((
+                    LOG_LEVEL_ERROR,
+                    "'off' is not a log level: use error, warn, info, debug, or trace".to_string()
+                )),
+            }
+        );
+        assert_eq!(set_log_tap_params_arc.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn tap_is_removed_when_the_last_subscriber_unsubscribes_or_disconnects() {
+        let system = System::new("test");
+        let set_log_tap_params_arc = Arc::new(Mutex::new(vec![]));
+        let log_controller = LogControllerMock::new().set_log_tap_params(&set_log_tap_params_arc);
+        let mut subject = make_subject(None);
+        subject.log_controller = Box::new(log_controller);
+        let subject_addr = subject.start();
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+        let subscribe = UiLogSubscribeRequest {
+            components: vec![],
+            level: "info".to_string(),
+        };
+        vec![
+            (1234, subscribe.clone().tmb(1)),
+            (2345, subscribe.tmb(2)),
+            (1234, UiLogUnsubscribeRequest {}.tmb(3)),
+            (2345, UiClientDisconnectedNotice {}.tmb(0)),
+        ]
+        .into_iter()
+        .for_each(|(client_id, body)| {
+            subject_addr
+                .try_send(NodeFromUiMessage { client_id, body })
+                .unwrap()
+        });
+
+        System::current().stop();
+        system.run();
+        let set_log_tap_params = set_log_tap_params_arc.lock().unwrap();
+        assert_eq!(
+            set_log_tap_params
+                .iter()
+                .map(|tap_opt| tap_opt.is_some())
+                .collect::<Vec<bool>>(),
+            vec![true, true, false]
+        );
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        assert_eq!(
+            ui_gateway_recording.get_record::<NodeToUiMessage>(2),
+            &NodeToUiMessage {
+                target: MessageTarget::ClientId(1234),
+                body: UiLogUnsubscribeResponse {}.tmb(3)
+            }
+        );
+        assert_eq!(ui_gateway_recording.len(), 3);
+    }
+
+    struct LogControllerMock {
+        log_levels_results: RefCell<Vec<LogLevels>>,
+        set_log_level_params: Arc<Mutex<Vec<(Option<String>, LevelFilter)>>>,
+        set_log_level_results: RefCell<Vec<Result<LogLevels, String>>>,
+        set_log_tap_params: Arc<Mutex<Vec<Option<Arc<dyn LogTap>>>>>,
+    }
+
+    impl LogController for LogControllerMock {
+        fn log_levels(&self) -> LogLevels {
+            self.log_levels_results.borrow_mut().remove(0)
+        }
+
+        fn set_log_level(
+            &mut self,
+            component_opt: Option<&str>,
+            level: LevelFilter,
+        ) -> Result<LogLevels, String> {
+            self.set_log_level_params
+                .lock()
+                .unwrap()
+                .push((component_opt.map(|component| component.to_string()), level));
+            self.set_log_level_results.borrow_mut().remove(0)
+        }
+
+        fn set_log_tap(&mut self, tap_opt: Option<Arc<dyn LogTap>>) {
+            self.set_log_tap_params.lock().unwrap().push(tap_opt);
+        }
+    }
+
+    impl LogControllerMock {
+        fn new() -> Self {
+            Self {
+                log_levels_results: RefCell::new(vec![]),
+                set_log_level_params: Arc::new(Mutex::new(vec![])),
+                set_log_level_results: RefCell::new(vec![]),
+                set_log_tap_params: Arc::new(Mutex::new(vec![])),
+            }
+        }
+
+        fn log_levels_result(self, result: LogLevels) -> Self {
+            self.log_levels_results.borrow_mut().push(result);
+            self
+        }
+
+        fn set_log_level_params(
+            mut self,
+            params: &Arc<Mutex<Vec<(Option<String>, LevelFilter)>>>,
+        ) -> Self {
+            self.set_log_level_params = params.clone();
+            self
+        }
+
+        fn set_log_level_result(self, result: Result<LogLevels, String>) -> Self {
+            self.set_log_level_results.borrow_mut().push(result);
+            self
+        }
+
+        fn set_log_tap_params(mut self, params: &Arc<Mutex<Vec<Option<Arc<dyn LogTap>>>>>) -> Self {
+            self.set_log_tap_params = params.clone();
+            self
+        }
+    }
+
     fn make_subject(persistent_config_opt: Option<PersistentConfigurationMock>) -> Configurator {
         let persistent_config: Box<dyn PersistentConfiguration> =
             Box::new(persistent_config_opt.unwrap_or(PersistentConfigurationMock::new()));
@@ -1,5 +1,8 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
+use crate::daemon::restart_policy::{
+    RestartPolicy, DEFAULT_RESTART_LIMIT, DEFAULT_RESTART_WINDOW_SECS,
+};
 use crate::node_configurator::{app_head, NodeConfigurator};
 use crate::sub_lib::utils::make_new_multi_config;
 use clap::{App, Arg};
@@ -16,11 +19,29 @@ lazy_static! {
         Best to accept the default unless you know what you're doing. Must be between {} and {}.",
         LOWEST_USABLE_INSECURE_PORT, HIGHEST_USABLE_PORT
     );
+    static ref DEFAULT_RESTART_LIMIT_VALUE: String = DEFAULT_RESTART_LIMIT.to_string();
+    static ref DEFAULT_RESTART_WINDOW_VALUE: String = DEFAULT_RESTART_WINDOW_SECS.to_string();
 }
 
+const RESTART_POLICY_HELP: &str =
+    "What the Daemon should do when the Node dies without being told to: never restart it, always restart it, \
+     or restart it only on-failure (that is, unless it exits with status 0). Restarts use the same setup the Node \
+     was last started with.";
+const RESTART_LIMIT_HELP: &str =
+    "The greatest number of times the Daemon will try to restart the Node within --restart-window seconds before \
+     giving up.";
+const RESTART_WINDOW_HELP: &str =
+    "The length, in seconds, of the period within which no more than --restart-limit restarts will be attempted.";
+const UI_AUTH_HELP: &str =
+    "Makes the Daemon write a secret token to the ui-token file in its data directory, readable only by you. \
+     UIs that present the token may use every command; UIs that don't may only use read-only commands such as \
+     descriptor and financials, and UIs that present the wrong token are refused.";
+
 #[derive(Default, Clone, PartialEq, Debug)]
 pub struct InitializationConfig {
     pub ui_port: u16,
+    pub restart_policy: RestartPolicy,
+    pub ui_auth: bool,
 }
 
 pub struct NodeConfiguratorInitialization {}
@@ -53,13 +74,55 @@ pub fn app() -> App<'static, 'static> {
                 .help("Directs MASQ to start the Daemon that controls the Node, rather than the Node itself"),
         )
         .arg(ui_port_arg(&UI_PORT_HELP))
+        .arg(
+            Arg::with_name("restart-policy")
+                .long("restart-policy")
+                .value_name("RESTART-POLICY")
+                .takes_value(true)
+                .possible_values(&["never", "always", "on-failure"])
+                .default_value("never")
+                .help(RESTART_POLICY_HELP),
+        )
+        .arg(
+            Arg::with_name("restart-limit")
+                .long("restart-limit")
+                .value_name("RESTART-LIMIT")
+                .takes_value(true)
+                .default_value(&DEFAULT_RESTART_LIMIT_VALUE)
+                .validator(validate_positive_integer)
+                .help(RESTART_LIMIT_HELP),
+        )
+        .arg(
+            Arg::with_name("restart-window")
+                .long("restart-window")
+                .value_name("SECONDS")
+                .takes_value(true)
+                .default_value(&DEFAULT_RESTART_WINDOW_VALUE)
+                .validator(validate_positive_integer)
+                .help(RESTART_WINDOW_HELP),
+        )
+        .arg(
+            Arg::with_name("ui-auth")
+                .long("ui-auth")
+                .takes_value(false)
+                .help(UI_AUTH_HELP),
+        )
+}
+
+fn validate_positive_integer(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(format!("Must be a positive integer, not '{}'", value)),
+    }
 }
 
 mod initialization {
     use super::*;
+    use crate::daemon::restart_policy::RestartMode;
     use clap::value_t;
     use masq_lib::constants::DEFAULT_UI_PORT;
     use masq_lib::multi_config::MultiConfig;
+    use std::time::Duration;
 
     pub fn parse_args(
         multi_config: &MultiConfig,
@@ -67,17 +130,30 @@ mod initialization {
         _streams: &mut StdStreams<'_>,
     ) {
         config.ui_port = value_m!(multi_config, "ui-port", u16).unwrap_or(DEFAULT_UI_PORT);
+        config.restart_policy = RestartPolicy {
+            mode: value_m!(multi_config, "restart-policy", RestartMode)
+                .unwrap_or(RestartMode::Never),
+            max_restarts: value_m!(multi_config, "restart-limit", u32)
+                .unwrap_or(DEFAULT_RESTART_LIMIT),
+            window: Duration::from_secs(
+                value_m!(multi_config, "restart-window", u64)
+                    .unwrap_or(DEFAULT_RESTART_WINDOW_SECS),
+            ),
+        };
+        config.ui_auth = multi_config.arg_matches().is_present("ui-auth");
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::daemon::restart_policy::RestartMode;
     use crate::sub_lib::utils::make_new_test_multi_config;
     use crate::test_utils::ArgsBuilder;
     use masq_lib::constants::DEFAULT_UI_PORT;
     use masq_lib::multi_config::{CommandLineVcl, VirtualCommandLine};
     use masq_lib::test_utils::fake_stream_holder::FakeStreamHolder;
+    use std::time::Duration;
 
     #[test]
     fn parse_args_creates_configuration_with_defaults() {
@@ -94,13 +170,19 @@ mod tests {
         );
 
         assert_eq!(config.ui_port, DEFAULT_UI_PORT);
+        assert_eq!(config.restart_policy, RestartPolicy::default());
+        assert_eq!(config.ui_auth, false);
     }
 
     #[test]
     fn parse_args_creates_configuration_with_values() {
         let args = ArgsBuilder::new()
             .opt("--initialization")
-            .param("--ui-port", "4321");
+            .param("--ui-port", "4321")
+            .param("--restart-policy", "on-failure")
+            .param("--restart-limit", "3")
+            .param("--restart-window", "120")
+            .opt("--ui-auth");
         let mut config = InitializationConfig::default();
         let vcls: Vec<Box<dyn VirtualCommandLine>> =
             vec![Box::new(CommandLineVcl::new(args.into()))];
@@ -113,5 +195,27 @@ mod tests {
         );
 
         assert_eq!(config.ui_port, 4321);
+        assert_eq!(
+            config.restart_policy,
+            RestartPolicy {
+                mode: RestartMode::OnFailure,
+                max_restarts: 3,
+                window: Duration::from_secs(120),
+            }
+        );
+        assert_eq!(config.ui_auth, true);
+    }
+
+    #[test]
+    fn restart_limit_and_window_must_be_positive_integers() {
+        assert_eq!(validate_positive_integer("1".to_string()), Ok(()));
+        assert_eq!(
+            validate_positive_integer("0".to_string()),
+            Err("Must be a positive integer, not '0'".to_string())
+        );
+        assert_eq!(
+            validate_positive_integer("booga".to_string()),
+            Err("Must be a positive integer, not 'booga'".to_string())
+        );
     }
 }
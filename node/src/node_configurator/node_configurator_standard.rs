@@ -28,13 +28,13 @@ impl NodeConfigurator<BootstrapperConfig> for NodeConfiguratorStandardPrivileged
             streams,
         )?;
         let mut bootstrapper_config = BootstrapperConfig::new();
-        standard::establish_port_configurations(&mut bootstrapper_config);
         standard::privileged_parse_args(
             self.dirs_wrapper.as_ref(),
             &multi_config,
             &mut bootstrapper_config,
             streams,
         )?;
+        standard::establish_port_configurations(&mut bootstrapper_config);
         Ok(bootstrapper_config)
     }
 }
@@ -153,19 +153,28 @@ pub mod standard {
     use crate::db_config::persistent_configuration::{
         PersistentConfigError, PersistentConfiguration,
     };
+    use crate::explicit_proxy_discriminator_factory::ExplicitProxyDiscriminatorFactory;
     use crate::http_request_start_finder::HttpRequestDiscriminatorFactory;
+    use crate::masquerader::MasqueradeKind;
     use crate::node_configurator::{
         data_directory_from_context, determine_config_file_path,
         real_user_data_directory_opt_and_chain_name, request_existing_db_password, DirsWrapper,
     };
+    use crate::opaque_discriminator_factory::OpaqueDiscriminatorFactory;
     use crate::sub_lib::accountant::DEFAULT_EARNING_WALLET;
+    use crate::sub_lib::bandwidth::{BandwidthLimits, ServiceBandwidthLimits};
     use crate::sub_lib::cryptde::{CryptDE, PublicKey};
     use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::cryptde_real::CryptDEReal;
+    use crate::sub_lib::logger::LogFormat;
     use crate::sub_lib::neighborhood::{
         NeighborhoodConfig, NeighborhoodMode, NodeDescriptor, DEFAULT_RATE_PACK,
     };
     use crate::sub_lib::node_addr::NodeAddr;
+    use crate::sub_lib::proxy_client::{
+        DnsProtocol, ExitPorts, DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS, DEFAULT_EXIT_IDLE_TIMEOUT_SECS,
+    };
+    use crate::sub_lib::proxy_server::PortMapping;
     use crate::sub_lib::utils::make_new_multi_config;
     use crate::sub_lib::wallet::Wallet;
     use crate::tls_discriminator_factory::TlsDiscriminatorFactory;
@@ -176,8 +185,11 @@ pub mod standard {
     use masq_lib::multi_config::{CommandLineVcl, ConfigFileVcl, EnvironmentVcl, MultiConfig};
     use masq_lib::shared_schema::{ConfiguratorError, ParamError};
     use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
+    use masq_lib::ui_auth::{read_ui_token, UI_TOKEN_FILE_ENV_VAR};
     use rustc_hex::FromHex;
+    use std::path::Path;
     use std::str::FromStr;
+    use std::time::Duration;
 
     pub fn make_service_mode_multi_config<'a>(
         dirs_wrapper: &dyn DirsWrapper,
@@ -220,6 +232,84 @@ pub mod standard {
                 false,
             ),
         );
+        if let Some(explicit_proxy_port) = config.explicit_proxy_port_opt {
+            config.port_configurations.insert(
+                explicit_proxy_port,
+                PortConfiguration::new(
+                    vec![Box::new(ExplicitProxyDiscriminatorFactory::new())],
+                    false,
+                ),
+            );
+        }
+        for port_mapping in &config.port_mappings {
+            config.port_configurations.insert(
+                port_mapping.local_port,
+                PortConfiguration::new(vec![Box::new(OpaqueDiscriminatorFactory::new())], false),
+            );
+        }
+    }
+
+    // A local port gets one set of discriminators, so a port mapping may not take over the HTTP
+    // or TLS port, the --proxy-port, or the local port of another mapping.
+    fn check_port_mapping_collisions(
+        port_mappings: &[PortMapping],
+        explicit_proxy_port_opt: Option<u16>,
+    ) -> Result<(), ConfiguratorError> {
+        let mut local_ports: Vec<u16> = vec![];
+        for port_mapping in port_mappings {
+            let local_port = port_mapping.local_port;
+            let reason_opt = if local_port == HTTP_PORT || local_port == TLS_PORT {
+                Some(format!(
+                    "Port mapping '{}' can't use local port {}, which is kept for HTTP and TLS",
+                    port_mapping, local_port
+                ))
+            } else if Some(local_port) == explicit_proxy_port_opt {
+                Some(format!(
+                    "Port mapping '{}' can't use local port {}, which is the --proxy-port",
+                    port_mapping, local_port
+                ))
+            } else if local_ports.contains(&local_port) {
+                Some(format!(
+                    "Port mapping '{}' can't use local port {}, which an earlier mapping uses",
+                    port_mapping, local_port
+                ))
+            } else {
+                None
+            };
+            if let Some(reason) = reason_opt {
+                return Err(ConfiguratorError::required("port-mappings", &reason));
+            }
+            local_ports.push(local_port);
+        }
+        Ok(())
+    }
+
+    pub fn dns_bypass_domains(multi_config: &MultiConfig) -> Vec<String> {
+        match value_m!(multi_config, "dns-bypass", String) {
+            Some(joined_domains) => joined_domains
+                .split(',')
+                .map(|domain| domain.trim_end_matches('.').to_lowercase())
+                .filter(|domain| !domain.is_empty())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    // The Daemon passes the location of its UI token file in the environment; a Node started
+    // some other way has no token and lets every UI do everything.
+    pub fn ui_token_opt(
+        token_file_opt: Option<String>,
+    ) -> Result<Option<String>, ConfiguratorError> {
+        match token_file_opt {
+            None => Ok(None),
+            Some(token_file) => match read_ui_token(Path::new(&token_file)) {
+                Ok(token) => Ok(Some(token)),
+                Err(e) => Err(ConfiguratorError::required(
+                    "ui-token-file",
+                    &format!("Couldn't read UI token from {}: {}", token_file, e),
+                )),
+            },
+        }
     }
 
     pub fn privileged_parse_args(
@@ -240,6 +330,9 @@ pub mod standard {
         privileged_config.data_directory = directory;
         privileged_config.blockchain_bridge_config.chain_id = chain_id_from_name(&chain_name);
 
+        privileged_config.dns_protocol =
+            value_m!(multi_config, "dns-protocol", DnsProtocol).unwrap_or_default();
+        let dns_port = privileged_config.dns_protocol.default_port();
         let joined_dns_servers_opt = value_m!(multi_config, "dns-servers", String);
         privileged_config.dns_servers = match joined_dns_servers_opt {
             Some(joined_dns_servers) => joined_dns_servers
@@ -247,18 +340,66 @@ pub mod standard {
                 .map(|ip_str| {
                     SocketAddr::new(
                         IpAddr::from_str(ip_str).expect("Bad clap validation for dns-servers"),
-                        53,
+                        dns_port,
                     )
                 })
                 .collect(),
-            None => vec![SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)), 53)],
+            None => vec![SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+                dns_port,
+            )],
         };
 
         privileged_config.log_level =
             value_m!(multi_config, "log-level", LevelFilter).unwrap_or(LevelFilter::Warn);
 
+        privileged_config.log_format =
+            value_m!(multi_config, "log-format", LogFormat).unwrap_or_default();
+
         privileged_config.ui_gateway_config.ui_port =
             value_m!(multi_config, "ui-port", u16).unwrap_or(DEFAULT_UI_PORT);
+        privileged_config.ui_gateway_config.ui_token_opt =
+            ui_token_opt(std::env::var(UI_TOKEN_FILE_ENV_VAR).ok())?;
+
+        privileged_config.masquerade =
+            value_m!(multi_config, "masquerade", MasqueradeKind).unwrap_or_default();
+
+        privileged_config.explicit_proxy_port_opt = value_m!(multi_config, "proxy-port", u16);
+
+        privileged_config.metrics_port_opt = value_m!(multi_config, "metrics-port", u16);
+
+        privileged_config.exit_idle_timeout = Duration::from_secs(
+            value_m!(multi_config, "exit-idle-timeout", u64)
+                .unwrap_or(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+        );
+        privileged_config.exit_absolute_timeout = Duration::from_secs(
+            value_m!(multi_config, "exit-absolute-timeout", u64)
+                .unwrap_or(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+        );
+        privileged_config.exit_ports_opt = value_m!(multi_config, "exit-ports", ExitPorts);
+        // Only for test networks whose servers live on private addresses.
+        privileged_config.local_destinations_allowed =
+            value_m!(multi_config, "allow-local-destinations", bool).unwrap_or(false);
+
+        privileged_config.bandwidth_limits = BandwidthLimits {
+            routing: value_m!(multi_config, "routing-bandwidth", ServiceBandwidthLimits)
+                .unwrap_or_default(),
+            exit: value_m!(multi_config, "exit-bandwidth", ServiceBandwidthLimits)
+                .unwrap_or_default(),
+        };
+
+        privileged_config.port_mappings = match value_m!(multi_config, "port-mappings", String) {
+            Some(joined_port_mappings) => joined_port_mappings
+                .split(',')
+                .map(PortMapping::from_str)
+                .collect::<Result<Vec<PortMapping>, String>>()
+                .map_err(|e| ConfiguratorError::required("port-mappings", &e))?,
+            None => vec![],
+        };
+        check_port_mapping_collisions(
+            &privileged_config.port_mappings,
+            privileged_config.explicit_proxy_port_opt,
+        )?;
 
         privileged_config.crash_point =
             value_m!(multi_config, "crash-point", CrashPoint).unwrap_or(CrashPoint::None);
@@ -577,7 +718,7 @@ pub mod standard {
             Some(ref s) if s == "zero-hop" => {
                 if !neighbor_configs.is_empty() {
                     Err(ConfiguratorError::required("neighborhood-mode", "Node cannot run as --neighborhood-mode zero-hop if --neighbors is specified"))
-                } else if value_m!(multi_config, "ip", IpAddr).is_some() {
+                } else if value_m!(multi_config, "ip", String).is_some() {
                     Err(ConfiguratorError::required(
                         "neighborhood-mode",
                         "Node cannot run as --neighborhood-mode zero-hop if --ip is specified",
@@ -599,8 +740,11 @@ pub mod standard {
         multi_config: &MultiConfig,
         neighbor_configs: Vec<NodeDescriptor>,
     ) -> Result<NeighborhoodMode, ConfiguratorError> {
-        let ip = match value_m!(multi_config, "ip", IpAddr) {
-            Some(ip) => ip,
+        let ip_addrs = match value_m!(multi_config, "ip", String) {
+            Some(ips) => ips
+                .split(',')
+                .map(|ip| IpAddr::from_str(ip).expect("--ip not properly constrained by clap"))
+                .collect::<Vec<IpAddr>>(),
             None => {
                 return Err(ConfiguratorError::required(
                     "neighborhood-mode",
@@ -608,8 +752,12 @@ pub mod standard {
                 ))
             }
         };
+        let node_addr = match ip_addrs.get(1) {
+            Some(alternate) => NodeAddr::new(&ip_addrs[0], &[]).with_alternate_ip_addr(alternate),
+            None => NodeAddr::new(&ip_addrs[0], &[]),
+        };
         Ok(NeighborhoodMode::Standard(
-            NodeAddr::new(&ip, &[]),
+            node_addr,
             neighbor_configs,
             DEFAULT_RATE_PACK,
         ))
@@ -1108,15 +1256,22 @@ mod tests {
     use crate::db_config::persistent_configuration::{
         PersistentConfigError, PersistentConfigurationReal,
     };
+    use crate::masquerader::MasqueradeKind;
     use crate::node_configurator::RealDirsWrapper;
     use crate::sub_lib::accountant::DEFAULT_EARNING_WALLET;
+    use crate::sub_lib::bandwidth::{BandwidthLimits, ServiceBandwidthLimits};
     use crate::sub_lib::cryptde::{CryptDE, PlainData, PublicKey};
     use crate::sub_lib::cryptde_null::CryptDENull;
     use crate::sub_lib::cryptde_real::CryptDEReal;
+    use crate::sub_lib::logger::LogFormat;
     use crate::sub_lib::neighborhood::{
         NeighborhoodConfig, NeighborhoodMode, NodeDescriptor, DEFAULT_RATE_PACK,
     };
     use crate::sub_lib::node_addr::NodeAddr;
+    use crate::sub_lib::proxy_client::{
+        DnsProtocol, ExitPorts, DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS, DEFAULT_EXIT_IDLE_TIMEOUT_SECS,
+    };
+    use crate::sub_lib::proxy_server::PortMapping;
     use crate::sub_lib::utils::make_new_test_multi_config;
     use crate::sub_lib::wallet::Wallet;
     use crate::test_utils;
@@ -1143,11 +1298,46 @@ mod tests {
     use std::path::PathBuf;
     use std::str::FromStr;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
 
     fn make_default_cli_params() -> ArgsBuilder {
         ArgsBuilder::new().param("--ip", "1.2.3.4")
     }
 
+    #[test]
+    fn make_neighborhood_config_standard_accepts_an_ip_address_of_each_family() {
+        running_test();
+        let multi_config = make_new_test_multi_config(
+            &app(),
+            vec![Box::new(CommandLineVcl::new(
+                ArgsBuilder::new()
+                    .param("--neighborhood-mode", "standard")
+                    .param("--ip", "1.2.3.4,2001:db8::5")
+                    .into(),
+            ))],
+        )
+        .unwrap();
+
+        let result = standard::make_neighborhood_config(
+            &multi_config,
+            &mut FakeStreamHolder::new().streams(),
+            Some(&mut make_default_persistent_configuration()),
+            &mut BootstrapperConfig::new(),
+        );
+
+        assert_eq!(
+            result,
+            Ok(NeighborhoodConfig {
+                mode: NeighborhoodMode::Standard(
+                    NodeAddr::new(&IpAddr::from_str("1.2.3.4").unwrap(), &[])
+                        .with_alternate_ip_addr(&IpAddr::from_str("2001:db8::5").unwrap()),
+                    vec![],
+                    DEFAULT_RATE_PACK
+                )
+            })
+        );
+    }
+
     #[test]
     fn make_neighborhood_config_standard_happy_path() {
         running_test();
@@ -2629,6 +2819,552 @@ mod tests {
         assert_eq!(config.crash_point, CrashPoint::Panic);
     }
 
+    #[test]
+    fn no_parameters_produces_configuration_for_text_log_format() {
+        running_test();
+        let args = make_default_cli_params();
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.log_format, LogFormat::Text);
+    }
+
+    #[test]
+    fn with_parameters_produces_configuration_for_log_format() {
+        running_test();
+        let args = make_default_cli_params().param("--log-format", "JSON");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn no_parameters_produces_configuration_for_json_masquerade() {
+        running_test();
+        let args = make_default_cli_params();
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.masquerade, MasqueradeKind::Json);
+    }
+
+    #[test]
+    fn with_parameters_produces_configuration_for_masquerade() {
+        running_test();
+        let args = make_default_cli_params().param("--masquerade", "TLS");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.masquerade, MasqueradeKind::Tls);
+    }
+
+    #[test]
+    fn proxy_port_produces_explicit_proxy_port_configuration() {
+        running_test();
+        let args = make_default_cli_params().param("--proxy-port", "8080");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+        standard::establish_port_configurations(&mut config);
+
+        assert_eq!(config.explicit_proxy_port_opt, Some(8080));
+        let port_configuration = config.port_configurations.get(&8080).unwrap();
+        assert_eq!(port_configuration.is_clandestine, false);
+        assert_eq!(port_configuration.discriminator_factories.len(), 1);
+        assert_eq!(config.port_configurations.len(), 3);
+    }
+
+    #[test]
+    fn metrics_port_is_absent_unless_specified() {
+        running_test();
+        let args = make_default_cli_params();
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.metrics_port_opt, None);
+    }
+
+    #[test]
+    fn metrics_port_is_parsed() {
+        running_test();
+        let args = make_default_cli_params().param("--metrics-port", "9333");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.metrics_port_opt, Some(9333));
+    }
+
+    #[test]
+    fn exit_timeouts_are_parsed_in_seconds() {
+        running_test();
+        let args = make_default_cli_params()
+            .param("--exit-idle-timeout", "120")
+            .param("--exit-absolute-timeout", "7200");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.exit_idle_timeout, Duration::from_secs(120));
+        assert_eq!(config.exit_absolute_timeout, Duration::from_secs(7200));
+    }
+
+    #[test]
+    fn exit_ports_are_parsed_into_an_allowlist() {
+        running_test();
+        let args = make_default_cli_params().param("--exit-ports", "22,993,8000-8100");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.exit_ports_opt,
+            Some(ExitPorts::from_str("22,993,8000-8100").unwrap())
+        );
+    }
+
+    #[test]
+    fn local_destinations_can_be_allowed_for_test_networks() {
+        running_test();
+        let args = make_default_cli_params().param("--allow-local-destinations", "true");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.local_destinations_allowed, true);
+    }
+
+    #[test]
+    fn bandwidth_limits_are_parsed_in_kilobytes_per_second() {
+        running_test();
+        let args = make_default_cli_params()
+            .param("--routing-bandwidth", "2048:1024:256:128")
+            .param("--exit-bandwidth", "0:512:0:64");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.bandwidth_limits,
+            BandwidthLimits {
+                routing: ServiceBandwidthLimits {
+                    download: 2048,
+                    upload: 1024,
+                    neighbor_download: 256,
+                    neighbor_upload: 128,
+                },
+                exit: ServiceBandwidthLimits {
+                    download: 0,
+                    upload: 512,
+                    neighbor_download: 0,
+                    neighbor_upload: 64,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn bandwidth_limits_are_unlimited_when_not_specified() {
+        running_test();
+        let args = make_default_cli_params();
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.bandwidth_limits, BandwidthLimits::default());
+    }
+
+    #[test]
+    fn exit_timeouts_default_when_not_specified() {
+        running_test();
+        let args = make_default_cli_params();
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.exit_idle_timeout,
+            Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS)
+        );
+        assert_eq!(
+            config.exit_absolute_timeout,
+            Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS)
+        );
+        assert_eq!(config.exit_ports_opt, None);
+        assert_eq!(config.local_destinations_allowed, false);
+    }
+
+    #[test]
+    fn dns_protocol_is_parsed_and_picks_the_port_for_dns_servers() {
+        running_test();
+        let args = make_default_cli_params()
+            .param("--dns-servers", "1.1.1.1,1.0.0.1")
+            .param("--dns-protocol", "tls:cloudflare-dns.com");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.dns_protocol,
+            DnsProtocol::Tls("cloudflare-dns.com".to_string())
+        );
+        assert_eq!(
+            config.dns_servers,
+            vec![
+                SocketAddr::from_str("1.1.1.1:853").unwrap(),
+                SocketAddr::from_str("1.0.0.1:853").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn dns_bypass_domains_are_normalized() {
+        running_test();
+        let args =
+            make_default_cli_params().param("--dns-bypass", "LAN,home.arpa.,Printer.Example.com");
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        let result = standard::dns_bypass_domains(&multi_config);
+
+        assert_eq!(
+            result,
+            vec![
+                "lan".to_string(),
+                "home.arpa".to_string(),
+                "printer.example.com".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn dns_bypass_domains_default_to_none() {
+        running_test();
+        let args = make_default_cli_params();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        let result = standard::dns_bypass_domains(&multi_config);
+
+        assert_eq!(result, Vec::<String>::new());
+    }
+
+    #[test]
+    fn ui_token_opt_is_absent_without_a_token_file() {
+        let result = standard::ui_token_opt(None);
+
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn ui_token_opt_is_read_from_the_token_file() {
+        let home_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "ui_token_opt_is_read_from_the_token_file",
+        );
+        let token_file = home_dir.join("ui-token");
+        std::fs::write(&token_file, "0123456789abcdef\n").unwrap();
+
+        let result = standard::ui_token_opt(Some(token_file.to_string_lossy().to_string()));
+
+        assert_eq!(result, Ok(Some("0123456789abcdef".to_string())));
+    }
+
+    #[test]
+    fn ui_token_opt_complains_about_an_unreadable_token_file() {
+        let home_dir = ensure_node_home_directory_exists(
+            "node_configurator_standard",
+            "ui_token_opt_complains_about_an_unreadable_token_file",
+        );
+        let token_file = home_dir.join("nonexistent").to_string_lossy().to_string();
+
+        let result = standard::ui_token_opt(Some(token_file.clone()));
+
+        match result {
+            Err(ConfiguratorError { param_errors }) => {
+                assert_eq!(param_errors.len(), 1);
+                assert_eq!(param_errors[0].parameter, "ui-token-file".to_string());
+                assert_string_contains(
+                    &param_errors[0].reason,
+                    &format!("Couldn't read UI token from {}: ", token_file),
+                );
+            }
+            x => panic!("Expected ConfiguratorError, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn dns_protocol_defaults_to_udp() {
+        running_test();
+        let args = make_default_cli_params();
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+
+        assert_eq!(config.dns_protocol, DnsProtocol::Udp);
+        assert_eq!(config.dns_servers[0].port(), 53);
+    }
+
+    #[test]
+    fn port_mappings_produce_opaque_port_configurations() {
+        running_test();
+        let args = make_default_cli_params().param(
+            "--port-mappings",
+            "2222:ssh.example.com:22,9993:imap.example.com:993",
+        );
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        )
+        .unwrap();
+        standard::establish_port_configurations(&mut config);
+
+        assert_eq!(
+            config.port_mappings,
+            vec![
+                PortMapping {
+                    local_port: 2222,
+                    target_hostname: "ssh.example.com".to_string(),
+                    target_port: 22,
+                },
+                PortMapping {
+                    local_port: 9993,
+                    target_hostname: "imap.example.com".to_string(),
+                    target_port: 993,
+                },
+            ]
+        );
+        assert_eq!(
+            config
+                .port_configurations
+                .get(&2222)
+                .unwrap()
+                .is_clandestine,
+            false
+        );
+        assert_eq!(
+            config
+                .port_configurations
+                .get(&9993)
+                .unwrap()
+                .is_clandestine,
+            false
+        );
+        assert_eq!(config.port_configurations.len(), 4);
+    }
+
+    #[test]
+    fn port_mappings_may_not_take_the_http_or_tls_port() {
+        running_test();
+        let args = make_default_cli_params().param(
+            "--port-mappings",
+            "2222:ssh.example.com:22,443:imap.example.com:993",
+        );
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        let result = standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        );
+
+        assert_eq!(
+            result,
+            Err(ConfiguratorError::required(
+                "port-mappings",
+                "Port mapping '443:imap.example.com:993' can't use local port 443, which is kept for HTTP and TLS"
+            ))
+        );
+    }
+
+    #[test]
+    fn port_mappings_may_not_take_the_proxy_port() {
+        running_test();
+        let args = make_default_cli_params()
+            .param("--port-mappings", "8080:web.example.com:8080")
+            .param("--proxy-port", "8080");
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        let result = standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        );
+
+        assert_eq!(
+            result,
+            Err(ConfiguratorError::required(
+                "port-mappings",
+                "Port mapping '8080:web.example.com:8080' can't use local port 8080, which is the --proxy-port"
+            ))
+        );
+    }
+
+    #[test]
+    fn port_mappings_may_not_share_a_local_port() {
+        running_test();
+        let args = make_default_cli_params().param(
+            "--port-mappings",
+            "2222:ssh.example.com:22,2222:imap.example.com:993",
+        );
+        let mut config = BootstrapperConfig::new();
+        let vcl = Box::new(CommandLineVcl::new(args.into()));
+        let multi_config = make_new_test_multi_config(&app(), vec![vcl]).unwrap();
+
+        let result = standard::privileged_parse_args(
+            &RealDirsWrapper {},
+            &multi_config,
+            &mut config,
+            &mut FakeStreamHolder::new().streams(),
+        );
+
+        assert_eq!(
+            result,
+            Err(ConfiguratorError::required(
+                "port-mappings",
+                "Port mapping '2222:imap.example.com:993' can't use local port 2222, which an earlier mapping uses"
+            ))
+        );
+    }
+
     #[test]
     fn privileged_generate_configuration_senses_when_user_specifies_config_file() {
         running_test();
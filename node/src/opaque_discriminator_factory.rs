@@ -0,0 +1,89 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::Discriminator;
+use crate::discriminator::DiscriminatorFactory;
+use crate::null_masquerader::NullMasquerader;
+use crate::sub_lib::framer::FramedChunk;
+use crate::sub_lib::framer::Framer;
+
+// Frames whatever data it has, exactly as it arrived, without trying to find any structure in it.
+#[derive(Default)]
+pub struct OpaqueFramer {
+    data_so_far: Vec<u8>,
+}
+
+impl Framer for OpaqueFramer {
+    fn add_data(&mut self, data: &[u8]) {
+        self.data_so_far.extend_from_slice(data);
+    }
+
+    fn take_frame(&mut self) -> Option<FramedChunk> {
+        if self.data_so_far.is_empty() {
+            return None;
+        }
+        Some(FramedChunk {
+            chunk: self.data_so_far.split_off(0),
+            last_chunk: true,
+        })
+    }
+}
+
+impl OpaqueFramer {
+    pub fn new() -> OpaqueFramer {
+        Default::default()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OpaqueDiscriminatorFactory {}
+
+impl DiscriminatorFactory for OpaqueDiscriminatorFactory {
+    fn make(&self) -> Discriminator {
+        Discriminator::new(
+            Box::new(OpaqueFramer::new()),
+            vec![Box::new(NullMasquerader::new())],
+        )
+    }
+
+    fn duplicate(&self) -> Box<dyn DiscriminatorFactory> {
+        Box::new(OpaqueDiscriminatorFactory {})
+    }
+}
+
+impl OpaqueDiscriminatorFactory {
+    pub fn new() -> OpaqueDiscriminatorFactory {
+        Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discriminator::UnmaskedChunk;
+
+    #[test]
+    fn discriminator_factory_duplicate_works() {
+        let subject = OpaqueDiscriminatorFactory::new();
+
+        subject.duplicate();
+
+        // no panic; test passes
+    }
+
+    #[test]
+    fn frames_everything_it_has_been_given() {
+        let mut subject = OpaqueDiscriminatorFactory::new().make();
+
+        subject.add_data(b"SSH-2.0-");
+        subject.add_data(b"OpenSSH_8.2p1\r\n");
+
+        assert_eq!(
+            subject.take_chunk(),
+            Some(UnmaskedChunk::new(
+                b"SSH-2.0-OpenSSH_8.2p1\r\n".to_vec(),
+                true,
+                true
+            ))
+        );
+        assert_eq!(subject.take_chunk(), None);
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+use trust_dns_resolver::error::{ResolveError, ResolveErrorKind};
+use trust_dns_resolver::lookup_ip::LookupIp;
+
+pub const DNS_CACHE_CAPACITY: usize = 4096;
+pub const NEGATIVE_DNS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+pub type DnsLookupResult = Result<LookupIp, ResolveError>;
+
+// Remembers answers from the exit resolver until their TTLs run out, so that a busy exit Node
+// doesn't ask its DNS servers about the same host for every stream. Answers that say a host
+// doesn't exist are remembered too; failures to get an answer at all are not.
+pub struct DnsCache {
+    entries: HashMap<String, (DnsLookupResult, Instant)>,
+    last_check: Instant,
+    capacity: usize,
+    negative_ttl: Duration,
+}
+
+impl DnsCache {
+    pub fn new(capacity: usize, negative_ttl: Duration) -> DnsCache {
+        DnsCache {
+            entries: HashMap::new(),
+            last_check: Instant::now(),
+            capacity,
+            negative_ttl,
+        }
+    }
+
+    pub fn get(&mut self, host: &str, now: Instant) -> Option<DnsLookupResult> {
+        self.remove_expired_entries(now);
+        let key = Self::key(host);
+        match self.entries.get(&key) {
+            Some((result, valid_until)) if *valid_until > now => Some(result.clone()),
+            Some(_) => {
+                self.entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&mut self, host: &str, result: &DnsLookupResult, now: Instant) {
+        let valid_until = match result {
+            Ok(lookup_ip) => lookup_ip.valid_until(),
+            Err(e) => match e.kind() {
+                ResolveErrorKind::NoRecordsFound { valid_until, .. } => {
+                    valid_until.unwrap_or(now + self.negative_ttl)
+                }
+                _ => return,
+            },
+        };
+        if valid_until <= now {
+            return;
+        }
+        let key = Self::key(host);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.make_room(now);
+        }
+        self.entries.insert(key, (result.clone(), valid_until));
+    }
+
+    fn key(host: &str) -> String {
+        host.trim_end_matches('.').to_lowercase()
+    }
+
+    fn make_room(&mut self, now: Instant) {
+        self.last_check = now;
+        self.entries
+            .retain(|_, (_, valid_until)| *valid_until > now);
+        if self.entries.len() < self.capacity {
+            return;
+        }
+        let soonest_expiring_opt = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (_, valid_until))| *valid_until)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = soonest_expiring_opt {
+            self.entries.remove(&key);
+        }
+    }
+
+    fn remove_expired_entries(&mut self, now: Instant) {
+        if now.duration_since(self.last_check) < self.negative_ttl {
+            return;
+        }
+        self.last_check = now;
+        self.entries
+            .retain(|_, (_, valid_until)| *valid_until > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::IpAddr;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use trust_dns::rr::{Name, Record};
+    use trust_dns_proto::op::Query;
+    use trust_dns_resolver::lookup::Lookup;
+    use trust_dns_resolver::proto::rr::RData;
+
+    fn make_lookup_ip(ip_str: &str, valid_until: Instant) -> LookupIp {
+        let ip_addr = match IpAddr::from_str(ip_str).unwrap() {
+            IpAddr::V4(ip_addr) => ip_addr,
+            IpAddr::V6(_) => panic!("IPv4 only, please"),
+        };
+        let record = Record::from_rdata(Name::from(ip_addr), 60, RData::A(ip_addr));
+        Lookup::new_with_deadline(Query::default(), Arc::new(vec![record]), valid_until).into()
+    }
+
+    fn ips_of(result: Option<DnsLookupResult>) -> Vec<IpAddr> {
+        result.unwrap().unwrap().iter().collect()
+    }
+
+    fn no_records_found(valid_until: Option<Instant>) -> DnsLookupResult {
+        Err(ResolveErrorKind::NoRecordsFound {
+            query: Query::default(),
+            valid_until,
+        }
+        .into())
+    }
+
+    #[test]
+    fn positive_answers_are_served_until_their_ttls_run_out() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(10, NEGATIVE_DNS_CACHE_TTL);
+
+        subject.insert(
+            "booga.com",
+            &Ok(make_lookup_ip("1.2.3.4", now + Duration::from_secs(60))),
+            now,
+        );
+
+        assert_eq!(
+            ips_of(subject.get("booga.com", now + Duration::from_secs(59))),
+            vec![IpAddr::from_str("1.2.3.4").unwrap()]
+        );
+        assert_eq!(
+            subject
+                .get("booga.com", now + Duration::from_secs(60))
+                .is_none(),
+            true
+        );
+        assert_eq!(subject.entries.len(), 0);
+    }
+
+    #[test]
+    fn host_names_are_compared_without_case_or_trailing_dot() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(10, NEGATIVE_DNS_CACHE_TTL);
+
+        subject.insert(
+            "Booga.COM.",
+            &Ok(make_lookup_ip("1.2.3.4", now + Duration::from_secs(60))),
+            now,
+        );
+
+        assert_eq!(
+            ips_of(subject.get("booga.com", now)),
+            vec![IpAddr::from_str("1.2.3.4").unwrap()]
+        );
+    }
+
+    #[test]
+    fn negative_answers_use_their_own_ttl_or_the_negative_ttl() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(10, Duration::from_secs(30));
+
+        subject.insert(
+            "with-soa.com",
+            &no_records_found(Some(now + Duration::from_secs(120))),
+            now,
+        );
+        subject.insert("without-soa.com", &no_records_found(None), now);
+
+        let later = now + Duration::from_secs(29);
+        assert_eq!(subject.get("with-soa.com", later).unwrap().is_err(), true);
+        assert_eq!(
+            subject.get("without-soa.com", later).unwrap().is_err(),
+            true
+        );
+        let much_later = now + Duration::from_secs(31);
+        assert_eq!(subject.get("with-soa.com", much_later).is_some(), true);
+        assert_eq!(subject.get("without-soa.com", much_later).is_none(), true);
+    }
+
+    #[test]
+    fn failures_that_are_not_answers_are_not_cached() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(10, NEGATIVE_DNS_CACHE_TTL);
+
+        subject.insert("booga.com", &Err(ResolveErrorKind::Timeout.into()), now);
+        subject.insert(
+            "agoob.com",
+            &Err(ResolveErrorKind::Message("booga").into()),
+            now,
+        );
+
+        assert_eq!(subject.entries.len(), 0);
+    }
+
+    #[test]
+    fn answers_that_are_already_stale_are_not_cached() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(10, NEGATIVE_DNS_CACHE_TTL);
+
+        subject.insert("booga.com", &Ok(make_lookup_ip("1.2.3.4", now)), now);
+
+        assert_eq!(subject.entries.len(), 0);
+    }
+
+    #[test]
+    fn full_cache_evicts_expired_entries_before_live_ones() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(2, NEGATIVE_DNS_CACHE_TTL);
+        subject.insert(
+            "short.com",
+            &Ok(make_lookup_ip("1.1.1.1", now + Duration::from_secs(5))),
+            now,
+        );
+        subject.insert(
+            "long.com",
+            &Ok(make_lookup_ip("2.2.2.2", now + Duration::from_secs(500))),
+            now,
+        );
+        let later = now + Duration::from_secs(10);
+
+        subject.insert(
+            "new.com",
+            &Ok(make_lookup_ip("3.3.3.3", later + Duration::from_secs(60))),
+            later,
+        );
+
+        assert_eq!(subject.entries.len(), 2);
+        assert_eq!(subject.get("long.com", later).is_some(), true);
+        assert_eq!(subject.get("new.com", later).is_some(), true);
+    }
+
+    #[test]
+    fn full_cache_of_live_entries_evicts_the_one_expiring_soonest() {
+        let now = Instant::now();
+        let mut subject = DnsCache::new(2, NEGATIVE_DNS_CACHE_TTL);
+        subject.insert(
+            "short.com",
+            &Ok(make_lookup_ip("1.1.1.1", now + Duration::from_secs(50))),
+            now,
+        );
+        subject.insert(
+            "long.com",
+            &Ok(make_lookup_ip("2.2.2.2", now + Duration::from_secs(500))),
+            now,
+        );
+
+        subject.insert(
+            "new.com",
+            &Ok(make_lookup_ip("3.3.3.3", now + Duration::from_secs(60))),
+            now,
+        );
+
+        assert_eq!(subject.entries.len(), 2);
+        assert_eq!(subject.get("short.com", now).is_none(), true);
+        assert_eq!(subject.get("long.com", now).is_some(), true);
+        assert_eq!(subject.get("new.com", now).is_some(), true);
+    }
+}
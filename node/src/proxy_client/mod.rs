@@ -1,5 +1,6 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
+mod dns_cache;
 #[cfg(test)]
 mod local_test_utils;
 mod resolver_wrapper;
@@ -8,55 +9,87 @@ mod stream_handler_pool;
 mod stream_reader;
 mod stream_writer;
 
+use crate::proxy_client::dns_cache::{DNS_CACHE_CAPACITY, NEGATIVE_DNS_CACHE_TTL};
+use crate::proxy_client::resolver_wrapper::CachingResolverWrapper;
 use crate::proxy_client::resolver_wrapper::ResolverWrapperFactory;
 use crate::proxy_client::resolver_wrapper::ResolverWrapperFactoryReal;
 use crate::proxy_client::stream_handler_pool::StreamHandlerPool;
 use crate::proxy_client::stream_handler_pool::StreamHandlerPoolFactory;
 use crate::proxy_client::stream_handler_pool::StreamHandlerPoolFactoryReal;
 use crate::sub_lib::accountant::ReportExitServiceProvidedMessage;
+use crate::sub_lib::bandwidth::{
+    bandwidth_throttle, BandwidthService, BandwidthThrottle, Traffic, MAX_BANDWIDTH_DELAY,
+};
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::cryptde::PublicKey;
 use crate::sub_lib::hopper::MessageType;
 use crate::sub_lib::hopper::{ExpiredCoresPackage, IncipientCoresPackage};
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::proxy_client::InboundServerData;
+use crate::sub_lib::proxy_client::DnsProtocol;
+use crate::sub_lib::proxy_client::ExitPorts;
 use crate::sub_lib::proxy_client::ProxyClientConfig;
 use crate::sub_lib::proxy_client::ProxyClientSubs;
+use crate::sub_lib::proxy_client::StreamPressure_0v1;
+use crate::sub_lib::proxy_client::{error_socket_addr, InboundServerData};
 use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
+use crate::sub_lib::proxy_server::ProxyProtocol;
 use crate::sub_lib::route::Route;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
+use crate::sub_lib::stream_buffer::{PauseReason, Pressure};
 use crate::sub_lib::stream_key::StreamKey;
 use crate::sub_lib::utils::NODE_MAILBOX_CAPACITY;
 use crate::sub_lib::versioned_data::VersionedData;
 use crate::sub_lib::wallet::Wallet;
 use actix::Actor;
 use actix::Addr;
+use actix::AsyncContext;
 use actix::Context;
 use actix::Handler;
 use actix::Recipient;
+use masq_lib::messages::{FromMessageBody, ToMessageBody};
+use masq_lib::messages::{UiExitStreamStatsRequest, UiExitStreamStatsResponse};
+use masq_lib::ui_gateway::MessageTarget::ClientId;
+use masq_lib::ui_gateway::{NodeFromUiMessage, NodeToUiMessage};
 use pretty_hex::PrettyHex;
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use trust_dns_resolver::config::LookupIpStrategy;
 use trust_dns_resolver::config::NameServerConfig;
 use trust_dns_resolver::config::Protocol;
 use trust_dns_resolver::config::ResolverConfig;
 use trust_dns_resolver::config::ResolverOpts;
 
 pub const CRASH_KEY: &str = "PROXYCLIENT";
+pub const STREAM_REAPING_INTERVAL: Duration = Duration::from_secs(10);
 
 pub struct ProxyClient {
     dns_servers: Vec<SocketAddr>,
+    dns_protocol: DnsProtocol,
     resolver_wrapper_factory: Box<dyn ResolverWrapperFactory>,
     stream_handler_pool_factory: Box<dyn StreamHandlerPoolFactory>,
     cryptde: &'static dyn CryptDE,
     to_hopper: Option<Recipient<IncipientCoresPackage>>,
     to_accountant: Option<Recipient<ReportExitServiceProvidedMessage>>,
+    to_ui_message_sub: Option<Recipient<NodeToUiMessage>>,
     pool: Option<Box<dyn StreamHandlerPool>>,
     stream_contexts: HashMap<StreamKey, StreamContext>,
+    bandwidth_throttle: Arc<BandwidthThrottle>,
+    bandwidth_pauses: HashMap<StreamKey, Instant>,
+    originator_pauses: HashMap<StreamKey, Instant>,
+    max_bandwidth_delay: Duration,
     exit_service_rate: u64,
     exit_byte_rate: u64,
+    exit_idle_timeout: Duration,
+    exit_absolute_timeout: Duration,
+    exit_ports_opt: Option<ExitPorts>,
+    local_destinations_allowed: bool,
+    stream_reaping_interval: Duration,
+    idle_streams_reaped: u64,
+    expired_streams_reaped: u64,
     logger: Logger,
 }
 
@@ -72,17 +105,34 @@ impl Handler<BindMessage> for ProxyClient {
         ctx.set_mailbox_capacity(NODE_MAILBOX_CAPACITY);
         self.to_hopper = Some(msg.peer_actors.hopper.from_hopper_client);
         self.to_accountant = Some(msg.peer_actors.accountant.report_exit_service_provided);
+        self.to_ui_message_sub = Some(msg.peer_actors.ui_gateway.node_to_ui_message_sub);
+        let (protocol, tls_dns_name) = match &self.dns_protocol {
+            DnsProtocol::Udp => (Protocol::Udp, None),
+            DnsProtocol::Tls(tls_dns_name) => (Protocol::Tls, Some(tls_dns_name.clone())),
+            DnsProtocol::Https(tls_dns_name) => (Protocol::Https, Some(tls_dns_name.clone())),
+        };
         let mut config = ResolverConfig::new();
         for dns_server_ref in &self.dns_servers {
-            info!(self.logger, "Adding DNS server: {}", dns_server_ref.ip());
+            info!(
+                self.logger,
+                "Adding DNS server: {} ({})",
+                dns_server_ref.ip(),
+                self.dns_protocol
+            );
             config.add_name_server(NameServerConfig {
                 socket_addr: *dns_server_ref,
-                protocol: Protocol::Udp,
-                tls_dns_name: None,
+                protocol,
+                tls_dns_name: tls_dns_name.clone(),
             })
         }
-        let opts = ResolverOpts::default();
-        let resolver = self.resolver_wrapper_factory.make(config, opts);
+        // Ask for both A and AAAA records, so that IPv6-only targets can be reached.
+        let mut opts = ResolverOpts::default();
+        opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+        let resolver = Box::new(CachingResolverWrapper::new(
+            self.resolver_wrapper_factory.make(config, opts),
+            DNS_CACHE_CAPACITY,
+            NEGATIVE_DNS_CACHE_TTL,
+        ));
         self.pool = Some(self.stream_handler_pool_factory.make(
             resolver,
             self.cryptde,
@@ -90,7 +140,11 @@ impl Handler<BindMessage> for ProxyClient {
             msg.peer_actors.proxy_client,
             self.exit_service_rate,
             self.exit_byte_rate,
+            self.local_destinations_allowed,
         ));
+        ctx.run_interval(self.stream_reaping_interval, |proxy_client, _ctx| {
+            proxy_client.reap_streams(Instant::now());
+        });
     }
 }
 
@@ -100,42 +154,52 @@ impl Handler<ExpiredCoresPackage<ClientRequestPayload_0v1>> for ProxyClient {
     fn handle(
         &mut self,
         msg: ExpiredCoresPackage<ClientRequestPayload_0v1>,
-        _ctx: &mut Self::Context,
+        ctx: &mut Self::Context,
     ) -> Self::Result {
         let is_zero_hop = match msg.remaining_route.next_hop(self.cryptde) {
             Ok(live_hop) => &live_hop.public_key == self.cryptde.public_key(),
             Err(_) => false,
         };
-        let payload = msg.payload;
-        let paying_wallet = msg.paying_wallet;
-        if paying_wallet.is_some() || is_zero_hop {
-            let pool = self.pool.as_mut().expect("StreamHandlerPool unbound");
-            let return_route = msg.remaining_route;
-            let latest_stream_context = StreamContext {
-                return_route,
-                payload_destination_key: payload.originator_public_key.clone(),
-                paying_wallet: paying_wallet.clone(),
-            };
-            debug!(
-                self.logger,
-                "Received ClientRequestPayload: stream {}, sequence {}, length {}",
-                payload.stream_key,
-                payload.sequenced_packet.sequence_number,
-                payload.sequenced_packet.data.len()
-            );
-            self.stream_contexts
-                .insert(payload.stream_key, latest_stream_context);
-            pool.process_package(payload, paying_wallet);
-        } else {
-            warning!(self.logger, "Refusing to provide exit services for CORES package with {}-byte payload without paying wallet", payload.sequenced_packet.data.len());
+        if msg.paying_wallet.is_none() && !is_zero_hop {
+            warning!(self.logger, "Refusing to provide exit services for CORES package with {}-byte payload without paying wallet", msg.payload.sequenced_packet.data.len());
+            return;
         }
+        if is_zero_hop {
+            self.process_client_request(msg);
+            return;
+        }
+        let data_len = msg.payload.sequenced_packet.data.len();
+        let now = Instant::now();
+        let delay = self.bandwidth_throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Download(msg.immediate_neighbor.ip()),
+            data_len,
+            now,
+        );
+        if delay == Duration::from_secs(0) {
+            self.process_client_request(msg);
+            return;
+        }
+        debug!(
+            self.logger,
+            "Holding {}-byte request for stream {} for {}ms to stay within the exit bandwidth limit",
+            data_len,
+            msg.payload.stream_key,
+            delay.as_millis()
+        );
+        if delay > self.max_bandwidth_delay {
+            self.pause_originator(ctx, &msg, now + delay);
+        }
+        ctx.run_later(delay, move |proxy_client, _| {
+            proxy_client.process_client_request(msg)
+        });
     }
 }
 
 impl Handler<InboundServerData> for ProxyClient {
     type Result = ();
 
-    fn handle(&mut self, msg: InboundServerData, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: InboundServerData, ctx: &mut Self::Context) -> Self::Result {
         let msg_data_len = msg.data.len();
         let msg_source = msg.source;
         let msg_sequence_number = msg.sequence_number;
@@ -161,6 +225,11 @@ impl Handler<InboundServerData> for ProxyClient {
             return;
         };
         self.report_response_exit_to_accountant(&stream_context, msg_data_len);
+        self.charge_response_bandwidth(ctx, msg_stream_key, msg_data_len);
+        if let Some(stream_context) = self.stream_contexts.get_mut(&msg_stream_key) {
+            stream_context.last_activity = Instant::now();
+            stream_context.next_sequence_number = msg_sequence_number + 1;
+        }
         if msg_last_data {
             debug!(
                 self.logger,
@@ -208,6 +277,59 @@ impl Handler<DnsResolveFailure_0v1> for ProxyClient {
     }
 }
 
+impl Handler<StreamPressure_0v1> for ProxyClient {
+    type Result = ();
+
+    fn handle(&mut self, msg: StreamPressure_0v1, _ctx: &mut Self::Context) -> Self::Result {
+        let stream_context = match self.stream_contexts.get(&msg.stream_key) {
+            Some(sc) => sc,
+            None => {
+                debug!(
+                    self.logger,
+                    "No stream {} to send {:?} back for; ignoring", msg.stream_key, msg.pressure
+                );
+                return;
+            }
+        };
+        debug!(
+            self.logger,
+            "Asking originator of stream {} to apply {:?}", msg.stream_key, msg.pressure
+        );
+        self.send_stream_pressure(
+            stream_context.return_route.clone(),
+            &stream_context.payload_destination_key,
+            msg,
+        );
+    }
+}
+
+impl Handler<ExpiredCoresPackage<StreamPressure_0v1>> for ProxyClient {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: ExpiredCoresPackage<StreamPressure_0v1>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let pool = self.pool.as_ref().expect("StreamHandlerPool unbound");
+        pool.apply_read_pressure(
+            &msg.payload.stream_key,
+            PauseReason::Backpressure,
+            msg.payload.pressure,
+        );
+    }
+}
+
+impl Handler<NodeFromUiMessage> for ProxyClient {
+    type Result = ();
+
+    fn handle(&mut self, msg: NodeFromUiMessage, _ctx: &mut Self::Context) -> Self::Result {
+        if let Ok((_, context_id)) = UiExitStreamStatsRequest::fmb(msg.body) {
+            self.handle_exit_stream_stats_request(msg.client_id, context_id);
+        }
+    }
+}
+
 impl ProxyClient {
     pub fn new(config: ProxyClientConfig) -> ProxyClient {
         if config.dns_servers.is_empty() {
@@ -215,15 +337,28 @@ impl ProxyClient {
         }
         ProxyClient {
             dns_servers: config.dns_servers,
+            dns_protocol: config.dns_protocol,
             resolver_wrapper_factory: Box::new(ResolverWrapperFactoryReal {}),
             stream_handler_pool_factory: Box::new(StreamHandlerPoolFactoryReal {}),
             cryptde: config.cryptde,
             to_hopper: None,
             to_accountant: None,
+            to_ui_message_sub: None,
             pool: None,
             stream_contexts: HashMap::new(),
+            bandwidth_throttle: bandwidth_throttle(),
+            bandwidth_pauses: HashMap::new(),
+            originator_pauses: HashMap::new(),
+            max_bandwidth_delay: MAX_BANDWIDTH_DELAY,
             exit_service_rate: config.exit_service_rate,
             exit_byte_rate: config.exit_byte_rate,
+            exit_idle_timeout: config.exit_idle_timeout,
+            exit_absolute_timeout: config.exit_absolute_timeout,
+            exit_ports_opt: config.exit_ports_opt,
+            local_destinations_allowed: config.local_destinations_allowed,
+            stream_reaping_interval: STREAM_REAPING_INTERVAL,
+            idle_streams_reaped: 0,
+            expired_streams_reaped: 0,
             logger: Logger::new("ProxyClient"),
         }
     }
@@ -236,7 +371,279 @@ impl ProxyClient {
                 .recipient::<ExpiredCoresPackage<ClientRequestPayload_0v1>>(),
             inbound_server_data: addr.clone().recipient::<InboundServerData>(),
             dns_resolve_failed: addr.clone().recipient::<DnsResolveFailure_0v1>(),
+            stream_pressure: addr.clone().recipient::<StreamPressure_0v1>(),
+            stream_pressure_from_hopper: addr
+                .clone()
+                .recipient::<ExpiredCoresPackage<StreamPressure_0v1>>(),
+            ui_sub: addr.clone().recipient::<NodeFromUiMessage>(),
+        }
+    }
+
+    fn reap_streams(&mut self, now: Instant) {
+        let reapable_streams = self
+            .stream_contexts
+            .iter()
+            .filter_map(|(stream_key, stream_context)| {
+                if now.duration_since(stream_context.created_at) >= self.exit_absolute_timeout {
+                    Some((*stream_key, ReapingReason::Expired))
+                } else if now.duration_since(stream_context.last_activity) >= self.exit_idle_timeout
+                {
+                    Some((*stream_key, ReapingReason::Idle))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<(StreamKey, ReapingReason)>>();
+        reapable_streams
+            .into_iter()
+            .for_each(|(stream_key, reason)| self.reap_stream(stream_key, reason));
+    }
+
+    fn reap_stream(&mut self, stream_key: StreamKey, reason: ReapingReason) {
+        let stream_context = self
+            .stream_contexts
+            .remove(&stream_key)
+            .expect("Reaping a stream that doesn't exist");
+        match reason {
+            ReapingReason::Idle => {
+                info!(
+                    self.logger,
+                    "Reaping stream {}: idle for longer than {}s",
+                    stream_key,
+                    self.exit_idle_timeout.as_secs()
+                );
+                self.idle_streams_reaped += 1;
+            }
+            ReapingReason::Expired => {
+                info!(
+                    self.logger,
+                    "Reaping stream {}: open for longer than {}s",
+                    stream_key,
+                    self.exit_absolute_timeout.as_secs()
+                );
+                self.expired_streams_reaped += 1;
+            }
+        }
+        let terminator = InboundServerData {
+            stream_key,
+            last_data: true,
+            sequence_number: stream_context.next_sequence_number,
+            source: error_socket_addr(),
+            data: vec![],
+        };
+        let _ = self.send_response_to_hopper(terminator, &stream_context);
+        self.pool
+            .as_ref()
+            .expect("StreamHandlerPool unbound")
+            .reap_stream(&stream_key);
+    }
+
+    fn process_client_request(&mut self, msg: ExpiredCoresPackage<ClientRequestPayload_0v1>) {
+        let payload = msg.payload;
+        let paying_wallet = msg.paying_wallet;
+        let return_route = msg.remaining_route;
+        let now = Instant::now();
+        let (created_at, next_sequence_number) = match self.stream_contexts.get(&payload.stream_key)
+        {
+            Some(sc) => (sc.created_at, sc.next_sequence_number),
+            None => (now, 0),
+        };
+        let latest_stream_context = StreamContext {
+            return_route,
+            payload_destination_key: payload.originator_public_key.clone(),
+            paying_wallet: paying_wallet.clone(),
+            created_at,
+            last_activity: now,
+            next_sequence_number,
+        };
+        debug!(
+            self.logger,
+            "Received ClientRequestPayload: stream {}, sequence {}, length {}",
+            payload.stream_key,
+            payload.sequenced_packet.sequence_number,
+            payload.sequenced_packet.data.len()
+        );
+        if !self.exit_port_allowed(&payload) {
+            warning!(
+                self.logger,
+                "Refusing opaque stream {} to port {}: {}",
+                payload.stream_key,
+                payload.target_port,
+                match self.exit_ports_opt {
+                    Some(_) => "not among the --exit-ports",
+                    None => "mail ports must be listed in --exit-ports",
+                }
+            );
+            let terminator = InboundServerData {
+                stream_key: payload.stream_key,
+                last_data: true,
+                sequence_number: latest_stream_context.next_sequence_number,
+                source: error_socket_addr(),
+                data: vec![],
+            };
+            let _ = self.send_response_to_hopper(terminator, &latest_stream_context);
+            self.stream_contexts.remove(&payload.stream_key);
+            return;
+        }
+        self.stream_contexts
+            .insert(payload.stream_key, latest_stream_context);
+        self.pool
+            .as_mut()
+            .expect("StreamHandlerPool unbound")
+            .process_package(payload, paying_wallet);
+    }
+
+    // HTTP and TLS requests carry their own host names and go where the web goes; opaque streams
+    // can go anywhere, so an exit may limit them to the ports it's willing to serve. One that
+    // doesn't still keeps them away from the mail ports.
+    fn exit_port_allowed(&self, payload: &ClientRequestPayload_0v1) -> bool {
+        match (&payload.protocol, &self.exit_ports_opt) {
+            (ProxyProtocol::Opaque, Some(exit_ports)) => exit_ports.allows(payload.target_port),
+            (ProxyProtocol::Opaque, None) => ExitPorts::allowed_by_default(payload.target_port),
+            _ => true,
+        }
+    }
+
+    // A response has already been read from the server by the time it gets here, so it's always
+    // sent; but if it puts the first hop back toward the client over its exit bandwidth, reading
+    // from the server stops until the bandwidth has been paid for.
+    fn charge_response_bandwidth(
+        &mut self,
+        ctx: &mut Context<Self>,
+        stream_key: StreamKey,
+        data_len: usize,
+    ) {
+        let first_hop_key = match self.stream_contexts.get(&stream_key) {
+            Some(stream_context) => match stream_context.return_route.next_hop(self.cryptde) {
+                Ok(live_hop) if &live_hop.public_key != self.cryptde.public_key() => {
+                    live_hop.public_key
+                }
+                _ => return,
+            },
+            None => return,
+        };
+        let now = Instant::now();
+        let delay = self.bandwidth_throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Upload(first_hop_key),
+            data_len,
+            now,
+        );
+        if delay == Duration::from_secs(0) {
+            return;
+        }
+        let resume_at = now + delay;
+        match self.bandwidth_pauses.get(&stream_key) {
+            Some(paused_until) if *paused_until >= resume_at => return,
+            Some(_) => (),
+            None => {
+                debug!(
+                    self.logger,
+                    "Pausing stream {} for {}ms to stay within the exit bandwidth limit",
+                    stream_key,
+                    delay.as_millis()
+                );
+                self.pool
+                    .as_ref()
+                    .expect("StreamHandlerPool unbound")
+                    .apply_read_pressure(&stream_key, PauseReason::Bandwidth, Pressure::Pause);
+            }
+        }
+        self.bandwidth_pauses.insert(stream_key, resume_at);
+        ctx.run_later(delay, move |proxy_client, _| {
+            proxy_client.resume_paused_stream(stream_key, resume_at)
+        });
+    }
+
+    // Only the latest pause a stream was given gets to resume it.
+    fn resume_paused_stream(&mut self, stream_key: StreamKey, resume_at: Instant) {
+        if self.bandwidth_pauses.get(&stream_key) != Some(&resume_at) {
+            return;
+        }
+        self.bandwidth_pauses.remove(&stream_key);
+        self.pool
+            .as_ref()
+            .expect("StreamHandlerPool unbound")
+            .apply_read_pressure(&stream_key, PauseReason::Bandwidth, Pressure::Resume);
+    }
+
+    // Requests held this long mean the originator is sending faster than the exit bandwidth limit
+    // allows. They're all served in the end, since a missing one would stall its stream; but the
+    // originator is asked to stop reading from its client until they have been. Only the latest
+    // pause a stream was given gets to resume it.
+    fn pause_originator(
+        &mut self,
+        ctx: &mut Context<Self>,
+        msg: &ExpiredCoresPackage<ClientRequestPayload_0v1>,
+        resume_at: Instant,
+    ) {
+        let stream_key = msg.payload.stream_key;
+        let return_route = msg.remaining_route.clone();
+        let originator_key = msg.payload.originator_public_key.clone();
+        match self.originator_pauses.get(&stream_key) {
+            Some(paused_until) if *paused_until >= resume_at => return,
+            Some(_) => (),
+            None => {
+                debug!(
+                    self.logger,
+                    "Asking originator of stream {} to pause to stay within the exit bandwidth limit",
+                    stream_key
+                );
+                self.send_stream_pressure(
+                    return_route.clone(),
+                    &originator_key,
+                    StreamPressure_0v1::new(stream_key, Pressure::Pause),
+                );
+            }
+        }
+        self.originator_pauses.insert(stream_key, resume_at);
+        ctx.run_later(
+            resume_at.saturating_duration_since(Instant::now()),
+            move |proxy_client, _| {
+                if proxy_client.originator_pauses.get(&stream_key) != Some(&resume_at) {
+                    return;
+                }
+                proxy_client.originator_pauses.remove(&stream_key);
+                proxy_client.send_stream_pressure(
+                    return_route,
+                    &originator_key,
+                    StreamPressure_0v1::new(stream_key, Pressure::Resume),
+                );
+            },
+        );
+    }
+
+    fn send_stream_pressure(
+        &self,
+        return_route: Route,
+        originator_key: &PublicKey,
+        msg: StreamPressure_0v1,
+    ) {
+        let package =
+            IncipientCoresPackage::new(self.cryptde, return_route, msg.into(), originator_key)
+                .expect("Failed to create IncipientCoresPackage");
+        self.to_hopper
+            .as_ref()
+            .expect("Hopper is unbound")
+            .try_send(package)
+            .expect("Hopper is dead");
+    }
+
+    fn handle_exit_stream_stats_request(&self, client_id: u64, context_id: u64) {
+        let body = UiExitStreamStatsResponse {
+            active_streams: self.stream_contexts.len() as u64,
+            reaped_idle: self.idle_streams_reaped,
+            reaped_expired: self.expired_streams_reaped,
         }
+        .tmb(context_id);
+        self.to_ui_message_sub
+            .as_ref()
+            .expect("UiGateway is unbound")
+            .try_send(NodeToUiMessage {
+                target: ClientId(client_id),
+                body,
+            })
+            .expect("UiGateway is dead");
     }
 
     fn send_response_to_hopper(
@@ -316,6 +723,15 @@ struct StreamContext {
     return_route: Route,
     payload_destination_key: PublicKey,
     paying_wallet: Option<Wallet>,
+    created_at: Instant,
+    last_activity: Instant,
+    next_sequence_number: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ReapingReason {
+    Idle,
+    Expired,
 }
 
 #[cfg(test)]
@@ -328,15 +744,20 @@ mod tests {
     use crate::proxy_client::stream_handler_pool::StreamHandlerPool;
     use crate::proxy_client::stream_handler_pool::StreamHandlerPoolFactory;
     use crate::sub_lib::accountant::ReportExitServiceProvidedMessage;
+    use crate::sub_lib::bandwidth::{BandwidthLimits, ServiceBandwidthLimits};
     use crate::sub_lib::cryptde::CryptData;
     use crate::sub_lib::cryptde::PublicKey;
     use crate::sub_lib::dispatcher::Component;
     use crate::sub_lib::hopper::MessageType;
     use crate::sub_lib::proxy_client::ClientResponsePayload_0v1;
+    use crate::sub_lib::proxy_client::{
+        DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS, DEFAULT_EXIT_IDLE_TIMEOUT_SECS,
+    };
     use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
     use crate::sub_lib::proxy_server::ProxyProtocol;
     use crate::sub_lib::route::{Route, RouteSegment};
     use crate::sub_lib::sequence_buffer::SequencedPacket;
+    use crate::sub_lib::stream_buffer::Pressure;
     use crate::sub_lib::versioned_data::VersionedData;
     use crate::sub_lib::wallet::Wallet;
     use crate::test_utils::logging::init_test_logging;
@@ -346,6 +767,8 @@ mod tests {
     use crate::test_utils::recorder::Recorder;
     use crate::test_utils::*;
     use actix::System;
+    use futures::future::Future;
+    use masq_lib::constants::HTTP_PORT;
     use std::cell::RefCell;
     use std::net::IpAddr;
     use std::net::SocketAddr;
@@ -353,6 +776,7 @@ mod tests {
     use std::sync::Arc;
     use std::sync::Mutex;
     use std::thread;
+    use tokio::timer::Delay;
 
     fn dnss() -> Vec<SocketAddr> {
         vec![SocketAddr::from_str("8.8.8.8:53").unwrap()]
@@ -360,6 +784,8 @@ mod tests {
 
     pub struct StreamHandlerPoolMock {
         process_package_parameters: Arc<Mutex<Vec<(ClientRequestPayload_0v1, Option<Wallet>)>>>,
+        reap_stream_parameters: Arc<Mutex<Vec<StreamKey>>>,
+        apply_read_pressure_parameters: Arc<Mutex<Vec<(StreamKey, PauseReason, Pressure)>>>,
     }
 
     impl StreamHandlerPool for StreamHandlerPoolMock {
@@ -373,12 +799,34 @@ mod tests {
                 .unwrap()
                 .push((payload, paying_wallet));
         }
+
+        fn reap_stream(&self, stream_key: &StreamKey) {
+            self.reap_stream_parameters
+                .lock()
+                .unwrap()
+                .push(*stream_key);
+        }
+
+        fn apply_read_pressure(
+            &self,
+            stream_key: &StreamKey,
+            reason: PauseReason,
+            pressure: Pressure,
+        ) {
+            self.apply_read_pressure_parameters.lock().unwrap().push((
+                *stream_key,
+                reason,
+                pressure,
+            ));
+        }
     }
 
     impl StreamHandlerPoolMock {
         pub fn new() -> StreamHandlerPoolMock {
             StreamHandlerPoolMock {
                 process_package_parameters: Arc::new(Mutex::new(vec![])),
+                reap_stream_parameters: Arc::new(Mutex::new(vec![])),
+                apply_read_pressure_parameters: Arc::new(Mutex::new(vec![])),
             }
         }
 
@@ -389,6 +837,22 @@ mod tests {
             *parameters = self.process_package_parameters.clone();
             self
         }
+
+        pub fn reap_stream_parameters(
+            self,
+            parameters: &mut Arc<Mutex<Vec<StreamKey>>>,
+        ) -> StreamHandlerPoolMock {
+            *parameters = self.reap_stream_parameters.clone();
+            self
+        }
+
+        pub fn apply_read_pressure_parameters(
+            self,
+            parameters: &mut Arc<Mutex<Vec<(StreamKey, PauseReason, Pressure)>>>,
+        ) -> StreamHandlerPoolMock {
+            *parameters = self.apply_read_pressure_parameters.clone();
+            self
+        }
     }
 
     pub struct StreamHandlerPoolFactoryMock {
@@ -401,6 +865,7 @@ mod tests {
                     ProxyClientSubs,
                     u64,
                     u64,
+                    bool,
                 )>,
             >,
         >,
@@ -416,6 +881,7 @@ mod tests {
             proxy_client_subs: ProxyClientSubs,
             exit_service_rate: u64,
             exit_byte_rate: u64,
+            local_destinations_allowed: bool,
         ) -> Box<dyn StreamHandlerPool> {
             self.make_parameters.lock().unwrap().push((
                 resolver,
@@ -424,6 +890,7 @@ mod tests {
                 proxy_client_subs,
                 exit_service_rate,
                 exit_byte_rate,
+                local_destinations_allowed,
             ));
             self.make_results.borrow_mut().remove(0)
         }
@@ -448,6 +915,7 @@ mod tests {
                         ProxyClientSubs,
                         u64,
                         u64,
+                        bool,
                     )>,
                 >,
             >,
@@ -473,8 +941,13 @@ mod tests {
         ProxyClient::new(ProxyClientConfig {
             cryptde: main_cryptde(),
             dns_servers: vec![],
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
     }
 
@@ -500,8 +973,13 @@ mod tests {
                 SocketAddr::from_str("4.3.2.1:4321").unwrap(),
                 SocketAddr::from_str("5.4.3.2:5432").unwrap(),
             ],
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: true,
         });
         subject.resolver_wrapper_factory = Box::new(resolver_wrapper_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -512,6 +990,11 @@ mod tests {
         System::current().stop_with_code(0);
         system.run();
 
+        let (_, _, _, _, exit_service_rate, exit_byte_rate, local_destinations_allowed) =
+            pool_factory_make_parameters.lock().unwrap().remove(0);
+        assert_eq!(exit_service_rate, 100);
+        assert_eq!(exit_byte_rate, 200);
+        assert_eq!(local_destinations_allowed, true);
         let mut resolver_wrapper_new_parameters =
             resolver_wrapper_new_parameters_arc.lock().unwrap();
         let (config, opts) = resolver_wrapper_new_parameters.remove(0);
@@ -532,10 +1015,58 @@ mod tests {
                 },
             ]
         );
-        assert_eq!(opts, ResolverOpts::default());
+        let mut expected_opts = ResolverOpts::default();
+        expected_opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+        assert_eq!(opts, expected_opts);
         assert_eq!(resolver_wrapper_new_parameters.is_empty(), true);
     }
 
+    #[test]
+    fn bind_configures_encrypted_dns_when_asked() {
+        let system = System::new("bind_configures_encrypted_dns_when_asked");
+        let mut resolver_wrapper_new_parameters_arc: Arc<
+            Mutex<Vec<(ResolverConfig, ResolverOpts)>>,
+        > = Arc::new(Mutex::new(vec![]));
+        let resolver_wrapper_factory = ResolverWrapperFactoryMock::new()
+            .new_parameters(&mut resolver_wrapper_new_parameters_arc)
+            .new_result(Box::new(ResolverWrapperMock::new()));
+        let pool_factory =
+            StreamHandlerPoolFactoryMock::new().make_result(Box::new(StreamHandlerPoolMock::new()));
+        let peer_actors = peer_actors_builder().build();
+        let mut subject = ProxyClient::new(ProxyClientConfig {
+            cryptde: main_cryptde(),
+            dns_servers: vec![SocketAddr::from_str("1.1.1.1:853").unwrap()],
+            dns_protocol: DnsProtocol::Tls("cloudflare-dns.com".to_string()),
+            exit_service_rate: 100,
+            exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+        });
+        subject.resolver_wrapper_factory = Box::new(resolver_wrapper_factory);
+        subject.stream_handler_pool_factory = Box::new(pool_factory);
+        let subject_addr: Addr<ProxyClient> = subject.start();
+
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+
+        let (config, _) = resolver_wrapper_new_parameters_arc
+            .lock()
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            config.name_servers(),
+            &[NameServerConfig {
+                socket_addr: SocketAddr::from_str("1.1.1.1:853").unwrap(),
+                protocol: Protocol::Tls,
+                tls_dns_name: Some("cloudflare-dns.com".to_string()),
+            }]
+        );
+    }
+
     #[test]
     #[should_panic(expected = "StreamHandlerPool unbound")]
     fn panics_if_unbound() {
@@ -563,8 +1094,13 @@ mod tests {
         let subject = ProxyClient::new(ProxyClientConfig {
             cryptde,
             dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         let subject_addr: Addr<ProxyClient> = subject.start();
 
@@ -585,8 +1121,13 @@ mod tests {
             let subject = ProxyClient::new(ProxyClientConfig {
                 cryptde,
                 dns_servers: vec![SocketAddr::from_str("1.1.1.1:53").unwrap()],
+                dns_protocol: DnsProtocol::Udp,
                 exit_service_rate: 0,
                 exit_byte_rate: 0,
+                exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+                exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+                exit_ports_opt: None,
+                local_destinations_allowed: false,
             });
             let subject_addr = subject.start();
             let subject_subs = ProxyClient::make_subs_from(&subject_addr);
@@ -624,8 +1165,13 @@ mod tests {
             let mut subject = ProxyClient::new(ProxyClientConfig {
                 cryptde,
                 dns_servers: vec![SocketAddr::from_str("1.1.1.1:53").unwrap()],
+                dns_protocol: DnsProtocol::Udp,
                 exit_service_rate: 0,
                 exit_byte_rate: 0,
+                exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+                exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+                exit_ports_opt: None,
+                local_destinations_allowed: false,
             });
             subject.stream_contexts.insert(
                 stream_key_inner,
@@ -633,6 +1179,9 @@ mod tests {
                     return_route: return_route_inner,
                     payload_destination_key: originator_key_inner,
                     paying_wallet: None,
+                    created_at: Instant::now(),
+                    last_activity: Instant::now(),
+                    next_sequence_number: 0,
                 },
             );
             let subject_addr = subject.start();
@@ -674,45 +1223,423 @@ mod tests {
     }
 
     #[test]
-    fn data_from_hopper_is_relayed_to_stream_handler_pool() {
+    fn forwards_stream_pressure_to_the_originator_without_retiring_the_stream() {
+        init_test_logging();
         let cryptde = main_cryptde();
-        let request = ClientRequestPayload_0v1 {
-            stream_key: make_meaningless_stream_key(),
-            sequenced_packet: SequencedPacket {
-                data: b"inbound data".to_vec(),
-                sequence_number: 0,
-                last_data: false,
-            },
-            target_hostname: None,
-            target_port: 0,
-            protocol: ProxyProtocol::HTTP,
-            originator_public_key: PublicKey::new(&b"originator"[..]),
-        };
-        let package = ExpiredCoresPackage::new(
-            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
-            Some(make_wallet("consuming")),
-            make_meaningless_route(),
-            request.clone().into(),
-            0,
+        let (hopper, hopper_awaiter, hopper_recording_arc) = make_recorder();
+        let stream_key = make_meaningless_stream_key();
+        let unknown_stream_key = StreamKey::new(
+            PublicKey::new(&b"unknown"[..]),
+            SocketAddr::from_str("2.3.4.5:6789").unwrap(),
         );
-        let hopper = Recorder::new();
+        let return_route = make_meaningless_route();
+        let originator_key = make_meaningless_public_key();
+        let return_route_inner = return_route.clone();
+        let originator_key_inner = originator_key.clone();
+        thread::spawn(move || {
+            let system = System::new("forwards_stream_pressure_to_the_originator");
+            let peer_actors = peer_actors_builder().hopper(hopper).build();
+            let mut subject = ProxyClient::new(ProxyClientConfig {
+                cryptde,
+                dns_servers: vec![SocketAddr::from_str("1.1.1.1:53").unwrap()],
+                dns_protocol: DnsProtocol::Udp,
+                exit_service_rate: 0,
+                exit_byte_rate: 0,
+                exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+                exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+                exit_ports_opt: None,
+                local_destinations_allowed: false,
+            });
+            subject.stream_contexts.insert(
+                stream_key,
+                StreamContext {
+                    return_route: return_route_inner,
+                    payload_destination_key: originator_key_inner,
+                    paying_wallet: None,
+                    created_at: Instant::now(),
+                    last_activity: Instant::now(),
+                    next_sequence_number: 0,
+                },
+            );
+            let subject_addr = subject.start();
+            let subject_subs = ProxyClient::make_subs_from(&subject_addr);
 
-        let system = System::new("data_from_hopper_is_relayed_to_stream_handler_pool");
-        let peer_actors = peer_actors_builder().hopper(hopper).build();
-        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
-        let pool = Box::new(
-            StreamHandlerPoolMock::new()
-                .process_package_parameters(&mut process_package_parameters),
-        );
-        let pool_factory = StreamHandlerPoolFactoryMock::new().make_result(pool);
-        let resolver = ResolverWrapperMock::new()
-            .lookup_ip_success(vec![IpAddr::from_str("4.3.2.1").unwrap()]);
-        let resolver_factory = ResolverWrapperFactoryMock::new().new_result(Box::new(resolver));
-        let mut subject = ProxyClient::new(ProxyClientConfig {
-            cryptde,
-            dns_servers: dnss(),
+            send_bind_message!(subject_subs, peer_actors);
+
+            subject_subs
+                .stream_pressure
+                .try_send(StreamPressure_0v1::new(stream_key, Pressure::Pause))
+                .unwrap();
+            subject_subs
+                .stream_pressure
+                .try_send(StreamPressure_0v1::new(unknown_stream_key, Pressure::Pause))
+                .unwrap();
+            subject_subs
+                .stream_pressure
+                .try_send(StreamPressure_0v1::new(stream_key, Pressure::Resume))
+                .unwrap();
+
+            system.run();
+        });
+
+        hopper_awaiter.await_message_count(2);
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        let expected_package = |pressure| {
+            IncipientCoresPackage::new(
+                cryptde,
+                return_route.clone(),
+                StreamPressure_0v1::new(stream_key, pressure).into(),
+                &originator_key,
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &expected_package(Pressure::Pause)
+        );
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(1),
+            &expected_package(Pressure::Resume)
+        );
+        assert_eq!(hopper_recording.len(), 2);
+        TestLogHandler::new().exists_log_containing(&format!(
+            "DEBUG: ProxyClient: No stream {} to send Pause back for; ignoring",
+            unknown_stream_key
+        ));
+    }
+
+    #[test]
+    fn stream_pressure_from_hopper_is_applied_to_stream_handler_pool() {
+        let cryptde = main_cryptde();
+        let stream_key = make_meaningless_stream_key();
+        let package = ExpiredCoresPackage::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            None,
+            make_meaningless_route(),
+            StreamPressure_0v1::new(stream_key, Pressure::Pause),
+            0,
+        );
+        let system = System::new("stream_pressure_from_hopper_is_applied_to_stream_handler_pool");
+        let peer_actors = peer_actors_builder().build();
+        let mut apply_read_pressure_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = Box::new(
+            StreamHandlerPoolMock::new()
+                .apply_read_pressure_parameters(&mut apply_read_pressure_parameters),
+        );
+        let pool_factory = StreamHandlerPoolFactoryMock::new().make_result(pool);
+        let resolver_factory =
+            ResolverWrapperFactoryMock::new().new_result(Box::new(ResolverWrapperMock::new()));
+        let mut subject = ProxyClient::new(ProxyClientConfig {
+            cryptde,
+            dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+        });
+        subject.resolver_wrapper_factory = Box::new(resolver_factory);
+        subject.stream_handler_pool_factory = Box::new(pool_factory);
+        let subject_addr: Addr<ProxyClient> = subject.start();
+        subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+        subject_addr.try_send(package).unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(
+            *apply_read_pressure_parameters.lock().unwrap(),
+            vec![(stream_key, PauseReason::Backpressure, Pressure::Pause)]
+        );
+    }
+
+    fn make_reaping_subject(idle_timeout: Duration, absolute_timeout: Duration) -> ProxyClient {
+        ProxyClient::new(ProxyClientConfig {
+            cryptde: main_cryptde(),
+            dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
+            exit_service_rate: 100,
+            exit_byte_rate: 200,
+            exit_idle_timeout: idle_timeout,
+            exit_absolute_timeout: absolute_timeout,
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+        })
+    }
+
+    fn make_reaping_stream_context(
+        created_at: Instant,
+        last_activity: Instant,
+        next_sequence_number: u64,
+    ) -> StreamContext {
+        StreamContext {
+            return_route: make_meaningless_route(),
+            payload_destination_key: PublicKey::new(&b"originator"[..]),
+            paying_wallet: Some(make_wallet("paying")),
+            created_at,
+            last_activity,
+            next_sequence_number,
+        }
+    }
+
+    fn make_terminating_package(
+        stream_key: StreamKey,
+        sequence_number: u64,
+    ) -> IncipientCoresPackage {
+        IncipientCoresPackage::new(
+            main_cryptde(),
+            make_meaningless_route(),
+            MessageType::ClientResponse(VersionedData::new(
+                &crate::sub_lib::migrations::client_response_payload::MIGRATIONS,
+                &ClientResponsePayload_0v1 {
+                    stream_key,
+                    sequenced_packet: SequencedPacket {
+                        data: vec![],
+                        sequence_number,
+                        last_data: true,
+                    },
+                },
+            )),
+            &PublicKey::new(&b"originator"[..]),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reap_streams_terminates_idle_and_expired_streams_and_counts_them() {
+        init_test_logging();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let (accountant, _, accountant_recording_arc) = make_recorder();
+        let system =
+            System::new("reap_streams_terminates_idle_and_expired_streams_and_counts_them");
+        let peer_actors = peer_actors_builder()
+            .hopper(hopper)
+            .accountant(accountant)
+            .build();
+        let mut reap_stream_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new().reap_stream_parameters(&mut reap_stream_parameters);
+        let mut subject = make_reaping_subject(Duration::from_secs(60), Duration::from_secs(3600));
+        subject.to_hopper = Some(peer_actors.hopper.from_hopper_client);
+        subject.pool = Some(Box::new(pool));
+        let start = Instant::now();
+        let idle_key = StreamKey::new(
+            PublicKey::new(&b"idle"[..]),
+            SocketAddr::from_str("1.1.1.1:1111").unwrap(),
+        );
+        let busy_key = StreamKey::new(
+            PublicKey::new(&b"busy"[..]),
+            SocketAddr::from_str("2.2.2.2:2222").unwrap(),
+        );
+        let expired_key = StreamKey::new(
+            PublicKey::new(&b"expired"[..]),
+            SocketAddr::from_str("3.3.3.3:3333").unwrap(),
+        );
+        subject.stream_contexts.insert(
+            idle_key,
+            make_reaping_stream_context(
+                start + Duration::from_secs(3000),
+                start + Duration::from_secs(3000),
+                5,
+            ),
+        );
+        subject.stream_contexts.insert(
+            busy_key,
+            make_reaping_stream_context(
+                start + Duration::from_secs(3500),
+                start + Duration::from_secs(3590),
+                6,
+            ),
+        );
+        subject.stream_contexts.insert(
+            expired_key,
+            make_reaping_stream_context(start, start + Duration::from_secs(3590), 7),
+        );
+
+        subject.reap_streams(start + Duration::from_secs(3600));
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(
+            subject.stream_contexts.keys().collect::<Vec<&StreamKey>>(),
+            vec![&busy_key]
+        );
+        assert_eq!(subject.idle_streams_reaped, 1);
+        assert_eq!(subject.expired_streams_reaped, 1);
+        let mut reaped_keys = reap_stream_parameters.lock().unwrap().clone();
+        reaped_keys.sort_by_key(|key| key.to_string());
+        let mut expected_keys = vec![idle_key, expired_key];
+        expected_keys.sort_by_key(|key| key.to_string());
+        assert_eq!(reaped_keys, expected_keys);
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 2);
+        let packages = vec![
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            hopper_recording.get_record::<IncipientCoresPackage>(1),
+        ];
+        assert!(packages.contains(&&make_terminating_package(idle_key, 5)));
+        assert!(packages.contains(&&make_terminating_package(expired_key, 7)));
+        let accountant_recording = accountant_recording_arc.lock().unwrap();
+        assert_eq!(accountant_recording.len(), 0);
+        let tlh = TestLogHandler::new();
+        tlh.exists_log_containing(&format!(
+            "INFO: ProxyClient: Reaping stream {}: idle for longer than 60s",
+            idle_key
+        ));
+        tlh.exists_log_containing(&format!(
+            "INFO: ProxyClient: Reaping stream {}: open for longer than 3600s",
+            expired_key
+        ));
+    }
+
+    #[test]
+    fn idle_streams_are_reaped_on_a_timer() {
+        let (hopper, hopper_awaiter, hopper_recording_arc) = make_recorder();
+        let stream_key = make_meaningless_stream_key();
+        let request = ClientRequestPayload_0v1 {
+            stream_key,
+            sequenced_packet: SequencedPacket {
+                data: b"inbound data".to_vec(),
+                sequence_number: 0,
+                last_data: false,
+            },
+            target_hostname: Some("example.com".to_string()),
+            target_port: HTTP_PORT,
+            protocol: ProxyProtocol::HTTP,
+            originator_public_key: PublicKey::new(&b"originator"[..]),
+        };
+        let package = ExpiredCoresPackage::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            Some(make_wallet("consuming")),
+            make_meaningless_route(),
+            request,
+            0,
+        );
+        let mut reap_stream_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new().reap_stream_parameters(&mut reap_stream_parameters);
+        thread::spawn(move || {
+            let system = System::new("idle_streams_are_reaped_on_a_timer");
+            let peer_actors = peer_actors_builder().hopper(hopper).build();
+            let pool_factory = StreamHandlerPoolFactoryMock::new().make_result(Box::new(pool));
+            let resolver_factory =
+                ResolverWrapperFactoryMock::new().new_result(Box::new(ResolverWrapperMock::new()));
+            let mut subject =
+                make_reaping_subject(Duration::from_millis(10), Duration::from_secs(3600));
+            subject.stream_reaping_interval = Duration::from_millis(10);
+            subject.resolver_wrapper_factory = Box::new(resolver_factory);
+            subject.stream_handler_pool_factory = Box::new(pool_factory);
+            let subject_addr: Addr<ProxyClient> = subject.start();
+            subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+            subject_addr.try_send(package).unwrap();
+
+            system.run();
+        });
+
+        hopper_awaiter.await_message_count(1);
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &make_terminating_package(stream_key, 0)
+        );
+        assert_eq!(*reap_stream_parameters.lock().unwrap(), vec![stream_key]);
+    }
+
+    #[test]
+    fn proxy_client_responds_to_exit_stream_stats_request() {
+        let (ui_gateway, ui_gateway_awaiter, ui_gateway_recording_arc) = make_recorder();
+        thread::spawn(move || {
+            let system = System::new("proxy_client_responds_to_exit_stream_stats_request");
+            let peer_actors = peer_actors_builder().ui_gateway(ui_gateway).build();
+            let mut subject = make_reaping_subject(
+                Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+                Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            );
+            subject.stream_handler_pool_factory = Box::new(
+                StreamHandlerPoolFactoryMock::new()
+                    .make_result(Box::new(StreamHandlerPoolMock::new())),
+            );
+            subject.stream_contexts.insert(
+                make_meaningless_stream_key(),
+                make_reaping_stream_context(Instant::now(), Instant::now(), 0),
+            );
+            subject.idle_streams_reaped = 12;
+            subject.expired_streams_reaped = 3;
+            let subject_addr: Addr<ProxyClient> = subject.start();
+            subject_addr.try_send(BindMessage { peer_actors }).unwrap();
+
+            subject_addr
+                .try_send(NodeFromUiMessage {
+                    client_id: 1234,
+                    body: UiExitStreamStatsRequest {}.tmb(4321),
+                })
+                .unwrap();
+
+            system.run();
+        });
+
+        ui_gateway_awaiter.await_message_count(1);
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let response = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(response.target, ClientId(1234));
+        let (body, context_id) = UiExitStreamStatsResponse::fmb(response.body.clone()).unwrap();
+        assert_eq!(context_id, 4321);
+        assert_eq!(
+            body,
+            UiExitStreamStatsResponse {
+                active_streams: 1,
+                reaped_idle: 12,
+                reaped_expired: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn data_from_hopper_is_relayed_to_stream_handler_pool() {
+        let cryptde = main_cryptde();
+        let request = ClientRequestPayload_0v1 {
+            stream_key: make_meaningless_stream_key(),
+            sequenced_packet: SequencedPacket {
+                data: b"inbound data".to_vec(),
+                sequence_number: 0,
+                last_data: false,
+            },
+            target_hostname: None,
+            target_port: 0,
+            protocol: ProxyProtocol::HTTP,
+            originator_public_key: PublicKey::new(&b"originator"[..]),
+        };
+        let package = ExpiredCoresPackage::<ClientRequestPayload_0v1>::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            Some(make_wallet("consuming")),
+            make_meaningless_route(),
+            request.clone().into(),
+            0,
+        );
+        let hopper = Recorder::new();
+
+        let system = System::new("data_from_hopper_is_relayed_to_stream_handler_pool");
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = Box::new(
+            StreamHandlerPoolMock::new()
+                .process_package_parameters(&mut process_package_parameters),
+        );
+        let pool_factory = StreamHandlerPoolFactoryMock::new().make_result(pool);
+        let resolver = ResolverWrapperMock::new()
+            .lookup_ip_success(vec![IpAddr::from_str("4.3.2.1").unwrap()]);
+        let resolver_factory = ResolverWrapperFactoryMock::new().new_result(Box::new(resolver));
+        let mut subject = ProxyClient::new(ProxyClientConfig {
+            cryptde,
+            dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
+            exit_service_rate: 100,
+            exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         subject.resolver_wrapper_factory = Box::new(resolver_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -766,8 +1693,13 @@ mod tests {
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde,
             dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: rate_pack_exit(100),
             exit_byte_rate: rate_pack_exit_byte(100),
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         subject.resolver_wrapper_factory = Box::new(resolver_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -782,6 +1714,149 @@ mod tests {
         TestLogHandler::new().exists_log_containing(format!("WARN: ProxyClient: Refusing to provide exit services for CORES package with 12-byte payload without paying wallet").as_str());
     }
 
+    fn make_opaque_request_package(
+        target_port: u16,
+    ) -> ExpiredCoresPackage<ClientRequestPayload_0v1> {
+        ExpiredCoresPackage::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            Some(make_wallet("consuming")),
+            make_meaningless_route(),
+            ClientRequestPayload_0v1 {
+                stream_key: make_meaningless_stream_key(),
+                sequenced_packet: SequencedPacket {
+                    data: b"opaque data".to_vec(),
+                    sequence_number: 0,
+                    last_data: false,
+                },
+                target_hostname: Some("server.example.com".to_string()),
+                target_port,
+                protocol: ProxyProtocol::Opaque,
+                originator_public_key: PublicKey::new(&b"originator"[..]),
+            },
+            0,
+        )
+    }
+
+    #[test]
+    fn opaque_streams_to_ports_outside_the_exit_ports_are_refused_and_terminated() {
+        init_test_logging();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let system = System::new(
+            "opaque_streams_to_ports_outside_the_exit_ports_are_refused_and_terminated",
+        );
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let mut subject = make_reaping_subject(
+            Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+        );
+        subject.exit_ports_opt = Some(ExitPorts::from_str("22,8000-8100").unwrap());
+        subject.to_hopper = Some(peer_actors.hopper.from_hopper_client);
+        subject.pool = Some(Box::new(pool));
+        let package = make_opaque_request_package(25);
+        let stream_key = package.payload.stream_key;
+
+        subject.process_client_request(package);
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(process_package_parameters.lock().unwrap().len(), 0);
+        assert!(subject.stream_contexts.is_empty());
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 1);
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &make_terminating_package(stream_key, 0)
+        );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: ProxyClient: Refusing opaque stream {} to port 25: not among the --exit-ports",
+            stream_key
+        ));
+    }
+
+    #[test]
+    fn opaque_streams_to_mail_ports_are_refused_and_terminated_without_exit_ports() {
+        init_test_logging();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let system = System::new(
+            "opaque_streams_to_mail_ports_are_refused_and_terminated_without_exit_ports",
+        );
+        let peer_actors = peer_actors_builder().hopper(hopper).build();
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let mut subject = make_reaping_subject(
+            Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+        );
+        subject.exit_ports_opt = None;
+        subject.to_hopper = Some(peer_actors.hopper.from_hopper_client);
+        subject.pool = Some(Box::new(pool));
+        let package = make_opaque_request_package(587);
+        let stream_key = package.payload.stream_key;
+
+        subject.process_client_request(package);
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(process_package_parameters.lock().unwrap().len(), 0);
+        assert!(subject.stream_contexts.is_empty());
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(hopper_recording.len(), 1);
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &make_terminating_package(stream_key, 0)
+        );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: ProxyClient: Refusing opaque stream {} to port 587: mail ports must be listed in --exit-ports",
+            stream_key
+        ));
+    }
+
+    #[test]
+    fn opaque_streams_to_other_ports_are_relayed_to_stream_handler_pool_without_exit_ports() {
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let mut subject = make_reaping_subject(
+            Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+        );
+        subject.exit_ports_opt = None;
+        subject.pool = Some(Box::new(pool));
+        let package = make_opaque_request_package(22);
+        let request = package.payload.clone();
+
+        subject.process_client_request(package);
+
+        let parameter = process_package_parameters.lock().unwrap().remove(0);
+        assert_eq!(parameter, (request, Some(make_wallet("consuming"))));
+        assert_eq!(subject.stream_contexts.len(), 1);
+    }
+
+    #[test]
+    fn opaque_streams_to_ports_among_the_exit_ports_are_relayed_to_stream_handler_pool() {
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let mut subject = make_reaping_subject(
+            Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+        );
+        subject.exit_ports_opt = Some(ExitPorts::from_str("22,8000-8100").unwrap());
+        subject.pool = Some(Box::new(pool));
+        let package = make_opaque_request_package(8080);
+        let request = package.payload.clone();
+
+        subject.process_client_request(package);
+
+        let parameter = process_package_parameters.lock().unwrap().remove(0);
+        assert_eq!(parameter, (request, Some(make_wallet("consuming"))));
+        assert_eq!(subject.stream_contexts.len(), 1);
+    }
+
     #[test]
     fn does_provide_zero_hop_exit_services_with_no_paying_wallet() {
         let main_cryptde = main_cryptde();
@@ -808,7 +1883,7 @@ mod tests {
             Some(ROPSTEN_TESTNET_CONTRACT_ADDRESS),
         )
         .unwrap();
-        let package = ExpiredCoresPackage::new(
+        let package = ExpiredCoresPackage::<ClientRequestPayload_0v1>::new(
             SocketAddr::from_str("1.2.3.4:1234").unwrap(),
             None,
             zero_hop_remaining_route,
@@ -831,8 +1906,13 @@ mod tests {
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde: main_cryptde,
             dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: rate_pack_exit(100),
             exit_byte_rate: rate_pack_exit_byte(100),
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         subject.resolver_wrapper_factory = Box::new(resolver_factory);
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -858,8 +1938,13 @@ mod tests {
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde: main_cryptde(),
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         subject.stream_contexts.insert(
             stream_key.clone(),
@@ -867,6 +1952,9 @@ mod tests {
                 return_route: make_meaningless_route(),
                 payload_destination_key: PublicKey::new(&b"abcd"[..]),
                 paying_wallet: Some(make_wallet("paying")),
+                created_at: Instant::now(),
+                last_activity: Instant::now(),
+                next_sequence_number: 0,
             },
         );
         let subject_addr: Addr<ProxyClient> = subject.start();
@@ -994,8 +2082,13 @@ mod tests {
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde: main_cryptde(),
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         subject.stream_contexts.insert(
             stream_key.clone(),
@@ -1003,6 +2096,9 @@ mod tests {
                 return_route: make_meaningless_route(),
                 payload_destination_key: PublicKey::new(&b"abcd"[..]),
                 paying_wallet: None,
+                created_at: Instant::now(),
+                last_activity: Instant::now(),
+                next_sequence_number: 0,
             },
         );
         let subject_addr: Addr<ProxyClient> = subject.start();
@@ -1044,8 +2140,13 @@ mod tests {
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde: main_cryptde(),
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         subject.stream_contexts.insert(
             stream_key.clone(),
@@ -1053,6 +2154,9 @@ mod tests {
                 return_route: make_meaningless_route(),
                 payload_destination_key: PublicKey::new(&[]),
                 paying_wallet: Some(make_wallet("consuming")),
+                created_at: Instant::now(),
+                last_activity: Instant::now(),
+                next_sequence_number: 0,
             },
         );
         let subject_addr: Addr<ProxyClient> = subject.start();
@@ -1092,8 +2196,13 @@ mod tests {
         let mut subject = ProxyClient::new(ProxyClientConfig {
             cryptde,
             dns_servers: vec![SocketAddr::from_str("8.7.6.5:4321").unwrap()],
+            dns_protocol: DnsProtocol::Udp,
             exit_service_rate: 100,
             exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
         });
         let mut process_package_params_arc = Arc::new(Mutex::new(vec![]));
         let pool = StreamHandlerPoolMock::new()
@@ -1110,6 +2219,9 @@ mod tests {
                 return_route: old_return_route,
                 payload_destination_key: originator_public_key.clone(),
                 paying_wallet: Some(make_wallet("consuming")),
+                created_at: Instant::now(),
+                last_activity: Instant::now(),
+                next_sequence_number: 0,
             },
         );
         subject.stream_handler_pool_factory = Box::new(pool_factory);
@@ -1133,7 +2245,7 @@ mod tests {
         };
 
         subject_addr
-            .try_send(ExpiredCoresPackage::new(
+            .try_send(ExpiredCoresPackage::<ClientRequestPayload_0v1>::new(
                 SocketAddr::from_str("2.3.4.5:1235").unwrap(),
                 Some(make_wallet("gnimusnoc")),
                 new_return_route.clone(),
@@ -1191,4 +2303,315 @@ mod tests {
             }
         )
     }
+
+    fn make_exit_limits(exit: &str) -> BandwidthLimits {
+        BandwidthLimits {
+            routing: ServiceBandwidthLimits::default(),
+            exit: ServiceBandwidthLimits::from_str(exit).unwrap(),
+        }
+    }
+
+    fn make_throttled_subject(
+        throttle: Arc<BandwidthThrottle>,
+        pool: StreamHandlerPoolMock,
+    ) -> ProxyClient {
+        let mut subject = ProxyClient::new(ProxyClientConfig {
+            cryptde: main_cryptde(),
+            dns_servers: dnss(),
+            dns_protocol: DnsProtocol::Udp,
+            exit_service_rate: 100,
+            exit_byte_rate: 200,
+            exit_idle_timeout: Duration::from_secs(DEFAULT_EXIT_IDLE_TIMEOUT_SECS),
+            exit_absolute_timeout: Duration::from_secs(DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS),
+            exit_ports_opt: None,
+            local_destinations_allowed: false,
+        });
+        subject.resolver_wrapper_factory = Box::new(
+            ResolverWrapperFactoryMock::new().new_result(Box::new(ResolverWrapperMock::new())),
+        );
+        subject.stream_handler_pool_factory =
+            Box::new(StreamHandlerPoolFactoryMock::new().make_result(Box::new(pool)));
+        subject.bandwidth_throttle = throttle;
+        subject
+    }
+
+    fn make_throttled_request_package() -> ExpiredCoresPackage<ClientRequestPayload_0v1> {
+        let request = ClientRequestPayload_0v1 {
+            stream_key: make_meaningless_stream_key(),
+            sequenced_packet: SequencedPacket {
+                data: b"inbound data".to_vec(),
+                sequence_number: 0,
+                last_data: false,
+            },
+            target_hostname: None,
+            target_port: 0,
+            protocol: ProxyProtocol::HTTP,
+            originator_public_key: PublicKey::new(&b"originator"[..]),
+        };
+        ExpiredCoresPackage::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            Some(make_wallet("consuming")),
+            make_meaningless_route(),
+            request.into(),
+            0,
+        )
+    }
+
+    #[test]
+    fn request_that_would_wait_too_long_for_exit_bandwidth_is_held_and_its_originator_paused() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_exit_limits("1:0:0:0"));
+        throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Download(IpAddr::from_str("9.9.9.9").unwrap()),
+            10 * 1024,
+            Instant::now(),
+        );
+        let package = make_throttled_request_package();
+        let stream_key = package.payload.stream_key;
+        let return_route = package.remaining_route.clone();
+        let originator_key = package.payload.originator_public_key.clone();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let system = System::new(
+            "request_that_would_wait_too_long_for_exit_bandwidth_is_held_and_its_originator_paused",
+        );
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let subject = make_throttled_subject(throttle.clone(), pool);
+        let subject_addr: Addr<ProxyClient> = subject.start();
+        subject_addr
+            .try_send(BindMessage {
+                peer_actors: peer_actors_builder().hopper(hopper).build(),
+            })
+            .unwrap();
+
+        subject_addr.try_send(package).unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(process_package_parameters.lock().unwrap().len(), 0);
+        assert_eq!(throttle.delayed_bytes(), 10 * 1024 + 12);
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &IncipientCoresPackage::new(
+                main_cryptde(),
+                return_route,
+                StreamPressure_0v1::new(stream_key, Pressure::Pause).into(),
+                &originator_key,
+            )
+            .unwrap()
+        );
+        assert_eq!(hopper_recording.len(), 1);
+    }
+
+    #[test]
+    fn originator_paused_for_exit_bandwidth_is_resumed_once_its_request_is_served() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_exit_limits("1:0:0:0"));
+        throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Download(IpAddr::from_str("9.9.9.9").unwrap()),
+            1024 + 200,
+            Instant::now(),
+        );
+        let package = make_throttled_request_package();
+        let request = package.payload.clone();
+        let return_route = package.remaining_route.clone();
+        let originator_key = package.payload.originator_public_key.clone();
+        let (hopper, _, hopper_recording_arc) = make_recorder();
+        let system = System::new(
+            "originator_paused_for_exit_bandwidth_is_resumed_once_its_request_is_served",
+        );
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let mut subject = make_throttled_subject(throttle, pool);
+        subject.max_bandwidth_delay = Duration::from_millis(100);
+        let subject_addr: Addr<ProxyClient> = subject.start();
+        subject_addr
+            .try_send(BindMessage {
+                peer_actors: peer_actors_builder().hopper(hopper).build(),
+            })
+            .unwrap();
+
+        subject_addr.try_send(package).unwrap();
+
+        actix::spawn(
+            Delay::new(Instant::now() + Duration::from_millis(500)).then(|_| {
+                System::current().stop();
+                Ok(())
+            }),
+        );
+        system.run();
+        assert_eq!(
+            *process_package_parameters.lock().unwrap(),
+            vec![(request.clone(), Some(make_wallet("consuming")))]
+        );
+        let hopper_recording = hopper_recording_arc.lock().unwrap();
+        let expected_package = |pressure| {
+            IncipientCoresPackage::new(
+                main_cryptde(),
+                return_route.clone(),
+                StreamPressure_0v1::new(request.stream_key, pressure).into(),
+                &originator_key,
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(0),
+            &expected_package(Pressure::Pause)
+        );
+        assert_eq!(
+            hopper_recording.get_record::<IncipientCoresPackage>(1),
+            &expected_package(Pressure::Resume)
+        );
+        assert_eq!(hopper_recording.len(), 2);
+    }
+
+    #[test]
+    fn request_over_exit_bandwidth_is_held_back() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_exit_limits("0:0:1:0"));
+        throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Download(IpAddr::from_str("1.2.3.4").unwrap()),
+            2048,
+            Instant::now(),
+        );
+        let system = System::new("request_over_exit_bandwidth_is_held_back");
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let subject = make_throttled_subject(throttle.clone(), pool);
+        let subject_addr: Addr<ProxyClient> = subject.start();
+        subject_addr
+            .try_send(BindMessage {
+                peer_actors: peer_actors_builder().build(),
+            })
+            .unwrap();
+
+        subject_addr
+            .try_send(make_throttled_request_package())
+            .unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(process_package_parameters.lock().unwrap().len(), 0);
+        assert_eq!(throttle.delayed_bytes(), 2048 + 12);
+    }
+
+    #[test]
+    fn held_back_request_is_processed_once_its_exit_bandwidth_is_available() {
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_exit_limits("1:0:0:0"));
+        throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Download(IpAddr::from_str("9.9.9.9").unwrap()),
+            1024 + 100,
+            Instant::now(),
+        );
+        let package = make_throttled_request_package();
+        let request = package.payload.clone();
+        let system =
+            System::new("held_back_request_is_processed_once_its_exit_bandwidth_is_available");
+        let mut process_package_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .process_package_parameters(&mut process_package_parameters);
+        let subject = make_throttled_subject(throttle, pool);
+        let subject_addr: Addr<ProxyClient> = subject.start();
+        subject_addr
+            .try_send(BindMessage {
+                peer_actors: peer_actors_builder().build(),
+            })
+            .unwrap();
+
+        subject_addr.try_send(package).unwrap();
+
+        actix::spawn(
+            Delay::new(Instant::now() + Duration::from_millis(500)).then(|_| {
+                System::current().stop();
+                Ok(())
+            }),
+        );
+        system.run();
+        assert_eq!(
+            *process_package_parameters.lock().unwrap(),
+            vec![(request, Some(make_wallet("consuming")))]
+        );
+    }
+
+    #[test]
+    fn response_over_exit_bandwidth_pauses_its_stream_until_the_bandwidth_is_paid_for() {
+        let cryptde = main_cryptde();
+        let first_hop_key = PublicKey::new(&[1, 2, 3, 4]);
+        let stream_key = make_meaningless_stream_key();
+        let throttle = Arc::new(BandwidthThrottle::new());
+        throttle.set_limits(make_exit_limits("0:0:0:1"));
+        throttle.charge(
+            BandwidthService::Exit,
+            Traffic::Upload(first_hop_key.clone()),
+            1024,
+            Instant::now(),
+        );
+        let system = System::new(
+            "response_over_exit_bandwidth_pauses_its_stream_until_the_bandwidth_is_paid_for",
+        );
+        let mut apply_read_pressure_parameters = Arc::new(Mutex::new(vec![]));
+        let pool = StreamHandlerPoolMock::new()
+            .apply_read_pressure_parameters(&mut apply_read_pressure_parameters);
+        let mut subject = make_throttled_subject(throttle, pool);
+        subject.stream_contexts.insert(
+            stream_key,
+            StreamContext {
+                return_route: Route::one_way(
+                    RouteSegment::new(
+                        vec![cryptde.public_key(), &first_hop_key],
+                        Component::ProxyServer,
+                    ),
+                    cryptde,
+                    None,
+                    None,
+                )
+                .unwrap(),
+                payload_destination_key: PublicKey::new(&b"abcd"[..]),
+                paying_wallet: None,
+                created_at: Instant::now(),
+                last_activity: Instant::now(),
+                next_sequence_number: 0,
+            },
+        );
+        let subject_addr: Addr<ProxyClient> = subject.start();
+        subject_addr
+            .try_send(BindMessage {
+                peer_actors: peer_actors_builder().build(),
+            })
+            .unwrap();
+
+        subject_addr
+            .try_send(InboundServerData {
+                stream_key,
+                last_data: false,
+                sequence_number: 0,
+                source: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+                data: vec![0; 100],
+            })
+            .unwrap();
+
+        actix::spawn(
+            Delay::new(Instant::now() + Duration::from_millis(500)).then(|_| {
+                System::current().stop();
+                Ok(())
+            }),
+        );
+        system.run();
+        assert_eq!(
+            *apply_read_pressure_parameters.lock().unwrap(),
+            vec![
+                (stream_key, PauseReason::Bandwidth, Pressure::Pause),
+                (stream_key, PauseReason::Bandwidth, Pressure::Resume)
+            ]
+        );
+    }
 }
@@ -1,4 +1,8 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use crate::proxy_client::dns_cache::DnsCache;
+use futures::future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::prelude::Future;
 use trust_dns_resolver::config::ResolverConfig;
 use trust_dns_resolver::config::ResolverOpts;
@@ -36,3 +40,215 @@ impl ResolverWrapperFactory for ResolverWrapperFactoryReal {
         Box::new(ResolverWrapperReal { delegate })
     }
 }
+
+// Puts a DnsCache in front of another ResolverWrapper. Every stream the StreamHandlerPool opens
+// goes through the same CachingResolverWrapper, so they all share the one cache.
+pub struct CachingResolverWrapper {
+    delegate: Box<dyn ResolverWrapper>,
+    cache: Arc<Mutex<DnsCache>>,
+}
+
+impl ResolverWrapper for CachingResolverWrapper {
+    fn lookup_ip(&self, host: &str) -> Box<WrappedLookupIpFuture> {
+        let cached_opt = self
+            .cache
+            .lock()
+            .expect("DNS cache is poisoned")
+            .get(host, Instant::now());
+        match cached_opt {
+            Some(result) => Box::new(future::result(result)),
+            None => {
+                let cache = self.cache.clone();
+                let host = host.to_string();
+                Box::new(self.delegate.lookup_ip(&host).then(move |result| {
+                    cache.lock().expect("DNS cache is poisoned").insert(
+                        &host,
+                        &result,
+                        Instant::now(),
+                    );
+                    result
+                }))
+            }
+        }
+    }
+}
+
+impl CachingResolverWrapper {
+    pub fn new(
+        delegate: Box<dyn ResolverWrapper>,
+        capacity: usize,
+        negative_ttl: Duration,
+    ) -> CachingResolverWrapper {
+        CachingResolverWrapper {
+            delegate,
+            cache: Arc::new(Mutex::new(DnsCache::new(capacity, negative_ttl))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy_client::dns_cache::NEGATIVE_DNS_CACHE_TTL;
+    use crate::proxy_client::local_test_utils::ResolverWrapperMock;
+    use futures::future::lazy;
+    use std::io::{ErrorKind, Read};
+    use std::net::{IpAddr, SocketAddr, TcpListener};
+    use std::str::FromStr;
+    use std::thread;
+    use trust_dns_proto::op::Query;
+    use trust_dns_resolver::config::{NameServerConfig, Protocol};
+    use trust_dns_resolver::error::ResolveErrorKind;
+
+    #[test]
+    fn caching_resolver_wrapper_answers_repeated_lookups_from_the_cache() {
+        let lookup_ip_parameters = Arc::new(Mutex::new(vec![]));
+        let delegate = ResolverWrapperMock::new()
+            .lookup_ip_success(vec![IpAddr::from_str("1.2.3.4").unwrap()])
+            .lookup_ip_parameters(&lookup_ip_parameters);
+        let subject = CachingResolverWrapper::new(Box::new(delegate), 10, NEGATIVE_DNS_CACHE_TTL);
+
+        let first_result = subject.lookup_ip("booga.com").wait().unwrap();
+        let second_result = subject.lookup_ip("BOOGA.com").wait().unwrap();
+
+        let expected_ips = vec![IpAddr::from_str("1.2.3.4").unwrap()];
+        assert_eq!(first_result.iter().collect::<Vec<IpAddr>>(), expected_ips);
+        assert_eq!(second_result.iter().collect::<Vec<IpAddr>>(), expected_ips);
+        assert_eq!(
+            *lookup_ip_parameters.lock().unwrap(),
+            vec!["booga.com".to_string()]
+        );
+    }
+
+    #[test]
+    fn caching_resolver_wrapper_remembers_that_a_host_does_not_exist() {
+        let lookup_ip_parameters = Arc::new(Mutex::new(vec![]));
+        let delegate = ResolverWrapperMock::new()
+            .lookup_ip_failure(
+                ResolveErrorKind::NoRecordsFound {
+                    query: Query::default(),
+                    valid_until: None,
+                }
+                .into(),
+            )
+            .lookup_ip_parameters(&lookup_ip_parameters);
+        let subject = CachingResolverWrapper::new(Box::new(delegate), 10, NEGATIVE_DNS_CACHE_TTL);
+
+        let first_result = subject.lookup_ip("nonexistent.com").wait();
+        let second_result = subject.lookup_ip("nonexistent.com").wait();
+
+        assert_eq!(first_result.is_err(), true);
+        assert_eq!(
+            second_result.err().unwrap().to_string(),
+            first_result.err().unwrap().to_string()
+        );
+        assert_eq!(lookup_ip_parameters.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn caching_resolver_wrapper_asks_again_after_a_timeout() {
+        let lookup_ip_parameters = Arc::new(Mutex::new(vec![]));
+        let delegate = ResolverWrapperMock::new()
+            .lookup_ip_failure(ResolveErrorKind::Timeout.into())
+            .lookup_ip_success(vec![IpAddr::from_str("1.2.3.4").unwrap()])
+            .lookup_ip_parameters(&lookup_ip_parameters);
+        let subject = CachingResolverWrapper::new(Box::new(delegate), 10, NEGATIVE_DNS_CACHE_TTL);
+
+        let first_result = subject.lookup_ip("booga.com").wait();
+        let second_result = subject.lookup_ip("booga.com").wait().unwrap();
+
+        assert_eq!(first_result.is_err(), true);
+        assert_eq!(
+            second_result.iter().collect::<Vec<IpAddr>>(),
+            vec![IpAddr::from_str("1.2.3.4").unwrap()]
+        );
+        assert_eq!(lookup_ip_parameters.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn real_resolver_opens_dns_over_tls_to_the_named_server() {
+        let client_hello = first_bytes_sent_to_stand_in_resolver(Protocol::Tls, "dot.example.com");
+
+        assert_eq!(
+            client_hello[0], 0x16,
+            "not a TLS handshake: {:?}",
+            client_hello
+        );
+        assert_eq!(
+            contains(&client_hello, b"dot.example.com"),
+            true,
+            "no server name: {:?}",
+            client_hello
+        );
+    }
+
+    #[test]
+    fn real_resolver_opens_dns_over_https_to_the_named_server() {
+        let client_hello =
+            first_bytes_sent_to_stand_in_resolver(Protocol::Https, "doh.example.com");
+
+        assert_eq!(
+            client_hello[0], 0x16,
+            "not a TLS handshake: {:?}",
+            client_hello
+        );
+        assert_eq!(
+            contains(&client_hello, b"doh.example.com"),
+            true,
+            "no server name: {:?}",
+            client_hello
+        );
+        assert_eq!(
+            contains(&client_hello, b"\x02h2"),
+            true,
+            "no HTTP/2 ALPN: {:?}",
+            client_hello
+        );
+    }
+
+    // Points a real resolver at a local stand-in that accepts the connection and records what the
+    // resolver says first; the stand-in can't finish a handshake, so the lookup itself fails.
+    fn first_bytes_sent_to_stand_in_resolver(protocol: Protocol, tls_dns_name: &str) -> Vec<u8> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let mut config = ResolverConfig::new();
+        config.add_name_server(NameServerConfig {
+            socket_addr: listener.local_addr().unwrap(),
+            protocol,
+            tls_dns_name: Some(tls_dns_name.to_string()),
+        });
+        let mut options = ResolverOpts::default();
+        options.timeout = Duration::from_secs(2);
+        options.attempts = 1;
+        thread::spawn(move || {
+            tokio::run(lazy(move || {
+                let subject = ResolverWrapperFactoryReal {}.make(config, options);
+                tokio::spawn(subject.lookup_ip("booga.com").then(|_| Ok(())));
+                Ok(())
+            }))
+        });
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let (mut stream, _): (_, SocketAddr) = loop {
+            match listener.accept() {
+                Ok(pair) => break pair,
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock && Instant::now() < deadline => {
+                    thread::sleep(Duration::from_millis(10))
+                }
+                Err(e) => panic!("Resolver never connected: {:?}", e),
+            }
+        };
+        stream.set_nonblocking(false).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut buf = [0u8; 4096];
+        let len = stream.read(&mut buf).unwrap();
+        buf[..len].to_vec()
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+}
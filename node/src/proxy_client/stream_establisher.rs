@@ -7,25 +7,37 @@ use crate::sub_lib::channel_wrappers::FuturesChannelFactoryReal;
 use crate::sub_lib::channel_wrappers::SenderWrapper;
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::logger::Logger;
-use crate::sub_lib::proxy_client::{InboundServerData, ProxyClientSubs};
+use crate::sub_lib::proxy_client::{InboundServerData, ProxyClientSubs, StreamPressure_0v1};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
+use crate::sub_lib::stream_buffer::ReadSwitch;
 use crate::sub_lib::stream_connector::StreamConnector;
 use crate::sub_lib::stream_connector::StreamConnectorReal;
 use crate::sub_lib::stream_key::StreamKey;
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
 use actix::Recipient;
+use futures::sync::oneshot;
 use std::io;
 use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::sync::mpsc::Sender;
 
+// A new stream's writer channel, the means of telling its reader to stop, and the means of
+// telling its reader to pause.
+pub type StreamAddition = (
+    StreamKey,
+    Box<dyn SenderWrapper<SequencedPacket>>,
+    oneshot::Sender<()>,
+    ReadSwitch,
+);
+
 pub struct StreamEstablisher {
     pub cryptde: &'static dyn CryptDE,
-    pub stream_adder_tx: Sender<(StreamKey, Box<dyn SenderWrapper<SequencedPacket>>)>,
+    pub stream_adder_tx: Sender<StreamAddition>,
     pub stream_killer_tx: Sender<(StreamKey, u64)>,
     pub stream_connector: Box<dyn StreamConnector>,
     pub proxy_client_sub: Recipient<InboundServerData>,
+    pub stream_pressure_sub: Recipient<StreamPressure_0v1>,
     pub logger: Logger,
     pub channel_factory: Box<dyn FuturesChannelFactory<SequencedPacket>>,
 }
@@ -38,6 +50,7 @@ impl Clone for StreamEstablisher {
             stream_killer_tx: self.stream_killer_tx.clone(),
             stream_connector: Box::new(StreamConnectorReal {}),
             proxy_client_sub: self.proxy_client_sub.clone(),
+            stream_pressure_sub: self.stream_pressure_sub.clone(),
             logger: self.logger.clone(),
             channel_factory: Box::new(FuturesChannelFactoryReal {}),
         }
@@ -58,13 +71,22 @@ impl StreamEstablisher {
             &self.logger,
         )?;
 
-        self.spawn_stream_reader(
+        let read_switch = ReadSwitch::new();
+        let reaper_tx = self.spawn_stream_reader(
             &payload.clone(),
             connection_info.reader,
             connection_info.peer_addr,
+            read_switch.clone(),
         )?;
 
         let (tx_to_write, rx_to_write) = self.channel_factory.make(connection_info.peer_addr);
+        let stream_pressure_sub = self.stream_pressure_sub.clone();
+        let stream_key = payload.stream_key;
+        tx_to_write.buffer().on_pressure(Box::new(move |pressure| {
+            stream_pressure_sub
+                .try_send(StreamPressure_0v1::new(stream_key, pressure))
+                .expect("ProxyClient is dead")
+        }));
         let stream_writer = StreamWriter::new(
             connection_info.writer,
             connection_info.peer_addr,
@@ -74,7 +96,12 @@ impl StreamEstablisher {
         tokio::spawn(stream_writer);
 
         self.stream_adder_tx
-            .send((payload.stream_key, tx_to_write.clone()))
+            .send((
+                payload.stream_key,
+                tx_to_write.clone(),
+                reaper_tx,
+                read_switch,
+            ))
             .expect("StreamHandlerPool died");
         Ok(tx_to_write)
     }
@@ -84,17 +111,21 @@ impl StreamEstablisher {
         payload: &ClientRequestPayload_0v1,
         read_stream: Box<dyn ReadHalfWrapper>,
         peer_addr: SocketAddr,
-    ) -> io::Result<()> {
+        read_switch: ReadSwitch,
+    ) -> io::Result<oneshot::Sender<()>> {
+        let (reaper_tx, reaper_rx) = oneshot::channel();
         let stream_reader = StreamReader::new(
             payload.stream_key,
             self.proxy_client_sub.clone(),
             read_stream,
             self.stream_killer_tx.clone(),
             peer_addr,
+            reaper_rx,
+            read_switch,
         );
         debug!(self.logger, "Spawning StreamReader for {}", peer_addr);
         tokio::spawn(stream_reader);
-        Ok(())
+        Ok(reaper_tx)
     }
 }
 
@@ -104,7 +135,7 @@ pub trait StreamEstablisherFactory: Send {
 
 pub struct StreamEstablisherFactoryReal {
     pub cryptde: &'static dyn CryptDE,
-    pub stream_adder_tx: Sender<(StreamKey, Box<dyn SenderWrapper<SequencedPacket>>)>,
+    pub stream_adder_tx: Sender<StreamAddition>,
     pub stream_killer_tx: Sender<(StreamKey, u64)>,
     pub proxy_client_subs: ProxyClientSubs,
     pub logger: Logger,
@@ -118,6 +149,7 @@ impl StreamEstablisherFactory for StreamEstablisherFactoryReal {
             stream_killer_tx: self.stream_killer_tx.clone(),
             stream_connector: Box::new(StreamConnectorReal {}),
             proxy_client_sub: self.proxy_client_subs.inbound_server_data.clone(),
+            stream_pressure_sub: self.proxy_client_subs.stream_pressure.clone(),
             logger: self.logger.clone(),
             channel_factory: Box::new(FuturesChannelFactoryReal {}),
         }
@@ -128,6 +160,7 @@ impl StreamEstablisherFactory for StreamEstablisherFactoryReal {
 mod tests {
     use super::*;
     use crate::sub_lib::proxy_server::ProxyProtocol;
+    use crate::sub_lib::stream_buffer::{Pressure, STREAM_BUFFER_PAUSE_LEVEL};
     use crate::test_utils::main_cryptde;
     use crate::test_utils::make_meaningless_stream_key;
     use crate::test_utils::recorder::make_recorder;
@@ -137,6 +170,7 @@ mod tests {
     use actix::System;
     use futures::future::lazy;
     use std::io::ErrorKind;
+    use std::net::Ipv4Addr;
     use std::net::SocketAddr;
     use std::str::FromStr;
     use std::sync::mpsc;
@@ -151,14 +185,17 @@ mod tests {
             let system = System::new("spawn_stream_reader_handles_data");
             let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
             sub_tx
-                .send(peer_actors.proxy_client.inbound_server_data)
+                .send((
+                    peer_actors.proxy_client.inbound_server_data,
+                    peer_actors.proxy_client.stream_pressure,
+                ))
                 .expect("Unable to send inbound_server_data sub from proxy_client to test");
             system.run();
         });
 
         let (ibsd_tx, ibsd_rx) = mpsc::channel();
         let test_future = lazy(move || {
-            let proxy_client_sub = sub_rx.recv().unwrap();
+            let (proxy_client_sub, stream_pressure_sub) = sub_rx.recv().unwrap();
 
             let (stream_adder_tx, _stream_adder_rx) = mpsc::channel();
             let (stream_killer_tx, _) = mpsc::channel();
@@ -175,6 +212,7 @@ mod tests {
                 stream_killer_tx,
                 stream_connector: Box::new(StreamConnectorMock::new()), // only used in "establish_stream"
                 proxy_client_sub,
+                stream_pressure_sub,
                 logger: Logger::new("ProxyClient"),
                 channel_factory: Box::new(FuturesChannelFactoryReal {}),
             };
@@ -194,6 +232,7 @@ mod tests {
                     },
                     read_stream,
                     SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+                    ReadSwitch::new(),
                 )
                 .expect("spawn_stream_reader () failed");
 
@@ -223,4 +262,83 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn stream_to_a_slow_server_asks_the_originator_to_pause() {
+        let (proxy_client, proxy_client_awaiter, proxy_client_recording_arc) = make_recorder();
+        let (sub_tx, sub_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let system = System::new("stream_to_a_slow_server_asks_the_originator_to_pause");
+            let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+            sub_tx
+                .send((
+                    peer_actors.proxy_client.inbound_server_data,
+                    peer_actors.proxy_client.stream_pressure,
+                ))
+                .expect("Unable to send subs from proxy_client to test");
+            system.run();
+        });
+        let (proxy_client_sub, stream_pressure_sub) = sub_rx.recv().unwrap();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:80").unwrap();
+        // The server never reads what's written to it, and never has anything to say.
+        let stream_connector = StreamConnectorMock::new().connection(
+            SocketAddr::from_str("127.0.0.1:5678").unwrap(),
+            peer_addr,
+            vec![(vec![], Ok(Async::NotReady))],
+            (0..10).map(|_| Ok(Async::NotReady)).collect(),
+        );
+        let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
+        let mut subject = StreamEstablisher {
+            cryptde: main_cryptde(),
+            stream_adder_tx,
+            stream_killer_tx: mpsc::channel().0,
+            stream_connector: Box::new(stream_connector),
+            proxy_client_sub,
+            stream_pressure_sub,
+            logger: Logger::new("ProxyClient"),
+            channel_factory: Box::new(FuturesChannelFactoryReal {}),
+        };
+        let payload = ClientRequestPayload_0v1 {
+            stream_key: make_meaningless_stream_key(),
+            sequenced_packet: SequencedPacket::new(vec![], 0, false),
+            target_hostname: Some("that.try".to_string()),
+            target_port: 80,
+            protocol: ProxyProtocol::HTTP,
+            originator_public_key: main_cryptde().public_key().clone(),
+        };
+
+        thread::spawn(move || {
+            tokio::run(lazy(move || {
+                let tx_to_write = subject
+                    .establish_stream(
+                        &payload,
+                        vec![IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))],
+                        "that.try".to_string(),
+                    )
+                    .unwrap();
+                tx_to_write
+                    .buffer()
+                    .reserve(STREAM_BUFFER_PAUSE_LEVEL)
+                    .unwrap();
+                tx_to_write
+                    .unbounded_send(SequencedPacket::new(
+                        vec![0u8; STREAM_BUFFER_PAUSE_LEVEL],
+                        0,
+                        false,
+                    ))
+                    .unwrap();
+                Ok(())
+            }));
+        });
+
+        proxy_client_awaiter.await_message_count(1);
+        let proxy_client_recording = proxy_client_recording_arc.lock().unwrap();
+        assert_eq!(
+            proxy_client_recording.get_record::<StreamPressure_0v1>(0),
+            &StreamPressure_0v1::new(make_meaningless_stream_key(), Pressure::Pause)
+        );
+        let (stream_key, _, _, read_switch) = stream_adder_rx.recv().unwrap();
+        assert_eq!(stream_key, make_meaningless_stream_key());
+        assert_eq!(read_switch.is_paused(), false);
+    }
 }
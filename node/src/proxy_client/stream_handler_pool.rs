@@ -3,20 +3,25 @@
 
 use crate::proxy_client::resolver_wrapper::ResolverWrapper;
 use crate::proxy_client::stream_establisher::StreamEstablisherFactoryReal;
-use crate::proxy_client::stream_establisher::{StreamEstablisher, StreamEstablisherFactory};
+use crate::proxy_client::stream_establisher::{
+    StreamAddition, StreamEstablisher, StreamEstablisherFactory,
+};
 use crate::sub_lib::accountant::ReportExitServiceProvidedMessage;
 use crate::sub_lib::channel_wrappers::SenderWrapper;
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::{metrics, Metrics};
 use crate::sub_lib::proxy_client::{error_socket_addr, ProxyClientSubs};
 use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, InboundServerData};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
+use crate::sub_lib::stream_buffer::{PauseReason, Pressure, ReadSwitch};
 use crate::sub_lib::stream_key::StreamKey;
 use crate::sub_lib::wallet::Wallet;
 use actix::Recipient;
 use futures::future;
 use futures::future::Future;
+use futures::sync::oneshot;
 use std::collections::HashMap;
 use std::io;
 use std::net::{AddrParseError, IpAddr, SocketAddr};
@@ -27,28 +32,34 @@ use std::sync::Arc;
 use std::sync::Mutex;
 use tokio::prelude::future::FutureResult;
 use tokio::prelude::future::{err, ok};
-use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::error::{ResolveError, ResolveErrorKind};
 use trust_dns_resolver::lookup_ip::LookupIp;
 
 pub trait StreamHandlerPool {
     fn process_package(&self, payload: ClientRequestPayload_0v1, paying_wallet: Option<Wallet>);
+    fn reap_stream(&self, stream_key: &StreamKey);
+    fn apply_read_pressure(&self, stream_key: &StreamKey, reason: PauseReason, pressure: Pressure);
 }
 
 pub struct StreamHandlerPoolReal {
     inner: Arc<Mutex<StreamHandlerPoolRealInner>>,
-    stream_adder_rx: Receiver<(StreamKey, Box<dyn SenderWrapper<SequencedPacket>>)>,
+    stream_adder_rx: Receiver<StreamAddition>,
     stream_killer_rx: Receiver<(StreamKey, u64)>,
+    metrics: Arc<Metrics>,
 }
 
 struct StreamHandlerPoolRealInner {
     accountant_sub: Recipient<ReportExitServiceProvidedMessage>,
     proxy_client_subs: ProxyClientSubs,
     stream_writer_channels: HashMap<StreamKey, Box<dyn SenderWrapper<SequencedPacket>>>,
+    stream_reapers: HashMap<StreamKey, oneshot::Sender<()>>,
+    read_switches: HashMap<StreamKey, ReadSwitch>,
     resolver: Box<dyn ResolverWrapper>,
     logger: Logger,
     establisher_factory: Box<dyn StreamEstablisherFactory>,
     exit_service_rate: u64,
     exit_byte_rate: u64,
+    local_destinations_allowed: bool,
 }
 
 impl StreamHandlerPool for StreamHandlerPoolReal {
@@ -56,6 +67,57 @@ impl StreamHandlerPool for StreamHandlerPoolReal {
         self.do_housekeeping();
         Self::process_package(payload, paying_wallet, self.inner.clone())
     }
+
+    fn reap_stream(&self, stream_key: &StreamKey) {
+        self.do_housekeeping();
+        let mut inner = self.inner.lock().expect("Stream handler pool is poisoned");
+        // Dropping the writer channel stops the StreamWriter; firing the reaper stops the
+        // StreamReader. Once both are gone, the connection to the server is closed.
+        if let Some(writer_channel) = inner.stream_writer_channels.remove(stream_key) {
+            debug!(
+                inner.logger,
+                "Reaping StreamWriter to {} for key {:?}",
+                writer_channel.peer_addr(),
+                stream_key
+            );
+        }
+        inner.read_switches.remove(stream_key);
+        match inner.stream_reapers.remove(stream_key) {
+            Some(reaper) => {
+                let _ = reaper.send(());
+                debug!(
+                    inner.logger,
+                    "Reaping StreamReader for key {:?}", stream_key
+                )
+            }
+            None => debug!(
+                inner.logger,
+                "Tried to reap StreamReader for key {:?}, but it was already gone", stream_key
+            ),
+        }
+        self.report_stream_count(&inner);
+    }
+
+    fn apply_read_pressure(&self, stream_key: &StreamKey, reason: PauseReason, pressure: Pressure) {
+        self.do_housekeeping();
+        let inner = self.inner.lock().expect("Stream handler pool is poisoned");
+        match inner.read_switches.get(stream_key) {
+            Some(read_switch) => {
+                debug!(
+                    inner.logger,
+                    "Applying {:?} for {:?} to StreamReader for key {:?}",
+                    pressure,
+                    reason,
+                    stream_key
+                );
+                read_switch.apply(reason, pressure)
+            }
+            None => debug!(
+                inner.logger,
+                "No StreamReader for key {:?} to apply {:?} to; ignoring", stream_key, pressure
+            ),
+        }
+    }
 }
 
 type StreamEstablisherResult =
@@ -69,6 +131,7 @@ impl StreamHandlerPoolReal {
         proxy_client_subs: ProxyClientSubs,
         exit_service_rate: u64,
         exit_byte_rate: u64,
+        local_destinations_allowed: bool,
     ) -> StreamHandlerPoolReal {
         let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
         let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
@@ -84,13 +147,17 @@ impl StreamHandlerPoolReal {
                 accountant_sub,
                 proxy_client_subs,
                 stream_writer_channels: HashMap::new(),
+                stream_reapers: HashMap::new(),
+                read_switches: HashMap::new(),
                 resolver,
                 logger: Logger::new("ProxyClient"),
                 exit_service_rate,
                 exit_byte_rate,
+                local_destinations_allowed,
             })),
             stream_adder_rx,
             stream_killer_rx,
+            metrics: metrics(),
         }
     }
 
@@ -158,6 +225,8 @@ impl StreamHandlerPoolReal {
                 sender_wrapper.peer_addr()
             );
         }
+        inner.stream_reapers.remove(stream_key);
+        inner.read_switches.remove(stream_key);
         Self::send_terminating_package(
             stream_key,
             source,
@@ -272,15 +341,84 @@ impl StreamHandlerPoolReal {
         inner_arc: Arc<Mutex<StreamHandlerPoolRealInner>>,
         target_hostname: String,
     ) -> StreamEstablisherResult {
+        let local_destinations_allowed = Self::local_destinations_allowed(&inner_arc);
+        let logger = Self::make_logger_copy(&inner_arc);
         let mut stream_establisher = StreamHandlerPoolReal::make_establisher(inner_arc);
         Box::new(
             future::lazy(move || {
-                stream_establisher.establish_stream(&payload, vec![ip_addr], target_hostname)
+                let ip_addrs = Self::remove_local_destinations(
+                    &target_hostname,
+                    vec![ip_addr],
+                    local_destinations_allowed,
+                    &logger,
+                )?;
+                stream_establisher.establish_stream(&payload, ip_addrs, target_hostname)
             })
             .map_err(|io_error| format!("Could not establish stream: {:?}", io_error)),
         )
     }
 
+    fn local_destinations_allowed(inner_arc: &Arc<Mutex<StreamHandlerPoolRealInner>>) -> bool {
+        let inner = inner_arc.lock().expect("Stream handler pool is poisoned");
+        inner.local_destinations_allowed
+    }
+
+    // An exit carries streams only to public addresses, so that nobody can use it to reach the
+    // services on its own host or its LAN. This is checked after name resolution, because a
+    // public name can resolve to a local address.
+    fn remove_local_destinations(
+        target_hostname: &str,
+        ip_addrs: Vec<IpAddr>,
+        local_destinations_allowed: bool,
+        logger: &Logger,
+    ) -> io::Result<Vec<IpAddr>> {
+        if local_destinations_allowed {
+            return Ok(ip_addrs);
+        }
+        let (local_ip_addrs, public_ip_addrs): (Vec<IpAddr>, Vec<IpAddr>) =
+            ip_addrs.into_iter().partition(Self::is_local_destination);
+        if !local_ip_addrs.is_empty() {
+            warning!(
+                logger,
+                "Refusing to carry a stream for {} to local addresses {:?}",
+                target_hostname,
+                local_ip_addrs
+            );
+        }
+        if public_ip_addrs.is_empty() {
+            Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("{} has no public address", target_hostname),
+            ))
+        } else {
+            Ok(public_ip_addrs)
+        }
+    }
+
+    fn is_local_destination(ip_addr: &IpAddr) -> bool {
+        match ip_addr {
+            IpAddr::V4(ipv4_addr) => {
+                ipv4_addr.is_loopback()
+                    || ipv4_addr.is_private()
+                    || ipv4_addr.is_link_local()
+                    || ipv4_addr.is_unspecified()
+                    || ipv4_addr.is_broadcast()
+            }
+            IpAddr::V6(ipv6_addr) => {
+                let segments = ipv6_addr.segments();
+                let is_ipv4_mapped = segments[..6] == [0, 0, 0, 0, 0, 0xffff];
+                ipv6_addr.is_loopback()
+                    || ipv6_addr.is_unspecified()
+                    || (segments[0] & 0xfe00) == 0xfc00 // unique local, fc00::/7
+                    || (segments[0] & 0xffc0) == 0xfe80 // link-local, fe80::/10
+                    || (is_ipv4_mapped
+                        && Self::is_local_destination(&IpAddr::V4(
+                            ipv6_addr.to_ipv4().expect("IPv4-mapped address isn't"),
+                        )))
+            }
+        }
+    }
+
     fn lookup_dns(
         inner_arc: Arc<Mutex<StreamHandlerPoolRealInner>>,
         target_hostname: String,
@@ -296,6 +434,7 @@ impl StreamHandlerPoolReal {
         let mut establisher = StreamHandlerPoolReal::make_establisher(inner_arc.clone());
         let stream_key = payload.stream_key;
         let logger = StreamHandlerPoolReal::make_logger_copy(&inner_arc);
+        let local_destinations_allowed = Self::local_destinations_allowed(&inner_arc);
         Box::new(
             inner_arc
                 .lock()
@@ -303,8 +442,14 @@ impl StreamHandlerPoolReal {
                 .resolver
                 .lookup_ip(&fqdn)
                 .map_err(move |err| {
+                    let failure = match err.kind() {
+                        ResolveErrorKind::NoRecordsFound { .. } => {
+                            DnsResolveFailure_0v1::for_nonexistent_domain(stream_key)
+                        }
+                        _ => DnsResolveFailure_0v1::new(stream_key),
+                    };
                     dns_resolve_failed_sub
-                        .try_send(DnsResolveFailure_0v1::new(stream_key))
+                        .try_send(failure)
                         .expect("ProxyClient is poisoned");
                     err
                 })
@@ -313,6 +458,7 @@ impl StreamHandlerPoolReal {
                         target_hostname.to_string(),
                         &payload,
                         lookup_result,
+                        local_destinations_allowed,
                         logger,
                         &mut establisher,
                     )
@@ -325,6 +471,7 @@ impl StreamHandlerPoolReal {
         target_hostname: String,
         payload: &ClientRequestPayload_0v1,
         lookup_result: Result<LookupIp, ResolveError>,
+        local_destinations_allowed: bool,
         logger: Logger,
         establisher: &mut StreamEstablisher,
     ) -> io::Result<Box<dyn SenderWrapper<SequencedPacket>>> {
@@ -342,6 +489,12 @@ impl StreamHandlerPoolReal {
             logger,
             "Found IP addresses for {}: {:?}", target_hostname, &ip_addrs
         );
+        let ip_addrs = Self::remove_local_destinations(
+            &target_hostname,
+            ip_addrs,
+            local_destinations_allowed,
+            &logger,
+        )?;
         establisher.establish_stream(&payload, ip_addrs, target_hostname)
     }
 
@@ -370,6 +523,13 @@ impl StreamHandlerPoolReal {
         sequenced_packet: SequencedPacket,
         sender_wrapper: Box<dyn SenderWrapper<SequencedPacket>>,
     ) -> FutureResult<(), String> {
+        if let Err(overflow) = sender_wrapper.buffer().reserve(sequenced_packet.data.len()) {
+            return err::<(), String>(format!(
+                "Could not queue write to stream to {}: {}",
+                sender_wrapper.peer_addr(),
+                overflow
+            ));
+        }
         match sender_wrapper.unbounded_send(sequenced_packet) {
             Ok(_) => ok::<(), String>(()),
             Err(_) => {
@@ -397,11 +557,21 @@ impl StreamHandlerPoolReal {
     fn do_housekeeping(&self) {
         self.clean_up_dead_streams();
         self.add_new_streams();
+        let inner = self.inner.lock().expect("Stream handler pool is poisoned");
+        self.report_stream_count(&inner);
+    }
+
+    fn report_stream_count(&self, inner: &StreamHandlerPoolRealInner) {
+        self.metrics
+            .proxy_client_streams
+            .set(inner.stream_writer_channels.len() as u64);
     }
 
     fn clean_up_dead_streams(&self) {
         let mut inner = self.inner.lock().expect("Stream handler pool is poisoned");
         while let Ok((stream_key, sequence_number)) = self.stream_killer_rx.try_recv() {
+            inner.stream_reapers.remove(&stream_key);
+            inner.read_switches.remove(&stream_key);
             match inner.stream_writer_channels.remove(&stream_key) {
                 Some(writer_channel) => {
                     inner
@@ -434,7 +604,7 @@ impl StreamHandlerPoolReal {
         loop {
             match self.stream_adder_rx.try_recv() {
                 Err(_) => break,
-                Ok((stream_key, stream_writer_channel)) => {
+                Ok((stream_key, stream_writer_channel, stream_reaper, read_switch)) => {
                     debug!(
                         inner.logger,
                         "Persisting StreamWriter to {} under key {:?}",
@@ -443,7 +613,9 @@ impl StreamHandlerPoolReal {
                     );
                     inner
                         .stream_writer_channels
-                        .insert(stream_key, stream_writer_channel)
+                        .insert(stream_key, stream_writer_channel);
+                    inner.stream_reapers.insert(stream_key, stream_reaper);
+                    inner.read_switches.insert(stream_key, read_switch);
                 }
             };
         }
@@ -459,6 +631,7 @@ pub trait StreamHandlerPoolFactory {
         proxy_client_subs: ProxyClientSubs,
         exit_service_rate: u64,
         exit_byte_rate: u64,
+        local_destinations_allowed: bool,
     ) -> Box<dyn StreamHandlerPool>;
 }
 
@@ -473,6 +646,7 @@ impl StreamHandlerPoolFactory for StreamHandlerPoolFactoryReal {
         proxy_client_subs: ProxyClientSubs,
         exit_service_rate: u64,
         exit_byte_rate: u64,
+        local_destinations_allowed: bool,
     ) -> Box<dyn StreamHandlerPool> {
         Box::new(StreamHandlerPoolReal::new(
             resolver,
@@ -481,6 +655,7 @@ impl StreamHandlerPoolFactory for StreamHandlerPoolFactoryReal {
             proxy_client_subs,
             exit_service_rate,
             exit_byte_rate,
+            local_destinations_allowed,
         ))
     }
 }
@@ -496,6 +671,7 @@ mod tests {
     use crate::sub_lib::hopper::ExpiredCoresPackage;
     use crate::sub_lib::hopper::MessageType;
     use crate::sub_lib::proxy_server::ProxyProtocol;
+    use crate::sub_lib::stream_buffer::StreamBuffer;
     use crate::test_utils::channel_wrapper_mocks::FuturesChannelFactoryMock;
     use crate::test_utils::channel_wrapper_mocks::ReceiverWrapperMock;
     use crate::test_utils::channel_wrapper_mocks::SenderWrapperMock;
@@ -524,6 +700,7 @@ mod tests {
     use std::thread;
     use tokio;
     use tokio::prelude::Async;
+    use trust_dns_proto::op::Query;
     use trust_dns_resolver::error::ResolveErrorKind;
 
     struct StreamEstablisherFactoryMock {
@@ -553,23 +730,25 @@ mod tests {
         })
     }
 
-    #[test]
-    fn dns_resolution_failure_sends_a_message_to_proxy_client() {
+    fn dns_failure_sent_to_proxy_client(
+        test_name: &'static str,
+        error: ResolveError,
+    ) -> DnsResolveFailure_0v1 {
         let (proxy_client, proxy_client_awaiter, proxy_client_recording) = make_recorder();
         let stream_key = make_meaningless_stream_key();
         thread::spawn(move || {
-            let system = System::new("dns_resolution_failure_sends_a_message_to_proxy_client");
+            let system = System::new(test_name);
             let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
             let cryptde = main_cryptde();
-            let resolver_mock =
-                ResolverWrapperMock::new().lookup_ip_failure(ResolveErrorKind::Io.into());
-            let logger = Logger::new("dns_resolution_failure_sends_a_message_to_proxy_client");
+            let resolver_mock = ResolverWrapperMock::new().lookup_ip_failure(error);
+            let logger = Logger::new(test_name);
             let establisher = StreamEstablisher {
                 cryptde,
                 stream_adder_tx: mpsc::channel().0,
                 stream_killer_tx: mpsc::channel().0,
                 stream_connector: Box::new(StreamConnectorMock::new()),
                 proxy_client_sub: peer_actors.proxy_client.inbound_server_data.clone(),
+                stream_pressure_sub: peer_actors.proxy_client.stream_pressure.clone(),
                 logger: logger.clone(),
                 channel_factory: Box::new(FuturesChannelFactoryMock::default()),
             };
@@ -577,6 +756,8 @@ mod tests {
                 accountant_sub: peer_actors.accountant.report_exit_service_provided.clone(),
                 proxy_client_subs: peer_actors.proxy_client.clone(),
                 stream_writer_channels: HashMap::new(),
+                stream_reapers: HashMap::new(),
+                read_switches: HashMap::new(),
                 resolver: Box::new(resolver_mock),
                 logger,
                 establisher_factory: Box::new(StreamEstablisherFactoryMock {
@@ -584,6 +765,7 @@ mod tests {
                 }),
                 exit_service_rate: Default::default(),
                 exit_byte_rate: Default::default(),
+                local_destinations_allowed: false,
             };
             let payload = ClientRequestPayload_0v1 {
                 stream_key,
@@ -601,12 +783,37 @@ mod tests {
 
         proxy_client_awaiter.await_message_count(1);
 
+        let recording = proxy_client_recording.lock().unwrap();
+        recording.get_record::<DnsResolveFailure_0v1>(0).clone()
+    }
+
+    #[test]
+    fn dns_resolution_failure_sends_a_message_to_proxy_client() {
+        let result = dns_failure_sent_to_proxy_client(
+            "dns_resolution_failure_sends_a_message_to_proxy_client",
+            ResolveErrorKind::Io.into(),
+        );
+
         assert_eq!(
-            &DnsResolveFailure_0v1::new(stream_key),
-            proxy_client_recording
-                .lock()
-                .unwrap()
-                .get_record::<DnsResolveFailure_0v1>(0),
+            result,
+            DnsResolveFailure_0v1::new(make_meaningless_stream_key())
+        );
+    }
+
+    #[test]
+    fn nonexistent_domain_is_reported_as_such_to_proxy_client() {
+        let result = dns_failure_sent_to_proxy_client(
+            "nonexistent_domain_is_reported_as_such_to_proxy_client",
+            ResolveErrorKind::NoRecordsFound {
+                query: Query::default(),
+                valid_until: None,
+            }
+            .into(),
+        );
+
+        assert_eq!(
+            result,
+            DnsResolveFailure_0v1::for_nonexistent_domain(make_meaningless_stream_key())
         );
     }
 
@@ -650,6 +857,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             subject
                 .inner
@@ -711,6 +919,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             subject
                 .inner
@@ -793,6 +1002,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -810,6 +1020,7 @@ mod tests {
                         writer,
                     )),
                     proxy_client_sub: inner.proxy_client_subs.inbound_server_data.clone(),
+                    stream_pressure_sub: inner.proxy_client_subs.stream_pressure.clone(),
                     logger: inner.logger.clone(),
                     channel_factory: Box::new(FuturesChannelFactoryReal {}),
                 };
@@ -902,6 +1113,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -919,6 +1131,7 @@ mod tests {
                         writer,
                     )),
                     proxy_client_sub: inner.proxy_client_subs.inbound_server_data.clone(),
+                    stream_pressure_sub: inner.proxy_client_subs.stream_pressure.clone(),
                     logger: inner.logger.clone(),
                     channel_factory: Box::new(FuturesChannelFactoryReal {}),
                 };
@@ -989,6 +1202,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
 
             run_process_package_in_actix(subject, package);
@@ -1073,6 +1287,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -1090,6 +1305,7 @@ mod tests {
                         writer,
                     )),
                     proxy_client_sub: inner.proxy_client_subs.inbound_server_data.clone(),
+                    stream_pressure_sub: inner.proxy_client_subs.stream_pressure.clone(),
                     logger: inner.logger.clone(),
                     channel_factory: Box::new(FuturesChannelFactoryReal {}),
                 };
@@ -1166,6 +1382,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
             subject.stream_killer_rx = stream_killer_rx;
@@ -1179,6 +1396,7 @@ mod tests {
                         .connect_pair_result(Err(Error::from(ErrorKind::Other))),
                 ),
                 proxy_client_sub,
+                stream_pressure_sub: peer_actors.proxy_client.stream_pressure.clone(),
                 logger: subject.inner.lock().unwrap().logger.clone(),
                 channel_factory: Box::new(FuturesChannelFactoryReal {}),
             };
@@ -1205,6 +1423,171 @@ mod tests {
         );
     }
 
+    fn local_destination_is_refused_with_an_error_response(
+        target_hostname: &str,
+        resolved_ip_addrs: Vec<IpAddr>,
+    ) {
+        init_test_logging();
+        let cryptde = main_cryptde();
+        let stream_key = make_meaningless_stream_key();
+        let (proxy_client, proxy_client_awaiter, proxy_client_recording_arc) = make_recorder();
+        let originator_key = PublicKey::new(&b"men's souls"[..]);
+        let target_hostname = target_hostname.to_string();
+        thread::spawn(move || {
+            let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+            let client_request_payload = ClientRequestPayload_0v1 {
+                stream_key,
+                sequenced_packet: SequencedPacket {
+                    data: b"These are the times".to_vec(),
+                    sequence_number: 0,
+                    last_data: false,
+                },
+                target_hostname: Some(target_hostname),
+                target_port: 22,
+                protocol: ProxyProtocol::Opaque,
+                originator_public_key: originator_key,
+            };
+            let package = ExpiredCoresPackage::new(
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+                Some(make_wallet("consuming")),
+                make_meaningless_route(),
+                client_request_payload.into(),
+                0,
+            );
+            let resolver = ResolverWrapperMock::new().lookup_ip_success(resolved_ip_addrs);
+            let proxy_client_sub = peer_actors.proxy_client.inbound_server_data.clone();
+            let subject = StreamHandlerPoolReal::new(
+                Box::new(resolver),
+                cryptde,
+                peer_actors.accountant.report_exit_service_provided.clone(),
+                peer_actors.proxy_client.clone(),
+                100,
+                200,
+                false,
+            );
+            // No connection results: trying to connect anywhere would panic.
+            let establisher = StreamEstablisher {
+                cryptde,
+                stream_adder_tx: mpsc::channel().0,
+                stream_killer_tx: mpsc::channel().0,
+                stream_connector: Box::new(StreamConnectorMock::new()),
+                proxy_client_sub,
+                stream_pressure_sub: peer_actors.proxy_client.stream_pressure.clone(),
+                logger: subject.inner.lock().unwrap().logger.clone(),
+                channel_factory: Box::new(FuturesChannelFactoryReal {}),
+            };
+            subject.inner.lock().unwrap().establisher_factory =
+                Box::new(StreamEstablisherFactoryMock {
+                    make_results: RefCell::new(vec![establisher]),
+                });
+
+            run_process_package_in_actix(subject, package);
+        });
+
+        proxy_client_awaiter.await_message_count(1);
+        let proxy_client_recording = proxy_client_recording_arc.lock().unwrap();
+        assert_eq!(
+            proxy_client_recording.get_record::<InboundServerData>(0),
+            &InboundServerData {
+                stream_key,
+                last_data: true,
+                sequence_number: 0,
+                source: error_socket_addr(),
+                data: vec![],
+            }
+        );
+        TestLogHandler::new()
+            .exists_log_containing("WARN: ProxyClient: Refusing to carry a stream");
+    }
+
+    #[test]
+    fn stream_to_a_loopback_address_is_refused_with_an_error_response() {
+        local_destination_is_refused_with_an_error_response("127.0.0.1", vec![]);
+    }
+
+    #[test]
+    fn stream_to_a_name_that_resolves_only_to_local_addresses_is_refused_with_an_error_response() {
+        local_destination_is_refused_with_an_error_response(
+            "rebound.example.com",
+            vec![
+                IpAddr::from_str("192.168.1.1").unwrap(),
+                IpAddr::from_str("fe80::1").unwrap(),
+            ],
+        );
+    }
+
+    #[test]
+    fn local_destinations_are_recognized() {
+        vec![
+            "127.0.0.1",
+            "127.255.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.1.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fc00::1",
+            "fd12:3456::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:192.168.1.1",
+        ]
+        .into_iter()
+        .for_each(|ip_addr| {
+            assert_eq!(
+                StreamHandlerPoolReal::is_local_destination(&IpAddr::from_str(ip_addr).unwrap()),
+                true,
+                "{}",
+                ip_addr
+            )
+        });
+        vec![
+            "1.2.3.4",
+            "172.32.0.1",
+            "8.8.8.8",
+            "2001:db8::5",
+            "2606:4700::1111",
+            "::ffff:1.2.3.4",
+        ]
+        .into_iter()
+        .for_each(|ip_addr| {
+            assert_eq!(
+                StreamHandlerPoolReal::is_local_destination(&IpAddr::from_str(ip_addr).unwrap()),
+                false,
+                "{}",
+                ip_addr
+            )
+        });
+    }
+
+    #[test]
+    fn local_destinations_are_dropped_from_mixed_lookups_and_kept_when_allowed() {
+        let logger = Logger::new("test");
+        let ip_addrs = vec![
+            IpAddr::from_str("10.0.0.1").unwrap(),
+            IpAddr::from_str("2.3.4.5").unwrap(),
+        ];
+
+        let refused = StreamHandlerPoolReal::remove_local_destinations(
+            "that.try",
+            ip_addrs.clone(),
+            false,
+            &logger,
+        );
+        let allowed = StreamHandlerPoolReal::remove_local_destinations(
+            "that.try",
+            ip_addrs.clone(),
+            true,
+            &logger,
+        );
+
+        assert_eq!(refused.unwrap(), vec![IpAddr::from_str("2.3.4.5").unwrap()]);
+        assert_eq!(allowed.unwrap(), ip_addrs);
+    }
+
     #[test]
     fn trying_to_write_to_disconnected_stream_writer_sends_an_error_response() {
         let cryptde = main_cryptde();
@@ -1266,6 +1649,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
 
             let peer_addr = SocketAddr::from_str("3.4.5.6:80").unwrap();
@@ -1288,12 +1672,14 @@ mod tests {
                             .with_connection(peer_addr, peer_addr, reader, writer),
                     ),
                     proxy_client_sub: peer_actors.proxy_client.inbound_server_data.clone(),
+                    stream_pressure_sub: peer_actors.proxy_client.stream_pressure.clone(),
                     logger: inner.logger.clone(),
                     channel_factory: Box::new(FuturesChannelFactoryMock {
                         results: vec![(
                             disconnected_sender,
                             Box::new(ReceiverWrapperMock {
                                 poll_results: vec![Ok(Async::Ready(None))],
+                                ..ReceiverWrapperMock::new()
                             }),
                         )],
                     }),
@@ -1359,6 +1745,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             run_process_package_in_actix(subject, package);
         });
@@ -1427,6 +1814,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
             subject
                 .inner
@@ -1445,6 +1833,181 @@ mod tests {
         tlh.await_log_containing("Removing stream writer for 1.2.3.4:5678", 1000);
     }
 
+    #[test]
+    fn write_that_would_overflow_the_stream_buffer_removes_stream_without_sending() {
+        init_test_logging();
+        let cryptde = main_cryptde();
+        let stream_key = make_meaningless_stream_key();
+        let (proxy_client, proxy_client_awaiter, proxy_client_recording_arc) = make_recorder();
+        let sequenced_packet = SequencedPacket {
+            data: b"These are the times".to_vec(),
+            sequence_number: 0,
+            last_data: false,
+        };
+        let client_request_payload = ClientRequestPayload_0v1 {
+            stream_key: stream_key.clone(),
+            sequenced_packet: sequenced_packet.clone(),
+            target_hostname: Some(String::from("that.try")),
+            target_port: HTTP_PORT,
+            protocol: ProxyProtocol::HTTP,
+            originator_public_key: PublicKey::new(&b"men's souls"[..]),
+        };
+        let package = ExpiredCoresPackage::new(
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            Some(make_wallet("consuming")),
+            make_meaningless_route(),
+            client_request_payload.into(),
+            0,
+        );
+        let buffer = StreamBuffer::with_limits(10, 8, 4);
+        let send_params = Arc::new(Mutex::new(vec![]));
+        let sender_wrapper = SenderWrapperMock::new(SocketAddr::from_str("1.2.3.4:5678").unwrap())
+            .buffer_result(&buffer)
+            .unbounded_send_params(&send_params);
+        thread::spawn(move || {
+            let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+            let subject = StreamHandlerPoolReal::new(
+                Box::new(ResolverWrapperMock::new()),
+                cryptde,
+                peer_actors.accountant.report_exit_service_provided.clone(),
+                peer_actors.proxy_client.clone(),
+                100,
+                200,
+                false,
+            );
+            subject
+                .inner
+                .lock()
+                .unwrap()
+                .stream_writer_channels
+                .insert(stream_key, Box::new(sender_wrapper));
+
+            run_process_package_in_actix(subject, package);
+        });
+
+        proxy_client_awaiter.await_message_count(1);
+        let tlh = TestLogHandler::new();
+        tlh.exists_log_containing(
+            "Could not queue write to stream to 1.2.3.4:5678: 0 bytes already queued; buffer capacity is 10",
+        );
+        tlh.exists_log_containing("Removing stream writer for 1.2.3.4:5678");
+        assert_eq!(send_params.lock().unwrap().is_empty(), true);
+        assert_eq!(buffer.is_overflowed(), true);
+        let proxy_client_recording = proxy_client_recording_arc.lock().unwrap();
+        assert_eq!(
+            proxy_client_recording.get_record::<InboundServerData>(0),
+            &InboundServerData {
+                stream_key,
+                last_data: true,
+                sequence_number: 0,
+                source: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+                data: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn apply_read_pressure_flips_the_read_switch_for_a_known_stream() {
+        init_test_logging();
+        let system = System::new("apply_read_pressure_flips_the_read_switch_for_a_known_stream");
+        let peer_actors = peer_actors_builder().build();
+        let mut subject = StreamHandlerPoolReal::new(
+            Box::new(ResolverWrapperMock::new()),
+            main_cryptde(),
+            peer_actors.accountant.report_exit_service_provided,
+            peer_actors.proxy_client,
+            0,
+            0,
+            false,
+        );
+        let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
+        subject.stream_adder_rx = stream_adder_rx;
+        let stream_key = make_meaningless_stream_key();
+        let unknown_stream_key = StreamKey::new(
+            main_cryptde().public_key().clone(),
+            SocketAddr::from_str("2.3.4.5:6789").unwrap(),
+        );
+        let read_switch = ReadSwitch::new();
+        stream_adder_tx
+            .send((
+                stream_key,
+                Box::new(SenderWrapperMock::new(
+                    SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+                )) as Box<dyn SenderWrapper<SequencedPacket>>,
+                oneshot::channel().0,
+                read_switch.clone(),
+            ))
+            .unwrap();
+
+        let paused = future::lazy(|| {
+            subject.apply_read_pressure(&stream_key, PauseReason::Backpressure, Pressure::Pause);
+            subject.apply_read_pressure(
+                &unknown_stream_key,
+                PauseReason::Backpressure,
+                Pressure::Pause,
+            );
+            future::ok::<_, ()>(read_switch.is_paused())
+        })
+        .wait()
+        .unwrap();
+        subject.apply_read_pressure(&stream_key, PauseReason::Backpressure, Pressure::Resume);
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(paused, true);
+        assert_eq!(read_switch.is_paused(), false);
+        TestLogHandler::new().exists_log_containing(&format!(
+            "No StreamReader for key {:?} to apply Pause to; ignoring",
+            unknown_stream_key
+        ));
+    }
+
+    #[test]
+    fn bandwidth_resume_does_not_undo_a_backpressure_pause() {
+        let system = System::new("bandwidth_resume_does_not_undo_a_backpressure_pause");
+        let peer_actors = peer_actors_builder().build();
+        let mut subject = StreamHandlerPoolReal::new(
+            Box::new(ResolverWrapperMock::new()),
+            main_cryptde(),
+            peer_actors.accountant.report_exit_service_provided,
+            peer_actors.proxy_client,
+            0,
+            0,
+            false,
+        );
+        let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
+        subject.stream_adder_rx = stream_adder_rx;
+        let stream_key = make_meaningless_stream_key();
+        let read_switch = ReadSwitch::new();
+        stream_adder_tx
+            .send((
+                stream_key,
+                Box::new(SenderWrapperMock::new(
+                    SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+                )) as Box<dyn SenderWrapper<SequencedPacket>>,
+                oneshot::channel().0,
+                read_switch.clone(),
+            ))
+            .unwrap();
+
+        let (after_bandwidth_resume, after_backpressure_resume) = future::lazy(|| {
+            subject.apply_read_pressure(&stream_key, PauseReason::Backpressure, Pressure::Pause);
+            subject.apply_read_pressure(&stream_key, PauseReason::Bandwidth, Pressure::Pause);
+            subject.apply_read_pressure(&stream_key, PauseReason::Bandwidth, Pressure::Resume);
+            let after_bandwidth_resume = read_switch.is_paused();
+            subject.apply_read_pressure(&stream_key, PauseReason::Backpressure, Pressure::Resume);
+            let after_backpressure_resume = read_switch.is_paused();
+            future::ok::<_, ()>((after_bandwidth_resume, after_backpressure_resume))
+        })
+        .wait()
+        .unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(after_bandwidth_resume, true);
+        assert_eq!(after_backpressure_resume, false);
+    }
+
     #[test]
     fn process_package_does_not_create_new_connection_for_zero_length_data_with_unfamiliar_stream_key(
     ) {
@@ -1484,6 +2047,7 @@ mod tests {
                 peer_actors.proxy_client.clone(),
                 100,
                 200,
+                false,
             );
 
             subject.inner.lock().unwrap().establisher_factory =
@@ -1520,6 +2084,7 @@ mod tests {
             peer_actors.proxy_client,
             0,
             0,
+            false,
         );
         let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
         subject.stream_killer_rx = stream_killer_rx;
@@ -1563,6 +2128,7 @@ mod tests {
             peer_actors.proxy_client,
             0,
             0,
+            false,
         );
         let (stream_killer_tx, stream_killer_rx) = mpsc::channel();
         subject.stream_killer_rx = stream_killer_rx;
@@ -1576,4 +2142,99 @@ mod tests {
         let proxy_client_recording = proxy_client_recording_arc.lock().unwrap();
         assert_eq!(proxy_client_recording.len(), 0);
     }
+
+    #[test]
+    fn reap_stream_drops_writer_fires_reaper_and_sends_nothing_to_proxy_client() {
+        let system = System::new("test");
+        let (proxy_client, _, proxy_client_recording_arc) = make_recorder();
+        let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+        let mut subject = StreamHandlerPoolReal::new(
+            Box::new(ResolverWrapperMock::new()),
+            main_cryptde(),
+            peer_actors.accountant.report_exit_service_provided,
+            peer_actors.proxy_client,
+            0,
+            0,
+            false,
+        );
+        let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
+        subject.stream_adder_rx = stream_adder_rx;
+        let stream_key = make_meaningless_stream_key();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let (reaper_tx, reaper_rx) = oneshot::channel();
+        stream_adder_tx
+            .send((
+                stream_key,
+                Box::new(SenderWrapperMock::new(peer_addr))
+                    as Box<dyn SenderWrapper<SequencedPacket>>,
+                reaper_tx,
+                ReadSwitch::new(),
+            ))
+            .unwrap();
+
+        subject.reap_stream(&stream_key);
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(reaper_rx.wait(), Ok(()));
+        let inner = subject.inner.lock().unwrap();
+        assert_eq!(
+            inner.stream_writer_channels.contains_key(&stream_key),
+            false
+        );
+        assert_eq!(inner.stream_reapers.contains_key(&stream_key), false);
+        let proxy_client_recording = proxy_client_recording_arc.lock().unwrap();
+        assert_eq!(proxy_client_recording.len(), 0);
+    }
+
+    #[test]
+    fn open_streams_are_counted_in_metrics() {
+        let system = System::new("test");
+        let (proxy_client, _, _) = make_recorder();
+        let peer_actors = peer_actors_builder().proxy_client(proxy_client).build();
+        let mut subject = StreamHandlerPoolReal::new(
+            Box::new(ResolverWrapperMock::new()),
+            main_cryptde(),
+            peer_actors.accountant.report_exit_service_provided,
+            peer_actors.proxy_client,
+            0,
+            0,
+            false,
+        );
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+        let (stream_adder_tx, stream_adder_rx) = mpsc::channel();
+        subject.stream_adder_rx = stream_adder_rx;
+        let reaped_stream_key = StreamKey::new(
+            main_cryptde().public_key().clone(),
+            SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+        );
+        let kept_stream_key = StreamKey::new(
+            main_cryptde().public_key().clone(),
+            SocketAddr::from_str("2.3.4.5:6789").unwrap(),
+        );
+        vec![reaped_stream_key, kept_stream_key]
+            .into_iter()
+            .for_each(|stream_key| {
+                stream_adder_tx
+                    .send((
+                        stream_key,
+                        Box::new(SenderWrapperMock::new(
+                            SocketAddr::from_str("3.4.5.6:80").unwrap(),
+                        )) as Box<dyn SenderWrapper<SequencedPacket>>,
+                        oneshot::channel().0,
+                        ReadSwitch::new(),
+                    ))
+                    .unwrap()
+            });
+
+        subject.do_housekeeping();
+        let before_reaping = metrics.proxy_client_streams.get();
+        subject.reap_stream(&reaped_stream_key);
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(before_reaping, 2);
+        assert_eq!(metrics.proxy_client_streams.get(), 1);
+    }
 }
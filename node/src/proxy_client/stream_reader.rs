@@ -2,11 +2,13 @@
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::proxy_client::InboundServerData;
 use crate::sub_lib::sequencer::Sequencer;
+use crate::sub_lib::stream_buffer::ReadSwitch;
 use crate::sub_lib::stream_key::StreamKey;
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
 use crate::sub_lib::utils;
 use crate::sub_lib::utils::indicates_dead_stream;
 use actix::Recipient;
+use futures::sync::oneshot;
 use std::net::SocketAddr;
 use std::sync::mpsc::Sender;
 use tokio::prelude::Async;
@@ -20,6 +22,8 @@ pub struct StreamReader {
     peer_addr: SocketAddr,
     logger: Logger,
     sequencer: Sequencer,
+    reaper_opt: Option<oneshot::Receiver<()>>,
+    read_switch: ReadSwitch,
 }
 
 impl Future for StreamReader {
@@ -27,8 +31,18 @@ impl Future for StreamReader {
     type Error = ();
 
     fn poll(&mut self) -> Result<Async<<Self as Future>::Item>, <Self as Future>::Error> {
+        if self.has_been_reaped() {
+            debug!(
+                self.logger,
+                "Stream from {} was reaped by the StreamHandlerPool", self.peer_addr
+            );
+            return Ok(Async::Ready(()));
+        }
         let mut buf: [u8; 16384] = [0; 16384];
         loop {
+            if self.read_switch.is_paused() {
+                return Ok(Async::NotReady);
+            }
             match self.stream.poll_read(&mut buf) {
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Ok(Async::Ready(0)) => {
@@ -83,6 +97,8 @@ impl StreamReader {
         stream: Box<dyn ReadHalfWrapper>,
         stream_killer: Sender<(StreamKey, u64)>,
         peer_addr: SocketAddr,
+        reaper: oneshot::Receiver<()>,
+        read_switch: ReadSwitch,
     ) -> StreamReader {
         StreamReader {
             stream_key,
@@ -92,6 +108,25 @@ impl StreamReader {
             peer_addr,
             logger: Logger::new(&format!("StreamReader for {:?}/{}", stream_key, peer_addr)[..]),
             sequencer: Sequencer::new(),
+            reaper_opt: Some(reaper),
+            read_switch,
+        }
+    }
+
+    // The reaper fires when the pool decides the stream has lived too long. If the pool simply
+    // drops its end instead, the stream isn't being reaped, and there's nothing more to wait for.
+    fn has_been_reaped(&mut self) -> bool {
+        let poll_result = match self.reaper_opt.as_mut() {
+            Some(reaper) => reaper.poll(),
+            None => return false,
+        };
+        match poll_result {
+            Ok(Async::Ready(())) => true,
+            Ok(Async::NotReady) => false,
+            Err(_) => {
+                self.reaper_opt = None;
+                false
+            }
         }
     }
 
@@ -117,6 +152,7 @@ impl StreamReader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sub_lib::stream_buffer::{PauseReason, Pressure};
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
     use crate::test_utils::make_meaningless_stream_key;
@@ -124,6 +160,7 @@ mod tests {
     use crate::test_utils::recorder::peer_actors_builder;
     use crate::test_utils::tokio_wrapper_mocks::ReadHalfWrapperMock;
     use actix::System;
+    use futures::future::lazy;
     use std::io::Error;
     use std::io::ErrorKind;
     use std::net::SocketAddr;
@@ -179,6 +216,8 @@ mod tests {
             peer_addr: SocketAddr::from_str("8.7.4.3:50").unwrap(),
             logger: Logger::new("test"),
             sequencer: Sequencer::new(),
+            reaper_opt: None,
+            read_switch: ReadSwitch::new(),
         };
 
         let _res = subject.poll();
@@ -259,6 +298,8 @@ mod tests {
             peer_addr: SocketAddr::from_str("5.7.9.0:95").unwrap(),
             logger: Logger::new("test"),
             sequencer: Sequencer::new(),
+            reaper_opt: None,
+            read_switch: ReadSwitch::new(),
         };
 
         let result = subject.poll();
@@ -326,6 +367,8 @@ mod tests {
             peer_addr: SocketAddr::from_str("5.3.4.3:654").unwrap(),
             logger: Logger::new("test"),
             sequencer,
+            reaper_opt: None,
+            read_switch: ReadSwitch::new(),
         };
         System::current().stop_with_code(0);
         system.run();
@@ -338,6 +381,74 @@ mod tests {
             .exists_log_containing("Stream from 5.3.4.3:654 was closed: (0-byte read)");
     }
 
+    #[test]
+    fn reaped_stream_stops_reading_without_reporting_a_dead_stream() {
+        init_test_logging();
+        let stream_key = make_meaningless_stream_key();
+        let (stream_killer, kill_stream_params) = mpsc::channel();
+        let mut stream = ReadHalfWrapperMock::new();
+        stream.poll_read_results = vec![(b"unread".to_vec(), Ok(Async::Ready(6)))];
+        let (reaper_tx, reaper_rx) = oneshot::channel();
+        let system = System::new("reaped_stream_stops_reading_without_reporting_a_dead_stream");
+        let peer_actors = peer_actors_builder().build();
+        let mut subject = StreamReader::new(
+            stream_key,
+            peer_actors.proxy_client.inbound_server_data,
+            Box::new(stream),
+            stream_killer,
+            SocketAddr::from_str("5.3.4.3:654").unwrap(),
+            reaper_rx,
+            ReadSwitch::new(),
+        );
+        System::current().stop_with_code(0);
+        system.run();
+        reaper_tx.send(()).unwrap();
+
+        let result = lazy(move || subject.poll()).wait();
+
+        assert_eq!(result, Ok(Async::Ready(())));
+        assert_eq!(kill_stream_params.try_recv().is_err(), true);
+        TestLogHandler::new()
+            .exists_log_containing("Stream from 5.3.4.3:654 was reaped by the StreamHandlerPool");
+    }
+
+    #[test]
+    fn stream_reader_does_not_read_while_its_read_switch_is_paused() {
+        let stream_key = make_meaningless_stream_key();
+        let (stream_killer, kill_stream_params) = mpsc::channel();
+        let mut stream = ReadHalfWrapperMock::new();
+        stream.poll_read_results = vec![(vec![], Ok(Async::Ready(0)))];
+        let system = System::new("stream_reader_does_not_read_while_its_read_switch_is_paused");
+        let peer_actors = peer_actors_builder().build();
+        let read_switch = ReadSwitch::new();
+        read_switch.apply(PauseReason::Backpressure, Pressure::Pause);
+        let mut subject = StreamReader::new(
+            stream_key,
+            peer_actors.proxy_client.inbound_server_data,
+            Box::new(stream),
+            stream_killer,
+            SocketAddr::from_str("5.3.4.3:654").unwrap(),
+            oneshot::channel().1,
+            read_switch.clone(),
+        );
+        System::current().stop_with_code(0);
+        system.run();
+
+        let (paused_result, paused_kill, resumed_result) = lazy(move || {
+            let paused_result = subject.poll();
+            let paused_kill = kill_stream_params.try_recv().is_ok();
+            read_switch.apply(PauseReason::Backpressure, Pressure::Resume);
+            let resumed_result = subject.poll();
+            Ok::<_, ()>((paused_result, paused_kill, resumed_result))
+        })
+        .wait()
+        .unwrap();
+
+        assert_eq!(paused_result, Ok(Async::NotReady));
+        assert_eq!(paused_kill, false);
+        assert_eq!(resumed_result, Ok(Async::Ready(())));
+    }
+
     #[test]
     fn non_dead_stream_read_errors_log_but_do_not_shut_down() {
         init_test_logging();
@@ -374,6 +485,8 @@ mod tests {
             peer_addr: SocketAddr::from_str("6.5.4.1:8325").unwrap(),
             logger: Logger::new("test"),
             sequencer: Sequencer::new(),
+            reaper_opt: None,
+            read_switch: ReadSwitch::new(),
         };
 
         let result = subject.poll();
@@ -28,6 +28,16 @@ impl Future for StreamWriter {
             return self.shutdown();
         }
 
+        if self.rx_to_write.buffer().is_overflowed() {
+            warning!(
+                self.logger,
+                "Abandoning stream to {}: more data arrived for it than could be buffered",
+                self.peer_addr
+            );
+            self.shutting_down = true;
+            return self.shutdown();
+        }
+
         let read_result = self.read_data_from_channel();
         let write_result = self.write_from_buffer_to_stream();
 
@@ -121,6 +131,7 @@ impl StreamWriter {
                                 &packet.data.len(),
                                 &packet.sequence_number
                             );
+                            self.rx_to_write.buffer().release(bytes_written_count);
                             if bytes_written_count != packet.data.len() {
                                 debug!(
                                     self.logger,
@@ -154,6 +165,7 @@ impl StreamWriter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sub_lib::stream_buffer::StreamBuffer;
     use crate::test_utils::channel_wrapper_mocks::ReceiverWrapperMock;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
@@ -695,4 +707,58 @@ mod tests {
 
         assert_eq!(res, Err(()));
     }
+
+    #[test]
+    fn stream_writer_releases_buffer_space_for_the_bytes_it_writes() {
+        let buffer = StreamBuffer::new();
+        buffer.reserve(9).unwrap();
+        let mut rx_to_write = Box::new(ReceiverWrapperMock::new());
+        rx_to_write.buffer = buffer.clone();
+        rx_to_write.poll_results = vec![
+            Ok(Async::Ready(Some(SequencedPacket::new(
+                vec![1, 2, 3, 4, 5, 6, 7, 8, 9],
+                0,
+                false,
+            )))),
+            Ok(Async::NotReady),
+        ];
+        let writer = WriteHalfWrapperMock::new()
+            .poll_write_result(Ok(Async::Ready(6)))
+            .poll_write_result(Ok(Async::NotReady));
+        let peer_addr = SocketAddr::from_str("2.2.3.4:5678").unwrap();
+        let mut subject = StreamWriter::new(
+            Box::new(writer),
+            peer_addr,
+            rx_to_write,
+            make_meaningless_stream_key(),
+        );
+
+        let res = subject.poll();
+
+        assert_eq!(res, Ok(Async::NotReady));
+        assert_eq!(buffer.queued(), 3);
+    }
+
+    #[test]
+    fn stream_writer_abandons_the_stream_when_its_buffer_has_overflowed() {
+        init_test_logging();
+        let buffer = StreamBuffer::with_limits(10, 8, 2);
+        buffer.reserve(11).err().unwrap();
+        let mut rx_to_write = Box::new(ReceiverWrapperMock::new());
+        rx_to_write.buffer = buffer;
+        let writer = WriteHalfWrapperMock::new().shutdown_ok();
+        let write_params_mutex = writer.poll_write_params.clone();
+        let peer_addr = SocketAddr::from_str("2.2.3.4:5679").unwrap();
+        let stream_key = make_meaningless_stream_key();
+        let mut subject = StreamWriter::new(Box::new(writer), peer_addr, rx_to_write, stream_key);
+
+        let res = subject.poll();
+
+        assert_eq!(res, Ok(Async::Ready(())));
+        assert_eq!(write_params_mutex.lock().unwrap().is_empty(), true);
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: StreamWriter for {:?}/2.2.3.4:5679: Abandoning stream to 2.2.3.4:5679: more data arrived for it than could be buffered",
+            stream_key
+        ));
+    }
 }
@@ -1,10 +1,10 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
-use crate::proxy_server::protocol_pack::from_ibcd;
+use crate::proxy_server::protocol_pack::{from_ibcd, Host};
 use crate::sub_lib::cryptde::CryptDE;
 use crate::sub_lib::cryptde::PlainData;
 use crate::sub_lib::dispatcher::InboundClientData;
 use crate::sub_lib::logger::Logger;
-use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
+use crate::sub_lib::proxy_server::{ClientRequestPayload_0v1, ProxyProtocol};
 use crate::sub_lib::sequence_buffer::SequencedPacket;
 use crate::sub_lib::stream_key::StreamKey;
 
@@ -24,17 +24,7 @@ impl ClientRequestPayloadFactory {
         logger: &Logger,
     ) -> Option<ClientRequestPayload_0v1> {
         let protocol_pack = from_ibcd(&ibcd, logger)?;
-        let sequence_number = match ibcd.sequence_number {
-            Some(sequence_number) => sequence_number,
-            None => {
-                error!(
-                    logger,
-                    "internal error: got IBCD with no sequence number and {} bytes",
-                    ibcd.data.len()
-                );
-                return None;
-            }
-        };
+        let sequence_number = Self::sequence_number(ibcd, logger)?;
         let data = PlainData::new(&ibcd.data);
         let target_host = protocol_pack.find_host(&data);
         let (target_hostname, target_port) = match target_host {
@@ -57,6 +47,55 @@ impl ClientRequestPayloadFactory {
             originator_public_key: cryptde.public_key().clone(),
         })
     }
+
+    // For streams whose destination comes from configuration or a CONNECT request, rather than
+    // from anything in the data itself.
+    pub fn make_opaque(
+        &self,
+        ibcd: &InboundClientData,
+        stream_key: StreamKey,
+        target_host: &Host,
+        cryptde: &dyn CryptDE,
+        logger: &Logger,
+    ) -> Option<ClientRequestPayload_0v1> {
+        let sequence_number = Self::sequence_number(ibcd, logger)?;
+        let target_port = match target_host.port {
+            Some(port) => port,
+            None => {
+                error!(
+                    logger,
+                    "internal error: no port for opaque stream to {}", target_host.name
+                );
+                return None;
+            }
+        };
+        Some(ClientRequestPayload_0v1 {
+            stream_key,
+            sequenced_packet: SequencedPacket {
+                data: ibcd.data.clone(),
+                sequence_number,
+                last_data: ibcd.last_data,
+            },
+            target_hostname: Some(target_host.name.clone()),
+            target_port,
+            protocol: ProxyProtocol::Opaque,
+            originator_public_key: cryptde.public_key().clone(),
+        })
+    }
+
+    fn sequence_number(ibcd: &InboundClientData, logger: &Logger) -> Option<u64> {
+        match ibcd.sequence_number {
+            Some(sequence_number) => Some(sequence_number),
+            None => {
+                error!(
+                    logger,
+                    "internal error: got IBCD with no sequence number and {} bytes",
+                    ibcd.data.len()
+                );
+                None
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -328,4 +367,48 @@ mod tests {
             "ERROR: test: internal error: got IBCD with no sequence number and 4 bytes",
         );
     }
+
+    #[test]
+    fn makes_opaque_payload_without_looking_at_the_data() {
+        let data = b"GET / HTTP/1.1\r\nHost: decoy.com\r\n\r\n".to_vec();
+        let ibcd = InboundClientData {
+            peer_addr: SocketAddr::from_str("1.2.3.4:5678").unwrap(),
+            reception_port: Some(2222),
+            sequence_number: Some(3),
+            last_data: true,
+            is_clandestine: false,
+            data: data.clone(),
+        };
+        let cryptde = main_cryptde();
+        let stream_key = make_meaningless_stream_key();
+        let target_host = Host {
+            name: "ssh.example.com".to_string(),
+            port: Some(22),
+        };
+        let subject = ClientRequestPayloadFactory::new();
+
+        let result = subject.make_opaque(
+            &ibcd,
+            stream_key,
+            &target_host,
+            cryptde,
+            &Logger::new("test"),
+        );
+
+        assert_eq!(
+            result,
+            Some(ClientRequestPayload_0v1 {
+                stream_key,
+                sequenced_packet: SequencedPacket {
+                    data,
+                    sequence_number: 3,
+                    last_data: true
+                },
+                target_hostname: Some("ssh.example.com".to_string()),
+                target_port: 22,
+                protocol: ProxyProtocol::Opaque,
+                originator_public_key: cryptde.public_key().clone(),
+            })
+        );
+    }
 }
@@ -0,0 +1,47 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::proxy_server::protocol_pack::{Host, ProtocolPack, ServerImpersonator};
+use crate::proxy_server::server_impersonator_opaque::ServerImpersonatorOpaque;
+use crate::sub_lib::cryptde::PlainData;
+use crate::sub_lib::proxy_server::ProxyProtocol;
+
+// Opaque streams get their destinations from configuration, never from their contents.
+pub struct OpaqueProtocolPack {}
+
+impl ProtocolPack for OpaqueProtocolPack {
+    fn proxy_protocol(&self) -> ProxyProtocol {
+        ProxyProtocol::Opaque
+    }
+
+    fn standard_port(&self) -> u16 {
+        0
+    }
+
+    fn find_host(&self, _data: &PlainData) -> Option<Host> {
+        None
+    }
+
+    fn server_impersonator(&self) -> Box<dyn ServerImpersonator> {
+        Box::new(ServerImpersonatorOpaque {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knows_its_protocol() {
+        let result = OpaqueProtocolPack {}.proxy_protocol();
+
+        assert_eq!(result, ProxyProtocol::Opaque);
+    }
+
+    #[test]
+    fn never_finds_a_host() {
+        let data = PlainData::new(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n");
+
+        let result = OpaqueProtocolPack {}.find_host(&data);
+
+        assert_eq!(result, None);
+    }
+}
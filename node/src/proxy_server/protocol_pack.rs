@@ -1,5 +1,6 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::proxy_server::http_protocol_pack::HttpProtocolPack;
+use crate::proxy_server::opaque_protocol_pack::OpaqueProtocolPack;
 use crate::proxy_server::tls_protocol_pack::TlsProtocolPack;
 use crate::sub_lib::cryptde::{PlainData, PublicKey};
 use crate::sub_lib::dispatcher::InboundClientData;
@@ -24,6 +25,7 @@ pub fn from_protocol(protocol: ProxyProtocol) -> Box<dyn ProtocolPack> {
     match protocol {
         ProxyProtocol::HTTP => Box::new(HttpProtocolPack {}),
         ProxyProtocol::TLS => Box::new(TlsProtocolPack {}),
+        ProxyProtocol::Opaque => Box::new(OpaqueProtocolPack {}),
     }
 }
 
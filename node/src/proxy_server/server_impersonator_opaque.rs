@@ -0,0 +1,41 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::proxy_server::protocol_pack::ServerImpersonator;
+use crate::sub_lib::cryptde::PublicKey;
+
+// There's no way to explain a failure to a client whose protocol we don't know, so these
+// responses are empty: the client just sees its connection close.
+pub struct ServerImpersonatorOpaque {}
+
+impl ServerImpersonator for ServerImpersonatorOpaque {
+    fn route_query_failure_response(&self, _server_name: &str) -> Vec<u8> {
+        vec![]
+    }
+
+    fn dns_resolution_failure_response(
+        &self,
+        _exit_key: &PublicKey,
+        _server_name: Option<String>,
+    ) -> Vec<u8> {
+        vec![]
+    }
+
+    fn consuming_wallet_absent(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_responses_are_empty() {
+        let subject = ServerImpersonatorOpaque {};
+
+        assert!(subject.route_query_failure_response("ignored").is_empty());
+        assert!(subject
+            .dns_resolution_failure_response(&PublicKey::new(&b"ignored"[..]), None)
+            .is_empty());
+        assert!(subject.consuming_wallet_absent().is_empty());
+    }
+}
@@ -3,23 +3,27 @@ use super::bootstrapper::Bootstrapper;
 use super::privilege_drop::PrivilegeDropper;
 use super::privilege_drop::PrivilegeDropperReal;
 use crate::bootstrapper::{BootstrapperConfig, RealUser};
+use crate::daemon::crash_report::NodePanic;
 use crate::entry_dns::dns_socket_server::DnsSocketServer;
 use crate::node_configurator::node_configurator_standard::NodeConfiguratorStandardPrivileged;
 use crate::node_configurator::NodeConfigurator;
 use crate::node_configurator::RealDirsWrapper;
 use crate::sub_lib;
+use crate::sub_lib::log_control::{install_log_control, LogLevels};
+use crate::sub_lib::logger::LogFormat;
 use crate::sub_lib::socket_server::SocketServer;
 use backtrace::Backtrace;
 use chrono::{DateTime, Local};
-use flexi_logger::LogSpecBuilder;
 use flexi_logger::Logger;
 use flexi_logger::{Cleanup, Criterion, LevelFilter, Naming};
 use flexi_logger::{DeferredNow, Duplicate, Record};
 use futures::try_ready;
 use lazy_static::lazy_static;
+use log::kv;
 use masq_lib::command::Command;
 use masq_lib::command::StdStreams;
 use masq_lib::shared_schema::ConfiguratorError;
+use serde_json::{Map, Value};
 use std::any::Any;
 use std::fmt::Debug;
 use std::panic::{Location, PanicInfo};
@@ -147,6 +151,7 @@ impl Default for ServerInitializer {
 
 lazy_static! {
     pub static ref LOGFILE_NAME: Mutex<PathBuf> = Mutex::new(PathBuf::from("uninitialized"));
+    static ref PANIC_DIRECTORY: Mutex<Option<PathBuf>> = Mutex::new(None);
 }
 
 pub trait LoggerInitializerWrapper: Send {
@@ -155,6 +160,7 @@ pub trait LoggerInitializerWrapper: Send {
         file_path: PathBuf,
         real_user: &RealUser,
         log_level: LevelFilter,
+        log_format: LogFormat,
         discriminant_opt: Option<&str>,
     );
 }
@@ -167,30 +173,30 @@ impl LoggerInitializerWrapper for LoggerInitializerWrapperReal {
         file_path: PathBuf,
         real_user: &RealUser,
         log_level: LevelFilter,
+        log_format: LogFormat,
         discriminant_opt: Option<&str>,
     ) {
-        let mut logger = Logger::with(
-            LogSpecBuilder::new()
-                .default(log_level)
-                .module("tokio", LevelFilter::Off)
-                .module("mio", LevelFilter::Off)
-                .build(),
-        )
-        .log_to_file()
-        .directory(file_path.clone())
-        .print_message()
-        .duplicate_to_stderr(Duplicate::Info)
-        .suppress_timestamp()
-        .format(format_function)
-        .rotate(
-            Criterion::Size(100_000_000),
-            Naming::Numbers,
-            Cleanup::KeepZipFiles(50),
-        );
+        let log_levels = LogLevels::new(log_level);
+        let mut logger = Logger::with(log_levels.spec())
+            .log_to_file()
+            .directory(file_path.clone())
+            .print_message()
+            .duplicate_to_stderr(Duplicate::Info)
+            .suppress_timestamp()
+            .format(match log_format {
+                LogFormat::Text => format_function,
+                LogFormat::Json => json_format_function,
+            })
+            .rotate(
+                Criterion::Size(100_000_000),
+                Naming::Numbers,
+                Cleanup::KeepZipFiles(50),
+            );
         if let Some(discriminant) = discriminant_opt {
             logger = logger.discriminant(discriminant);
         }
-        logger.start().expect("Logging subsystem failed to start");
+        let handle = logger.start().expect("Logging subsystem failed to start");
+        install_log_control(handle, log_levels);
         let privilege_dropper = PrivilegeDropperReal::new();
         let logfile_name = file_path.join(format!(
             "MASQNode_{}rCURRENT.log",
@@ -201,6 +207,7 @@ impl LoggerInitializerWrapper for LoggerInitializerWrapperReal {
         ));
         privilege_dropper.chown(&logfile_name, real_user);
         *(Self::logfile_name_guard()) = logfile_name;
+        *(Self::panic_directory_guard()) = Some(file_path);
         std::panic::set_hook(Box::new(|panic_info| {
             panic_hook(AltPanicInfo::from(panic_info))
         }));
@@ -224,6 +231,13 @@ impl LoggerInitializerWrapperReal {
             Err(poison_err) => poison_err.into_inner(),
         }
     }
+
+    fn panic_directory_guard<'a>() -> MutexGuard<'a, Option<PathBuf>> {
+        match PANIC_DIRECTORY.lock() {
+            Ok(guard) => guard,
+            Err(poison_err) => poison_err.into_inner(),
+        }
+    }
 }
 
 struct AltLocation {
@@ -277,6 +291,19 @@ fn panic_hook(panic_info: AltPanicInfo) {
     error!(logger, "{} - {}", location, message);
     let backtrace = Backtrace::new();
     error!(logger, "{:?}", backtrace);
+    // Leave the panic where the Daemon can find it for its crash report
+    let panic_directory_opt = LoggerInitializerWrapperReal::panic_directory_guard().clone();
+    if let Some(directory) = panic_directory_opt {
+        let node_panic = NodePanic {
+            process_id: std::process::id(),
+            message,
+            location,
+            backtrace: format!("{:?}", backtrace),
+        };
+        if let Err(e) = node_panic.write_to(&directory) {
+            error!(logger, "Could not record panic in {:?}: {}", directory, e);
+        }
+    }
 }
 
 // DeferredNow can't be constructed in a test; therefore this function is untestable...
@@ -295,22 +322,86 @@ pub fn real_format_function(
     record: &Record,
 ) -> Result<(), io::Error> {
     let timestamp = timestamp.naive_local().format("%Y-%m-%dT%H:%M:%S%.3f");
-    let thread_id_str = format!("{:?}", thread::current().id());
-    let thread_id = &thread_id_str[9..(thread_id_str.len() - 1)];
     let level = record.level();
     let name = record.module_path().unwrap_or("<unnamed>");
     write.write_fmt(format_args!(
         "{} Thd{}: {}: {}: ",
-        timestamp, thread_id, level, name
+        timestamp,
+        current_thread_id(),
+        level,
+        name
     ))?;
     write.write_fmt(*record.args())
 }
 
+// DeferredNow can't be constructed in a test; therefore this function is untestable...
+fn json_format_function(
+    write: &mut dyn io::Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> Result<(), io::Error> {
+    real_json_format_function(write, now.now(), record)
+}
+
+// ...but this one isn't. Structured fields from the Logger go in first, so that they can't
+// displace the standard ones.
+pub fn real_json_format_function(
+    write: &mut dyn io::Write,
+    timestamp: &DateTime<Local>,
+    record: &Record,
+) -> Result<(), io::Error> {
+    let mut object = Map::new();
+    let _ = record.key_values().visit(&mut JsonFieldCollector {
+        object: &mut object,
+    });
+    object.insert(
+        "timestamp".to_string(),
+        Value::String(rfc3339_timestamp(timestamp)),
+    );
+    object.insert("thread".to_string(), Value::String(current_thread_id()));
+    object.insert(
+        "level".to_string(),
+        Value::String(record.level().to_string()),
+    );
+    object.insert(
+        "component".to_string(),
+        Value::String(record.module_path().unwrap_or("<unnamed>").to_string()),
+    );
+    object.insert(
+        "message".to_string(),
+        Value::String(record.args().to_string()),
+    );
+    serde_json::to_writer(write, &Value::Object(object)).map_err(io::Error::from)
+}
+
+struct JsonFieldCollector<'a> {
+    object: &'a mut Map<String, Value>,
+}
+
+impl<'a, 'kvs> kv::Visitor<'kvs> for JsonFieldCollector<'a> {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        self.object
+            .insert(key.as_str().to_string(), Value::String(value.to_string()));
+        Ok(())
+    }
+}
+
+// Log collectors can't be expected to know the Node's time zone, so JSON records carry its offset
+fn rfc3339_timestamp(timestamp: &DateTime<Local>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()
+}
+
+fn current_thread_id() -> String {
+    let thread_id_str = format!("{:?}", thread::current().id());
+    thread_id_str[9..(thread_id_str.len() - 1)].to_string()
+}
+
 #[cfg(test)]
 pub mod test_utils {
     use crate::bootstrapper::RealUser;
     use crate::privilege_drop::PrivilegeDropper;
     use crate::server_initializer::LoggerInitializerWrapper;
+    use crate::sub_lib::logger::LogFormat;
     #[cfg(not(target_os = "windows"))]
     use crate::test_utils::logging::init_test_logging;
     use log::LevelFilter;
@@ -381,7 +472,8 @@ pub mod test_utils {
     }
 
     pub struct LoggerInitializerWrapperMock {
-        init_parameters: Arc<Mutex<Vec<(PathBuf, RealUser, LevelFilter, Option<String>)>>>,
+        init_parameters:
+            Arc<Mutex<Vec<(PathBuf, RealUser, LevelFilter, LogFormat, Option<String>)>>>,
     }
 
     impl LoggerInitializerWrapper for LoggerInitializerWrapperMock {
@@ -390,12 +482,14 @@ pub mod test_utils {
             file_path: PathBuf,
             real_user: &RealUser,
             log_level: LevelFilter,
+            log_format: LogFormat,
             name_segment: Option<&str>,
         ) {
             self.init_parameters.lock().unwrap().push((
                 file_path,
                 real_user.clone(),
                 log_level,
+                log_format,
                 match name_segment {
                     Some(s) => Some(s.to_string()),
                     None => None,
@@ -415,7 +509,9 @@ pub mod test_utils {
 
         pub fn init_parameters(
             mut self,
-            parameters: &Arc<Mutex<Vec<(PathBuf, RealUser, LevelFilter, Option<String>)>>>,
+            parameters: &Arc<
+                Mutex<Vec<(PathBuf, RealUser, LevelFilter, LogFormat, Option<String>)>>,
+            >,
         ) -> Self {
             self.init_parameters = parameters.clone();
             self
@@ -430,11 +526,13 @@ pub mod tests {
     use crate::server_initializer::test_utils::PrivilegeDropperMock;
     use crate::test_utils::logfile_name_guard::LogfileNameGuard;
     use crate::test_utils::logging::{init_test_logging, TestLogHandler};
+    use chrono::TimeZone;
     use masq_lib::crash_point::CrashPoint;
     use masq_lib::shared_schema::{ConfiguratorError, ParamError};
     use masq_lib::test_utils::fake_stream_holder::{
         ByteArrayReader, ByteArrayWriter, FakeStreamHolder,
     };
+    use serde_json::json;
     use std::cell::RefCell;
     use std::sync::Arc;
     use std::sync::Mutex;
@@ -878,4 +976,65 @@ Problem with parameter dns-iau: dns-iau-reason\n\
 Problem with parameter boot-iau: boot-iau-reason\n"
         );
     }
+
+    #[test]
+    fn json_format_function_writes_one_object_with_standard_and_structured_fields() {
+        let timestamp = Local.ymd(2021, 2, 3).and_hms_milli(4, 5, 6, 789);
+        let fields: &[(&str, &str)] = &[("stream_key", "Ym9vZ2E"), ("level", "impostor")];
+        let mut buffer: Vec<u8> = vec![];
+
+        real_json_format_function(
+            &mut buffer,
+            &timestamp,
+            &Record::builder()
+                .args(format_args!("{} happened", "Something"))
+                .module_path(Some("Component"))
+                .level(log::Level::Info)
+                .key_values(&fields)
+                .build(),
+        )
+        .unwrap();
+
+        let line = String::from_utf8(buffer).unwrap();
+        assert_eq!(line.contains('\n'), false);
+        let record: Value = serde_json::from_str(&line).unwrap();
+        let expected_timestamp = format!("2021-02-03T04:05:06.789{}", timestamp.format("%:z"));
+        assert_eq!(
+            DateTime::parse_from_rfc3339(&expected_timestamp).unwrap(),
+            timestamp
+        );
+        assert_eq!(
+            record,
+            json!({
+                "timestamp": expected_timestamp,
+                "thread": current_thread_id(),
+                "level": "INFO",
+                "component": "Component",
+                "message": "Something happened",
+                "stream_key": "Ym9vZ2E",
+            })
+        );
+    }
+
+    #[test]
+    fn json_format_function_escapes_awkward_messages() {
+        let mut buffer: Vec<u8> = vec![];
+
+        real_json_format_function(
+            &mut buffer,
+            &Local::now(),
+            &Record::builder()
+                .args(format_args!("\"quoted\"\nand\tsplit"))
+                .level(log::Level::Warn)
+                .build(),
+        )
+        .unwrap();
+
+        let line = String::from_utf8(buffer).unwrap();
+        assert_eq!(line.contains('\n'), false);
+        let record: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(record["message"], json!("\"quoted\"\nand\tsplit"));
+        assert_eq!(record["component"], json!("<unnamed>"));
+        assert_eq!(record["level"], json!("WARN"));
+    }
 }
@@ -1,8 +1,8 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::bootstrapper::PortConfiguration;
 use crate::discriminator::DiscriminatorFactory;
-use crate::json_masquerader::JsonMasquerader;
 use crate::masquerader::Masquerader;
+use crate::masquerader::{MasqueradeKind, MasqueradeRole};
 use crate::stream_messages::*;
 use crate::stream_reader::StreamReaderReal;
 use crate::stream_writer_sorted::StreamWriterSorted;
@@ -13,8 +13,9 @@ use crate::sub_lib::channel_wrappers::SenderWrapper;
 use crate::sub_lib::cryptde::PublicKey;
 use crate::sub_lib::dispatcher;
 use crate::sub_lib::dispatcher::Endpoint;
-use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg};
+use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg, WritePressureMsg};
 use crate::sub_lib::logger::Logger;
+use crate::sub_lib::metrics::{metrics, Metrics};
 use crate::sub_lib::neighborhood::DispatcherNodeQueryMessage;
 use crate::sub_lib::neighborhood::NodeQueryMessage;
 use crate::sub_lib::neighborhood::NodeQueryResponseMetadata;
@@ -22,9 +23,11 @@ use crate::sub_lib::neighborhood::RemoveNeighborMessage;
 use crate::sub_lib::neighborhood::ZERO_RATE_PACK;
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
+use crate::sub_lib::stream_buffer::{PauseReason, ReadSwitch};
 use crate::sub_lib::stream_connector::StreamConnector;
 use crate::sub_lib::stream_connector::StreamConnectorReal;
 use crate::sub_lib::stream_handler_pool::DispatcherNodeQueryResponse;
+use crate::sub_lib::stream_handler_pool::ReadPressureMsg;
 use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
 use crate::sub_lib::tokio_wrappers::WriteHalfWrapper;
@@ -38,6 +41,7 @@ use masq_lib::utils::localhost;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use tokio::prelude::Future;
@@ -55,6 +59,8 @@ pub struct StreamHandlerPoolSubs {
     pub remove_sub: Recipient<RemoveStreamMsg>,
     pub bind: Recipient<PoolBindMessage>,
     pub node_query_response: Recipient<DispatcherNodeQueryResponse>,
+    pub masquerade_detected_sub: Recipient<MasqueradeDetectedMsg>,
+    pub read_pressure_sub: Recipient<ReadPressureMsg>,
 }
 
 impl Clone for StreamHandlerPoolSubs {
@@ -65,6 +71,8 @@ impl Clone for StreamHandlerPoolSubs {
             remove_sub: self.remove_sub.clone(),
             bind: self.bind.clone(),
             node_query_response: self.node_query_response.clone(),
+            masquerade_detected_sub: self.masquerade_detected_sub.clone(),
+            read_pressure_sub: self.read_pressure_sub.clone(),
         }
     }
 }
@@ -98,6 +106,7 @@ impl Display for StreamWriterKey {
 
 pub struct StreamHandlerPool {
     stream_writers: HashMap<StreamWriterKey, Option<Box<dyn SenderWrapper<SequencedPacket>>>>,
+    read_switches: HashMap<StreamWriterKey, ReadSwitch>,
     dispatcher_subs: Option<DispatcherSubs>,
     self_subs: Option<StreamHandlerPoolSubs>,
     ask_neighborhood: Option<Recipient<DispatcherNodeQueryMessage>>,
@@ -106,7 +115,10 @@ pub struct StreamHandlerPool {
     stream_connector: Box<dyn StreamConnector>,
     channel_factory: Box<dyn FuturesChannelFactory<SequencedPacket>>,
     clandestine_discriminator_factories: Vec<Box<dyn DiscriminatorFactory>>,
+    masquerade: MasqueradeKind,
+    masquerades: HashMap<StreamWriterKey, (MasqueradeKind, MasqueradeRole)>,
     traffic_analyzer: Box<dyn TrafficAnalyzer>,
+    metrics: Arc<Metrics>,
 }
 
 impl Actor for StreamHandlerPool {
@@ -137,6 +149,22 @@ impl Handler<TransmitDataMsg> for StreamHandlerPool {
     }
 }
 
+impl Handler<MasqueradeDetectedMsg> for StreamHandlerPool {
+    type Result = ();
+
+    fn handle(&mut self, msg: MasqueradeDetectedMsg, _ctx: &mut Self::Context) -> Self::Result {
+        self.handle_masquerade_detected_msg(msg)
+    }
+}
+
+impl Handler<ReadPressureMsg> for StreamHandlerPool {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReadPressureMsg, _ctx: &mut Self::Context) -> Self::Result {
+        self.handle_read_pressure_msg(msg)
+    }
+}
+
 impl Handler<DispatcherNodeQueryResponse> for StreamHandlerPool {
     type Result = ();
     fn handle(&mut self, msg: DispatcherNodeQueryResponse, _ctx: &mut Self::Context) {
@@ -159,9 +187,11 @@ impl Handler<PoolBindMessage> for StreamHandlerPool {
 impl StreamHandlerPool {
     pub fn new(
         clandestine_discriminator_factories: Vec<Box<dyn DiscriminatorFactory>>,
+        masquerade: MasqueradeKind,
     ) -> StreamHandlerPool {
         StreamHandlerPool {
             stream_writers: HashMap::new(),
+            read_switches: HashMap::new(),
             dispatcher_subs: None,
             self_subs: None,
             ask_neighborhood: None,
@@ -170,7 +200,10 @@ impl StreamHandlerPool {
             stream_connector: Box::new(StreamConnectorReal {}),
             channel_factory: Box::new(FuturesChannelFactoryReal {}),
             clandestine_discriminator_factories,
+            masquerade,
+            masquerades: HashMap::new(),
             traffic_analyzer: Box::new(TrafficAnalyzerReal {}),
+            metrics: metrics(),
         }
     }
 
@@ -181,6 +214,8 @@ impl StreamHandlerPool {
             remove_sub: recipient!(pool_addr, RemoveStreamMsg),
             bind: recipient!(pool_addr, PoolBindMessage),
             node_query_response: recipient!(pool_addr, DispatcherNodeQueryResponse),
+            masquerade_detected_sub: recipient!(pool_addr, MasqueradeDetectedMsg),
+            read_pressure_sub: recipient!(pool_addr, ReadPressureMsg),
         }
     }
 
@@ -210,16 +245,32 @@ impl StreamHandlerPool {
             .expect("Dispatcher is unbound")
             .stream_shutdown_sub
             .clone();
+        let masquerade_detected_sub: Recipient<MasqueradeDetectedMsg> = self
+            .self_subs
+            .as_ref()
+            .expect("StreamHandlerPool is unbound")
+            .masquerade_detected_sub
+            .clone();
+        let read_switch = ReadSwitch::new();
+        // A clandestine stream carries CORES packages for every route through its neighbor, so
+        // pausing it to ease one route would stall all the others. Its writer's buffer is still
+        // bounded, but a neighbor that falls that far behind is dropped rather than paused.
+        if !port_configuration.is_clandestine {
+            self.read_switches
+                .insert(StreamWriterKey::from(peer_addr), read_switch.clone());
+        }
         let stream_reader = StreamReaderReal::new(
             read_stream,
             origin_port,
             ibcd_sub,
             remove_sub,
             stream_shutdown_sub,
+            masquerade_detected_sub,
             port_configuration.discriminator_factories.clone(),
             port_configuration.is_clandestine,
             peer_addr,
             local_addr,
+            read_switch,
         );
         debug!(
             self.logger,
@@ -243,6 +294,22 @@ impl StreamHandlerPool {
         is_clandestine: bool,
     ) {
         let (tx, rx) = self.channel_factory.make(peer_addr);
+        if !is_clandestine {
+            let write_pressure_sub = self
+                .dispatcher_subs
+                .as_ref()
+                .expect("Dispatcher is unbound")
+                .write_pressure_sub
+                .clone();
+            tx.buffer().on_pressure(Box::new(move |pressure| {
+                write_pressure_sub
+                    .try_send(WritePressureMsg {
+                        peer_addr,
+                        pressure,
+                    })
+                    .expect("Dispatcher is dead")
+            }));
+        }
         self.stream_writers
             .insert(StreamWriterKey::from(peer_addr), Some(tx));
 
@@ -267,7 +334,7 @@ impl StreamHandlerPool {
             .expect("StreamHandlerPool is unbound.")
             .node_query_response
             .clone();
-        match msg.endpoint.clone() {
+        let node_addr = match msg.endpoint.clone() {
             Endpoint::Key(key) => {
                 let request = DispatcherNodeQueryMessage {
                     query: NodeQueryMessage::PublicKey(key.clone()),
@@ -282,25 +349,26 @@ impl StreamHandlerPool {
                     .as_ref()
                     .expect("StreamHandlerPool is unbound.")
                     .try_send(request)
-                    .expect("Neighborhood is Dead")
-            }
-            Endpoint::Socket(socket_addr) => {
-                debug!(
-                    self.logger,
-                    "Translating TransmitDataMsg to node query response about {}", socket_addr
-                );
-                node_query_response_recipient
-                    .try_send(DispatcherNodeQueryResponse {
-                        result: Some(NodeQueryResponseMetadata::new(
-                            PublicKey::new(&[]),
-                            Some(NodeAddr::from(&socket_addr)),
-                            ZERO_RATE_PACK.clone(),
-                        )),
-                        context: msg,
-                    })
-                    .expect("StreamHandlerPool is dead?")
+                    .expect("Neighborhood is Dead");
+                return;
             }
+            Endpoint::Socket(socket_addr) => NodeAddr::from(&socket_addr),
+            Endpoint::Node(node_addr) => node_addr,
         };
+        debug!(
+            self.logger,
+            "Translating TransmitDataMsg to node query response about {}", node_addr
+        );
+        node_query_response_recipient
+            .try_send(DispatcherNodeQueryResponse {
+                result: Some(NodeQueryResponseMetadata::new(
+                    PublicKey::new(&[]),
+                    Some(node_addr),
+                    ZERO_RATE_PACK.clone(),
+                )),
+                context: msg,
+            })
+            .expect("StreamHandlerPool is dead?")
     }
 
     fn handle_add_stream_msg(
@@ -320,6 +388,7 @@ impl StreamHandlerPool {
             msg.connection_info.peer_addr,
             msg.connection_info.local_addr,
         );
+        self.report_stream_count();
     }
 
     fn handle_remove_stream_msg(&mut self, msg: RemoveStreamMsg) {
@@ -331,10 +400,13 @@ impl StreamHandlerPool {
             msg.peer_addr,
             stream_writer_key
         );
+        self.masquerades.remove(&stream_writer_key);
+        self.read_switches.remove(&stream_writer_key);
         let report_to_counterpart = match self.stream_writers.remove(&stream_writer_key) {
             None | Some(None) => false,
             Some(Some(_sender_wrapper)) => true,
         };
+        self.report_stream_count();
         let stream_shutdown_msg = StreamShutdownMsg {
             peer_addr: msg.peer_addr,
             stream_type: msg.stream_type,
@@ -346,6 +418,41 @@ impl StreamHandlerPool {
             .expect("StreamShutdownMsg target is dead");
     }
 
+    fn handle_read_pressure_msg(&mut self, msg: ReadPressureMsg) {
+        let stream_writer_key = StreamWriterKey::from(msg.peer_addr);
+        match self.read_switches.get(&stream_writer_key) {
+            Some(read_switch) => {
+                debug!(
+                    self.logger,
+                    "Applying {:?} to reads from {}", msg.pressure, stream_writer_key
+                );
+                read_switch.apply(PauseReason::Backpressure, msg.pressure)
+            }
+            None => debug!(
+                self.logger,
+                "No stream from {} to apply {:?} to; ignoring", msg.peer_addr, msg.pressure
+            ),
+        }
+    }
+
+    fn handle_masquerade_detected_msg(&mut self, msg: MasqueradeDetectedMsg) {
+        let stream_writer_key = StreamWriterKey::from(msg.peer_addr);
+        // If we opened the stream, we already know what it's wearing; if the other end opened it,
+        // we answer in whatever it chose.
+        let (masquerade, role) = *self
+            .masquerades
+            .entry(stream_writer_key)
+            .or_insert((msg.masquerade, MasqueradeRole::Acceptor));
+        debug!(
+            self.logger,
+            "Stream to {} detected wearing the {} masquerade; using {} as {:?}",
+            stream_writer_key,
+            msg.masquerade,
+            masquerade,
+            role
+        );
+    }
+
     // TODO: This method is wayyyy too big
     fn handle_dispatcher_node_query_response(&mut self, msg: DispatcherNodeQueryResponse) {
         // TODO Can be recombined with TransmitDataMsg after SC-358/GH-96
@@ -382,7 +489,24 @@ impl StreamHandlerPool {
         // TODO: Picking the first port is a temporary hack. This problem should go away with clandestine ports.
         let peer_addr = SocketAddr::new(node_addr.ip_addr(), node_addr.ports()[0]);
 
-        let sw_key = StreamWriterKey::from(peer_addr);
+        // Nodes that haven't said they accept the other masquerades can unmask only json.
+        let masquerade = if msg
+            .result
+            .as_ref()
+            .map_or(false, |metadata| metadata.accepts_masquerades)
+        {
+            self.masquerade
+        } else {
+            MasqueradeKind::Json
+        };
+
+        // A dual-stack neighbor may already have a stream open to us from its other address.
+        let sw_key = node_addr
+            .ip_addrs()
+            .into_iter()
+            .map(|ip_addr| StreamWriterKey::from(SocketAddr::new(ip_addr, peer_addr.port())))
+            .find(|key| matches!(self.stream_writers.get(key), Some(Some(_))))
+            .unwrap_or_else(|| StreamWriterKey::from(peer_addr));
         match self.stream_writers.get(&sw_key) {
             Some(Some(tx_box)) => {
                 debug!(
@@ -393,7 +517,14 @@ impl StreamHandlerPool {
                 );
                 debug!(self.logger, "Masking {} bytes", msg.context.data.len());
                 let packet = if msg.context.sequence_number.is_none() {
-                    let masquerader = self.traffic_analyzer.get_masquerader();
+                    let (masquerade, role) = self
+                        .masquerades
+                        .get(&sw_key)
+                        .cloned()
+                        .unwrap_or((masquerade, MasqueradeRole::Initiator));
+                    let masquerader = self
+                        .traffic_analyzer
+                        .get_masquerader(masquerade, role, peer_addr);
                     match masquerader.mask(msg.context.data.as_slice()) {
                         Ok(masked_data) => SequencedPacket::new(masked_data, 0, false),
                         Err(e) => {
@@ -412,6 +543,18 @@ impl StreamHandlerPool {
                 };
 
                 let packet_len = packet.data.len();
+                if let Err(e) = tx_box.buffer().reserve(packet_len) {
+                    warning!(
+                        self.logger,
+                        "Abandoning stream to {}: can't queue {} more bytes: {}",
+                        peer_addr,
+                        packet_len,
+                        e
+                    );
+                    self.stream_writers.remove(&sw_key);
+                    self.report_stream_count();
+                    return;
+                }
                 match tx_box.unbounded_send(packet) {
                     Err(e) => {
                         debug!(
@@ -421,8 +564,7 @@ impl StreamHandlerPool {
                             peer_addr,
                             e
                         );
-                        self.stream_writers
-                            .remove(&StreamWriterKey::from(peer_addr));
+                        self.stream_writers.remove(&sw_key);
                     }
                     Ok(_) => {
                         debug!(self.logger, "Queued {} bytes for transmission", packet_len);
@@ -432,9 +574,9 @@ impl StreamHandlerPool {
                     debug!(self.logger,
                         "Removing channel to StreamWriter {} to {} in response to server-drop report", sw_key, peer_addr
                     );
-                    self.stream_writers
-                        .remove(&StreamWriterKey::from(peer_addr));
+                    self.stream_writers.remove(&sw_key);
                 }
+                self.report_stream_count();
             }
             Some(None) => {
                 debug!(self.logger, "Found in-the-process-of-being-opened stream to {} keyed by {}: preparing to use", peer_addr, sw_key);
@@ -482,8 +624,11 @@ impl StreamHandlerPool {
 
                 self.stream_writers
                     .insert(StreamWriterKey::from(peer_addr), None);
-                let logger_m = self.logger.clone();
-                let logger_me = self.logger.clone();
+                self.masquerades.insert(
+                    StreamWriterKey::from(peer_addr),
+                    (masquerade, MasqueradeRole::Initiator),
+                );
+                let logger_m = self.logger.with_peer_addr(peer_addr);
                 let clandestine_discriminator_factories =
                     self.clandestine_discriminator_factories.clone();
                 let msg_data_len = msg.context.data.len();
@@ -493,6 +638,7 @@ impl StreamHandlerPool {
                     .clone()
                     .map(|d| d.public_key)
                     .expect("Key magically disappeared");
+                let logger_me = logger_m.with_public_key(&key);
                 let sub = self
                     .dispatcher_subs
                     .as_ref()
@@ -500,9 +646,12 @@ impl StreamHandlerPool {
                     .stream_shutdown_sub
                     .clone();
 
-                let connect_future = self.stream_connector.connect(peer_addr, &self.logger)
-                    .map(move |connection_info| {
-                        debug!(logger_m, "Connection attempt to {} succeeded", peer_addr);
+                let connect_future = self.stream_connector.connect_any(node_addr.ip_addrs(), peer_addr.port(), &self.logger)
+                    .map(move |mut connection_info| {
+                        debug!(logger_m, "Connection attempt to {} succeeded via {}", peer_addr, connection_info.peer_addr);
+                        // Whichever of the neighbor's addresses won the race, the stream is known by
+                        // the one it was asked for, so that traffic waiting on that key finds it.
+                        connection_info.peer_addr = peer_addr;
                         let origin_port = connection_info.local_addr.port();
                         add_stream_sub.try_send(AddStreamMsg {
                             connection_info,
@@ -524,22 +673,45 @@ impl StreamHandlerPool {
                         tell_neighborhood.try_send(remove_node_message).expect("Neighborhood is Dead");
                     });
 
-                debug!(self.logger, "Beginning connection attempt to {}", peer_addr);
+                debug!(
+                    self.logger.with_peer_addr(peer_addr),
+                    "Beginning connection attempt to {}", peer_addr
+                );
                 tokio::spawn(connect_future);
             }
         }
     }
+
+    // Streams still being connected have no writer yet, and don't count as open.
+    fn report_stream_count(&self) {
+        let open_streams = self
+            .stream_writers
+            .values()
+            .filter(|writer_opt| writer_opt.is_some())
+            .count();
+        self.metrics.dispatcher_streams.set(open_streams as u64);
+    }
 }
 
 trait TrafficAnalyzer {
-    fn get_masquerader(&self) -> Box<dyn Masquerader>;
+    fn get_masquerader(
+        &self,
+        masquerade: MasqueradeKind,
+        role: MasqueradeRole,
+        peer_addr: SocketAddr,
+    ) -> Box<dyn Masquerader>;
 }
 
 struct TrafficAnalyzerReal {}
 
 impl TrafficAnalyzer for TrafficAnalyzerReal {
-    fn get_masquerader(&self) -> Box<dyn Masquerader> {
-        Box::new(JsonMasquerader::new())
+    fn get_masquerader(
+        &self,
+        masquerade: MasqueradeKind,
+        role: MasqueradeRole,
+        peer_addr: SocketAddr,
+    ) -> Box<dyn Masquerader> {
+        masquerade.masquerader(role, peer_addr)
     }
 }
 
@@ -548,6 +720,7 @@ impl TrafficAnalyzerReal {}
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http_masquerader::HttpMasquerader;
     use crate::http_request_start_finder::HttpRequestDiscriminatorFactory;
     use crate::json_discriminator_factory::JsonDiscriminatorFactory;
     use crate::json_masquerader::JsonMasquerader;
@@ -556,6 +729,9 @@ mod tests {
     use crate::sub_lib::cryptde::CryptDE;
     use crate::sub_lib::dispatcher::InboundClientData;
     use crate::sub_lib::neighborhood::NodeQueryResponseMetadata;
+    use crate::sub_lib::stream_buffer::{
+        Pressure, StreamBuffer, STREAM_BUFFER_CAPACITY, STREAM_BUFFER_PAUSE_LEVEL,
+    };
     use crate::sub_lib::stream_connector::ConnectionInfo;
     use crate::test_utils::channel_wrapper_mocks::SenderWrapperMock;
     use crate::test_utils::logging::init_test_logging;
@@ -569,9 +745,11 @@ mod tests {
     use crate::test_utils::tokio_wrapper_mocks::ReadHalfWrapperMock;
     use crate::test_utils::tokio_wrapper_mocks::WriteHalfWrapperMock;
     use crate::test_utils::{await_messages, main_cryptde};
+    use crate::tls_masquerader::TlsMasquerader;
     use actix::Actor;
     use actix::Addr;
     use actix::System;
+    use futures::future;
     use masq_lib::constants::HTTP_PORT;
     use std::io::Error;
     use std::io::ErrorKind;
@@ -588,7 +766,12 @@ mod tests {
     struct TrafficAnalyzerMock {}
 
     impl TrafficAnalyzer for TrafficAnalyzerMock {
-        fn get_masquerader(&self) -> Box<dyn Masquerader> {
+        fn get_masquerader(
+            &self,
+            _masquerade: MasqueradeKind,
+            _role: MasqueradeRole,
+            _peer_addr: SocketAddr,
+        ) -> Box<dyn Masquerader> {
             Box::new(FailingMasquerader {})
         }
     }
@@ -616,7 +799,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let mut subject = StreamHandlerPool::new(vec![]);
+            let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
             subject.stream_connector = Box::new(StreamConnectorMock::new());
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
@@ -726,7 +909,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let subject = StreamHandlerPool::new(vec![]);
+            let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
 
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
@@ -790,7 +973,7 @@ mod tests {
         thread::spawn(move || {
             let system = System::new("test");
 
-            let mut subject = StreamHandlerPool::new(vec![]);
+            let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
             subject.stream_connector = Box::new(
                 StreamConnectorMock::new()
                     .connect_pair_result(Err(Error::from(ErrorKind::ConnectionRefused))),
@@ -894,7 +1077,10 @@ mod tests {
         thread::spawn(move || {
             let system = System::new("test");
 
-            let mut subject = StreamHandlerPool::new(vec![Box::new(JsonDiscriminatorFactory {})]);
+            let mut subject = StreamHandlerPool::new(
+                vec![Box::new(JsonDiscriminatorFactory {})],
+                MasqueradeKind::Json,
+            );
             subject.stream_connector = Box::new(StreamConnectorMock::new().connection(
                 local_addr,
                 peer_addr,
@@ -966,7 +1152,7 @@ mod tests {
         let (recorder, _, recording_arc) = make_recorder();
         let system = System::new("test");
         let sub = recorder.start().recipient::<StreamShutdownMsg>();
-        let mut subject = StreamHandlerPool::new(vec![]);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
         let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
         let sw_key = StreamWriterKey::from(peer_addr);
@@ -997,12 +1183,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn open_streams_are_counted_in_metrics() {
+        let (recorder, _, _) = make_recorder();
+        let system = System::new("test");
+        let sub = recorder.start().recipient::<StreamShutdownMsg>();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+        let metrics = Arc::new(Metrics::default());
+        subject.metrics = metrics.clone();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
+        subject.stream_writers.insert(
+            StreamWriterKey::from(peer_addr),
+            Some(Box::new(SenderWrapperMock::new(local_addr))),
+        );
+        subject.stream_writers.insert(
+            StreamWriterKey::from(SocketAddr::from_str("2.3.4.5:6789").unwrap()),
+            Some(Box::new(SenderWrapperMock::new(local_addr))),
+        );
+        subject.stream_writers.insert(
+            StreamWriterKey::from(SocketAddr::from_str("3.4.5.6:7890").unwrap()),
+            None,
+        );
+
+        subject.handle_remove_stream_msg(RemoveStreamMsg {
+            peer_addr,
+            local_addr,
+            stream_type: RemovedStreamType::Clandestine,
+            sub,
+        });
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(metrics.dispatcher_streams.get(), 1);
+    }
+
     #[test]
     fn handle_remove_stream_msg_handles_no_report_to_counterpart_scenario() {
         let (recorder, _, recording_arc) = make_recorder();
         let system = System::new("test");
         let sub = recorder.start().recipient::<StreamShutdownMsg>();
-        let mut subject = StreamHandlerPool::new(vec![]);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
         let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
         let sw_key = StreamWriterKey::from(peer_addr);
@@ -1040,7 +1261,7 @@ mod tests {
         let (recorder, _, recording_arc) = make_recorder();
         let system = System::new("test");
         let sub = recorder.start().recipient::<StreamShutdownMsg>();
-        let mut subject = StreamHandlerPool::new(vec![]);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
         let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
         let local_addr = SocketAddr::from_str("127.0.0.1:0").unwrap();
         let sw_key = StreamWriterKey::from(peer_addr);
@@ -1079,7 +1300,7 @@ mod tests {
         let (neighborhood, neighborhood_awaiter, neighborhood_recording_arc) = make_recorder();
         thread::spawn(move || {
             let system = System::new("when_stream_handler_pool_fails_to_create_nonexistent_stream_for_write_then_it_logs_and_notifies_neighborhood");
-            let mut subject = StreamHandlerPool::new(vec![]);
+            let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
             subject.stream_connector = Box::new(
                 StreamConnectorMock::new()
                     .connect_pair_result(Err(Error::from(ErrorKind::Other)))
@@ -1153,7 +1374,8 @@ mod tests {
                 "stream_handler_pool_creates_nonexistent_stream_for_reading_and_writing",
             );
             let discriminator_factory = JsonDiscriminatorFactory::new();
-            let mut subject = StreamHandlerPool::new(vec![Box::new(discriminator_factory)]);
+            let mut subject =
+                StreamHandlerPool::new(vec![Box::new(discriminator_factory)], MasqueradeKind::Json);
             subject.stream_connector = Box::new(
                 StreamConnectorMock::new().connect_pair_result(Ok(ConnectionInfo {
                     reader: Box::new(
@@ -1241,6 +1463,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stream_handler_pool_races_a_dual_stack_neighbors_addresses_and_keys_the_winner_by_the_first()
+    {
+        let masquerader = JsonMasquerader::new();
+        let incoming_unmasked = b"Incoming data".to_vec();
+        let incoming_masked = masquerader.mask(&incoming_unmasked).unwrap();
+        let outgoing_unmasked = b"Outgoing data".to_vec();
+        let outgoing_masked = masquerader.mask(&outgoing_unmasked).unwrap();
+        let outgoing_masked_len = outgoing_masked.len();
+        let node_addr = NodeAddr::from_str("1.2.3.5/[2001:db8::5]:7000").unwrap();
+        let (dispatcher, dispatcher_awaiter, dispatcher_recording_arc) = make_recorder();
+        let connect_pair_params_arc = Arc::new(Mutex::new(vec![]));
+        let connect_pair_params_arc_a = connect_pair_params_arc.clone();
+        let poll_write_params_arc = Arc::new(Mutex::new(vec![]));
+        let poll_write_params_arc_a = poll_write_params_arc.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let system = System::new(
+                "stream_handler_pool_races_a_dual_stack_neighbors_addresses_and_keys_the_winner_by_the_first",
+            );
+            let discriminator_factory = JsonDiscriminatorFactory::new();
+            let mut subject =
+                StreamHandlerPool::new(vec![Box::new(discriminator_factory)], MasqueradeKind::Json);
+            subject.stream_connector = Box::new(
+                StreamConnectorMock::new()
+                    .connect_pair_result(Ok(ConnectionInfo {
+                        reader: Box::new(
+                            ReadHalfWrapperMock::new()
+                                .poll_read_ok(incoming_masked)
+                                .poll_read_result(vec![], Ok(Async::NotReady)),
+                        ),
+                        writer: Box::new(
+                            WriteHalfWrapperMock::new()
+                                .poll_write_ok(outgoing_masked_len)
+                                .poll_write_result(Ok(Async::NotReady))
+                                .poll_write_params(&poll_write_params_arc),
+                        ),
+                        local_addr: SocketAddr::from_str("[::1]:54321").unwrap(),
+                        peer_addr: SocketAddr::from_str("[2001:db8::5]:7000").unwrap(),
+                    }))
+                    .connect_pair_params(&connect_pair_params_arc),
+            );
+            let subject_addr: Addr<StreamHandlerPool> = subject.start();
+            let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
+            let peer_actors = peer_actors_builder().dispatcher(dispatcher).build();
+            subject_subs
+                .bind
+                .try_send(PoolBindMessage {
+                    dispatcher_subs: peer_actors.dispatcher,
+                    stream_handler_pool_subs: subject_subs.clone(),
+                    neighborhood_subs: peer_actors.neighborhood,
+                })
+                .unwrap();
+
+            tx.send(subject_subs).unwrap();
+
+            system.run();
+        });
+        let subject_subs = rx.recv().unwrap();
+
+        subject_subs
+            .transmit_sub
+            .try_send(TransmitDataMsg {
+                endpoint: Endpoint::Node(node_addr),
+                last_data: false,
+                sequence_number: None,
+                data: outgoing_unmasked,
+            })
+            .unwrap();
+
+        await_messages(1, &poll_write_params_arc_a);
+        let poll_write_params = poll_write_params_arc_a.lock().unwrap();
+        assert_eq!(poll_write_params[0], outgoing_masked);
+        let connect_pair_params = connect_pair_params_arc_a.lock().unwrap();
+        assert_eq!(
+            *connect_pair_params,
+            vec![
+                SocketAddr::from_str("1.2.3.5:7000").unwrap(),
+                SocketAddr::from_str("[2001:db8::5]:7000").unwrap(),
+            ]
+        );
+        dispatcher_awaiter.await_message_count(1);
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        let ibcd = dispatcher_recording.get_record::<InboundClientData>(0);
+        assert_eq!(
+            ibcd.peer_addr,
+            SocketAddr::from_str("1.2.3.5:7000").unwrap()
+        );
+        assert_eq!(ibcd.data, incoming_unmasked);
+    }
+
     #[test]
     fn transmit_data_msg_handler_finds_ip_from_neighborhood_and_transmits_message() {
         init_test_logging();
@@ -1262,7 +1575,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let subject = StreamHandlerPool::new(vec![]);
+            let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
 
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
@@ -1340,7 +1653,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let subject = StreamHandlerPool::new(vec![]);
+            let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
 
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
@@ -1388,7 +1701,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let subject = StreamHandlerPool::new(vec![]);
+            let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
 
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
@@ -1452,7 +1765,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let mut subject = StreamHandlerPool::new(vec![]);
+            let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
             subject
                 .stream_writers
                 .insert(StreamWriterKey::from(peer_addr), None);
@@ -1573,7 +1886,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let mut subject = StreamHandlerPool::new(vec![]);
+            let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
             subject.stream_connector = Box::new(
                 StreamConnectorMock::new()
                     .connect_pair_result(Err(Error::from(ErrorKind::Other)))
@@ -1642,7 +1955,7 @@ mod tests {
         let sender_wrapper = SenderWrapperMock::new(peer_addr)
             .unbounded_send_params(&sender_wrapper_unbounded_send_params_arc)
             .unbounded_send_result(Ok(()));
-        let mut subject = StreamHandlerPool::new(vec![]);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
         subject.stream_writers.insert(
             StreamWriterKey::from(peer_addr),
             Some(Box::new(sender_wrapper)),
@@ -1653,6 +1966,7 @@ mod tests {
                 public_key: key,
                 node_addr_opt: Some(NodeAddr::new(&peer_addr.ip(), &[peer_addr.port()])),
                 rate_pack: ZERO_RATE_PACK.clone(),
+                accepts_masquerades: false,
             }),
             context: TransmitDataMsg {
                 endpoint: Endpoint::Socket(peer_addr.clone()),
@@ -1684,6 +1998,371 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sequenced_data_reserves_room_in_the_stream_buffer() {
+        let peer_addr = SocketAddr::from_str("127.0.0.1:8006").unwrap();
+        let buffer = StreamBuffer::new();
+        let sender_wrapper = SenderWrapperMock::new(peer_addr).buffer_result(&buffer);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+        subject.stream_writers.insert(
+            StreamWriterKey::from(peer_addr),
+            Some(Box::new(sender_wrapper)),
+        );
+
+        subject.handle_dispatcher_node_query_response(DispatcherNodeQueryResponse {
+            result: Some(NodeQueryResponseMetadata {
+                public_key: main_cryptde().public_key().clone(),
+                node_addr_opt: Some(NodeAddr::new(&peer_addr.ip(), &[peer_addr.port()])),
+                rate_pack: ZERO_RATE_PACK.clone(),
+                accepts_masquerades: false,
+            }),
+            context: TransmitDataMsg {
+                endpoint: Endpoint::Socket(peer_addr),
+                last_data: false,
+                sequence_number: Some(0),
+                data: b"hello".to_vec(),
+            },
+        });
+
+        assert_eq!(buffer.queued(), 5);
+    }
+
+    #[test]
+    fn clandestine_data_reserves_room_in_the_stream_buffer_too() {
+        let peer_addr = SocketAddr::from_str("127.0.0.1:8008").unwrap();
+        let sender_wrapper_unbounded_send_params_arc = Arc::new(Mutex::new(vec![]));
+        let buffer = StreamBuffer::new();
+        let sender_wrapper = SenderWrapperMock::new(peer_addr)
+            .unbounded_send_params(&sender_wrapper_unbounded_send_params_arc)
+            .buffer_result(&buffer);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+        subject.stream_writers.insert(
+            StreamWriterKey::from(peer_addr),
+            Some(Box::new(sender_wrapper)),
+        );
+
+        subject.handle_dispatcher_node_query_response(DispatcherNodeQueryResponse {
+            result: Some(NodeQueryResponseMetadata {
+                public_key: main_cryptde().public_key().clone(),
+                node_addr_opt: Some(NodeAddr::new(&peer_addr.ip(), &[peer_addr.port()])),
+                rate_pack: ZERO_RATE_PACK.clone(),
+                accepts_masquerades: false,
+            }),
+            context: TransmitDataMsg {
+                endpoint: Endpoint::Socket(peer_addr),
+                last_data: false,
+                sequence_number: None,
+                data: b"hello".to_vec(),
+            },
+        });
+
+        let sent_packets = sender_wrapper_unbounded_send_params_arc.lock().unwrap();
+        assert_eq!(sent_packets.len(), 1);
+        assert_eq!(buffer.queued(), sent_packets[0].data.len());
+    }
+
+    #[test]
+    fn sequenced_data_that_overflows_the_stream_buffer_abandons_the_stream() {
+        init_test_logging();
+        let peer_addr = SocketAddr::from_str("127.0.0.1:8007").unwrap();
+        let sender_wrapper_unbounded_send_params_arc = Arc::new(Mutex::new(vec![]));
+        let buffer = StreamBuffer::with_limits(10, 8, 2);
+        buffer.reserve(8).unwrap();
+        let sender_wrapper = SenderWrapperMock::new(peer_addr)
+            .unbounded_send_params(&sender_wrapper_unbounded_send_params_arc)
+            .buffer_result(&buffer);
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+        subject.stream_writers.insert(
+            StreamWriterKey::from(peer_addr),
+            Some(Box::new(sender_wrapper)),
+        );
+
+        subject.handle_dispatcher_node_query_response(DispatcherNodeQueryResponse {
+            result: Some(NodeQueryResponseMetadata {
+                public_key: main_cryptde().public_key().clone(),
+                node_addr_opt: Some(NodeAddr::new(&peer_addr.ip(), &[peer_addr.port()])),
+                rate_pack: ZERO_RATE_PACK.clone(),
+                accepts_masquerades: false,
+            }),
+            context: TransmitDataMsg {
+                endpoint: Endpoint::Socket(peer_addr),
+                last_data: false,
+                sequence_number: Some(3),
+                data: b"hello".to_vec(),
+            },
+        });
+
+        assert_eq!(
+            sender_wrapper_unbounded_send_params_arc
+                .lock()
+                .unwrap()
+                .is_empty(),
+            true
+        );
+        assert_eq!(
+            subject
+                .stream_writers
+                .contains_key(&StreamWriterKey::from(peer_addr)),
+            false
+        );
+        assert_eq!(buffer.is_overflowed(), true);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: Dispatcher: Abandoning stream to 127.0.0.1:8007: can't queue 5 more bytes: 8 bytes already queued; buffer capacity is 10",
+        );
+    }
+
+    #[test]
+    fn read_pressure_is_applied_to_the_read_switch_of_the_right_stream() {
+        let peer_addr = SocketAddr::from_str("127.0.0.1:8008").unwrap();
+        let read_switch = ReadSwitch::new();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+        subject
+            .read_switches
+            .insert(StreamWriterKey::from(peer_addr), read_switch.clone());
+
+        let (paused, resumed) = future::lazy(move || {
+            subject.handle_read_pressure_msg(ReadPressureMsg {
+                peer_addr,
+                pressure: Pressure::Pause,
+            });
+            let paused = read_switch.is_paused();
+            subject.handle_read_pressure_msg(ReadPressureMsg {
+                peer_addr,
+                pressure: Pressure::Resume,
+            });
+            let resumed = !read_switch.is_paused();
+            future::ok::<_, ()>((paused, resumed))
+        })
+        .wait()
+        .unwrap();
+
+        assert_eq!(paused, true);
+        assert_eq!(resumed, true);
+    }
+
+    #[test]
+    fn read_pressure_for_an_unknown_stream_is_ignored() {
+        init_test_logging();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+
+        subject.handle_read_pressure_msg(ReadPressureMsg {
+            peer_addr: SocketAddr::from_str("127.0.0.1:8009").unwrap(),
+            pressure: Pressure::Pause,
+        });
+
+        TestLogHandler::new().exists_log_containing(
+            "DEBUG: Dispatcher: No stream from 127.0.0.1:8009 to apply Pause to; ignoring",
+        );
+    }
+
+    #[test]
+    fn slow_local_client_triggers_write_pressure_and_is_abandoned_when_its_buffer_overflows() {
+        init_test_logging();
+        let dispatcher = Recorder::new();
+        let dispatcher_recording_arc = dispatcher.get_recording();
+        let awaiter = dispatcher.get_awaiter();
+        let peer_addr = SocketAddr::from_str("127.0.0.1:8010").unwrap();
+        let local_addr = SocketAddr::from_str("127.0.0.1:80").unwrap();
+        let writer = WriteHalfWrapperMock::new()
+            .poll_write_result(Ok(Async::NotReady))
+            .poll_write_result(Ok(Async::NotReady))
+            .poll_write_result(Ok(Async::NotReady))
+            .poll_write_result(Ok(Async::NotReady))
+            .shutdown_ok();
+        let shutdown_results_arc = writer.shutdown_results.clone();
+        let half_of_pause_level = STREAM_BUFFER_PAUSE_LEVEL / 2;
+
+        thread::spawn(move || {
+            let system = System::new("test");
+            let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+            let subject_addr: Addr<StreamHandlerPool> = subject.start();
+            let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
+            let peer_actors = peer_actors_builder().dispatcher(dispatcher).build();
+            subject_subs
+                .bind
+                .try_send(PoolBindMessage {
+                    dispatcher_subs: peer_actors.dispatcher,
+                    stream_handler_pool_subs: subject_subs.clone(),
+                    neighborhood_subs: peer_actors.neighborhood,
+                })
+                .unwrap();
+            let connection_info = ConnectionInfo {
+                reader: Box::new(
+                    ReadHalfWrapperMock::new().poll_read_result(vec![], Ok(Async::NotReady)),
+                ),
+                writer: Box::new(writer),
+                local_addr,
+                peer_addr,
+            };
+            subject_subs
+                .add_sub
+                .try_send(AddStreamMsg::new(
+                    connection_info,
+                    Some(HTTP_PORT),
+                    PortConfiguration::new(
+                        vec![Box::new(HttpRequestDiscriminatorFactory::new())],
+                        false,
+                    ),
+                ))
+                .unwrap();
+            vec![
+                half_of_pause_level,
+                half_of_pause_level,
+                STREAM_BUFFER_CAPACITY,
+            ]
+            .into_iter()
+            .enumerate()
+            .for_each(|(sequence_number, len)| {
+                subject_subs
+                    .transmit_sub
+                    .try_send(TransmitDataMsg {
+                        endpoint: Endpoint::Socket(peer_addr),
+                        last_data: false,
+                        sequence_number: Some(sequence_number as u64),
+                        data: vec![0u8; len],
+                    })
+                    .unwrap()
+            });
+
+            system.run();
+        });
+
+        awaiter.await_message_count(1);
+        TestLogHandler::new().await_log_containing(
+            "WARN: StreamWriter for 127.0.0.1:8010: Abandoning stream to 127.0.0.1:8010: more data arrived for it than could be buffered",
+            5000,
+        );
+        let dispatcher_recording = dispatcher_recording_arc.lock().unwrap();
+        assert_eq!(
+            dispatcher_recording.get_record::<WritePressureMsg>(0),
+            &WritePressureMsg {
+                peer_addr,
+                pressure: Pressure::Pause,
+            }
+        );
+        TestLogHandler::new().exists_log_containing(&format!(
+            "WARN: Dispatcher: Abandoning stream to 127.0.0.1:8010: can't queue {} more bytes",
+            STREAM_BUFFER_CAPACITY
+        ));
+        assert_eq!(shutdown_results_arc.lock().unwrap().is_empty(), true);
+    }
+
+    fn transmit_unsequenced_data_through_existing_stream(
+        subject: &mut StreamHandlerPool,
+        peer_addr: SocketAddr,
+        accepts_masquerades: bool,
+        data: &[u8],
+    ) -> Vec<SequencedPacket> {
+        let sender_wrapper_unbounded_send_params_arc = Arc::new(Mutex::new(vec![]));
+        let sender_wrapper = SenderWrapperMock::new(peer_addr)
+            .unbounded_send_params(&sender_wrapper_unbounded_send_params_arc)
+            .unbounded_send_result(Ok(()));
+        subject.stream_writers.insert(
+            StreamWriterKey::from(peer_addr),
+            Some(Box::new(sender_wrapper)),
+        );
+
+        subject.handle_dispatcher_node_query_response(DispatcherNodeQueryResponse {
+            result: Some(NodeQueryResponseMetadata {
+                public_key: main_cryptde().public_key().clone(),
+                node_addr_opt: Some(NodeAddr::new(&peer_addr.ip(), &[peer_addr.port()])),
+                rate_pack: ZERO_RATE_PACK.clone(),
+                accepts_masquerades,
+            }),
+            context: TransmitDataMsg {
+                endpoint: Endpoint::Socket(peer_addr),
+                last_data: false,
+                sequence_number: None,
+                data: data.to_vec(),
+            },
+        });
+
+        let sender_wrapper_unbounded_send_params =
+            sender_wrapper_unbounded_send_params_arc.lock().unwrap();
+        sender_wrapper_unbounded_send_params.clone()
+    }
+
+    #[test]
+    fn unknown_stream_is_masked_with_configured_masquerade_as_initiator() {
+        let peer_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Http);
+
+        let result = transmit_unsequenced_data_through_existing_stream(
+            &mut subject,
+            peer_addr,
+            true,
+            b"hello",
+        );
+
+        let expected_data = HttpMasquerader::new(MasqueradeRole::Initiator)
+            .with_host(peer_addr)
+            .mask(b"hello")
+            .unwrap();
+        assert_eq!(result, vec![SequencedPacket::new(expected_data, 0, false)]);
+    }
+
+    #[test]
+    fn stream_to_node_that_does_not_accept_masquerades_is_masked_with_json() {
+        let peer_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Http);
+
+        let result = transmit_unsequenced_data_through_existing_stream(
+            &mut subject,
+            peer_addr,
+            false,
+            b"hello",
+        );
+
+        let expected_data = JsonMasquerader::new().mask(b"hello").unwrap();
+        assert_eq!(result, vec![SequencedPacket::new(expected_data, 0, false)]);
+    }
+
+    #[test]
+    fn accepted_stream_is_answered_in_the_masquerade_it_arrived_in() {
+        let peer_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+
+        subject.handle_masquerade_detected_msg(MasqueradeDetectedMsg {
+            peer_addr: SocketAddr::from_str("1.2.3.5:4321").unwrap(),
+            masquerade: MasqueradeKind::Http,
+        });
+        let result = transmit_unsequenced_data_through_existing_stream(
+            &mut subject,
+            peer_addr,
+            true,
+            b"hello",
+        );
+
+        let expected_data = HttpMasquerader::new(MasqueradeRole::Acceptor)
+            .mask(b"hello")
+            .unwrap();
+        assert_eq!(result, vec![SequencedPacket::new(expected_data, 0, false)]);
+    }
+
+    #[test]
+    fn masquerade_detected_on_stream_we_opened_does_not_change_our_masquerade() {
+        let peer_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
+        subject.masquerades.insert(
+            StreamWriterKey::from(peer_addr),
+            (MasqueradeKind::Tls, MasqueradeRole::Initiator),
+        );
+
+        subject.handle_masquerade_detected_msg(MasqueradeDetectedMsg {
+            peer_addr,
+            masquerade: MasqueradeKind::Http,
+        });
+        let result = transmit_unsequenced_data_through_existing_stream(
+            &mut subject,
+            peer_addr,
+            true,
+            b"hello",
+        );
+
+        let expected_data = TlsMasquerader::new().mask(b"hello").unwrap();
+        assert_eq!(result, vec![SequencedPacket::new(expected_data, 0, false)]);
+    }
+
     #[test]
     #[should_panic(
         expected = "Neighborhood has returned a NodeDescriptor with no ports. This indicates an unrecoverable error."
@@ -1702,7 +2381,7 @@ mod tests {
         };
 
         let system = System::new("test");
-        let subject = StreamHandlerPool::new(vec![]);
+        let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
         let subject_addr: Addr<StreamHandlerPool> = subject.start();
         let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
         let peer_actors = peer_actors_builder().build();
@@ -1750,7 +2429,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let subject = StreamHandlerPool::new(vec![]);
+            let subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
 
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
@@ -1823,7 +2502,7 @@ mod tests {
 
         thread::spawn(move || {
             let system = System::new("test");
-            let mut subject = StreamHandlerPool::new(vec![]);
+            let mut subject = StreamHandlerPool::new(vec![], MasqueradeKind::Json);
             subject.traffic_analyzer = Box::new(TrafficAnalyzerMock {});
 
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
@@ -1884,7 +2563,8 @@ mod tests {
                 "stream_handler_pool_creates_nonexistent_stream_for_reading_and_writing",
             );
             let discriminator_factory = JsonDiscriminatorFactory::new();
-            let mut subject = StreamHandlerPool::new(vec![Box::new(discriminator_factory)]);
+            let mut subject =
+                StreamHandlerPool::new(vec![Box::new(discriminator_factory)], MasqueradeKind::Json);
             subject.stream_connector = Box::new(StreamConnectorMock::new()); // this will panic if a connection is attempted
             let subject_addr: Addr<StreamHandlerPool> = subject.start();
             let subject_subs = StreamHandlerPool::make_subs_from(&subject_addr);
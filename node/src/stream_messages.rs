@@ -1,5 +1,6 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::bootstrapper::PortConfiguration;
+use crate::masquerader::MasqueradeKind;
 use crate::stream_handler_pool::StreamHandlerPoolSubs;
 use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg};
 use crate::sub_lib::neighborhood::NeighborhoodSubs;
@@ -61,6 +62,14 @@ impl Debug for RemoveStreamMsg {
     }
 }
 
+// Sent by a clandestine StreamReader once it has figured out which masquerade the Node on the
+// other end is using, so that the StreamHandlerPool can answer in kind.
+#[derive(PartialEq, Debug, Message)]
+pub struct MasqueradeDetectedMsg {
+    pub peer_addr: SocketAddr,
+    pub masquerade: MasqueradeKind,
+}
+
 #[derive(Message, Clone)]
 pub struct PoolBindMessage {
     pub dispatcher_subs: DispatcherSubs,
@@ -1,12 +1,15 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::discriminator::Discriminator;
 use crate::discriminator::DiscriminatorFactory;
+use crate::discriminator::UnmaskedChunk;
+use crate::masquerader::MasqueradeKind;
 use crate::proxy_server::http_protocol_pack::HttpProtocolPack;
 use crate::stream_messages::*;
 use crate::sub_lib::dispatcher;
 use crate::sub_lib::dispatcher::StreamShutdownMsg;
 use crate::sub_lib::logger::Logger;
 use crate::sub_lib::sequencer::Sequencer;
+use crate::sub_lib::stream_buffer::ReadSwitch;
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
 use crate::sub_lib::utils::indicates_dead_stream;
 use actix::Recipient;
@@ -22,8 +25,11 @@ pub struct StreamReaderReal {
     ibcd_sub: Recipient<dispatcher::InboundClientData>,
     remove_sub: Recipient<RemoveStreamMsg>,
     stream_shutdown_sub: Recipient<StreamShutdownMsg>,
+    masquerade_detected_sub: Recipient<MasqueradeDetectedMsg>,
     discriminators: Vec<Discriminator>,
+    masquerades: Vec<Option<MasqueradeKind>>,
     is_clandestine: bool,
+    read_switch: ReadSwitch,
     logger: Logger,
     sequencer: Sequencer,
 }
@@ -35,6 +41,9 @@ impl Future for StreamReaderReal {
     fn poll(&mut self) -> Result<Async<()>, ()> {
         let mut buf = [0u8; 0x0001_0000];
         loop {
+            if self.read_switch.is_paused() {
+                return Ok(Async::NotReady);
+            }
             match self.stream.poll_read(&mut buf) {
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Ok(Async::Ready(0)) => {
@@ -89,15 +98,21 @@ impl StreamReaderReal {
         ibcd_sub: Recipient<dispatcher::InboundClientData>,
         remove_sub: Recipient<RemoveStreamMsg>,
         stream_shutdown_sub: Recipient<StreamShutdownMsg>,
+        masquerade_detected_sub: Recipient<MasqueradeDetectedMsg>,
         discriminator_factories: Vec<Box<dyn DiscriminatorFactory>>,
         is_clandestine: bool,
         peer_addr: SocketAddr,
         local_addr: SocketAddr,
+        read_switch: ReadSwitch,
     ) -> StreamReaderReal {
         let name = format!("StreamReader for {}", peer_addr);
         if discriminator_factories.is_empty() {
             panic!("Internal error: no Discriminator factories!")
         }
+        let masquerades: Vec<Option<MasqueradeKind>> = discriminator_factories
+            .iter()
+            .map(|df| df.masquerade_opt())
+            .collect();
         let discriminators: Vec<Discriminator> = discriminator_factories
             .into_iter()
             .map(|df| (df.make()))
@@ -110,27 +125,40 @@ impl StreamReaderReal {
             ibcd_sub,
             remove_sub,
             stream_shutdown_sub,
+            masquerade_detected_sub,
             discriminators,
+            masquerades,
             is_clandestine,
+            read_switch,
             logger: Logger::new(&name),
             sequencer: Sequencer::new(),
         }
     }
 
     fn wrangle_discriminators(&mut self, buf: &[u8], length: usize) {
-        // Although discriminators is a vec, it was never really designed to have more than one.
         let is_connect = HttpProtocolPack::is_connect(buf);
-        let chosen_discriminator = if self.discriminators.len() > 1 && is_connect {
-            &mut self.discriminators[1]
+        let mut pending_chunk_opt = if self.is_clandestine && self.discriminators.len() > 1 {
+            match self.detect_masquerade(&buf[..length]) {
+                Some(unmasked_chunk) => Some(unmasked_chunk),
+                None => return,
+            }
         } else {
-            &mut self.discriminators[0]
+            debug!(self.logger, "Adding {} bytes to discriminator", length);
+            self.chosen_discriminator(is_connect)
+                .add_data(&buf[..length]);
+            None
         };
-
-        debug!(self.logger, "Adding {} bytes to discriminator", length);
-        chosen_discriminator.add_data(&buf[..length]);
         loop {
-            match chosen_discriminator.take_chunk() {
+            let unmasked_chunk_opt = match pending_chunk_opt.take() {
+                Some(unmasked_chunk) => Some(unmasked_chunk),
+                None => self.chosen_discriminator(is_connect).take_chunk(),
+            };
+            match unmasked_chunk_opt {
                 Some(unmasked_chunk) => {
+                    // A client may send the start of its tunneled conversation in the same read
+                    // as its CONNECT; only the CONNECT itself is sequenced specially.
+                    let is_connect =
+                        is_connect && HttpProtocolPack::is_connect(&unmasked_chunk.chunk);
                     // For Proxy Clients that send an Http Connect message via TLS, sequence_number
                     // should be Some(0). The next message the ProxyClient will send begins the TLS
                     // handshake and should start the sequence at Some(0) as well, the ProxyServer will
@@ -178,6 +206,57 @@ impl StreamReaderReal {
         }
     }
 
+    fn chosen_discriminator(&mut self, is_connect: bool) -> &mut Discriminator {
+        // For non-clandestine streams, discriminators is a vec, but it was never really designed
+        // to have more than one.
+        if self.discriminators.len() > 1 && is_connect {
+            &mut self.discriminators[1]
+        } else {
+            &mut self.discriminators[0]
+        }
+    }
+
+    // A clandestine stream may arrive wearing any of the masquerades we know. Until one of the
+    // discriminators manages to unmask a chunk, every one of them gets all the data; after that,
+    // the successful one is the only one kept.
+    fn detect_masquerade(&mut self, data: &[u8]) -> Option<UnmaskedChunk> {
+        debug!(
+            self.logger,
+            "Adding {} bytes to {} discriminators",
+            data.len(),
+            self.discriminators.len()
+        );
+        self.discriminators
+            .iter_mut()
+            .for_each(|discriminator| discriminator.add_data(data));
+        let (index, unmasked_chunk) =
+            self.discriminators
+                .iter_mut()
+                .enumerate()
+                .find_map(|(index, discriminator)| {
+                    discriminator
+                        .take_chunk()
+                        .map(|unmasked_chunk| (index, unmasked_chunk))
+                })?;
+        self.discriminators = vec![self.discriminators.remove(index)];
+        if let Some(masquerade) = self.masquerades.remove(index) {
+            debug!(
+                self.logger,
+                "Stream {} is using the {} masquerade",
+                Self::stringify(self.local_addr, self.peer_addr),
+                masquerade
+            );
+            self.masquerade_detected_sub
+                .try_send(MasqueradeDetectedMsg {
+                    peer_addr: self.peer_addr,
+                    masquerade,
+                })
+                .expect("StreamHandlerPool is dead");
+        }
+        self.masquerades.clear();
+        Some(unmasked_chunk)
+    }
+
     fn shutdown(&mut self) {
         debug!(self.logger, "Directing removal of {}clandestine StreamReader with reception_port {:?} on {} listening to {}", if self.is_clandestine {""} else {"non-"}, self.reception_port, self.local_addr, self.peer_addr);
         self.remove_sub
@@ -207,6 +286,7 @@ impl StreamReaderReal {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::explicit_proxy_discriminator_factory::ExplicitProxyDiscriminatorFactory;
     use crate::http_request_start_finder::HttpRequestDiscriminatorFactory;
     use crate::json_discriminator_factory::JsonDiscriminatorFactory;
     use crate::json_masquerader::JsonMasquerader;
@@ -215,6 +295,7 @@ mod tests {
     use crate::stream_handler_pool::StreamHandlerPoolSubs;
     use crate::stream_messages::RemovedStreamType::NonClandestine;
     use crate::sub_lib::dispatcher::DispatcherSubs;
+    use crate::sub_lib::stream_buffer::{PauseReason, Pressure};
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
     use crate::test_utils::recorder::make_dispatcher_subs_from;
@@ -223,9 +304,11 @@ mod tests {
     use crate::test_utils::recorder::Recording;
     use crate::test_utils::tokio_wrapper_mocks::ReadHalfWrapperMock;
     use crate::tls_discriminator_factory::TlsDiscriminatorFactory;
+    use crate::tls_masquerader::TlsMasquerader;
     use actix::Actor;
     use actix::Addr;
     use actix::System;
+    use futures::future;
     use masq_lib::constants::HTTP_PORT;
     use std::io;
     use std::io::ErrorKind;
@@ -245,6 +328,49 @@ mod tests {
         (recording, make_dispatcher_subs_from(&addr))
     }
 
+    #[test]
+    fn stream_reader_does_not_read_while_its_read_switch_is_paused() {
+        let system = System::new("test");
+        let (_, stream_handler_pool_subs) = stream_handler_pool_stuff();
+        let (_, dispatcher_subs) = dispatcher_stuff();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let local_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let discriminator_factories: Vec<Box<dyn DiscriminatorFactory>> =
+            vec![Box::new(HttpRequestDiscriminatorFactory::new())];
+        let reader = ReadHalfWrapperMock {
+            poll_read_results: vec![(vec![], Ok(Async::Ready(0)))],
+        };
+        let read_switch = ReadSwitch::new();
+        read_switch.apply(PauseReason::Backpressure, Pressure::Pause);
+        let mut subject = StreamReaderReal::new(
+            Box::new(reader),
+            Some(HTTP_PORT),
+            dispatcher_subs.ibcd_sub,
+            stream_handler_pool_subs.remove_sub,
+            dispatcher_subs.stream_shutdown_sub.clone(),
+            stream_handler_pool_subs.masquerade_detected_sub,
+            discriminator_factories,
+            false,
+            peer_addr,
+            local_addr,
+            read_switch.clone(),
+        );
+
+        let (paused_result, resumed_result) = future::lazy(move || {
+            let paused_result = subject.poll();
+            read_switch.apply(PauseReason::Backpressure, Pressure::Resume);
+            let resumed_result = subject.poll();
+            future::ok::<_, ()>((paused_result, resumed_result))
+        })
+        .wait()
+        .unwrap();
+
+        System::current().stop_with_code(0);
+        system.run();
+        assert_eq!(paused_result, Ok(Async::NotReady));
+        assert_eq!(resumed_result, Ok(Async::Ready(())));
+    }
+
     #[test]
     fn stream_reader_shuts_down_and_returns_ok_on_0_byte_read() {
         init_test_logging();
@@ -265,10 +391,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub.clone(),
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         let result = subject.poll();
@@ -314,10 +442,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub.clone(),
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         let result = subject.poll();
@@ -363,10 +493,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         let result = subject.poll();
@@ -406,10 +538,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         let _result = subject.poll();
@@ -446,10 +580,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
     }
 
@@ -485,10 +621,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         subject.poll().err();
@@ -517,6 +655,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn clandestine_stream_reader_detects_masquerade_and_sticks_with_it() {
+        let system = System::new("test");
+        let (shp_recording_arc, stream_handler_pool_subs) = stream_handler_pool_stuff();
+        let (d_recording_arc, dispatcher_subs) = dispatcher_stuff();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let local_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let discriminator_factories: Vec<Box<dyn DiscriminatorFactory>> = MasqueradeKind::all()
+            .into_iter()
+            .map(|masquerade| masquerade.discriminator_factory())
+            .collect();
+        let masquerader = TlsMasquerader::new();
+        let first_tls = masquerader.mask(b"booga").unwrap();
+        let json = JsonMasquerader::new().mask(b"ignored").unwrap();
+        let second_tls = masquerader.mask(b"agoob").unwrap();
+        let reader = ReadHalfWrapperMock {
+            poll_read_results: vec![
+                (first_tls.clone(), Ok(Async::Ready(first_tls.len()))),
+                (json.clone(), Ok(Async::Ready(json.len()))),
+                (second_tls.clone(), Ok(Async::Ready(second_tls.len()))),
+                (vec![], Ok(Async::NotReady)),
+            ],
+        };
+        let mut subject = StreamReaderReal::new(
+            Box::new(reader),
+            None,
+            dispatcher_subs.ibcd_sub,
+            stream_handler_pool_subs.remove_sub,
+            dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
+            discriminator_factories,
+            true,
+            peer_addr,
+            local_addr,
+            ReadSwitch::new(),
+        );
+
+        let _ = subject.poll();
+
+        System::current().stop_with_code(0);
+        system.run();
+        let shp_recording = shp_recording_arc.lock().unwrap();
+        assert_eq!(
+            shp_recording.get_record::<MasqueradeDetectedMsg>(0),
+            &MasqueradeDetectedMsg {
+                peer_addr,
+                masquerade: MasqueradeKind::Tls,
+            }
+        );
+        assert_eq!(shp_recording.len(), 1);
+        let d_recording = d_recording_arc.lock().unwrap();
+        let make_ibcd = |data: &[u8]| dispatcher::InboundClientData {
+            peer_addr,
+            reception_port: None,
+            last_data: false,
+            is_clandestine: true,
+            sequence_number: None,
+            data: data.to_vec(),
+        };
+        assert_eq!(
+            d_recording.get_record::<dispatcher::InboundClientData>(0),
+            &make_ibcd(b"booga")
+        );
+        assert_eq!(
+            d_recording.get_record::<dispatcher::InboundClientData>(1),
+            &make_ibcd(b"agoob")
+        );
+        assert_eq!(d_recording.len(), 2);
+    }
+
     #[test]
     fn stream_reader_sends_two_correct_sequenced_messages_when_sent_a_http_connect() {
         let system = System::new("test");
@@ -548,10 +756,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             false,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         subject.poll().err();
@@ -574,6 +784,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stream_reader_sequences_tunneled_data_that_arrives_in_the_same_read_as_a_connect() {
+        let system = System::new("test");
+        let (_, stream_handler_pool_subs) = stream_handler_pool_stuff();
+        let (d_recording_arc, dispatcher_subs) = dispatcher_stuff();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let local_addr = SocketAddr::from_str("1.2.3.5:6789").unwrap();
+        let discriminator_factories: Vec<Box<dyn DiscriminatorFactory>> =
+            vec![Box::new(ExplicitProxyDiscriminatorFactory::new())];
+        let http_connect_request = Vec::from("CONNECT example.com:443 HTTP/1.1\r\n\r\n".as_bytes());
+        let tls_request = Vec::from(&[0x16, 0x03, 0x01, 0x00, 0x03, 0x01, 0x02, 0x03][..]);
+        let mut first_read = http_connect_request.clone();
+        first_read.extend_from_slice(&tls_request);
+        let reader = ReadHalfWrapperMock {
+            poll_read_results: vec![
+                (first_read.clone(), Ok(Async::Ready(first_read.len()))),
+                (tls_request.clone(), Ok(Async::Ready(tls_request.len()))),
+                (vec![], Ok(Async::NotReady)),
+            ],
+        };
+
+        let mut subject = StreamReaderReal::new(
+            Box::new(reader),
+            Some(1234 as u16),
+            dispatcher_subs.ibcd_sub,
+            stream_handler_pool_subs.remove_sub,
+            dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
+            discriminator_factories,
+            false,
+            peer_addr,
+            local_addr,
+            ReadSwitch::new(),
+        );
+
+        subject.poll().err();
+
+        System::current().stop();
+        system.run();
+
+        let d_recording = d_recording_arc.lock().unwrap();
+        let records = (0..d_recording.len())
+            .map(|index| {
+                let ibcd = d_recording.get_record::<dispatcher::InboundClientData>(index);
+                (ibcd.data.clone(), ibcd.sequence_number)
+            })
+            .collect::<Vec<(Vec<u8>, Option<u64>)>>();
+        assert_eq!(
+            records,
+            vec![
+                (http_connect_request, Some(0)),
+                (tls_request.clone(), Some(0)),
+                (tls_request, Some(1)),
+            ]
+        );
+    }
+
     #[test]
     fn stream_reader_assigns_a_sequence_to_inbound_client_data_that_are_flagged_as_sequenced() {
         let system = System::new("test");
@@ -600,10 +867,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             false,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         let _result = subject.poll();
@@ -667,10 +936,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub,
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         let _result = subject.poll();
@@ -708,10 +979,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub.clone(),
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             true,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
 
         subject.shutdown();
@@ -747,10 +1020,12 @@ mod tests {
             dispatcher_subs.ibcd_sub,
             stream_handler_pool_subs.remove_sub,
             dispatcher_subs.stream_shutdown_sub.clone(),
+            stream_handler_pool_subs.masquerade_detected_sub,
             discriminator_factories,
             false,
             peer_addr,
             local_addr,
+            ReadSwitch::new(),
         );
         subject.sequencer.next_sequence_number(); // just so it's not 0
 
@@ -27,6 +27,16 @@ impl Future for StreamWriterSorted {
             return self.shutdown();
         }
 
+        if self.rx_to_write.buffer().is_overflowed() {
+            warning!(
+                self.logger,
+                "Abandoning stream to {}: more data arrived for it than could be buffered",
+                self.peer_addr
+            );
+            self.shutting_down = true;
+            return self.shutdown();
+        }
+
         let read_result = self.read_data_from_channel();
         let write_result = self.write_from_buffer_to_stream();
 
@@ -117,6 +127,7 @@ impl StreamWriterSorted {
                                 &packet.data.len(),
                                 &packet.sequence_number
                             );
+                            self.rx_to_write.buffer().release(len);
                             if len != packet.data.len() {
                                 debug!(
                                     self.logger,
@@ -161,6 +172,7 @@ enum WriteBufferStatus {
 mod tests {
     use super::*;
     use crate::sub_lib::sequence_buffer::SequencedPacket;
+    use crate::sub_lib::stream_buffer::StreamBuffer;
     use crate::test_utils::channel_wrapper_mocks::ReceiverWrapperMock;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
@@ -613,4 +625,57 @@ mod tests {
 
         assert_eq!(res, Err(()));
     }
+
+    #[test]
+    fn stream_writer_releases_buffer_space_for_the_bytes_it_writes() {
+        let buffer = StreamBuffer::new();
+        buffer.reserve(11).unwrap();
+        let mut rx = Box::new(ReceiverWrapperMock::new());
+        rx.buffer = buffer.clone();
+        rx.poll_results = vec![
+            Ok(Async::Ready(Some(SequencedPacket::new(
+                b"hello".to_vec(),
+                0,
+                false,
+            )))),
+            Ok(Async::Ready(Some(SequencedPacket::new(
+                b"worlds".to_vec(),
+                1,
+                false,
+            )))),
+            Ok(Async::NotReady),
+        ];
+        let writer = WriteHalfWrapperMock::new()
+            .poll_write_result(Ok(Async::Ready(5)))
+            .poll_write_result(Ok(Async::Ready(4)))
+            .poll_write_result(Ok(Async::NotReady));
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let mut subject = StreamWriterSorted::new(Box::new(writer), peer_addr, rx);
+
+        let result = subject.poll();
+
+        assert_eq!(result, Ok(Async::NotReady));
+        assert_eq!(buffer.queued(), 2);
+    }
+
+    #[test]
+    fn stream_writer_abandons_the_stream_when_its_buffer_has_overflowed() {
+        init_test_logging();
+        let buffer = StreamBuffer::with_limits(10, 8, 2);
+        buffer.reserve(11).err().unwrap();
+        let mut rx = Box::new(ReceiverWrapperMock::new());
+        rx.buffer = buffer;
+        let writer = WriteHalfWrapperMock::new().shutdown_ok();
+        let write_params = writer.poll_write_params.clone();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5679").unwrap();
+        let mut subject = StreamWriterSorted::new(Box::new(writer), peer_addr, rx);
+
+        let result = subject.poll();
+
+        assert_eq!(result, Ok(Async::Ready(())));
+        assert_eq!(write_params.lock().unwrap().is_empty(), true);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: StreamWriter for 1.2.3.4:5679: Abandoning stream to 1.2.3.4:5679: more data arrived for it than could be buffered",
+        );
+    }
 }
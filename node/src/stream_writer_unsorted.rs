@@ -9,6 +9,7 @@ use tokio::prelude::Future;
 
 pub struct StreamWriterUnsorted {
     stream: Box<dyn WriteHalfWrapper>,
+    peer_addr: SocketAddr,
     rx_to_write: Box<dyn ReceiverWrapper<SequencedPacket>>,
     logger: Logger,
     buf: Option<SequencedPacket>,
@@ -19,6 +20,14 @@ impl Future for StreamWriterUnsorted {
     type Error = ();
 
     fn poll(&mut self) -> Result<Async<<Self as Future>::Item>, <Self as Future>::Error> {
+        if self.rx_to_write.buffer().is_overflowed() {
+            warning!(
+                self.logger,
+                "Dropping connection to {}: more data arrived for it than could be buffered",
+                self.peer_addr
+            );
+            return Err(());
+        }
         loop {
             match self.buf.take() {
                 None => {
@@ -61,6 +70,7 @@ impl Future for StreamWriterUnsorted {
                                 len,
                                 &packet.data.len()
                             );
+                            self.rx_to_write.buffer().release(len);
                             if len != packet.data.len() {
                                 debug!(
                                     self.logger,
@@ -95,6 +105,7 @@ impl StreamWriterUnsorted {
         let logger = Logger::new(&name[..]);
         StreamWriterUnsorted {
             stream,
+            peer_addr,
             rx_to_write,
             logger,
             buf: None,
@@ -105,6 +116,7 @@ impl StreamWriterUnsorted {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sub_lib::stream_buffer::StreamBuffer;
     use crate::test_utils::channel_wrapper_mocks::ReceiverWrapperMock;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
@@ -402,4 +414,56 @@ mod tests {
         );
         assert_eq!(write_params.lock().unwrap().get(2).unwrap(), &b"s".to_vec());
     }
+
+    #[test]
+    fn stream_writer_releases_buffer_space_for_the_bytes_it_writes() {
+        let buffer = StreamBuffer::new();
+        buffer.reserve(11).unwrap();
+        let mut rx = Box::new(ReceiverWrapperMock::new());
+        rx.buffer = buffer.clone();
+        rx.poll_results = vec![
+            Ok(Async::Ready(Some(SequencedPacket::new(
+                b"hello".to_vec(),
+                0,
+                false,
+            )))),
+            Ok(Async::Ready(Some(SequencedPacket::new(
+                b"worlds".to_vec(),
+                0,
+                false,
+            )))),
+        ];
+        let writer = WriteHalfWrapperMock::new()
+            .poll_write_result(Ok(Async::Ready(5)))
+            .poll_write_result(Ok(Async::Ready(4)))
+            .poll_write_result(Ok(Async::NotReady));
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let mut subject = StreamWriterUnsorted::new(Box::new(writer), peer_addr, rx);
+
+        let result = subject.poll();
+
+        assert_eq!(result, Ok(Async::NotReady));
+        assert_eq!(buffer.queued(), 2);
+    }
+
+    #[test]
+    fn stream_writer_drops_the_connection_when_its_buffer_has_overflowed() {
+        init_test_logging();
+        let buffer = StreamBuffer::with_limits(10, 8, 2);
+        buffer.reserve(11).err().unwrap();
+        let mut rx = Box::new(ReceiverWrapperMock::new());
+        rx.buffer = buffer;
+        let writer = WriteHalfWrapperMock::new();
+        let write_params = writer.poll_write_params.clone();
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5679").unwrap();
+        let mut subject = StreamWriterUnsorted::new(Box::new(writer), peer_addr, rx);
+
+        let result = subject.poll();
+
+        assert_eq!(result, Err(()));
+        assert_eq!(write_params.lock().unwrap().is_empty(), true);
+        TestLogHandler::new().exists_log_containing(
+            "WARN: StreamWriter for 1.2.3.4:5679: Dropping connection to 1.2.3.4:5679: more data arrived for it than could be buffered",
+        );
+    }
 }
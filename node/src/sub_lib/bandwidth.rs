@@ -0,0 +1,700 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::sub_lib::cryptde::PublicKey;
+use lazy_static::lazy_static;
+use masq_lib::messages::UiServiceBandwidthLimits;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+pub const KILOBYTE: u64 = 1024;
+// A stream whose traffic has to wait longer than this for its bandwidth is asked to pause, where
+// the Node can ask.
+pub const MAX_BANDWIDTH_DELAY: Duration = Duration::from_secs(5);
+// The Node counts as throttled for this long after it last held back any traffic.
+pub const THROTTLED_MEMORY: Duration = Duration::from_secs(60);
+// Once there are more per-neighbor buckets than this, the ones that have refilled are forgotten.
+const NEIGHBOR_BUCKET_PRUNE_THRESHOLD: usize = 256;
+
+lazy_static! {
+    static ref BANDWIDTH_THROTTLE: Arc<BandwidthThrottle> = Arc::new(BandwidthThrottle::new());
+}
+
+// The BandwidthThrottle the RoutingService, the ProxyClient, the Neighborhood, and the
+// Configurator in this process share. Like metrics(), it's kept in a field, so that tests can
+// substitute a fresh one.
+pub fn bandwidth_throttle() -> Arc<BandwidthThrottle> {
+    BANDWIDTH_THROTTLE.clone()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BandwidthService {
+    Routing,
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+// Download traffic is charged to the neighbor it came from; upload traffic, to the neighbor it's
+// going to. Where it's going is known only by public key until the Dispatcher looks it up.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Traffic {
+    Download(IpAddr),
+    Upload(PublicKey),
+}
+
+impl Traffic {
+    pub fn direction(&self) -> Direction {
+        match self {
+            Traffic::Download(_) => Direction::Download,
+            Traffic::Upload(_) => Direction::Upload,
+        }
+    }
+}
+
+// Kilobytes per second; zero means unlimited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ServiceBandwidthLimits {
+    pub download: u64,
+    pub upload: u64,
+    pub neighbor_download: u64,
+    pub neighbor_upload: u64,
+}
+
+impl Display for ServiceBandwidthLimits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            self.download, self.upload, self.neighbor_download, self.neighbor_upload
+        )
+    }
+}
+
+impl FromStr for ServiceBandwidthLimits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UiServiceBandwidthLimits::from_str(s).map(ServiceBandwidthLimits::from)
+    }
+}
+
+impl From<UiServiceBandwidthLimits> for ServiceBandwidthLimits {
+    fn from(limits: UiServiceBandwidthLimits) -> Self {
+        ServiceBandwidthLimits {
+            download: limits.download,
+            upload: limits.upload,
+            neighbor_download: limits.neighbor_download,
+            neighbor_upload: limits.neighbor_upload,
+        }
+    }
+}
+
+impl From<ServiceBandwidthLimits> for UiServiceBandwidthLimits {
+    fn from(limits: ServiceBandwidthLimits) -> Self {
+        UiServiceBandwidthLimits {
+            download: limits.download,
+            upload: limits.upload,
+            neighbor_download: limits.neighbor_download,
+            neighbor_upload: limits.neighbor_upload,
+        }
+    }
+}
+
+impl ServiceBandwidthLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self == &ServiceBandwidthLimits::default()
+    }
+
+    fn global(&self, direction: Direction) -> u64 {
+        match direction {
+            Direction::Download => self.download,
+            Direction::Upload => self.upload,
+        }
+    }
+
+    fn per_neighbor(&self, direction: Direction) -> u64 {
+        match direction {
+            Direction::Download => self.neighbor_download,
+            Direction::Upload => self.neighbor_upload,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct BandwidthLimits {
+    pub routing: ServiceBandwidthLimits,
+    pub exit: ServiceBandwidthLimits,
+}
+
+impl BandwidthLimits {
+    pub fn for_service(&self, service: BandwidthService) -> &ServiceBandwidthLimits {
+        match service {
+            BandwidthService::Routing => &self.routing,
+            BandwidthService::Exit => &self.exit,
+        }
+    }
+}
+
+// Holds up to one second's worth of bytes. Traffic is always let into the bucket, even when that
+// puts it in debt; the debt says how long the traffic should wait before it goes on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenBucket {
+    bytes_per_second: u64,
+    balance: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn from_kbps(kbps: u64, now: Instant) -> Self {
+        Self::new(kbps.checked_mul(KILOBYTE).unwrap_or(u64::MAX), now)
+    }
+
+    pub fn new(bytes_per_second: u64, now: Instant) -> Self {
+        Self {
+            bytes_per_second,
+            balance: bytes_per_second as f64,
+            last_refill: now,
+        }
+    }
+
+    // How long byte_count more bytes would have to wait, were they consumed now
+    pub fn delay(&mut self, byte_count: usize, now: Instant) -> Duration {
+        self.refill(now);
+        let shortfall = byte_count as f64 - self.balance;
+        if shortfall <= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(shortfall / self.bytes_per_second as f64)
+        }
+    }
+
+    pub fn consume(&mut self, byte_count: usize, now: Instant) {
+        self.refill(now);
+        self.balance -= byte_count as f64;
+    }
+
+    pub fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.balance >= self.bytes_per_second as f64
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let capacity = self.bytes_per_second as f64;
+        self.balance = capacity.min(self.balance + elapsed.as_secs_f64() * capacity);
+        if now > self.last_refill {
+            self.last_refill = now;
+        }
+    }
+}
+
+pub struct BandwidthThrottle {
+    inner: Mutex<BandwidthThrottleInner>,
+}
+
+#[derive(Default)]
+struct BandwidthThrottleInner {
+    limits: BandwidthLimits,
+    global_buckets: HashMap<(BandwidthService, Direction), TokenBucket>,
+    neighbor_buckets: HashMap<(BandwidthService, Traffic), TokenBucket>,
+    last_throttled_opt: Option<Instant>,
+    delayed_bytes: u64,
+}
+
+impl Default for BandwidthThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BandwidthThrottle {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(BandwidthThrottleInner::default()),
+        }
+    }
+
+    pub fn limits(&self) -> BandwidthLimits {
+        self.lock().limits
+    }
+
+    // The buckets start over, full, at the new rates.
+    pub fn set_limits(&self, limits: BandwidthLimits) {
+        let mut inner = self.lock();
+        inner.limits = limits;
+        inner.global_buckets.clear();
+        inner.neighbor_buckets.clear();
+    }
+
+    // Traffic is always charged, because none of it is dropped: part of a stream that went missing
+    // would stall everything after it. The result is how long the traffic should be held, or its
+    // source should stop producing more.
+    pub fn charge(
+        &self,
+        service: BandwidthService,
+        traffic: Traffic,
+        byte_count: usize,
+        now: Instant,
+    ) -> Duration {
+        self.charge_all(service, vec![traffic], byte_count, now)
+    }
+
+    // Like charge(), for traffic that counts against several limits at once, such as a package
+    // that's downloaded from one neighbor and uploaded to another: it waits for the slowest.
+    pub fn charge_all(
+        &self,
+        service: BandwidthService,
+        traffics: Vec<Traffic>,
+        byte_count: usize,
+        now: Instant,
+    ) -> Duration {
+        let mut inner = self.lock();
+        let delay = traffics
+            .iter()
+            .map(|traffic| inner.delay(service, traffic, byte_count, now))
+            .max()
+            .unwrap_or_default();
+        traffics
+            .into_iter()
+            .for_each(|traffic| inner.consume(service, traffic, byte_count, now));
+        delay
+    }
+
+    pub fn is_throttled(&self, now: Instant) -> bool {
+        match self.lock().last_throttled_opt {
+            Some(last_throttled) => {
+                now.saturating_duration_since(last_throttled) < THROTTLED_MEMORY
+            }
+            None => false,
+        }
+    }
+
+    pub fn delayed_bytes(&self) -> u64 {
+        self.lock().delayed_bytes
+    }
+
+    fn lock(&self) -> MutexGuard<BandwidthThrottleInner> {
+        self.inner.lock().expect("BandwidthThrottle is poisoned")
+    }
+}
+
+impl BandwidthThrottleInner {
+    fn delay(
+        &mut self,
+        service: BandwidthService,
+        traffic: &Traffic,
+        byte_count: usize,
+        now: Instant,
+    ) -> Duration {
+        let limits = *self.limits.for_service(service);
+        let direction = traffic.direction();
+        let global_delay = match limits.global(direction) {
+            0 => Duration::from_secs(0),
+            kbps => self
+                .global_buckets
+                .entry((service, direction))
+                .or_insert_with(|| TokenBucket::from_kbps(kbps, now))
+                .delay(byte_count, now),
+        };
+        let neighbor_delay = match limits.per_neighbor(direction) {
+            0 => Duration::from_secs(0),
+            kbps => self
+                .neighbor_buckets
+                .entry((service, traffic.clone()))
+                .or_insert_with(|| TokenBucket::from_kbps(kbps, now))
+                .delay(byte_count, now),
+        };
+        global_delay.max(neighbor_delay)
+    }
+
+    // Must follow delay(), which creates any buckets the traffic needs.
+    fn consume(
+        &mut self,
+        service: BandwidthService,
+        traffic: Traffic,
+        byte_count: usize,
+        now: Instant,
+    ) {
+        let direction = traffic.direction();
+        let mut delayed = false;
+        if let Some(bucket) = self.global_buckets.get_mut(&(service, direction)) {
+            delayed |= bucket.delay(byte_count, now) > Duration::from_secs(0);
+            bucket.consume(byte_count, now);
+        }
+        if let Some(bucket) = self.neighbor_buckets.get_mut(&(service, traffic)) {
+            delayed |= bucket.delay(byte_count, now) > Duration::from_secs(0);
+            bucket.consume(byte_count, now);
+        }
+        if delayed {
+            self.last_throttled_opt = Some(now);
+            self.delayed_bytes += byte_count as u64;
+        }
+        if self.neighbor_buckets.len() > NEIGHBOR_BUCKET_PRUNE_THRESHOLD {
+            self.neighbor_buckets
+                .retain(|_, bucket| !bucket.is_full(now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(KILOBYTE, 1024);
+        assert_eq!(MAX_BANDWIDTH_DELAY, Duration::from_secs(5));
+        assert_eq!(THROTTLED_MEMORY, Duration::from_secs(60));
+        assert_eq!(NEIGHBOR_BUCKET_PRUNE_THRESHOLD, 256);
+    }
+
+    #[test]
+    fn service_bandwidth_limits_round_trip_through_strings() {
+        let subject = ServiceBandwidthLimits::from_str("2048:512:0:128").unwrap();
+
+        assert_eq!(
+            subject,
+            ServiceBandwidthLimits {
+                download: 2048,
+                upload: 512,
+                neighbor_download: 0,
+                neighbor_upload: 128,
+            }
+        );
+        assert_eq!(subject.to_string(), "2048:512:0:128".to_string());
+        assert_eq!(subject.is_unlimited(), false);
+        assert_eq!(
+            ServiceBandwidthLimits::from_str("0:0:0:0")
+                .unwrap()
+                .is_unlimited(),
+            true
+        );
+    }
+
+    #[test]
+    fn service_bandwidth_limits_rejects_malformed_strings() {
+        vec!["", "1:2:3", "1:2:3:4:5", "1:two:3:4", "1:-2:3:4"]
+            .into_iter()
+            .for_each(|text| {
+                assert_eq!(
+                    ServiceBandwidthLimits::from_str(text),
+                    Err(format!(
+                        "Bandwidth limits must be download:upload:neighbor-download:neighbor-upload in kilobytes per second, not '{}'",
+                        text
+                    ))
+                )
+            });
+    }
+
+    #[test]
+    fn service_bandwidth_limits_rejects_limits_that_are_too_big() {
+        let result = ServiceBandwidthLimits::from_str("0:0:10000001:0");
+
+        assert_eq!(
+            result,
+            Err("Bandwidth limits can be no more than 10000000 kilobytes per second, not 0:0:10000001:0".to_string())
+        );
+    }
+
+    #[test]
+    fn token_bucket_from_kbps_does_not_overflow() {
+        let now = Instant::now();
+
+        let normal = TokenBucket::from_kbps(2, now);
+        let huge = TokenBucket::from_kbps(u64::MAX, now);
+
+        assert_eq!(normal, TokenBucket::new(2048, now));
+        assert_eq!(huge, TokenBucket::new(u64::MAX, now));
+    }
+
+    #[test]
+    fn token_bucket_lets_a_seconds_worth_through_without_delay() {
+        let now = Instant::now();
+        let mut subject = TokenBucket::new(1000, now);
+
+        let first_delay = subject.delay(600, now);
+        subject.consume(600, now);
+        let second_delay = subject.delay(400, now);
+        subject.consume(400, now);
+
+        assert_eq!(first_delay, Duration::from_secs(0));
+        assert_eq!(second_delay, Duration::from_secs(0));
+        assert_eq!(subject.is_full(now), false);
+    }
+
+    #[test]
+    fn token_bucket_in_debt_delays_traffic_until_the_debt_is_paid() {
+        let now = Instant::now();
+        let mut subject = TokenBucket::new(1000, now);
+        subject.consume(1500, now);
+
+        let immediate_delay = subject.delay(500, now);
+        let later_delay = subject.delay(500, now + Duration::from_millis(500));
+
+        assert_eq!(immediate_delay, Duration::from_secs(1));
+        assert_eq!(later_delay, Duration::from_millis(500));
+    }
+
+    #[test]
+    fn token_bucket_refills_no_further_than_its_capacity() {
+        let now = Instant::now();
+        let mut subject = TokenBucket::new(1000, now);
+        subject.consume(1000, now);
+
+        let later = now + Duration::from_secs(10);
+        let full = subject.is_full(later);
+        let delay = subject.delay(1500, later);
+
+        assert_eq!(full, true);
+        assert_eq!(delay, Duration::from_millis(500));
+    }
+
+    fn make_limits(routing: &str, exit: &str) -> BandwidthLimits {
+        BandwidthLimits {
+            routing: ServiceBandwidthLimits::from_str(routing).unwrap(),
+            exit: ServiceBandwidthLimits::from_str(exit).unwrap(),
+        }
+    }
+
+    fn download_from(ip: &str) -> Traffic {
+        Traffic::Download(IpAddr::from_str(ip).unwrap())
+    }
+
+    #[test]
+    fn unlimited_throttle_never_delays_anything() {
+        let subject = BandwidthThrottle::new();
+        let now = Instant::now();
+
+        let routed = subject.charge_all(
+            BandwidthService::Routing,
+            vec![
+                download_from("1.2.3.4"),
+                Traffic::Upload(PublicKey::new(&[5, 6, 7, 8])),
+            ],
+            100_000_000,
+            now,
+        );
+        let charged = subject.charge(
+            BandwidthService::Exit,
+            Traffic::Upload(PublicKey::new(&[1, 2, 3, 4])),
+            100_000_000,
+            now,
+        );
+
+        assert_eq!(routed, Duration::from_secs(0));
+        assert_eq!(charged, Duration::from_secs(0));
+        assert_eq!(subject.is_throttled(now), false);
+        assert_eq!(subject.limits(), BandwidthLimits::default());
+    }
+
+    #[test]
+    fn global_limit_is_shared_by_all_neighbors_and_only_by_its_own_service_and_direction() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("1:0:0:0", "0:0:0:0"));
+        let now = Instant::now();
+
+        let first = subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            1024,
+            now,
+        );
+        let second = subject.charge(
+            BandwidthService::Routing,
+            download_from("5.6.7.8"),
+            512,
+            now,
+        );
+        let other_direction = subject.charge(
+            BandwidthService::Routing,
+            Traffic::Upload(PublicKey::new(&[1])),
+            4096,
+            now,
+        );
+        let other_service =
+            subject.charge(BandwidthService::Exit, download_from("5.6.7.8"), 4096, now);
+
+        assert_eq!(first, Duration::from_secs(0));
+        assert_eq!(second, Duration::from_millis(500));
+        assert_eq!(other_direction, Duration::from_secs(0));
+        assert_eq!(other_service, Duration::from_secs(0));
+        assert_eq!(subject.is_throttled(now), true);
+        assert_eq!(subject.delayed_bytes(), 512);
+    }
+
+    #[test]
+    fn per_neighbor_limit_applies_to_each_neighbor_separately() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("0:0:0:0", "0:0:0:1"));
+        let now = Instant::now();
+        let one_key = PublicKey::new(&[1]);
+        let another_key = PublicKey::new(&[2]);
+
+        let first = subject.charge(
+            BandwidthService::Exit,
+            Traffic::Upload(one_key.clone()),
+            2048,
+            now,
+        );
+        let same_neighbor =
+            subject.charge(BandwidthService::Exit, Traffic::Upload(one_key), 1024, now);
+        let another_neighbor = subject.charge(
+            BandwidthService::Exit,
+            Traffic::Upload(another_key),
+            1024,
+            now,
+        );
+
+        assert_eq!(first, Duration::from_secs(1));
+        assert_eq!(same_neighbor, Duration::from_secs(2));
+        assert_eq!(another_neighbor, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn delay_is_the_longer_of_the_global_and_per_neighbor_delays() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("2:0:1:0", "0:0:0:0"));
+        let now = Instant::now();
+
+        let result = subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            3072,
+            now,
+        );
+
+        assert_eq!(result, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn charge_charges_traffic_however_long_it_has_to_wait() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("1:0:0:0", "0:0:0:0"));
+        let now = Instant::now();
+
+        let long_wait = subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            10 * 1024,
+            now,
+        );
+        let afterward = subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            1024,
+            now,
+        );
+
+        assert_eq!(long_wait, Duration::from_secs(9));
+        assert_eq!(afterward, Duration::from_secs(10));
+        assert_eq!(subject.delayed_bytes(), 11 * 1024);
+        assert_eq!(subject.is_throttled(now), true);
+    }
+
+    #[test]
+    fn charge_all_charges_every_limit_and_waits_for_the_slowest() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("2:1:0:0", "0:0:0:0"));
+        let now = Instant::now();
+        let upload = Traffic::Upload(PublicKey::new(&[1, 2, 3, 4]));
+
+        let first = subject.charge_all(
+            BandwidthService::Routing,
+            vec![download_from("1.2.3.4"), upload.clone()],
+            2048,
+            now,
+        );
+        let download_afterward = subject.charge(
+            BandwidthService::Routing,
+            download_from("5.6.7.8"),
+            1024,
+            now,
+        );
+        let upload_afterward = subject.charge(BandwidthService::Routing, upload, 1024, now);
+
+        assert_eq!(first, Duration::from_secs(1));
+        assert_eq!(download_afterward, Duration::from_millis(500));
+        assert_eq!(upload_afterward, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn throttle_stops_being_throttled_after_a_while() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("0:0:0:0", "1:0:0:0"));
+        let now = Instant::now();
+        subject.charge(BandwidthService::Exit, download_from("1.2.3.4"), 2048, now);
+
+        let soon = subject.is_throttled(now + THROTTLED_MEMORY - Duration::from_millis(1));
+        let later = subject.is_throttled(now + THROTTLED_MEMORY);
+
+        assert_eq!(soon, true);
+        assert_eq!(later, false);
+    }
+
+    #[test]
+    fn set_limits_starts_the_buckets_over() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("1:0:0:0", "0:0:0:0"));
+        let now = Instant::now();
+        subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            4096,
+            now,
+        );
+        let new_limits = make_limits("4:0:0:0", "0:0:0:0");
+
+        subject.set_limits(new_limits);
+        let result = subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            4096,
+            now,
+        );
+
+        assert_eq!(result, Duration::from_secs(0));
+        assert_eq!(subject.limits(), new_limits);
+    }
+
+    #[test]
+    fn refilled_neighbor_buckets_are_pruned_when_there_are_too_many() {
+        let subject = BandwidthThrottle::new();
+        subject.set_limits(make_limits("0:0:1:0", "0:0:0:0"));
+        let now = Instant::now();
+        (0..=NEIGHBOR_BUCKET_PRUNE_THRESHOLD).for_each(|index| {
+            subject.charge(
+                BandwidthService::Routing,
+                download_from(&format!("10.0.{}.{}", index / 256, index % 256)),
+                1024,
+                now,
+            );
+        });
+        let later = now + Duration::from_secs(2);
+
+        subject.charge(
+            BandwidthService::Routing,
+            download_from("1.2.3.4"),
+            2048,
+            later,
+        );
+
+        assert_eq!(subject.lock().neighbor_buckets.len(), 1);
+    }
+
+    #[test]
+    fn bandwidth_throttle_is_shared() {
+        let one = bandwidth_throttle();
+        let another = bandwidth_throttle();
+
+        assert_eq!(Arc::ptr_eq(&one, &another), true);
+    }
+}
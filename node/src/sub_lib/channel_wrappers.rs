@@ -1,4 +1,5 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use crate::sub_lib::stream_buffer::StreamBuffer;
 use futures::sync::mpsc;
 use futures::sync::mpsc::SendError;
 use futures::sync::mpsc::UnboundedReceiver;
@@ -12,10 +13,12 @@ use tokio::prelude::Async;
 pub trait ReceiverWrapper<T: Send>: Send {
     #[allow(clippy::result_unit_err)]
     fn poll(&mut self) -> Result<Async<Option<T>>, ()>;
+    fn buffer(&self) -> &StreamBuffer;
 }
 
 pub struct ReceiverWrapperReal<T> {
     delegate: UnboundedReceiver<T>,
+    buffer: StreamBuffer,
 }
 
 impl<T: Send> ReceiverWrapper<T> for ReceiverWrapperReal<T> {
@@ -23,17 +26,22 @@ impl<T: Send> ReceiverWrapper<T> for ReceiverWrapperReal<T> {
     fn poll(&mut self) -> Result<Async<Option<T>>, ()> {
         self.delegate.poll()
     }
+
+    fn buffer(&self) -> &StreamBuffer {
+        &self.buffer
+    }
 }
 
 impl<T: Send> ReceiverWrapperReal<T> {
-    pub fn new(delegate: UnboundedReceiver<T>) -> ReceiverWrapperReal<T> {
-        ReceiverWrapperReal { delegate }
+    pub fn new(delegate: UnboundedReceiver<T>, buffer: StreamBuffer) -> ReceiverWrapperReal<T> {
+        ReceiverWrapperReal { delegate, buffer }
     }
 }
 
 pub trait SenderWrapper<T>: Debug + Send {
     fn unbounded_send(&self, data: T) -> Result<(), SendError<T>>;
     fn peer_addr(&self) -> SocketAddr;
+    fn buffer(&self) -> &StreamBuffer;
     fn clone(&self) -> Box<dyn SenderWrapper<T>>;
 }
 
@@ -41,6 +49,7 @@ pub trait SenderWrapper<T>: Debug + Send {
 pub struct SenderWrapperReal<T> {
     peer_addr: SocketAddr,
     delegate: UnboundedSender<T>,
+    buffer: StreamBuffer,
 }
 
 impl<T: 'static + Debug + Send> SenderWrapper<T> for SenderWrapperReal<T> {
@@ -52,19 +61,29 @@ impl<T: 'static + Debug + Send> SenderWrapper<T> for SenderWrapperReal<T> {
         self.peer_addr
     }
 
+    fn buffer(&self) -> &StreamBuffer {
+        &self.buffer
+    }
+
     fn clone(&self) -> Box<dyn SenderWrapper<T>> {
         Box::new(SenderWrapperReal::new(
             self.peer_addr(),
             self.delegate.clone(),
+            self.buffer.clone(),
         ))
     }
 }
 
 impl<T: Send> SenderWrapperReal<T> {
-    pub fn new(peer_addr: SocketAddr, delegate: UnboundedSender<T>) -> SenderWrapperReal<T> {
+    pub fn new(
+        peer_addr: SocketAddr,
+        delegate: UnboundedSender<T>,
+        buffer: StreamBuffer,
+    ) -> SenderWrapperReal<T> {
         SenderWrapperReal {
             peer_addr,
             delegate,
+            buffer,
         }
     }
 }
@@ -84,9 +103,10 @@ impl<T: 'static + Debug + Send> FuturesChannelFactory<T> for FuturesChannelFacto
         peer_addr: SocketAddr,
     ) -> (Box<dyn SenderWrapper<T>>, Box<dyn ReceiverWrapper<T>>) {
         let (tx, rx) = mpsc::unbounded();
+        let buffer = StreamBuffer::new();
         (
-            Box::new(SenderWrapperReal::new(peer_addr, tx)),
-            Box::new(ReceiverWrapperReal::new(rx)),
+            Box::new(SenderWrapperReal::new(peer_addr, tx, buffer.clone())),
+            Box::new(ReceiverWrapperReal::new(rx, buffer)),
         )
     }
 }
@@ -2,8 +2,10 @@
 use crate::proxy_server::http_protocol_pack::HttpProtocolPack;
 use crate::stream_messages::RemovedStreamType;
 use crate::sub_lib::cryptde::PublicKey;
+use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
+use crate::sub_lib::stream_buffer::Pressure;
+use crate::sub_lib::stream_handler_pool::{ReadPressureMsg, TransmitDataMsg};
 use actix::Message;
 use actix::Recipient;
 use masq_lib::ui_gateway::NodeFromUiMessage;
@@ -80,6 +82,7 @@ impl<'a> Visitor<'a> for ComponentVisitor {
 pub enum Endpoint {
     Key(PublicKey),
     Socket(SocketAddr),
+    Node(NodeAddr),
 }
 
 impl fmt::Debug for Endpoint {
@@ -87,6 +90,7 @@ impl fmt::Debug for Endpoint {
         match *self {
             Endpoint::Key(ref key) => write!(f, "PublicKey({})", key),
             Endpoint::Socket(ref socket_addr) => write!(f, "Socket({})", *socket_addr),
+            Endpoint::Node(ref node_addr) => write!(f, "Node({})", node_addr),
         }
     }
 }
@@ -156,11 +160,21 @@ pub struct StreamShutdownMsg {
     pub report_to_counterpart: bool,
 }
 
+// Sent when the backlog of data waiting to be written to a non-clandestine stream grows too big,
+// and again when it has drained.
+#[derive(PartialEq, Clone, Copy, Message, Debug)]
+pub struct WritePressureMsg {
+    pub peer_addr: SocketAddr,
+    pub pressure: Pressure,
+}
+
 pub struct DispatcherSubs {
     pub ibcd_sub: Recipient<InboundClientData>,
     pub bind: Recipient<BindMessage>,
     pub from_dispatcher_client: Recipient<TransmitDataMsg>,
     pub stream_shutdown_sub: Recipient<StreamShutdownMsg>,
+    pub write_pressure_sub: Recipient<WritePressureMsg>,
+    pub read_pressure_sub: Recipient<ReadPressureMsg>,
     pub ui_sub: Recipient<NodeFromUiMessage>,
 }
 
@@ -177,6 +191,8 @@ impl Clone for DispatcherSubs {
             bind: self.bind.clone(),
             from_dispatcher_client: self.from_dispatcher_client.clone(),
             stream_shutdown_sub: self.stream_shutdown_sub.clone(),
+            write_pressure_sub: self.write_pressure_sub.clone(),
+            read_pressure_sub: self.read_pressure_sub.clone(),
             ui_sub: self.ui_sub.clone(),
         }
     }
@@ -199,6 +215,8 @@ mod tests {
             bind: recipient!(addr, BindMessage),
             from_dispatcher_client: recipient!(addr, TransmitDataMsg),
             stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
+            write_pressure_sub: recipient!(addr, WritePressureMsg),
+            read_pressure_sub: recipient!(addr, ReadPressureMsg),
             ui_sub: recipient!(addr, NodeFromUiMessage),
         };
 
@@ -232,6 +250,15 @@ mod tests {
         assert_eq!(result, String::from("Socket(1.2.3.4:5678)"))
     }
 
+    #[test]
+    fn debug_string_for_endpoint_with_node_addr() {
+        let subject = Endpoint::Node(NodeAddr::from_str("1.2.3.4/[2001:db8::5]:5678").unwrap());
+
+        let result = format!("{:?}", subject);
+
+        assert_eq!(result, String::from("Node(1.2.3.4/[2001:db8::5]:5678)"))
+    }
+
     #[test]
     fn component_serializer_and_deserializer_talk_to_each_other() {
         let neighborhood_data = serde_cbor::ser::to_vec(&Component::Neighborhood).unwrap();
@@ -8,7 +8,9 @@ use crate::sub_lib::dispatcher::InboundClientData;
 use crate::sub_lib::neighborhood::GossipFailure_0v1;
 use crate::sub_lib::node_addr::NodeAddr;
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+use crate::sub_lib::proxy_client::{
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, StreamPressure_0v1,
+};
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::route::Route;
 use crate::sub_lib::versioned_data::VersionedData;
@@ -16,6 +18,7 @@ use crate::sub_lib::wallet::Wallet;
 use actix::Message;
 use actix::Recipient;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt::Debug;
 use std::net::SocketAddr;
 
@@ -70,6 +73,7 @@ pub enum MessageType {
     Gossip(VersionedData<Gossip_0v1>),
     GossipFailure(VersionedData<GossipFailure_0v1>),
     DnsResolveFailed(VersionedData<DnsResolveFailure_0v1>),
+    StreamPressure(VersionedData<StreamPressure_0v1>),
 }
 
 impl IncipientCoresPackage {
@@ -134,6 +138,7 @@ pub struct HopperSubs {
     pub from_hopper_client: Recipient<IncipientCoresPackage>,
     pub from_hopper_client_no_lookup: Recipient<NoLookupIncipientCoresPackage>,
     pub from_dispatcher: Recipient<InboundClientData>,
+    pub session_key_peers: Recipient<SessionKeyPeersMsg>,
 }
 
 impl Debug for HopperSubs {
@@ -142,6 +147,14 @@ impl Debug for HopperSubs {
     }
 }
 
+// The public keys of the Nodes that have said in their Gossip that they accept session-key
+// envelopes, sent by the Neighborhood whenever the set changes. Any other Node gets its packages
+// encrypted wholly with its public key, as before.
+#[derive(Clone, Debug, PartialEq, Message)]
+pub struct SessionKeyPeersMsg {
+    pub keys: HashSet<PublicKey>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +178,7 @@ mod tests {
             from_hopper_client: recipient!(recorder, IncipientCoresPackage),
             from_hopper_client_no_lookup: recipient!(recorder, NoLookupIncipientCoresPackage),
             from_dispatcher: recipient!(recorder, InboundClientData),
+            session_key_peers: recipient!(recorder, SessionKeyPeersMsg),
         };
 
         assert_eq!(format!("{:?}", subject), "HopperSubs");
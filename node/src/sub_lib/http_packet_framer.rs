@@ -108,6 +108,12 @@ impl HttpPacketFramer {
         }
     }
 
+    // Hands back whatever has arrived past the last complete frame, for a caller that has decided
+    // the rest of the stream isn't HTTP after all.
+    pub fn take_unframed_data(&mut self) -> Vec<u8> {
+        std::mem::replace(&mut self.framer_state.data_so_far, vec![])
+    }
+
     fn take_packet_frame(&mut self) -> Option<FramedChunk> {
         if self.framer_state.packet_progress_state == PacketProgressState::SeekingPacketStart
             && !self.start_finder.seek_packet_start(&mut self.framer_state)
@@ -563,6 +569,23 @@ mod framer_tests {
         assert_eq!(result.last_chunk, false)
     }
 
+    #[test]
+    fn take_unframed_data_hands_back_what_follows_the_last_frame() {
+        let request = "GOOD_FIRST_LINE\r\n\r\n".as_bytes();
+        let mut data = Vec::from(request);
+        data.append(&mut Vec::from("not http".as_bytes()));
+        let mut subject = HttpPacketFramer::new(Box::new(TameStartFinder {}));
+        subject.add_data(&data[..]);
+        subject.take_frame().unwrap();
+
+        let first_result = subject.take_unframed_data();
+        let second_result = subject.take_unframed_data();
+
+        assert_eq!(to_string(&first_result), "not http".to_string());
+        assert_eq!(second_result, Vec::<u8>::new());
+        assert_eq!(subject.take_frame(), None);
+    }
+
     #[test]
     fn handles_packet_in_two_pieces_divided_in_middle_of_body_with_garbage() {
         let first_piece = "GOOD_FIRST_LINE\r\nContent-Length: 10\r\n\r\nooga-".as_bytes();
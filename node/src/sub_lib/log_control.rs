@@ -0,0 +1,606 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use actix::Recipient;
+use chrono::Local;
+use flexi_logger::{LogSpecBuilder, LogSpecification, ReconfigurationHandle};
+use lazy_static::lazy_static;
+use log::{Level, LevelFilter};
+use masq_lib::messages::{ToMessageBody, UiLogBroadcast};
+use masq_lib::ui_gateway::MessageTarget::ClientId;
+use masq_lib::ui_gateway::NodeToUiMessage;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+// Streamed log lines travel through these components, which log about them; streaming their own
+// lines would feed back on itself.
+pub const UNSTREAMABLE_COMPONENTS: &[&str] = &["UiGateway", "WebSocketSupervisor"];
+
+// The names the Node's Loggers go by, and so the only components whose levels can be set. A few
+// components name their Loggers after each instance, as in "StreamReader for 1.2.3.4:5678"; those
+// instances follow the level of the name before the space.
+pub const LOG_COMPONENTS: &[&str] = &[
+    "Accountant",
+    "BlockchainBridge",
+    "BlockchainInterface",
+    "Bootstrapper",
+    "Configurator",
+    "ConsumingService",
+    "CrashTestDummy",
+    "Daemon",
+    "Discriminator",
+    "Dispatcher",
+    "EntryDnsServer",
+    "GossipAcceptor",
+    "GossipProducer",
+    "HopSessionKeys",
+    "HttpRequestFramer",
+    "JsonMasquerader",
+    "ListenerHandler",
+    "MetricsServer",
+    "Neighborhood",
+    "NeighborhoodDatabase",
+    "PanicHandler",
+    "ProxyClient",
+    "ProxyServer",
+    "ReceivableDaoReal",
+    "RoutingService",
+    "SequenceBuffer",
+    "StreamReader",
+    "StreamWriter",
+    "UiGateway",
+    "VerifierTools",
+    "WebSocketSupervisor",
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LogLevels {
+    pub default_level: LevelFilter,
+    pub component_levels: BTreeMap<String, LevelFilter>,
+}
+
+impl LogLevels {
+    pub fn new(default_level: LevelFilter) -> Self {
+        LogLevels {
+            default_level,
+            component_levels: BTreeMap::new(),
+        }
+    }
+
+    // A component set to the default level follows the default from then on
+    pub fn set(&mut self, component_opt: Option<&str>, level: LevelFilter) -> Result<(), String> {
+        match component_opt {
+            None => self.default_level = level,
+            Some(component) if LOG_COMPONENTS.contains(&component) => {
+                self.component_levels.insert(component.to_string(), level);
+            }
+            Some(component) => {
+                return Err(format!(
+                    "'{}' is not a component: use one of {}",
+                    component,
+                    LOG_COMPONENTS.join(", ")
+                ))
+            }
+        }
+        let default_level = self.default_level;
+        self.component_levels
+            .retain(|_, component_level| *component_level != default_level);
+        Ok(())
+    }
+
+    // The log crate matches module names as prefixes, and the longest match wins. So every
+    // component whose name merely starts with a configured one, as NeighborhoodDatabase starts
+    // with Neighborhood, is pinned to its own level.
+    pub fn spec(&self) -> LogSpecification {
+        let mut builder = LogSpecBuilder::new();
+        builder
+            .default(self.default_level)
+            .module("tokio", LevelFilter::Off)
+            .module("mio", LevelFilter::Off);
+        LOG_COMPONENTS
+            .iter()
+            .filter(|component| {
+                self.component_levels
+                    .keys()
+                    .any(|configured| component.starts_with(configured.as_str()))
+            })
+            .for_each(|component| {
+                builder.module(component, self.level_of(component));
+            });
+        builder.build()
+    }
+
+    fn level_of(&self, component: &str) -> LevelFilter {
+        match self.component_levels.get(component) {
+            Some(level) => *level,
+            None => self.default_level,
+        }
+    }
+}
+
+pub fn parse_level_filter(name: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(name).map_err(|_| {
+        format!(
+            "'{}' is not a log level: use off, error, warn, info, debug, or trace",
+            name
+        )
+    })
+}
+
+pub fn parse_level(name: &str) -> Result<Level, String> {
+    Level::from_str(name).map_err(|_| {
+        format!(
+            "'{}' is not a log level: use error, warn, info, debug, or trace",
+            name
+        )
+    })
+}
+
+pub fn level_filter_name(level: LevelFilter) -> String {
+    level.to_string().to_lowercase()
+}
+
+// Receives log lines from every Logger in the Node. Implementations are called from inside the
+// Logger, so they must never log anything themselves.
+pub trait LogTap: Send + Sync {
+    fn wants(&self, component: &str, level: Level) -> bool;
+    fn receive(&self, component: &str, level: Level, message: &str);
+}
+
+pub trait LogController {
+    fn log_levels(&self) -> LogLevels;
+    fn set_log_level(
+        &mut self,
+        component_opt: Option<&str>,
+        level: LevelFilter,
+    ) -> Result<LogLevels, String>;
+    fn set_log_tap(&mut self, tap_opt: Option<Arc<dyn LogTap>>);
+}
+
+struct LogControl {
+    handle_opt: Option<ReconfigurationHandle>,
+    levels: LogLevels,
+}
+
+lazy_static! {
+    static ref LOG_CONTROL: Mutex<LogControl> = Mutex::new(LogControl {
+        handle_opt: None,
+        levels: LogLevels::new(LevelFilter::Info),
+    });
+    static ref LOG_TAP: RwLock<Option<Arc<dyn LogTap>>> = RwLock::new(None);
+}
+
+// Consulted on every log call, so that nobody touches LOG_TAP unless somebody is listening
+static LOG_TAP_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub fn install_log_control(handle: ReconfigurationHandle, levels: LogLevels) {
+    let mut control = log_control_guard();
+    control.handle_opt = Some(handle);
+    control.levels = levels;
+}
+
+pub fn log_tap_wants(component: &str, level: Level) -> bool {
+    if !LOG_TAP_ACTIVE.load(Ordering::Relaxed) {
+        return false;
+    }
+    match log_tap_read_guard().as_ref() {
+        Some(tap) => tap.wants(component, level),
+        None => false,
+    }
+}
+
+pub fn log_tap_receive(component: &str, level: Level, message: &str) {
+    if !LOG_TAP_ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(tap) = log_tap_read_guard().as_ref() {
+        tap.receive(component, level, message)
+    }
+}
+
+#[derive(Default)]
+pub struct LogControllerReal {}
+
+impl LogController for LogControllerReal {
+    fn log_levels(&self) -> LogLevels {
+        log_control_guard().levels.clone()
+    }
+
+    fn set_log_level(
+        &mut self,
+        component_opt: Option<&str>,
+        level: LevelFilter,
+    ) -> Result<LogLevels, String> {
+        let mut control = log_control_guard();
+        let mut levels = control.levels.clone();
+        levels.set(component_opt, level)?;
+        match control.handle_opt.as_mut() {
+            Some(handle) => handle.set_new_spec(levels.spec()),
+            None => return Err("Logging has not been initialized".to_string()),
+        }
+        control.levels = levels.clone();
+        Ok(levels)
+    }
+
+    fn set_log_tap(&mut self, tap_opt: Option<Arc<dyn LogTap>>) {
+        let mut tap = log_tap_write_guard();
+        LOG_TAP_ACTIVE.store(tap_opt.is_some(), Ordering::Relaxed);
+        *tap = tap_opt;
+    }
+}
+
+impl LogControllerReal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn log_control_guard<'a>() -> MutexGuard<'a, LogControl> {
+    match LOG_CONTROL.lock() {
+        Ok(guard) => guard,
+        Err(poison_err) => poison_err.into_inner(),
+    }
+}
+
+fn log_tap_read_guard<'a>() -> RwLockReadGuard<'a, Option<Arc<dyn LogTap>>> {
+    match LOG_TAP.read() {
+        Ok(guard) => guard,
+        Err(poison_err) => poison_err.into_inner(),
+    }
+}
+
+fn log_tap_write_guard<'a>() -> RwLockWriteGuard<'a, Option<Arc<dyn LogTap>>> {
+    match LOG_TAP.write() {
+        Ok(guard) => guard,
+        Err(poison_err) => poison_err.into_inner(),
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct LogSubscription {
+    pub components: Vec<String>, // empty means every component
+    pub level: Level,
+}
+
+impl LogSubscription {
+    pub fn matches(&self, component: &str, level: Level) -> bool {
+        level <= self.level
+            && (self.components.is_empty() || self.components.iter().any(|c| c == component))
+    }
+}
+
+// Streams log lines to the UIs that have subscribed to them, as log broadcasts
+pub struct LogStream {
+    subscriptions: Mutex<HashMap<u64, LogSubscription>>,
+    node_to_ui_sub: Mutex<Recipient<NodeToUiMessage>>,
+}
+
+impl LogTap for LogStream {
+    fn wants(&self, component: &str, level: Level) -> bool {
+        !UNSTREAMABLE_COMPONENTS.contains(&component)
+            && self
+                .subscriptions_guard()
+                .values()
+                .any(|subscription| subscription.matches(component, level))
+    }
+
+    fn receive(&self, component: &str, level: Level, message: &str) {
+        if UNSTREAMABLE_COMPONENTS.contains(&component) {
+            return;
+        }
+        let client_ids = self
+            .subscriptions_guard()
+            .iter()
+            .filter(|(_, subscription)| subscription.matches(component, level))
+            .map(|(client_id, _)| *client_id)
+            .collect::<Vec<u64>>();
+        if client_ids.is_empty() {
+            return;
+        }
+        let body = UiLogBroadcast {
+            time: Local::now().format("%Y-%m-%dT%H:%M:%S%.3f").to_string(),
+            level: level.to_string().to_lowercase(),
+            component: component.to_string(),
+            message: message.to_string(),
+        }
+        .tmb(0);
+        let node_to_ui_sub = match self.node_to_ui_sub.lock() {
+            Ok(guard) => guard,
+            Err(poison_err) => poison_err.into_inner(),
+        };
+        client_ids.into_iter().for_each(|client_id| {
+            // A full mailbox costs the subscriber a line; complaining about it would cost more
+            let _ = node_to_ui_sub.try_send(NodeToUiMessage {
+                target: ClientId(client_id),
+                body: body.clone(),
+            });
+        });
+    }
+}
+
+impl LogStream {
+    pub fn new(node_to_ui_sub: Recipient<NodeToUiMessage>) -> Self {
+        LogStream {
+            subscriptions: Mutex::new(HashMap::new()),
+            node_to_ui_sub: Mutex::new(node_to_ui_sub),
+        }
+    }
+
+    pub fn subscribe(&self, client_id: u64, subscription: LogSubscription) {
+        self.subscriptions_guard().insert(client_id, subscription);
+    }
+
+    pub fn unsubscribe(&self, client_id: u64) -> bool {
+        self.subscriptions_guard().remove(&client_id).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions_guard().is_empty()
+    }
+
+    fn subscriptions_guard(&self) -> MutexGuard<HashMap<u64, LogSubscription>> {
+        match self.subscriptions.lock() {
+            Ok(guard) => guard,
+            Err(poison_err) => poison_err.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sub_lib::logger::Logger;
+    use crate::test_utils::recorder::make_recorder;
+    use actix::{Actor, System};
+    use masq_lib::messages::FromMessageBody;
+
+    #[test]
+    fn log_levels_drop_component_levels_that_match_the_default() {
+        let mut subject = LogLevels::new(LevelFilter::Warn);
+
+        subject
+            .set(Some("Neighborhood"), LevelFilter::Debug)
+            .unwrap();
+        subject
+            .set(Some("ProxyServer"), LevelFilter::Trace)
+            .unwrap();
+        subject
+            .set(Some("RoutingService"), LevelFilter::Warn)
+            .unwrap();
+        subject.set(None, LevelFilter::Trace).unwrap();
+
+        let mut expected_component_levels = BTreeMap::new();
+        expected_component_levels.insert("Neighborhood".to_string(), LevelFilter::Debug);
+        assert_eq!(
+            subject,
+            LogLevels {
+                default_level: LevelFilter::Trace,
+                component_levels: expected_component_levels,
+            }
+        );
+    }
+
+    #[test]
+    fn log_levels_make_a_spec_with_component_overrides() {
+        let mut subject = LogLevels::new(LevelFilter::Warn);
+        subject
+            .set(Some("Neighborhood"), LevelFilter::Debug)
+            .unwrap();
+
+        let result = subject.spec();
+
+        assert_eq!(result.enabled(Level::Debug, "Neighborhood"), true);
+        assert_eq!(result.enabled(Level::Trace, "Neighborhood"), false);
+        assert_eq!(result.enabled(Level::Warn, "ProxyServer"), true);
+        assert_eq!(result.enabled(Level::Info, "ProxyServer"), false);
+        assert_eq!(result.enabled(Level::Error, "tokio_reactor"), false);
+    }
+
+    #[test]
+    fn log_levels_match_component_names_exactly() {
+        let mut subject = LogLevels::new(LevelFilter::Warn);
+        subject
+            .set(Some("Neighborhood"), LevelFilter::Debug)
+            .unwrap();
+        subject
+            .set(Some("StreamReader"), LevelFilter::Error)
+            .unwrap();
+
+        let result = subject.spec();
+
+        assert_eq!(result.enabled(Level::Debug, "Neighborhood"), true);
+        assert_eq!(result.enabled(Level::Debug, "NeighborhoodDatabase"), false);
+        assert_eq!(result.enabled(Level::Warn, "NeighborhoodDatabase"), true);
+        assert_eq!(
+            result.enabled(Level::Warn, "StreamReader for 1.2.3.4:5678"),
+            false
+        );
+        assert_eq!(
+            result.enabled(Level::Error, "StreamReader for 1.2.3.4:5678"),
+            true
+        );
+    }
+
+    #[test]
+    fn log_levels_keep_separately_set_levels_of_longer_names() {
+        let mut subject = LogLevels::new(LevelFilter::Warn);
+        subject
+            .set(Some("NeighborhoodDatabase"), LevelFilter::Trace)
+            .unwrap();
+        subject
+            .set(Some("Neighborhood"), LevelFilter::Info)
+            .unwrap();
+
+        let result = subject.spec();
+
+        assert_eq!(result.enabled(Level::Info, "Neighborhood"), true);
+        assert_eq!(result.enabled(Level::Debug, "Neighborhood"), false);
+        assert_eq!(result.enabled(Level::Trace, "NeighborhoodDatabase"), true);
+    }
+
+    #[test]
+    fn log_levels_refuse_unknown_components() {
+        let mut subject = LogLevels::new(LevelFilter::Warn);
+
+        let result = subject.set(Some("Neighbourhood"), LevelFilter::Debug);
+
+        assert_eq!(
+            result,
+            Err(format!(
+                "'Neighbourhood' is not a component: use one of {}",
+                LOG_COMPONENTS.join(", ")
+            ))
+        );
+        assert_eq!(subject, LogLevels::new(LevelFilter::Warn));
+    }
+
+    #[test]
+    fn log_level_names_are_parsed_and_rendered() {
+        assert_eq!(parse_level_filter("debug"), Ok(LevelFilter::Debug));
+        assert_eq!(parse_level_filter("OFF"), Ok(LevelFilter::Off));
+        assert_eq!(
+            parse_level_filter("loud"),
+            Err(
+                "'loud' is not a log level: use off, error, warn, info, debug, or trace"
+                    .to_string()
+            )
+        );
+        assert_eq!(parse_level("trace"), Ok(Level::Trace));
+        assert_eq!(
+            parse_level("off"),
+            Err("'off' is not a log level: use error, warn, info, debug, or trace".to_string())
+        );
+        assert_eq!(level_filter_name(LevelFilter::Warn), "warn".to_string());
+    }
+
+    #[test]
+    fn set_log_level_complains_before_logging_is_initialized() {
+        let mut subject = LogControllerReal::new();
+
+        let result = subject.set_log_level(Some("Neighborhood"), LevelFilter::Debug);
+
+        assert_eq!(result, Err("Logging has not been initialized".to_string()));
+    }
+
+    #[test]
+    fn subscriptions_match_components_and_levels() {
+        let everything = LogSubscription {
+            components: vec![],
+            level: Level::Info,
+        };
+        let neighborhood = LogSubscription {
+            components: vec!["Neighborhood".to_string()],
+            level: Level::Debug,
+        };
+
+        assert_eq!(everything.matches("ProxyServer", Level::Info), true);
+        assert_eq!(everything.matches("ProxyServer", Level::Debug), false);
+        assert_eq!(neighborhood.matches("Neighborhood", Level::Debug), true);
+        assert_eq!(neighborhood.matches("Neighborhood", Level::Trace), false);
+        assert_eq!(neighborhood.matches("ProxyServer", Level::Error), false);
+    }
+
+    #[test]
+    fn log_stream_broadcasts_lines_to_matching_subscribers_only() {
+        let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
+        let system = System::new("log_stream_broadcasts_lines_to_matching_subscribers_only");
+        let subject = LogStream::new(ui_gateway.start().recipient());
+        subject.subscribe(
+            1234,
+            LogSubscription {
+                components: vec!["Neighborhood".to_string()],
+                level: Level::Debug,
+            },
+        );
+        subject.subscribe(
+            2345,
+            LogSubscription {
+                components: vec![],
+                level: Level::Warn,
+            },
+        );
+
+        subject.receive("Neighborhood", Level::Debug, "Gossip arrived");
+        subject.receive("ProxyServer", Level::Info, "Nobody wants this");
+        subject.receive("WebSocketSupervisor", Level::Error, "Nor this");
+
+        System::current().stop();
+        system.run();
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let message = ui_gateway_recording.get_record::<NodeToUiMessage>(0);
+        assert_eq!(message.target, ClientId(1234));
+        let (broadcast, _) = UiLogBroadcast::fmb(message.body.clone()).unwrap();
+        assert_eq!(broadcast.level, "debug".to_string());
+        assert_eq!(broadcast.component, "Neighborhood".to_string());
+        assert_eq!(broadcast.message, "Gossip arrived".to_string());
+        assert_eq!(ui_gateway_recording.len(), 1);
+        assert_eq!(subject.wants("ProxyServer", Level::Warn), true);
+        assert_eq!(subject.wants("ProxyServer", Level::Info), false);
+        assert_eq!(subject.wants("UiGateway", Level::Error), false);
+    }
+
+    #[test]
+    fn log_stream_forgets_unsubscribed_clients() {
+        let (ui_gateway, _, _) = make_recorder();
+        let system = System::new("log_stream_forgets_unsubscribed_clients");
+        let subject = LogStream::new(ui_gateway.start().recipient());
+        subject.subscribe(
+            1234,
+            LogSubscription {
+                components: vec![],
+                level: Level::Trace,
+            },
+        );
+
+        let first = subject.unsubscribe(1234);
+        let second = subject.unsubscribe(1234);
+
+        System::current().stop();
+        system.run();
+        assert_eq!(first, true);
+        assert_eq!(second, false);
+        assert_eq!(subject.is_empty(), true);
+        assert_eq!(subject.wants("Neighborhood", Level::Error), false);
+    }
+
+    struct LogTapRecorder {
+        lines: Mutex<Vec<(String, Level, String)>>,
+    }
+
+    impl LogTap for LogTapRecorder {
+        fn wants(&self, component: &str, _level: Level) -> bool {
+            component == "log_control_tap_test"
+        }
+
+        fn receive(&self, component: &str, level: Level, message: &str) {
+            self.lines
+                .lock()
+                .unwrap()
+                .push((component.to_string(), level, message.to_string()));
+        }
+    }
+
+    #[test]
+    fn loggers_feed_the_installed_tap() {
+        let tap = Arc::new(LogTapRecorder {
+            lines: Mutex::new(vec![]),
+        });
+        let mut subject = LogControllerReal::new();
+        subject.set_log_tap(Some(tap.clone()));
+        let logger = Logger::new("log_control_tap_test");
+
+        debug!(logger, "Tapped {}", 42);
+        subject.set_log_tap(None);
+        debug!(logger, "Not tapped");
+
+        assert_eq!(
+            *tap.lines.lock().unwrap(),
+            vec![(
+                "log_control_tap_test".to_string(),
+                Level::Debug,
+                "Tapped 42".to_string()
+            )]
+        );
+    }
+}
@@ -1,13 +1,67 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use crate::sub_lib::cryptde::PublicKey;
+use crate::sub_lib::log_control::{log_tap_receive, log_tap_wants};
+use crate::sub_lib::stream_key::StreamKey;
 use log::logger;
 use log::Level;
 #[cfg(not(test))]
 use log::Metadata;
 use log::Record;
+use std::fmt::Display;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub const STREAM_KEY_FIELD: &str = "stream_key";
+pub const PEER_ADDR_FIELD: &str = "peer_addr";
+pub const PUBLIC_KEY_FIELD: &str = "public_key";
+
+// How the Node's logfile is written: free text for people, or one JSON object per line for
+// log aggregators. Structured fields only appear in the JSON format.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::Text
+    }
+}
+
+impl Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "Unknown log format '{}': must be text or json",
+                other
+            )),
+        }
+    }
+}
+
+// A structured field's value, kept as it was given; it's only turned into text when a record
+// that carries it is actually written.
+type FieldValue = Arc<dyn Display + Send + Sync>;
 
 #[derive(Clone)]
 pub struct Logger {
-    name: String,
+    name: Arc<str>,
+    fields: Vec<(&'static str, FieldValue)>,
     #[cfg(test)]
     level_limit: Level,
 }
@@ -51,12 +105,49 @@ macro_rules! fatal {
 impl Logger {
     pub fn new(name: &str) -> Logger {
         Logger {
-            name: String::from(name),
+            name: Arc::from(name),
+            fields: vec![],
             #[cfg(test)]
             level_limit: Level::Trace,
         }
     }
 
+    // Returns a copy of this Logger whose records carry the field as well; a field with the
+    // same key is replaced. Use it right in the macro: debug!(self.logger.with_field(...), ...)
+    // The copy shares the name, and the value isn't formatted unless something is logged, so
+    // this is cheap even when the level is disabled.
+    pub fn with_field<T>(&self, key: &'static str, value: T) -> Logger
+    where
+        T: Display + Send + Sync + 'static,
+    {
+        let mut logger = self.clone();
+        let value: FieldValue = Arc::new(value);
+        match logger.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some(field) => field.1 = value,
+            None => logger.fields.push((key, value)),
+        }
+        logger
+    }
+
+    pub fn with_stream_key(&self, stream_key: &StreamKey) -> Logger {
+        self.with_field(STREAM_KEY_FIELD, *stream_key)
+    }
+
+    pub fn with_peer_addr(&self, peer_addr: SocketAddr) -> Logger {
+        self.with_field(PEER_ADDR_FIELD, peer_addr)
+    }
+
+    pub fn with_public_key(&self, public_key: &PublicKey) -> Logger {
+        self.with_field(PUBLIC_KEY_FIELD, public_key.clone())
+    }
+
+    pub fn fields(&self) -> Vec<(String, String)> {
+        self.fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
     pub fn trace<F>(&self, log_function: F)
     where
         F: FnOnce() -> String,
@@ -121,13 +212,17 @@ impl Logger {
         self.level_enabled(Level::Error)
     }
 
-    #[cfg(not(test))]
     pub fn level_enabled(&self, level: Level) -> bool {
+        self.logger_enabled(level) || log_tap_wants(&self.name, level)
+    }
+
+    #[cfg(not(test))]
+    fn logger_enabled(&self, level: Level) -> bool {
         logger().enabled(&Metadata::builder().level(level).target(&self.name).build())
     }
 
     #[cfg(test)]
-    pub fn level_enabled(&self, level: Level) -> bool {
+    fn logger_enabled(&self, level: Level) -> bool {
         level <= self.level_limit
     }
 
@@ -135,19 +230,51 @@ impl Logger {
     where
         F: FnOnce() -> String,
     {
-        if !self.level_enabled(level) {
+        let logger_enabled = self.logger_enabled(level);
+        let tap_wants = log_tap_wants(&self.name, level);
+        if !logger_enabled && !tap_wants {
             return;
         }
         let string = log_function();
-        self.log(level, string)
+        if logger_enabled {
+            self.log_to_logger(level, &string);
+        }
+        if tap_wants {
+            log_tap_receive(&self.name, level, &string);
+        }
     }
 
     pub fn log(&self, level: Level, msg: String) {
-        logger().log(
+        self.log_to_logger(level, &msg);
+        if log_tap_wants(&self.name, level) {
+            log_tap_receive(&self.name, level, &msg);
+        }
+    }
+
+    fn log_to_logger(&self, level: Level, msg: &str) {
+        self.with_record(level, msg, |record| logger().log(record));
+    }
+
+    fn with_record<F>(&self, level: Level, msg: &str, consumer: F)
+    where
+        F: FnOnce(&Record),
+    {
+        let values = self
+            .fields
+            .iter()
+            .map(|(key, value)| (*key, value.to_string()))
+            .collect::<Vec<(&str, String)>>();
+        let key_values = values
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect::<Vec<(&str, &str)>>();
+        let key_values: &[(&str, &str)] = &key_values;
+        consumer(
             &Record::builder()
                 .args(format_args!("{}", msg))
-                .module_path(Some(&self.name))
+                .module_path(Some(&*self.name))
                 .level(level)
+                .key_values(&key_values)
                 .build(),
         );
     }
@@ -156,10 +283,13 @@ impl Logger {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::server_initializer::real_json_format_function;
     use crate::test_utils::logging::init_test_logging;
     use crate::test_utils::logging::TestLogHandler;
     use chrono::format::StrftimeItems;
     use chrono::{DateTime, Local};
+    use log::kv;
+    use serde_json::{json, Value};
     use std::sync::{Arc, Mutex};
     use std::thread;
     use std::thread::ThreadId;
@@ -196,7 +326,8 @@ mod tests {
     #[test]
     fn trace_is_not_computed_when_log_level_is_debug() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Debug,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -216,7 +347,8 @@ mod tests {
     #[test]
     fn debug_is_not_computed_when_log_level_is_info() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Info,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -236,7 +368,8 @@ mod tests {
     #[test]
     fn info_is_not_computed_when_log_level_is_warn() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Warn,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -256,7 +389,8 @@ mod tests {
     #[test]
     fn warning_is_not_computed_when_log_level_is_error() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Error,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -276,7 +410,8 @@ mod tests {
     #[test]
     fn trace_is_computed_when_log_level_is_trace() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Trace,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -296,7 +431,8 @@ mod tests {
     #[test]
     fn debug_is_computed_when_log_level_is_debug() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Debug,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -316,7 +452,8 @@ mod tests {
     #[test]
     fn info_is_computed_when_log_level_is_info() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Info,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -336,7 +473,8 @@ mod tests {
     #[test]
     fn warn_is_computed_when_log_level_is_warn() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Warn,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -356,7 +494,8 @@ mod tests {
     #[test]
     fn error_is_computed_when_log_level_is_error() {
         let logger = Logger {
-            name: "test".to_string(),
+            name: Arc::from("test"),
+            fields: vec![],
             level_limit: Level::Error,
         };
         let signal = Arc::new(Mutex::new(Some(false)));
@@ -392,6 +531,186 @@ mod tests {
         tlh.exists_log_containing("error! 42");
     }
 
+    #[test]
+    fn log_format_is_displayed_and_parsed() {
+        assert_eq!(LogFormat::default(), LogFormat::Text);
+        assert_eq!(LogFormat::Text.to_string(), "text".to_string());
+        assert_eq!(LogFormat::Json.to_string(), "json".to_string());
+        assert_eq!(LogFormat::from_str("TEXT"), Ok(LogFormat::Text));
+        assert_eq!(LogFormat::from_str("Json"), Ok(LogFormat::Json));
+        assert_eq!(
+            LogFormat::from_str("xml"),
+            Err("Unknown log format 'xml': must be text or json".to_string())
+        );
+    }
+
+    #[test]
+    fn records_carry_name_level_and_message_but_no_fields_by_default() {
+        let subject = Logger::new("RecordComponent");
+        let mut captured = None;
+
+        subject.with_record(Level::Warn, "plain message", |record| {
+            captured = Some(capture(record))
+        });
+
+        assert_eq!(
+            captured,
+            Some((
+                Level::Warn,
+                Some("RecordComponent".to_string()),
+                "plain message".to_string(),
+                vec![]
+            ))
+        );
+    }
+
+    #[test]
+    fn with_field_adds_fields_to_records_without_changing_the_original() {
+        let original = Logger::new("RecordComponent");
+        let mut captured = None;
+
+        let subject = original.with_field("color", "red").with_field("size", 42);
+        subject.with_record(Level::Debug, "fielded message", |record| {
+            captured = Some(capture(record))
+        });
+
+        assert_eq!(
+            captured,
+            Some((
+                Level::Debug,
+                Some("RecordComponent".to_string()),
+                "fielded message".to_string(),
+                vec![
+                    ("color".to_string(), "red".to_string()),
+                    ("size".to_string(), "42".to_string())
+                ]
+            ))
+        );
+        assert_eq!(original.fields().is_empty(), true);
+    }
+
+    #[test]
+    fn with_field_replaces_a_field_with_the_same_key() {
+        let subject = Logger::new("test")
+            .with_field("color", "red")
+            .with_field("size", 42)
+            .with_field("color", "blue");
+
+        assert_eq!(
+            subject.fields(),
+            vec![
+                ("color".to_string(), "blue".to_string()),
+                ("size".to_string(), "42".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn standard_fields_have_standard_names() {
+        let public_key = PublicKey::new(b"booga");
+        let peer_addr = SocketAddr::from_str("1.2.3.4:5678").unwrap();
+        let stream_key = StreamKey::new(public_key.clone(), peer_addr);
+
+        let subject = Logger::new("test")
+            .with_stream_key(&stream_key)
+            .with_peer_addr(peer_addr)
+            .with_public_key(&public_key);
+
+        assert_eq!(STREAM_KEY_FIELD, "stream_key");
+        assert_eq!(PEER_ADDR_FIELD, "peer_addr");
+        assert_eq!(PUBLIC_KEY_FIELD, "public_key");
+        assert_eq!(
+            subject.fields(),
+            vec![
+                ("stream_key".to_string(), stream_key.to_string()),
+                ("peer_addr".to_string(), "1.2.3.4:5678".to_string()),
+                ("public_key".to_string(), public_key.to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn json_records_carry_logger_fields() {
+        let public_key = PublicKey::new(b"booga");
+        let subject = Logger::new("JsonComponent").with_public_key(&public_key);
+        let mut buffer: Vec<u8> = vec![];
+
+        subject.with_record(Level::Error, "went wrong", |record| {
+            real_json_format_function(&mut buffer, &Local::now(), record).unwrap()
+        });
+
+        let record: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(record["component"], json!("JsonComponent"));
+        assert_eq!(record["level"], json!("ERROR"));
+        assert_eq!(record["message"], json!("went wrong"));
+        assert_eq!(record["public_key"], json!(public_key.to_string()));
+    }
+
+    #[test]
+    fn macros_work_with_fielded_loggers() {
+        init_test_logging();
+        let logger = Logger::new("macros_work_with_fielded_loggers");
+
+        info!(logger.with_field("color", "red"), "fielded info! {}", 42);
+
+        TestLogHandler::new()
+            .exists_log_containing("INFO: macros_work_with_fielded_loggers: fielded info! 42");
+    }
+
+    #[test]
+    fn fields_are_not_formatted_when_the_level_is_disabled() {
+        struct FormatCounter(Arc<Mutex<u32>>);
+        impl Display for FormatCounter {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                *self.0.lock().unwrap() += 1;
+                write!(f, "counted")
+            }
+        }
+        let format_count = Arc::new(Mutex::new(0));
+        let logger = Logger {
+            name: Arc::from("test"),
+            fields: vec![],
+            level_limit: Level::Info,
+        };
+
+        debug!(
+            logger.with_field("counter", FormatCounter(format_count.clone())),
+            "not logged"
+        );
+        let before_record = *format_count.lock().unwrap();
+        logger
+            .with_field("counter", FormatCounter(format_count.clone()))
+            .with_record(Level::Info, "logged", |_| ());
+        let after_record = *format_count.lock().unwrap();
+
+        assert_eq!(before_record, 0);
+        assert_eq!(after_record, 1);
+    }
+
+    type CapturedRecord = (Level, Option<String>, String, Vec<(String, String)>);
+
+    fn capture(record: &Record) -> CapturedRecord {
+        struct FieldCollector(Vec<(String, String)>);
+        impl<'kvs> kv::Visitor<'kvs> for FieldCollector {
+            fn visit_pair(
+                &mut self,
+                key: kv::Key<'kvs>,
+                value: kv::Value<'kvs>,
+            ) -> Result<(), kv::Error> {
+                self.0.push((key.to_string(), value.to_string()));
+                Ok(())
+            }
+        }
+        let mut collector = FieldCollector(vec![]);
+        record.key_values().visit(&mut collector).unwrap();
+        (
+            record.level(),
+            record.module_path().map(|name| name.to_string()),
+            record.args().to_string(),
+            collector.0,
+        )
+    }
+
     fn timestamp_as_string(timestamp: &SystemTime) -> String {
         let date_time: DateTime<Local> = DateTime::from(timestamp.clone());
         let fmt = StrftimeItems::new("%Y-%m-%dT%H:%M:%S%.3f");
@@ -0,0 +1,296 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use lazy_static::lazy_static;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const METRICS_PATH: &str = "/metrics";
+
+lazy_static! {
+    static ref METRICS: Arc<Metrics> = Arc::new(Metrics::default());
+}
+
+// The Metrics every actor in this process reports to. Actors keep an Arc to it in a field, so
+// that tests can give them a fresh one and see exactly what they reported.
+pub fn metrics() -> Arc<Metrics> {
+    METRICS.clone()
+}
+
+#[derive(Default, Debug)]
+pub struct Counter {
+    value: AtomicU64,
+}
+
+impl Counter {
+    pub fn increment(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, amount: u64) {
+        self.value.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Gauge {
+    value: AtomicU64,
+}
+
+impl Gauge {
+    pub fn set(&self, value: u64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Metrics {
+    pub routing_bytes: Counter,
+    pub exit_bytes: Counter,
+    pub dispatcher_streams: Gauge,
+    pub proxy_client_streams: Gauge,
+    pub route_query_successes: Counter,
+    pub route_query_failures: Counter,
+    pub gossip_received: Counter,
+    pub gossip_accepted: Counter,
+    pub gossip_ignored: Counter,
+    pub dns_queries: Counter,
+    pub dns_latency_nanos: Counter,
+    pub payable_total: Gauge,
+    pub receivable_total: Gauge,
+}
+
+impl Metrics {
+    pub fn record_dns_query(&self, latency: Duration) {
+        self.dns_queries.increment();
+        self.dns_latency_nanos.add(latency.as_nanos() as u64);
+    }
+
+    // Prometheus text exposition format, version 0.0.4
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        Self::family(
+            &mut text,
+            "masq_routing_bytes_total",
+            "counter",
+            "Payload bytes this Node has routed for other Nodes.",
+            &[("", self.routing_bytes.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_exit_bytes_total",
+            "counter",
+            "Payload bytes this Node has carried to and from servers as an exit Node.",
+            &[("", self.exit_bytes.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_active_streams",
+            "gauge",
+            "Streams currently open in each stream handler pool.",
+            &[
+                (
+                    "{pool=\"dispatcher\"}",
+                    self.dispatcher_streams.get().to_string(),
+                ),
+                (
+                    "{pool=\"proxy_client\"}",
+                    self.proxy_client_streams.get().to_string(),
+                ),
+            ],
+        );
+        Self::family(
+            &mut text,
+            "masq_route_queries_total",
+            "counter",
+            "Route queries the Neighborhood has answered, by result.",
+            &[
+                (
+                    "{result=\"success\"}",
+                    self.route_query_successes.get().to_string(),
+                ),
+                (
+                    "{result=\"failure\"}",
+                    self.route_query_failures.get().to_string(),
+                ),
+            ],
+        );
+        Self::family(
+            &mut text,
+            "masq_gossip_received_total",
+            "counter",
+            "Gossip messages received from other Nodes.",
+            &[("", self.gossip_received.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_gossip_accepted_total",
+            "counter",
+            "Gossip messages that changed this Node's picture of the network.",
+            &[("", self.gossip_accepted.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_gossip_ignored_total",
+            "counter",
+            "Gossip messages that were ignored, including those from malefactors.",
+            &[("", self.gossip_ignored.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_dns_queries_total",
+            "counter",
+            "Queries answered by the entry DNS server.",
+            &[("", self.dns_queries.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_dns_query_latency_seconds",
+            "summary",
+            "Time the entry DNS server took to answer its queries.",
+            &[
+                (
+                    "_sum",
+                    format!("{}", self.dns_latency_nanos.get() as f64 / 1_000_000_000.0),
+                ),
+                ("_count", self.dns_queries.get().to_string()),
+            ],
+        );
+        Self::family(
+            &mut text,
+            "masq_payable_total",
+            "gauge",
+            "Total this Node owes other Nodes, in Gwub, as of the last payable scan.",
+            &[("", self.payable_total.get().to_string())],
+        );
+        Self::family(
+            &mut text,
+            "masq_receivable_total",
+            "gauge",
+            "Total other Nodes owe this Node, in Gwub, as of the last receivable scan.",
+            &[("", self.receivable_total.get().to_string())],
+        );
+        text
+    }
+
+    fn family(text: &mut String, name: &str, kind: &str, help: &str, samples: &[(&str, String)]) {
+        writeln!(text, "# HELP {} {}", name, help).expect("Write to String failed");
+        writeln!(text, "# TYPE {} {}", name, kind).expect("Write to String failed");
+        samples.iter().for_each(|(suffix, value)| {
+            writeln!(text, "{}{} {}", name, suffix, value).expect("Write to String failed")
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(METRICS_PATH, "/metrics");
+    }
+
+    #[test]
+    fn counters_count_and_gauges_hold_the_last_value() {
+        let counter = Counter::default();
+        let gauge = Gauge::default();
+
+        counter.increment();
+        counter.add(41);
+        gauge.set(17);
+        gauge.set(5);
+
+        assert_eq!(counter.get(), 42);
+        assert_eq!(gauge.get(), 5);
+    }
+
+    #[test]
+    fn metrics_function_always_returns_the_same_metrics() {
+        let first = metrics();
+        let second = metrics();
+
+        assert_eq!(Arc::ptr_eq(&first, &second), true);
+    }
+
+    #[test]
+    fn record_dns_query_counts_the_query_and_accumulates_latency() {
+        let subject = Metrics::default();
+
+        subject.record_dns_query(Duration::from_millis(3));
+        subject.record_dns_query(Duration::from_micros(500));
+
+        assert_eq!(subject.dns_queries.get(), 2);
+        assert_eq!(subject.dns_latency_nanos.get(), 3_500_000);
+    }
+
+    #[test]
+    fn render_produces_prometheus_text_format() {
+        let subject = Metrics::default();
+        subject.routing_bytes.add(1000);
+        subject.exit_bytes.add(2000);
+        subject.dispatcher_streams.set(3);
+        subject.proxy_client_streams.set(4);
+        subject.route_query_successes.add(5);
+        subject.route_query_failures.add(6);
+        subject.gossip_received.add(7);
+        subject.gossip_accepted.add(8);
+        subject.gossip_ignored.add(9);
+        subject.record_dns_query(Duration::from_millis(1500));
+        subject.payable_total.set(11);
+        subject.receivable_total.set(12);
+
+        let result = subject.render();
+
+        assert_eq!(
+            result,
+            "\
+# HELP masq_routing_bytes_total Payload bytes this Node has routed for other Nodes.
+# TYPE masq_routing_bytes_total counter
+masq_routing_bytes_total 1000
+# HELP masq_exit_bytes_total Payload bytes this Node has carried to and from servers as an exit Node.
+# TYPE masq_exit_bytes_total counter
+masq_exit_bytes_total 2000
+# HELP masq_active_streams Streams currently open in each stream handler pool.
+# TYPE masq_active_streams gauge
+masq_active_streams{pool=\"dispatcher\"} 3
+masq_active_streams{pool=\"proxy_client\"} 4
+# HELP masq_route_queries_total Route queries the Neighborhood has answered, by result.
+# TYPE masq_route_queries_total counter
+masq_route_queries_total{result=\"success\"} 5
+masq_route_queries_total{result=\"failure\"} 6
+# HELP masq_gossip_received_total Gossip messages received from other Nodes.
+# TYPE masq_gossip_received_total counter
+masq_gossip_received_total 7
+# HELP masq_gossip_accepted_total Gossip messages that changed this Node's picture of the network.
+# TYPE masq_gossip_accepted_total counter
+masq_gossip_accepted_total 8
+# HELP masq_gossip_ignored_total Gossip messages that were ignored, including those from malefactors.
+# TYPE masq_gossip_ignored_total counter
+masq_gossip_ignored_total 9
+# HELP masq_dns_queries_total Queries answered by the entry DNS server.
+# TYPE masq_dns_queries_total counter
+masq_dns_queries_total 1
+# HELP masq_dns_query_latency_seconds Time the entry DNS server took to answer its queries.
+# TYPE masq_dns_query_latency_seconds summary
+masq_dns_query_latency_seconds_sum 1.5
+masq_dns_query_latency_seconds_count 1
+# HELP masq_payable_total Total this Node owes other Nodes, in Gwub, as of the last payable scan.
+# TYPE masq_payable_total gauge
+masq_payable_total 11
+# HELP masq_receivable_total Total other Nodes owe this Node, in Gwub, as of the last receivable scan.
+# TYPE masq_receivable_total gauge
+masq_receivable_total 12
+"
+        );
+    }
+}
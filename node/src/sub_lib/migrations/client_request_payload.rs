@@ -14,13 +14,20 @@ use std::convert::TryFrom;
 
 lazy_static! {
     pub static ref MIGRATIONS: Migrations = {
-        let current_version = dv!(0, 1);
+        // 0.2 added ProxyProtocol::Opaque. Every 0.1 payload is also a valid 0.2 payload, but a 0.1
+        // exit Node can't read a 0.2 payload that uses the new protocol.
+        let current_version = dv!(0, 2);
         let mut migrations = Migrations::new(current_version);
 
-        migrate_value!(dv!(0, 1), ClientRequestPayload_0v1, ClientRequestPayloadMF_0v1, {|value: serde_cbor::Value| {
+        migrate_value!(dv!(0, 2), ClientRequestPayload_0v1, ClientRequestPayloadMF_0v2, {|value: serde_cbor::Value| {
             ClientRequestPayload_0v1::try_from (&value)
         }});
-        migrations.add_step (FUTURE_VERSION, dv!(0, 1), Box::new (ClientRequestPayloadMF_0v1{}));
+        migrations.add_step (FUTURE_VERSION, dv!(0, 2), Box::new (ClientRequestPayloadMF_0v2{}));
+
+        migrate_value!(dv!(0, 2), ClientRequestPayload_0v1, ClientRequestPayloadM0v1_0v2, {|value: serde_cbor::Value| {
+            ClientRequestPayload_0v1::try_from (&value)
+        }});
+        migrations.add_step (dv!(0, 1), dv!(0, 2), Box::new (ClientRequestPayloadM0v1_0v2{}));
 
         // add more steps here
 
@@ -99,7 +106,10 @@ impl TryFrom<&Value> for ClientRequestPayload_0v1 {
                     &originator_public_key_opt,
                 );
                 if !missing_fields.is_empty() {
-                    unimplemented!("{:?}", missing_fields.clone())
+                    return Err(StepError::SemanticError(format!(
+                        "Missing or unreadable fields: {:?}",
+                        missing_fields
+                    )));
                 }
                 Ok(ClientRequestPayload_0v1 {
                     stream_key: stream_key_opt.expect("stream_key disappeared"),
@@ -174,6 +184,66 @@ mod tests {
         assert_eq!(actual_crp, expected_crp);
     }
 
+    #[test]
+    fn can_migrate_from_0v1() {
+        let expected_crp = ClientRequestPayload_0v1 {
+            stream_key: StreamKey::new(
+                PublicKey::new(&[1, 2, 3, 4]),
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            ),
+            sequenced_packet: SequencedPacket::new(vec![4, 3, 2, 1], 4321, false),
+            target_hostname: Some("target.hostname.com".to_string()),
+            target_port: 1234,
+            protocol: ProxyProtocol::TLS,
+            originator_public_key: PublicKey::new(&[2, 3, 4, 5]),
+        };
+        let old_migrations = Migrations::new(dv!(0, 1));
+        let serialized =
+            serde_cbor::ser::to_vec(&VersionedData::new(&old_migrations, &expected_crp)).unwrap();
+        let old_vd =
+            serde_cbor::de::from_slice::<VersionedData<ClientRequestPayload_0v1>>(&serialized)
+                .unwrap();
+
+        let actual_crp = ClientRequestPayload_0v1::try_from(old_vd).unwrap();
+
+        assert_eq!(actual_crp, expected_crp);
+    }
+
+    #[test]
+    fn opaque_protocol_survives_serialization_at_the_current_version() {
+        let expected_crp = ClientRequestPayload_0v1 {
+            stream_key: StreamKey::new(
+                PublicKey::new(&[1, 2, 3, 4]),
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            ),
+            sequenced_packet: SequencedPacket::new(vec![4, 3, 2, 1], 4321, false),
+            target_hostname: Some("ssh.example.com".to_string()),
+            target_port: 22,
+            protocol: ProxyProtocol::Opaque,
+            originator_public_key: PublicKey::new(&[2, 3, 4, 5]),
+        };
+        let vd = VersionedData::new(&MIGRATIONS, &expected_crp);
+
+        let actual_crp = ClientRequestPayload_0v1::try_from(vd.clone()).unwrap();
+
+        assert_eq!(vd.version(), dv!(0, 2));
+        assert_eq!(actual_crp, expected_crp);
+    }
+
+    #[test]
+    fn missing_fields_are_an_error_rather_than_a_panic() {
+        let value = Value::Map(std::collections::BTreeMap::new());
+
+        let result = ClientRequestPayload_0v1::try_from(&value);
+
+        assert_eq!(
+            result,
+            Err(StepError::SemanticError(
+                "Missing or unreadable fields: [\"stream_key\", \"sequenced_packet\", \"target_hostname\", \"target_port\", \"protocol\", \"originator_public_key\"]".to_string()
+            ))
+        )
+    }
+
     #[test]
     fn cannot_migrate_from_value_other_than_map() {
         let value = Value::Bool(true);
@@ -47,11 +47,16 @@ impl TryFrom<&Value> for DnsResolveFailure_0v1 {
         match value {
             Value::Map(map) => {
                 let mut stream_key_opt: Option<StreamKey> = None;
+                let mut nonexistent_domain = false;
                 map.keys().for_each(|k| {
                     let v = map.get(k).expect("Disappeared");
                     if let Value::Text(field_name) = k {
-                        if field_name.as_str() == "stream_key" {
-                            stream_key_opt = value_to_type::<StreamKey>(v)
+                        match field_name.as_str() {
+                            "stream_key" => stream_key_opt = value_to_type::<StreamKey>(v),
+                            "nonexistent_domain" => {
+                                nonexistent_domain = value_to_type::<bool>(v).unwrap_or(false)
+                            }
+                            _ => (),
                         }
                     }
                 });
@@ -71,6 +76,7 @@ impl TryFrom<&Value> for DnsResolveFailure_0v1 {
                 }
                 Ok(DnsResolveFailure_0v1 {
                     stream_key: stream_key_opt.expect("stream_key disappeared"),
+                    nonexistent_domain,
                 })
             }
             _ => Err(StepError::SemanticError(format!(
@@ -95,6 +101,7 @@ mod tests {
         #[derive(Serialize, Deserialize)]
         struct ExampleFutureDRF {
             pub stream_key: StreamKey,
+            pub nonexistent_domain: bool,
             pub another_field: String,
             pub yet_another_field: u64,
         }
@@ -103,9 +110,11 @@ mod tests {
                 PublicKey::new(&[1, 2, 3, 4]),
                 SocketAddr::from_str("1.2.3.4:1234").unwrap(),
             ),
+            nonexistent_domain: true,
         };
         let future_crp = ExampleFutureDRF {
             stream_key: expected_crp.stream_key.clone(),
+            nonexistent_domain: true,
             another_field: "These are the times that try men's souls".to_string(),
             yet_another_field: 1234567890,
         };
@@ -121,6 +130,24 @@ mod tests {
         assert_eq!(actual_crp, expected_crp);
     }
 
+    #[test]
+    fn nonexistent_domain_defaults_to_false_when_missing() {
+        #[derive(Serialize, Deserialize)]
+        struct ExampleOlderDRF {
+            pub stream_key: StreamKey,
+        }
+        let stream_key = StreamKey::new(
+            PublicKey::new(&[1, 2, 3, 4]),
+            SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+        );
+        let serialized = serde_cbor::ser::to_vec(&ExampleOlderDRF { stream_key }).unwrap();
+        let value = serde_cbor::de::from_slice::<Value>(&serialized).unwrap();
+
+        let result = DnsResolveFailure_0v1::try_from(&value);
+
+        assert_eq!(result, Ok(DnsResolveFailure_0v1::new(stream_key)));
+    }
+
     #[test]
     fn cannot_migrate_from_value_other_than_map() {
         let value = Value::Bool(true);
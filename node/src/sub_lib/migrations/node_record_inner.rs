@@ -57,6 +57,9 @@ impl TryFrom<&Value> for NodeRecordInner_0v1 {
                 let mut accepts_connections_opt: Option<bool> = None;
                 let mut routes_data_opt: Option<bool> = None;
                 let mut version_opt: Option<u32> = None;
+                let mut reduced_capacity = false;
+                let mut accepts_session_keys = false;
+                let mut accepts_masquerades = false;
                 map.keys().for_each(|k| {
                     let v = map.get(k).expect("Disappeared");
                     match (k, v) {
@@ -85,6 +88,9 @@ impl TryFrom<&Value> for NodeRecordInner_0v1 {
                                     accepts_connections_opt = Some(*field_value)
                                 }
                                 "routes_data" => routes_data_opt = Some(*field_value),
+                                "reduced_capacity" => reduced_capacity = *field_value,
+                                "accepts_session_keys" => accepts_session_keys = *field_value,
+                                "accepts_masquerades" => accepts_masquerades = *field_value,
                                 _ => (),
                             }
                         }
@@ -133,6 +139,9 @@ impl TryFrom<&Value> for NodeRecordInner_0v1 {
                     accepts_connections: accepts_connections_opt.expect("public_key disappeared"),
                     routes_data: routes_data_opt.expect("public_key disappeared"),
                     version: version_opt.expect("public_key disappeared"),
+                    reduced_capacity,
+                    accepts_session_keys,
+                    accepts_masquerades,
                 })
             }
             _ => Err(StepError::SemanticError(format!(
@@ -175,6 +184,9 @@ mod tests {
             pub accepts_connections: bool,
             pub routes_data: bool,
             pub version: u32,
+            pub reduced_capacity: bool,
+            pub accepts_session_keys: bool,
+            pub accepts_masquerades: bool,
             pub another_field: String,
             pub yet_another_field: u64,
         }
@@ -188,6 +200,9 @@ mod tests {
             accepts_connections: false,
             routes_data: true,
             version: 42,
+            reduced_capacity: true,
+            accepts_session_keys: true,
+            accepts_masquerades: true,
         };
         let future_nri = ExampleFutureNRI {
             public_key: expected_nri.public_key.clone(),
@@ -197,6 +212,9 @@ mod tests {
             accepts_connections: expected_nri.accepts_connections,
             routes_data: expected_nri.routes_data,
             version: expected_nri.version,
+            reduced_capacity: expected_nri.reduced_capacity,
+            accepts_session_keys: expected_nri.accepts_session_keys,
+            accepts_masquerades: expected_nri.accepts_masquerades,
             another_field: "These are the times that try men's souls".to_string(),
             yet_another_field: 1234567890,
         };
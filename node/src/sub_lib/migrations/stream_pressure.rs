@@ -0,0 +1,166 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use crate::sub_lib::migrations::utils::value_to_type;
+use crate::sub_lib::proxy_client::StreamPressure_0v1;
+use crate::sub_lib::stream_buffer::Pressure;
+use crate::sub_lib::stream_key::StreamKey;
+use crate::sub_lib::versioned_data::Migrations;
+use crate::sub_lib::versioned_data::FUTURE_VERSION;
+use crate::sub_lib::versioned_data::{MigrationError, StepError, VersionedData};
+use lazy_static::lazy_static;
+use serde_cbor::Value;
+use std::convert::TryFrom;
+
+lazy_static! {
+    pub static ref MIGRATIONS: Migrations = {
+        let current_version = dv!(0, 1);
+        let mut migrations = Migrations::new(current_version);
+
+        migrate_value!(dv!(0, 1), StreamPressure_0v1, StreamPressureMF_0v1, {|value: serde_cbor::Value| {
+            StreamPressure_0v1::try_from (&value)
+        }});
+        migrations.add_step (FUTURE_VERSION, dv!(0, 1), Box::new (StreamPressureMF_0v1{}));
+
+        // add more steps here
+
+        migrations
+    };
+}
+
+impl Into<VersionedData<StreamPressure_0v1>> for StreamPressure_0v1 {
+    fn into(self) -> VersionedData<StreamPressure_0v1> {
+        VersionedData::new(&MIGRATIONS, &self)
+    }
+}
+
+impl TryFrom<VersionedData<StreamPressure_0v1>> for StreamPressure_0v1 {
+    type Error = MigrationError;
+
+    fn try_from(vd: VersionedData<StreamPressure_0v1>) -> Result<Self, Self::Error> {
+        vd.extract(&MIGRATIONS)
+    }
+}
+
+impl TryFrom<&Value> for StreamPressure_0v1 {
+    type Error = StepError;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Map(map) => {
+                let mut stream_key_opt: Option<StreamKey> = None;
+                let mut pressure_opt: Option<Pressure> = None;
+                map.keys().for_each(|k| {
+                    let v = map.get(k).expect("Disappeared");
+                    if let Value::Text(field_name) = k {
+                        match field_name.as_str() {
+                            "stream_key" => stream_key_opt = value_to_type::<StreamKey>(v),
+                            "pressure" => pressure_opt = value_to_type::<Pressure>(v),
+                            _ => (),
+                        }
+                    }
+                });
+                let mut missing_fields: Vec<&str> = vec![];
+                fn check_field<'a, T>(
+                    missing_fields: &mut Vec<&'a str>,
+                    name: &'a str,
+                    field: &Option<T>,
+                ) {
+                    if field.is_none() {
+                        missing_fields.push(name)
+                    }
+                }
+                check_field(&mut missing_fields, "stream_key", &stream_key_opt);
+                check_field(&mut missing_fields, "pressure", &pressure_opt);
+                if !missing_fields.is_empty() {
+                    return Err(StepError::SemanticError(format!(
+                        "Missing fields: {:?}",
+                        missing_fields
+                    )));
+                }
+                Ok(StreamPressure_0v1 {
+                    stream_key: stream_key_opt.expect("stream_key disappeared"),
+                    pressure: pressure_opt.expect("pressure disappeared"),
+                })
+            }
+            _ => Err(StepError::SemanticError(format!(
+                "Expected Value::Map; found {:?}",
+                value
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sub_lib::cryptde::PublicKey;
+    use crate::sub_lib::versioned_data::DataVersion;
+    use serde_derive::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+
+    #[test]
+    fn can_migrate_from_the_future() {
+        #[derive(Serialize, Deserialize)]
+        struct ExampleFutureSP {
+            pub stream_key: StreamKey,
+            pub pressure: Pressure,
+            pub another_field: String,
+        }
+        let expected_sp = StreamPressure_0v1 {
+            stream_key: StreamKey::new(
+                PublicKey::new(&[1, 2, 3, 4]),
+                SocketAddr::from_str("1.2.3.4:1234").unwrap(),
+            ),
+            pressure: Pressure::Pause,
+        };
+        let future_sp = ExampleFutureSP {
+            stream_key: expected_sp.stream_key.clone(),
+            pressure: expected_sp.pressure,
+            another_field: "Slow down, you move too fast".to_string(),
+        };
+        let future_migrations = Migrations::new(DataVersion::new(4095, 4095));
+        let serialized =
+            serde_cbor::ser::to_vec(&VersionedData::new(&future_migrations, &future_sp)).unwrap();
+        let future_vd =
+            serde_cbor::de::from_slice::<VersionedData<StreamPressure_0v1>>(&serialized).unwrap();
+
+        let actual_sp = StreamPressure_0v1::try_from(future_vd).unwrap();
+
+        assert_eq!(actual_sp, expected_sp);
+    }
+
+    #[test]
+    fn cannot_migrate_from_value_other_than_map() {
+        let value = Value::Bool(true);
+
+        let result = StreamPressure_0v1::try_from(&value);
+
+        assert_eq!(
+            result,
+            Err(StepError::SemanticError(
+                "Expected Value::Map; found Bool(true)".to_string()
+            ))
+        )
+    }
+
+    #[test]
+    fn cannot_migrate_from_map_without_pressure() {
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::Text("another_field".to_string()),
+            Value::Text("booga".to_string()),
+        );
+        let value = Value::Map(map);
+
+        let result = StreamPressure_0v1::try_from(&value);
+
+        assert_eq!(
+            result,
+            Err(StepError::SemanticError(
+                "Missing fields: [\"stream_key\", \"pressure\"]".to_string()
+            ))
+        )
+    }
+}
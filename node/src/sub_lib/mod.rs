@@ -10,6 +10,7 @@ pub mod utils;
 pub mod versioned_data;
 
 pub mod accountant;
+pub mod bandwidth;
 pub mod bidi_hashmap;
 pub mod binary_traverser;
 pub mod blockchain_bridge;
@@ -27,7 +28,9 @@ pub mod hopper;
 pub mod http_packet_framer;
 pub mod http_response_start_finder;
 pub mod limiter;
+pub mod log_control;
 pub mod main_tools;
+pub mod metrics;
 pub mod migrations;
 pub mod neighborhood;
 pub mod node_addr;
@@ -39,6 +42,7 @@ pub mod sequence_buffer;
 pub mod sequencer;
 pub mod set_consuming_wallet_message;
 pub mod socket_server;
+pub mod stream_buffer;
 pub mod stream_connector;
 pub mod stream_handler_pool;
 pub mod stream_key;
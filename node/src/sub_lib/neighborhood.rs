@@ -241,6 +241,7 @@ pub struct NeighborhoodSubs {
     pub start: Recipient<StartMessage>,
     pub node_query: Recipient<NodeQueryMessage>,
     pub route_query: Recipient<RouteQueryMessage>,
+    pub status_query: Recipient<NeighborhoodStatusQueryMessage>,
     pub update_node_record_metadata: Recipient<NodeRecordMetadataMessage>,
     pub from_hopper: Recipient<ExpiredCoresPackage<Gossip_0v1>>,
     pub gossip_failure: Recipient<ExpiredCoresPackage<GossipFailure_0v1>>,
@@ -263,6 +264,7 @@ pub struct NodeQueryResponseMetadata {
     pub public_key: PublicKey,
     pub node_addr_opt: Option<NodeAddr>,
     pub rate_pack: RatePack,
+    pub accepts_masquerades: bool,
 }
 
 impl NodeQueryResponseMetadata {
@@ -275,6 +277,7 @@ impl NodeQueryResponseMetadata {
             public_key,
             node_addr_opt,
             rate_pack,
+            accepts_masquerades: false,
         }
     }
 }
@@ -327,6 +330,20 @@ impl RouteQueryMessage {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct NeighborhoodStatusQueryMessage {}
+
+impl Message for NeighborhoodStatusQueryMessage {
+    type Result = NeighborhoodStatus;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NeighborhoodStatus {
+    pub node_descriptor: String,
+    pub neighbor_count: usize,
+    pub round_trip_route_available: bool,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ExpectedService {
     Routing(PublicKey, Wallet, RatePack),
@@ -429,6 +446,7 @@ mod tests {
             start: recipient!(recorder, StartMessage),
             node_query: recipient!(recorder, NodeQueryMessage),
             route_query: recipient!(recorder, RouteQueryMessage),
+            status_query: recipient!(recorder, NeighborhoodStatusQueryMessage),
             update_node_record_metadata: recipient!(recorder, NodeRecordMetadataMessage),
             from_hopper: recipient!(recorder, ExpiredCoresPackage<Gossip_0v1>),
             gossip_failure: recipient!(recorder, ExpiredCoresPackage<GossipFailure_0v1>),
@@ -527,6 +545,42 @@ mod tests {
         )
     }
 
+    #[test]
+    fn node_descriptor_from_str_handles_ipv6_node_addr() {
+        let result =
+            NodeDescriptor::from_str(main_cryptde(), "R29vZEtleQ@[2001:db8::5]:1234;2345;3456");
+
+        assert_eq!(
+            result.unwrap(),
+            NodeDescriptor {
+                encryption_public_key: PublicKey::new(b"GoodKey"),
+                mainnet: true,
+                node_addr_opt: Some(NodeAddr::new(
+                    &IpAddr::from_str("2001:db8::5").unwrap(),
+                    &[1234, 2345, 3456],
+                ))
+            },
+        )
+    }
+
+    #[test]
+    fn node_descriptor_survives_a_round_trip_with_a_dual_stack_node_addr() {
+        let cryptde: &dyn CryptDE = main_cryptde();
+        let public_key = PublicKey::new(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let node_addr = NodeAddr::new(&IpAddr::from_str("123.45.67.89").unwrap(), &[2345, 3456])
+            .with_alternate_ip_addr(&IpAddr::from_str("2001:db8::5").unwrap());
+        let subject = NodeDescriptor::from((&public_key, &node_addr, true, cryptde));
+
+        let string = subject.to_string(cryptde);
+        let result = NodeDescriptor::from_str(cryptde, &string);
+
+        assert_eq!(
+            string,
+            "AQIDBAUGBwg@123.45.67.89/[2001:db8::5]:2345;3456".to_string()
+        );
+        assert_eq!(result, Ok(subject));
+    }
+
     #[test]
     fn node_descriptor_from_key_node_addr_and_mainnet_flag_works() {
         let cryptde: &dyn CryptDE = main_cryptde();
@@ -607,6 +661,18 @@ mod tests {
         assert_eq!(result, "AQIDBAUGBwg:123.45.67.89:2345;3456".to_string());
     }
 
+    #[test]
+    fn node_descriptor_to_string_works_for_ipv6() {
+        let cryptde: &dyn CryptDE = main_cryptde();
+        let public_key = PublicKey::new(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        let node_addr = NodeAddr::new(&IpAddr::from_str("2001:db8::5").unwrap(), &[2345, 3456]);
+        let subject = NodeDescriptor::from((&public_key, &node_addr, false, cryptde));
+
+        let result = subject.to_string(main_cryptde());
+
+        assert_eq!(result, "AQIDBAUGBwg:[2001:db8::5]:2345;3456".to_string());
+    }
+
     #[test]
     fn data_indefinite_route_request() {
         let result = RouteQueryMessage::data_indefinite_route_request(2);
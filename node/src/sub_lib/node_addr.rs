@@ -14,6 +14,10 @@ use std::str::FromStr;
 pub struct NodeAddr {
     ip_addr: IpAddr,
     ports: Vec<u16>,
+    // A dual-stack Node's address in the other family, at the same ports. Older Nodes neither
+    // send it nor look for it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alternate_ip_addr_opt: Option<IpAddr>,
 }
 
 impl NodeAddr {
@@ -25,13 +29,33 @@ impl NodeAddr {
         NodeAddr {
             ip_addr: *ip_addr,
             ports,
+            alternate_ip_addr_opt: None,
         }
     }
 
+    pub fn with_alternate_ip_addr(mut self, ip_addr: &IpAddr) -> NodeAddr {
+        self.alternate_ip_addr_opt = Some(*ip_addr);
+        self
+    }
+
     pub fn ip_addr(&self) -> IpAddr {
         self.ip_addr
     }
 
+    pub fn alternate_ip_addr_opt(&self) -> Option<IpAddr> {
+        self.alternate_ip_addr_opt
+    }
+
+    pub fn ip_addrs(&self) -> Vec<IpAddr> {
+        let mut ip_addrs = vec![self.ip_addr];
+        ip_addrs.extend(self.alternate_ip_addr_opt);
+        ip_addrs
+    }
+
+    pub fn has_ip_addr(&self, ip_addr: IpAddr) -> bool {
+        self.ip_addr == ip_addr || self.alternate_ip_addr_opt == Some(ip_addr)
+    }
+
     pub fn ports(&self) -> Vec<u16> {
         self.ports.clone()
     }
@@ -61,24 +85,39 @@ impl Into<Vec<SocketAddr>> for NodeAddr {
 
 impl Clone for NodeAddr {
     fn clone(&self) -> Self {
-        NodeAddr::new(&self.ip_addr(), &self.ports())
+        NodeAddr {
+            ip_addr: self.ip_addr,
+            ports: self.ports.clone(),
+            alternate_ip_addr_opt: self.alternate_ip_addr_opt,
+        }
     }
 }
 
 impl Debug for NodeAddr {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{:?}", self.ip_addr(), self.ports())
+        match self.alternate_ip_addr_opt {
+            None => write!(f, "{}:{:?}", self.ip_addr(), self.ports()),
+            Some(alternate) => write!(f, "{}/{}:{:?}", self.ip_addr(), alternate, self.ports()),
+        }
     }
 }
 
 impl Display for NodeAddr {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let host_list = self
+            .ip_addrs()
+            .into_iter()
+            .map(|ip_addr| match ip_addr {
+                IpAddr::V4(_) => format!("{}", ip_addr),
+                IpAddr::V6(_) => format!("[{}]", ip_addr),
+            })
+            .collect::<Vec<String>>();
         let port_list = self
             .ports
             .iter()
             .map(|x| format!("{}", x))
             .collect::<Vec<String>>();
-        write!(f, "{}:{}", self.ip_addr(), port_list.join(";"))
+        write!(f, "{}:{}", host_list.join("/"), port_list.join(";"))
     }
 }
 
@@ -86,23 +125,48 @@ impl FromStr for NodeAddr {
     type Err = String;
 
     fn from_str(input: &str) -> Result<NodeAddr, String> {
-        let pieces: Vec<&str> = input.split(':').collect();
-        if pieces.len() != 2 {
-            return Err(format!(
+        // IPv6 addresses contain colons of their own, so they must be bracketed: [::1]:1234;2345.
+        // A dual-stack Node lists one address of each family: 1.2.3.4/[::1]:1234;2345.
+        let format_error = || {
+            format!(
                 "NodeAddr should be expressed as '<IP address>:<port>;<port>,...', not '{}'",
                 input
-            ));
+            )
+        };
+        let (host_list, port_list) = match input.rfind(':') {
+            Some(idx) => (&input[..idx], &input[(idx + 1)..]),
+            None => return Err(format_error()),
+        };
+        let hosts: Vec<&str> = host_list.split('/').collect();
+        if hosts.len() > 2 {
+            return Err(format_error());
         }
-        let ip_addr = match IpAddr::from_str(&pieces[0]) {
-            Err(_) => {
-                return Err(format!(
-                    "NodeAddr must have a valid IP address, not '{}'",
-                    pieces[0]
-                ));
+        let mut ip_addrs = vec![];
+        for host in hosts {
+            let ip_addr_str = if host.starts_with('[') && host.ends_with(']') {
+                &host[1..(host.len() - 1)]
+            } else if host.contains(|c| c == ':' || c == '[' || c == ']') {
+                return Err(format_error());
+            } else {
+                host
+            };
+            match IpAddr::from_str(ip_addr_str) {
+                Err(_) => {
+                    return Err(format!(
+                        "NodeAddr must have a valid IP address, not '{}'",
+                        ip_addr_str
+                    ));
+                }
+                Ok(ip_addr) => ip_addrs.push(ip_addr),
             }
-            Ok(ip_addr) => ip_addr,
-        };
-        let ports: Vec<u16> = match pieces[1]
+        }
+        if ip_addrs.len() == 2 && ip_addrs[0].is_ipv4() == ip_addrs[1].is_ipv4() {
+            return Err(format!(
+                "NodeAddr may have only one IP address of each family, not '{}'",
+                host_list
+            ));
+        }
+        let ports: Vec<u16> = match port_list
             .split(';')
             .map(|s| match s.parse::<u16>() {
                 Err(_) => Err(format!(
@@ -125,7 +189,11 @@ impl FromStr for NodeAddr {
             Ok(ports) => ports,
             Err(msg) => return Err(msg),
         };
-        Ok(NodeAddr::new(&ip_addr, &ports))
+        let node_addr = NodeAddr::new(&ip_addrs[0], &ports);
+        Ok(match ip_addrs.get(1) {
+            Some(alternate) => node_addr.with_alternate_ip_addr(alternate),
+            None => node_addr,
+        })
     }
 }
 
@@ -210,6 +278,17 @@ mod tests {
         assert_eq!(result, "2.5.8.1:6;9");
     }
 
+    #[test]
+    fn node_addrs_produces_bracketed_display_string_for_ipv6() {
+        let ip_addr = IpAddr::from_str("2001:db8::5").unwrap();
+        let ports = vec![9, 6];
+        let subject = NodeAddr::new(&ip_addr, &ports);
+
+        let result = format!("{}", subject);
+
+        assert_eq!(result, "[2001:db8::5]:6;9");
+    }
+
     #[test]
     fn node_addrs_from_str_needs_two_pieces() {
         let result = NodeAddr::from_str("Booga");
@@ -222,6 +301,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn node_addrs_from_str_needs_brackets_around_ipv6_address() {
+        let result = NodeAddr::from_str("2001:db8::5:1234");
+
+        assert_eq!(
+            result,
+            Err(String::from(
+                "NodeAddr should be expressed as '<IP address>:<port>;<port>,...', not '2001:db8::5:1234'"
+            ))
+        );
+    }
+
+    #[test]
+    fn node_addrs_from_str_needs_closing_bracket_around_ipv6_address() {
+        let result = NodeAddr::from_str("[2001:db8::5:1234");
+
+        assert_eq!(
+            result,
+            Err(String::from(
+                "NodeAddr should be expressed as '<IP address>:<port>;<port>,...', not '[2001:db8::5:1234'"
+            ))
+        );
+    }
+
     #[test]
     fn node_addrs_from_str_needs_good_ip_address() {
         let result = NodeAddr::from_str("253.254.255.256:1234;2345;3456");
@@ -270,4 +373,122 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn node_addrs_from_str_follows_the_happy_path_for_ipv6() {
+        let result = NodeAddr::from_str("[2001:db8::5]:1234;2345;3456");
+
+        assert_eq!(
+            result,
+            Ok(NodeAddr::new(
+                &IpAddr::from_str("2001:db8::5").unwrap(),
+                &[1234, 2345, 3456]
+            ))
+        );
+    }
+
+    #[test]
+    fn ipv6_node_addrs_survive_a_round_trip_through_a_string() {
+        let subject = NodeAddr::new(&IpAddr::from_str("fe80::1:2").unwrap(), &[4321, 1234]);
+
+        let result = NodeAddr::from_str(&subject.to_string());
+
+        assert_eq!(result, Ok(subject));
+    }
+
+    #[test]
+    fn dual_stack_node_addrs_produce_display_and_debug_strings() {
+        let subject = NodeAddr::new(&IpAddr::from_str("2.5.8.1").unwrap(), &[9, 6])
+            .with_alternate_ip_addr(&IpAddr::from_str("2001:db8::5").unwrap());
+
+        assert_eq!(format!("{}", subject), "2.5.8.1/[2001:db8::5]:6;9");
+        assert_eq!(format!("{:?}", subject), "2.5.8.1/2001:db8::5:[6, 9]");
+    }
+
+    #[test]
+    fn node_addrs_from_str_follows_the_happy_path_for_dual_stack() {
+        let result = NodeAddr::from_str("[2001:db8::5]/1.2.3.4:1234;2345");
+
+        assert_eq!(
+            result,
+            Ok(
+                NodeAddr::new(&IpAddr::from_str("2001:db8::5").unwrap(), &[1234, 2345])
+                    .with_alternate_ip_addr(&IpAddr::from_str("1.2.3.4").unwrap())
+            )
+        );
+    }
+
+    #[test]
+    fn node_addrs_from_str_wants_only_one_ip_address_of_each_family() {
+        let result = NodeAddr::from_str("1.2.3.4/5.6.7.8:1234");
+
+        assert_eq!(
+            result,
+            Err(String::from(
+                "NodeAddr may have only one IP address of each family, not '1.2.3.4/5.6.7.8'"
+            ))
+        );
+    }
+
+    #[test]
+    fn node_addrs_from_str_wants_no_more_than_two_ip_addresses() {
+        let result = NodeAddr::from_str("1.2.3.4/[::1]/5.6.7.8:1234");
+
+        assert_eq!(
+            result,
+            Err(String::from(
+                "NodeAddr should be expressed as '<IP address>:<port>;<port>,...', not '1.2.3.4/[::1]/5.6.7.8:1234'"
+            ))
+        );
+    }
+
+    #[test]
+    fn dual_stack_node_addrs_know_all_their_ip_addresses() {
+        let ipv4 = IpAddr::from_str("1.2.3.4").unwrap();
+        let ipv6 = IpAddr::from_str("2001:db8::5").unwrap();
+        let single = NodeAddr::new(&ipv4, &[1234]);
+        let dual = single.clone().with_alternate_ip_addr(&ipv6);
+
+        assert_eq!(single.ip_addrs(), vec![ipv4]);
+        assert_eq!(single.has_ip_addr(ipv6), false);
+        assert_eq!(dual.ip_addrs(), vec![ipv4, ipv6]);
+        assert_eq!(dual.has_ip_addr(ipv4), true);
+        assert_eq!(dual.has_ip_addr(ipv6), true);
+        assert_eq!(dual.clone(), dual);
+        assert_ne!(dual, single);
+    }
+
+    #[test]
+    fn node_addrs_without_alternates_serialize_as_they_always_have() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct OldNodeAddr {
+            ip_addr: IpAddr,
+            ports: Vec<u16>,
+        }
+        let old = OldNodeAddr {
+            ip_addr: IpAddr::from_str("1.2.3.4").unwrap(),
+            ports: vec![1234],
+        };
+        let single = NodeAddr::new(&IpAddr::from_str("1.2.3.4").unwrap(), &[1234]);
+        let dual = single
+            .clone()
+            .with_alternate_ip_addr(&IpAddr::from_str("2001:db8::5").unwrap());
+
+        let single_serialized = serde_cbor::ser::to_vec(&single).unwrap();
+        let dual_serialized = serde_cbor::ser::to_vec(&dual).unwrap();
+
+        assert_eq!(single_serialized, serde_cbor::ser::to_vec(&old).unwrap());
+        assert_eq!(
+            serde_cbor::de::from_slice::<NodeAddr>(&single_serialized).unwrap(),
+            single
+        );
+        assert_eq!(
+            serde_cbor::de::from_slice::<NodeAddr>(&dual_serialized).unwrap(),
+            dual
+        );
+        assert_eq!(
+            serde_cbor::de::from_slice::<OldNodeAddr>(&dual_serialized).unwrap(),
+            old
+        );
+    }
 }
@@ -4,26 +4,39 @@ use crate::sub_lib::hopper::{ExpiredCoresPackage, MessageType};
 use crate::sub_lib::peer_actors::BindMessage;
 use crate::sub_lib::proxy_server::ClientRequestPayload_0v1;
 use crate::sub_lib::sequence_buffer::SequencedPacket;
+use crate::sub_lib::stream_buffer::Pressure;
 use crate::sub_lib::stream_key::StreamKey;
 use crate::sub_lib::versioned_data::VersionedData;
 use actix::Message;
 use actix::Recipient;
+pub use masq_lib::dns_protocol::DnsProtocol;
+pub use masq_lib::exit_ports::ExitPorts;
+use masq_lib::ui_gateway::NodeFromUiMessage;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt::{Debug, Formatter};
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
 use std::net::SocketAddrV4;
+use std::time::Duration;
 
 pub fn error_socket_addr() -> SocketAddr {
     SocketAddr::from(SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 0))
 }
 
+pub const DEFAULT_EXIT_IDLE_TIMEOUT_SECS: u64 = 300;
+pub const DEFAULT_EXIT_ABSOLUTE_TIMEOUT_SECS: u64 = 86400;
+
 #[derive(Clone)]
 pub struct ProxyClientConfig {
     pub cryptde: &'static dyn CryptDE,
     pub dns_servers: Vec<SocketAddr>,
+    pub dns_protocol: DnsProtocol,
     pub exit_service_rate: u64,
     pub exit_byte_rate: u64,
+    pub exit_idle_timeout: Duration,
+    pub exit_absolute_timeout: Duration,
+    pub exit_ports_opt: Option<ExitPorts>,
+    pub local_destinations_allowed: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
@@ -37,11 +50,42 @@ pub struct ClientResponsePayload_0v1 {
 #[allow(non_camel_case_types)]
 pub struct DnsResolveFailure_0v1 {
     pub stream_key: StreamKey,
+    // True when the name servers answered that the name has no address: the exit Node did its job
+    #[serde(default)]
+    pub nonexistent_domain: bool,
 }
 
 impl DnsResolveFailure_0v1 {
     pub fn new(stream_key: StreamKey) -> Self {
-        Self { stream_key }
+        Self {
+            stream_key,
+            nonexistent_domain: false,
+        }
+    }
+
+    pub fn for_nonexistent_domain(stream_key: StreamKey) -> Self {
+        Self {
+            stream_key,
+            nonexistent_domain: true,
+        }
+    }
+}
+
+// Travels between the ProxyServer and the ProxyClient at either end of a stream, asking the
+// receiver to stop or start reading from its side, because the sender can't write fast enough.
+#[derive(Message, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[allow(non_camel_case_types)]
+pub struct StreamPressure_0v1 {
+    pub stream_key: StreamKey,
+    pub pressure: Pressure,
+}
+
+impl StreamPressure_0v1 {
+    pub fn new(stream_key: StreamKey, pressure: Pressure) -> Self {
+        Self {
+            stream_key,
+            pressure,
+        }
     }
 }
 
@@ -63,12 +107,24 @@ impl Into<MessageType> for DnsResolveFailure_0v1 {
     }
 }
 
+impl Into<MessageType> for StreamPressure_0v1 {
+    fn into(self) -> MessageType {
+        MessageType::StreamPressure(VersionedData::new(
+            &crate::sub_lib::migrations::stream_pressure::MIGRATIONS,
+            &self,
+        ))
+    }
+}
+
 #[derive(Clone)]
 pub struct ProxyClientSubs {
     pub bind: Recipient<BindMessage>,
     pub from_hopper: Recipient<ExpiredCoresPackage<ClientRequestPayload_0v1>>,
     pub inbound_server_data: Recipient<InboundServerData>,
     pub dns_resolve_failed: Recipient<DnsResolveFailure_0v1>,
+    pub stream_pressure: Recipient<StreamPressure_0v1>,
+    pub stream_pressure_from_hopper: Recipient<ExpiredCoresPackage<StreamPressure_0v1>>,
+    pub ui_sub: Recipient<NodeFromUiMessage>,
 }
 
 impl Debug for ProxyClientSubs {
@@ -135,6 +191,12 @@ mod tests {
             from_hopper: recipient!(recorder, ExpiredCoresPackage<ClientRequestPayload_0v1>),
             inbound_server_data: recipient!(recorder, InboundServerData),
             dns_resolve_failed: recipient!(recorder, DnsResolveFailure_0v1),
+            stream_pressure: recipient!(recorder, StreamPressure_0v1),
+            stream_pressure_from_hopper: recipient!(
+                recorder,
+                ExpiredCoresPackage<StreamPressure_0v1>
+            ),
+            ui_sub: recipient!(recorder, NodeFromUiMessage),
         };
 
         assert_eq!(format!("{:?}", subject), "ProxyClientSubs");
@@ -2,19 +2,23 @@
 use crate::sub_lib::cryptde::PublicKey;
 use crate::sub_lib::data_version::DataVersion;
 use crate::sub_lib::dispatcher::InboundClientData;
-use crate::sub_lib::dispatcher::StreamShutdownMsg;
+use crate::sub_lib::dispatcher::{StreamShutdownMsg, WritePressureMsg};
 use crate::sub_lib::hopper::{ExpiredCoresPackage, MessageType};
 use crate::sub_lib::neighborhood::{ExpectedService, RouteQueryResponse};
 use crate::sub_lib::peer_actors::BindMessage;
-use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, DnsResolveFailure_0v1};
+use crate::sub_lib::proxy_client::{
+    ClientResponsePayload_0v1, DnsResolveFailure_0v1, StreamPressure_0v1,
+};
 use crate::sub_lib::sequence_buffer::SequencedPacket;
 use crate::sub_lib::set_consuming_wallet_message::SetConsumingWalletMessage;
 use crate::sub_lib::stream_key::StreamKey;
 use crate::sub_lib::versioned_data::VersionedData;
 use actix::Message;
 use actix::Recipient;
+pub use masq_lib::port_mapping::PortMapping;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt::Debug;
+use std::net::SocketAddr;
 
 pub const DEFAULT_MINIMUM_HOP_COUNT: usize = 3;
 
@@ -22,6 +26,8 @@ pub const DEFAULT_MINIMUM_HOP_COUNT: usize = 3;
 pub enum ProxyProtocol {
     HTTP,
     TLS,
+    // A TCP stream whose contents the ProxyServer doesn't try to understand
+    Opaque,
 }
 
 // TODO: Based on the way it's used, this struct should comprise two elements: one, a nested
@@ -67,6 +73,14 @@ pub struct AddRouteMessage {
     pub route: RouteQueryResponse,
 }
 
+#[derive(Message, Debug, PartialEq)]
+pub struct RetryRouteQueryMessage {
+    pub payload: ClientRequestPayload_0v1,
+    pub source_addr: SocketAddr,
+    pub retire_stream_key: bool,
+    pub attempt: usize,
+}
+
 #[derive(Clone)]
 pub struct ProxyServerSubs {
     // ProxyServer will handle these messages:
@@ -74,9 +88,12 @@ pub struct ProxyServerSubs {
     pub from_dispatcher: Recipient<InboundClientData>,
     pub from_hopper: Recipient<ExpiredCoresPackage<ClientResponsePayload_0v1>>,
     pub dns_failure_from_hopper: Recipient<ExpiredCoresPackage<DnsResolveFailure_0v1>>,
+    pub stream_pressure_from_hopper: Recipient<ExpiredCoresPackage<StreamPressure_0v1>>,
     pub add_return_route: Recipient<AddReturnRouteMessage>,
     pub add_route: Recipient<AddRouteMessage>,
+    pub retry_route_query: Recipient<RetryRouteQueryMessage>,
     pub stream_shutdown_sub: Recipient<StreamShutdownMsg>,
+    pub write_pressure_sub: Recipient<WritePressureMsg>,
     pub set_consuming_wallet_sub: Recipient<SetConsumingWalletMessage>,
 }
 
@@ -105,9 +122,15 @@ mod tests {
                 recorder,
                 ExpiredCoresPackage<DnsResolveFailure_0v1>
             ),
+            stream_pressure_from_hopper: recipient!(
+                recorder,
+                ExpiredCoresPackage<StreamPressure_0v1>
+            ),
             add_return_route: recipient!(recorder, AddReturnRouteMessage),
             add_route: recipient!(recorder, AddRouteMessage),
+            retry_route_query: recipient!(recorder, RetryRouteQueryMessage),
             stream_shutdown_sub: recipient!(recorder, StreamShutdownMsg),
+            write_pressure_sub: recipient!(recorder, WritePressureMsg),
             set_consuming_wallet_sub: recipient!(recorder, SetConsumingWalletMessage),
         };
 
@@ -0,0 +1,589 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use futures::task;
+use futures::task::Task;
+use futures::{Async, Future};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+// Data queued for a stream beyond this many bytes is refused, and the stream is abandoned.
+pub const STREAM_BUFFER_CAPACITY: usize = 4 * 1024 * 1024;
+// Once this many bytes are waiting to be written, the other end of the stream is asked to stop
+// reading...
+pub const STREAM_BUFFER_PAUSE_LEVEL: usize = 1024 * 1024;
+// ...and once the backlog drains to this many, it's told to start again.
+pub const STREAM_BUFFER_RESUME_LEVEL: usize = 256 * 1024;
+// While the backlog stays above the resume level, the other end is reminded this often to stay
+// paused, in case it has stopped listening for some reason...
+pub const PAUSE_REPEAT_INTERVAL: Duration = Duration::from_secs(10);
+// ...and a reader paused by the other end goes back to reading after this long without a reminder,
+// in case the Resume got lost on the way.
+pub const BACKPRESSURE_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pressure {
+    Pause,
+    Resume,
+}
+
+// Why a stream's reading is paused. Each reason pauses and resumes independently; reading goes on
+// only once none of them is holding it back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PauseReason {
+    Backpressure,
+    Bandwidth,
+}
+
+pub type PressureSignal = Box<dyn Fn(Pressure) + Send>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StreamBufferOverflow {
+    pub queued: usize,
+    pub capacity: usize,
+}
+
+impl fmt::Display for StreamBufferOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} bytes already queued; buffer capacity is {}",
+            self.queued, self.capacity
+        )
+    }
+}
+
+// Counts the bytes handed to a stream's writer that haven't made it onto the stream yet. Whoever
+// queues data reserves room for it; the writer releases the room once the data is written.
+#[derive(Clone)]
+pub struct StreamBuffer {
+    inner: Arc<Mutex<StreamBufferInner>>,
+}
+
+struct StreamBufferInner {
+    queued: usize,
+    capacity: usize,
+    pause_level: usize,
+    resume_level: usize,
+    paused: bool,
+    last_pause_opt: Option<Instant>,
+    pause_repeat_interval: Duration,
+    overflowed: bool,
+    signal_opt: Option<PressureSignal>,
+}
+
+impl Debug for StreamBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "StreamBuffer ({} bytes queued)", self.queued())
+    }
+}
+
+impl Default for StreamBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamBuffer {
+    pub fn new() -> Self {
+        Self::with_limits(
+            STREAM_BUFFER_CAPACITY,
+            STREAM_BUFFER_PAUSE_LEVEL,
+            STREAM_BUFFER_RESUME_LEVEL,
+        )
+    }
+
+    pub fn with_limits(capacity: usize, pause_level: usize, resume_level: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(StreamBufferInner {
+                queued: 0,
+                capacity,
+                pause_level,
+                resume_level,
+                paused: false,
+                last_pause_opt: None,
+                pause_repeat_interval: PAUSE_REPEAT_INTERVAL,
+                overflowed: false,
+                signal_opt: None,
+            })),
+        }
+    }
+
+    // The signal is called with Pause each time the backlog crosses the pause level going up (and
+    // again every so often, as more data arrives, until it drains), and with Resume once each time
+    // it drains to the resume level afterward.
+    pub fn on_pressure(&self, signal: PressureSignal) {
+        self.lock().signal_opt = Some(signal);
+    }
+
+    pub fn queued(&self) -> usize {
+        self.lock().queued
+    }
+
+    pub fn is_overflowed(&self) -> bool {
+        self.lock().overflowed
+    }
+
+    // An overflow is permanent: once data has been refused, the stream has a hole in it, and
+    // nothing more should be written to it.
+    pub fn reserve(&self, byte_count: usize) -> Result<(), StreamBufferOverflow> {
+        let mut inner = self.lock();
+        if inner.overflowed || (inner.queued + byte_count > inner.capacity) {
+            inner.overflowed = true;
+            return Err(StreamBufferOverflow {
+                queued: inner.queued,
+                capacity: inner.capacity,
+            });
+        }
+        inner.queued += byte_count;
+        let now = Instant::now();
+        let pause_is_due = if inner.paused {
+            inner.last_pause_opt.map_or(true, |last_pause| {
+                now.duration_since(last_pause) >= inner.pause_repeat_interval
+            })
+        } else {
+            inner.queued >= inner.pause_level
+        };
+        if pause_is_due {
+            inner.paused = true;
+            inner.last_pause_opt = Some(now);
+            inner.signal(Pressure::Pause);
+        }
+        Ok(())
+    }
+
+    pub fn release(&self, byte_count: usize) {
+        let mut inner = self.lock();
+        inner.queued = inner.queued.saturating_sub(byte_count);
+        if inner.paused && (inner.queued <= inner.resume_level) {
+            inner.paused = false;
+            inner.last_pause_opt = None;
+            inner.signal(Pressure::Resume);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<StreamBufferInner> {
+        self.inner.lock().expect("StreamBuffer is poisoned")
+    }
+}
+
+impl StreamBufferInner {
+    fn signal(&self, pressure: Pressure) {
+        if let Some(signal) = self.signal_opt.as_ref() {
+            signal(pressure)
+        }
+    }
+}
+
+// Lets something other than a stream's reader tell the reader to stop reading for a while.
+#[derive(Clone)]
+pub struct ReadSwitch {
+    inner: Arc<Mutex<ReadSwitchInner>>,
+}
+
+struct ReadSwitchInner {
+    // Each reason holding reading back, with the time it expires, if it does
+    pause_reasons: HashMap<PauseReason, Option<Instant>>,
+    backpressure_timeout: Duration,
+    parked_task_opt: Option<Task>,
+    wakeup_opt: Option<Delay>,
+}
+
+impl Default for ReadSwitch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadSwitch {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(ReadSwitchInner {
+                pause_reasons: HashMap::new(),
+                backpressure_timeout: BACKPRESSURE_TIMEOUT,
+                parked_task_opt: None,
+                wakeup_opt: None,
+            })),
+        }
+    }
+
+    // Backpressure comes from the other end of a route, and its Resume can be lost on the way, so a
+    // Backpressure pause expires unless it's repeated. Bandwidth pauses are made and lifted here in
+    // the Node, and last until they're lifted.
+    pub fn apply(&self, reason: PauseReason, pressure: Pressure) {
+        let mut inner = self.lock();
+        match pressure {
+            Pressure::Pause => {
+                let expiration_opt = match reason {
+                    PauseReason::Backpressure => Some(Instant::now() + inner.backpressure_timeout),
+                    PauseReason::Bandwidth => None,
+                };
+                inner.pause_reasons.insert(reason, expiration_opt);
+            }
+            Pressure::Resume => {
+                inner.pause_reasons.remove(&reason);
+                if inner.pause_reasons.is_empty() {
+                    inner.wakeup_opt = None;
+                    if let Some(task) = inner.parked_task_opt.take() {
+                        task.notify()
+                    }
+                }
+            }
+        }
+    }
+
+    // Must be called from within the reader's poll(): if reading is paused, the reader's task is
+    // parked here, to be woken when reading resumes or the pause expires.
+    pub fn is_paused(&self) -> bool {
+        let mut inner = self.lock();
+        let now = Instant::now();
+        inner
+            .pause_reasons
+            .retain(|_, expiration_opt| expiration_opt.map_or(true, |expiration| expiration > now));
+        if inner.pause_reasons.is_empty() {
+            inner.parked_task_opt = None;
+            inner.wakeup_opt = None;
+            return false;
+        }
+        inner.parked_task_opt = Some(task::current());
+        let next_expiration_opt = inner.pause_reasons.values().flatten().min().copied();
+        match next_expiration_opt {
+            Some(expiration) => {
+                let wakeup_is_stale = inner
+                    .wakeup_opt
+                    .as_ref()
+                    .map_or(true, |wakeup| wakeup.deadline() != expiration);
+                if wakeup_is_stale {
+                    inner.wakeup_opt = Some(Delay::new(expiration));
+                }
+                // Polling the Delay has the timer wake this task at the expiration. With no timer
+                // around, the poll fails, and only a Resume will wake the task.
+                if let Some(wakeup) = inner.wakeup_opt.as_mut() {
+                    if let Ok(Async::Ready(())) = wakeup.poll() {
+                        task::current().notify()
+                    }
+                }
+            }
+            None => inner.wakeup_opt = None,
+        }
+        true
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<ReadSwitchInner> {
+        self.inner.lock().expect("ReadSwitch is poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(STREAM_BUFFER_CAPACITY, 4_194_304);
+        assert_eq!(STREAM_BUFFER_PAUSE_LEVEL, 1_048_576);
+        assert_eq!(STREAM_BUFFER_RESUME_LEVEL, 262_144);
+        assert_eq!(PAUSE_REPEAT_INTERVAL, Duration::from_secs(10));
+        assert_eq!(BACKPRESSURE_TIMEOUT, Duration::from_secs(30));
+    }
+
+    fn make_recording_buffer(
+        capacity: usize,
+        pause_level: usize,
+        resume_level: usize,
+    ) -> (StreamBuffer, Arc<Mutex<Vec<Pressure>>>) {
+        let subject = StreamBuffer::with_limits(capacity, pause_level, resume_level);
+        let signals_arc = Arc::new(Mutex::new(vec![]));
+        let signals_inner = signals_arc.clone();
+        subject.on_pressure(Box::new(move |pressure| {
+            signals_inner.lock().unwrap().push(pressure)
+        }));
+        (subject, signals_arc)
+    }
+
+    #[test]
+    fn buffer_signals_pause_once_when_backlog_reaches_pause_level() {
+        let (subject, signals_arc) = make_recording_buffer(100, 50, 10);
+
+        subject.reserve(30).unwrap();
+        subject.reserve(20).unwrap();
+        subject.reserve(20).unwrap();
+
+        assert_eq!(subject.queued(), 70);
+        assert_eq!(*signals_arc.lock().unwrap(), vec![Pressure::Pause]);
+    }
+
+    #[test]
+    fn buffer_repeats_pause_as_data_arrives_until_backlog_drains_to_resume_level() {
+        let (subject, signals_arc) = make_recording_buffer(100, 50, 10);
+        subject.inner.lock().unwrap().pause_repeat_interval = Duration::from_millis(0);
+
+        subject.reserve(60).unwrap();
+        subject.reserve(10).unwrap();
+        subject.release(55);
+        subject.reserve(1).unwrap();
+        subject.release(16);
+        subject.reserve(1).unwrap();
+
+        assert_eq!(
+            *signals_arc.lock().unwrap(),
+            vec![
+                Pressure::Pause,
+                Pressure::Pause,
+                Pressure::Pause,
+                Pressure::Resume
+            ]
+        );
+    }
+
+    #[test]
+    fn buffer_signals_resume_once_when_backlog_drains_to_resume_level() {
+        let (subject, signals_arc) = make_recording_buffer(100, 50, 10);
+        subject.reserve(60).unwrap();
+
+        subject.release(30);
+        subject.release(20);
+        subject.release(10);
+
+        assert_eq!(subject.queued(), 0);
+        assert_eq!(
+            *signals_arc.lock().unwrap(),
+            vec![Pressure::Pause, Pressure::Resume]
+        );
+    }
+
+    #[test]
+    fn buffer_does_not_signal_resume_without_having_signaled_pause() {
+        let (subject, signals_arc) = make_recording_buffer(100, 50, 10);
+        subject.reserve(40).unwrap();
+
+        subject.release(40);
+
+        assert_eq!(signals_arc.lock().unwrap().is_empty(), true);
+    }
+
+    #[test]
+    fn buffer_refuses_data_that_would_exceed_capacity_and_everything_after() {
+        let (subject, _) = make_recording_buffer(100, 50, 10);
+        subject.reserve(90).unwrap();
+
+        let overflow_result = subject.reserve(11);
+        subject.release(90);
+        let later_result = subject.reserve(1);
+
+        assert_eq!(
+            overflow_result,
+            Err(StreamBufferOverflow {
+                queued: 90,
+                capacity: 100
+            })
+        );
+        assert_eq!(
+            later_result,
+            Err(StreamBufferOverflow {
+                queued: 0,
+                capacity: 100
+            })
+        );
+        assert_eq!(subject.is_overflowed(), true);
+    }
+
+    #[test]
+    fn buffer_works_without_a_signal() {
+        let subject = StreamBuffer::with_limits(100, 50, 10);
+
+        subject.reserve(60).unwrap();
+        subject.release(60);
+
+        assert_eq!(subject.queued(), 0);
+        assert_eq!(subject.is_overflowed(), false);
+    }
+
+    #[test]
+    fn buffer_clones_share_their_backlog() {
+        let subject = StreamBuffer::new();
+        let clone = subject.clone();
+
+        subject.reserve(1000).unwrap();
+        clone.release(400);
+
+        assert_eq!(subject.queued(), 600);
+        assert_eq!(
+            format!("{:?}", clone),
+            "StreamBuffer (600 bytes queued)".to_string()
+        );
+    }
+
+    #[test]
+    fn overflow_displays_properly() {
+        let subject = StreamBufferOverflow {
+            queued: 1234,
+            capacity: 2000,
+        };
+
+        assert_eq!(
+            subject.to_string(),
+            "1234 bytes already queued; buffer capacity is 2000".to_string()
+        );
+    }
+
+    #[test]
+    fn read_switch_pauses_and_resumes() {
+        let subject = ReadSwitch::new();
+        let reader_view = subject.clone();
+
+        let result = future::lazy(move || {
+            let initially = reader_view.is_paused();
+            subject.apply(PauseReason::Backpressure, Pressure::Pause);
+            let after_pause = reader_view.is_paused();
+            let parked = subject.inner.lock().unwrap().parked_task_opt.is_some();
+            subject.apply(PauseReason::Backpressure, Pressure::Resume);
+            let after_resume = reader_view.is_paused();
+            let unparked = subject.inner.lock().unwrap().parked_task_opt.is_none();
+            future::ok::<Async<_>, ()>(Async::Ready((
+                initially,
+                after_pause,
+                parked,
+                after_resume,
+                unparked,
+            )))
+        })
+        .wait();
+
+        assert_eq!(result, Ok(Async::Ready((false, true, true, false, true))));
+    }
+
+    #[test]
+    fn read_switch_stays_paused_until_every_reason_to_pause_is_gone() {
+        let subject = ReadSwitch::new();
+        let reader_view = subject.clone();
+
+        let result = future::lazy(move || {
+            subject.apply(PauseReason::Backpressure, Pressure::Pause);
+            subject.apply(PauseReason::Bandwidth, Pressure::Pause);
+            let both = reader_view.is_paused();
+            subject.apply(PauseReason::Bandwidth, Pressure::Resume);
+            let backpressure_only = reader_view.is_paused();
+            let still_parked = subject.inner.lock().unwrap().parked_task_opt.is_some();
+            subject.apply(PauseReason::Bandwidth, Pressure::Resume);
+            let repeated_resume = reader_view.is_paused();
+            subject.apply(PauseReason::Backpressure, Pressure::Resume);
+            let neither = reader_view.is_paused();
+            let unparked = subject.inner.lock().unwrap().parked_task_opt.is_none();
+            future::ok::<Async<_>, ()>(Async::Ready((
+                both,
+                backpressure_only,
+                still_parked,
+                repeated_resume,
+                neither,
+                unparked,
+            )))
+        })
+        .wait();
+
+        assert_eq!(
+            result,
+            Ok(Async::Ready((true, true, true, true, false, true)))
+        );
+    }
+
+    #[test]
+    fn read_switch_lets_backpressure_pauses_expire_but_not_bandwidth_pauses() {
+        let subject = ReadSwitch::new();
+        subject.inner.lock().unwrap().backpressure_timeout = Duration::from_millis(10);
+        let reader_view = subject.clone();
+
+        let result = future::lazy(move || {
+            subject.apply(PauseReason::Backpressure, Pressure::Pause);
+            subject.apply(PauseReason::Bandwidth, Pressure::Pause);
+            thread::sleep(Duration::from_millis(20));
+            let bandwidth_only = reader_view.is_paused();
+            let reasons = subject
+                .inner
+                .lock()
+                .unwrap()
+                .pause_reasons
+                .keys()
+                .copied()
+                .collect::<Vec<_>>();
+            subject.apply(PauseReason::Bandwidth, Pressure::Resume);
+            subject.apply(PauseReason::Backpressure, Pressure::Pause);
+            thread::sleep(Duration::from_millis(20));
+            let expired = reader_view.is_paused();
+            future::ok::<Async<_>, ()>(Async::Ready((bandwidth_only, reasons, expired)))
+        })
+        .wait();
+
+        assert_eq!(
+            result,
+            Ok(Async::Ready((true, vec![PauseReason::Bandwidth], false)))
+        );
+    }
+
+    #[test]
+    fn read_switch_lets_a_backpressure_pause_expire_unless_it_is_repeated() {
+        let subject = ReadSwitch::new();
+        subject.inner.lock().unwrap().backpressure_timeout = Duration::from_millis(200);
+        let reader_view = subject.clone();
+
+        let result = future::lazy(move || {
+            subject.apply(PauseReason::Backpressure, Pressure::Pause);
+            thread::sleep(Duration::from_millis(120));
+            subject.apply(PauseReason::Backpressure, Pressure::Pause);
+            thread::sleep(Duration::from_millis(120));
+            let repeated = reader_view.is_paused();
+            thread::sleep(Duration::from_millis(120));
+            let expired = reader_view.is_paused();
+            let unparked = subject.inner.lock().unwrap().parked_task_opt.is_none();
+            future::ok::<Async<_>, ()>(Async::Ready((repeated, expired, unparked)))
+        })
+        .wait();
+
+        assert_eq!(result, Ok(Async::Ready((true, false, true))));
+    }
+
+    struct PausedReader {
+        read_switch: ReadSwitch,
+        poll_count: usize,
+    }
+
+    impl Future for PausedReader {
+        type Item = usize;
+        type Error = ();
+
+        fn poll(&mut self) -> Result<Async<usize>, ()> {
+            self.poll_count += 1;
+            if self.read_switch.is_paused() {
+                Ok(Async::NotReady)
+            } else {
+                Ok(Async::Ready(self.poll_count))
+            }
+        }
+    }
+
+    #[test]
+    fn read_switch_wakes_a_parked_reader_when_its_backpressure_pause_expires() {
+        let subject = ReadSwitch::new();
+        subject.inner.lock().unwrap().backpressure_timeout = Duration::from_millis(50);
+        subject.apply(PauseReason::Backpressure, Pressure::Pause);
+        let reader = PausedReader {
+            read_switch: subject.clone(),
+            poll_count: 0,
+        };
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            tokio::run(reader.map(move |poll_count| tx.send(poll_count).unwrap()));
+        });
+
+        let poll_count = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(poll_count, 2);
+    }
+}
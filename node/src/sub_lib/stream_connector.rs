@@ -4,10 +4,13 @@ use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapperReal;
 use crate::sub_lib::tokio_wrappers::WriteHalfWrapper;
 use crate::sub_lib::tokio_wrappers::WriteHalfWrapperReal;
+use futures::sync::oneshot;
 use std::io::ErrorKind;
 use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::net::TcpStream as StdTcpStream;
+use std::sync::mpsc;
+use std::thread;
 use std::time::Duration;
 use tokio::io;
 use tokio::io::AsyncRead;
@@ -17,6 +20,8 @@ use tokio::reactor::Handle;
 use tokio::timer::Timeout;
 
 pub const CONNECT_TIMEOUT_MS: u64 = 5000;
+pub const CONNECT_ATTEMPT_TIMEOUT_MS: u64 = 2000;
+pub const CONNECTION_ATTEMPT_DELAY_MS: u64 = 250;
 pub type ConnectionInfoFuture = Box<dyn Future<Item = ConnectionInfo, Error = io::Error> + Send>;
 
 pub struct ConnectionInfo {
@@ -35,6 +40,12 @@ pub trait StreamConnector: Send {
         target_port: u16,
         logger: &Logger,
     ) -> Result<ConnectionInfo, io::Error>;
+    fn connect_any(
+        &self,
+        ip_addrs: Vec<IpAddr>,
+        port: u16,
+        logger: &Logger,
+    ) -> ConnectionInfoFuture;
     fn split_stream(&self, stream: TcpStream, logger: &Logger) -> Option<ConnectionInfo>;
 }
 
@@ -97,36 +108,68 @@ impl StreamConnector for StreamConnectorReal {
         target_port: u16,
         logger: &Logger,
     ) -> Result<ConnectionInfo, io::Error> {
-        let mut last_error = io::Error::from(ErrorKind::Other);
-        let mut socket_addrs_tried = vec![];
-
-        for ip_addr in ip_addrs {
-            let socket_addr = SocketAddr::new(ip_addr, target_port);
-
-            match StdTcpStream::connect(&socket_addr) {
-                Ok(stream) => {
-                    debug!(logger, "Connected new stream to {}", socket_addr);
-                    let tokio_stream = TcpStream::from_std(stream, &Handle::default())
-                        .expect("Tokio could not create a TcpStream");
-                    return Ok(self.split_stream(tokio_stream, logger).unwrap_or_else(|| {
-                        panic!("Stream to {} could not be split", socket_addr)
-                    }));
-                }
-                Err(e) => {
-                    last_error = e;
-                    socket_addrs_tried.push(format!("{}", socket_addr));
-                    continue;
-                }
-            };
+        let socket_addrs = happy_eyeballs_socket_addrs(ip_addrs, target_port);
+        match race_connection_attempts(socket_addrs.clone()) {
+            Ok((socket_addr, stream)) => {
+                debug!(logger, "Connected new stream to {}", socket_addr);
+                let tokio_stream = TcpStream::from_std(stream, &Handle::default())
+                    .expect("Tokio could not create a TcpStream");
+                Ok(self
+                    .split_stream(tokio_stream, logger)
+                    .unwrap_or_else(|| panic!("Stream to {} could not be split", socket_addr)))
+            }
+            Err(e) => {
+                error!(
+                    logger,
+                    "Could not connect to any of the IP addresses supplied for {}: {:?}",
+                    target_hostname,
+                    socket_addrs
+                        .iter()
+                        .map(|socket_addr| format!("{}", socket_addr))
+                        .collect::<Vec<String>>()
+                );
+                Err(e)
+            }
         }
+    }
 
-        error!(
-            logger,
-            "Could not connect to any of the IP addresses supplied for {}: {:?}",
-            target_hostname,
-            socket_addrs_tried
-        );
-        Err(last_error)
+    fn connect_any(
+        &self,
+        ip_addrs: Vec<IpAddr>,
+        port: u16,
+        logger: &Logger,
+    ) -> ConnectionInfoFuture {
+        if ip_addrs.len() == 1 {
+            return self.connect(SocketAddr::new(ip_addrs[0], port), logger);
+        }
+        let socket_addrs = happy_eyeballs_socket_addrs(ip_addrs, port);
+        let future_logger = logger.clone();
+        let (result_tx, result_rx) = oneshot::channel();
+        let attempted_socket_addrs = socket_addrs.clone();
+        // The race blocks, so it runs on its own thread and reports back to the reactor.
+        thread::spawn(move || {
+            let _ = result_tx.send(race_connection_attempts(attempted_socket_addrs));
+        });
+        Box::new(
+            result_rx
+                .map_err(|_| io::Error::from(ErrorKind::Interrupted))
+                .and_then(move |result| match result {
+                    Ok((socket_addr, stream)) => {
+                        debug!(future_logger, "Connected new stream to {}", socket_addr);
+                        let tokio_stream = TcpStream::from_std(stream, &Handle::default())?;
+                        StreamConnectorReal {}
+                            .split_stream(tokio_stream, &future_logger)
+                            .ok_or_else(|| io::Error::from(ErrorKind::NotConnected))
+                    }
+                    Err(e) => {
+                        error!(
+                            future_logger,
+                            "Could not connect TCP stream to any of {:?}", socket_addrs
+                        );
+                        Err(e)
+                    }
+                }),
+        )
     }
 
     fn split_stream(&self, stream: TcpStream, logger: &Logger) -> Option<ConnectionInfo> {
@@ -150,6 +193,80 @@ impl StreamConnector for StreamConnectorReal {
     }
 }
 
+type ConnectionAttempt = (SocketAddr, io::Result<StdTcpStream>);
+
+// Attempts race each other in the manner of RFC 8305: a new one starts whenever the last one
+// fails or has been outstanding for CONNECTION_ATTEMPT_DELAY_MS, and the first to connect wins.
+// Streams connected by losing attempts are dropped.
+fn race_connection_attempts(
+    socket_addrs: Vec<SocketAddr>,
+) -> Result<(SocketAddr, StdTcpStream), io::Error> {
+    let (result_tx, result_rx) = mpsc::channel();
+    let mut pending = socket_addrs.into_iter();
+    let mut in_flight = 0;
+    let mut last_error = io::Error::from(ErrorKind::Other);
+    loop {
+        if let Some(socket_addr) = pending.next() {
+            start_connection_attempt(socket_addr, result_tx.clone());
+            in_flight += 1;
+        } else if in_flight == 0 {
+            return Err(last_error);
+        }
+        let (socket_addr, result) = if pending.len() > 0 {
+            match result_rx.recv_timeout(Duration::from_millis(CONNECTION_ATTEMPT_DELAY_MS)) {
+                Ok(attempt) => attempt,
+                Err(_) => continue,
+            }
+        } else {
+            result_rx
+                .recv()
+                .expect("Connection attempts dropped their channel")
+        };
+        in_flight -= 1;
+        match result {
+            Ok(stream) => return Ok((socket_addr, stream)),
+            Err(e) => last_error = e,
+        }
+    }
+}
+
+fn start_connection_attempt(socket_addr: SocketAddr, result_tx: mpsc::Sender<ConnectionAttempt>) {
+    thread::spawn(move || {
+        let result = StdTcpStream::connect_timeout(
+            &socket_addr,
+            Duration::from_millis(CONNECT_ATTEMPT_TIMEOUT_MS),
+        );
+        // If another attempt has already won, nobody is listening, and the stream is dropped.
+        let _ = result_tx.send((socket_addr, result));
+    });
+}
+
+fn happy_eyeballs_socket_addrs(ip_addrs: Vec<IpAddr>, port: u16) -> Vec<SocketAddr> {
+    happy_eyeballs_order(ip_addrs)
+        .into_iter()
+        .map(|ip_addr| SocketAddr::new(ip_addr, port))
+        .collect()
+}
+
+// Interleaves address families, IPv6 first, in the manner of RFC 8305: if one family is broken
+// on this host, the next attempt uses the other one instead of another address of the same family.
+pub fn happy_eyeballs_order(ip_addrs: Vec<IpAddr>) -> Vec<IpAddr> {
+    let (mut ipv6s, mut ipv4s): (Vec<IpAddr>, Vec<IpAddr>) =
+        ip_addrs.into_iter().partition(|ip_addr| ip_addr.is_ipv6());
+    let mut ordered = Vec::with_capacity(ipv6s.len() + ipv4s.len());
+    ipv6s.reverse();
+    ipv4s.reverse();
+    loop {
+        match (ipv6s.pop(), ipv4s.pop()) {
+            (None, None) => return ordered,
+            (ipv6_opt, ipv4_opt) => {
+                ordered.extend(ipv6_opt);
+                ordered.extend(ipv4_opt);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,6 +283,7 @@ mod tests {
     use std::sync::Mutex;
     use std::thread;
     use std::time::Duration;
+    use std::time::Instant;
     use tokio;
     use tokio::io::ErrorKind;
 
@@ -287,6 +405,151 @@ mod tests {
         assert_eq!(server.count_connections(Duration::from_millis(200)), 1);
     }
 
+    #[test]
+    fn stream_connector_does_not_wait_for_a_slow_attempt_before_starting_the_next() {
+        let logger = Logger::new("test");
+        let server = LittleTcpServer::start();
+        let socket_addr = server.socket_addr();
+        // Unroutable: connecting to it hangs until the attempt times out, or fails at once
+        let black_hole_ip = IpAddr::from_str("10.255.255.1").unwrap();
+        let subject = StreamConnectorReal {};
+        let ip_addrs = vec![black_hole_ip, socket_addr.ip()];
+
+        let (tx, rx) = mpsc::channel();
+        let test_future = lazy(move || {
+            let started = Instant::now();
+            let connection_result = subject.connect_one(
+                ip_addrs,
+                &"some hostname".to_string(),
+                socket_addr.port(),
+                &logger,
+            );
+            tx.send((connection_result, started.elapsed())).unwrap();
+            Ok(())
+        });
+
+        thread::spawn(move || {
+            tokio::run(test_future);
+        });
+
+        let (connection_result, elapsed) = rx.recv().unwrap();
+
+        assert_eq!(connection_result.unwrap().peer_addr, socket_addr);
+        assert!(
+            elapsed < Duration::from_millis(CONNECT_ATTEMPT_TIMEOUT_MS),
+            "Took {:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn connect_any_races_the_addresses_it_is_given_and_uses_the_winner() {
+        let logger = Logger::new("test");
+        let server = LittleTcpServer::start();
+        let socket_addr = server.socket_addr();
+        // Nothing listens there, so that attempt loses
+        let refusing_ip = IpAddr::from_str("127.0.0.2").unwrap();
+        let subject = StreamConnectorReal {};
+
+        let future = subject.connect_any(
+            vec![refusing_ip, socket_addr.ip()],
+            socket_addr.port(),
+            &logger,
+        );
+
+        FutureAsserter::new(future).assert(move |result| {
+            let connection_info = result.unwrap();
+            assert_eq!(connection_info.peer_addr, socket_addr);
+            assert_eq!(connection_info.local_addr.ip(), socket_addr.ip());
+            success()
+        });
+    }
+
+    #[test]
+    fn connect_any_fails_when_none_of_the_addresses_will_connect() {
+        init_test_logging();
+        let logger = Logger::new("test");
+        let dead_port = find_free_port();
+        let subject = StreamConnectorReal {};
+
+        let future = subject.connect_any(
+            vec![localhost(), IpAddr::from_str("127.0.0.2").unwrap()],
+            dead_port,
+            &logger,
+        );
+
+        FutureAsserter::new(future).assert(move |result| {
+            assert_eq!(result.err().unwrap().kind(), ErrorKind::ConnectionRefused);
+            success()
+        });
+        TestLogHandler::new().exists_log_containing(&format!(
+            "ERROR: test: Could not connect TCP stream to any of [127.0.0.1:{}, 127.0.0.2:{}]",
+            dead_port, dead_port
+        ));
+    }
+
+    #[test]
+    fn connect_any_with_a_single_address_just_connects_to_it() {
+        init_test_logging();
+        let logger = Logger::new("test");
+        let dead_port = find_free_port();
+        let subject = StreamConnectorReal {};
+
+        let future = subject.connect_any(vec![localhost()], dead_port, &logger);
+
+        FutureAsserter::new(future).assert(move |result| {
+            assert_eq!(result.err().unwrap().kind(), ErrorKind::ConnectionRefused);
+            success()
+        });
+        TestLogHandler::new().exists_log_containing(&format!(
+            "ERROR: test: Could not connect TCP stream to 127.0.0.1:{}",
+            dead_port
+        ));
+    }
+
+    #[test]
+    fn constants_have_correct_values() {
+        assert_eq!(CONNECT_TIMEOUT_MS, 5000);
+        assert_eq!(CONNECT_ATTEMPT_TIMEOUT_MS, 2000);
+        assert_eq!(CONNECTION_ATTEMPT_DELAY_MS, 250);
+    }
+
+    #[test]
+    fn happy_eyeballs_order_interleaves_address_families_starting_with_ipv6() {
+        let ip_addrs = vec![
+            IpAddr::from_str("1.1.1.1").unwrap(),
+            IpAddr::from_str("2.2.2.2").unwrap(),
+            IpAddr::from_str("3.3.3.3").unwrap(),
+            IpAddr::from_str("::1").unwrap(),
+            IpAddr::from_str("::2").unwrap(),
+        ];
+
+        let result = happy_eyeballs_order(ip_addrs);
+
+        assert_eq!(
+            result,
+            vec![
+                IpAddr::from_str("::1").unwrap(),
+                IpAddr::from_str("1.1.1.1").unwrap(),
+                IpAddr::from_str("::2").unwrap(),
+                IpAddr::from_str("2.2.2.2").unwrap(),
+                IpAddr::from_str("3.3.3.3").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn happy_eyeballs_order_leaves_single_family_lists_alone() {
+        let ip_addrs = vec![
+            IpAddr::from_str("3.3.3.3").unwrap(),
+            IpAddr::from_str("1.1.1.1").unwrap(),
+        ];
+
+        let result = happy_eyeballs_order(ip_addrs.clone());
+
+        assert_eq!(result, ip_addrs);
+    }
+
     #[test]
     fn stream_connector_returns_err_when_it_cannot_connect_to_any_of_the_provided_ip_addrs() {
         init_test_logging();
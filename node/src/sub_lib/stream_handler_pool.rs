@@ -1,7 +1,9 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 use crate::sub_lib::dispatcher::Endpoint;
 use crate::sub_lib::neighborhood::NodeQueryResponseMetadata;
+use crate::sub_lib::stream_buffer::Pressure;
 use actix::Message;
+use std::net::SocketAddr;
 
 #[derive(PartialEq, Debug, Message, Clone)]
 pub struct TransmitDataMsg {
@@ -11,6 +13,14 @@ pub struct TransmitDataMsg {
     pub data: Vec<u8>,
 }
 
+// Tells the StreamHandlerPool to stop or start reading from a non-clandestine stream, because the
+// far end of it can't keep up.
+#[derive(PartialEq, Debug, Message, Clone, Copy)]
+pub struct ReadPressureMsg {
+    pub peer_addr: SocketAddr,
+    pub pressure: Pressure,
+}
+
 #[derive(Message, Clone)]
 pub struct DispatcherNodeQueryResponse {
     pub result: Option<NodeQueryResponseMetadata>,
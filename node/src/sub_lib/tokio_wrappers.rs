@@ -1,9 +1,11 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
+use net2::TcpBuilder;
 use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::marker::Send;
 use std::net::SocketAddr;
+use std::net::TcpListener as StdTcpListener;
 use tokio::io::ReadHalf;
 use tokio::io::WriteHalf;
 use tokio::net::TcpListener;
@@ -11,6 +13,7 @@ use tokio::net::TcpStream;
 use tokio::prelude::Async;
 use tokio::prelude::AsyncRead;
 use tokio::prelude::AsyncWrite;
+use tokio::reactor::Handle;
 
 pub trait TokioListenerWrapper: Send {
     fn bind(&mut self, addr: SocketAddr) -> io::Result<()>;
@@ -42,13 +45,12 @@ pub struct TokioListenerWrapperFactoryReal {}
 
 impl TokioListenerWrapper for TokioListenerWrapperReal {
     fn bind(&mut self, addr: SocketAddr) -> io::Result<()> {
-        match TcpListener::bind(&addr) {
-            Ok(tcp_listener) => {
-                self.delegate = Some(tcp_listener);
-                Ok(())
-            }
-            Err(e) => Err(e),
-        }
+        let tcp_listener = match addr {
+            SocketAddr::V4(_) => TcpListener::bind(&addr)?,
+            SocketAddr::V6(_) => TcpListener::from_std(bind_dual_stack(addr)?, &Handle::default())?,
+        };
+        self.delegate = Some(tcp_listener);
+        Ok(())
     }
 
     fn poll_accept(&mut self) -> Result<Async<(TcpStream, SocketAddr)>, io::Error> {
@@ -110,6 +112,17 @@ impl TokioListenerWrapperFactory for TokioListenerWrapperFactoryReal {
     }
 }
 
+// Whether an IPv6 socket also accepts IPv4 connections by default varies by platform and
+// configuration (Windows and some BSDs say no), so turn IPV6_V6ONLY off explicitly.
+fn bind_dual_stack(addr: SocketAddr) -> io::Result<StdTcpListener> {
+    let builder = TcpBuilder::new_v6()?;
+    builder.only_v6(false)?;
+    #[cfg(unix)]
+    builder.reuse_address(true)?;
+    builder.bind(addr)?;
+    builder.listen(1024)
+}
+
 impl TokioListenerWrapperReal {
     pub fn new() -> Self {
         Self::default()
@@ -124,7 +137,22 @@ impl TokioListenerWrapperReal {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use masq_lib::utils::find_free_port;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpStream as StdTcpStream};
 
     #[test]
-    fn nothing() {}
+    fn ipv6_wildcard_listener_accepts_ipv4_connections() {
+        let port = find_free_port();
+        let mut subject = TokioListenerWrapperReal::new();
+        let bind_result = subject.bind(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port));
+        if bind_result.is_err() {
+            eprintln!("No IPv6 on this host; skipping test");
+            return;
+        }
+
+        let result = StdTcpStream::connect(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+
+        assert!(result.is_ok(), "{:?}", result);
+    }
 }
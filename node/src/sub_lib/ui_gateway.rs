@@ -8,6 +8,7 @@ use std::fmt::{Debug, Formatter};
 #[derive(Clone, Debug)]
 pub struct UiGatewayConfig {
     pub ui_port: u16,
+    pub ui_token_opt: Option<String>,
     pub node_descriptor: String, // TODO: This really shouldn't be here; it exists only to answer
                                  // the GetNodeDescriptor message, which A) is part of MASQNode-UI,
                                  // and B) shouldn't be answered by the UiGateway anyway. Move it
@@ -1,6 +1,7 @@
 use crate::sub_lib::channel_wrappers::FuturesChannelFactory;
 use crate::sub_lib::channel_wrappers::ReceiverWrapper;
 use crate::sub_lib::channel_wrappers::SenderWrapper;
+use crate::sub_lib::stream_buffer::StreamBuffer;
 use futures::sync::mpsc::SendError;
 use std::cell::RefCell;
 use std::fmt::Debug;
@@ -35,6 +36,7 @@ impl<T: 'static + Clone + Debug + Send> FuturesChannelFactory<T> for FuturesChan
 #[derive(Default)]
 pub struct ReceiverWrapperMock<T> {
     pub poll_results: Vec<Result<Async<Option<T>>, ()>>,
+    pub buffer: StreamBuffer,
 }
 
 impl<T: Send> ReceiverWrapper<T> for ReceiverWrapperMock<T> {
@@ -45,12 +47,17 @@ impl<T: Send> ReceiverWrapper<T> for ReceiverWrapperMock<T> {
             panic!("ReceiverWrapper tried to remove from pull_results but there were none");
         }
     }
+
+    fn buffer(&self) -> &StreamBuffer {
+        &self.buffer
+    }
 }
 
 impl<T> ReceiverWrapperMock<T> {
     pub fn new() -> Self {
         Self {
             poll_results: vec![],
+            buffer: StreamBuffer::new(),
         }
     }
 }
@@ -60,6 +67,7 @@ pub struct SenderWrapperMock<T> {
     peer_addr_result: SocketAddr,
     unbounded_send_params: Arc<Mutex<Vec<T>>>,
     unbounded_send_results: RefCell<Vec<Result<(), SendError<T>>>>,
+    buffer: StreamBuffer,
 }
 
 impl<T: 'static + Clone + Debug + Send> SenderWrapper<T> for SenderWrapperMock<T> {
@@ -76,11 +84,16 @@ impl<T: 'static + Clone + Debug + Send> SenderWrapper<T> for SenderWrapperMock<T
         self.peer_addr_result
     }
 
+    fn buffer(&self) -> &StreamBuffer {
+        &self.buffer
+    }
+
     fn clone(&self) -> Box<dyn SenderWrapper<T>> {
         Box::new(SenderWrapperMock {
             peer_addr_result: self.peer_addr_result,
             unbounded_send_params: self.unbounded_send_params.clone(),
             unbounded_send_results: self.unbounded_send_results.clone(),
+            buffer: self.buffer.clone(),
         })
     }
 }
@@ -91,9 +104,15 @@ impl<T> SenderWrapperMock<T> {
             peer_addr_result: peer_addr,
             unbounded_send_params: Arc::new(Mutex::new(vec![])),
             unbounded_send_results: RefCell::new(vec![]),
+            buffer: StreamBuffer::new(),
         }
     }
 
+    pub fn buffer_result(mut self, buffer: &StreamBuffer) -> SenderWrapperMock<T> {
+        self.buffer = buffer.clone();
+        self
+    }
+
     pub fn unbounded_send_params(mut self, params: &Arc<Mutex<Vec<T>>>) -> SenderWrapperMock<T> {
         self.unbounded_send_params = params.clone();
         self
@@ -1,6 +1,7 @@
 // Copyright (c) 2019-2020, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
 
 use crate::db_config::persistent_configuration::{PersistentConfigError, PersistentConfiguration};
+use crate::neighborhood::neighborhood_snapshot::NeighborhoodSnapshot;
 use crate::sub_lib::cryptde::PlainData;
 use crate::sub_lib::neighborhood::NodeDescriptor;
 use crate::sub_lib::wallet::Wallet;
@@ -39,6 +40,10 @@ pub struct PersistentConfigurationMock {
         RefCell<Vec<Result<Option<Vec<NodeDescriptor>>, PersistentConfigError>>>,
     set_past_neighbors_params: Arc<Mutex<Vec<(Option<Vec<NodeDescriptor>>, String)>>>,
     set_past_neighbors_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
+    neighborhood_snapshot_results:
+        RefCell<Vec<Result<Option<NeighborhoodSnapshot>, PersistentConfigError>>>,
+    set_neighborhood_snapshot_params: Arc<Mutex<Vec<Option<NeighborhoodSnapshot>>>>,
+    set_neighborhood_snapshot_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
     start_block_results: RefCell<Vec<Result<Option<u64>, PersistentConfigError>>>,
     set_start_block_params: Arc<Mutex<Vec<u64>>>,
     set_start_block_results: RefCell<Vec<Result<(), PersistentConfigError>>>,
@@ -154,6 +159,27 @@ impl PersistentConfiguration for PersistentConfigurationMock {
         self.set_past_neighbors_results.borrow_mut().remove(0)
     }
 
+    fn neighborhood_snapshot(&self) -> Result<Option<NeighborhoodSnapshot>, PersistentConfigError> {
+        if self.neighborhood_snapshot_results.borrow().is_empty() {
+            return Ok(None);
+        }
+        Self::result_from(&self.neighborhood_snapshot_results)
+    }
+
+    fn set_neighborhood_snapshot(
+        &mut self,
+        snapshot_opt: Option<NeighborhoodSnapshot>,
+    ) -> Result<(), PersistentConfigError> {
+        self.set_neighborhood_snapshot_params
+            .lock()
+            .unwrap()
+            .push(snapshot_opt);
+        if self.set_neighborhood_snapshot_results.borrow().is_empty() {
+            return Ok(());
+        }
+        Self::result_from(&self.set_neighborhood_snapshot_results)
+    }
+
     fn start_block(&self) -> Result<Option<u64>, PersistentConfigError> {
         if self.start_block_results.borrow().is_empty() {
             return Ok(Some(0));
@@ -343,6 +369,32 @@ impl PersistentConfigurationMock {
         self
     }
 
+    pub fn neighborhood_snapshot_result(
+        self,
+        result: Result<Option<NeighborhoodSnapshot>, PersistentConfigError>,
+    ) -> PersistentConfigurationMock {
+        self.neighborhood_snapshot_results.borrow_mut().push(result);
+        self
+    }
+
+    pub fn set_neighborhood_snapshot_params(
+        mut self,
+        params: &Arc<Mutex<Vec<Option<NeighborhoodSnapshot>>>>,
+    ) -> PersistentConfigurationMock {
+        self.set_neighborhood_snapshot_params = params.clone();
+        self
+    }
+
+    pub fn set_neighborhood_snapshot_result(
+        self,
+        result: Result<(), PersistentConfigError>,
+    ) -> PersistentConfigurationMock {
+        self.set_neighborhood_snapshot_results
+            .borrow_mut()
+            .push(result);
+        self
+    }
+
     pub fn earning_wallet_from_address_result(
         self,
         result: Result<Option<Wallet>, PersistentConfigError>,
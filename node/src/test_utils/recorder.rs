@@ -6,7 +6,9 @@ use crate::blockchain::blockchain_interface::{BlockchainError, BlockchainResult,
 use crate::daemon::crash_notification::CrashNotification;
 use crate::daemon::DaemonBindMessage;
 use crate::neighborhood::gossip::Gossip_0v1;
-use crate::stream_messages::{AddStreamMsg, PoolBindMessage, RemoveStreamMsg};
+use crate::stream_messages::{
+    AddStreamMsg, MasqueradeDetectedMsg, PoolBindMessage, RemoveStreamMsg,
+};
 use crate::sub_lib::accountant::ReportExitServiceConsumedMessage;
 use crate::sub_lib::accountant::ReportExitServiceProvidedMessage;
 use crate::sub_lib::accountant::ReportRoutingServiceConsumedMessage;
@@ -16,11 +18,15 @@ use crate::sub_lib::blockchain_bridge::{BlockchainBridgeSubs, SetDbPasswordMsg};
 use crate::sub_lib::blockchain_bridge::{ReportAccountsPayable, SetGasPriceMsg};
 use crate::sub_lib::configurator::{ConfiguratorSubs, NewPasswordMessage};
 use crate::sub_lib::dispatcher::InboundClientData;
-use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg};
+use crate::sub_lib::dispatcher::{DispatcherSubs, StreamShutdownMsg, WritePressureMsg};
 use crate::sub_lib::hopper::IncipientCoresPackage;
-use crate::sub_lib::hopper::{ExpiredCoresPackage, NoLookupIncipientCoresPackage};
+use crate::sub_lib::hopper::{
+    ExpiredCoresPackage, NoLookupIncipientCoresPackage, SessionKeyPeersMsg,
+};
 use crate::sub_lib::hopper::{HopperSubs, MessageType};
 use crate::sub_lib::neighborhood::NeighborhoodDotGraphRequest;
+use crate::sub_lib::neighborhood::NeighborhoodStatus;
+use crate::sub_lib::neighborhood::NeighborhoodStatusQueryMessage;
 use crate::sub_lib::neighborhood::NeighborhoodSubs;
 use crate::sub_lib::neighborhood::NodeQueryMessage;
 use crate::sub_lib::neighborhood::NodeQueryResponseMetadata;
@@ -32,13 +38,14 @@ use crate::sub_lib::neighborhood::{DispatcherNodeQueryMessage, GossipFailure_0v1
 use crate::sub_lib::peer_actors::PeerActors;
 use crate::sub_lib::peer_actors::{BindMessage, StartMessage};
 use crate::sub_lib::proxy_client::{ClientResponsePayload_0v1, InboundServerData};
-use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, ProxyClientSubs};
+use crate::sub_lib::proxy_client::{DnsResolveFailure_0v1, ProxyClientSubs, StreamPressure_0v1};
 use crate::sub_lib::proxy_server::ProxyServerSubs;
 use crate::sub_lib::proxy_server::{
-    AddReturnRouteMessage, AddRouteMessage, ClientRequestPayload_0v1,
+    AddReturnRouteMessage, AddRouteMessage, ClientRequestPayload_0v1, RetryRouteQueryMessage,
 };
 use crate::sub_lib::set_consuming_wallet_message::SetConsumingWalletMessage;
 use crate::sub_lib::stream_handler_pool::DispatcherNodeQueryResponse;
+use crate::sub_lib::stream_handler_pool::ReadPressureMsg;
 use crate::sub_lib::stream_handler_pool::TransmitDataMsg;
 use crate::sub_lib::ui_gateway::UiGatewaySubs;
 use crate::test_utils::to_millis;
@@ -60,6 +67,7 @@ pub struct Recorder {
     recording: Arc<Mutex<Recording>>,
     node_query_responses: Vec<Option<NodeQueryResponseMetadata>>,
     route_query_responses: Vec<Option<RouteQueryResponse>>,
+    neighborhood_status_responses: Vec<NeighborhoodStatus>,
     retrieve_transactions_responses: Vec<Result<Vec<Transaction>, BlockchainError>>,
     report_accounts_payable_responses: Vec<Result<Vec<BlockchainResult<Payment>>, String>>,
 }
@@ -91,6 +99,7 @@ macro_rules! recorder_message_handler {
 
 recorder_message_handler!(AddReturnRouteMessage);
 recorder_message_handler!(AddRouteMessage);
+recorder_message_handler!(RetryRouteQueryMessage);
 recorder_message_handler!(AddStreamMsg);
 recorder_message_handler!(BindMessage);
 recorder_message_handler!(CrashNotification);
@@ -104,6 +113,7 @@ recorder_message_handler!(ExpiredCoresPackage<DnsResolveFailure_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<Gossip_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<GossipFailure_0v1>);
 recorder_message_handler!(ExpiredCoresPackage<MessageType>);
+recorder_message_handler!(ExpiredCoresPackage<StreamPressure_0v1>);
 recorder_message_handler!(GetFinancialStatisticsMessage);
 recorder_message_handler!(InboundClientData);
 recorder_message_handler!(InboundServerData);
@@ -115,20 +125,25 @@ recorder_message_handler!(NodeToUiMessage);
 recorder_message_handler!(NodeRecordMetadataMessage);
 recorder_message_handler!(NoLookupIncipientCoresPackage);
 recorder_message_handler!(PoolBindMessage);
+recorder_message_handler!(ReadPressureMsg);
 recorder_message_handler!(ReceivedPayments);
 recorder_message_handler!(RemoveNeighborMessage);
 recorder_message_handler!(RemoveStreamMsg);
+recorder_message_handler!(MasqueradeDetectedMsg);
 recorder_message_handler!(ReportExitServiceConsumedMessage);
 recorder_message_handler!(ReportExitServiceProvidedMessage);
 recorder_message_handler!(ReportRoutingServiceConsumedMessage);
 recorder_message_handler!(ReportRoutingServiceProvidedMessage);
 recorder_message_handler!(SentPayments);
+recorder_message_handler!(SessionKeyPeersMsg);
 recorder_message_handler!(SetConsumingWalletMessage);
 recorder_message_handler!(SetDbPasswordMsg);
 recorder_message_handler!(SetGasPriceMsg);
 recorder_message_handler!(StartMessage);
+recorder_message_handler!(StreamPressure_0v1);
 recorder_message_handler!(StreamShutdownMsg);
 recorder_message_handler!(TransmitDataMsg);
+recorder_message_handler!(WritePressureMsg);
 
 impl Handler<NodeQueryMessage> for Recorder {
     type Result = MessageResult<NodeQueryMessage>;
@@ -162,6 +177,22 @@ impl Handler<RouteQueryMessage> for Recorder {
     }
 }
 
+impl Handler<NeighborhoodStatusQueryMessage> for Recorder {
+    type Result = MessageResult<NeighborhoodStatusQueryMessage>;
+
+    fn handle(
+        &mut self,
+        msg: NeighborhoodStatusQueryMessage,
+        _ctx: &mut Self::Context,
+    ) -> <Self as Handler<NeighborhoodStatusQueryMessage>>::Result {
+        self.record(msg);
+        MessageResult(extract_response(
+            &mut self.neighborhood_status_responses,
+            "No NeighborhoodStatuses prepared for NeighborhoodStatusQueryMessage",
+        ))
+    }
+}
+
 impl Handler<RetrieveTransactions> for Recorder {
     type Result = MessageResult<RetrieveTransactions>;
 
@@ -240,6 +271,11 @@ impl Recorder {
         self
     }
 
+    pub fn neighborhood_status_response(mut self, response: NeighborhoodStatus) -> Recorder {
+        self.neighborhood_status_responses.push(response);
+        self
+    }
+
     pub fn retrieve_transactions_response(
         mut self,
         response: Result<Vec<Transaction>, BlockchainError>,
@@ -339,9 +375,14 @@ pub fn make_proxy_server_subs_from(addr: &Addr<Recorder>) -> ProxyServerSubs {
         dns_failure_from_hopper: addr
             .clone()
             .recipient::<ExpiredCoresPackage<DnsResolveFailure_0v1>>(),
+        stream_pressure_from_hopper: addr
+            .clone()
+            .recipient::<ExpiredCoresPackage<StreamPressure_0v1>>(),
         add_return_route: recipient!(addr, AddReturnRouteMessage),
         add_route: recipient!(addr, AddRouteMessage),
+        retry_route_query: recipient!(addr, RetryRouteQueryMessage),
         stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
+        write_pressure_sub: recipient!(addr, WritePressureMsg),
         set_consuming_wallet_sub: recipient!(addr, SetConsumingWalletMessage),
     }
 }
@@ -352,6 +393,8 @@ pub fn make_dispatcher_subs_from(addr: &Addr<Recorder>) -> DispatcherSubs {
         bind: recipient!(addr, BindMessage),
         from_dispatcher_client: recipient!(addr, TransmitDataMsg),
         stream_shutdown_sub: recipient!(addr, StreamShutdownMsg),
+        write_pressure_sub: recipient!(addr, WritePressureMsg),
+        read_pressure_sub: recipient!(addr, ReadPressureMsg),
         ui_sub: recipient!(addr, NodeFromUiMessage),
     }
 }
@@ -362,6 +405,7 @@ pub fn make_hopper_subs_from(addr: &Addr<Recorder>) -> HopperSubs {
         from_hopper_client: recipient!(addr, IncipientCoresPackage),
         from_hopper_client_no_lookup: recipient!(addr, NoLookupIncipientCoresPackage),
         from_dispatcher: recipient!(addr, InboundClientData),
+        session_key_peers: recipient!(addr, SessionKeyPeersMsg),
     }
 }
 
@@ -373,6 +417,11 @@ pub fn make_proxy_client_subs_from(addr: &Addr<Recorder>) -> ProxyClientSubs {
             .recipient::<ExpiredCoresPackage<ClientRequestPayload_0v1>>(),
         inbound_server_data: recipient!(addr, InboundServerData),
         dns_resolve_failed: recipient!(addr, DnsResolveFailure_0v1),
+        stream_pressure: recipient!(addr, StreamPressure_0v1),
+        stream_pressure_from_hopper: addr
+            .clone()
+            .recipient::<ExpiredCoresPackage<StreamPressure_0v1>>(),
+        ui_sub: recipient!(addr, NodeFromUiMessage),
     }
 }
 
@@ -382,6 +431,7 @@ pub fn make_neighborhood_subs_from(addr: &Addr<Recorder>) -> NeighborhoodSubs {
         start: recipient!(addr, StartMessage),
         node_query: recipient!(addr, NodeQueryMessage),
         route_query: recipient!(addr, RouteQueryMessage),
+        status_query: recipient!(addr, NeighborhoodStatusQueryMessage),
         update_node_record_metadata: recipient!(addr, NodeRecordMetadataMessage),
         from_hopper: addr.clone().recipient::<ExpiredCoresPackage<Gossip_0v1>>(),
         gossip_failure: addr
@@ -39,6 +39,21 @@ impl StreamConnector for StreamConnectorMock {
         self.connect_pair_results.borrow_mut().remove(0)
     }
 
+    fn connect_any(
+        &self,
+        ip_addrs: Vec<IpAddr>,
+        port: u16,
+        _logger: &Logger,
+    ) -> ConnectionInfoFuture {
+        self.connect_pair_params.lock().unwrap().extend(
+            ip_addrs
+                .into_iter()
+                .map(|ip_addr| SocketAddr::new(ip_addr, port)),
+        );
+        let connection_info_result = self.connect_pair_results.borrow_mut().remove(0);
+        Box::new(result(connection_info_result))
+    }
+
     fn split_stream(&self, _stream: TcpStream, _logger: &Logger) -> Option<ConnectionInfo> {
         self.split_stream_results.borrow_mut().remove(0)
     }
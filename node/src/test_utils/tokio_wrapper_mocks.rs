@@ -1,14 +1,17 @@
 // Copyright (c) 2017-2019, Substratum LLC (https://substratum.net) and/or its affiliates. All rights reserved.
 
 use crate::sub_lib::tokio_wrappers::ReadHalfWrapper;
+use crate::sub_lib::tokio_wrappers::TokioListenerWrapper;
 use crate::sub_lib::tokio_wrappers::WriteHalfWrapper;
 use std::io;
 use std::io::Read;
 use std::io::Write;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::sync::Mutex;
 use tokio::io::AsyncRead;
 use tokio::io::AsyncWrite;
+use tokio::net::TcpStream;
 use tokio::prelude::Async;
 
 type PollReadResult = (Vec<u8>, Result<Async<usize>, io::Error>);
@@ -134,3 +137,51 @@ impl WriteHalfWrapperMock {
         self.shutdown_result(Ok(Async::Ready(())))
     }
 }
+
+// Once its prepared poll_accept results run out, this mock reports NotReady, the way a listener
+// with nobody connecting would.
+#[derive(Default)]
+pub struct TokioListenerWrapperMock {
+    bind_params: Arc<Mutex<Vec<SocketAddr>>>,
+    bind_results: Vec<io::Result<()>>,
+    poll_accept_results: Vec<io::Result<Async<(TcpStream, SocketAddr)>>>,
+}
+
+impl TokioListenerWrapper for TokioListenerWrapperMock {
+    fn bind(&mut self, addr: SocketAddr) -> io::Result<()> {
+        self.bind_params.lock().unwrap().push(addr);
+        self.bind_results.remove(0)
+    }
+
+    fn poll_accept(&mut self) -> io::Result<Async<(TcpStream, SocketAddr)>> {
+        if self.poll_accept_results.is_empty() {
+            Ok(Async::NotReady)
+        } else {
+            self.poll_accept_results.remove(0)
+        }
+    }
+}
+
+impl TokioListenerWrapperMock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind_params(mut self, params: &Arc<Mutex<Vec<SocketAddr>>>) -> Self {
+        self.bind_params = params.clone();
+        self
+    }
+
+    pub fn bind_result(mut self, result: io::Result<()>) -> Self {
+        self.bind_results.push(result);
+        self
+    }
+
+    pub fn poll_accept_result(
+        mut self,
+        result: io::Result<Async<(TcpStream, SocketAddr)>>,
+    ) -> Self {
+        self.poll_accept_results.push(result);
+        self
+    }
+}
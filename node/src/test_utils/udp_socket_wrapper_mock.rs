@@ -0,0 +1,78 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::sub_lib::udp_socket_wrapper::UdpSocketWrapperTrait;
+use std::cmp::min;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::prelude::Async;
+
+// Once its prepared recv_from results run out, this mock reports NotReady, the way an idle socket
+// would, so that it can sit quietly under a Future that polls it in a loop.
+#[derive(Default)]
+pub struct UdpSocketWrapperMock {
+    bind_params: Arc<Mutex<Vec<SocketAddr>>>,
+    bind_results: Vec<io::Result<bool>>,
+    recv_from_results: Vec<(Vec<u8>, io::Result<Async<(usize, SocketAddr)>>)>,
+    send_to_params: Arc<Mutex<Vec<(Vec<u8>, SocketAddr)>>>,
+    send_to_results: Vec<io::Result<Async<usize>>>,
+}
+
+impl UdpSocketWrapperTrait for UdpSocketWrapperMock {
+    fn bind(&mut self, addr: SocketAddr) -> io::Result<bool> {
+        self.bind_params.lock().unwrap().push(addr);
+        self.bind_results.remove(0)
+    }
+
+    fn recv_from(&mut self, buf: &mut [u8]) -> Result<Async<(usize, SocketAddr)>, io::Error> {
+        if self.recv_from_results.is_empty() {
+            return Ok(Async::NotReady);
+        }
+        let (data, result) = self.recv_from_results.remove(0);
+        let to_copy = min(buf.len(), data.len());
+        buf[..to_copy].copy_from_slice(&data[..to_copy]);
+        result
+    }
+
+    fn send_to(&mut self, buf: &[u8], addr: SocketAddr) -> Result<Async<usize>, io::Error> {
+        self.send_to_params
+            .lock()
+            .unwrap()
+            .push((buf.to_vec(), addr));
+        self.send_to_results.remove(0)
+    }
+}
+
+impl UdpSocketWrapperMock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bind_params(mut self, params: &Arc<Mutex<Vec<SocketAddr>>>) -> Self {
+        self.bind_params = params.clone();
+        self
+    }
+
+    pub fn bind_result(mut self, result: io::Result<bool>) -> Self {
+        self.bind_results.push(result);
+        self
+    }
+
+    pub fn recv_from_result(
+        mut self,
+        data: Vec<u8>,
+        result: io::Result<Async<(usize, SocketAddr)>>,
+    ) -> Self {
+        self.recv_from_results.push((data, result));
+        self
+    }
+
+    pub fn send_to_params(mut self, params: &Arc<Mutex<Vec<(Vec<u8>, SocketAddr)>>>) -> Self {
+        self.send_to_params = params.clone();
+        self
+    }
+
+    pub fn send_to_result(mut self, result: io::Result<Async<usize>>) -> Self {
+        self.send_to_results.push(result);
+        self
+    }
+}
@@ -0,0 +1,213 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::Discriminator;
+use crate::discriminator::DiscriminatorFactory;
+use crate::masquerader::MasqueradeKind;
+use crate::sub_lib::framer::FramedChunk;
+use crate::sub_lib::framer::Framer;
+use crate::tls_masquerader::TlsMasquerader;
+use crate::tls_masquerader::TLS_APPLICATION_DATA_HEADER;
+use crate::tls_masquerader::TLS_MASQUERADE_LENGTH_PREFIX_LEN;
+use crate::tls_masquerader::TLS_RECORD_HEADER_LEN;
+use masq_lib::utils::index_of;
+
+// Anything claiming to be bigger than this is garbage, and we don't want to buffer it forever.
+const MAX_PACKAGE_LEN: usize = 0x0010_0000;
+
+// TlsFramer hands back one record at a time; this framer hands back all the records that
+// TlsMasquerader used to carry one package, using the length prefix in the first of them.
+#[derive(Default)]
+pub struct TlsMasqueradeFramer {
+    data_so_far: Vec<u8>,
+}
+
+enum Scan {
+    Complete(usize),
+    Incomplete,
+    NotOurs,
+}
+
+impl Framer for TlsMasqueradeFramer {
+    fn add_data(&mut self, data: &[u8]) {
+        self.data_so_far.extend(data);
+    }
+
+    fn take_frame(&mut self) -> Option<FramedChunk> {
+        loop {
+            match index_of(&self.data_so_far, TLS_APPLICATION_DATA_HEADER) {
+                Some(start) => self.data_so_far = self.data_so_far.split_off(start),
+                None => {
+                    let keep = TLS_APPLICATION_DATA_HEADER.len() - 1;
+                    if self.data_so_far.len() > keep {
+                        let split_point = self.data_so_far.len() - keep;
+                        self.data_so_far = self.data_so_far.split_off(split_point);
+                    }
+                    return None;
+                }
+            }
+            match Self::scan(&self.data_so_far) {
+                Scan::Complete(frame_len) => {
+                    let leftovers = self.data_so_far.split_off(frame_len);
+                    let chunk = std::mem::replace(&mut self.data_so_far, leftovers);
+                    return Some(FramedChunk {
+                        chunk,
+                        last_chunk: true,
+                    });
+                }
+                Scan::Incomplete => return None,
+                Scan::NotOurs => self.data_so_far = self.data_so_far.split_off(1),
+            }
+        }
+    }
+}
+
+impl TlsMasqueradeFramer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn scan(data: &[u8]) -> Scan {
+        let prefix_end = TLS_RECORD_HEADER_LEN + TLS_MASQUERADE_LENGTH_PREFIX_LEN;
+        if data.len() < prefix_end {
+            return Scan::Incomplete;
+        }
+        if Self::record_len(data) < TLS_MASQUERADE_LENGTH_PREFIX_LEN {
+            return Scan::NotOurs;
+        }
+        let needed = TLS_MASQUERADE_LENGTH_PREFIX_LEN
+            + TlsMasquerader::length_prefix(&data[TLS_RECORD_HEADER_LEN..]);
+        if needed > MAX_PACKAGE_LEN {
+            return Scan::NotOurs;
+        }
+        let mut carried = 0;
+        let mut offset = 0;
+        while carried < needed {
+            if offset > data.len() {
+                return Scan::Incomplete;
+            }
+            let header = &data[offset..];
+            if header.len() < TLS_RECORD_HEADER_LEN {
+                return Scan::Incomplete;
+            }
+            if !header.starts_with(TLS_APPLICATION_DATA_HEADER) {
+                return Scan::NotOurs;
+            }
+            let record_len = Self::record_len(header);
+            carried += record_len;
+            offset += TLS_RECORD_HEADER_LEN + record_len;
+        }
+        if offset > data.len() {
+            Scan::Incomplete
+        } else {
+            Scan::Complete(offset)
+        }
+    }
+
+    fn record_len(header: &[u8]) -> usize {
+        ((header[3] as usize) << 8) | (header[4] as usize)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TlsMasqueradeDiscriminatorFactory {}
+
+impl DiscriminatorFactory for TlsMasqueradeDiscriminatorFactory {
+    fn make(&self) -> Discriminator {
+        Discriminator::new(
+            Box::new(TlsMasqueradeFramer::new()),
+            vec![Box::new(TlsMasquerader::new())],
+        )
+    }
+
+    fn duplicate(&self) -> Box<dyn DiscriminatorFactory> {
+        Box::new(TlsMasqueradeDiscriminatorFactory {})
+    }
+
+    fn masquerade_opt(&self) -> Option<MasqueradeKind> {
+        Some(MasqueradeKind::Tls)
+    }
+}
+
+impl TlsMasqueradeDiscriminatorFactory {
+    pub fn new() -> TlsMasqueradeDiscriminatorFactory {
+        TlsMasqueradeDiscriminatorFactory {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discriminator::UnmaskedChunk;
+    use crate::masquerader::Masquerader;
+
+    #[test]
+    fn discriminator_factory_duplicate_works() {
+        let subject = TlsMasqueradeDiscriminatorFactory::new();
+
+        subject.duplicate();
+
+        // no panic; test passes
+    }
+
+    #[test]
+    fn framer_waits_for_every_record_of_a_multi_record_package() {
+        let data: Vec<u8> = (0..20_000).map(|n| (n % 251) as u8).collect();
+        let masked = TlsMasquerader::new().mask(&data).unwrap();
+        let mut subject = TlsMasqueradeFramer::new();
+
+        subject.add_data(&masked[..17_000]);
+        let first_result = subject.take_frame();
+        subject.add_data(&masked[17_000..]);
+        let second_result = subject.take_frame();
+
+        assert_eq!(first_result, None);
+        assert_eq!(
+            second_result,
+            Some(FramedChunk {
+                chunk: masked,
+                last_chunk: true
+            })
+        );
+    }
+
+    #[test]
+    fn framer_skips_garbage_and_separates_consecutive_packages() {
+        let first = TlsMasquerader::new().mask(b"booga").unwrap();
+        let second = TlsMasquerader::new().mask(b"agoob").unwrap();
+        let mut subject = TlsMasqueradeFramer::new();
+        subject.add_data(&[0x17, 0x03, 0x03, 0x00, 0x01, 0xFF]);
+        subject.add_data(&first);
+        subject.add_data(&second);
+
+        let first_result = subject.take_frame();
+        let second_result = subject.take_frame();
+        let third_result = subject.take_frame();
+
+        assert_eq!(first_result.unwrap().chunk, first);
+        assert_eq!(second_result.unwrap().chunk, second);
+        assert_eq!(third_result, None);
+    }
+
+    #[test]
+    fn factory_makes_discriminator_that_ignores_json_data() {
+        let subject = TlsMasqueradeDiscriminatorFactory::new();
+        let mut discriminator = subject.make();
+
+        discriminator.add_data(b"{\"component\": \"NBHD\", \"bodyText\": \"Booga\"}");
+        let result = discriminator.take_chunk();
+
+        assert_eq!(result, None)
+    }
+
+    #[test]
+    fn factory_makes_discriminator_that_unmasks_tls_masquerade() {
+        let data = &b"I am contained in TLS!"[..];
+        let masked = TlsMasquerader::new().mask(data).unwrap();
+        let subject = TlsMasqueradeDiscriminatorFactory::new();
+        let mut discriminator = subject.make();
+
+        discriminator.add_data(&masked[..]);
+        let result = discriminator.take_chunk();
+
+        assert_eq!(result, Some(UnmaskedChunk::new(data.to_vec(), true, false)))
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+use crate::discriminator::UnmaskedChunk;
+use crate::masquerader::MasqueradeError;
+use crate::masquerader::Masquerader;
+
+pub const TLS_APPLICATION_DATA_HEADER: &[u8] = &[0x17, 0x03, 0x03];
+pub const TLS_RECORD_HEADER_LEN: usize = 5;
+pub const TLS_MAX_RECORD_PAYLOAD: usize = 0x4000;
+pub const TLS_MASQUERADE_LENGTH_PREFIX_LEN: usize = 4;
+
+// Dresses CORES packages up as TLS 1.2 application-data records. A package can be bigger than
+// one record may legally carry, so the first record's payload starts with the length of the
+// whole package, and as many records follow as it takes to carry it.
+#[derive(Default)]
+pub struct TlsMasquerader {}
+
+impl Masquerader for TlsMasquerader {
+    fn try_unmask(&self, item: &[u8]) -> Result<UnmaskedChunk, MasqueradeError> {
+        if !item.starts_with(TLS_APPLICATION_DATA_HEADER) {
+            return Err(MasqueradeError::NotThisMasquerader);
+        }
+        let payload = Self::concatenate_payloads(item)?;
+        if payload.len() < TLS_MASQUERADE_LENGTH_PREFIX_LEN {
+            return Err(MasqueradeError::MidLevelDataError(
+                "TLS records too short to hold a length prefix".to_string(),
+            ));
+        }
+        let declared_length = Self::length_prefix(&payload);
+        let data = &payload[TLS_MASQUERADE_LENGTH_PREFIX_LEN..];
+        if data.len() != declared_length {
+            return Err(MasqueradeError::MidLevelDataError(format!(
+                "Length prefix is {}, but TLS records carry {} bytes",
+                declared_length,
+                data.len()
+            )));
+        }
+        Ok(UnmaskedChunk::new(data.to_vec(), true, false))
+    }
+
+    fn mask(&self, data: &[u8]) -> Result<Vec<u8>, MasqueradeError> {
+        let mut payload = (data.len() as u32).to_be_bytes().to_vec();
+        payload.extend(data);
+        let mut masked = Vec::with_capacity(
+            payload.len() + TLS_RECORD_HEADER_LEN * (payload.len() / TLS_MAX_RECORD_PAYLOAD + 1),
+        );
+        payload.chunks(TLS_MAX_RECORD_PAYLOAD).for_each(|record| {
+            masked.extend(TLS_APPLICATION_DATA_HEADER);
+            masked.extend(&(record.len() as u16).to_be_bytes());
+            masked.extend(record);
+        });
+        Ok(masked)
+    }
+}
+
+impl TlsMasquerader {
+    pub fn new() -> TlsMasquerader {
+        TlsMasquerader {}
+    }
+
+    pub fn length_prefix(payload: &[u8]) -> usize {
+        let mut bytes = [0u8; TLS_MASQUERADE_LENGTH_PREFIX_LEN];
+        bytes.copy_from_slice(&payload[..TLS_MASQUERADE_LENGTH_PREFIX_LEN]);
+        u32::from_be_bytes(bytes) as usize
+    }
+
+    fn concatenate_payloads(item: &[u8]) -> Result<Vec<u8>, MasqueradeError> {
+        let mut payload = vec![];
+        let mut offset = 0;
+        while offset < item.len() {
+            let header = &item[offset..];
+            if header.len() < TLS_RECORD_HEADER_LEN
+                || !header.starts_with(TLS_APPLICATION_DATA_HEADER)
+            {
+                return Err(MasqueradeError::MidLevelDataError(format!(
+                    "Malformed TLS record header at offset {}",
+                    offset
+                )));
+            }
+            let record_len = ((header[3] as usize) << 8) | (header[4] as usize);
+            let record_end = offset + TLS_RECORD_HEADER_LEN + record_len;
+            if record_end > item.len() {
+                return Err(MasqueradeError::MidLevelDataError(format!(
+                    "TLS record at offset {} is truncated",
+                    offset
+                )));
+            }
+            payload.extend(&item[(offset + TLS_RECORD_HEADER_LEN)..record_end]);
+            offset = record_end;
+        }
+        Ok(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_data_is_masked_into_a_single_record() {
+        let subject = TlsMasquerader::new();
+
+        let result = subject.mask(b"booga").unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                0x17, 0x03, 0x03, 0x00, 0x09, 0x00, 0x00, 0x00, 0x05, b'b', b'o', b'o', b'g', b'a'
+            ]
+        );
+    }
+
+    #[test]
+    fn large_data_is_split_across_records_and_unmasked_whole() {
+        let data: Vec<u8> = (0..40_000).map(|n| (n % 251) as u8).collect();
+        let subject = TlsMasquerader::new();
+
+        let masked = subject.mask(&data).unwrap();
+
+        assert_eq!(&masked[0..5], &[0x17, 0x03, 0x03, 0x40, 0x00]);
+        let second_record = TLS_RECORD_HEADER_LEN + TLS_MAX_RECORD_PAYLOAD;
+        assert_eq!(
+            &masked[second_record..(second_record + 5)],
+            &[0x17, 0x03, 0x03, 0x40, 0x00]
+        );
+        assert_eq!(
+            masked.len(),
+            data.len() + TLS_MASQUERADE_LENGTH_PREFIX_LEN + 3 * TLS_RECORD_HEADER_LEN
+        );
+        let result = subject.try_unmask(&masked);
+        assert_eq!(result, Ok(UnmaskedChunk::new(data, true, false)));
+    }
+
+    #[test]
+    fn unmask_rejects_other_tls_content_types() {
+        let subject = TlsMasquerader::new();
+
+        let result = subject.try_unmask(&[0x16, 0x03, 0x03, 0x00, 0x01, 0xCA]);
+
+        assert_eq!(result, Err(MasqueradeError::NotThisMasquerader));
+    }
+
+    #[test]
+    fn unmask_complains_about_truncated_record() {
+        let subject = TlsMasquerader::new();
+
+        let result = subject.try_unmask(&[0x17, 0x03, 0x03, 0x00, 0x09, 0x00, 0x00]);
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "TLS record at offset 0 is truncated".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unmask_complains_about_wrong_length_prefix() {
+        let subject = TlsMasquerader::new();
+
+        let result =
+            subject.try_unmask(&[0x17, 0x03, 0x03, 0x00, 0x05, 0x00, 0x00, 0x00, 0x02, b'b']);
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "Length prefix is 2, but TLS records carry 1 bytes".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn unmask_complains_about_missing_length_prefix() {
+        let subject = TlsMasquerader::new();
+
+        let result = subject.try_unmask(&[0x17, 0x03, 0x03, 0x00, 0x02, 0x00, 0x00]);
+
+        assert_eq!(
+            result,
+            Err(MasqueradeError::MidLevelDataError(
+                "TLS records too short to hold a length prefix".to_string()
+            ))
+        );
+    }
+}
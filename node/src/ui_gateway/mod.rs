@@ -24,6 +24,7 @@ pub const CRASH_KEY: &str = "UIGATEWAY";
 
 pub struct UiGateway {
     port: u16,
+    ui_token_opt: Option<String>,
     websocket_supervisor: Option<Box<dyn WebSocketSupervisor>>,
     incoming_message_recipients: Vec<Recipient<NodeFromUiMessage>>,
     logger: Logger,
@@ -33,6 +34,7 @@ impl UiGateway {
     pub fn new(config: &UiGatewayConfig) -> UiGateway {
         UiGateway {
             port: config.ui_port,
+            ui_token_opt: config.ui_token_opt.clone(),
             websocket_supervisor: None,
             incoming_message_recipients: vec![],
             logger: Logger::new("UiGateway"),
@@ -62,10 +64,12 @@ impl Handler<BindMessage> for UiGateway {
             msg.peer_actors.blockchain_bridge.ui_sub.clone(),
             msg.peer_actors.dispatcher.ui_sub.clone(),
             msg.peer_actors.configurator.node_from_ui_sub.clone(),
+            msg.peer_actors.proxy_client.ui_sub.clone(),
         ];
         self.websocket_supervisor = match WebSocketSupervisorReal::new(
             self.port,
             msg.peer_actors.ui_gateway.node_from_ui_message_sub,
+            self.ui_token_opt.clone(),
         ) {
             Ok(wss) => Some(Box::new(wss)),
             Err(e) => panic!("Couldn't start WebSocketSupervisor: {:?}", e),
@@ -80,11 +84,14 @@ impl Handler<DaemonBindMessage> for UiGateway {
     fn handle(&mut self, msg: DaemonBindMessage, ctx: &mut Self::Context) -> Self::Result {
         ctx.set_mailbox_capacity(NODE_MAILBOX_CAPACITY);
         self.incoming_message_recipients = msg.from_ui_message_recipients;
-        self.websocket_supervisor =
-            match WebSocketSupervisorReal::new(self.port, msg.from_ui_message_recipient) {
-                Ok(wss) => Some(Box::new(wss)),
-                Err(e) => panic!("Couldn't start WebSocketSupervisor: {:?}", e),
-            };
+        self.websocket_supervisor = match WebSocketSupervisorReal::new(
+            self.port,
+            msg.from_ui_message_recipient,
+            self.ui_token_opt.clone(),
+        ) {
+            Ok(wss) => Some(Box::new(wss)),
+            Err(e) => panic!("Couldn't start WebSocketSupervisor: {:?}", e),
+        };
         debug!(self.logger, "UIGateway bound");
     }
 }
@@ -135,13 +142,14 @@ mod tests {
         let (blockchain, _, blockchain_recording_arc) = make_recorder();
         let (dispatcher, _, dispatcher_recording_arc) = make_recorder();
         let (configurator, _, configurator_recording_arc) = make_recorder();
+        let (proxy_client, _, proxy_client_recording_arc) = make_recorder();
         // These actors should not receive NodeFromUiMessages
         let (ui_gateway, _, ui_gateway_recording_arc) = make_recorder();
-        let (proxy_client, _, proxy_client_recording_arc) = make_recorder();
         let (proxy_server, _, proxy_server_recording_arc) = make_recorder();
         let (hopper, _, hopper_recording_arc) = make_recorder();
         let subject = UiGateway::new(&UiGatewayConfig {
             ui_port: find_free_port(),
+            ui_token_opt: None,
             node_descriptor: String::from(""),
         });
         let system = System::new("test");
@@ -184,8 +192,8 @@ mod tests {
         did_receive(blockchain_recording_arc);
         did_receive(dispatcher_recording_arc);
         did_receive(configurator_recording_arc);
+        did_receive(proxy_client_recording_arc);
         did_not_receive(ui_gateway_recording_arc);
-        did_not_receive(proxy_client_recording_arc);
         did_not_receive(proxy_server_recording_arc);
         did_not_receive(hopper_recording_arc);
     }
@@ -198,6 +206,7 @@ mod tests {
             WebSocketSupervisorMock::new().send_msg_parameters(&send_msg_parameters_arc);
         let mut subject = UiGateway::new(&UiGatewayConfig {
             ui_port: find_free_port(),
+            ui_token_opt: None,
             node_descriptor: String::from(""),
         });
         let system = System::new("test");
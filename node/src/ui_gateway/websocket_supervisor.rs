@@ -10,7 +10,11 @@ use futures::Future;
 use futures::Sink;
 use futures::Stream;
 use itertools::Itertools;
-use masq_lib::messages::{ToMessageBody, UiUnmarshalError, NODE_UI_PROTOCOL, UNMARSHAL_ERROR};
+use masq_lib::messages::{
+    ToMessageBody, UiClientDisconnectedNotice, UiUnmarshalError, NODE_UI_PROTOCOL,
+    UNAUTHORIZED_ERROR, UNMARSHAL_ERROR,
+};
+use masq_lib::ui_auth::{is_read_only_opcode, UI_TOKEN_HEADER};
 use masq_lib::ui_gateway::MessagePath::Conversation;
 use masq_lib::ui_gateway::MessageTarget::ClientId;
 use masq_lib::ui_gateway::{MessageBody, MessageTarget, NodeFromUiMessage, NodeToUiMessage};
@@ -18,7 +22,7 @@ use masq_lib::ui_traffic_converter::UiTrafficConverter;
 use masq_lib::ui_traffic_converter::UnmarshalError::{Critical, NonCritical};
 use masq_lib::utils::localhost;
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::net::SocketAddr;
 use std::sync::Mutex;
@@ -71,6 +75,14 @@ struct WebSocketSupervisorInner {
     from_ui_message_sub: Recipient<NodeFromUiMessage>,
     client_id_by_socket_addr: HashMap<SocketAddr, u64>,
     client_by_id: HashMap<u64, Box<dyn ClientWrapper>>,
+    ui_token_opt: Option<String>,
+    read_only_client_ids: HashSet<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UiAccess {
+    Full,
+    ReadOnly,
 }
 
 impl WebSocketSupervisor for WebSocketSupervisorReal {
@@ -84,6 +96,7 @@ impl WebSocketSupervisorReal {
     pub fn new(
         port: u16,
         from_ui_message_sub: Recipient<NodeFromUiMessage>,
+        ui_token_opt: Option<String>,
     ) -> std::io::Result<WebSocketSupervisorReal> {
         let inner = Arc::new(Mutex::new(WebSocketSupervisorInner {
             port,
@@ -91,6 +104,8 @@ impl WebSocketSupervisorReal {
             from_ui_message_sub,
             client_id_by_socket_addr: HashMap::new(),
             client_by_id: HashMap::new(),
+            ui_token_opt,
+            read_only_client_ids: HashSet::new(),
         }));
         let logger = Logger::new("WebSocketSupervisor");
         let logger_1 = logger.clone();
@@ -161,30 +176,88 @@ impl WebSocketSupervisorReal {
         inner: Arc<Mutex<WebSocketSupervisorInner>>,
         logger: &Logger,
     ) {
-        if upgrade
+        if !upgrade
             .protocols()
             .contains(&String::from(NODE_UI_PROTOCOL))
         {
-            Self::accept_upgrade_request(upgrade, socket_addr, inner, logger);
-        } else {
             Self::reject_upgrade_request(upgrade, &logger);
+            return;
         }
+        let presented_token_opt = upgrade
+            .request
+            .headers
+            .get_raw(UI_TOKEN_HEADER)
+            .and_then(|values| values.first())
+            .map(|value| String::from_utf8_lossy(value).to_string());
+        let ui_token_opt = inner
+            .lock()
+            .expect("WebSocketSupervisor is poisoned")
+            .ui_token_opt
+            .clone();
+        match Self::authenticate(&ui_token_opt, &presented_token_opt) {
+            Some(access) => {
+                Self::accept_upgrade_request(upgrade, socket_addr, inner, access, logger)
+            }
+            None => {
+                warning!(
+                    logger,
+                    "UI at {} presented an invalid token; rejecting connection",
+                    socket_addr
+                );
+                tokio::spawn(upgrade.reject().then(|_| ok::<(), ()>(())));
+            }
+        }
+    }
+
+    // Without a token configured, everyone gets full access, as before. With one, a client that
+    // presents it gets full access, a client that presents nothing is downgraded to read-only
+    // commands, and a client that presents the wrong token is turned away.
+    fn authenticate(
+        ui_token_opt: &Option<String>,
+        presented_token_opt: &Option<String>,
+    ) -> Option<UiAccess> {
+        match (ui_token_opt, presented_token_opt) {
+            (None, _) => Some(UiAccess::Full),
+            (Some(_), None) => Some(UiAccess::ReadOnly),
+            (Some(expected), Some(presented)) if Self::tokens_match(expected, presented) => {
+                Some(UiAccess::Full)
+            }
+            (Some(_), Some(_)) => None,
+        }
+    }
+
+    // Compares every byte no matter where the first difference is, so that response timing
+    // doesn't reveal how much of a guessed token is right.
+    fn tokens_match(expected: &str, presented: &str) -> bool {
+        expected.len() == presented.len()
+            && expected
+                .bytes()
+                .zip(presented.bytes())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
     }
 
     fn accept_upgrade_request(
         upgrade: WsUpgrade<TcpStream, BytesMut>,
         socket_addr: SocketAddr,
         inner: Arc<Mutex<WebSocketSupervisorInner>>,
+        access: UiAccess,
         logger: &Logger,
     ) {
         let logger_clone = logger.clone();
-        info!(logger_clone, "UI connected at {}", socket_addr);
+        match access {
+            UiAccess::Full => info!(logger_clone, "UI connected at {}", socket_addr),
+            UiAccess::ReadOnly => info!(
+                logger_clone,
+                "UI connected at {} without a token; allowing read-only commands only", socket_addr
+            ),
+        }
         let upgrade_future =
             upgrade
                 .use_protocol(NODE_UI_PROTOCOL)
                 .accept()
                 .map(move |(client, _)| {
-                    Self::handle_connection(client, &inner, &logger_clone, socket_addr);
+                    Self::handle_connection(client, &inner, &logger_clone, socket_addr, access);
                 });
         tokio::spawn(upgrade_future.then(|result| {
             match result {
@@ -209,6 +282,7 @@ impl WebSocketSupervisorReal {
         inner: &Arc<Mutex<WebSocketSupervisorInner>>,
         logger: &Logger,
         socket_addr: SocketAddr,
+        access: UiAccess,
     ) {
         let logger_1 = logger.clone();
         let logger_2 = logger.clone();
@@ -226,6 +300,9 @@ impl WebSocketSupervisorReal {
             .client_id_by_socket_addr
             .insert(socket_addr, client_id);
         locked_inner.client_by_id.insert(client_id, client_wrapper);
+        if access == UiAccess::ReadOnly {
+            locked_inner.read_only_client_ids.insert(client_id);
+        }
         let incoming_future = incoming
             .then(move |result| Self::handle_websocket_errors(result, &logger_2, socket_addr))
             .map(move |owned_message| match owned_message {
@@ -264,6 +341,17 @@ impl WebSocketSupervisorReal {
             }
         };
         match UiTrafficConverter::new_unmarshal_from_ui(message, client_id) {
+            Ok(from_ui_message)
+                if locked_inner.read_only_client_ids.contains(&client_id)
+                    && !is_read_only_opcode(&from_ui_message.body.opcode) =>
+            {
+                Self::refuse_unauthorized_message(
+                    &mut locked_inner,
+                    logger,
+                    socket_addr,
+                    from_ui_message,
+                );
+            }
             Ok(from_ui_message) => {
                 locked_inner
                     .from_ui_message_sub
@@ -331,6 +419,42 @@ impl WebSocketSupervisorReal {
         ok::<(), ()>(())
     }
 
+    fn refuse_unauthorized_message(
+        locked_inner: &mut MutexGuard<WebSocketSupervisorInner>,
+        logger: &Logger,
+        socket_addr: SocketAddr,
+        from_ui_message: NodeFromUiMessage,
+    ) {
+        let client_id = from_ui_message.client_id;
+        let opcode = from_ui_message.body.opcode;
+        warning!(
+            logger,
+            "Refused '{}' from unauthenticated UI at {} (client {})",
+            opcode,
+            socket_addr,
+            client_id
+        );
+        if let Conversation(context_id) = from_ui_message.body.path {
+            Self::send_msg(
+                locked_inner,
+                NodeToUiMessage {
+                    target: ClientId(client_id),
+                    body: MessageBody {
+                        payload: Err((
+                            UNAUTHORIZED_ERROR,
+                            format!(
+                                "'{}' requires a connection authenticated with the UI token",
+                                opcode
+                            ),
+                        )),
+                        opcode,
+                        path: Conversation(context_id),
+                    },
+                },
+            );
+        }
+    }
+
     fn handle_close_message(
         inner_arc: &Arc<Mutex<WebSocketSupervisorInner>>,
         logger: &Logger,
@@ -355,6 +479,13 @@ impl WebSocketSupervisorReal {
             locked_inner.port
         );
         Self::close_connection(&mut locked_inner, client_id, socket_addr, &logger);
+        locked_inner
+            .from_ui_message_sub
+            .try_send(NodeFromUiMessage {
+                client_id,
+                body: UiClientDisconnectedNotice {}.tmb(0),
+            })
+            .expect("UiGateway is dead");
 
         err::<(), ()>(()) // end the stream
     }
@@ -423,6 +554,7 @@ impl WebSocketSupervisorReal {
         socket_addr: SocketAddr,
         logger: &Logger,
     ) {
+        locked_inner.read_only_client_ids.remove(&client_id);
         let mut client = match locked_inner.client_by_id.remove(&client_id) {
             Some(client) => client,
             None => panic!("WebSocketSupervisor got a disconnect from a client that has disappeared from the stable!"),
@@ -455,8 +587,8 @@ mod tests {
     use actix::{Actor, Addr};
     use futures::future::lazy;
     use masq_lib::messages::{
-        FromMessageBody, UiShutdownRequest, UiStartOrder, UiUnmarshalError, NODE_UI_PROTOCOL,
-        UNMARSHAL_ERROR,
+        FromMessageBody, UiDescriptorRequest, UiShutdownRequest, UiShutdownResponse, UiStartOrder,
+        UiUnmarshalError, NODE_UI_PROTOCOL, UNAUTHORIZED_ERROR, UNMARSHAL_ERROR,
     };
     use masq_lib::test_utils::ui_connection::UiConnection;
     use masq_lib::ui_gateway::MessagePath::FireAndForget;
@@ -627,7 +759,7 @@ mod tests {
             let system = System::new("logs_pre_upgrade_connection_errors");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -649,7 +781,7 @@ mod tests {
             let system = System::new("rejects_connection_attempt_with_improper_protocol_name");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -676,7 +808,7 @@ mod tests {
             let system = System::new("logs_unexpected_binary_ping_pong_websocket_messages");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -713,7 +845,7 @@ mod tests {
             let system = System::new("can_connect_two_clients_and_receive_messages_from_them");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -740,9 +872,9 @@ mod tests {
             .unwrap();
         let another_close_msg = another_client.recv_message().unwrap();
 
-        ui_gateway_awaiter.await_message_count(3);
+        ui_gateway_awaiter.await_message_count(5);
         let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
-        let messages = vec![0, 1, 2]
+        let messages = vec![0, 1, 2, 3, 4]
             .into_iter()
             .map(|i| {
                 ui_gateway_recording
@@ -783,6 +915,20 @@ mod tests {
                 },
             },
         );
+        assert_contains(
+            &messages,
+            &NodeFromUiMessage {
+                client_id: 0,
+                body: UiClientDisconnectedNotice {}.tmb(0),
+            },
+        );
+        assert_contains(
+            &messages,
+            &NodeFromUiMessage {
+                client_id: 1,
+                body: UiClientDisconnectedNotice {}.tmb(0),
+            },
+        );
         assert_eq!(one_close_msg, OwnedMessage::Close(None));
         assert_eq!(another_close_msg, OwnedMessage::Close(None));
     }
@@ -797,6 +943,8 @@ mod tests {
             from_ui_message_sub: ui_message_sub.start().recipient::<NodeFromUiMessage>(),
             client_id_by_socket_addr: Default::default(),
             client_by_id: Default::default(),
+            ui_token_opt: None,
+            read_only_client_ids: Default::default(),
         };
         let subject = WebSocketSupervisorReal {
             inner: Arc::new(Mutex::new(subject_inner)),
@@ -864,6 +1012,8 @@ mod tests {
             from_ui_message_sub: ui_message_sub.start().recipient::<NodeFromUiMessage>(),
             client_id_by_socket_addr: Default::default(),
             client_by_id: Default::default(),
+            ui_token_opt: None,
+            read_only_client_ids: Default::default(),
         };
         let subject = WebSocketSupervisorReal {
             inner: Arc::new(Mutex::new(subject_inner)),
@@ -931,6 +1081,8 @@ mod tests {
             from_ui_message_sub: ui_message_sub.start().recipient::<NodeFromUiMessage>(),
             client_id_by_socket_addr: Default::default(),
             client_by_id: Default::default(),
+            ui_token_opt: None,
+            read_only_client_ids: Default::default(),
         };
         let subject = WebSocketSupervisorReal {
             inner: Arc::new(Mutex::new(subject_inner)),
@@ -1007,6 +1159,8 @@ mod tests {
             from_ui_message_sub,
             client_id_by_socket_addr: Default::default(),
             client_by_id,
+            ui_token_opt: None,
+            read_only_client_ids: Default::default(),
         }));
 
         WebSocketSupervisorReal::send_to_clients(
@@ -1018,6 +1172,118 @@ mod tests {
         TestLogHandler::new().exists_log_containing ("WARN: WebSocketSupervisor: Client 1234 dropped its connection before it could be flushed");
     }
 
+    #[test]
+    fn authenticate_grants_access_according_to_presented_token() {
+        let token = Some("0123456789abcdef".to_string());
+
+        assert_eq!(
+            WebSocketSupervisorReal::authenticate(&None, &None),
+            Some(UiAccess::Full)
+        );
+        assert_eq!(
+            WebSocketSupervisorReal::authenticate(&None, &Some("booga".to_string())),
+            Some(UiAccess::Full)
+        );
+        assert_eq!(
+            WebSocketSupervisorReal::authenticate(&token, &None),
+            Some(UiAccess::ReadOnly)
+        );
+        assert_eq!(
+            WebSocketSupervisorReal::authenticate(&token, &token),
+            Some(UiAccess::Full)
+        );
+        assert_eq!(
+            WebSocketSupervisorReal::authenticate(&token, &Some("0123456789abcdeF".to_string())),
+            None
+        );
+        assert_eq!(
+            WebSocketSupervisorReal::authenticate(&token, &Some("0123456789abcde".to_string())),
+            None
+        );
+    }
+
+    #[test]
+    fn clients_are_authenticated_with_the_ui_token() {
+        init_test_logging();
+        let port = find_free_port();
+        let (ui_gateway, ui_gateway_awaiter, ui_gateway_recording_arc) = make_recorder();
+
+        thread::spawn(move || {
+            let system = System::new("clients_are_authenticated_with_the_ui_token");
+            let ui_message_sub = subs(ui_gateway);
+            let subject = lazy(move || {
+                let _subject = WebSocketSupervisorReal::new(
+                    port,
+                    ui_message_sub,
+                    Some("0123456789abcdef".to_string()),
+                )
+                .unwrap();
+                Ok(())
+            });
+            actix::spawn(subject);
+            system.run();
+        });
+        let mut authenticated_client = await_value(None, || {
+            UiConnection::make_with_token(port, NODE_UI_PROTOCOL, "0123456789abcdef")
+        });
+        let mut unauthenticated_client = UiConnection::new(port, NODE_UI_PROTOCOL);
+        let impostor_result =
+            UiConnection::make_with_token(port, NODE_UI_PROTOCOL, "fedcba9876543210");
+
+        authenticated_client.send_with_context_id(UiShutdownRequest {}, 1);
+        unauthenticated_client.send_with_context_id(UiDescriptorRequest {}, 2);
+        let refusal = unauthenticated_client
+            .transact_with_context_id::<UiShutdownRequest, UiShutdownResponse>(
+                UiShutdownRequest {},
+                3,
+            );
+
+        assert_eq!(impostor_result.is_err(), true);
+        assert_eq!(
+            refusal,
+            Err((
+                UNAUTHORIZED_ERROR,
+                "'shutdown' requires a connection authenticated with the UI token".to_string()
+            ))
+        );
+        ui_gateway_awaiter.await_message_count(2);
+        thread::sleep(Duration::from_millis(100)); // make sure the refused request doesn't follow
+        let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
+        let messages = vec![0, 1]
+            .into_iter()
+            .map(|i| {
+                ui_gateway_recording
+                    .get_record::<NodeFromUiMessage>(i)
+                    .clone()
+            })
+            .collect::<Vec<NodeFromUiMessage>>();
+        assert_contains(
+            &messages,
+            &NodeFromUiMessage {
+                client_id: 0,
+                body: UiShutdownRequest {}.tmb(1),
+            },
+        );
+        assert_contains(
+            &messages,
+            &NodeFromUiMessage {
+                client_id: 1,
+                body: UiDescriptorRequest {}.tmb(2),
+            },
+        );
+        assert_eq!(ui_gateway_recording.len(), 2);
+        let tlh = TestLogHandler::new();
+        tlh.exists_log_matching(
+            "UI connected at 127\\.0\\.0\\.1:\\d+ without a token; allowing read-only commands only",
+        );
+        tlh.exists_log_matching(
+            "UI at 127\\.0\\.0\\.1:\\d+ presented an invalid token; rejecting connection",
+        );
+        tlh.exists_log_matching(
+            "Refused 'shutdown' from unauthenticated UI at 127\\.0\\.0\\.1:\\d+ \\(client 1\\)",
+        );
+    }
+
     #[test]
     fn once_a_client_sends_a_close_no_more_data_is_accepted() {
         let port = find_free_port();
@@ -1027,7 +1293,7 @@ mod tests {
             let system = System::new("once_a_client_sends_a_close_no_more_data_is_accepted");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -1041,7 +1307,7 @@ mod tests {
         client.send(UiStartOrder {});
 
         client.shutdown();
-        ui_gateway_awaiter.await_message_count(1);
+        ui_gateway_awaiter.await_message_count(2);
         thread::sleep(Duration::from_millis(500)); // make sure there's not another message sent
         let ui_gateway_recording = ui_gateway_recording_arc.lock().unwrap();
         assert_eq!(
@@ -1051,7 +1317,14 @@ mod tests {
                 body: UiShutdownRequest {}.tmb(0),
             }
         );
-        assert_eq!(ui_gateway_recording.len(), 1);
+        assert_eq!(
+            ui_gateway_recording.get_record::<NodeFromUiMessage>(1),
+            &NodeFromUiMessage {
+                client_id: 0,
+                body: UiClientDisconnectedNotice {}.tmb(0),
+            }
+        );
+        assert_eq!(ui_gateway_recording.len(), 2);
     }
 
     #[test]
@@ -1063,7 +1336,7 @@ mod tests {
             let system = System::new("a_client_that_violates_the_protocol_is_terminated");
             let ui_message_sub = subs(ui_gateway);
             let subject = lazy(move || {
-                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+                let _subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
                 Ok(())
             });
             actix::spawn(subject);
@@ -1096,7 +1369,7 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_sends_a_message_to_the_client");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
             let one_mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Ok(()));
@@ -1137,7 +1410,7 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_sends_a_message_to_the_client");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
             let one_mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Ok(()));
@@ -1180,7 +1453,7 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_sends_a_message_to_the_client");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
             let one_mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Ok(()));
@@ -1230,7 +1503,7 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_tries_to_send_message_and_panics_on_flush");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
             let mock_client = ClientWrapperMock::new()
                 .send_result(Ok(()))
                 .flush_result(Err(WebSocketError::NoDataAvailable));
@@ -1262,7 +1535,7 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_tries_to_send_message_and_panics");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
             let mock_client =
                 ClientWrapperMock::new().send_result(Err(WebSocketError::NoDataAvailable));
             let msg = NodeToUiMessage {
@@ -1292,7 +1565,7 @@ mod tests {
         let ui_message_sub = subs(ui_gateway);
         let system = System::new("send_msg_fails_to_look_up_client_to_send_to");
         let lazy_future = lazy(move || {
-            let subject = WebSocketSupervisorReal::new(port, ui_message_sub).unwrap();
+            let subject = WebSocketSupervisorReal::new(port, ui_message_sub, None).unwrap();
             let msg = NodeToUiMessage {
                 target: MessageTarget::ClientId(7),
                 body: MessageBody {
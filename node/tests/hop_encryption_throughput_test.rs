@@ -0,0 +1,65 @@
+// Copyright (c) 2019-2021, MASQ (https://masq.ai) and/or its affiliates. All rights reserved.
+
+use masq_lib::test_utils::utils::DEFAULT_CHAIN_ID;
+use node_lib::hopper::session_keys::HopSessionKeys;
+use node_lib::sub_lib::cryptde::{decodex, encodex, CryptDE};
+use node_lib::sub_lib::cryptde_real::CryptDEReal;
+use std::time::{Duration, Instant};
+
+const PACKAGE_COUNT: usize = 5_000;
+const PACKAGE_SIZE: usize = 1_400;
+
+// This is a benchmark rather than a test: run it with
+// cargo test --release --test hop_encryption_throughput_test -- --ignored --nocapture
+#[test]
+#[ignore]
+fn hop_encryption_throughput_integration() {
+    let sender = CryptDEReal::new(DEFAULT_CHAIN_ID);
+    let receiver = CryptDEReal::new(DEFAULT_CHAIN_ID);
+    let package = vec![0xA5u8; PACKAGE_SIZE];
+
+    let public_key_elapsed = time_packages(|| {
+        let encoded = encodex(&sender, receiver.public_key(), &package).unwrap();
+        let decoded: Vec<u8> = decodex(&receiver, &encoded).unwrap();
+        assert_eq!(decoded.len(), PACKAGE_SIZE);
+    });
+    let mut sender_keys =
+        HopSessionKeys::with_peers(vec![receiver.public_key().clone()].into_iter().collect());
+    let mut receiver_keys = HopSessionKeys::new();
+    let session_elapsed = time_packages(|| {
+        let now = Instant::now();
+        let encoded = sender_keys
+            .encode(&sender, receiver.public_key(), &package, now)
+            .unwrap();
+        let decoded: Vec<u8> = receiver_keys.decode(&receiver, &encoded, now).unwrap();
+        assert_eq!(decoded.len(), PACKAGE_SIZE);
+    });
+
+    report("Public-key hop encryption", public_key_elapsed);
+    report("Session-key hop encryption", session_elapsed);
+    assert!(
+        session_elapsed < public_key_elapsed,
+        "Session keys ({:?}) should beat public keys ({:?})",
+        session_elapsed,
+        public_key_elapsed
+    );
+}
+
+fn time_packages<F: FnMut()>(mut f: F) -> Duration {
+    let began_at = Instant::now();
+    (0..PACKAGE_COUNT).for_each(|_| f());
+    began_at.elapsed()
+}
+
+fn report(label: &str, elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    eprintln!(
+        "{}: {} {}-byte packages in {:.3}s: {:.0} packages/s, {:.2} MB/s",
+        label,
+        PACKAGE_COUNT,
+        PACKAGE_SIZE,
+        seconds,
+        PACKAGE_COUNT as f64 / seconds,
+        (PACKAGE_COUNT * PACKAGE_SIZE) as f64 / seconds / 1_000_000.0
+    );
+}